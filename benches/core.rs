@@ -0,0 +1,47 @@
+// benches/core.rs
+
+//! `cargo bench` for the palette-mapping core. Save a baseline before a change with
+//! `cargo bench -- --save-baseline before` and compare after it with `cargo bench -- --baseline before`.
+
+use catppuccin::FlavorName;
+use catppuccin_bot::bench;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use std::time::Duration;
+
+fn lut_generation(c: &mut Criterion) {
+    let mut group = c.benchmark_group("lut_generation");
+    // A LUT takes seconds to build, so take the fewest samples Criterion allows
+    group.sample_size(10).measurement_time(Duration::from_secs(60));
+    for algorithm in bench::ALGORITHMS {
+        group.bench_with_input(BenchmarkId::from_parameter(algorithm), algorithm, |b, algorithm| {
+            b.iter(|| bench::lut_generation(FlavorName::Mocha, black_box(algorithm)))
+        });
+    }
+    group.finish();
+}
+
+fn lut_application(c: &mut Criterion) {
+    let img = bench::sample_image(1000, 1000);
+    let lut = bench::lut_generation(FlavorName::Mocha, "nearest-neighbor");
+    let mut group = c.benchmark_group("lut_application");
+    // Per pixel, so the report reads in megapixels per second
+    group.throughput(Throughput::Elements((img.width() * img.height()) as u64));
+    group.bench_function("1_megapixel", |b| {
+        b.iter_batched_ref(|| img.clone(), |mapped| bench::lut_application(mapped, black_box(&lut)), criterion::BatchSize::LargeInput)
+    });
+    group.finish();
+}
+
+fn gif_frames(c: &mut Criterion) {
+    const FRAMES: u32 = 12;
+    let gif = bench::sample_gif(128, 128, FRAMES);
+    // Build the LUT up front so only the frame work is measured
+    catppuccin_bot::image_processing::generate_catppuccin_lut(FlavorName::Mocha, "nearest-neighbor");
+    let mut group = c.benchmark_group("gif_frames");
+    group.throughput(Throughput::Elements(FRAMES as u64));
+    group.bench_function("128x128", |b| b.iter(|| bench::gif_frames(black_box(&gif), FlavorName::Mocha, "nearest-neighbor")));
+    group.finish();
+}
+
+criterion_group!(benches, lut_generation, lut_application, gif_frames);
+criterion_main!(benches);
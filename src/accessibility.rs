@@ -0,0 +1,203 @@
+// src/accessibility.rs
+
+use crate::text_rendering::{draw_text, fill_rect, measure_text};
+use catppuccin::FlavorName;
+use image::{imageops, Rgba, RgbaImage};
+use rayon::prelude::*;
+
+/// A kind of color vision deficiency `!cat simulate` can show
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Deficiency {
+    Protanopia,
+    Deuteranopia,
+    Tritanopia,
+    Protanomaly,
+    Deuteranomaly,
+    Tritanomaly,
+    Achromatopsia,
+}
+
+impl Deficiency {
+    pub const ALL: [Deficiency; 7] = [
+        Deficiency::Protanopia,
+        Deficiency::Deuteranopia,
+        Deficiency::Tritanopia,
+        Deficiency::Protanomaly,
+        Deficiency::Deuteranomaly,
+        Deficiency::Tritanomaly,
+        Deficiency::Achromatopsia,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Deficiency::Protanopia => "protanopia",
+            Deficiency::Deuteranopia => "deuteranopia",
+            Deficiency::Tritanopia => "tritanopia",
+            Deficiency::Protanomaly => "protanomaly",
+            Deficiency::Deuteranomaly => "deuteranomaly",
+            Deficiency::Tritanomaly => "tritanomaly",
+            Deficiency::Achromatopsia => "achromatopsia",
+        }
+    }
+
+    /// Severity used when none is given: anomalous trichromacy is a weakened cone, so it
+    /// defaults to a typical partial shift rather than the full dichromat result
+    pub fn default_severity(&self) -> f32 {
+        match self {
+            Deficiency::Protanomaly | Deficiency::Deuteranomaly | Deficiency::Tritanomaly => 0.6,
+            _ => 1.0,
+        }
+    }
+
+    // The full-severity transform, one row per output channel
+    fn matrix(&self) -> [[f32; 3]; 3] {
+        // Dichromat matrices from https://ixora.io/projects/colorblindness/color-blindness-simulation-research/
+        match self {
+            Deficiency::Protanopia | Deficiency::Protanomaly => [[0.56667, 0.43333, 0.0], [0.55833, 0.44167, 0.0], [0.0, 0.24167, 0.75833]],
+            Deficiency::Deuteranopia | Deficiency::Deuteranomaly => [[0.625, 0.375, 0.0], [0.7, 0.3, 0.0], [0.0, 0.3, 0.7]],
+            Deficiency::Tritanopia | Deficiency::Tritanomaly => [[0.95, 0.05, 0.0], [0.0, 0.43333, 0.56667], [0.0, 0.475, 0.525]],
+            // Rod monochromacy: only luminance survives
+            Deficiency::Achromatopsia => [[0.299, 0.587, 0.114]; 3],
+        }
+    }
+}
+
+/// Simulate one color as seen with a deficiency; `severity` runs from 0 (normal vision) to 1 (complete)
+pub fn simulate_color((r, g, b): (u8, u8, u8), deficiency: Deficiency, severity: f32) -> (u8, u8, u8) {
+    let severity = severity.clamp(0.0, 1.0);
+    let m = deficiency.matrix();
+    let input = [r as f32, g as f32, b as f32];
+    let channel = |row: usize| {
+        let simulated = m[row][0] * input[0] + m[row][1] * input[1] + m[row][2] * input[2];
+        (input[row] + (simulated - input[row]) * severity).round().clamp(0.0, 255.0) as u8
+    };
+    (channel(0), channel(1), channel(2))
+}
+
+/// Simulate a whole image, keeping alpha
+pub fn simulate_image(img: &RgbaImage, deficiency: Deficiency, severity: f32) -> RgbaImage {
+    let mut out = img.clone();
+    out.par_chunks_mut(4).for_each(|pixel| {
+        let (r, g, b) = simulate_color((pixel[0], pixel[1], pixel[2]), deficiency, severity);
+        pixel[..3].copy_from_slice(&[r, g, b]);
+    });
+    out
+}
+
+/// Daltonize one color: the detail lost to the deficiency is moved onto channels the viewer
+/// still sees. Achromatopsia has no such channel, so it's returned unchanged.
+pub fn daltonize_color((r, g, b): (u8, u8, u8), deficiency: Deficiency, severity: f32) -> (u8, u8, u8) {
+    // Fidaner et al.'s daltonization: the LMS-space dichromat projections folded into RGB
+    // (the simpler simulation matrices above lose too little to correct against)
+    let m = match deficiency {
+        Deficiency::Protanopia | Deficiency::Protanomaly => [[0.11238, 0.88761, 0.0], [0.11238, 0.88762, 0.0], [0.00401, -0.00401, 1.0]],
+        Deficiency::Deuteranopia | Deficiency::Deuteranomaly => [[0.29275, 0.70725, 0.0], [0.29275, 0.70725, 0.0], [-0.02234, 0.02234, 1.0]],
+        Deficiency::Tritanopia | Deficiency::Tritanomaly => [[0.49326, 0.50675, 0.0], [0.49326, 0.50674, 0.0], [-3.01087, 3.01091, 1.0]],
+        Deficiency::Achromatopsia => return (r, g, b),
+    };
+    let severity = severity.clamp(0.0, 1.0);
+    let input = [r as f32, g as f32, b as f32];
+    let err: Vec<f32> = (0..3)
+        .map(|row| (input[row] - (m[row][0] * input[0] + m[row][1] * input[1] + m[row][2] * input[2])) * severity)
+        .collect();
+    // Spread the lost detail into green and blue
+    let shifted = [input[0], input[1] + 0.7 * err[0] + err[1], input[2] + 0.7 * err[0] + err[2]];
+    let channel = |value: f32| value.round().clamp(0.0, 255.0) as u8;
+    (channel(shifted[0]), channel(shifted[1]), channel(shifted[2]))
+}
+
+/// Daltonize a whole image, keeping alpha
+pub fn daltonize_image(img: &RgbaImage, deficiency: Deficiency, severity: f32) -> RgbaImage {
+    let mut out = img.clone();
+    out.par_chunks_mut(4).for_each(|pixel| {
+        let (r, g, b) = daltonize_color((pixel[0], pixel[1], pixel[2]), deficiency, severity);
+        pixel[..3].copy_from_slice(&[r, g, b]);
+    });
+    out
+}
+
+const GRID_TILE: u32 = 360;
+const GRID_COLUMNS: u32 = 4;
+const GRID_GAP: u32 = 12;
+const LABEL_SIZE: f32 = 20.0;
+const LABEL_HEIGHT: u32 = 32;
+
+/// The original next to every simulation, each labeled, on the flavor's base.
+/// `severity` overrides each deficiency's default when given.
+pub fn render_simulation_grid(img: &RgbaImage, severity: Option<f32>, flavor: FlavorName) -> RgbaImage {
+    let rgb = |name: &str| crate::utils::catppuccin_color_name_to_rgb(name, flavor).unwrap();
+    let scale = (GRID_TILE as f32 / img.width().max(img.height()) as f32).min(1.0);
+    let thumb = imageops::resize(
+        img,
+        ((img.width() as f32 * scale).round() as u32).max(1),
+        ((img.height() as f32 * scale).round() as u32).max(1),
+        imageops::FilterType::Triangle,
+    );
+    let mut tiles = vec![("original".to_string(), thumb.clone())];
+    tiles.extend(Deficiency::ALL.par_iter().map(|d| {
+        let severity = severity.unwrap_or_else(|| d.default_severity());
+        let label = if severity < 1.0 { format!("{} {:.0}%", d.name(), severity * 100.0) } else { d.name().to_string() };
+        (label, simulate_image(&thumb, *d, severity))
+    }).collect::<Vec<_>>());
+
+    let rows = (tiles.len() as u32).div_ceil(GRID_COLUMNS);
+    let cell_w = thumb.width().max(measure_text("deuteranomaly 100%", LABEL_SIZE).0);
+    let cell_h = LABEL_HEIGHT + thumb.height();
+    let width = GRID_GAP + GRID_COLUMNS * (cell_w + GRID_GAP);
+    let height = GRID_GAP + rows * (cell_h + GRID_GAP);
+    let (br, bg, bb) = rgb("base");
+    let mut canvas = RgbaImage::from_pixel(width, height, Rgba([br, bg, bb, 255]));
+    let (sr, sg, sb) = rgb("surface0");
+    for (i, (label, tile)) in tiles.iter().enumerate() {
+        let x = GRID_GAP + (i as u32 % GRID_COLUMNS) * (cell_w + GRID_GAP);
+        let y = GRID_GAP + (i as u32 / GRID_COLUMNS) * (cell_h + GRID_GAP);
+        fill_rect(&mut canvas, x as i32, (y + LABEL_HEIGHT) as i32, cell_w, thumb.height(), Rgba([sr, sg, sb, 255]));
+        draw_text(&mut canvas, label, x as i32, y as i32 + 4, LABEL_SIZE, rgb(if i == 0 { "mauve" } else { "text" }));
+        let offset = (cell_w - tile.width()) / 2;
+        imageops::overlay(&mut canvas, tile, (x + offset) as i64, (y + LABEL_HEIGHT) as i64);
+    }
+    canvas
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_severity_interpolates() {
+        let red = (220, 40, 40);
+        assert_eq!(simulate_color(red, Deficiency::Protanopia, 0.0), red);
+        let full = simulate_color(red, Deficiency::Protanopia, 1.0);
+        let half = simulate_color(red, Deficiency::Protanomaly, 0.5);
+        assert!(half.0 < red.0 && half.0 > full.0);
+        // Achromatopsia leaves only gray
+        let (r, g, b) = simulate_color(red, Deficiency::Achromatopsia, 1.0);
+        assert!(r == g && g == b);
+    }
+
+    #[test]
+    fn test_daltonize_separates_red_and_green() {
+        let (red, green) = ((200, 80, 80), (110, 140, 80));
+        let distance = |a: (u8, u8, u8), b: (u8, u8, u8)| {
+            let d = |x: u8, y: u8| (x as f32 - y as f32).powi(2);
+            (d(a.0, b.0) + d(a.1, b.1) + d(a.2, b.2)).sqrt()
+        };
+        let seen = |c| simulate_color(c, Deficiency::Deuteranopia, 1.0);
+        let before = distance(seen(red), seen(green));
+        let after = distance(
+            seen(daltonize_color(red, Deficiency::Deuteranopia, 1.0)),
+            seen(daltonize_color(green, Deficiency::Deuteranopia, 1.0)),
+        );
+        assert!(after > before);
+        // Grays carry no lost detail, so they're left alone
+        assert_eq!(daltonize_color((128, 128, 128), Deficiency::Protanopia, 1.0), (128, 128, 128));
+    }
+
+    #[test]
+    fn test_simulation_grid_has_every_tile() {
+        let img = RgbaImage::from_pixel(100, 50, Rgba([200, 60, 90, 255]));
+        let grid = render_simulation_grid(&img, None, FlavorName::Mocha);
+        // Eight tiles in two rows of four
+        assert!(grid.width() > 4 * 100 && grid.height() > 2 * 50);
+    }
+}
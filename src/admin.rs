@@ -0,0 +1,224 @@
+// src/admin.rs
+
+use crate::config;
+use crate::eta;
+use crate::image_processing;
+use crate::jobs::{self, QueuedJob};
+use crate::memory;
+use crate::responses;
+use crate::utils;
+use once_cell::sync::{Lazy, OnceCell};
+use serenity::http::Http;
+use serenity::model::channel::Message;
+use serenity::model::id::UserId;
+use std::sync::Mutex;
+use std::time::Instant;
+use tracing::{info, warn};
+
+/// Most jobs listed by `!cat admin queue`, to stay within Discord's embed limits
+const MAX_QUEUE_LINES: usize = 20;
+
+// The application's owner, or every member of the team that owns it; looked up once connected
+static APPLICATION_OWNERS: OnceCell<Vec<UserId>> = OnceCell::new();
+
+// Set while job intake is paused, with the reason given, if any
+static PAUSE: Lazy<Mutex<Option<Pause>>> = Lazy::new(|| Mutex::new(None));
+
+#[derive(Debug, Clone)]
+struct Pause {
+    reason: Option<String>,
+    since: Instant,
+}
+
+/// Look up who owns the application so they can use admin commands without being in `OWNER_IDS`
+pub async fn load_application_owners(http: &Http) {
+    match http.get_current_application_info().await {
+        Ok(info) => {
+            let mut owners: Vec<UserId> = info.owner.map(|user| user.id).into_iter().collect();
+            if let Some(team) = info.team {
+                owners.extend(team.members.iter().map(|member| member.user.id));
+            }
+            let _ = APPLICATION_OWNERS.set(owners);
+        }
+        Err(why) => warn!(?why, "Failed to look up the application's owner; only OWNER_IDS can use admin commands"),
+    }
+}
+
+/// Whether a user may run `!cat admin`: the application's owner or team, or anyone in `OWNER_IDS`
+pub fn is_owner(user: UserId) -> bool {
+    config::get().owners.contains(&user) || APPLICATION_OWNERS.get().is_some_and(|owners| owners.contains(&user))
+}
+
+/// Whether an owner has paused job intake
+pub fn is_paused() -> bool {
+    PAUSE.lock().unwrap().is_some()
+}
+
+/// Commands that still work while intake is paused: they don't start jobs, or they're needed to manage them
+const ALWAYS_AVAILABLE: &[&str] = &["admin", "help", "-h", "--help", "cancel"];
+
+/// The notice to send instead of starting a command while intake is paused. Owners aren't turned away.
+/// `parts` are the words after `!cat`.
+pub fn intake_notice(user: UserId, parts: &[&str]) -> Option<String> {
+    let pause = PAUSE.lock().unwrap().clone()?;
+    if parts.first().is_some_and(|p| ALWAYS_AVAILABLE.contains(p)) || is_owner(user) {
+        return None;
+    }
+    Some(match pause.reason {
+        Some(reason) => format!("⏸️ Processing is paused for maintenance: {}\nPlease try again later.", reason),
+        None => "⏸️ Processing is paused for maintenance. Please try again later.".to_string(),
+    })
+}
+
+/// An `!cat admin` subcommand
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    /// Drop cached LUTs and results kept for duplicate requests
+    Flush,
+    /// Re-read `.env` and the environment
+    Reload,
+    /// Stop accepting new jobs, optionally saying why
+    Pause(Option<String>),
+    Resume,
+    /// List running and waiting jobs
+    Queue,
+    /// Post a notice to the notice channels and every server's announcement channel
+    Broadcast(String),
+}
+
+impl Action {
+    /// Parse the words after `!cat admin`. Text for `pause` and `broadcast` may be quoted or not.
+    pub fn parse(parts: &[&str], quoted: Option<String>) -> Result<Self, String> {
+        let text = quoted.or_else(|| Some(parts.get(1..)?.join(" "))).filter(|t| !t.trim().is_empty());
+        match parts.first().map(|p| p.to_lowercase()).as_deref() {
+            Some("flush") => Ok(Action::Flush),
+            Some("reload") => Ok(Action::Reload),
+            Some("pause") => Ok(Action::Pause(text)),
+            Some("resume") => Ok(Action::Resume),
+            Some("queue") => Ok(Action::Queue),
+            Some("broadcast") => text.map(Action::Broadcast).ok_or_else(|| "Usage: `!cat admin broadcast \"message\"`".to_string()),
+            _ => Err("Usage: `!cat admin <flush|reload|pause|resume|queue|broadcast> [\"message\"]`".to_string()),
+        }
+    }
+}
+
+// One line per job, running ones first, with how long each has been running or waiting
+fn describe_queue(jobs: &[QueuedJob], now: Instant) -> String {
+    if jobs.is_empty() {
+        return "The queue is empty.".to_string();
+    }
+    let (running, waiting): (Vec<&QueuedJob>, Vec<&QueuedJob>) = jobs.iter().partition(|q| q.started.is_some());
+    let mut text = format!("**Processing queue:** {} running, {} waiting\n", running.len(), waiting.len());
+    for queued in running.iter().chain(&waiting).take(MAX_QUEUE_LINES) {
+        let line = match queued.started {
+            Some(started) => format!("▶️ `{}` for <@{}>, running {}", queued.job.command, queued.job.user, eta::describe(now - started)),
+            None => format!("⏳ `{}` for <@{}>, waiting {}", queued.job.command, queued.job.user, eta::describe(now - queued.queued)),
+        };
+        text.push_str(&line);
+        text.push('\n');
+    }
+    if jobs.len() > MAX_QUEUE_LINES {
+        text.push_str(&format!("…and {} more", jobs.len() - MAX_QUEUE_LINES));
+    }
+    text
+}
+
+/// Run `!cat admin ...` for an owner. `parts` are the words after `admin`.
+pub async fn handle(http: &Http, msg: &Message, parts: &[&str]) {
+    if !is_owner(msg.author.id) {
+        warn!(user = %msg.author.id, "Non-owner tried an admin command");
+        let _ = responses::warning(http, msg.channel_id, "Admin commands are only available to the bot's owners.").await;
+        return;
+    }
+    let quoted = utils::extract_quoted_args(&msg.content).into_iter().next();
+    let action = match Action::parse(parts, quoted) {
+        Ok(action) => action,
+        Err(usage) => {
+            let _ = responses::warning(http, msg.channel_id, usage).await;
+            return;
+        }
+    };
+    info!(admin = %msg.author.id, ?action, "Admin action");
+    match action {
+        Action::Flush => {
+            let luts = image_processing::clear_lut_cache();
+            let results = jobs::clear_shared();
+            let _ = responses::success(http, msg.channel_id, format!("🧹 Flushed {} cached LUTs and {} shared results.", luts, results)).await;
+        }
+        Action::Reload => match config::reload() {
+            Ok(config) => {
+                let text = format!(
+                    "🔄 Reloaded configuration: {} owners, {} notice channels.",
+                    config.owners.len(),
+                    config.notice_channels.len()
+                );
+                let _ = responses::success(http, msg.channel_id, text).await;
+            }
+            Err(e) => {
+                let _ = responses::error(http, msg.channel_id, format!("Failed to reload configuration: {}", e)).await;
+            }
+        },
+        Action::Pause(reason) => {
+            *PAUSE.lock().unwrap() = Some(Pause { reason, since: Instant::now() });
+            let _ = responses::success(http, msg.channel_id, "⏸️ Paused job intake. Jobs already running will finish.").await;
+        }
+        Action::Resume => {
+            let text = match PAUSE.lock().unwrap().take() {
+                Some(pause) => format!("▶️ Resumed job intake after {}.", eta::describe(pause.since.elapsed()).trim_start_matches('~')),
+                None => "Job intake wasn't paused.".to_string(),
+            };
+            let _ = responses::success(http, msg.channel_id, text).await;
+        }
+        Action::Queue => {
+            let _ = responses::info(http, msg.channel_id, format!("{}\n{}", describe_queue(&jobs::queue(), Instant::now()), memory::describe())).await;
+        }
+        Action::Broadcast(text) => {
+            let mut channels = config::get().notice_channels.clone();
+            channels.extend(config::announcement_channels());
+            channels.sort();
+            channels.dedup();
+            let mut sent = 0;
+            for channel_id in &channels {
+                match responses::warning(http, *channel_id, format!("📢 **Maintenance notice**\n{}", text)).await {
+                    Ok(_) => sent += 1,
+                    Err(why) => warn!(?why, %channel_id, "Failed to send maintenance notice"),
+                }
+            }
+            let _ = responses::success(http, msg.channel_id, format!("📢 Sent the notice to {} of {} channels.", sent, channels.len())).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jobs::Job;
+    use serenity::model::id::MessageId;
+    use std::time::Duration;
+
+    #[test]
+    fn test_parse_actions() {
+        assert_eq!(Action::parse(&["flush"], None), Ok(Action::Flush));
+        assert_eq!(Action::parse(&["PAUSE"], None), Ok(Action::Pause(None)));
+        assert_eq!(Action::parse(&["pause", "\"Updating"], Some("Updating".to_string())), Ok(Action::Pause(Some("Updating".to_string()))));
+        assert_eq!(Action::parse(&["broadcast", "back", "soon"], None), Ok(Action::Broadcast("back soon".to_string())));
+        assert!(Action::parse(&["broadcast"], None).is_err());
+        assert!(Action::parse(&["explode"], None).is_err());
+        assert!(Action::parse(&[], None).is_err());
+    }
+
+    #[test]
+    fn test_describe_queue() {
+        let now = Instant::now();
+        let job = |command| Job { command, user: UserId::new(7), message: MessageId::new(1) };
+        assert_eq!(describe_queue(&[], now), "The queue is empty.");
+        let jobs = [
+            QueuedJob { job: job("tile"), queued: now - Duration::from_secs(9), started: None },
+            QueuedJob { job: job("process"), queued: now - Duration::from_secs(30), started: Some(now - Duration::from_secs(20)) },
+        ];
+        let text = describe_queue(&jobs, now);
+        assert!(text.starts_with("**Processing queue:** 1 running, 1 waiting\n"));
+        // Running jobs are listed before waiting ones
+        assert!(text.contains("▶️ `process` for <@7>, running ~20 s\n⏳ `tile` for <@7>, waiting ~9 s"));
+    }
+}
@@ -0,0 +1,81 @@
+// src/archive.rs
+
+// Every entry is dated 1980-01-01 00:00, the earliest DOS date, so archives of the same files are identical
+const DOS_DATE: u16 = (1 << 5) | 1;
+// General purpose flag marking names as UTF-8
+const UTF8_NAMES: u16 = 1 << 11;
+
+/// Pack files into a zip archive. Entries are stored uncompressed: what goes in (PNGs) is already compressed.
+pub fn write_zip(files: &[(String, Vec<u8>)]) -> Vec<u8> {
+    let mut zip = Vec::new();
+    let mut central_directory = Vec::new();
+    for (name, data) in files {
+        let offset = zip.len() as u32;
+        let crc = crc32fast::hash(data);
+        // The fields a local header shares with its central directory entry, from "version needed" on
+        let mut shared = Vec::new();
+        shared.extend_from_slice(&20u16.to_le_bytes());
+        shared.extend_from_slice(&UTF8_NAMES.to_le_bytes());
+        shared.extend_from_slice(&0u16.to_le_bytes()); // stored
+        shared.extend_from_slice(&0u16.to_le_bytes()); // time
+        shared.extend_from_slice(&DOS_DATE.to_le_bytes());
+        shared.extend_from_slice(&crc.to_le_bytes());
+        shared.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        shared.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        shared.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        shared.extend_from_slice(&0u16.to_le_bytes()); // extra field length
+
+        zip.extend_from_slice(&0x04034b50u32.to_le_bytes());
+        zip.extend_from_slice(&shared);
+        zip.extend_from_slice(name.as_bytes());
+        zip.extend_from_slice(data);
+
+        central_directory.extend_from_slice(&0x02014b50u32.to_le_bytes());
+        central_directory.extend_from_slice(&20u16.to_le_bytes()); // version made by
+        central_directory.extend_from_slice(&shared);
+        central_directory.extend_from_slice(&0u16.to_le_bytes()); // comment length
+        central_directory.extend_from_slice(&0u16.to_le_bytes()); // disk number
+        central_directory.extend_from_slice(&0u16.to_le_bytes()); // internal attributes
+        central_directory.extend_from_slice(&0u32.to_le_bytes()); // external attributes
+        central_directory.extend_from_slice(&offset.to_le_bytes());
+        central_directory.extend_from_slice(name.as_bytes());
+    }
+    let directory_offset = zip.len() as u32;
+    let directory_size = central_directory.len() as u32;
+    zip.extend(central_directory);
+    zip.extend_from_slice(&0x06054b50u32.to_le_bytes());
+    zip.extend_from_slice(&0u16.to_le_bytes());
+    zip.extend_from_slice(&0u16.to_le_bytes());
+    zip.extend_from_slice(&(files.len() as u16).to_le_bytes());
+    zip.extend_from_slice(&(files.len() as u16).to_le_bytes());
+    zip.extend_from_slice(&directory_size.to_le_bytes());
+    zip.extend_from_slice(&directory_offset.to_le_bytes());
+    zip.extend_from_slice(&0u16.to_le_bytes()); // comment length
+    zip
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_zip_layout() {
+        let files = vec![("a.txt".to_string(), b"hello".to_vec()), ("b/c.txt".to_string(), Vec::new())];
+        let zip = write_zip(&files);
+        let u16_at = |i: usize| u16::from_le_bytes(zip[i..i + 2].try_into().unwrap());
+        let u32_at = |i: usize| u32::from_le_bytes(zip[i..i + 4].try_into().unwrap());
+        assert_eq!(u32_at(0), 0x04034b50);
+        assert_eq!(u32_at(14), crc32fast::hash(b"hello"));
+        assert_eq!(&zip[30..35], b"a.txt");
+        assert_eq!(&zip[35..40], b"hello");
+        // The end record points back at a central directory holding both entries
+        let end = zip.len() - 22;
+        assert_eq!(u32_at(end), 0x06054b50);
+        assert_eq!(u16_at(end + 10), 2);
+        let directory = u32_at(end + 16) as usize;
+        assert_eq!(u32_at(directory), 0x02014b50);
+        assert_eq!(directory + u32_at(end + 12) as usize, end);
+        // The second entry's local header follows the first entry's data
+        assert_eq!(u32_at(40), 0x04034b50);
+    }
+}
@@ -0,0 +1,97 @@
+// src/audit.rs
+
+use crate::convert::{self, Conversion};
+use catppuccin::FlavorName;
+use image::RgbaImage;
+use serenity::model::channel::Embed;
+
+/// Most images of a message `!cat audit` samples
+pub const MAX_IMAGES: usize = 4;
+
+/// Dominant colors taken from each image
+const COLORS_PER_IMAGE: usize = 5;
+
+/// At or under this ΔE a color can't be told apart from its palette match
+const ON_PALETTE: f32 = 2.0;
+/// Under this ΔE a color reads as its palette match at a glance
+const CLOSE: f32 = 10.0;
+
+/// The side color of each embed that has one, labelled by position
+pub fn embed_colors(embeds: &[Embed]) -> Vec<(String, (u8, u8, u8))> {
+    embeds
+        .iter()
+        .enumerate()
+        .filter_map(|(i, embed)| embed.colour.map(|colour| (format!("embed {}", i + 1), colour.tuple())))
+        .collect()
+}
+
+/// An image's most used colors, labelled with its name. Large images are sampled from a thumbnail,
+/// so near-identical shades from compression count together.
+pub fn image_colors(label: &str, img: &RgbaImage) -> Vec<(String, (u8, u8, u8))> {
+    let (dominant, _) = if img.width().max(img.height()) > 128 {
+        crate::image_processing::analyze_image_colors(&crate::image_processing::resize_longest_side(img, 128))
+    } else {
+        crate::image_processing::analyze_image_colors(img)
+    };
+    dominant.into_iter().take(COLORS_PER_IMAGE).map(|(r, g, b, _)| (label.to_string(), (r, g, b))).collect()
+}
+
+/// The flavor the colors sit closest to overall
+pub fn best_flavor(colors: &[(String, (u8, u8, u8))]) -> FlavorName {
+    let total = |flavor: FlavorName| convert::convert_colors(colors, flavor).iter().map(Conversion::delta_e).sum::<f32>();
+    [FlavorName::Latte, FlavorName::Frappe, FlavorName::Macchiato, FlavorName::Mocha]
+        .into_iter()
+        .min_by(|a, b| total(*a).total_cmp(&total(*b)))
+        .unwrap()
+}
+
+/// How many colors are on the palette, out of all of them
+pub fn compliant_count(conversions: &[Conversion]) -> usize {
+    conversions.iter().filter(|c| c.delta_e() <= ON_PALETTE).count()
+}
+
+/// One line per color: on the palette, close to a palette color, or off it, with the substitution to make
+pub fn report_lines(conversions: &[Conversion]) -> Vec<String> {
+    conversions
+        .iter()
+        .map(|c| {
+            let hex = |(r, g, b): (u8, u8, u8)| format!("#{:02x}{:02x}{:02x}", r, g, b);
+            let delta_e = c.delta_e();
+            if delta_e <= ON_PALETTE {
+                format!("✅ {} `{}` is **{}**", c.input, hex(c.rgb), c.name)
+            } else {
+                let marker = if delta_e < CLOSE { "🟡" } else { "❌" };
+                format!("{} {} `{}` → **{}** `{}` (ΔE {:.1})", marker, c.input, hex(c.rgb), c.name, hex(c.matched), delta_e)
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_audit_report() {
+        let colors = vec![("embed 1".to_string(), (203, 166, 247)), ("banner.png".to_string(), (255, 0, 0))];
+        assert_eq!(best_flavor(&colors[..1]), FlavorName::Mocha);
+        let conversions = convert::convert_colors(&colors, FlavorName::Mocha);
+        assert_eq!(compliant_count(&conversions), 1);
+        let lines = report_lines(&conversions);
+        assert_eq!(lines[0], "✅ embed 1 `#cba6f7` is **mauve**");
+        assert!(lines[1].starts_with("❌ banner.png `#ff0000` → **red** `#f38ba8`"));
+    }
+
+    #[test]
+    fn test_image_colors() {
+        let mut img = RgbaImage::from_pixel(200, 100, image::Rgba([30, 30, 46, 255]));
+        for x in 0..50 {
+            for y in 0..100 {
+                img.put_pixel(x, y, image::Rgba([137, 180, 250, 255]));
+            }
+        }
+        let colors = image_colors("shot.png", &img);
+        assert_eq!(colors[0], ("shot.png".to_string(), (30, 30, 46)));
+        assert_eq!(colors[1].1, (137, 180, 250));
+    }
+}
@@ -0,0 +1,324 @@
+// src/autochannel.rs
+
+use crate::admin;
+use crate::config::{self, GuildSettings};
+use crate::icc;
+use crate::image_processing;
+use crate::jobs::{self, Job};
+use crate::limits;
+use crate::memory;
+use crate::policy;
+use crate::responses;
+use crate::setup;
+use crate::source::{self, SourceKind};
+use crate::utils;
+use catppuccin::FlavorName;
+use dashmap::DashMap;
+use image::ImageFormat;
+use once_cell::sync::Lazy;
+use serenity::builder::{CreateAttachment, CreateMessage};
+use serenity::model::channel::{Message, Reaction, ReactionType};
+use serenity::model::id::ChannelId;
+use serenity::prelude::Context;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+use tracing::{debug, error, info, warn};
+
+/// Reacting with this on an automatic reply to your own image removes the reply and opts you out
+pub const OPT_OUT_EMOJI: char = '🚫';
+
+/// Automatic replies use the default algorithm; there's no command to pick another
+const ALGORITHM: &str = "shepards-method";
+
+/// The same image posted again in a channel within this long isn't processed twice
+const DEDUP_WINDOW: Duration = Duration::from_secs(10 * 60);
+
+// Passive processing shouldn't crowd out commands: at most this many images per member and per channel a minute
+static MEMBER_LIMIT: Lazy<RateLimiter> = Lazy::new(|| RateLimiter::new(3, Duration::from_secs(60)));
+static CHANNEL_LIMIT: Lazy<RateLimiter> = Lazy::new(|| RateLimiter::new(10, Duration::from_secs(60)));
+
+// When each image was last processed, by channel and image contents
+static RECENT_IMAGES: Lazy<DashMap<(ChannelId, u64), Instant>> = Lazy::new(DashMap::new);
+
+/// At most `max` events per key in any `window`
+pub struct RateLimiter {
+    max: usize,
+    window: Duration,
+    hits: DashMap<u64, VecDeque<Instant>>,
+}
+
+impl RateLimiter {
+    pub fn new(max: usize, window: Duration) -> Self {
+        RateLimiter { max, window, hits: DashMap::new() }
+    }
+
+    /// Count an event for a key if it's within the limit; returns whether it was
+    pub fn allow(&self, key: u64, now: Instant) -> bool {
+        let mut hits = self.hits.entry(key).or_default();
+        while hits.front().is_some_and(|at| now.duration_since(*at) >= self.window) {
+            hits.pop_front();
+        }
+        if hits.len() >= self.max {
+            return false;
+        }
+        hits.push_back(now);
+        true
+    }
+}
+
+// Whether an image was already processed in a channel within `DEDUP_WINDOW`; records it if not
+fn seen_recently(channel_id: ChannelId, image_key: u64, now: Instant) -> bool {
+    RECENT_IMAGES.retain(|_, at| now.duration_since(*at) < DEDUP_WINDOW);
+    let mut seen = true;
+    RECENT_IMAGES.entry((channel_id, image_key)).or_insert_with(|| {
+        seen = false;
+        now
+    });
+    seen
+}
+
+/// Catppuccinify a still image (as PNG) or an animated GIF, returning the bytes and their extension.
+/// Images over the size limit are shrunk to fit with `autoscale`, the server's setting.
+pub fn catppuccinify(bytes: &[u8], flavor: FlavorName, autoscale: bool) -> Result<(Vec<u8>, &'static str), String> {
+    if image::guess_format(bytes).ok() == Some(ImageFormat::Gif) {
+        let resized = limits::fit_gif(bytes, autoscale)?.map(|(gif, _)| gif);
+        return image_processing::process_gif_with_palette(resized.as_deref().unwrap_or(bytes), flavor, ALGORITHM).map(|gif| (gif, "gif"));
+    }
+    let img = icc::decode(bytes).map_err(|e| format!("Failed to decode image: {e}"))?;
+    let (img, _) = limits::fit_still(img, autoscale)?;
+    let processed = image_processing::process_image_with_palette(&img, flavor, ALGORITHM);
+    let mut buffer = std::io::Cursor::new(Vec::new());
+    processed.write_to(&mut buffer, ImageFormat::Png).map_err(|e| format!("Failed to encode image: {e}"))?;
+    Ok((icc::tag_srgb_png(buffer.into_inner()), "png"))
+}
+
+/// Memory to reserve for `catppuccinify` on these bytes, sized from the GIF or image header
+pub fn memory_estimate(bytes: &[u8]) -> u64 {
+    if let Ok((width, height, frames)) = image_processing::gif_size(bytes) {
+        return memory::gif_estimate(width, height, frames);
+    }
+    let (width, height) = image::ImageReader::new(std::io::Cursor::new(bytes))
+        .with_guessed_format()
+        .ok()
+        .and_then(|reader| reader.into_dimensions().ok())
+        .unwrap_or((0, 0));
+    memory::still_estimate(width, height)
+}
+
+/// Catppuccinify the first image of a message posted in an auto-processing channel and reply with it.
+/// Nobody asked for the reply, so anything that stops it (another channel, an opted-out member, a
+/// repeat, the rate limits, paused intake, a failure) is logged rather than posted.
+pub async fn handle(ctx: &Context, msg: &Message) {
+    let Some(guild_id) = msg.guild_id else {
+        return;
+    };
+    let settings = config::guild(guild_id);
+    let Some(flavor) = settings.auto_channels.get(&msg.channel_id).copied() else {
+        return;
+    };
+    if settings.auto_opt_out.contains(&msg.author.id) {
+        return;
+    }
+    let Some(image) = source::message_sources(msg).into_iter().find(|s| s.kind == SourceKind::Image) else {
+        return;
+    };
+    if admin::intake_notice(msg.author.id, &["auto"]).is_some() {
+        debug!(channel = %msg.channel_id, "Skipped automatic processing while intake is paused");
+        return;
+    }
+    let parts: Vec<&str> = msg.content.split_whitespace().collect();
+    if let Some(refusal) = policy::vet(ctx, msg, &parts).await {
+        debug!(channel = %msg.channel_id, %refusal, "Skipped an image refused by the server's content policy");
+        return;
+    }
+    let now = Instant::now();
+    if !MEMBER_LIMIT.allow(msg.author.id.get(), now) || !CHANNEL_LIMIT.allow(msg.channel_id.get(), now) {
+        debug!(user = %msg.author.id, channel = %msg.channel_id, "Skipped automatic processing over the rate limit");
+        return;
+    }
+    let bytes = match source::fetch_url(&image.url).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            warn!(error = %e, url = %image.url, "Failed to download image for automatic processing");
+            return;
+        }
+    };
+    if seen_recently(msg.channel_id, jobs::content_key(&bytes), now) {
+        debug!(channel = %msg.channel_id, "Skipped an image already processed in this channel");
+        return;
+    }
+    let autoscale = settings.autoscale;
+    // Nobody is waiting on a reply, so the wait for memory goes unannounced
+    let _reservation = memory::reserve(memory_estimate(&bytes), config::get().max_job_memory).await;
+    let (output, extension) = match jobs::run(Job::new("auto", msg), move || catppuccinify(&bytes, flavor, autoscale)).await {
+        Ok(Ok(output)) => output,
+        Ok(Err(e)) => {
+            warn!(error = %e, url = %image.url, "Automatic processing failed");
+            return;
+        }
+        // Already logged with its error ID
+        Err(_) => return,
+    };
+    let filename = utils::sanitize_filename(&format!("catppuccinified_{}.{}", flavor.identifier(), extension), extension);
+    let content = format!("**Catppuccinified with {}** · React {} if you'd rather I left your images alone", flavor, OPT_OUT_EMOJI);
+    let builder = CreateMessage::new().content(content).reference_message(msg);
+    match msg.channel_id.send_files(&ctx.http, vec![CreateAttachment::bytes(output, filename)], builder).await {
+        Ok(reply) => {
+            if let Err(why) = reply.react(&ctx.http, OPT_OUT_EMOJI).await {
+                warn!(?why, "Failed to add the opt-out reaction");
+            }
+        }
+        Err(why) => warn!(?why, channel = %msg.channel_id, "Failed to send automatic reply"),
+    }
+}
+
+/// When the author of an automatically processed image reacts with `OPT_OUT_EMOJI` on the reply,
+/// delete the reply and stop processing their images in that server. Like the help pages this keeps
+/// no state: the reply itself says whose image it answered, so it works across restarts.
+pub async fn handle_reaction(ctx: &Context, reaction: &Reaction) {
+    let (Some(guild_id), Some(user_id)) = (reaction.guild_id, reaction.user_id) else {
+        return;
+    };
+    let is_opt_out = matches!(&reaction.emoji, ReactionType::Unicode(emoji) if emoji.starts_with(OPT_OUT_EMOJI));
+    if !is_opt_out || reaction.message_author_id != Some(ctx.cache.current_user().id) {
+        return;
+    }
+    if !config::guild(guild_id).auto_channels.contains_key(&reaction.channel_id) {
+        return;
+    }
+    let reply = match reaction.message(&ctx.http).await {
+        Ok(reply) => reply,
+        Err(why) => {
+            warn!(?why, "Failed to fetch the message an opt-out reaction was added to");
+            return;
+        }
+    };
+    let answered = reply.referenced_message.as_ref().map(|original| original.author.id);
+    if answered != Some(user_id) || !reply.content.contains(OPT_OUT_EMOJI) {
+        return;
+    }
+    if let Err(why) = reply.delete(&ctx.http).await {
+        warn!(?why, "Failed to delete an automatic reply");
+    }
+    let opted_out = config::update_guild(guild_id, |settings| {
+        if !settings.auto_opt_out.contains(&user_id) {
+            settings.auto_opt_out.push(user_id);
+        }
+    });
+    if let Err(e) = opted_out {
+        error!(error = %e, guild = %guild_id, "Failed to save an auto-processing opt-out");
+    }
+    info!(user = %user_id, guild = %guild_id, "Member opted out of automatic processing");
+    // Say how to undo it privately rather than in the channel
+    if let Ok(dm) = user_id.create_dm_channel(&ctx.http).await {
+        let text = "I won't reply to your images in auto-processing channels anymore. Send `!cat config autochannel optin` in the server to undo this.";
+        let _ = responses::info(&ctx.http, dm.id, text).await;
+    }
+}
+
+// The server's auto-processing channels, one per line
+fn describe_auto_channels(settings: &GuildSettings) -> String {
+    if settings.auto_channels.is_empty() {
+        return "No auto-processing channels yet. Add one with `!cat config autochannel #channel mocha`.".to_string();
+    }
+    let mut channels: Vec<_> = settings.auto_channels.iter().collect();
+    channels.sort_by_key(|(channel_id, _)| **channel_id);
+    let lines: Vec<String> = channels.iter().map(|(channel_id, flavor)| format!("<#{}> — {}", channel_id, flavor)).collect();
+    format!("**Auto-processing channels:**\n{}", lines.join("\n"))
+}
+
+/// `!cat config autochannel [#channel] [flavor] [off|optin]`; `parts` are the words after `autochannel`.
+/// Without a channel it lists the server's auto-processing channels.
+pub async fn configure(ctx: &Context, msg: &Message, parts: &[&str]) {
+    let Some(guild_id) = msg.guild_id else {
+        let _ = responses::warning(&ctx.http, msg.channel_id, "Auto-processing channels are a server setting; run this in a server.").await;
+        return;
+    };
+    let has = |word: &str| parts.iter().any(|p| p.eq_ignore_ascii_case(word));
+    // Anyone can undo their own opt-out
+    if has("optin") {
+        let author = msg.author.id;
+        let text = match config::update_guild(guild_id, |settings| settings.auto_opt_out.retain(|user| *user != author)) {
+            Ok(_) => format!("You'll get automatic replies to your images again. React {} on one to opt out.", OPT_OUT_EMOJI),
+            Err(e) => format!("Couldn't save that: {}", e),
+        };
+        let _ = responses::success(&ctx.http, msg.channel_id, text).await;
+        return;
+    }
+    let Some(channel_id) = parts.iter().find_map(|p| utils::parse_channel_mention(p)) else {
+        let _ = responses::info(&ctx.http, msg.channel_id, describe_auto_channels(&config::guild(guild_id))).await;
+        return;
+    };
+    if !setup::can_manage(&ctx.cache, msg) {
+        let _ = responses::warning(&ctx.http, msg.channel_id, "You need the **Manage Server** permission to change auto-processing channels.").await;
+        return;
+    }
+    let off = has("off");
+    let flavor = parts.iter().find_map(|p| utils::parse_flavor(p)).unwrap_or_else(|| config::default_flavor(Some(guild_id)));
+    let saved = config::update_guild(guild_id, |settings| {
+        if off {
+            settings.auto_channels.remove(&channel_id);
+        } else {
+            settings.auto_channels.insert(channel_id, flavor);
+        }
+    });
+    match saved {
+        Ok(_) => {
+            info!(guild = %guild_id, channel = %channel_id, off, "Auto-processing channel changed");
+            let text = if off {
+                format!("Stopped automatic processing in <#{}>.", channel_id)
+            } else {
+                format!(
+                    "Every image posted in <#{}> will now be Catppuccinified with {}. Members can react {} on a reply to opt out.",
+                    channel_id, flavor, OPT_OUT_EMOJI
+                )
+            };
+            let _ = responses::success(&ctx.http, msg.channel_id, text).await;
+        }
+        Err(e) => {
+            error!(error = %e, guild = %guild_id, "Failed to save auto-processing channel");
+            let _ = responses::error(&ctx.http, msg.channel_id, "The change applies for now, but couldn't be saved and will be lost on restart.").await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_rate_limiter_window() {
+        let limiter = RateLimiter::new(2, Duration::from_secs(60));
+        let start = Instant::now();
+        assert!(limiter.allow(1, start));
+        assert!(limiter.allow(1, start + Duration::from_secs(1)));
+        assert!(!limiter.allow(1, start + Duration::from_secs(2)));
+        // Keys are limited separately
+        assert!(limiter.allow(2, start + Duration::from_secs(2)));
+        // The first event has left the window
+        assert!(limiter.allow(1, start + Duration::from_secs(60)));
+        assert!(!limiter.allow(1, start + Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn test_seen_recently() {
+        let now = Instant::now();
+        let channel = ChannelId::new(424242);
+        assert!(!seen_recently(channel, 7, now));
+        assert!(seen_recently(channel, 7, now + Duration::from_secs(5)));
+        assert!(!seen_recently(channel, 8, now));
+        assert!(!seen_recently(ChannelId::new(434343), 7, now));
+        assert!(!seen_recently(channel, 7, now + DEDUP_WINDOW));
+    }
+
+    #[test]
+    fn test_describe_auto_channels() {
+        assert!(describe_auto_channels(&GuildSettings::default()).starts_with("No auto-processing channels"));
+        let settings = GuildSettings {
+            auto_channels: HashMap::from([(ChannelId::new(20), FlavorName::Mocha), (ChannelId::new(10), FlavorName::Frappe)]),
+            ..Default::default()
+        };
+        assert_eq!(describe_auto_channels(&settings), "**Auto-processing channels:**\n<#10> — Frappé\n<#20> — Mocha");
+    }
+}
@@ -0,0 +1,139 @@
+// src/bench.rs
+
+//! The workloads behind `benches/core.rs`, with a small runner so the same numbers can be taken
+//! without Criterion (from CI, or a quick local check) and compared against a saved baseline
+
+use crate::image_processing;
+use catppuccin::FlavorName;
+use image::{Rgba, RgbaImage};
+use std::time::{Duration, Instant};
+
+/// Every palette-mapping algorithm, in the order they're benchmarked
+pub const ALGORITHMS: &[&str] = &[
+    "shepards-method", "gaussian-rbf", "linear-rbf", "gaussian-sampling", "nearest-neighbor", "nearest-k", "hald", "euclide", "mean", "std",
+];
+
+/// A deterministic image that covers the whole color cube: red across, green down, and a blue pattern
+pub fn sample_image(width: u32, height: u32) -> RgbaImage {
+    RgbaImage::from_fn(width, height, |x, y| {
+        Rgba([(x * 255 / width.max(2).saturating_sub(1)) as u8, (y * 255 / height.max(2).saturating_sub(1)) as u8, ((x ^ y) & 0xFF) as u8, 255])
+    })
+}
+
+/// A deterministic animated GIF; each frame shifts the blue channel so frames differ
+pub fn sample_gif(width: u32, height: u32, frames: u32) -> Vec<u8> {
+    let frames = (0..frames)
+        .map(|i| {
+            let mut frame = sample_image(width, height);
+            frame.pixels_mut().for_each(|pixel| pixel[2] = pixel[2].wrapping_add((i * 16) as u8));
+            (frame, 4)
+        })
+        .collect();
+    image_processing::encode_gif_frames(frames).expect("sample frames encode")
+}
+
+/// Build one LUT from scratch, skipping the cache
+pub fn lut_generation(flavor: FlavorName, algorithm: &str) -> Vec<u8> {
+    image_processing::build_catppuccin_lut(flavor, algorithm)
+}
+
+/// Map an image through a LUT
+pub fn lut_application(img: &mut RgbaImage, lut: &[u8]) {
+    image_processing::apply_lut_to_image(img, lut);
+}
+
+/// Decode, map, and re-encode every frame of a GIF
+pub fn gif_frames(gif_bytes: &[u8], flavor: FlavorName, algorithm: &str) -> Result<Vec<u8>, String> {
+    image_processing::process_gif_with_palette(gif_bytes, flavor, algorithm)
+}
+
+/// One timed workload: the best of its runs, and how much work a run does (pixels, frames, or LUTs)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Measurement {
+    pub name: String,
+    pub best: Duration,
+    pub units: f64,
+    pub unit: &'static str,
+}
+
+impl Measurement {
+    /// Units per second
+    pub fn throughput(&self) -> f64 {
+        self.units / self.best.as_secs_f64().max(f64::EPSILON)
+    }
+
+    /// `name<TAB>nanoseconds`, the line format baselines are saved in
+    pub fn to_line(&self) -> String {
+        format!("{}\t{}", self.name, self.best.as_nanos())
+    }
+}
+
+fn measure(name: String, runs: u32, units: f64, unit: &'static str, mut work: impl FnMut()) -> Measurement {
+    let best = (0..runs.max(1))
+        .map(|_| {
+            let start = Instant::now();
+            work();
+            start.elapsed()
+        })
+        .min()
+        .unwrap_or_default();
+    Measurement { name, best, units, unit }
+}
+
+/// Time LUT generation for each of `algorithms`, then LUT application on a megapixel and a 12-frame GIF
+/// with nearest-neighbor. LUT generation dominates: expect seconds per algorithm in a release build.
+pub fn run(algorithms: &[&str], runs: u32) -> Vec<Measurement> {
+    let flavor = FlavorName::Mocha;
+    let mut results: Vec<Measurement> = algorithms
+        .iter()
+        .map(|algorithm| measure(format!("lut_generation/{algorithm}"), runs, 1.0, "LUTs", || drop(lut_generation(flavor, algorithm))))
+        .collect();
+    // Application doesn't depend on how the LUT was built
+    let lut = image_processing::generate_catppuccin_lut(flavor, "nearest-neighbor");
+    let img = sample_image(1000, 1000);
+    results.push(measure("lut_application/1_megapixel".to_string(), runs, (img.width() * img.height()) as f64, "pixels", || {
+        let mut mapped = img.clone();
+        lut_application(&mut mapped, &lut);
+    }));
+    let gif = sample_gif(128, 128, 12);
+    results.push(measure("gif_frames/128x128".to_string(), runs, 12.0, "frames", || {
+        let _ = gif_frames(&gif, flavor, "nearest-neighbor");
+    }));
+    results
+}
+
+/// Parse a baseline saved with `Measurement::to_line`, one per line
+pub fn parse_baseline(text: &str) -> Vec<(String, Duration)> {
+    text.lines()
+        .filter_map(|line| {
+            let (name, nanos) = line.split_once('\t')?;
+            Some((name.to_string(), Duration::from_nanos(nanos.trim().parse().ok()?)))
+        })
+        .collect()
+}
+
+/// The workloads more than `tolerance` slower than the baseline (0.1 is 10%), with their slowdown factor
+pub fn regressions(baseline: &[(String, Duration)], current: &[Measurement], tolerance: f64) -> Vec<(String, f64)> {
+    current
+        .iter()
+        .filter_map(|measurement| {
+            let (_, before) = baseline.iter().find(|(name, _)| *name == measurement.name)?;
+            let factor = measurement.best.as_secs_f64() / before.as_secs_f64().max(f64::EPSILON);
+            (factor > 1.0 + tolerance).then(|| (measurement.name.clone(), factor))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_regressions_against_baseline() {
+        let timed = |name: &str, millis| Measurement { name: name.to_string(), best: Duration::from_millis(millis), units: 1.0, unit: "LUTs" };
+        let baseline = parse_baseline(&[timed("lut_generation/std", 100), timed("gif_frames/std", 100)].map(|m| m.to_line()).join("\n"));
+        assert_eq!(baseline.len(), 2);
+        let current = [timed("lut_generation/std", 105), timed("gif_frames/std", 200), timed("lut_application/std", 1)];
+        assert_eq!(regressions(&baseline, &current, 0.1), vec![("gif_frames/std".to_string(), 2.0)]);
+    }
+}
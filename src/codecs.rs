@@ -0,0 +1,147 @@
+// src/codecs.rs
+
+use crate::video::ffmpeg_available;
+use crate::workdir::WorkDir;
+use image::codecs::jpeg::JpegEncoder;
+use image::{DynamicImage, ImageFormat};
+use std::process::{Command, Stdio};
+
+/// JPEG quality when none is asked for, the same as the `image` crate's own default
+const DEFAULT_JPEG_QUALITY: u8 = 75;
+
+/// AVIF quality when none is asked for; around JPEG 85 in looks at a fraction of the size
+const DEFAULT_AVIF_QUALITY: u8 = 60;
+
+/// Formats the `image` crate can't read, decoded through ffmpeg instead
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ModernFormat {
+    Avif,
+    Jxl,
+}
+
+impl ModernFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ModernFormat::Avif => "avif",
+            ModernFormat::Jxl => "jxl",
+        }
+    }
+
+    /// Recognize AVIF (an ISO-BMFF `ftyp` box naming an AVIF brand) and JPEG XL (a bare codestream or
+    /// its container) from their first bytes
+    pub fn sniff(bytes: &[u8]) -> Option<ModernFormat> {
+        if bytes.starts_with(&[0xFF, 0x0A]) || bytes.starts_with(b"\0\0\0\x0cJXL \r\n\x87\n") {
+            return Some(ModernFormat::Jxl);
+        }
+        if bytes.get(4..8) != Some(b"ftyp") {
+            return None;
+        }
+        // The major brand, then the compatible brands up to the end of the box
+        let box_end = u32::from_be_bytes(bytes.get(0..4)?.try_into().ok()?) as usize;
+        let brands = bytes.get(8..box_end.min(bytes.len()))?;
+        brands.chunks_exact(4).any(|brand| brand == b"avif" || brand == b"avis").then_some(ModernFormat::Avif)
+    }
+}
+
+/// Decode an AVIF or JPEG XL image to a 16-bit PNG, so 10- and 12-bit HDR sources keep their precision
+/// until they're tone-mapped
+pub fn decode_to_png(bytes: &[u8], format: ModernFormat) -> Result<Vec<u8>, String> {
+    if !ffmpeg_available() {
+        return Err(format!("Reading {} images needs ffmpeg, which isn't installed on this bot's host.", format.extension().to_uppercase()));
+    }
+    let work_dir = WorkDir::new()?;
+    // Named with its extension so ffmpeg probes the right demuxer
+    let input_path = work_dir.file(&format!("input.{}", format.extension()));
+    let output_path = work_dir.file("output.png");
+    std::fs::write(&input_path, bytes).map_err(|e| format!("Failed to buffer image: {e}"))?;
+    let output = Command::new("ffmpeg")
+        .args(["-hide_banner", "-loglevel", "error", "-y", "-i"])
+        .arg(&input_path)
+        .args(["-frames:v", "1", "-pix_fmt", "rgba64be"])
+        .arg(&output_path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()
+        .map_err(|e| format!("Failed to start ffmpeg: {e}"))?;
+    if !output.status.success() {
+        return Err(format!("ffmpeg failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    std::fs::read(&output_path).map_err(|e| format!("Failed to read decoded image: {e}"))
+}
+
+/// Whether `format` is written with every pixel's color exactly as given, which `--strict` output
+/// needs. WebP is listed with JPEG and AVIF as a compressed format, so it's left out too.
+pub fn keeps_exact_colors(format: ImageFormat) -> bool {
+    matches!(format, ImageFormat::Png | ImageFormat::Gif | ImageFormat::Bmp | ImageFormat::Tiff)
+}
+
+/// Encode an image in `format`. `quality` (1-100) applies to JPEG and AVIF and is ignored by lossless
+/// formats; AVIF goes through ffmpeg's libaom encoder.
+pub fn encode(img: &DynamicImage, format: ImageFormat, quality: Option<u8>) -> Result<Vec<u8>, String> {
+    let mut buffer = std::io::Cursor::new(Vec::new());
+    match format {
+        ImageFormat::Jpeg => {
+            // JPEG has no alpha channel
+            let encoder = JpegEncoder::new_with_quality(&mut buffer, quality.unwrap_or(DEFAULT_JPEG_QUALITY).clamp(1, 100));
+            img.to_rgb8().write_with_encoder(encoder).map_err(|e| format!("Failed to encode JPEG: {e}"))?;
+        }
+        ImageFormat::Avif => return encode_avif(img, quality.unwrap_or(DEFAULT_AVIF_QUALITY)),
+        _ => img.write_to(&mut buffer, format).map_err(|e| format!("Failed to encode image: {e}"))?,
+    }
+    Ok(buffer.into_inner())
+}
+
+// libaom's CRF runs from 0 (lossless) to 63; quality 100 maps to 0
+fn avif_crf(quality: u8) -> u8 {
+    ((100 - quality.clamp(1, 100) as u32) * 63 / 99) as u8
+}
+
+fn encode_avif(img: &DynamicImage, quality: u8) -> Result<Vec<u8>, String> {
+    if !ffmpeg_available() {
+        return Err("AVIF output needs ffmpeg, which isn't installed on this bot's host.".to_string());
+    }
+    let work_dir = WorkDir::new()?;
+    let input_path = work_dir.file("input.png");
+    let output_path = work_dir.file("output.avif");
+    DynamicImage::ImageRgba8(img.to_rgba8()).save_with_format(&input_path, ImageFormat::Png).map_err(|e| format!("Failed to buffer image: {e}"))?;
+    let output = Command::new("ffmpeg")
+        .args(["-hide_banner", "-loglevel", "error", "-y", "-i"])
+        .arg(&input_path)
+        // yuv420p needs even dimensions
+        .args(["-vf", "pad=ceil(iw/2)*2:ceil(ih/2)*2", "-c:v", "libaom-av1", "-still-picture", "1", "-cpu-used", "6"])
+        .args(["-crf", &avif_crf(quality).to_string(), "-pix_fmt", "yuv420p"])
+        .arg(&output_path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()
+        .map_err(|e| format!("Failed to start ffmpeg: {e}"))?;
+    if !output.status.success() {
+        return Err(format!("ffmpeg failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    std::fs::read(&output_path).map_err(|e| format!("Failed to read encoded image: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sniff_and_encode() {
+        let avif = [&[0, 0, 0, 0x1c][..], b"ftypmif1\0\0\0\0mif1avifmiaf"].concat();
+        assert_eq!(ModernFormat::sniff(&avif), Some(ModernFormat::Avif));
+        // HEIC shares the box layout but not the brand
+        assert_eq!(ModernFormat::sniff(&[&[0, 0, 0, 0x18][..], b"ftypheic\0\0\0\0mif1heic"].concat()), None);
+        assert_eq!(ModernFormat::sniff(&[0xFF, 0x0A, 0xFA]), Some(ModernFormat::Jxl));
+        assert_eq!(ModernFormat::sniff(b"\x89PNG\r\n\x1a\n"), None);
+        assert_eq!((avif_crf(100), avif_crf(1), avif_crf(60)), (0, 63, 25));
+        let img = DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(16, 16, image::Rgba([203, 166, 247, 128])));
+        let small = encode(&img, ImageFormat::Jpeg, Some(10)).unwrap();
+        let large = encode(&img, ImageFormat::Jpeg, Some(100)).unwrap();
+        assert!(small.len() < large.len());
+        assert_eq!(image::guess_format(&encode(&img, ImageFormat::Png, Some(10)).unwrap()).unwrap(), ImageFormat::Png);
+        assert!(keeps_exact_colors(ImageFormat::Png) && keeps_exact_colors(ImageFormat::Gif));
+        assert!(!keeps_exact_colors(ImageFormat::Jpeg) && !keeps_exact_colors(ImageFormat::WebP) && !keeps_exact_colors(ImageFormat::Avif));
+    }
+}
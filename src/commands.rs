@@ -3,216 +3,96 @@
 use serenity::async_trait;
 use serenity::model::channel::Message;
 use serenity::prelude::*;
-use crate::utils;
+use crate::utils::{self, rgb_to_hsl};
 use crate::palette;
 use crate::image_processing;
 use image::ImageReader;
-use regex;
 use tracing::{info, warn, error, debug};
-use crate::utils::MOCHA_MAUVE;
+use futures::StreamExt;
+use crate::responses;
+use crate::pagination::PagedEmbed;
+use crate::registry;
+use crate::source::{self, ImageFetcher, ImageSource};
+use crate::workdir::FrameStore;
+use crate::jobs::{self, CancelGuard, Job};
+use crate::memory;
+use crate::accessibility;
+use crate::admin;
+use crate::archive;
+use crate::audit;
+use crate::autochannel;
+use crate::codecs;
+use crate::config;
+use crate::convert;
+use crate::custom_palettes;
+use crate::emoji_retheme;
+use crate::error::BotError;
+use crate::eta;
+use crate::favorites;
+use crate::history;
+use crate::icc;
+use crate::export;
+use crate::icons;
+use crate::last_result;
+use crate::limits;
+use crate::outbox::Outbox;
+use crate::pdf;
+use crate::policy;
+use crate::progress;
+use crate::quiz;
+use crate::role_colors;
+use crate::schedule;
+use crate::screenshot;
+use crate::setup;
+use crate::svg;
+use crate::theme_file;
+use crate::timing;
+use crate::watermark;
 use indicatif::{ProgressBar, ProgressStyle};
 use std::time::Duration;
-use serenity::model::prelude::interaction::{Interaction, InteractionResponseType};
-use serenity::builder::{CreateButton, CreateActionRow};
+use serenity::model::application::{ButtonStyle, Interaction};
+use serenity::builder::{CreateActionRow, CreateButton, CreateInteractionResponse, CreateInteractionResponseMessage};
 use std::sync::Mutex;
 use once_cell::sync::Lazy;
-use image::Rgba;
 
-// --- Color conversion helpers for harmony ---
-fn rgb_to_hsl(r: u8, g: u8, b: u8) -> (f32, f32, f32) {
-    let r = r as f32 / 255.0;
-    let g = g as f32 / 255.0;
-    let b = b as f32 / 255.0;
-    let max = r.max(g).max(b);
-    let min = r.min(g).min(b);
-    let l = (max + min) / 2.0;
-    let d = max - min;
-    let (h, s);
-    if d == 0.0 {
-        h = 0.0;
-        s = 0.0;
-    } else {
-        s = if l > 0.5 { d / (2.0 - max - min) } else { d / (max + min) };
-        h = if max == r {
-            ((g - b) / d) % 6.0
-        } else if max == g {
-            ((b - r) / d) + 2.0
-        } else {
-            ((r - g) / d) + 4.0
-        } * 60.0;
-    }
-    let h = if h < 0.0 { h + 360.0 } else { h };
-    (h, s, l)
-}
-fn hsl_to_rgb(h: f32, s: f32, l: f32) -> (u8, u8, u8) {
-    let c = (1.0 - (2.0 * l - 1.0).abs()) * s;
-    let h_ = h / 60.0;
-    let x = c * (1.0 - ((h_ % 2.0) - 1.0).abs());
-    let (r1, g1, b1) = if (0.0..1.0).contains(&h_) {
-        (c, x, 0.0)
-    } else if (1.0..2.0).contains(&h_) {
-        (x, c, 0.0)
-    } else if (2.0..3.0).contains(&h_) {
-        (0.0, c, x)
-    } else if (3.0..4.0).contains(&h_) {
-        (0.0, x, c)
-    } else if (4.0..5.0).contains(&h_) {
-        (x, 0.0, c)
-    } else {
-        (c, 0.0, x)
-    };
-    let m = l - c / 2.0;
-    let r = ((r1 + m) * 255.0).round().clamp(0.0, 255.0) as u8;
-    let g = ((g1 + m) * 255.0).round().clamp(0.0, 255.0) as u8;
-    let b = ((b1 + m) * 255.0).round().clamp(0.0, 255.0) as u8;
-    (r, g, b)
-}
-
-// --- Color blindness simulation helper ---
-fn simulate_color_blindness(r: u8, g: u8, b: u8, kind: &str) -> (u8, u8, u8) {
-    // Matrices from https://ixora.io/projects/colorblindness/color-blindness-simulation-research/
-    let (m0, m1, m2) = match kind {
-        "protanopia" => ([0.56667, 0.43333, 0.0], [0.55833, 0.44167, 0.0], [0.0, 0.24167, 0.75833]),
-        "deuteranopia" => ([0.625, 0.375, 0.0], [0.7, 0.3, 0.0], [0.0, 0.3, 0.7]),
-        "tritanopia" => ([0.95, 0.05, 0.0], [0.0, 0.43333, 0.56667], [0.0, 0.475, 0.525]),
-        _ => ([1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]),
-    };
-    let rf = r as f32;
-    let gf = g as f32;
-    let bf = b as f32;
-    let r2 = (m0[0] * rf + m0[1] * gf + m0[2] * bf).clamp(0.0, 255.0);
-    let g2 = (m1[0] * rf + m1[1] * gf + m1[2] * bf).clamp(0.0, 255.0);
-    let b2 = (m2[0] * rf + m2[1] * gf + m2[2] * bf).clamp(0.0, 255.0);
-    (r2.round() as u8, g2.round() as u8, b2.round() as u8)
-}
+// A stored image with its format, dimensions, suggested flavor and algorithm
+type PendingAnalysis = (Vec<u8>, image::ImageFormat, u32, u32, catppuccin::FlavorName, String);
 
 // Store pending color analysis confirmations: (user_id, channel_id) -> (image bytes, suggested flavor, algorithm, etc.)
-static COLOR_CONFIRM_MAP: Lazy<Mutex<std::collections::HashMap<(u64, u64), (Vec<u8>, image::ImageFormat, u32, u32, catppuccin::FlavorName, String)>>> = Lazy::new(|| Mutex::new(std::collections::HashMap::new()));
+static COLOR_CONFIRM_MAP: Lazy<Mutex<std::collections::HashMap<(u64, u64), PendingAnalysis>>> = Lazy::new(|| Mutex::new(std::collections::HashMap::new()));
 
 pub struct Handler;
 
-// Helper function to send help message
-pub async fn send_help_message(ctx: &Context, channel_id: serenity::model::id::ChannelId) -> Result<(), serenity::Error> {
-    let help_parts = vec![
-        r#"**Catppuccinifier Bot Commands**
-
-**Basic Usage:**
-`!cat [image]` - Process image with default Latte flavor
-`!cat [flavor] [image]` - Process image with specific flavor
-`!cat [flavor] [algorithm] [image]` - Process image with flavor and algorithm
-
-**Hex Color Conversion:**
-`!cat #FF0000` - Convert hex color to Catppuccin
-`!cat [flavor] #FF0000` - Convert hex color with specific flavor
-
-**Color Palette Preview:**
-`!cat palette [flavor]` - Show all colors in a specific flavor
-`!cat palette all` - Show all flavors' color palettes
-
-**Before/After Comparison:**
-`!cat compare [image]` - Send original + processed image side by side
+// Most images one command will batch-process; Discord allows at most 10 files per message
+const MAX_BATCH_IMAGES: usize = 10;
 
-**Batch Processing:**
-`!cat batch [multiple images]` - Process multiple images at once
+// Batch images downloaded at once, ahead of the one being processed
+const BATCH_DOWNLOADS: usize = 4;
 
-**Quality Settings:**
-`!cat [flavor] [quality] [image]` - quality: fast, normal, high
-
-**Color Statistics:**
-`!cat stats [image]` - Show dominant colors and suggest best flavor
-
-**Export Options:**
-`!cat [flavor] [format] [image]` - format: png, jpg, webp
-
-**All Flavors Processing:**
-`!cat all [image]` - Process image with all 4 flavors (Latte, Frappe, Macchiato, Mocha)
-
-**Random Color/Palette:**
-`!cat random` - Get a random Catppuccin color
-`!cat random palette` - Get a random palette preview
-
-**List Options:**
-`!cat list` - List all flavors, algorithms, formats
-
-**Cancel:**
-`!cat cancel` - Cancel your current job
-
-**Help:**
-`!cat -h` or `!cat help` - Show this help message
-"#,
-        r#"**Advanced Color Analysis & Creative Features:**
-
-`!cat extract [image]`      - Extract the actual color palette from an image
-`!cat harmony [image]`      - Show complementary, analogous, triadic colors for the dominant color
-`!cat simulate [type] [image]` - Simulate color blindness (protanopia, deuteranopia, tritanopia)
-`!cat temperature [image]`  - Analyze and report the proportion of warm vs cool colors
-`!cat gradient [colors]`    - Generate a gradient from Catppuccin color names or hex codes
-`!cat scheme [type] [image]` - Preview color schemes (complementary, analogous, triadic, monochromatic)
-`!cat animate [effect] [image]` - Add animation effects (e.g., fade) to images as GIF
-`!cat texture [type] [image]` - Overlay Catppuccin-themed textures (dots, stripes) on images
-"#,
-        r#"**Available Flavors:**
-• `latte` - Light, warm theme
-• `frappe` - Medium, balanced theme  
-• `macchiato` - Dark, rich theme
-• `mocha` - Darkest, deep theme
+// Every image a command could work on: attachments, embeds, stickers, image URLs, and linked messages
+async fn find_image_sources(ctx: &Context, msg: &Message, parts: &[&str]) -> Vec<ImageSource> {
+    source::resolve(&ctx.http, msg, parts).await
+}
 
-**Available Algorithms:**
-• `shepards` - Best quality (default)
-• `gaussian` - Smooth gradients
-• `linear` - Fast processing
-• `sampling` - High quality, slower
-• `nearest` - Fastest, basic
-• `hald` - Hald CLUT method
-• `euclide` - Euclidean distance
-• `mean` - Mean-based mapping
-• `std` - Standard deviation method
+// Find the image a command should work on: the first of `find_image_sources`
+async fn find_image_source(ctx: &Context, msg: &Message, parts: &[&str]) -> Option<ImageSource> {
+    find_image_sources(ctx, msg, parts).await.into_iter().next()
+}
 
-**Quality Levels:**
-• `fast` - Nearest neighbor (fastest)
-• `normal` - Shepard's method (balanced)
-• `high` - Gaussian sampling (best quality)
+// Download and decode an image, also enforcing the pixel budget. Images slightly over it, or any amount
+// over with `autoscale`, are shrunk to fit.
+async fn fetch_image(fetcher: &dyn ImageFetcher, image_source: &ImageSource, autoscale: bool) -> Result<image::DynamicImage, BotError> {
+    let bytes = source::download(fetcher, image_source).await?;
+    let img = icc::decode(&bytes).map_err(|e| BotError::Decode(e.to_string()))?;
+    limits::fit_still(img, autoscale).map(|(img, _)| img)
+}
 
-**Export Formats:**
-• `png` - Lossless, supports transparency
-• `jpg` - Compressed, smaller files
-• `webp` - Modern, good compression
-• `gif` - Animated images
-"#,
-        r#"**Examples:**
-`!cat mocha shepards [image]` - Mocha flavor with Shepard's method
-`!cat frappe gaussian [image]` - Frappe flavor with Gaussian algorithm
-`!cat all [image]` - Process with all flavors at once
-`!cat palette latte` - Show Latte color palette
-`!cat compare [image]` - Show before/after comparison
-`!cat mocha high [image]` - High quality Mocha processing
-`!cat latte png [image]` - Export as PNG format
+// Help pages, generated from the command registry and navigated with buttons and a category menu
+static HELP_PAGES: Lazy<PagedEmbed> = Lazy::new(registry::help);
 
-**Creative Examples:**
-`!cat gradient rosewater mauve blue` - Gradient from Catppuccin colors
-`!cat scheme triadic [image]` - Triadic color scheme preview
-`!cat animate fade [image]` - Fade animation effect
-`!cat texture dots [image]` - Dots texture overlay
-"#
-    ];
-    for (i, help_part) in help_parts.iter().enumerate() {
-        let part_number = if help_parts.len() > 1 {
-            format!(" (Part {}/{})", i + 1, help_parts.len())
-        } else {
-            String::new()
-        };
-        let embed = serenity::builder::CreateEmbed::default()
-            .description(format!("{}{}", help_part, part_number))
-            .color(MOCHA_MAUVE);
-        let builder = serenity::builder::CreateMessage::new().embed(embed);
-        if let Err(why) = channel_id.send_message(&ctx.http, builder).await {
-            error!(?why, "Error sending help message part {}", i + 1);
-            break;
-        }
-        if i < help_parts.len() - 1 {
-            tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
-        }
-    }
+// Helper function to send help message
+pub async fn send_help_message(ctx: &Context, channel_id: serenity::model::id::ChannelId) -> Result<(), serenity::Error> {
+    channel_id.send_message(&ctx.http, HELP_PAGES.message(0)).await?;
     Ok(())
 }
 
@@ -223,7 +103,8 @@ impl EventHandler for Handler {
         debug!(user = %msg.author.name, id = %msg.author.id, content = %msg.content, "Message event received");
 
         // Ignore messages from the bot itself or webhooks
-        let current_user_id = ctx.http.get_current_user().await.unwrap().id;
+        // From the cache rather than an HTTP call, which could fail and costs a request per message
+        let current_user_id = ctx.cache.current_user().id;
         if msg.author.id == current_user_id {
             debug!(user = %msg.author.name, "Ignored message from self (bot user id)");
             return;
@@ -237,10 +118,34 @@ impl EventHandler for Handler {
             return;
         }
 
+        // Anything else may be an image posted in an auto-processing channel
+        if !msg.content.starts_with("!cat") {
+            autochannel::handle(&ctx, &msg).await;
+            return;
+        }
+
         // Check if the message starts with our command prefix.
         if msg.content.starts_with("!cat") {
             info!(content = %msg.content, user = %msg.author.name, "Received !cat command");
-            let parts: Vec<&str> = msg.content.split_whitespace().collect();
+            let all_parts: Vec<&str> = utils::split_args(&msg.content);
+            let (mut flags, parts) = CommandFlags::parse(&all_parts);
+            // The server's watermark goes on last, unless the member asked to leave it off this time
+            if let Some(watermark) = match msg.guild_id {
+                Some(guild_id) if !flags.no_watermark => watermark::effect(&ctx, guild_id).await,
+                _ => None,
+            } {
+                flags.post_effects.push(watermark);
+            }
+            // Subcommands read these themselves; catppuccinifying gets them all through `dispatch`
+            let (post_effects, pre_adjust, tuning) = (&flags.post_effects, flags.pre_adjust, flags.tuning);
+            let autoscale = limits::autoscale(msg.guild_id, flags.autoscale);
+            let fetcher: &dyn ImageFetcher = &source::HttpFetcher;
+
+            // Servers can limit the bot to some channels; `setup` still works everywhere so that can be undone
+            if !config::channel_allowed(msg.guild_id, msg.channel_id) && parts.get(1) != Some(&"setup") {
+                debug!(channel = %msg.channel_id, "Ignored command outside the server's allowed channels");
+                return;
+            }
 
             // Handle help command
             if parts.len() > 1 && (parts[1] == "-h" || parts[1] == "--help" || parts[1] == "help") {
@@ -266,81 +171,335 @@ impl EventHandler for Handler {
                 return;
             }
 
-            // Determine the flavor from the command arguments.
-            let mut selected_flavor = utils::parse_flavor("latte").unwrap(); // Default flavor
-            let mut has_explicit_flavor_arg = false;
-            let mut selected_algorithm = "shepards-method"; // Default algorithm
-            let mut process_all_flavors = false;
-            let mut show_palette = false;
-            let mut show_comparison = false;
-            let mut show_stats = false;
-            let mut batch_mode = false; // Now used for batch processing
-            let mut selected_quality = None;
-            let mut selected_format = None;
+            // Reject arguments a command wouldn't understand instead of silently falling back to defaults
+            if let Err(why) = registry::check(&parts[1..]) {
+                let _ = responses::warning(&ctx.http, msg.channel_id, why.to_string()).await;
+                return;
+            }
+            // Tuning changes how an image is catppuccinified; other commands would silently ignore it
+            if !tuning.is_default() && parts.get(1).and_then(|name| registry::find(name)).is_some() {
+                let _ = responses::warning(&ctx.http, msg.channel_id, "`--power`, `--sigma`, `--neighbors` and `--cutoff` only apply when catppuccinifying an image, like `!cat mocha shepards --power 3`.").await;
+                return;
+            }
+
+            // Owner-only maintenance commands
+            if parts.get(1) == Some(&"admin") {
+                admin::handle(&ctx.http, &msg, &parts[2..]).await;
+                return;
+            }
+
+            // Per-server settings changed by command rather than in the setup panel
+            if parts.get(1) == Some(&"config") {
+                match parts.get(2).copied() {
+                    Some("contentpolicy") => policy::configure(&ctx, &msg, &parts[3..]).await,
+                    Some("watermark") => watermark::configure(&ctx, &msg, &parts[3..]).await,
+                    Some("autoscale") => limits::configure(&ctx, &msg, &parts[3..]).await,
+                    _ => autochannel::configure(&ctx, &msg, &parts[3..]).await,
+                }
+                return;
+            }
+
+            // The server setup panel, for changing what was picked when the bot joined
+            if parts.get(1) == Some(&"setup") {
+                match msg.guild_id {
+                    Some(guild_id) => {
+                        if let Err(why) = msg.channel_id.send_message(&ctx.http, setup::message(&config::guild(guild_id))).await {
+                            error!(?why, "Error sending setup panel");
+                        }
+                    }
+                    None => {
+                        let _ = responses::warning(&ctx.http, msg.channel_id, "Setup is for servers; run `!cat setup` in one.").await;
+                    }
+                }
+                return;
+            }
+
+            // The member's recent jobs, with buttons to run them again
+            if parts.get(1) == Some(&"history") {
+                if let Err(why) = msg.channel_id.send_message(&ctx.http, history::message(msg.author.id)).await {
+                    error!(?why, "Error sending job history");
+                }
+                return;
+            }
+
+            // The guess-the-color game and its leaderboard
+            if parts.get(1) == Some(&"quiz") {
+                quiz::run(&ctx, &msg, &parts[2..]).await;
+                return;
+            }
+
+            // Favorite palette colors, saved per member
+            if parts.get(1) == Some(&"fav") {
+                favorites::run(&ctx, &msg, &parts[2..]).await;
+                return;
+            }
+
+            // Turn new work away while an owner has paused intake
+            if let Some(notice) = admin::intake_notice(msg.author.id, &parts[1..]) {
+                let _ = responses::warning(&ctx.http, msg.channel_id, notice).await;
+                return;
+            }
+
+            // Images the server's content policy or a channel's NSFW flag rule out
+            if let Some(refusal) = policy::vet(&ctx, &msg, &parts[1..]).await {
+                let _ = responses::warning(&ctx.http, msg.channel_id, refusal).await;
+                return;
+            }
+
+            // Server emoji retheming, an admin job of its own with a preview and an apply step
+            if parts.get(1) == Some(&"retheme-emojis") {
+                emoji_retheme::run(&ctx, &msg, &parts[2..]).await;
+                return;
+            }
+
+            // Recurring batches over a channel's images
+            if parts.get(1) == Some(&"schedule") {
+                schedule::configure(&ctx, &msg, &parts[2..]).await;
+                return;
+            }
+
+            // Redo the last conversion with another flavor, or compare it with the one before
+            if parts.get(1) == Some(&"again") {
+                last_result::again(&ctx, &msg, &parts[2..]).await;
+                return;
+            }
+            if parts.get(1) == Some(&"compare") && parts.get(2) == Some(&"last") {
+                last_result::compare_last(&ctx, &msg).await;
+                return;
+            }
+
+            // A page of an attached PDF
+            if parts.get(1) == Some(&"pdf") {
+                pdf::run(&ctx, &msg, &parts[2..]).await;
+                return;
+            }
+
+            // A screenshot of an allowed website
+            if parts.get(1) == Some(&"shot") {
+                screenshot::run(&ctx, &msg, &parts[2..]).await;
+                return;
+            }
+
+            // Role color suggestions, applied only after they've been looked over
+            if parts.get(1) == Some(&"roles") {
+                role_colors::run(&ctx, &msg, &parts[2..]).await;
+                return;
+            }
 
-            if msg.content.split_whitespace().any(|arg| arg == "-f") {
-                selected_quality = Some("fast".to_string());
-                selected_algorithm = "nearest-neighbor";
-                let _ = msg.channel_id.say(&ctx.http, "⚡ Fast mode enabled! Your image will be processed using the fastest settings (nearest-neighbor algorithm).").await;
+            // Subcommands start from the server's default flavor (or Latte), and `-f` speeds them up too
+            let selected_flavor = config::default_flavor(msg.guild_id);
+            let selected_algorithm = ImageArgs::parse(&parts, selected_flavor).algorithm;
+            if parts.contains(&"-f") {
+                let _ = responses::success(&ctx.http, msg.channel_id, "⚡ Fast mode enabled! Your image will be processed using the fastest settings (nearest-neighbor algorithm).").await;
             }
 
             if parts.len() > 1 {
-                if parts[1] == "all" {
-                    process_all_flavors = true;
-                } else if parts[1] == "palette" {
-                    show_palette = true;
-                } else if parts[1] == "compare" {
-                    show_comparison = true;
+                if parts[1] == "palette" && parts.get(2) == Some(&"import") {
+                    // --- CUSTOM PALETTE IMPORT SUBCOMMAND ---
+                    // Usage: !cat palette import <coolors.co URL | terminal.sexy JSON URL> [name], or attach the JSON export
+                    //        !cat palette import code <share code> [name]
+                    let by_code = parts.get(3) == Some(&"code");
+                    let name_start = if by_code { 5 } else { 3 };
+                    let link = parts.iter().skip(3).find(|s| !by_code && (s.starts_with("http://") || s.starts_with("https://"))).copied();
+                    let json_attachment = msg.attachments.iter().find(|a| a.filename.to_lowercase().ends_with(".json"));
+                    let (imported_name, mut colors) = if by_code {
+                        let Some(code) = parts.get(4) else {
+                            let _ = responses::warning(&ctx.http, msg.channel_id, "Please provide a palette code. Example: `!cat palette import code <code>`").await;
+                            return;
+                        };
+                        match custom_palettes::decode_share_code(code) {
+                            Ok(shared) => (Some(shared.name), shared.colors),
+                            Err(e) => {
+                                let _ = responses::warning(&ctx.http, msg.channel_id, e).await;
+                                return;
+                            }
+                        }
+                    } else if let Some(colors) = link.and_then(custom_palettes::parse_coolors_url) {
+                        (None, colors)
+                    } else {
+                        let url = match (link, json_attachment) {
+                            (Some(link), _) => link.to_string(),
+                            (None, Some(attachment)) => attachment.url.clone(),
+                            (None, None) => {
+                                let _ = responses::warning(&ctx.http, msg.channel_id, "Please provide a coolors.co palette link or a terminal.sexy JSON export (as a link or an attached `.json` file). Example: `!cat palette import https://coolors.co/264653-2a9d8f-e9c46a sunset`").await;
+                                return;
+                            }
+                        };
+                        let bytes = match source::fetch_url(&url).await {
+                            Ok(bytes) => bytes,
+                            Err(_) => {
+                                let _ = responses::error(&ctx.http, msg.channel_id, "Failed to download the palette.").await;
+                                return;
+                            }
+                        };
+                        match custom_palettes::parse_terminal_sexy(&String::from_utf8_lossy(&bytes)) {
+                            Some(parsed) => parsed,
+                            None => {
+                                let _ = responses::warning(&ctx.http, msg.channel_id, "That doesn't look like a coolors.co palette or a terminal.sexy JSON export.").await;
+                                return;
+                            }
+                        }
+                    };
+                    colors.truncate(custom_palettes::MAX_COLORS);
+                    let given_name = parts.iter().skip(name_start).filter(|s| Some(**s) != link).copied().collect::<Vec<_>>().join(" ");
+                    let name = custom_palettes::sanitize_name(&given_name)
+                        .or_else(|| imported_name.as_deref().and_then(custom_palettes::sanitize_name))
+                        .unwrap_or_else(|| format!("imported-{}", custom_palettes::list(msg.author.id).len() + 1));
+                    let preview = palette::render_custom_palette_preview(&colors, selected_flavor);
+                    let mut output_buffer = std::io::Cursor::new(Vec::new());
+                    if preview.write_to(&mut output_buffer, image::ImageFormat::Png).is_err() {
+                        let _ = responses::error(&ctx.http, msg.channel_id, "Failed to generate palette preview.").await;
+                        return;
+                    }
+                    let count = colors.len();
+                    if let Err(e) = custom_palettes::save(msg.author.id, custom_palettes::CustomPalette { name: name.clone(), colors }) {
+                        let _ = responses::error(&ctx.http, msg.channel_id, e).await;
+                        return;
+                    }
+                    let filename = crate::utils::sanitize_filename(&format!("palette_{}.png", name), "png");
+                    let attachment_data = serenity::builder::CreateAttachment::bytes(output_buffer.into_inner(), filename);
+                    let message_content = format!("**Imported palette `{}`** ({} colors)\nSee your saved palettes with `!cat palette mine`.", name, count);
+                    let message_builder = serenity::builder::CreateMessage::new().content(message_content);
+                    let _ = msg.channel_id.send_files(&ctx.http, vec![attachment_data], message_builder).await;
+                    return;
+                } else if parts[1] == "palette" && parts.get(2) == Some(&"export") && (parts.len() == 5 || parts.get(3).is_some_and(|s| utils::parse_flavor(s).is_some())) {
+                    // --- PALETTE FILE EXPORT ---
+                    // Usage: !cat palette export <flavor | saved palette> [json|css|gpl|ase|act]
+                    let format = match parts.get(4) {
+                        Some(s) => match utils::parse_palette_format(s) {
+                            Some(format) => format,
+                            None => {
+                                let _ = responses::warning(&ctx.http, msg.channel_id, "Please pick a palette file format: `json`, `css`, `gpl`, `ase` or `act`. Example: `!cat palette export mocha ase`").await;
+                                return;
+                            }
+                        },
+                        None => export::PaletteFormat::Gpl,
+                    };
+                    let (title, file_stem, named_colors): (String, String, Vec<(String, (u8, u8, u8))>) = if let Some(flavor) = utils::parse_flavor(parts[3]) {
+                        let colors = utils::CATPPUCCIN_COLOR_NAMES.iter().map(|name| (name.to_string(), utils::catppuccin_color_name_to_rgb(name, flavor).unwrap())).collect();
+                        (format!("Catppuccin {}", flavor), format!("catppuccin_{}", flavor.identifier()), colors)
+                    } else {
+                        let wanted = custom_palettes::sanitize_name(parts[3]).unwrap_or_default();
+                        let Some(saved) = custom_palettes::list(msg.author.id).into_iter().find(|p| p.name == wanted) else {
+                            let _ = responses::warning(&ctx.http, msg.channel_id, format!("`{}` isn't a flavor or one of your palettes. See yours with `!cat palette mine`.", parts[3])).await;
+                            return;
+                        };
+                        // Each color is named after the palette and numbered by `write_palette`
+                        let colors = saved.colors.iter().map(|rgb| (saved.name.clone(), *rgb)).collect();
+                        (saved.name.clone(), saved.name.clone(), colors)
+                    };
+                    let colors: Vec<(&str, (u8, u8, u8))> = named_colors.iter().map(|(name, rgb)| (name.as_str(), *rgb)).collect();
+                    let extension = format.extension();
+                    let filename = crate::utils::sanitize_filename(&format!("{}.{}", file_stem, extension), extension);
+                    let attachment_data = serenity::builder::CreateAttachment::bytes(export::write_palette(&title, &colors, format), filename);
+                    let message_content = format!("**{}** as a `.{}` palette ({} colors)", title, extension, colors.len());
+                    let message_builder = serenity::builder::CreateMessage::new().content(message_content);
+                    let _ = msg.channel_id.send_files(&ctx.http, vec![attachment_data], message_builder).await;
+                    return;
+                } else if parts[1] == "palette" && parts.get(2) == Some(&"export") && parts.len() == 4 {
+                    // --- CUSTOM PALETTE SHARE CODE ---
+                    // Usage: !cat palette export <name>
+                    let wanted = custom_palettes::sanitize_name(parts[3]).unwrap_or_default();
+                    let Some(saved) = custom_palettes::list(msg.author.id).into_iter().find(|p| p.name == wanted) else {
+                        let _ = responses::warning(&ctx.http, msg.channel_id, format!("You don't have a palette named `{}`. See yours with `!cat palette mine`.", parts[3])).await;
+                        return;
+                    };
+                    let code = custom_palettes::encode_share_code(&saved);
+                    let _ = responses::success(&ctx.http, msg.channel_id, format!("**Share code for `{}`**\n`{}`\nAnyone can import it with `!cat palette import code {}`", saved.name, code, code)).await;
+                    return;
+                } else if parts[1] == "palette" && parts.get(2) == Some(&"mine") {
+                    // --- SAVED CUSTOM PALETTES ---
+                    let saved = custom_palettes::list(msg.author.id);
+                    if saved.is_empty() {
+                        let _ = responses::info(&ctx.http, msg.channel_id, "You haven't imported any palettes yet. Try `!cat palette import <coolors.co URL>`.").await;
+                        return;
+                    }
+                    let lines: Vec<String> = saved.iter().map(|p| {
+                        // The first few colors keep the list within one message
+                        let hexes: Vec<String> = p.colors.iter().take(8).map(|(r, g, b)| format!("#{:02X}{:02X}{:02X}", r, g, b)).collect();
+                        let more = if p.colors.len() > 8 { " …" } else { "" };
+                        format!("**{}** ({} colors): `{}`{}", p.name, p.colors.len(), hexes.join(" "), more)
+                    }).collect();
+                    let _ = responses::info(&ctx.http, msg.channel_id, format!("**Your palettes**\n{}", lines.join("\n"))).await;
+                    return;
+                } else if parts[1] == "theme" {
+                    // --- TERMINAL SCHEME EXPORT SUBCOMMAND ---
+                    // Usage: !cat theme export <flavor> <xresources|base16>
+                    let flavor = parts.iter().skip(2).find_map(|s| utils::parse_flavor(s));
+                    let format = parts.iter().skip(2).find_map(|s| utils::parse_scheme_format(s));
+                    let (Some("export"), Some(flavor), Some(format)) = (parts.get(2).copied(), flavor, format) else {
+                        let _ = responses::warning(&ctx.http, msg.channel_id, "Usage: `!cat theme export <flavor> <xresources|base16>`. Example: `!cat theme export mocha xresources`").await;
+                        return;
+                    };
+                    // Built from fixed names, and `.Xresources` is longer than `sanitize_filename` allows an extension to be
+                    let attachment_data = serenity::builder::CreateAttachment::bytes(export::write_scheme(flavor, format), format.filename(flavor));
+                    let message_content = format!("**Catppuccin {}** terminal scheme, with the upstream ANSI color mapping", flavor);
+                    let message_builder = serenity::builder::CreateMessage::new().content(message_content);
+                    let _ = msg.channel_id.send_files(&ctx.http, vec![attachment_data], message_builder).await;
+                    return;
                 } else if parts[1] == "gradient" {
                     // --- GRADIENT GENERATION SUBCOMMAND ---
-                    // Usage: !cat gradient [color1] [color2] ...
+                    // Usage: !cat gradient [animate] [flavor] [direction] [easing] [speed] [WxH] [color[@pos]] ...
                     let mut color_args = parts[2..].to_vec();
+                    let animate = color_args.iter().any(|s| s.eq_ignore_ascii_case("animate"));
+                    color_args.retain(|s| !s.eq_ignore_ascii_case("animate"));
                     let mut flavor = utils::parse_flavor("latte").unwrap();
                     // If the first color arg is a flavor, use it
-                    if let Some(f) = color_args.get(0).and_then(|s| utils::parse_flavor(s)) {
+                    if let Some(f) = color_args.first().and_then(|s| utils::parse_flavor(s)) {
                         flavor = f;
                         color_args = color_args[1..].to_vec();
                     }
                     if color_args.is_empty() {
-                        let _ = msg.channel_id.say(&ctx.http, "Please provide at least two colors (Catppuccin color names or hex codes). Example: `!cat gradient rosewater mauve blue` or `!cat gradient #f5e0dc #a6e3a1`").await;
+                        let _ = responses::warning(&ctx.http, msg.channel_id, "Please provide at least two colors (Catppuccin color names or hex codes). Example: `!cat gradient rosewater mauve blue` or `!cat gradient #f5e0dc #a6e3a1`").await;
                         return;
                     }
-                    let mut colors = Vec::new();
+                    let mut direction = palette::GradientDirection::Linear(0.0);
+                    let mut easing = "linear";
+                    let mut frame_delay = 5u16;
+                    let (mut width, mut height) = if animate { (480u32, 120u32) } else { (512u32, 80u32) };
+                    let mut stops: Vec<((u8, u8, u8), Option<f32>)> = Vec::new();
                     for arg in color_args.iter() {
-                        // Try Catppuccin color name
-                        if let Some(rgb) = utils::catppuccin_color_name_to_rgb(arg, flavor) {
-                            colors.push(rgb);
-                        } else {
-                            // Try hex code
-                            let hex = arg.trim_start_matches('#');
-                            if hex.len() == 6 || hex.len() == 3 {
-                                let parse_hex = |h: &str| -> Option<(u8, u8, u8)> {
-                                    if h.len() == 6 {
-                                        Some((
-                                            u8::from_str_radix(&h[0..2], 16).ok()?,
-                                            u8::from_str_radix(&h[2..4], 16).ok()?,
-                                            u8::from_str_radix(&h[4..6], 16).ok()?,
-                                        ))
-                                    } else if h.len() == 3 {
-                                        Some((
-                                            u8::from_str_radix(&h[0..1].repeat(2), 16).ok()?,
-                                            u8::from_str_radix(&h[1..2].repeat(2), 16).ok()?,
-                                            u8::from_str_radix(&h[2..3].repeat(2), 16).ok()?,
-                                        ))
-                                    } else {
-                                        None
-                                    }
-                                };
-                                if let Some(rgb) = parse_hex(hex) {
-                                    colors.push(rgb);
-                                }
+                        if let Some(d) = utils::parse_gradient_direction(arg) {
+                            direction = d;
+                            continue;
+                        }
+                        if let Some(e) = utils::parse_easing(arg) {
+                            easing = e;
+                            continue;
+                        }
+                        if animate {
+                            if let Some(delay) = utils::parse_animation_speed(arg) {
+                                frame_delay = delay;
+                                continue;
                             }
                         }
+                        if let Some((w, h)) = utils::parse_dimensions(arg) {
+                            width = w;
+                            height = h;
+                            continue;
+                        }
+                        // Optional stop position as a percentage, e.g. mauve@30
+                        let (color_str, pos) = match arg.split_once('@') {
+                            Some((c, p)) => (c, p.trim_end_matches('%').parse::<f32>().ok().map(|p| (p / 100.0).clamp(0.0, 1.0))),
+                            None => (*arg, None),
+                        };
+                        // Try Catppuccin color name, then any CSS color
+                        let rgb = utils::parse_color(color_str, flavor);
+                        if let Some(rgb) = rgb {
+                            stops.push((rgb, pos));
+                        }
                     }
-                    if colors.len() < 2 {
-                        let _ = msg.channel_id.say(&ctx.http, "Please provide at least two valid colors (Catppuccin color names or hex codes). Example: `!cat gradient rosewater mauve blue` or `!cat gradient #f5e0dc #a6e3a1`").await;
+                    if stops.len() < 2 {
+                        let _ = responses::warning(&ctx.http, msg.channel_id, "Please provide at least two valid colors (Catppuccin color names or hex codes). Example: `!cat gradient rosewater mauve blue` or `!cat gradient radial ease-in-out rosewater@0 mauve@30 blue@100`").await;
                         return;
                     }
+                    // Stops without an explicit position are spread evenly
+                    let colors: Vec<(u8, u8, u8)> = stops.iter().map(|(rgb, _)| *rgb).collect();
+                    let gradient_stops: Vec<((u8, u8, u8), f32)> = palette::evenly_spaced_stops(&colors)
+                        .into_iter()
+                        .zip(stops.iter())
+                        .map(|((rgb, even_pos), (_, pos))| (rgb, pos.unwrap_or(even_pos)))
+                        .collect();
                     // Start typing indicator
                     let _typing = msg.channel_id.start_typing(&ctx.http);
                     let progress_bar = ProgressBar::new_spinner();
@@ -351,13 +510,38 @@ impl EventHandler for Handler {
                     );
                     progress_bar.set_message("🌈 Generating gradient image...");
                     progress_bar.enable_steady_tick(Duration::from_millis(100));
-                    let width = 512u32;
-                    let height = 80u32;
-                    let gradient_img = palette::generate_gradient_image(&colors, width, height);
+                    if animate {
+                        // Keep animated GIFs small enough to encode quickly and upload
+                        let (width, height) = (width.min(800), height.min(800));
+                        progress_bar.set_message("🎞️ Generating animated gradient...");
+                        let gif_result = jobs::run(Job::new("gradient", &msg), move || {
+                            palette::generate_animated_gradient_gif(&colors, width, height, direction, easing, 24, frame_delay)
+                        }).await;
+                        match gif_result {
+                            Ok(Ok(gif_bytes)) => {
+                                let filename = crate::utils::sanitize_filename("catppuccin_gradient_animated.gif", "gif");
+                                let attachment_data = serenity::builder::CreateAttachment::bytes(gif_bytes, filename);
+                                let message_content = "**Animated Catppuccin Gradient**";
+                                let message_builder = serenity::builder::CreateMessage::new().content(message_content);
+                                let _ = msg.channel_id.send_files(&ctx.http, vec![attachment_data], message_builder).await;
+                                progress_bar.finish_with_message("✅ Animated gradient sent!");
+                            }
+                            Ok(Err(e)) => {
+                                progress_bar.finish_with_message("❌ Failed to generate animated gradient");
+                                let _ = responses::error(&ctx.http, msg.channel_id, &format!("Failed to generate animated gradient: {}", e)).await;
+                            }
+                            Err(panic) => {
+                                progress_bar.finish_with_message("❌ Animated gradient generation panicked");
+                                let _ = responses::error(&ctx.http, msg.channel_id, panic.to_string()).await;
+                            }
+                        }
+                        return;
+                    }
+                    let gradient_img = palette::generate_gradient_image(&gradient_stops, width, height, direction, easing);
                     let mut output_buffer = std::io::Cursor::new(Vec::new());
                     if let Err(_e) = gradient_img.write_to(&mut output_buffer, image::ImageFormat::Png) {
                         progress_bar.finish_with_message("❌ Failed to generate gradient image");
-                        let _ = msg.channel_id.say(&ctx.http, "Failed to generate gradient image.").await;
+                        let _ = responses::error(&ctx.http, msg.channel_id, "Failed to generate gradient image.").await;
                         return;
                     }
                     let filename = crate::utils::sanitize_filename("catppuccin_gradient.png", "png");
@@ -368,840 +552,3280 @@ impl EventHandler for Handler {
                     let _ = msg.channel_id.send_files(&ctx.http, vec![attachment_data], message_builder).await;
                     progress_bar.finish_with_message("✅ Gradient image sent!");
                     return;
-                } else if parts[1] == "stats" {
-                    show_stats = true;
-                } else if parts[1] == "simulate" {
-                    // --- COLOR BLINDNESS SIMULATION SUBCOMMAND ---
-                    let kind = parts.get(2).map(|s| s.to_lowercase()).unwrap_or("protanopia".to_string());
-                    let valid_types = ["protanopia", "deuteranopia", "tritanopia"];
-                    if !valid_types.contains(&kind.as_str()) {
-                        let _ = msg.channel_id.say(&ctx.http, "Please specify a valid simulation type: protanopia, deuteranopia, tritanopia.").await;
-                        return;
-                    }
-                    let attachment = msg.attachments.iter().find(|a| a.width.is_some() && a.height.is_some());
-                    let image_url = if let Some(attachment) = attachment {
-                        Some(attachment.url.as_str().to_string())
-                    } else {
-                        let url_regex = regex::Regex::new(r"^(https?://[\w\-./%?=&]+\.(png|jpe?g|gif|bmp|webp))$").unwrap();
-                        parts.iter().find(|s| url_regex.is_match(s)).map(|s| s.to_string())
-                    };
-                    if let Some(image_url) = image_url {
-                        let _typing = msg.channel_id.start_typing(&ctx.http);
-                        let progress_bar = ProgressBar::new_spinner();
-                        progress_bar.set_style(
-                            ProgressStyle::default_spinner()
-                                .template("{spinner:.green} {wide_msg}")
-                                .unwrap()
-                        );
-                        progress_bar.set_message("👁️ Simulating color blindness...");
-                        progress_bar.enable_steady_tick(Duration::from_millis(100));
-                        let response = reqwest::get(&image_url).await;
-                        if let Ok(resp) = response {
-                            let bytes = resp.bytes().await;
-                            if let Ok(image_bytes) = bytes {
-                                let img_reader = ImageReader::new(std::io::Cursor::new(&image_bytes)).with_guessed_format();
-                                if let Ok(reader) = img_reader {
-                                    if let Ok(img) = reader.decode() {
-                                        let mut rgba_img = img.to_rgba8();
-                                        for pixel in rgba_img.pixels_mut() {
-                                            let (r, g, b, a) = (pixel[0], pixel[1], pixel[2], pixel[3]);
-                                            let (r2, g2, b2) = simulate_color_blindness(r, g, b, &kind);
-                                            *pixel = image::Rgba([r2, g2, b2, a]);
-                                        }
-                                        let mut output_buffer = std::io::Cursor::new(Vec::new());
-                                        if let Err(_e) = rgba_img.write_to(&mut output_buffer, image::ImageFormat::Png) {
-                                            progress_bar.finish_with_message("❌ Failed to generate simulated image");
-                                            let _ = msg.channel_id.say(&ctx.http, "Failed to generate simulated image.").await;
-                                            return;
-                                        }
-                                        let message_content = format!("**Color Blindness Simulation: {}**", kind.to_uppercase());
-                                        let filename = crate::utils::sanitize_filename(&format!("simulated_{}.png", kind), "png");
-                                        let attachment_data = serenity::builder::CreateAttachment::bytes(output_buffer.into_inner(), filename);
-                                        let message_builder = serenity::builder::CreateMessage::new().content(message_content);
-                                        let _ = msg.channel_id.send_files(&ctx.http, vec![attachment_data], message_builder).await;
-                                        progress_bar.finish_with_message("✅ Simulation sent!");
-                                        return;
-                                    }
-                                }
-                            }
+                } else if parts[1] == "diff" {
+                    // --- FLAVOR DIFF SUBCOMMAND ---
+                    // Usage: !cat diff [flavor1] [flavor2]
+                    let flavor_a = parts.get(2).and_then(|s| utils::parse_flavor(s));
+                    let flavor_b = parts.get(3).and_then(|s| utils::parse_flavor(s));
+                    let (flavor_a, flavor_b) = match (flavor_a, flavor_b) {
+                        (Some(a), Some(b)) => (a, b),
+                        _ => {
+                            let _ = responses::warning(&ctx.http, msg.channel_id, "Please provide two flavors to compare. Example: `!cat diff latte mocha`").await;
+                            return;
                         }
-                        progress_bar.finish_with_message("❌ Failed to simulate color blindness");
-                        let _ = msg.channel_id.say(&ctx.http, "Failed to simulate color blindness. Please ensure your image is valid and accessible.").await;
-                        return;
-                    } else {
-                        let _ = msg.channel_id.say(&ctx.http, "Please attach an image or provide a direct image URL to simulate color blindness.").await;
+                    };
+                    let _typing = msg.channel_id.start_typing(&ctx.http);
+                    let progress_bar = ProgressBar::new_spinner();
+                    progress_bar.set_style(
+                        ProgressStyle::default_spinner()
+                            .template("{spinner:.green} {wide_msg}")
+                            .unwrap()
+                    );
+                    progress_bar.set_message("🔍 Generating flavor diff...");
+                    progress_bar.enable_steady_tick(Duration::from_millis(100));
+                    let (diff_img, deltas) = palette::generate_flavor_diff_image(flavor_a, flavor_b);
+                    let mut output_buffer = std::io::Cursor::new(Vec::new());
+                    if let Err(_e) = diff_img.write_to(&mut output_buffer, image::ImageFormat::Png) {
+                        progress_bar.finish_with_message("❌ Failed to generate flavor diff image");
+                        let _ = responses::error(&ctx.http, msg.channel_id, "Failed to generate flavor diff image.").await;
                         return;
                     }
-                } else if parts[1] == "temperature" {
-                    // --- COLOR TEMPERATURE ANALYSIS SUBCOMMAND ---
-                    let attachment = msg.attachments.iter().find(|a| a.width.is_some() && a.height.is_some());
-                    let image_url = if let Some(attachment) = attachment {
-                        Some(attachment.url.as_str().to_string())
-                    } else {
-                        let url_regex = regex::Regex::new(r"^(https?://[\w\-./%?=&]+\.(png|jpe?g|gif|bmp|webp))$").unwrap();
-                        parts.iter().find(|s| url_regex.is_match(s)).map(|s| s.to_string())
+                    let mut table = String::new();
+                    for (name, de) in deltas.iter() {
+                        table.push_str(&format!("{:<10} ΔE {:>5.1}\n", name, de));
+                    }
+                    let message_content = format!(
+                        "**Flavor Diff: {} → {}**\n```\n{}```",
+                        flavor_a.to_string().to_uppercase(), flavor_b.to_string().to_uppercase(), table
+                    );
+                    let filename = crate::utils::sanitize_filename(
+                        &format!("catppuccin_diff_{}_{}.png", flavor_a.to_string().to_lowercase(), flavor_b.to_string().to_lowercase()),
+                        "png",
+                    );
+                    let attachment_data = serenity::builder::CreateAttachment::bytes(output_buffer.into_inner(), filename);
+                    let message_builder = serenity::builder::CreateMessage::new().content(message_content);
+                    let _ = msg.channel_id.send_files(&ctx.http, vec![attachment_data], message_builder).await;
+                    progress_bar.finish_with_message("✅ Flavor diff sent!");
+                    return;
+                } else if parts[1] == "lut" {
+                    // --- LUT VISUALIZATION SUBCOMMAND ---
+                    // Usage: !cat lut show <flavor> [algorithm...]
+                    let flavor = parts.get(3).and_then(|s| utils::parse_flavor(s));
+                    let flavor = match flavor {
+                        Some(flavor) if parts.get(2) == Some(&"show") => flavor,
+                        _ => {
+                            let _ = responses::warning(&ctx.http, msg.channel_id, "Usage: `!cat lut show <flavor> [algorithm...]`. Example: `!cat lut show mocha shepards-method nearest-k`").await;
+                            return;
+                        }
                     };
-                    if let Some(image_url) = image_url {
-                        let _typing = msg.channel_id.start_typing(&ctx.http);
-                        let progress_bar = ProgressBar::new_spinner();
-                        progress_bar.set_style(
-                            ProgressStyle::default_spinner()
-                                .template("{spinner:.green} {wide_msg}")
-                                .unwrap()
-                        );
-                        progress_bar.set_message("🌡️ Analyzing color temperature...");
-                        progress_bar.enable_steady_tick(Duration::from_millis(100));
-                        let response = reqwest::get(&image_url).await;
-                        if let Ok(resp) = response {
-                            let bytes = resp.bytes().await;
-                            if let Ok(image_bytes) = bytes {
-                                let img_reader = ImageReader::new(std::io::Cursor::new(&image_bytes)).with_guessed_format();
-                                if let Ok(reader) = img_reader {
-                                    if let Ok(img) = reader.decode() {
-                                        let rgba_img = img.to_rgba8();
-                                        let mut warm = 0u64;
-                                        let mut cool = 0u64;
-                                        let mut total = 0u64;
-                                        for pixel in rgba_img.pixels() {
-                                            let (r, g, b, _a) = (pixel[0], pixel[1], pixel[2], pixel[3]);
-                                            let (h, _s, _l) = rgb_to_hsl(r, g, b);
-                                            if (h >= 0.0 && h <= 90.0) || (h >= 330.0 && h <= 360.0) {
-                                                warm += 1;
-                                            } else {
-                                                cool += 1;
-                                            }
-                                            total += 1;
-                                        }
-                                        let warm_pct = (warm as f64 / total as f64) * 100.0;
-                                        let cool_pct = (cool as f64 / total as f64) * 100.0;
-                                        let message_content = format!(
-                                            "**Color Temperature Analysis**\nWarm colors: {:.1}%\nCool colors: {:.1}%\n(>50% warm = warm image, >50% cool = cool image)",
-                                            warm_pct, cool_pct
-                                        );
-                                        let _ = msg.channel_id.say(&ctx.http, message_content).await;
-                                        progress_bar.finish_with_message("✅ Color temperature analyzed!");
-                                        return;
-                                    }
-                                }
+                    let mut algorithms: Vec<&'static str> = Vec::new();
+                    for token in &parts[4..] {
+                        match utils::parse_algorithm(token) {
+                            Some(algorithm) if !algorithms.contains(&algorithm) => algorithms.push(algorithm),
+                            Some(_) => {}
+                            None => {
+                                let _ = responses::warning(&ctx.http, msg.channel_id, format!("`{}` isn't an algorithm. See `!cat list` for the options.", token)).await;
+                                return;
                             }
                         }
-                        progress_bar.finish_with_message("❌ Failed to analyze color temperature");
-                        let _ = msg.channel_id.say(&ctx.http, "Failed to analyze color temperature. Please ensure your image is valid and accessible.").await;
-                        return;
-                    } else {
-                        let _ = msg.channel_id.say(&ctx.http, "Please attach an image or provide a direct image URL to analyze color temperature.").await;
-                        return;
                     }
-                } else if parts[1] == "scheme" {
-                    // --- COLOR SCHEME SUBCOMMAND ---
-                    // Usage: !cat scheme [type] [image]
-                    let scheme_type = parts.get(2).map(|s| s.to_lowercase()).unwrap_or("complementary".to_string());
-                    let valid_types = ["monochromatic", "complementary", "analogous", "triadic"];
-                    if !valid_types.contains(&scheme_type.as_str()) {
-                        let _ = msg.channel_id.say(&ctx.http, "Please specify a valid scheme type: monochromatic, complementary, analogous, triadic.").await;
+                    if algorithms.is_empty() {
+                        algorithms.push("shepards-method");
+                    }
+                    if algorithms.len() > image_processing::LUT_SLICE_MAX_ALGORITHMS {
+                        let _ = responses::warning(&ctx.http, msg.channel_id, format!("Compare at most {} algorithms at once.", image_processing::LUT_SLICE_MAX_ALGORITHMS)).await;
                         return;
                     }
-                    let attachment = msg.attachments.iter().find(|a| a.width.is_some() && a.height.is_some());
-                    let image_url = if let Some(attachment) = attachment {
-                        Some(attachment.url.as_str().to_string())
-                    } else {
-                        let url_regex = regex::Regex::new(r"^(https?://[\w\-./%?=&]+\.(png|jpe?g|gif|bmp|webp))$").unwrap();
-                        parts.iter().find(|s| url_regex.is_match(s)).map(|s| s.to_string())
-                    };
-                    if let Some(image_url) = image_url {
-                        let _typing = msg.channel_id.start_typing(&ctx.http);
-                        let progress_bar = ProgressBar::new_spinner();
-                        progress_bar.set_style(
-                            ProgressStyle::default_spinner()
-                                .template("{spinner:.green} {wide_msg}")
-                                .unwrap()
-                        );
-                        progress_bar.set_message("🎨 Analyzing color scheme...");
-                        progress_bar.enable_steady_tick(Duration::from_millis(100));
-                        let response = reqwest::get(&image_url).await;
-                        if let Ok(resp) = response {
-                            let bytes = resp.bytes().await;
-                            if let Ok(image_bytes) = bytes {
-                                let img_reader = ImageReader::new(std::io::Cursor::new(&image_bytes)).with_guessed_format();
-                                if let Ok(reader) = img_reader {
-                                    if let Ok(img) = reader.decode() {
-                                        let rgba_img = img.to_rgba8();
-                                        // Extract most dominant color
-                                        let mut color_counts = std::collections::HashMap::new();
-                                        for pixel in rgba_img.pixels() {
-                                            let key = (pixel[0], pixel[1], pixel[2]);
-                                            *color_counts.entry(key).or_insert(0) += 1;
-                                        }
-                                        let mut sorted_colors: Vec<_> = color_counts.into_iter().collect();
-                                        sorted_colors.sort_by(|a, b| b.1.cmp(&a.1));
-                                        let base_rgb = sorted_colors.get(0).map(|(rgb, _)| *rgb);
-                                        if let Some((r, g, b)) = base_rgb {
-                                            let (h, s, l) = rgb_to_hsl(r, g, b);
-                                            let scheme_colors = match scheme_type.as_str() {
-                                                "monochromatic" => {
-                                                    // 5 tints/shades
-                                                    vec![
-                                                        hsl_to_rgb(h, s, (l * 0.5).clamp(0.0, 1.0)),
-                                                        hsl_to_rgb(h, s, (l * 0.75).clamp(0.0, 1.0)),
-                                                        hsl_to_rgb(h, s, l),
-                                                        hsl_to_rgb(h, s, (l + 0.25).clamp(0.0, 1.0)),
-                                                        hsl_to_rgb(h, s, (l + 0.5).clamp(0.0, 1.0)),
-                                                    ]
-                                                },
-                                                "complementary" => {
-                                                    vec![
-                                                        (r, g, b),
-                                                        hsl_to_rgb((h + 180.0) % 360.0, s, l),
-                                                    ]
-                                                },
-                                                "analogous" => {
-                                                    vec![
-                                                        hsl_to_rgb((h + 330.0) % 360.0, s, l),
-                                                        (r, g, b),
-                                                        hsl_to_rgb((h + 30.0) % 360.0, s, l),
-                                                    ]
-                                                },
-                                                "triadic" => {
-                                                    vec![
-                                                        (r, g, b),
-                                                        hsl_to_rgb((h + 120.0) % 360.0, s, l),
-                                                        hsl_to_rgb((h + 240.0) % 360.0, s, l),
-                                                    ]
-                                                },
-                                                _ => vec![(r, g, b)],
-                                            };
-                                            // Swatch image
-                                            let swatch_size = 80u32;
-                                            let margin = 10u32;
-                                            let width = scheme_colors.len() as u32 * (swatch_size + margin) + margin;
-                                            let height = swatch_size + 2 * margin;
-                                            let mut swatch_img = image::RgbaImage::new(width, height);
-                                            for (i, (r, g, b)) in scheme_colors.iter().enumerate() {
-                                                let x0 = margin + i as u32 * (swatch_size + margin);
-                                                for x in x0..x0 + swatch_size {
-                                                    for y in margin..margin + swatch_size {
-                                                        swatch_img.put_pixel(x, y, image::Rgba([*r, *g, *b, 255]));
-                                                    }
-                                                }
-                                            }
-                                            let mut output_buffer = std::io::Cursor::new(Vec::new());
-                                            if let Err(_e) = swatch_img.write_to(&mut output_buffer, image::ImageFormat::Png) {
-                                                progress_bar.finish_with_message("❌ Failed to generate scheme swatch image");
-                                                let _ = msg.channel_id.say(&ctx.http, "Failed to generate scheme swatch image.").await;
-                                                return;
-                                            }
-                                            // Prepare hex codes
-                                            let hex_codes: Vec<String> = scheme_colors.iter().map(|(r, g, b)| format!("`#{:02X}{:02X}{:02X}`", r, g, b)).collect();
-                                            let hex_list = hex_codes.join(" ");
-                                            let message_content = format!("**{} Color Scheme**\n{}", scheme_type.to_uppercase(), hex_list);
-                                            let filename = crate::utils::sanitize_filename(&format!("color_scheme_{}.png", scheme_type), "png");
-                                            let attachment_data = serenity::builder::CreateAttachment::bytes(output_buffer.into_inner(), filename);
-                                            let message_builder = serenity::builder::CreateMessage::new().content(message_content);
-                                            let _ = msg.channel_id.send_files(&ctx.http, vec![attachment_data], message_builder).await;
-                                            progress_bar.finish_with_message("✅ Color scheme sent!");
-                                            return;
-                                        }
-                                    }
-                                }
-                            }
+                    let _typing = msg.channel_id.start_typing(&ctx.http);
+                    let progress_bar = ProgressBar::new_spinner();
+                    progress_bar.set_style(
+                        ProgressStyle::default_spinner()
+                            .template("{spinner:.green} {wide_msg}")
+                            .unwrap()
+                    );
+                    progress_bar.set_message("🧊 Rendering LUT slices...");
+                    progress_bar.enable_steady_tick(Duration::from_millis(100));
+                    let slice_algorithms = algorithms.clone();
+                    let slices_result = jobs::run(Job::new("lut", &msg), move || {
+                        let (slices, distinct) = image_processing::render_lut_slices(flavor, &slice_algorithms);
+                        let mut output_buffer = std::io::Cursor::new(Vec::new());
+                        slices.write_to(&mut output_buffer, image::ImageFormat::Png).map(|_| (output_buffer.into_inner(), distinct))
+                    }).await;
+                    match slices_result {
+                        Ok(Ok((png_bytes, distinct))) => {
+                            let counts: Vec<String> = algorithms.iter().zip(&distinct).map(|(algorithm, count)| format!("{}: {} colors", algorithm, count)).collect();
+                            let message_content = format!(
+                                "**LUT Slices: {}**\nHue runs across and lightness down; each row is one saturation. Distinct outputs in these slices: {}",
+                                flavor.to_string().to_uppercase(), counts.join(", ")
+                            );
+                            let filename = crate::utils::sanitize_filename(&format!("catppuccin_lut_{}_{}.png", flavor.to_string().to_lowercase(), algorithms.join("_")), "png");
+                            let attachment_data = serenity::builder::CreateAttachment::bytes(png_bytes, filename);
+                            let message_builder = serenity::builder::CreateMessage::new().content(message_content);
+                            let _ = msg.channel_id.send_files(&ctx.http, vec![attachment_data], message_builder).await;
+                            progress_bar.finish_with_message("✅ LUT slices sent!");
                         }
-                        progress_bar.finish_with_message("❌ Failed to analyze color scheme");
-                        let _ = msg.channel_id.say(&ctx.http, "Failed to analyze color scheme. Please ensure your image is valid and accessible.").await;
-                        return;
-                    } else {
-                        let _ = msg.channel_id.say(&ctx.http, "Please attach an image or provide a direct image URL to analyze color scheme.").await;
+                        Err(panic) => {
+                            progress_bar.finish_with_message("❌ Failed to render LUT slices");
+                            let _ = responses::error(&ctx.http, msg.channel_id, panic.to_string()).await;
+                        }
+                        _ => {
+                            progress_bar.finish_with_message("❌ Failed to render LUT slices");
+                            let _ = responses::error(&ctx.http, msg.channel_id, "Failed to render LUT slices.").await;
+                        }
+                    }
+                    return;
+                } else if parts[1] == "wallpaper" {
+                    // --- WALLPAPER GENERATION SUBCOMMAND ---
+                    // Usage: !cat wallpaper [style] [flavor] [WxH]
+                    let style = parts.get(2).map(|s| s.to_lowercase()).unwrap_or("noise".to_string());
+                    let valid_styles = ["noise", "grain", "paper", "grid", "diamonds", "dots", "stripes"];
+                    if !valid_styles.contains(&style.as_str()) {
+                        let _ = responses::warning(&ctx.http, msg.channel_id, "Please specify a valid wallpaper style: noise, grain, paper, grid, diamonds, dots, stripes.").await;
                         return;
                     }
-                } else if parts[1] == "animate" {
-                    // --- ANIMATION EFFECT SUBCOMMAND ---
-                    // Usage: !cat animate [effect] [image]
-                    let effect = parts.get(2).map(|s| s.to_lowercase()).unwrap_or("fade".to_string());
-                    let valid_effects = ["fade"];
-                    if !valid_effects.contains(&effect.as_str()) {
-                        let _ = msg.channel_id.say(&ctx.http, "Please specify a valid animation effect: fade.").await;
-                        return;
+                    let flavor = parts.iter().skip(3).find_map(|s| utils::parse_flavor(s)).unwrap_or(catppuccin::FlavorName::Mocha);
+                    let (width, height) = parts.iter().skip(3).find_map(|s| utils::parse_dimensions(s)).unwrap_or((1920, 1080));
+                    let _typing = msg.channel_id.start_typing(&ctx.http);
+                    let progress_bar = ProgressBar::new_spinner();
+                    progress_bar.set_style(
+                        ProgressStyle::default_spinner()
+                            .template("{spinner:.green} {wide_msg}")
+                            .unwrap()
+                    );
+                    progress_bar.set_message("🖼️ Generating wallpaper...");
+                    progress_bar.enable_steady_tick(Duration::from_millis(100));
+                    let wallpaper_style = style.clone();
+                    let wallpaper_result = jobs::run(Job::new("wallpaper", &msg), move || {
+                        let wallpaper = image_processing::generate_wallpaper(&wallpaper_style, flavor, width, height);
+                        let mut output_buffer = std::io::Cursor::new(Vec::new());
+                        wallpaper.write_to(&mut output_buffer, image::ImageFormat::Png).map(|_| output_buffer.into_inner())
+                    }).await;
+                    match wallpaper_result {
+                        Ok(Ok(png_bytes)) => {
+                            let filename = crate::utils::sanitize_filename(&format!("catppuccin_wallpaper_{}_{}.png", style, flavor.to_string().to_lowercase()), "png");
+                            let attachment_data = serenity::builder::CreateAttachment::bytes(png_bytes, filename);
+                            let message_content = format!("**Catppuccin Wallpaper: {}** ({}, {}x{})", style, flavor.to_string().to_uppercase(), width, height);
+                            let message_builder = serenity::builder::CreateMessage::new().content(message_content);
+                            let _ = msg.channel_id.send_files(&ctx.http, vec![attachment_data], message_builder).await;
+                            progress_bar.finish_with_message("✅ Wallpaper sent!");
+                        }
+                        Err(panic) => {
+                            progress_bar.finish_with_message("❌ Failed to generate wallpaper");
+                            let _ = responses::error(&ctx.http, msg.channel_id, panic.to_string()).await;
+                        }
+                        _ => {
+                            progress_bar.finish_with_message("❌ Failed to generate wallpaper");
+                            let _ = responses::error(&ctx.http, msg.channel_id, "Failed to generate wallpaper.").await;
+                        }
                     }
-                    let attachment = msg.attachments.iter().find(|a| a.width.is_some() && a.height.is_some());
-                    let image_url = if let Some(attachment) = attachment {
-                        Some(attachment.url.as_str().to_string())
-                    } else {
-                        let url_regex = regex::Regex::new(r"^(https?://[\w\-./%?=&]+\.(png|jpe?g|gif|bmp|webp))$").unwrap();
-                        parts.iter().find(|s| url_regex.is_match(s)).map(|s| s.to_string())
-                    };
-                    if let Some(image_url) = image_url {
-                        let _typing = msg.channel_id.start_typing(&ctx.http);
-                        let progress_bar = ProgressBar::new_spinner();
-                        progress_bar.set_style(
-                            ProgressStyle::default_spinner()
-                                .template("{spinner:.green} {wide_msg}")
-                                .unwrap()
-                        );
-                        progress_bar.set_message("🎬 Generating animation effect...");
-                        progress_bar.enable_steady_tick(Duration::from_millis(100));
-                        let response = reqwest::get(&image_url).await;
-                        if let Ok(resp) = response {
-                            let bytes = resp.bytes().await;
-                            if let Ok(image_bytes) = bytes {
-                                let img_reader = ImageReader::new(std::io::Cursor::new(&image_bytes)).with_guessed_format();
-                                if let Ok(reader) = img_reader {
-                                    if let Ok(img) = reader.decode() {
-                                        let rgba_img = img.to_rgba8();
-                                        match image_processing::animate_image_effect(&rgba_img, &effect) {
-                                            Ok(gif_bytes) => {
-                                                let filename = crate::utils::sanitize_filename(&format!("animation_{}.gif", effect), "gif");
-                                                let attachment_data = serenity::builder::CreateAttachment::bytes(gif_bytes, filename);
-                                                let message_content = format!("**Animation Effect: {}**", effect);
-                                        let message_builder = serenity::builder::CreateMessage::new().content(message_content);
-                                        let _ = msg.channel_id.send_files(&ctx.http, vec![attachment_data], message_builder).await;
-                                                progress_bar.finish_with_message("✅ Animation sent!");
-                                        return;
-                                    }
-                                            Err(e) => {
-                                                progress_bar.finish_with_message("❌ Failed to generate animation");
-                                                let _ = msg.channel_id.say(&ctx.http, &format!("Failed to generate animation: {}", e)).await;
-                                                return;
-                                            }
-                                        }
-                                    }
-                                }
-                            }
+                    return;
+                } else if parts[1] == "identicon" {
+                    // --- IDENTICON SUBCOMMAND ---
+                    // Usage: !cat identicon <seed> [flavor]
+                    let mut seed_parts: Vec<&str> = parts[2..].to_vec();
+                    let flavor = match seed_parts.last().and_then(|s| utils::parse_flavor(s)) {
+                        Some(f) if seed_parts.len() > 1 => {
+                            seed_parts.pop();
+                            f
                         }
-                        progress_bar.finish_with_message("❌ Failed to generate animation");
-                        let _ = msg.channel_id.say(&ctx.http, "Failed to generate animation. Please ensure your image is valid and accessible.").await;
-                        return;
-                    } else {
-                        let _ = msg.channel_id.say(&ctx.http, "Please attach an image or provide a direct image URL to animate.").await;
+                        _ => catppuccin::FlavorName::Mocha,
+                    };
+                    // Default to the author's ID so everyone gets their own avatar
+                    let seed = if seed_parts.is_empty() { msg.author.id.to_string() } else { seed_parts.join(" ") };
+                    let _typing = msg.channel_id.start_typing(&ctx.http);
+                    let progress_bar = ProgressBar::new_spinner();
+                    progress_bar.set_style(
+                        ProgressStyle::default_spinner()
+                            .template("{spinner:.green} {wide_msg}")
+                            .unwrap()
+                    );
+                    progress_bar.set_message("🧩 Generating identicon...");
+                    progress_bar.enable_steady_tick(Duration::from_millis(100));
+                    let identicon = palette::generate_identicon(&seed, flavor, 420);
+                    let mut output_buffer = std::io::Cursor::new(Vec::new());
+                    if let Err(_e) = identicon.write_to(&mut output_buffer, image::ImageFormat::Png) {
+                        progress_bar.finish_with_message("❌ Failed to generate identicon");
+                        let _ = responses::error(&ctx.http, msg.channel_id, "Failed to generate identicon.").await;
                         return;
                     }
-                } else if parts[1] == "texture" {
-                    // --- TEXTURE OVERLAY SUBCOMMAND ---
-                    // Usage: !cat texture [type] [image]
-                    let texture_type = parts.get(2).map(|s| s.to_lowercase()).unwrap_or("dots".to_string());
-                    let valid_types = ["dots", "stripes"];
-                    if !valid_types.contains(&texture_type.as_str()) {
-                        let _ = msg.channel_id.say(&ctx.http, "Please specify a valid texture type: dots, stripes.").await;
+                    let filename = crate::utils::sanitize_filename(&format!("identicon_{}.png", seed), "png");
+                    let attachment_data = serenity::builder::CreateAttachment::bytes(output_buffer.into_inner(), filename);
+                    let message_content = format!("**Catppuccin Identicon** (Seed: `{}`, Flavor: {})", seed, flavor.to_string().to_uppercase());
+                    let message_builder = serenity::builder::CreateMessage::new().content(message_content);
+                    let _ = msg.channel_id.send_files(&ctx.http, vec![attachment_data], message_builder).await;
+                    progress_bar.finish_with_message("✅ Identicon sent!");
+                    return;
+                } else if parts[1] == "card" {
+                    // --- TEXT CARD SUBCOMMAND ---
+                    // Usage: !cat card "some text" [flavor] [accent]
+                    let text = utils::extract_quoted_args(&msg.content).into_iter().next()
+                        .unwrap_or_else(|| parts[2..].iter().filter(|s| utils::parse_flavor(s).is_none()).cloned().collect::<Vec<_>>().join(" "));
+                    if text.trim().is_empty() {
+                        let _ = responses::warning(&ctx.http, msg.channel_id, "Please provide some text for the card. Example: `!cat card \"Welcome to the server!\" mocha`").await;
                         return;
                     }
-                    let attachment = msg.attachments.iter().find(|a| a.width.is_some() && a.height.is_some());
-                    let image_url = if let Some(attachment) = attachment {
-                        Some(attachment.url.as_str().to_string())
-                    } else {
-                        let url_regex = regex::Regex::new(r"^(https?://[\w\-./%?=&]+\.(png|jpe?g|gif|bmp|webp))$").unwrap();
-                        parts.iter().find(|s| url_regex.is_match(s)).map(|s| s.to_string())
-                    };
-                    if let Some(image_url) = image_url {
-                        let _typing = msg.channel_id.start_typing(&ctx.http);
-                        let progress_bar = ProgressBar::new_spinner();
-                        progress_bar.set_style(
-                            ProgressStyle::default_spinner()
-                                .template("{spinner:.green} {wide_msg}")
-                                .unwrap()
-                        );
-                        progress_bar.set_message("🖌️ Applying Catppuccin texture overlay...");
-                        progress_bar.enable_steady_tick(Duration::from_millis(100));
-                        let response = reqwest::get(&image_url).await;
-                        if let Ok(resp) = response {
-                            let bytes = resp.bytes().await;
-                            if let Ok(image_bytes) = bytes {
-                                let img_reader = ImageReader::new(std::io::Cursor::new(&image_bytes)).with_guessed_format();
-                                if let Ok(reader) = img_reader {
-                                    if let Ok(img) = reader.decode() {
-                                        let rgba_img = img.to_rgba8();
-                                        let flavor = crate::utils::parse_flavor("latte").unwrap(); // Default to Latte for now
-                                        let textured_img = image_processing::overlay_catppuccin_texture(&rgba_img, &texture_type, flavor);
-                                        let mut output_buffer = std::io::Cursor::new(Vec::new());
-                                        if let Err(_e) = textured_img.write_to(&mut output_buffer, image::ImageFormat::Png) {
-                                            progress_bar.finish_with_message("❌ Failed to generate texture overlay image");
-                                            let _ = msg.channel_id.say(&ctx.http, "Failed to generate texture overlay image.").await;
-                                            return;
-                                        }
-                                        let filename = crate::utils::sanitize_filename(&format!("catppuccin_texture_{}.png", texture_type), "png");
-                                        let attachment_data = serenity::builder::CreateAttachment::bytes(output_buffer.into_inner(), filename);
-                                        let message_content = format!("**Catppuccin Texture Overlay: {}**", texture_type);
-                                        let message_builder = serenity::builder::CreateMessage::new().content(message_content);
-                                        let _ = msg.channel_id.send_files(&ctx.http, vec![attachment_data], message_builder).await;
-                                        progress_bar.finish_with_message("✅ Texture overlay image sent!");
-                                        return;
-                                    }
-                                }
-                            }
-                        }
-                        progress_bar.finish_with_message("❌ Failed to apply texture overlay");
-                        let _ = msg.channel_id.say(&ctx.http, "Failed to apply texture overlay. Please ensure your image is valid and accessible.").await;
+                    if text.chars().count() > 500 {
+                        let _ = responses::warning(&ctx.http, msg.channel_id, "Card text is too long. Please keep it under 500 characters.").await;
                         return;
-                    } else {
-                        let _ = msg.channel_id.say(&ctx.http, "Please attach an image or provide a direct image URL to apply a texture overlay.").await;
-                        return;
-                    }
-                } else if let Some(flavor) = utils::parse_flavor(parts[1]) {
-                    selected_flavor = flavor;
-                    has_explicit_flavor_arg = true;
-                } else if let Some(algorithm) = utils::parse_algorithm(parts[1]) {
-                    selected_algorithm = algorithm;
-                } else if let Some(quality) = utils::parse_quality(parts[1]) {
-                    selected_quality = Some(quality.to_string());
-                } else if let Some(format) = utils::parse_format(parts[1]) {
-                    selected_format = Some(format);
-                }
-            }
-
-            // Enable batch mode if multiple image attachments are present
-            if msg.attachments.len() > 1 {
-                batch_mode = true;
-            }
-
-            if parts.len() > 2 {
-                if show_palette {
-                    // Start typing indicator for palette generation
+                    }
+                    let unquoted = utils::strip_quoted_args(&msg.content);
+                    let unquoted_parts: Vec<&str> = unquoted.split_whitespace().collect();
+                    let flavor = unquoted_parts.iter().find_map(|s| utils::parse_flavor(s)).unwrap_or(catppuccin::FlavorName::Mocha);
+                    let accent = unquoted_parts.iter()
+                        .find(|s| utils::catppuccin_color_name_to_rgb(s, flavor).is_some())
+                        .map(|s| s.to_lowercase())
+                        .unwrap_or("mauve".to_string());
                     let _typing = msg.channel_id.start_typing(&ctx.http);
-                    
-                    // Create progress bar for palette generation
                     let progress_bar = ProgressBar::new_spinner();
                     progress_bar.set_style(
                         ProgressStyle::default_spinner()
                             .template("{spinner:.green} {wide_msg}")
                             .unwrap()
                     );
-                    progress_bar.set_message("🎨 Generating palette preview...");
+                    progress_bar.set_message("🪧 Rendering text card...");
                     progress_bar.enable_steady_tick(Duration::from_millis(100));
-                    
-                    if parts[2] == "all" {
-                        progress_bar.set_message("🎨 Generating all palette previews...");
-                        let palette_img = palette::generate_all_palettes_preview();
-                        let mut output_buffer = std::io::Cursor::new(Vec::new());
-                        if let Err(_e) = palette_img.write_to(&mut output_buffer, image::ImageFormat::Png) {
-                            progress_bar.finish_with_message("❌ Failed to generate palette preview");
-                            let _ = msg.channel_id.say(&ctx.http, "Failed to generate palette preview.").await;
-                            return;
-                        }
-                        let attachment_data = serenity::builder::CreateAttachment::bytes(output_buffer.into_inner(), "catppuccin_palettes_all.png");
-                        let message_content = "**All Catppuccin Color Palettes**\nFrom left to right: Latte, Frappe, Macchiato, Mocha";
-                        let message_builder = serenity::builder::CreateMessage::new().content(message_content);
-                        progress_bar.set_message("📤 Uploading palette preview...");
-                        let _ = msg.channel_id.send_files(&ctx.http, vec![attachment_data], message_builder).await;
-                        progress_bar.finish_with_message("✅ All palette previews uploaded successfully!");
-                        return;
-                    } else if let Some(flavor) = utils::parse_flavor(parts[2]) {
-                        progress_bar.set_message("🎨 Generating palette preview...");
-                        let palette_img = palette::generate_palette_preview(flavor);
-                        let mut output_buffer = std::io::Cursor::new(Vec::new());
-                        if let Err(_e) = palette_img.write_to(&mut output_buffer, image::ImageFormat::Png) {
-                            progress_bar.finish_with_message("❌ Failed to generate palette preview");
-                            let _ = msg.channel_id.say(&ctx.http, "Failed to generate palette preview.").await;
-                            return;
-                        }
-                        let filename = format!("catppuccin_palette_{}.png", flavor.to_string().to_lowercase());
-                        let attachment_data = serenity::builder::CreateAttachment::bytes(output_buffer.into_inner(), filename);
-                        let message_content = format!("**Catppuccin {} Color Palette**", flavor.to_string().to_uppercase());
-                        let message_builder = serenity::builder::CreateMessage::new().content(message_content);
-                        progress_bar.set_message("📤 Uploading palette preview...");
-                        let _ = msg.channel_id.send_files(&ctx.http, vec![attachment_data], message_builder).await;
-                        progress_bar.finish_with_message("✅ Palette preview uploaded successfully!");
-                        return;
-                    } else {
-                        progress_bar.finish_with_message("❌ Invalid palette command");
-                        let _ = msg.channel_id.say(&ctx.http, "Invalid palette command. Use `!cat palette [flavor]` or `!cat palette all`").await;
+                    let card = palette::generate_text_card(&text, flavor, &accent);
+                    let mut output_buffer = std::io::Cursor::new(Vec::new());
+                    if let Err(_e) = card.write_to(&mut output_buffer, image::ImageFormat::Png) {
+                        progress_bar.finish_with_message("❌ Failed to render text card");
+                        let _ = responses::error(&ctx.http, msg.channel_id, "Failed to render text card.").await;
                         return;
                     }
-                }
-                if has_explicit_flavor_arg {
-                    if let Some(algorithm) = utils::parse_algorithm(parts[2]) {
-                        selected_algorithm = algorithm;
-                    } else if let Some(quality) = utils::parse_quality(parts[2]) {
-                        selected_quality = Some(quality.to_string());
-                        selected_algorithm = quality;
-                    } else if let Some(format) = utils::parse_format(parts[2]) {
-                        selected_format = Some(format);
+                    let filename = crate::utils::sanitize_filename(&format!("catppuccin_card_{}.png", flavor.to_string().to_lowercase()), "png");
+                    let attachment_data = serenity::builder::CreateAttachment::bytes(output_buffer.into_inner(), filename);
+                    let message_builder = serenity::builder::CreateMessage::new();
+                    let _ = msg.channel_id.send_files(&ctx.http, vec![attachment_data], message_builder).await;
+                    progress_bar.finish_with_message("✅ Text card sent!");
+                    return;
+                } else if parts[1] == "preview" {
+                    // --- THEME PREVIEW MOCK SUBCOMMAND ---
+                    // Usage: !cat preview [flavor] [code|terminal|editor]
+                    let flavor = parts.iter().skip(2).find_map(|s| utils::parse_flavor(s)).unwrap_or(catppuccin::FlavorName::Mocha);
+                    let kind = parts.iter().skip(2).find_map(|s| utils::parse_preview_kind(s)).unwrap_or(crate::mockups::PreviewKind::Code);
+                    let _typing = msg.channel_id.start_typing(&ctx.http);
+                    let progress_bar = ProgressBar::new_spinner();
+                    progress_bar.set_style(
+                        ProgressStyle::default_spinner()
+                            .template("{spinner:.green} {wide_msg}")
+                            .unwrap()
+                    );
+                    progress_bar.set_message("🖥️ Rendering theme preview...");
+                    progress_bar.enable_steady_tick(Duration::from_millis(100));
+                    let preview = crate::mockups::render_theme_preview(flavor, kind);
+                    let mut output_buffer = std::io::Cursor::new(Vec::new());
+                    if let Err(_e) = preview.write_to(&mut output_buffer, image::ImageFormat::Png) {
+                        progress_bar.finish_with_message("❌ Failed to render theme preview");
+                        let _ = responses::error(&ctx.http, msg.channel_id, "Failed to render theme preview.").await;
+                        return;
                     }
-                }
-            }
-
-            // Hex Color Conversion Logic
-            if msg.attachments.is_empty() {
-                let input_color_arg_index = if has_explicit_flavor_arg { 2 } else { 1 };
-                if parts.len() > input_color_arg_index {
-                    let input_color = parts[input_color_arg_index];
-                    let hex_regex = regex::Regex::new(r"^#?([0-9a-fA-F]{3}){1,2}$").unwrap();
-                    if !hex_regex.is_match(input_color) {
-                        let _ = msg.channel_id.say(&ctx.http, "That doesn't look like a valid hex color or flavor. Please use formats like `#FF0000` or `FF0000` for colors, or specify a flavor like `latte`, `frappe`, `macchiato`, `mocha` with an image.").await;
+                    let kind_name = format!("{:?}", kind).to_lowercase();
+                    let filename = crate::utils::sanitize_filename(&format!("catppuccin_{}_{}.png", kind_name, flavor.to_string().to_lowercase()), "png");
+                    let attachment_data = serenity::builder::CreateAttachment::bytes(output_buffer.into_inner(), filename);
+                    let message_content = format!("**Theme Preview**: {} ({})", flavor.to_string().to_uppercase(), kind_name);
+                    let message_builder = serenity::builder::CreateMessage::new().content(message_content);
+                    let _ = msg.channel_id.send_files(&ctx.http, vec![attachment_data], message_builder).await;
+                    progress_bar.finish_with_message("✅ Theme preview sent!");
+                    return;
+                } else if parts[1] == "caption" {
+                    // --- MEME CAPTION SUBCOMMAND ---
+                    // Usage: !cat caption "top text" "bottom text" [flavor] [image]
+                    let captions = utils::extract_quoted_args(&msg.content);
+                    let top = captions.first().cloned().unwrap_or_default();
+                    let bottom = captions.get(1).cloned().unwrap_or_default();
+                    if top.trim().is_empty() && bottom.trim().is_empty() {
+                        let _ = responses::warning(&ctx.http, msg.channel_id, "Please provide caption text in quotes. Example: `!cat caption \"top text\" \"bottom text\" mocha [image]`").await;
                         return;
                     }
-                    // Start typing indicator for hex conversion
+                    let unquoted = utils::strip_quoted_args(&msg.content);
+                    let unquoted_parts: Vec<&str> = unquoted.split_whitespace().collect();
+                    let flavor = unquoted_parts.iter().find_map(|s| utils::parse_flavor(s)).unwrap_or(catppuccin::FlavorName::Latte);
+                    let Some(image_url) = find_image_source(&ctx, &msg, &unquoted_parts).await else {
+                        let _ = responses::warning(&ctx.http, msg.channel_id, "Please attach an image or provide a direct image URL to caption.").await;
+                        return;
+                    };
                     let _typing = msg.channel_id.start_typing(&ctx.http);
-                    
-                    // Create progress bar for hex conversion
                     let progress_bar = ProgressBar::new_spinner();
                     progress_bar.set_style(
                         ProgressStyle::default_spinner()
                             .template("{spinner:.green} {wide_msg}")
                             .unwrap()
                     );
-                    progress_bar.set_message("🎨 Converting hex color to Catppuccin...");
+                    progress_bar.set_message("🖋️ Captioning image...");
                     progress_bar.enable_steady_tick(Duration::from_millis(100));
-                    
-                    match utils::find_closest_catppuccin_hex(input_color, selected_flavor) {
-                        Some((color_name, converted_hex)) => {
-                            progress_bar.set_message("✅ Color conversion completed");
-                            let _embed_color = u32::from_str_radix(&converted_hex, 16).unwrap_or(0x000000);
-                            let original_color_display = if input_color.starts_with('#') {
-                                input_color.to_string()
-                            } else {
-                                format!("#{}", input_color)
-                            };
-                            let converted_color_display = format!("#{}", converted_hex);
-                            let embed = serenity::builder::CreateEmbed::default()
-                                .title("Catppuccin Color Conversion")
-                                .description(format!("Original Color: `{}`", original_color_display))
-                                .color(MOCHA_MAUVE)
-                                .field(
-                                    "Closest Catppuccin Color",
-                                    format!("**{}** (`{}`) (Flavor: {})", color_name.to_uppercase(), converted_color_display, selected_flavor.to_string().to_uppercase()),
-                                    false,
-                                )
-                                .field("\u{200b}", "**Color Swatch:** \u{2588}\u{2588}\u{2588}\u{2588}\u{2588}", false);
-                            let builder = serenity::builder::CreateMessage::new().embed(embed);
-                            let _ = msg.channel_id.send_message(&ctx.http, builder).await;
-                            progress_bar.finish_with_message("✅ Color conversion result sent!");
+                    let img = match fetch_image(fetcher, &image_url, autoscale).await {
+                        Ok(img) => img,
+                        Err(e) => {
+                            progress_bar.finish_with_message("❌ Failed to load image for caption");
+                            let _ = responses::error(&ctx.http, msg.channel_id, e).await;
+                            return;
                         }
-                        None => {
-                            progress_bar.finish_with_message("❌ Error converting hex color");
-                            let _ = msg.channel_id.say(&ctx.http, "Error converting hex color. Please ensure it's a valid 3 or 6 digit hex code.").await;
+                    };
+                    let caption_result = jobs::run(Job::new("caption", &msg), move || {
+                        let processed = image_processing::process_image_with_palette(&img, flavor, "shepards-method").to_rgba8();
+                        let captioned = image_processing::caption_image(&processed, &top, &bottom, flavor);
+                        let mut output_buffer = std::io::Cursor::new(Vec::new());
+                        captioned.write_to(&mut output_buffer, image::ImageFormat::Png).map(|_| output_buffer.into_inner())
+                    }).await;
+                    match caption_result {
+                        Ok(Ok(png_bytes)) => {
+                            let filename = crate::utils::sanitize_filename(&format!("catppuccin_caption_{}.png", flavor.to_string().to_lowercase()), "png");
+                            let attachment_data = serenity::builder::CreateAttachment::bytes(png_bytes, filename);
+                            let message_builder = serenity::builder::CreateMessage::new();
+                            let _ = msg.channel_id.send_files(&ctx.http, vec![attachment_data], message_builder).await;
+                            progress_bar.finish_with_message("✅ Captioned image sent!");
+                        }
+                        Err(panic) => {
+                            progress_bar.finish_with_message("❌ Failed to caption image");
+                            let _ = responses::error(&ctx.http, msg.channel_id, panic.to_string()).await;
+                        }
+                        _ => {
+                            progress_bar.finish_with_message("❌ Failed to caption image");
+                            let _ = responses::error(&ctx.http, msg.channel_id, "Failed to caption image.").await;
                         }
                     }
                     return;
-                }
-            }
-
-            // Image Processing Logic
-            if batch_mode && !msg.attachments.is_empty() {
-                // Start typing indicator for batch processing
-                let _typing = msg.channel_id.start_typing(&ctx.http);
-                
-                // Create progress bar for batch processing
-                let progress_bar = ProgressBar::new_spinner();
-                progress_bar.set_style(
-                    ProgressStyle::default_spinner()
-                        .template("{spinner:.green} {wide_msg}")
-                        .unwrap()
-                );
-                progress_bar.set_message("🔄 Starting batch processing...");
-                progress_bar.enable_steady_tick(Duration::from_millis(100));
-                
-                // Batch processing: process all image attachments
-                let mut processed_attachments = Vec::new();
-                let mut failed_count = 0;
-                for (_i, attachment) in msg.attachments.iter().enumerate() {
-                    progress_bar.set_message("📥 Processing image...");
-                    let content_type_is_image = attachment.content_type.as_deref().map_or(false, |s| s.starts_with("image/"));
-                    if !content_type_is_image {
-                        continue;
-                    }
-                    let reqwest_client = reqwest::Client::new();
-                    let image_bytes = match reqwest_client.get(&attachment.url).send().await {
-                        Ok(response) => match response.bytes().await {
-                            Ok(bytes) => bytes,
-                            Err(_) => {
-                                failed_count += 1;
-                                continue;
-                            }
-                        },
-                        Err(_) => {
-                            failed_count += 1;
-                            continue;
+                } else if parts[1] == "info" {
+                    // --- IMAGE INFO SUBCOMMAND ---
+                    // Usage: !cat info [image]
+                    let Some(image_source) = find_image_source(&ctx, &msg, &parts).await else {
+                        let _ = responses::warning(&ctx.http, msg.channel_id, "Please attach an image or provide a direct image URL to inspect.").await;
+                        return;
+                    };
+                    let _typing = msg.channel_id.start_typing(&ctx.http);
+                    let image_bytes = match source::download(fetcher, &image_source).await {
+                        Ok(bytes) => bytes,
+                        Err(e) => {
+                            let _ = responses::error(&ctx.http, msg.channel_id, e).await;
+                            return;
                         }
                     };
-                    let img = match ImageReader::new(std::io::Cursor::new(image_bytes)).with_guessed_format().expect("Failed to guess image format").decode() {
-                        Ok(img) => img,
-                        Err(_) => {
-                            failed_count += 1;
-                            continue;
+                    let info_result = jobs::run(Job::new("info", &msg), move || {
+                        let info = image_processing::image_info(&image_bytes)?;
+                        // Closeness is judged on the first frame, and skipped for images too large to process anyway
+                        let distances = if limits::still(info.width, info.height, false).is_ok() {
+                            let img = icc::decode(&image_bytes).map_err(|e| BotError::Decode(e.to_string()))?;
+                            image_processing::flavor_distances(&img.to_rgba8())
+                        } else {
+                            Vec::new()
+                        };
+                        Ok::<_, String>((info, distances))
+                    }).await;
+                    let (info, distances) = match info_result {
+                        Ok(Ok(result)) => result,
+                        Ok(Err(e)) => {
+                            let _ = responses::error(&ctx.http, msg.channel_id, e).await;
+                            return;
+                        }
+                        Err(panic) => {
+                            let _ = responses::error(&ctx.http, msg.channel_id, panic.to_string()).await;
+                            return;
                         }
                     };
-                    let mut rgba_img = img.to_rgba8();
-                    let lut = image_processing::generate_catppuccin_lut(selected_flavor, selected_algorithm);
-                    image_processing::apply_lut_to_image(&mut rgba_img, &lut);
-                    let mut output_buffer = std::io::Cursor::new(Vec::new());
-                    let output_format = selected_format.unwrap_or(image::ImageFormat::Png);
-                    let dynamic_img = image::DynamicImage::ImageRgba8(rgba_img);
-                    if let Err(_) = dynamic_img.write_to(&mut output_buffer, output_format) {
-                        failed_count += 1;
-                        continue;
+                    let frames = if info.frames > 1 { format!("{} (animated)", info.frames) } else { "1".to_string() };
+                    let mut text = format!(
+                        "**Format:** {}\n**Dimensions:** {}×{} ({:.2} MP)\n**Frames:** {}\n**Color:** {:?}, {}-bit per channel\n**File size:** {}\n",
+                        format!("{:?}", info.format).to_uppercase(),
+                        info.width,
+                        info.height,
+                        (info.width as f64 * info.height as f64) / 1_000_000.0,
+                        frames,
+                        info.color_type,
+                        info.bit_depth(),
+                        utils::format_file_size(info.file_size),
+                    );
+                    // Below this average ΔE, mapping to the flavor barely changes the image
+                    const ALREADY_CLOSE: f32 = 5.0;
+                    match distances.first() {
+                        Some((flavor, distance)) if *distance < ALREADY_CLOSE => {
+                            text.push_str(&format!("**Closest flavor:** {} (average ΔE {:.1}), already close; processing with it will change little\n", flavor, distance));
+                        }
+                        Some((flavor, distance)) => text.push_str(&format!("**Closest flavor:** {} (average ΔE {:.1})\n", flavor, distance)),
+                        None => text.push_str("**Closest flavor:** not analyzed; the image is over the size limit\n"),
                     }
-                    let filename = format!("catppuccinified_{}_{}.", selected_flavor.to_string().to_lowercase(), attachment.filename);
-                    let filename = if let Some(ext) = output_format.extensions_str().first() {
-                        format!("{}{}", filename, ext)
-                    } else {
-                        format!("{}png", filename)
-                    };
-                    let attachment_data = serenity::builder::CreateAttachment::bytes(output_buffer.into_inner(), filename);
-                    processed_attachments.push(attachment_data);
-                }
-                if !processed_attachments.is_empty() {
-                    progress_bar.set_message("📤 Uploading batch processed images...");
-                    let message_content = if failed_count > 0 {
-                        format!("Here are your Catppuccinified images! ({} failed)", failed_count)
+                    let (kind, units) = if info.frames > 1 {
+                        (eta::WorkKind::Gif, info.width as u64 * info.height as u64 * info.frames as u64)
                     } else {
-                        "Here are your Catppuccinified images!".to_string()
+                        (eta::WorkKind::Still, info.width as u64 * info.height as u64)
                     };
-                    let message_builder = serenity::builder::CreateMessage::new().content(message_content);
-                    let _processed_count = processed_attachments.len();
-                    let _ = msg.channel_id.send_files(&ctx.http, processed_attachments, message_builder).await;
-                    progress_bar.finish_with_message("✅ Batch processing completed!");
-                } else {
-                    progress_bar.finish_with_message("❌ Failed to process any images. Please ensure your attachments are valid images.");
-                }
-                return;
-            }
-            if let Some(attachment) = msg.attachments.first() {
-                info!(filename = %attachment.filename, url = %attachment.url, "Image received");
-                
-                // Start typing indicator
-                let _typing = msg.channel_id.start_typing(&ctx.http);
-                
-                // Create progress bar for console output
-                let progress_bar = ProgressBar::new_spinner();
-                progress_bar.set_style(
-                    ProgressStyle::default_spinner()
-                        .template("{spinner:.green} {wide_msg}")
-                        .unwrap()
-                );
-                progress_bar.set_message("🔄 Starting image processing...");
-                progress_bar.enable_steady_tick(Duration::from_millis(100));
-                
-                // Only process if it's an image
-                let content_type_is_image = attachment.content_type.as_deref().map_or(false, |s| s.starts_with("image/"));
-                if !content_type_is_image {
-                    progress_bar.finish_with_message("❌ Attachment is not an image");
-                    warn!(?attachment.content_type, "Attachment is not an image");
-                    let _ = msg.channel_id.say(&ctx.http, "Please attach an image to catppuccinify it.").await;
+                    let estimates: Vec<String> = registry::ALGORITHMS
+                        .values
+                        .iter()
+                        .filter_map(|(name, _)| utils::parse_algorithm(name).map(|algorithm| (name, algorithm)))
+                        .map(|(name, algorithm)| format!("`{}` {}", name, eta::describe(eta::estimate(algorithm, kind, units))))
+                        .collect();
+                    let embed = responses::embed(responses::Tone::Info, text)
+                        .title(format!("🔍 {}", image_source.filename))
+                        .field("Estimated processing time", estimates.join(" · "), false);
+                    let _ = msg.channel_id.send_message(&ctx.http, serenity::builder::CreateMessage::new().embed(embed)).await;
                     return;
-                }
-
-                // Download the image
-                progress_bar.set_message("📥 Downloading image...");
-                info!(url = %attachment.url, "Downloading image");
-                let reqwest_client = reqwest::Client::new();
-                let image_bytes = match reqwest_client.get(&attachment.url).send().await {
-                    Ok(response) => match response.bytes().await {
-                        Ok(bytes) => {
-                            progress_bar.set_message("✅ Image downloaded successfully");
-                            bytes
-                        },
-                        Err(_) => {
-                            progress_bar.finish_with_message("❌ Failed to read image data");
-                            error!("Failed to read image data");
-                            let _ = msg.channel_id.say(&ctx.http, "Failed to read image data.").await;
+                } else if parts[1] == "deltamap" {
+                    // --- DELTA-E HEATMAP SUBCOMMAND ---
+                    // Usage: !cat deltamap [flavor] [algorithm] [image]
+                    let flavor = parts.iter().skip(2).find_map(|s| utils::parse_flavor(s)).unwrap_or(selected_flavor);
+                    let algorithm = parts.iter().skip(2).find_map(|s| utils::parse_algorithm(s)).unwrap_or(selected_algorithm);
+                    let Some(image_url) = find_image_source(&ctx, &msg, &parts).await else {
+                        let _ = responses::warning(&ctx.http, msg.channel_id, "Please attach an image or provide a direct image URL to map.").await;
+                        return;
+                    };
+                    let _typing = msg.channel_id.start_typing(&ctx.http);
+                    let progress_bar = ProgressBar::new_spinner();
+                    progress_bar.set_style(
+                        ProgressStyle::default_spinner()
+                            .template("{spinner:.green} {wide_msg}")
+                            .unwrap()
+                    );
+                    progress_bar.set_message("🌡️ Mapping perceptual change...");
+                    progress_bar.enable_steady_tick(Duration::from_millis(100));
+                    let img = match fetch_image(fetcher, &image_url, autoscale).await {
+                        Ok(img) => img,
+                        Err(e) => {
+                            progress_bar.finish_with_message("❌ Failed to load image");
+                            let _ = responses::error(&ctx.http, msg.channel_id, e).await;
                             return;
                         }
-                    },
-                    Err(_) => {
-                        progress_bar.finish_with_message("❌ Failed to download image from Discord");
-                        error!("Failed to download image from Discord");
-                        let _ = msg.channel_id.say(&ctx.http, "Failed to download image from Discord.").await;
-                        return;
-                    }
-                };
-
-                // Load the image from bytes
-                progress_bar.set_message("🔍 Decoding image...");
-                info!("Decoding image");
-                let img = match ImageReader::new(std::io::Cursor::new(image_bytes)).with_guessed_format().expect("Failed to guess image format").decode() {
-                    Ok(img) => {
-                        progress_bar.set_message("✅ Image decoded successfully");
-                        img
-                    },
-                    Err(_) => {
-                        progress_bar.finish_with_message("❌ Failed to decode the image");
-                        error!("Failed to decode the image");
-                        let _ = msg.channel_id.say(&ctx.http, "Failed to decode the image. Is it a valid image file?").await;
-                        return;
+                    };
+                    let map_result = jobs::run(Job::new("deltamap", &msg), move || {
+                        let original = img.to_rgba8();
+                        let mut processed = original.clone();
+                        image_processing::apply_algorithm_lut(&mut processed, &image_processing::generate_catppuccin_lut(flavor, algorithm), algorithm);
+                        let (map, mean, max) = image_processing::render_delta_map(&original, &processed, flavor);
+                        let mut output_buffer = std::io::Cursor::new(Vec::new());
+                        map.write_to(&mut output_buffer, image::ImageFormat::Png).map_err(|_| "Failed to encode the delta map.".to_string())?;
+                        Ok::<_, String>((output_buffer.into_inner(), mean, max))
+                    }).await;
+                    match map_result {
+                        Ok(Ok((png_bytes, mean, max))) => {
+                            let filename = crate::utils::sanitize_filename(&format!("catppuccin_deltamap_{}.png", flavor.identifier()), "png");
+                            let attachment_data = serenity::builder::CreateAttachment::bytes(png_bytes, filename);
+                            let message_content = format!(
+                                "**Perceptual change with {} ({})**\nAverage ΔE {:.1}, largest {:.1}. Base means unchanged; blue, green, yellow and peach run up to red at ΔE {:.0} and above.",
+                                flavor, algorithm, mean, max, image_processing::DELTA_MAP_SCALE
+                            );
+                            let message_builder = serenity::builder::CreateMessage::new().content(message_content);
+                            let _ = msg.channel_id.send_files(&ctx.http, vec![attachment_data], message_builder).await;
+                            progress_bar.finish_with_message("✅ Delta map sent!");
+                        }
+                        Ok(Err(e)) => {
+                            progress_bar.finish_with_message("❌ Failed to map perceptual change");
+                            let _ = responses::error(&ctx.http, msg.channel_id, e).await;
+                        }
+                        Err(panic) => {
+                            progress_bar.finish_with_message("❌ Failed to map perceptual change");
+                            let _ = responses::error(&ctx.http, msg.channel_id, panic.to_string()).await;
+                        }
                     }
-                };
-
-                // Convert to RGBA
-                progress_bar.set_message("🔄 Converting image to RGBA...");
-                debug!("Converting image to RGBA");
-                let mut rgba_img = img.to_rgba8();
-                let (width, height) = rgba_img.dimensions();
-                progress_bar.set_message("📐 Image dimensions analyzed");
-
-                // Handle color statistics
-                if show_stats {
-                    progress_bar.set_message("🎨 Analyzing image colors...");
-                    info!("Analyzing image colors");
-                    let (dominant_colors, suggested_flavor) = image_processing::analyze_image_colors(&rgba_img);
-                    progress_bar.set_message("📊 Generating color statistics...");
-                    let mut stats_message = format!("**Color Analysis Results**\n\n**Dominant Colors:**\n");
-                    for (i, (r, g, b, count)) in dominant_colors.iter().enumerate() {
-                        let hex = format!("{:02X}{:02X}{:02X}", r, g, b);
-                        let percentage = (*count as f32 / (width * height) as f32 * 100.0).round() as u32;
-                        stats_message.push_str(&format!("{}. `#{}` (RGB: {},{},{}) - {}%\n", i + 1, hex, r, g, b, percentage));
-                    }
-                    stats_message.push_str(&format!("\n**Suggested Flavor:** {}\n", suggested_flavor.to_string().to_uppercase()));
-                    stats_message.push_str("\n*Based on average brightness of dominant colors*");
-                    progress_bar.finish_with_message("✅ Color analysis completed");
-                    // Store the image and context for confirmation
-                    let mut buf = Vec::new();
-                    img.write_to(&mut buf, image::ImageFormat::Png).unwrap();
-                    {
-                        let mut map = COLOR_CONFIRM_MAP.lock().unwrap();
-                        map.insert((msg.author.id.0, msg.channel_id.0), (buf, image::ImageFormat::Png, width, height, suggested_flavor, selected_algorithm.to_string()));
-                    }
-                    // Send stats message with button
-                    let mut action_row = CreateActionRow::default();
-                    action_row.add_button(CreateButton::new("apply_suggested_flavor")
-                        .label(format!("Apply {}", suggested_flavor.to_string().to_uppercase()))
-                        .style(serenity::model::prelude::component::ButtonStyle::Primary));
-                    let builder = serenity::builder::CreateMessage::new()
-                        .content(stats_message)
-                        .components(vec![action_row]);
-                    let _ = msg.channel_id.send_message(&ctx.http, builder).await;
                     return;
-                }
-
-                if process_all_flavors {
-                    progress_bar.set_message("🎨 Processing image with all flavors...");
-                    info!("Processing image with all flavors");
-                    let flavors = [
-                        (utils::parse_flavor("latte").unwrap(), "latte"),
-                        (utils::parse_flavor("frappe").unwrap(), "frappe"),
-                        (utils::parse_flavor("macchiato").unwrap(), "macchiato"),
-                        (utils::parse_flavor("mocha").unwrap(), "mocha")
-                    ];
-                    let mut attachments = Vec::new();
-                    for (_i, (flavor, flavor_name)) in flavors.iter().enumerate() {
-                        progress_bar.set_message("🎨 Processing with flavor...");
-                        info!(flavor = %flavor_name, "Processing image with flavor");
-                        let mut flavor_img = rgba_img.clone();
-                        let lut = image_processing::generate_catppuccin_lut(*flavor, selected_algorithm);
-                        image_processing::apply_lut_to_image(&mut flavor_img, &lut);
+                } else if parts[1] == "simulate" {
+                    // --- COLOR BLINDNESS SIMULATION SUBCOMMAND ---
+                    // Usage: !cat simulate [type|all] [severity%] [flavor] [image]
+                    let show_all = parts.get(2).is_some_and(|s| s.eq_ignore_ascii_case("all"));
+                    let deficiency = parts.iter().skip(2).find_map(|s| utils::parse_deficiency(s)).unwrap_or(accessibility::Deficiency::Protanopia);
+                    let severity = parts.iter().skip(2).find_map(|s| utils::parse_severity(s));
+                    // Only the comparison grid is drawn in a flavor's colors
+                    let flavor = parts.iter().skip(2).find_map(|s| utils::parse_flavor(s)).unwrap_or(selected_flavor);
+                    let Some(image_url) = find_image_source(&ctx, &msg, &parts).await else {
+                        let _ = responses::warning(&ctx.http, msg.channel_id, "Please attach an image or provide a direct image URL to simulate color blindness.").await;
+                        return;
+                    };
+                    let _typing = msg.channel_id.start_typing(&ctx.http);
+                    let progress_bar = ProgressBar::new_spinner();
+                    progress_bar.set_style(
+                        ProgressStyle::default_spinner()
+                            .template("{spinner:.green} {wide_msg}")
+                            .unwrap()
+                    );
+                    progress_bar.set_message("👁️ Simulating color blindness...");
+                    progress_bar.enable_steady_tick(Duration::from_millis(100));
+                    let img = match fetch_image(fetcher, &image_url, autoscale).await {
+                        Ok(img) => img,
+                        Err(e) => {
+                            progress_bar.finish_with_message("❌ Failed to load image to simulate");
+                            let _ = responses::error(&ctx.http, msg.channel_id, e).await;
+                            return;
+                        }
+                    };
+                    let applied_severity = severity.unwrap_or_else(|| deficiency.default_severity());
+                    let simulate_result = jobs::run(Job::new("simulate", &msg), move || {
+                        let rgba_img = img.to_rgba8();
+                        let simulated = if show_all {
+                            accessibility::render_simulation_grid(&rgba_img, severity, flavor)
+                        } else {
+                            accessibility::simulate_image(&rgba_img, deficiency, applied_severity)
+                        };
                         let mut output_buffer = std::io::Cursor::new(Vec::new());
-                        let output_format = selected_format.unwrap_or(image::ImageFormat::Png);
-                        let dynamic_img = image::DynamicImage::ImageRgba8(flavor_img);
-                        if let Err(_e) = dynamic_img.write_to(&mut output_buffer, output_format) {
-                            error!(flavor = %flavor_name, "Failed to encode processed image");
-                            continue;
+                        simulated.write_to(&mut output_buffer, image::ImageFormat::Png).map(|_| output_buffer.into_inner())
+                    }).await;
+                    match simulate_result {
+                        Ok(Ok(png_bytes)) => {
+                            let (label, message_content) = if show_all {
+                                ("all".to_string(), "**Color Blindness Simulations** (original and every type)".to_string())
+                            } else {
+                                (deficiency.name().to_string(), format!("**Color Blindness Simulation: {}** ({:.0}% severity)", deficiency.name().to_uppercase(), applied_severity * 100.0))
+                            };
+                            let filename = crate::utils::sanitize_filename(&format!("simulated_{}.png", label), "png");
+                            let attachment_data = serenity::builder::CreateAttachment::bytes(png_bytes, filename);
+                            let message_builder = serenity::builder::CreateMessage::new().content(message_content);
+                            let _ = msg.channel_id.send_files(&ctx.http, vec![attachment_data], message_builder).await;
+                            progress_bar.finish_with_message("✅ Simulation sent!");
+                        }
+                        Err(panic) => {
+                            progress_bar.finish_with_message("❌ Failed to simulate color blindness");
+                            let _ = responses::error(&ctx.http, msg.channel_id, panic.to_string()).await;
+                        }
+                        _ => {
+                            progress_bar.finish_with_message("❌ Failed to simulate color blindness");
+                            let _ = responses::error(&ctx.http, msg.channel_id, "Failed to simulate color blindness. Please ensure your image is valid and accessible.").await;
                         }
-                        let filename = format!("catppuccinified_{}.{}", flavor_name, output_format.extensions_str().first().unwrap_or(&"png"));
-                        let attachment_data = serenity::builder::CreateAttachment::bytes(output_buffer.into_inner(), filename);
-                        attachments.push(attachment_data);
-                    }
-                    if !attachments.is_empty() {
-                        progress_bar.set_message("📤 Uploading all processed images...");
-                        info!(count = attachments.len(), "Uploading all processed images");
-                        let message_content = "Here are your Catppuccinified images with all flavors!";
-                        let message_builder = serenity::builder::CreateMessage::new().content(message_content);
-                        let _ = msg.channel_id.send_files(&ctx.http, attachments, message_builder).await;
-                        progress_bar.finish_with_message("✅ All flavors processed and uploaded successfully!");
-                    } else {
-                        progress_bar.finish_with_message("❌ Failed to process any flavors");
                     }
                     return;
-                }
-
-                // Single flavor processing
-                progress_bar.set_message("🎨 Processing with flavor and algorithm...");
-                info!(flavor = ?selected_flavor, "Processing image with selected flavor");
-                let lut = image_processing::generate_catppuccin_lut(selected_flavor, selected_algorithm);
-                image_processing::apply_lut_to_image(&mut rgba_img, &lut);
-
-                // Handle comparison mode
-                if show_comparison {
-                    progress_bar.set_message("🔄 Creating before/after comparison image...");
-                    info!("Creating before/after comparison image");
-                    let original_img = img.to_rgba8();
-                    let comparison_img = image_processing::create_comparison_image(&original_img, &rgba_img);
-                    let mut output_buffer = std::io::Cursor::new(Vec::new());
-                    let output_format = selected_format.unwrap_or(image::ImageFormat::Png);
-                    if let Err(_e) = comparison_img.write_to(&mut output_buffer, output_format) {
-                        progress_bar.finish_with_message("❌ Failed to create comparison image");
-                        error!("Failed to create comparison image");
-                        let _ = msg.channel_id.say(&ctx.http, "Failed to create comparison image.").await;
+                } else if parts[1] == "daltonize" {
+                    // --- DALTONIZE (COLOR-BLIND CORRECTION) SUBCOMMAND ---
+                    // Usage: !cat daltonize [type] [severity%] [image]
+                    let deficiency = parts.iter().skip(2).find_map(|s| utils::parse_deficiency(s)).unwrap_or(accessibility::Deficiency::Deuteranopia);
+                    if deficiency == accessibility::Deficiency::Achromatopsia {
+                        let _ = responses::warning(&ctx.http, msg.channel_id, "Achromatopsia leaves no color channel to move detail into, so it can't be daltonized. Try `!cat simulate achromatopsia` instead.").await;
                         return;
                     }
-                    let filename = format!("comparison_{}.{}", selected_flavor.to_string().to_lowercase(), output_format.extensions_str().first().unwrap_or(&"png"));
-                    let attachment_data = serenity::builder::CreateAttachment::bytes(output_buffer.into_inner(), filename);
-                    let message_content = format!("**Before/After Comparison**\nLeft: Original | Right: {} flavor", selected_flavor.to_string().to_uppercase());
-                    let message_builder = serenity::builder::CreateMessage::new().content(message_content);
-                    progress_bar.set_message("📤 Uploading comparison image...");
-                    info!("Uploading comparison image");
-                    let _ = msg.channel_id.send_files(&ctx.http, vec![attachment_data], message_builder).await;
-                    progress_bar.finish_with_message("✅ Comparison image uploaded successfully!");
-                    return;
-                }
-
-                // Save the processed image to a buffer
-                progress_bar.set_message("💾 Encoding processed image...");
-                let mut output_buffer = std::io::Cursor::new(Vec::new());
-                let output_format = selected_format.unwrap_or(image::ImageFormat::Png);
-                let dynamic_img = image::DynamicImage::ImageRgba8(rgba_img);
-                if let Err(_e) = dynamic_img.write_to(&mut output_buffer, output_format) {
-                    progress_bar.finish_with_message("❌ Failed to encode the processed image");
-                    error!("Failed to encode the processed image");
-                    let _ = msg.channel_id.say(&ctx.http, "Failed to encode the processed image.").await;
+                    let severity = parts.iter().skip(2).find_map(|s| utils::parse_severity(s)).unwrap_or_else(|| deficiency.default_severity());
+                    let Some(image_url) = find_image_source(&ctx, &msg, &parts).await else {
+                        let _ = responses::warning(&ctx.http, msg.channel_id, "Please attach an image or provide a direct image URL to daltonize.").await;
+                        return;
+                    };
+                    let _typing = msg.channel_id.start_typing(&ctx.http);
+                    let progress_bar = ProgressBar::new_spinner();
+                    progress_bar.set_style(
+                        ProgressStyle::default_spinner()
+                            .template("{spinner:.green} {wide_msg}")
+                            .unwrap()
+                    );
+                    progress_bar.set_message("👁️ Daltonizing...");
+                    progress_bar.enable_steady_tick(Duration::from_millis(100));
+                    let img = match fetch_image(fetcher, &image_url, autoscale).await {
+                        Ok(img) => img,
+                        Err(e) => {
+                            progress_bar.finish_with_message("❌ Failed to load image to daltonize");
+                            let _ = responses::error(&ctx.http, msg.channel_id, e).await;
+                            return;
+                        }
+                    };
+                    let daltonize_result = jobs::run(Job::new("daltonize", &msg), move || {
+                        let corrected = accessibility::daltonize_image(&img.to_rgba8(), deficiency, severity);
+                        let mut output_buffer = std::io::Cursor::new(Vec::new());
+                        corrected.write_to(&mut output_buffer, image::ImageFormat::Png).map(|_| output_buffer.into_inner())
+                    }).await;
+                    match daltonize_result {
+                        Ok(Ok(png_bytes)) => {
+                            let filename = crate::utils::sanitize_filename(&format!("daltonized_{}.png", deficiency.name()), "png");
+                            let attachment_data = serenity::builder::CreateAttachment::bytes(png_bytes, filename);
+                            let message_content = format!(
+                                "**Daltonized for {}** ({:.0}% severity)\nColors the deficiency would merge are pushed apart; check with `!cat simulate {}`.",
+                                deficiency.name().to_uppercase(), severity * 100.0, deficiency.name()
+                            );
+                            let message_builder = serenity::builder::CreateMessage::new().content(message_content);
+                            let _ = msg.channel_id.send_files(&ctx.http, vec![attachment_data], message_builder).await;
+                            progress_bar.finish_with_message("✅ Daltonized image sent!");
+                        }
+                        Err(panic) => {
+                            progress_bar.finish_with_message("❌ Failed to daltonize image");
+                            let _ = responses::error(&ctx.http, msg.channel_id, panic.to_string()).await;
+                        }
+                        _ => {
+                            progress_bar.finish_with_message("❌ Failed to daltonize image");
+                            let _ = responses::error(&ctx.http, msg.channel_id, "Failed to daltonize image.").await;
+                        }
+                    }
+                    return;
+                } else if parts[1] == "temperature" && parts.get(2).is_some_and(|s| s.eq_ignore_ascii_case("warm") || s.eq_ignore_ascii_case("cool")) {
+                    // --- COLOR TEMPERATURE ADJUSTMENT SUBCOMMAND ---
+                    // Usage: !cat temperature warm|cool [amount] [flavor] [algorithm] [image]
+                    // With a flavor the shifted image is then catppuccinified; without one only the shift is applied
+                    let warmer = parts[2].eq_ignore_ascii_case("warm");
+                    let amount = parts.iter().skip(3).find_map(|s| s.parse::<f32>().ok()).unwrap_or(20.0).clamp(1.0, 100.0);
+                    let flavor = parts.iter().skip(3).find_map(|s| utils::parse_flavor(s));
+                    let algorithm = parts.iter().skip(3).find_map(|s| utils::parse_algorithm(s)).unwrap_or(selected_algorithm);
+                    let Some(image_url) = find_image_source(&ctx, &msg, &parts).await else {
+                        let _ = responses::warning(&ctx.http, msg.channel_id, "Please attach an image or provide a direct image URL to adjust its color temperature.").await;
+                        return;
+                    };
+                    let _typing = msg.channel_id.start_typing(&ctx.http);
+                    let progress_bar = ProgressBar::new_spinner();
+                    progress_bar.set_style(
+                        ProgressStyle::default_spinner()
+                            .template("{spinner:.green} {wide_msg}")
+                            .unwrap()
+                    );
+                    progress_bar.set_message("🌡️ Adjusting color temperature...");
+                    progress_bar.enable_steady_tick(Duration::from_millis(100));
+                    let img = match fetch_image(fetcher, &image_url, autoscale).await {
+                        Ok(img) => img,
+                        Err(e) => {
+                            progress_bar.finish_with_message("❌ Failed to load image to adjust");
+                            let _ = responses::error(&ctx.http, msg.channel_id, e).await;
+                            return;
+                        }
+                    };
+                    let adjust = image_processing::PreAdjust { temperature: if warmer { amount } else { -amount }, ..pre_adjust };
+                    let temperature_post_effects = post_effects.clone();
+                    let temperature_result = jobs::run(Job::new("temperature", &msg), move || {
+                        let adjusted = match flavor {
+                            Some(flavor) => image_processing::process_image_with_progress(&img, flavor, algorithm, &adjust, None).to_rgba8(),
+                            None => {
+                                let mut rgba_img = img.to_rgba8();
+                                image_processing::apply_pre_adjustments(&mut rgba_img, &adjust, selected_flavor);
+                                rgba_img
+                            }
+                        };
+                        let finished = image_processing::apply_post_effects(adjusted, &temperature_post_effects, flavor.unwrap_or(selected_flavor));
+                        let mut output_buffer = std::io::Cursor::new(Vec::new());
+                        finished.write_to(&mut output_buffer, image::ImageFormat::Png).map(|_| output_buffer.into_inner())
+                    }).await;
+                    match temperature_result {
+                        Ok(Ok(png_bytes)) => {
+                            let direction = if warmer { "warm" } else { "cool" };
+                            let filename = crate::utils::sanitize_filename(&format!("temperature_{}_{}.png", direction, amount), "png");
+                            let attachment_data = serenity::builder::CreateAttachment::bytes(png_bytes, filename);
+                            let message_content = match flavor {
+                                Some(flavor) => format!("**Color Temperature**: {} {} then mapped to {} ({})", direction, amount, flavor.to_string().to_uppercase(), algorithm),
+                                None => format!("**Color Temperature**: {} {}", direction, amount),
+                            };
+                            let message_builder = serenity::builder::CreateMessage::new().content(message_content);
+                            let _ = msg.channel_id.send_files(&ctx.http, vec![attachment_data], message_builder).await;
+                            progress_bar.finish_with_message("✅ Temperature adjusted!");
+                        }
+                        Err(panic) => {
+                            progress_bar.finish_with_message("❌ Failed to adjust color temperature");
+                            let _ = responses::error(&ctx.http, msg.channel_id, panic.to_string()).await;
+                        }
+                        _ => {
+                            progress_bar.finish_with_message("❌ Failed to adjust color temperature");
+                            let _ = responses::error(&ctx.http, msg.channel_id, "Failed to adjust color temperature.").await;
+                        }
+                    }
+                    return;
+                } else if parts[1] == "temperature" {
+                    // --- COLOR TEMPERATURE ANALYSIS SUBCOMMAND ---
+                    // These decode a single still, so videos contribute their poster frame
+                    let image_url = find_image_source(&ctx, &msg, &parts).await.map(|s| s.still_url().to_string());
+                    if let Some(image_url) = image_url {
+                        let _typing = msg.channel_id.start_typing(&ctx.http);
+                        let progress_bar = ProgressBar::new_spinner();
+                        progress_bar.set_style(
+                            ProgressStyle::default_spinner()
+                                .template("{spinner:.green} {wide_msg}")
+                                .unwrap()
+                        );
+                        progress_bar.set_message("🌡️ Analyzing color temperature...");
+                        progress_bar.enable_steady_tick(Duration::from_millis(100));
+                        if let Ok(image_bytes) = source::fetch_url(&image_url).await {
+                            let img_reader = ImageReader::new(std::io::Cursor::new(&image_bytes)).with_guessed_format();
+                            if let Ok(reader) = img_reader {
+                                if let Ok(img) = reader.decode() {
+                                    let rgba_img = img.to_rgba8();
+                                    let mut warm = 0u64;
+                                    let mut cool = 0u64;
+                                    let mut total = 0u64;
+                                    for pixel in rgba_img.pixels() {
+                                        let (r, g, b, _a) = (pixel[0], pixel[1], pixel[2], pixel[3]);
+                                        let (h, _s, _l) = rgb_to_hsl(r, g, b);
+                                        if (0.0..=90.0).contains(&h) || (330.0..=360.0).contains(&h) {
+                                            warm += 1;
+                                        } else {
+                                            cool += 1;
+                                        }
+                                        total += 1;
+                                    }
+                                    let warm_pct = (warm as f64 / total as f64) * 100.0;
+                                    let cool_pct = (cool as f64 / total as f64) * 100.0;
+                                    let message_content = format!(
+                                        "**Color Temperature Analysis**\nWarm colors: {:.1}%\nCool colors: {:.1}%\n(>50% warm = warm image, >50% cool = cool image)",
+                                        warm_pct, cool_pct
+                                    );
+                                    let _ = responses::info(&ctx.http, msg.channel_id, message_content).await;
+                                    progress_bar.finish_with_message("✅ Color temperature analyzed!");
+                                    return;
+                                }
+                            }
+                        }
+                        progress_bar.finish_with_message("❌ Failed to analyze color temperature");
+                        let _ = responses::error(&ctx.http, msg.channel_id, "Failed to analyze color temperature. Please ensure your image is valid and accessible.").await;
+                        return;
+                    } else {
+                        let _ = responses::warning(&ctx.http, msg.channel_id, "Please attach an image or provide a direct image URL to analyze color temperature.").await;
+                        return;
+                    }
+                } else if parts[1] == "scheme" {
+                    // --- COLOR SCHEME SUBCOMMAND ---
+                    // Usage: !cat scheme [type] [color|#hex] [flavor] [json|css|gpl] [image]
+                    // Built from the given color, or the image's dominant color, and snapped to the flavor;
+                    // a file format also attaches the snapped colors as a palette file
+                    let scheme_type = parts.iter().skip(2).map(|s| s.to_lowercase()).find(|s| palette::SCHEME_TYPES.contains(&s.as_str())).unwrap_or("complementary".to_string());
+                    let flavor = parts.iter().skip(2).find_map(|s| utils::parse_flavor(s)).unwrap_or(selected_flavor);
+                    let base_color = parts.iter().skip(2).find_map(|s| utils::parse_color(s, flavor));
+                    let export_format = parts.iter().skip(2).find_map(|s| utils::parse_palette_format(s));
+                    let _typing = msg.channel_id.start_typing(&ctx.http);
+                    let progress_bar = ProgressBar::new_spinner();
+                    progress_bar.set_style(
+                        ProgressStyle::default_spinner()
+                            .template("{spinner:.green} {wide_msg}")
+                            .unwrap()
+                    );
+                    progress_bar.set_message("🎨 Building color scheme...");
+                    progress_bar.enable_steady_tick(Duration::from_millis(100));
+                    let img = match base_color {
+                        Some(_) => None,
+                        None => {
+                            let Some(image_url) = find_image_source(&ctx, &msg, &parts).await else {
+                                progress_bar.finish_and_clear();
+                                let _ = responses::warning(&ctx.http, msg.channel_id, "Please give a base color (e.g. `!cat scheme triadic mauve` or `!cat scheme analogous #89b4fa`), or attach an image to use its dominant color.").await;
+                                return;
+                            };
+                            match fetch_image(fetcher, &image_url, autoscale).await {
+                                Ok(img) => Some(img),
+                                Err(e) => {
+                                    progress_bar.finish_with_message("❌ Failed to load image for color scheme");
+                                    let _ = responses::error(&ctx.http, msg.channel_id, e).await;
+                                    return;
+                                }
+                            }
+                        }
+                    };
+                    let job_scheme_type = scheme_type.clone();
+                    let scheme_result = jobs::run(Job::new("scheme", &msg), move || {
+                        let base = base_color.or_else(|| img.and_then(|img| palette::dominant_color(&img.to_rgba8())))?;
+                        let scheme_colors = palette::generate_scheme(base, &job_scheme_type);
+                        let (swatches, snapped) = palette::render_snapped_swatches(&scheme_colors, flavor);
+                        let mut output_buffer = std::io::Cursor::new(Vec::new());
+                        swatches.write_to(&mut output_buffer, image::ImageFormat::Png).ok()?;
+                        let palette_file = export_format.map(|format| {
+                            let title = format!("Catppuccin {} {} scheme", flavor, job_scheme_type);
+                            (export::write_palette(&title, &snapped, format), format.extension())
+                        });
+                        Some((output_buffer.into_inner(), base, scheme_colors, snapped, palette_file))
+                    }).await;
+                    match scheme_result {
+                        Ok(Some((png_bytes, base, scheme_colors, snapped, palette_file))) => {
+                            let lines: Vec<String> = scheme_colors.iter().zip(&snapped).map(|((r, g, b), (name, (pr, pg, pb)))| {
+                                format!("`#{:02X}{:02X}{:02X}` → {} `#{:02X}{:02X}{:02X}`", r, g, b, name, pr, pg, pb)
+                            }).collect();
+                            let message_content = format!(
+                                "**{} Color Scheme** from `#{:02X}{:02X}{:02X}`, snapped to {}\n{}",
+                                scheme_type.to_uppercase(), base.0, base.1, base.2, flavor.to_string().to_uppercase(), lines.join("\n")
+                            );
+                            let filename = crate::utils::sanitize_filename(&format!("color_scheme_{}.png", scheme_type), "png");
+                            let mut attachments = vec![serenity::builder::CreateAttachment::bytes(png_bytes, filename)];
+                            if let Some((file_bytes, extension)) = palette_file {
+                                let filename = crate::utils::sanitize_filename(&format!("catppuccin_{}_{}.{}", flavor.identifier(), scheme_type, extension), extension);
+                                attachments.push(serenity::builder::CreateAttachment::bytes(file_bytes, filename));
+                            }
+                            let message_builder = serenity::builder::CreateMessage::new().content(message_content);
+                            let _ = msg.channel_id.send_files(&ctx.http, attachments, message_builder).await;
+                            progress_bar.finish_with_message("✅ Color scheme sent!");
+                        }
+                        Err(panic) => {
+                            progress_bar.finish_with_message("❌ Failed to build color scheme");
+                            let _ = responses::error(&ctx.http, msg.channel_id, panic.to_string()).await;
+                        }
+                        _ => {
+                            progress_bar.finish_with_message("❌ Failed to build color scheme");
+                            let _ = responses::error(&ctx.http, msg.channel_id, "Failed to build a color scheme. Please ensure your image is valid and accessible.").await;
+                        }
+                    }
+                    return;
+                } else if parts[1] == "convert" {
+                    // --- BULK COLOR CONVERSION SUBCOMMAND ---
+                    // Usage: !cat convert [flavor] <colors...>, or attach a text or JSON file of colors
+                    let flavor = parts.iter().skip(2).find_map(|s| utils::parse_flavor(s)).unwrap_or(selected_flavor);
+                    let color_args: Vec<&str> = parts.iter().skip(2).copied().filter(|s| utils::parse_flavor(s).is_none()).collect();
+                    let list_attachment = msg.attachments.iter().find(|a| {
+                        let filename = a.filename.to_lowercase();
+                        filename.ends_with(".txt") || filename.ends_with(".json")
+                    });
+                    let mut colors = Vec::new();
+                    let mut skipped = Vec::new();
+                    for arg in &color_args {
+                        match utils::parse_color_literal(arg) {
+                            Some(rgb) if !colors.iter().any(|(_, seen)| *seen == rgb) => colors.push((arg.to_string(), rgb)),
+                            Some(_) => {}
+                            None => skipped.push(*arg),
+                        }
+                    }
+                    if let Some(attachment) = list_attachment {
+                        let bytes = match source::fetch_url(&attachment.url).await {
+                            Ok(bytes) => bytes,
+                            Err(_) => {
+                                let _ = responses::error(&ctx.http, msg.channel_id, "Failed to download the color list.").await;
+                                return;
+                            }
+                        };
+                        for (input, rgb) in convert::colors_in_text(&String::from_utf8_lossy(&bytes)) {
+                            if !colors.iter().any(|(_, seen)| *seen == rgb) {
+                                colors.push((input, rgb));
+                            }
+                        }
+                    }
+                    if colors.is_empty() {
+                        let _ = responses::warning(&ctx.http, msg.channel_id, "Please give some colors to convert, or attach a `.txt` or `.json` file of them. Example: `!cat convert mocha #ff0000 #00ff00 rgb(17, 34, 51)`").await;
+                        return;
+                    }
+                    let total = colors.len();
+                    colors.truncate(convert::MAX_COLORS);
+                    let conversions = convert::convert_colors(&colors, flavor);
+                    let table = convert::render_conversion_table(&conversions, flavor);
+                    let mut output_buffer = std::io::Cursor::new(Vec::new());
+                    if table.write_to(&mut output_buffer, image::ImageFormat::Png).is_err() {
+                        let _ = responses::error(&ctx.http, msg.channel_id, "Failed to generate the conversion table.").await;
+                        return;
+                    }
+                    let mut message_content = format!("**Converted {} colors to {}**", conversions.len(), flavor.to_string().to_uppercase());
+                    if total > convert::MAX_COLORS {
+                        message_content.push_str(&format!("\nOnly the first {} of {} colors were converted.", convert::MAX_COLORS, total));
+                    }
+                    if !skipped.is_empty() {
+                        message_content.push_str(&format!("\nSkipped (not colors): `{}`", skipped.join("` `")));
+                    }
+                    let attachments = vec![
+                        serenity::builder::CreateAttachment::bytes(output_buffer.into_inner(), crate::utils::sanitize_filename(&format!("catppuccin_{}_conversion.png", flavor.identifier()), "png")),
+                        serenity::builder::CreateAttachment::bytes(convert::mappings_json(&conversions, flavor), crate::utils::sanitize_filename(&format!("catppuccin_{}_conversion.json", flavor.identifier()), "json")),
+                    ];
+                    let message_builder = serenity::builder::CreateMessage::new().content(message_content);
+                    let _ = msg.channel_id.send_files(&ctx.http, attachments, message_builder).await;
+                    return;
+                } else if parts[1] == "audit" {
+                    // --- COLOR AUDIT SUBCOMMAND ---
+                    // Usage: !cat audit [flavor] [message link], or reply to the message to audit
+                    let linked = parts.iter().skip(2).find_map(|s| source::parse_message_link(s));
+                    let target = match (linked, &msg.referenced_message) {
+                        (Some((channel_id, message_id)), _) => match channel_id.message(&ctx.http, message_id).await {
+                            Ok(linked_message) => linked_message,
+                            Err(_) => {
+                                let _ = responses::error(&ctx.http, msg.channel_id, "Couldn't fetch that message. Is it in a channel I can see?").await;
+                                return;
+                            }
+                        },
+                        (None, Some(replied)) => (**replied).clone(),
+                        (None, None) => {
+                            let _ = responses::warning(&ctx.http, msg.channel_id, "Reply to a message or give a message link to audit its colors. Example: `!cat audit https://discord.com/channels/1/2/3`").await;
+                            return;
+                        }
+                    };
+                    let _typing = msg.channel_id.start_typing(&ctx.http);
+                    let mut colors = audit::embed_colors(&target.embeds);
+                    for image_source in source::message_sources(&target).iter().take(audit::MAX_IMAGES) {
+                        if let Ok(img) = fetch_image(fetcher, image_source, autoscale).await {
+                            colors.extend(audit::image_colors(&image_source.filename, &img.to_rgba8()));
+                        }
+                    }
+                    if colors.is_empty() {
+                        let _ = responses::info(&ctx.http, msg.channel_id, "That message has no embed colors or images to audit.").await;
+                        return;
+                    }
+                    let flavor = parts.iter().skip(2).find_map(|s| utils::parse_flavor(s)).unwrap_or_else(|| audit::best_flavor(&colors));
+                    let conversions = convert::convert_colors(&colors, flavor);
+                    let compliant = audit::compliant_count(&conversions);
+                    let report = format!(
+                        "**{} of {} colors are Catppuccin {}** ({}%)\n{}",
+                        compliant,
+                        conversions.len(),
+                        flavor,
+                        compliant * 100 / conversions.len().max(1),
+                        audit::report_lines(&conversions).join("\n"),
+                    );
+                    let table = convert::render_conversion_table(&conversions, flavor);
+                    let mut output_buffer = std::io::Cursor::new(Vec::new());
+                    if table.write_to(&mut output_buffer, image::ImageFormat::Png).is_err() {
+                        let _ = responses::error(&ctx.http, msg.channel_id, "Failed to generate the audit table.").await;
+                        return;
+                    }
+                    let filename = format!("catppuccin_{}_audit.png", flavor.identifier());
+                    let tone = if compliant == conversions.len() { responses::Tone::Success } else { responses::Tone::Warning };
+                    let embed = responses::embed(tone, report).image(format!("attachment://{}", filename));
+                    let attachment_data = serenity::builder::CreateAttachment::bytes(output_buffer.into_inner(), filename);
+                    let message_builder = serenity::builder::CreateMessage::new().embed(embed);
+                    let _ = msg.channel_id.send_files(&ctx.http, vec![attachment_data], message_builder).await;
+                    return;
+                } else if parts[1] == "convertfile" {
+                    // --- THEME FILE CONVERSION SUBCOMMAND ---
+                    // Usage: !cat convertfile [flavor] with a CSS file or tailwind.config.js attached
+                    let flavor = parts.iter().skip(2).find_map(|s| utils::parse_flavor(s)).unwrap_or(selected_flavor);
+                    let theme_attachment = msg.attachments.iter().find(|a| {
+                        let extension = a.filename.rsplit('.').next().unwrap_or("").to_lowercase();
+                        theme_file::EXTENSIONS.contains(&extension.as_str())
+                    });
+                    let Some(attachment) = theme_attachment else {
+                        let _ = responses::warning(&ctx.http, msg.channel_id, format!("Please attach a theme file to convert ({}). Example: `!cat convertfile mocha` with `tailwind.config.js` attached", theme_file::EXTENSIONS.map(|e| format!("`.{}`", e)).join(", "))).await;
+                        return;
+                    };
+                    if attachment.size > theme_file::MAX_FILE_BYTES {
+                        let _ = responses::warning(&ctx.http, msg.channel_id, format!("That file is {}; theme files can be up to {}.", utils::format_file_size(attachment.size as usize), utils::format_file_size(theme_file::MAX_FILE_BYTES as usize))).await;
+                        return;
+                    }
+                    let bytes = match source::fetch_url(&attachment.url).await {
+                        Ok(bytes) => bytes,
+                        Err(_) => {
+                            let _ = responses::error(&ctx.http, msg.channel_id, "Failed to download the theme file.").await;
+                            return;
+                        }
+                    };
+                    let Ok(text) = String::from_utf8(bytes) else {
+                        let _ = responses::warning(&ctx.http, msg.channel_id, "That file isn't UTF-8 text.").await;
+                        return;
+                    };
+                    let rewritten = theme_file::rewrite_theme(&text, flavor);
+                    if rewritten.conversions.is_empty() {
+                        let _ = responses::info(&ctx.http, msg.channel_id, "No hex, `rgb()` or `hsl()` colors found in that file.").await;
+                        return;
+                    }
+                    // The first few mappings keep the report within one message
+                    let lines: Vec<String> = rewritten.conversions.iter().take(15).map(|c| {
+                        format!("`{}` → {} `#{:02X}{:02X}{:02X}` (ΔE {:.1})", c.input, c.name, c.matched.0, c.matched.1, c.matched.2, c.delta_e())
+                    }).collect();
+                    let more = if rewritten.conversions.len() > 15 { "\n…" } else { "" };
+                    let message_content = format!(
+                        "**Converted `{}` to {}** ({} colors, {} replacements)\n{}{}",
+                        attachment.filename, flavor.to_string().to_uppercase(), rewritten.conversions.len(), rewritten.replaced, lines.join("\n"), more
+                    );
+                    let (stem, extension) = attachment.filename.rsplit_once('.').unwrap_or((&attachment.filename, "txt"));
+                    let attachments = vec![
+                        serenity::builder::CreateAttachment::bytes(rewritten.text.into_bytes(), crate::utils::sanitize_filename(&format!("{}-catppuccin-{}.{}", stem, flavor.identifier(), extension), extension)),
+                        serenity::builder::CreateAttachment::bytes(convert::mappings_json(&rewritten.conversions, flavor), crate::utils::sanitize_filename(&format!("{}-catppuccin-{}-mapping.json", stem, flavor.identifier()), "json")),
+                    ];
+                    let message_builder = serenity::builder::CreateMessage::new().content(message_content);
+                    let _ = msg.channel_id.send_files(&ctx.http, attachments, message_builder).await;
+                    return;
+                } else if parts[1] == "match" {
+                    // --- PALETTE IMAGE MATCH SUBCOMMAND ---
+                    // Usage: !cat match [flavor] [palette image]
+                    let flavor = parts.iter().skip(2).find_map(|s| utils::parse_flavor(s)).unwrap_or(selected_flavor);
+                    let Some(image_url) = find_image_source(&ctx, &msg, &parts).await else {
+                        let _ = responses::warning(&ctx.http, msg.channel_id, "Please attach a palette screenshot or provide a direct image URL to match against the palette.").await;
+                        return;
+                    };
+                    let _typing = msg.channel_id.start_typing(&ctx.http);
+                    let progress_bar = ProgressBar::new_spinner();
+                    progress_bar.set_style(
+                        ProgressStyle::default_spinner()
+                            .template("{spinner:.green} {wide_msg}")
+                            .unwrap()
+                    );
+                    progress_bar.set_message("🎯 Matching palette swatches...");
+                    progress_bar.enable_steady_tick(Duration::from_millis(100));
+                    let img = match fetch_image(fetcher, &image_url, autoscale).await {
+                        Ok(img) => img,
+                        Err(e) => {
+                            progress_bar.finish_with_message("❌ Failed to load palette image");
+                            let _ = responses::error(&ctx.http, msg.channel_id, e).await;
+                            return;
+                        }
+                    };
+                    let match_result = jobs::run(Job::new("match", &msg), move || {
+                        let swatches = palette::detect_swatches(&img.to_rgba8());
+                        if swatches.is_empty() {
+                            return Err("No flat color swatches found. `!cat match` works on palette screenshots with solid blocks of color.".to_string());
+                        }
+                        let (strip, snapped) = palette::render_snapped_swatches(&swatches, flavor);
+                        let mut output_buffer = std::io::Cursor::new(Vec::new());
+                        strip.write_to(&mut output_buffer, image::ImageFormat::Png).map_err(|_| "Failed to encode the swatch strip.".to_string())?;
+                        Ok((output_buffer.into_inner(), swatches, snapped))
+                    }).await;
+                    match match_result {
+                        Ok(Ok((png_bytes, swatches, snapped))) => {
+                            let lines: Vec<String> = swatches.iter().zip(&snapped).map(|(&(r, g, b), &(name, palette_color))| {
+                                format!(
+                                    "`#{:02X}{:02X}{:02X}` → {} `#{:02X}{:02X}{:02X}` (ΔE {:.1})",
+                                    r, g, b, name, palette_color.0, palette_color.1, palette_color.2, image_processing::delta_e((r, g, b), palette_color)
+                                )
+                            }).collect();
+                            let message_content = format!("**Palette Match** ({} swatches → {})\n{}", swatches.len(), flavor.to_string().to_uppercase(), lines.join("\n"));
+                            let filename = crate::utils::sanitize_filename(&format!("palette_match_{}.png", flavor.identifier()), "png");
+                            let attachment_data = serenity::builder::CreateAttachment::bytes(png_bytes, filename);
+                            let message_builder = serenity::builder::CreateMessage::new().content(message_content);
+                            let _ = msg.channel_id.send_files(&ctx.http, vec![attachment_data], message_builder).await;
+                            progress_bar.finish_with_message("✅ Palette match sent!");
+                        }
+                        Ok(Err(e)) => {
+                            progress_bar.finish_with_message("❌ No swatches to match");
+                            let _ = responses::warning(&ctx.http, msg.channel_id, e).await;
+                        }
+                        Err(panic) => {
+                            progress_bar.finish_with_message("❌ Failed to match palette");
+                            let _ = responses::error(&ctx.http, msg.channel_id, panic.to_string()).await;
+                        }
+                    }
+                    return;
+                } else if parts[1] == "animate" {
+                    // --- ANIMATION EFFECT SUBCOMMAND ---
+                    // Usage: !cat animate [effect] [image]
+                    let effect = parts.get(2).map(|s| s.to_lowercase()).unwrap_or("fade".to_string());
+                    let valid_effects = ["fade"];
+                    if !valid_effects.contains(&effect.as_str()) {
+                        let _ = responses::warning(&ctx.http, msg.channel_id, "Please specify a valid animation effect: fade.").await;
+                        return;
+                    }
+                    // These decode a single still, so videos contribute their poster frame
+                    let image_url = find_image_source(&ctx, &msg, &parts).await.map(|s| s.still_url().to_string());
+                    if let Some(image_url) = image_url {
+                        let _typing = msg.channel_id.start_typing(&ctx.http);
+                        let progress_bar = ProgressBar::new_spinner();
+                        progress_bar.set_style(
+                            ProgressStyle::default_spinner()
+                                .template("{spinner:.green} {wide_msg}")
+                                .unwrap()
+                        );
+                        progress_bar.set_message("🎬 Generating animation effect...");
+                        progress_bar.enable_steady_tick(Duration::from_millis(100));
+                        if let Ok(image_bytes) = source::fetch_url(&image_url).await {
+                            let img_reader = ImageReader::new(std::io::Cursor::new(&image_bytes)).with_guessed_format();
+                            if let Ok(reader) = img_reader {
+                                if let Ok(img) = reader.decode() {
+                                    let rgba_img = img.to_rgba8();
+                                    match image_processing::animate_image_effect(&rgba_img, &effect) {
+                                        Ok(gif_bytes) => {
+                                            let filename = crate::utils::sanitize_filename(&format!("animation_{}.gif", effect), "gif");
+                                            let attachment_data = serenity::builder::CreateAttachment::bytes(gif_bytes, filename);
+                                            let message_content = format!("**Animation Effect: {}**", effect);
+                                    let message_builder = serenity::builder::CreateMessage::new().content(message_content);
+                                    let _ = msg.channel_id.send_files(&ctx.http, vec![attachment_data], message_builder).await;
+                                            progress_bar.finish_with_message("✅ Animation sent!");
+                                    return;
+                                }
+                                        Err(e) => {
+                                            progress_bar.finish_with_message("❌ Failed to generate animation");
+                                            let _ = responses::error(&ctx.http, msg.channel_id, &format!("Failed to generate animation: {}", e)).await;
+                                            return;
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                        progress_bar.finish_with_message("❌ Failed to generate animation");
+                        let _ = responses::error(&ctx.http, msg.channel_id, "Failed to generate animation. Please ensure your image is valid and accessible.").await;
+                        return;
+                    } else {
+                        let _ = responses::warning(&ctx.http, msg.channel_id, "Please attach an image or provide a direct image URL to animate.").await;
+                        return;
+                    }
+                } else if parts[1] == "effect" {
+                    // --- IMAGE EFFECT SUBCOMMAND ---
+                    // Usage: !cat effect <name> [flavor] [image]
+                    let effect_list = image_processing::EFFECTS.iter().map(|e| format!("`{}` - {}", e.name, e.description)).collect::<Vec<_>>().join("\n");
+                    let Some(effect) = parts.get(2).and_then(|s| image_processing::find_effect(s)) else {
+                        let _ = responses::warning(&ctx.http, msg.channel_id, format!("Please specify a valid effect:\n{}", effect_list)).await;
+                        return;
+                    };
+                    let flavor = parts.iter().skip(3).find_map(|s| utils::parse_flavor(s)).unwrap_or(catppuccin::FlavorName::Latte);
+                    let Some(image_url) = find_image_source(&ctx, &msg, &parts).await else {
+                        let _ = responses::warning(&ctx.http, msg.channel_id, "Please attach an image or provide a direct image URL to apply an effect.").await;
+                        return;
+                    };
+                    let _typing = msg.channel_id.start_typing(&ctx.http);
+                    let progress_bar = ProgressBar::new_spinner();
+                    progress_bar.set_style(
+                        ProgressStyle::default_spinner()
+                            .template("{spinner:.green} {wide_msg}")
+                            .unwrap()
+                    );
+                    progress_bar.set_message(format!("✨ Applying {} effect...", effect.name));
+                    progress_bar.enable_steady_tick(Duration::from_millis(100));
+                    let img = match fetch_image(fetcher, &image_url, autoscale).await {
+                        Ok(img) => img,
+                        Err(e) => {
+                            progress_bar.finish_with_message("❌ Failed to load image for effect");
+                            let _ = responses::error(&ctx.http, msg.channel_id, e).await;
+                            return;
+                        }
+                    };
+                    let effect_post_effects = post_effects.clone();
+                    let effect_result = jobs::run(Job::new("effect", &msg), move || {
+                        let processed = image_processing::process_image_with_palette(&img, flavor, "shepards-method").to_rgba8();
+                        let affected = (effect.apply)(&processed, flavor);
+                        let finished = image_processing::apply_post_effects(affected, &effect_post_effects, flavor);
+                        let mut output_buffer = std::io::Cursor::new(Vec::new());
+                        finished.write_to(&mut output_buffer, image::ImageFormat::Png).map(|_| output_buffer.into_inner())
+                    }).await;
+                    match effect_result {
+                        Ok(Ok(png_bytes)) => {
+                            let filename = crate::utils::sanitize_filename(&format!("catppuccin_{}_{}.png", effect.name, flavor.to_string().to_lowercase()), "png");
+                            let attachment_data = serenity::builder::CreateAttachment::bytes(png_bytes, filename);
+                            let message_content = format!("**Catppuccin Effect: {}** (Flavor: {})", effect.name, flavor.to_string().to_uppercase());
+                            let message_builder = serenity::builder::CreateMessage::new().content(message_content);
+                            let _ = msg.channel_id.send_files(&ctx.http, vec![attachment_data], message_builder).await;
+                            progress_bar.finish_with_message("✅ Effect image sent!");
+                        }
+                        Err(panic) => {
+                            progress_bar.finish_with_message("❌ Failed to apply effect");
+                            let _ = responses::error(&ctx.http, msg.channel_id, panic.to_string()).await;
+                        }
+                        _ => {
+                            progress_bar.finish_with_message("❌ Failed to apply effect");
+                            let _ = responses::error(&ctx.http, msg.channel_id, "Failed to apply effect.").await;
+                        }
+                    }
+                    return;
+                } else if parts[1] == "halftone" || parts[1] == "stitch" {
+                    // --- HALFTONE / CROSS-STITCH PATTERN SUBCOMMANDS ---
+                    // Usage: !cat halftone [flavor] [image]
+                    //        !cat stitch [flavor] [stitches] [image]
+                    let pattern = parts[1];
+                    let flavor = parts.iter().skip(2).find_map(|s| utils::parse_flavor(s)).unwrap_or(catppuccin::FlavorName::Latte);
+                    let max_stitches = parts.iter().skip(2).find_map(|s| s.parse::<u32>().ok()).unwrap_or(60).clamp(10, 120);
+                    let Some(image_url) = find_image_source(&ctx, &msg, &parts).await else {
+                        let _ = responses::warning(&ctx.http, msg.channel_id, format!("Please attach an image or provide a direct image URL to render a {} pattern.", pattern)).await;
+                        return;
+                    };
+                    let _typing = msg.channel_id.start_typing(&ctx.http);
+                    let progress_bar = ProgressBar::new_spinner();
+                    progress_bar.set_style(
+                        ProgressStyle::default_spinner()
+                            .template("{spinner:.green} {wide_msg}")
+                            .unwrap()
+                    );
+                    progress_bar.set_message(format!("🧵 Rendering {} pattern...", pattern));
+                    progress_bar.enable_steady_tick(Duration::from_millis(100));
+                    let img = match fetch_image(fetcher, &image_url, autoscale).await {
+                        Ok(img) => img,
+                        Err(e) => {
+                            progress_bar.finish_with_message("❌ Failed to load image for pattern");
+                            let _ = responses::error(&ctx.http, msg.channel_id, e).await;
+                            return;
+                        }
+                    };
+                    let is_stitch = pattern == "stitch";
+                    let pattern_result = jobs::run(Job::new("halftone", &msg), move || {
+                        let rgba_img = img.to_rgba8();
+                        let (rendered, legend) = if is_stitch {
+                            image_processing::render_cross_stitch(&rgba_img, flavor, max_stitches)
+                        } else {
+                            (image_processing::render_halftone(&rgba_img, flavor), Vec::new())
+                        };
+                        let mut output_buffer = std::io::Cursor::new(Vec::new());
+                        rendered.write_to(&mut output_buffer, image::ImageFormat::Png).map(|_| (output_buffer.into_inner(), legend))
+                    }).await;
+                    match pattern_result {
+                        Ok(Ok((png_bytes, legend))) => {
+                            let filename = crate::utils::sanitize_filename(&format!("catppuccin_{}_{}.png", pattern, flavor.to_string().to_lowercase()), "png");
+                            let attachment_data = serenity::builder::CreateAttachment::bytes(png_bytes, filename);
+                            let message_content = if is_stitch {
+                                let total: u32 = legend.iter().map(|(_, _, count)| count).sum();
+                                format!("**Catppuccin Cross-Stitch Chart** (Flavor: {})\n{} colors, {} stitches", flavor.to_string().to_uppercase(), legend.len(), total)
+                            } else {
+                                format!("**Catppuccin Halftone** (Flavor: {})", flavor.to_string().to_uppercase())
+                            };
+                            let message_builder = serenity::builder::CreateMessage::new().content(message_content);
+                            let _ = msg.channel_id.send_files(&ctx.http, vec![attachment_data], message_builder).await;
+                            progress_bar.finish_with_message("✅ Pattern image sent!");
+                        }
+                        Err(panic) => {
+                            progress_bar.finish_with_message("❌ Failed to render pattern");
+                            let _ = responses::error(&ctx.http, msg.channel_id, panic.to_string()).await;
+                        }
+                        _ => {
+                            progress_bar.finish_with_message("❌ Failed to render pattern");
+                            let _ = responses::error(&ctx.http, msg.channel_id, format!("Failed to render {} pattern.", pattern)).await;
+                        }
+                    }
+                    return;
+                } else if parts[1] == "posterize" || parts[1] == "cel" {
+                    // --- POSTERIZE / CEL-SHADING SUBCOMMANDS ---
+                    // Usage: !cat posterize <levels> [flavor] [image]
+                    //        !cat cel [flavor] [image]
+                    let is_cel = parts[1] == "cel";
+                    let levels = parts.get(2).and_then(|s| s.parse::<u32>().ok());
+                    if !is_cel && !levels.is_some_and(|l| (2..=8).contains(&l)) {
+                        let _ = responses::warning(&ctx.http, msg.channel_id, "Please give a number of levels from 2 to 8, e.g. `!cat posterize 4 mocha`.").await;
+                        return;
+                    }
+                    let flavor = parts.iter().skip(2).find_map(|s| utils::parse_flavor(s)).unwrap_or(catppuccin::FlavorName::Latte);
+                    let Some(image_url) = find_image_source(&ctx, &msg, &parts).await else {
+                        let _ = responses::warning(&ctx.http, msg.channel_id, "Please attach an image or provide a direct image URL to stylize.").await;
+                        return;
+                    };
+                    let _typing = msg.channel_id.start_typing(&ctx.http);
+                    let progress_bar = ProgressBar::new_spinner();
+                    progress_bar.set_style(
+                        ProgressStyle::default_spinner()
+                            .template("{spinner:.green} {wide_msg}")
+                            .unwrap()
+                    );
+                    progress_bar.set_message(if is_cel { "🖍️ Cel shading..." } else { "🖍️ Posterizing..." });
+                    progress_bar.enable_steady_tick(Duration::from_millis(100));
+                    let img = match fetch_image(fetcher, &image_url, autoscale).await {
+                        Ok(img) => img,
+                        Err(e) => {
+                            progress_bar.finish_with_message("❌ Failed to load image to stylize");
+                            let _ = responses::error(&ctx.http, msg.channel_id, e).await;
+                            return;
+                        }
+                    };
+                    let stylize_post_effects = post_effects.clone();
+                    let stylize_result = jobs::run(Job::new(if is_cel { "cel" } else { "posterize" }, &msg), move || {
+                        let rgba_img = img.to_rgba8();
+                        let rendered = if is_cel {
+                            image_processing::render_cel_shade(&rgba_img, flavor)
+                        } else {
+                            image_processing::render_posterize(&rgba_img, flavor, levels.unwrap_or(4))
+                        };
+                        let finished = image_processing::apply_post_effects(rendered, &stylize_post_effects, flavor);
+                        let mut output_buffer = std::io::Cursor::new(Vec::new());
+                        finished.write_to(&mut output_buffer, image::ImageFormat::Png).map(|_| output_buffer.into_inner())
+                    }).await;
+                    match stylize_result {
+                        Ok(Ok(png_bytes)) => {
+                            let style = if is_cel { "cel".to_string() } else { format!("posterize{}", levels.unwrap_or(4)) };
+                            let filename = crate::utils::sanitize_filename(&format!("catppuccin_{}_{}.png", style, flavor.to_string().to_lowercase()), "png");
+                            let attachment_data = serenity::builder::CreateAttachment::bytes(png_bytes, filename);
+                            let message_content = if is_cel {
+                                format!("**Catppuccin Cel Shading** (Flavor: {})", flavor.to_string().to_uppercase())
+                            } else {
+                                format!("**Catppuccin Posterize** (Flavor: {}, {} levels)", flavor.to_string().to_uppercase(), levels.unwrap_or(4))
+                            };
+                            let message_builder = serenity::builder::CreateMessage::new().content(message_content);
+                            let _ = msg.channel_id.send_files(&ctx.http, vec![attachment_data], message_builder).await;
+                            progress_bar.finish_with_message("✅ Stylized image sent!");
+                        }
+                        Err(panic) => {
+                            progress_bar.finish_with_message("❌ Failed to stylize image");
+                            let _ = responses::error(&ctx.http, msg.channel_id, panic.to_string()).await;
+                        }
+                        _ => {
+                            progress_bar.finish_with_message("❌ Failed to stylize image");
+                            let _ = responses::error(&ctx.http, msg.channel_id, "Failed to stylize image.").await;
+                        }
+                    }
+                    return;
+                } else if parts[1] == "sketch" {
+                    // --- LINE-ART SKETCH SUBCOMMAND ---
+                    // Usage: !cat sketch [flavor] [ink color] [wash] [image]
+                    let flavor = parts.iter().skip(2).find_map(|s| utils::parse_flavor(s)).unwrap_or(catppuccin::FlavorName::Latte);
+                    let ink_name = parts.iter().skip(2).map(|s| s.to_lowercase()).find(|s| utils::CATPPUCCIN_COLOR_NAMES.contains(&s.as_str())).unwrap_or_else(|| "text".to_string());
+                    let wash = parts.iter().skip(2).any(|s| s.eq_ignore_ascii_case("wash"));
+                    let ink = utils::catppuccin_color_name_to_rgb(&ink_name, flavor).unwrap();
+                    let Some(image_url) = find_image_source(&ctx, &msg, &parts).await else {
+                        let _ = responses::warning(&ctx.http, msg.channel_id, "Please attach an image or provide a direct image URL to sketch.").await;
+                        return;
+                    };
+                    let _typing = msg.channel_id.start_typing(&ctx.http);
+                    let progress_bar = ProgressBar::new_spinner();
+                    progress_bar.set_style(
+                        ProgressStyle::default_spinner()
+                            .template("{spinner:.green} {wide_msg}")
+                            .unwrap()
+                    );
+                    progress_bar.set_message("✏️ Tracing line art...");
+                    progress_bar.enable_steady_tick(Duration::from_millis(100));
+                    let img = match fetch_image(fetcher, &image_url, autoscale).await {
+                        Ok(img) => img,
+                        Err(e) => {
+                            progress_bar.finish_with_message("❌ Failed to load image to sketch");
+                            let _ = responses::error(&ctx.http, msg.channel_id, e).await;
+                            return;
+                        }
+                    };
+                    let sketch_post_effects = post_effects.clone();
+                    let sketch_result = jobs::run(Job::new("sketch", &msg), move || {
+                        let rendered = image_processing::render_sketch(&img.to_rgba8(), flavor, ink, wash);
+                        let finished = image_processing::apply_post_effects(rendered, &sketch_post_effects, flavor);
+                        let mut output_buffer = std::io::Cursor::new(Vec::new());
+                        finished.write_to(&mut output_buffer, image::ImageFormat::Png).map(|_| output_buffer.into_inner())
+                    }).await;
+                    match sketch_result {
+                        Ok(Ok(png_bytes)) => {
+                            let filename = crate::utils::sanitize_filename(&format!("catppuccin_sketch_{}_{}.png", ink_name, flavor.to_string().to_lowercase()), "png");
+                            let attachment_data = serenity::builder::CreateAttachment::bytes(png_bytes, filename);
+                            let message_content = format!(
+                                "**Catppuccin Sketch** (Flavor: {}, Ink: {}{})",
+                                flavor.to_string().to_uppercase(), ink_name, if wash { ", with wash" } else { "" }
+                            );
+                            let message_builder = serenity::builder::CreateMessage::new().content(message_content);
+                            let _ = msg.channel_id.send_files(&ctx.http, vec![attachment_data], message_builder).await;
+                            progress_bar.finish_with_message("✅ Sketch sent!");
+                        }
+                        Err(panic) => {
+                            progress_bar.finish_with_message("❌ Failed to sketch image");
+                            let _ = responses::error(&ctx.http, msg.channel_id, panic.to_string()).await;
+                        }
+                        _ => {
+                            progress_bar.finish_with_message("❌ Failed to sketch image");
+                            let _ = responses::error(&ctx.http, msg.channel_id, "Failed to sketch image.").await;
+                        }
+                    }
+                    return;
+                } else if parts[1] == "icons" {
+                    // --- ICON SET SUBCOMMAND ---
+                    // Usage: !cat icons [flavor] [algorithm] [image]
+                    let flavor = parts.iter().skip(2).find_map(|s| utils::parse_flavor(s)).unwrap_or(selected_flavor);
+                    let algorithm = parts.iter().skip(2).find_map(|s| utils::parse_algorithm(s)).unwrap_or("shepards-method");
+                    let Some(image_url) = find_image_source(&ctx, &msg, &parts).await else {
+                        let _ = responses::warning(&ctx.http, msg.channel_id, "Please attach an image or provide a direct image URL to make icons from.").await;
+                        return;
+                    };
+                    let _typing = msg.channel_id.start_typing(&ctx.http);
+                    let progress_bar = ProgressBar::new_spinner();
+                    progress_bar.set_style(
+                        ProgressStyle::default_spinner()
+                            .template("{spinner:.green} {wide_msg}")
+                            .unwrap()
+                    );
+                    progress_bar.set_message("🧩 Building icon set...");
+                    progress_bar.enable_steady_tick(Duration::from_millis(100));
+                    let img = match fetch_image(fetcher, &image_url, autoscale).await {
+                        Ok(img) => img,
+                        Err(e) => {
+                            progress_bar.finish_with_message("❌ Failed to load image for icons");
+                            let _ = responses::error(&ctx.http, msg.channel_id, e).await;
+                            return;
+                        }
+                    };
+                    let icons_result = jobs::run(Job::new("icons", &msg), move || {
+                        let processed = image_processing::process_image_with_palette(&img, flavor, algorithm).to_rgba8();
+                        archive::write_zip(&icons::render_icon_set(&processed))
+                    }).await;
+                    match icons_result {
+                        Ok(zip_bytes) => {
+                            let filename = crate::utils::sanitize_filename(&format!("catppuccin_icons_{}.zip", flavor.identifier()), "zip");
+                            let attachment_data = serenity::builder::CreateAttachment::bytes(zip_bytes, filename);
+                            let sizes: Vec<String> = icons::ICON_SIZES.iter().map(|s| s.to_string()).collect();
+                            let message_content = format!("**Catppuccin {} icon set**: {} px PNGs and a multi-size `favicon.ico`", flavor, sizes.join(", "));
+                            let message_builder = serenity::builder::CreateMessage::new().content(message_content);
+                            let _ = msg.channel_id.send_files(&ctx.http, vec![attachment_data], message_builder).await;
+                            progress_bar.finish_with_message("✅ Icon set sent!");
+                        }
+                        Err(panic) => {
+                            progress_bar.finish_with_message("❌ Failed to build icon set");
+                            let _ = responses::error(&ctx.http, msg.channel_id, panic.to_string()).await;
+                        }
+                    }
+                    return;
+                } else if parts[1] == "emojipack" {
+                    // --- EMOJI PACK SUBCOMMAND ---
+                    // Usage: !cat emojipack [algorithm] [image | custom emoji]
+                    let algorithm = parts.iter().skip(2).find_map(|s| utils::parse_algorithm(s)).unwrap_or("shepards-method");
+                    let Some(image_url) = find_image_source(&ctx, &msg, &parts).await else {
+                        let _ = responses::warning(&ctx.http, msg.channel_id, "Please attach an image, provide a direct image URL, or use a custom emoji to make an emoji pack from.").await;
+                        return;
+                    };
+                    let stem = image_url.filename.rsplit_once('.').map_or(image_url.filename.as_str(), |(stem, _)| stem).to_string();
+                    let _typing = msg.channel_id.start_typing(&ctx.http);
+                    let progress_bar = ProgressBar::new_spinner();
+                    progress_bar.set_style(
+                        ProgressStyle::default_spinner()
+                            .template("{spinner:.green} {wide_msg}")
+                            .unwrap()
+                    );
+                    progress_bar.set_message("😺 Building emoji pack...");
+                    progress_bar.enable_steady_tick(Duration::from_millis(100));
+                    let img = match fetch_image(fetcher, &image_url, autoscale).await {
+                        Ok(img) => img,
+                        Err(e) => {
+                            progress_bar.finish_with_message("❌ Failed to load image for emoji pack");
+                            let _ = responses::error(&ctx.http, msg.channel_id, e).await;
+                            return;
+                        }
+                    };
+                    let job_stem = stem.clone();
+                    let emojipack_result = jobs::run(Job::new("emojipack", &msg), move || {
+                        let flavors = [catppuccin::FlavorName::Latte, catppuccin::FlavorName::Frappe, catppuccin::FlavorName::Macchiato, catppuccin::FlavorName::Mocha];
+                        let files: Vec<(String, Vec<u8>)> = flavors
+                            .iter()
+                            .map(|flavor| {
+                                let processed = image_processing::process_image_with_palette(&img, *flavor, algorithm).to_rgba8();
+                                (format!("{}.png", icons::emoji_name(&job_stem, *flavor)), icons::emoji_png(&processed))
+                            })
+                            .collect();
+                        archive::write_zip(&files)
+                    }).await;
+                    match emojipack_result {
+                        Ok(zip_bytes) => {
+                            let filename = crate::utils::sanitize_filename(&format!("{}_emojipack.zip", stem), "zip");
+                            let attachment_data = serenity::builder::CreateAttachment::bytes(zip_bytes, filename);
+                            let message_content = format!(
+                                "**Emoji pack** ({}×{} in all four flavors)\nEach file is named for upload, e.g. `{}`.",
+                                icons::EMOJI_SIZE, icons::EMOJI_SIZE, icons::emoji_name(&stem, catppuccin::FlavorName::Mocha)
+                            );
+                            let message_builder = serenity::builder::CreateMessage::new().content(message_content);
+                            let _ = msg.channel_id.send_files(&ctx.http, vec![attachment_data], message_builder).await;
+                            progress_bar.finish_with_message("✅ Emoji pack sent!");
+                        }
+                        Err(panic) => {
+                            progress_bar.finish_with_message("❌ Failed to build emoji pack");
+                            let _ = responses::error(&ctx.http, msg.channel_id, panic.to_string()).await;
+                        }
+                    }
+                    return;
+                } else if parts[1] == "polaroid" {
+                    // --- POLAROID FRAME SUBCOMMAND ---
+                    // Usage: !cat polaroid [flavor] [image] ["caption"]
+                    let caption = utils::extract_quoted_args(&msg.content).into_iter().next().unwrap_or_default();
+                    let unquoted = utils::strip_quoted_args(&msg.content);
+                    let unquoted_parts: Vec<&str> = unquoted.split_whitespace().collect();
+                    let flavor = unquoted_parts.iter().find_map(|s| utils::parse_flavor(s)).unwrap_or(catppuccin::FlavorName::Latte);
+                    let Some(image_url) = find_image_source(&ctx, &msg, &unquoted_parts).await else {
+                        let _ = responses::warning(&ctx.http, msg.channel_id, "Please attach an image or provide a direct image URL to frame.").await;
+                        return;
+                    };
+                    let _typing = msg.channel_id.start_typing(&ctx.http);
+                    let progress_bar = ProgressBar::new_spinner();
+                    progress_bar.set_style(
+                        ProgressStyle::default_spinner()
+                            .template("{spinner:.green} {wide_msg}")
+                            .unwrap()
+                    );
+                    progress_bar.set_message("📸 Framing polaroid...");
+                    progress_bar.enable_steady_tick(Duration::from_millis(100));
+                    let img = match fetch_image(fetcher, &image_url, autoscale).await {
+                        Ok(img) => img,
+                        Err(e) => {
+                            progress_bar.finish_with_message("❌ Failed to load image for polaroid");
+                            let _ = responses::error(&ctx.http, msg.channel_id, e).await;
+                            return;
+                        }
+                    };
+                    let polaroid_result = jobs::run(Job::new("polaroid", &msg), move || {
+                        let processed = image_processing::process_image_with_palette(&img, flavor, "shepards-method").to_rgba8();
+                        let framed = image_processing::render_polaroid(&processed, &caption, flavor);
+                        let mut output_buffer = std::io::Cursor::new(Vec::new());
+                        framed.write_to(&mut output_buffer, image::ImageFormat::Png).map(|_| output_buffer.into_inner())
+                    }).await;
+                    match polaroid_result {
+                        Ok(Ok(png_bytes)) => {
+                            let filename = crate::utils::sanitize_filename(&format!("catppuccin_polaroid_{}.png", flavor.to_string().to_lowercase()), "png");
+                            let attachment_data = serenity::builder::CreateAttachment::bytes(png_bytes, filename);
+                            let message_builder = serenity::builder::CreateMessage::new();
+                            let _ = msg.channel_id.send_files(&ctx.http, vec![attachment_data], message_builder).await;
+                            progress_bar.finish_with_message("✅ Polaroid sent!");
+                        }
+                        Err(panic) => {
+                            progress_bar.finish_with_message("❌ Failed to frame polaroid");
+                            let _ = responses::error(&ctx.http, msg.channel_id, panic.to_string()).await;
+                        }
+                        _ => {
+                            progress_bar.finish_with_message("❌ Failed to frame polaroid");
+                            let _ = responses::error(&ctx.http, msg.channel_id, "Failed to create polaroid.").await;
+                        }
+                    }
+                    return;
+                } else if parts[1] == "rice" {
+                    // --- RICE MOCKUP SUBCOMMAND ---
+                    // Usage: !cat rice [flavor] [algorithm] [wallpaper image]
+                    let flavor = parts.iter().skip(2).find_map(|s| utils::parse_flavor(s)).unwrap_or(catppuccin::FlavorName::Mocha);
+                    let algorithm = parts.iter().skip(2).find_map(|s| utils::parse_algorithm(s)).unwrap_or(selected_algorithm);
+                    let Some(image_url) = find_image_source(&ctx, &msg, &parts).await else {
+                        let _ = responses::warning(&ctx.http, msg.channel_id, "Please attach a wallpaper or provide a direct image URL for the rice preview.").await;
+                        return;
+                    };
+                    let _typing = msg.channel_id.start_typing(&ctx.http);
+                    let progress_bar = ProgressBar::new_spinner();
+                    progress_bar.set_style(
+                        ProgressStyle::default_spinner()
+                            .template("{spinner:.green} {wide_msg}")
+                            .unwrap()
+                    );
+                    progress_bar.set_message("🍚 Composing rice preview...");
+                    progress_bar.enable_steady_tick(Duration::from_millis(100));
+                    let img = match fetch_image(fetcher, &image_url, autoscale).await {
+                        Ok(img) => img,
+                        Err(e) => {
+                            progress_bar.finish_with_message("❌ Failed to load wallpaper for rice preview");
+                            let _ = responses::error(&ctx.http, msg.channel_id, e).await;
+                            return;
+                        }
+                    };
+                    let rice_result = jobs::run(Job::new("rice", &msg), move || {
+                        let wallpaper = image_processing::process_image_with_palette(&img, flavor, algorithm).to_rgba8();
+                        let rice = crate::mockups::compose_rice(&wallpaper, flavor);
+                        let mut output_buffer = std::io::Cursor::new(Vec::new());
+                        rice.write_to(&mut output_buffer, image::ImageFormat::Png).map(|_| output_buffer.into_inner())
+                    }).await;
+                    match rice_result {
+                        Ok(Ok(png_bytes)) => {
+                            let filename = crate::utils::sanitize_filename(&format!("catppuccin_rice_{}.png", flavor.to_string().to_lowercase()), "png");
+                            let attachment_data = serenity::builder::CreateAttachment::bytes(png_bytes, filename);
+                            let message_content = format!("**Rice Preview**: {}", flavor.to_string().to_uppercase());
+                            let message_builder = serenity::builder::CreateMessage::new().content(message_content);
+                            let _ = msg.channel_id.send_files(&ctx.http, vec![attachment_data], message_builder).await;
+                            progress_bar.finish_with_message("✅ Rice preview sent!");
+                        }
+                        Err(panic) => {
+                            progress_bar.finish_with_message("❌ Failed to compose rice preview");
+                            let _ = responses::error(&ctx.http, msg.channel_id, panic.to_string()).await;
+                        }
+                        _ => {
+                            progress_bar.finish_with_message("❌ Failed to compose rice preview");
+                            let _ = responses::error(&ctx.http, msg.channel_id, "Failed to create rice preview.").await;
+                        }
+                    }
+                    return;
+                } else if parts[1] == "tile" {
+                    // --- SEAMLESS TILE SUBCOMMAND ---
+                    // Usage: !cat tile [mirror|blend] [size] [flavor] [image]
+                    let mode = if parts.iter().skip(2).any(|s| s.eq_ignore_ascii_case("mirror")) { "mirror" } else { "blend" };
+                    let tile_size = parts.iter().skip(2).find_map(|s| s.parse::<u32>().ok()).unwrap_or(256);
+                    if !(64..=1024).contains(&tile_size) {
+                        let _ = responses::warning(&ctx.http, msg.channel_id, "Tile size must be between 64 and 1024 pixels.").await;
+                        return;
+                    }
+                    let flavor = parts.iter().skip(2).find_map(|s| utils::parse_flavor(s)).unwrap_or(catppuccin::FlavorName::Latte);
+                    let Some(image_url) = find_image_source(&ctx, &msg, &parts).await else {
+                        let _ = responses::warning(&ctx.http, msg.channel_id, "Please attach an image or provide a direct image URL to make a tile from.").await;
+                        return;
+                    };
+                    let _typing = msg.channel_id.start_typing(&ctx.http);
+                    let progress_bar = ProgressBar::new_spinner();
+                    progress_bar.set_style(
+                        ProgressStyle::default_spinner()
+                            .template("{spinner:.green} {wide_msg}")
+                            .unwrap()
+                    );
+                    progress_bar.set_message("🧩 Generating seamless tile...");
+                    progress_bar.enable_steady_tick(Duration::from_millis(100));
+                    let img = match fetch_image(fetcher, &image_url, autoscale).await {
+                        Ok(img) => img,
+                        Err(e) => {
+                            progress_bar.finish_with_message("❌ Failed to load image for tile");
+                            let _ = responses::error(&ctx.http, msg.channel_id, e).await;
+                            return;
+                        }
+                    };
+                    let tile_result = jobs::run(Job::new("tile", &msg), move || -> Result<(Vec<u8>, Vec<u8>), image::ImageError> {
+                        let processed = image_processing::process_image_with_palette(&img, flavor, "shepards-method").to_rgba8();
+                        let tile = image_processing::make_seamless_tile(&processed, mode, tile_size);
+                        // Preview a 3x3 repeat, capped at roughly 1024px
+                        let mut preview = image_processing::repeat_tile(&tile, 3);
+                        if preview.width() > 1024 {
+                            preview = image::imageops::resize(&preview, 1024, 1024, image::imageops::FilterType::Triangle);
+                        }
+                        let mut tile_buffer = std::io::Cursor::new(Vec::new());
+                        tile.write_to(&mut tile_buffer, image::ImageFormat::Png)?;
+                        let mut preview_buffer = std::io::Cursor::new(Vec::new());
+                        preview.write_to(&mut preview_buffer, image::ImageFormat::Png)?;
+                        Ok((tile_buffer.into_inner(), preview_buffer.into_inner()))
+                    }).await;
+                    match tile_result {
+                        Ok(Ok((tile_bytes, preview_bytes))) => {
+                            let flavor_name = flavor.to_string().to_lowercase();
+                            let attachments = vec![
+                                serenity::builder::CreateAttachment::bytes(tile_bytes, crate::utils::sanitize_filename(&format!("catppuccin_tile_{}_{}.png", mode, flavor_name), "png")),
+                                serenity::builder::CreateAttachment::bytes(preview_bytes, crate::utils::sanitize_filename(&format!("catppuccin_tile_{}_{}_preview.png", mode, flavor_name), "png")),
+                            ];
+                            let message_content = format!("**Seamless Tile** ({}, {}px, Flavor: {})\nThe second image previews it repeated 3×3.", mode, tile_size, flavor.to_string().to_uppercase());
+                            let message_builder = serenity::builder::CreateMessage::new().content(message_content);
+                            let _ = msg.channel_id.send_files(&ctx.http, attachments, message_builder).await;
+                            progress_bar.finish_with_message("✅ Seamless tile sent!");
+                        }
+                        Err(panic) => {
+                            progress_bar.finish_with_message("❌ Failed to generate tile");
+                            let _ = responses::error(&ctx.http, msg.channel_id, panic.to_string()).await;
+                        }
+                        _ => {
+                            progress_bar.finish_with_message("❌ Failed to generate tile");
+                            let _ = responses::error(&ctx.http, msg.channel_id, "Failed to generate seamless tile.").await;
+                        }
+                    }
+                    return;
+                } else if parts[1] == "replace" {
+                    // --- COLOR REPLACE SUBCOMMAND ---
+                    // Usage: !cat replace <source color> <color name> [flavor] [tolerance] [image]
+                    let source = parts.get(2).and_then(|s| utils::parse_color_literal(s));
+                    let target_name = parts.get(3).map(|s| s.to_lowercase()).filter(|s| utils::CATPPUCCIN_COLOR_NAMES.contains(&s.as_str()));
+                    let (Some(source), Some(target_name)) = (source, target_name) else {
+                        let _ = responses::warning(&ctx.http, msg.channel_id, "Usage: `!cat replace #ff0000 red [flavor] [tolerance] [image]` - the source can be a hex, `rgb()`, `hsl()` or CSS color, and the target must be a Catppuccin color name.").await;
+                        return;
+                    };
+                    let flavor = parts.iter().skip(4).find_map(|s| utils::parse_flavor(s)).unwrap_or(catppuccin::FlavorName::Latte);
+                    let tolerance = parts.iter().skip(4).find_map(|s| s.parse::<f32>().ok()).unwrap_or(20.0).clamp(1.0, 100.0);
+                    let target = utils::catppuccin_color_name_to_rgb(&target_name, flavor).unwrap();
+                    let Some(image_url) = find_image_source(&ctx, &msg, &parts).await else {
+                        let _ = responses::warning(&ctx.http, msg.channel_id, "Please attach an image or provide a direct image URL to replace colors in.").await;
+                        return;
+                    };
+                    let _typing = msg.channel_id.start_typing(&ctx.http);
+                    let progress_bar = ProgressBar::new_spinner();
+                    progress_bar.set_style(
+                        ProgressStyle::default_spinner()
+                            .template("{spinner:.green} {wide_msg}")
+                            .unwrap()
+                    );
+                    progress_bar.set_message("🎯 Replacing color...");
+                    progress_bar.enable_steady_tick(Duration::from_millis(100));
+                    let img = match fetch_image(fetcher, &image_url, autoscale).await {
+                        Ok(img) => img,
+                        Err(e) => {
+                            progress_bar.finish_with_message("❌ Failed to load image for color replace");
+                            let _ = responses::error(&ctx.http, msg.channel_id, e).await;
+                            return;
+                        }
+                    };
+                    let replace_post_effects = post_effects.clone();
+                    let replace_result = jobs::run(Job::new("replace", &msg), move || {
+                        let mut rgba_img = img.to_rgba8();
+                        let replaced = image_processing::replace_color(&mut rgba_img, source, target, tolerance);
+                        let total = (rgba_img.width() * rgba_img.height()) as usize;
+                        let finished = image_processing::apply_post_effects(rgba_img, &replace_post_effects, flavor);
+                        let mut output_buffer = std::io::Cursor::new(Vec::new());
+                        finished.write_to(&mut output_buffer, image::ImageFormat::Png).map(|_| (output_buffer.into_inner(), replaced, total))
+                    }).await;
+                    match replace_result {
+                        Ok(Ok((png_bytes, replaced, total))) => {
+                            let filename = crate::utils::sanitize_filename(&format!("catppuccin_replace_{}.png", target_name), "png");
+                            let attachment_data = serenity::builder::CreateAttachment::bytes(png_bytes, filename);
+                            let message_content = format!(
+                                "**Color Replace**: `#{:02X}{:02X}{:02X}` → {} (`#{:02X}{:02X}{:02X}`, Flavor: {})\nReplaced {} pixels ({:.1}%) within tolerance {}",
+                                source.0, source.1, source.2, target_name, target.0, target.1, target.2, flavor.to_string().to_uppercase(),
+                                replaced, replaced as f32 / total.max(1) as f32 * 100.0, tolerance
+                            );
+                            let message_builder = serenity::builder::CreateMessage::new().content(message_content);
+                            let _ = msg.channel_id.send_files(&ctx.http, vec![attachment_data], message_builder).await;
+                            progress_bar.finish_with_message("✅ Color replaced!");
+                        }
+                        Err(panic) => {
+                            progress_bar.finish_with_message("❌ Failed to replace color");
+                            let _ = responses::error(&ctx.http, msg.channel_id, panic.to_string()).await;
+                        }
+                        _ => {
+                            progress_bar.finish_with_message("❌ Failed to replace color");
+                            let _ = responses::error(&ctx.http, msg.channel_id, "Failed to replace color.").await;
+                        }
+                    }
+                    return;
+                } else if parts[1] == "region" {
+                    // --- SELECTIVE REGION SUBCOMMAND ---
+                    // Usage: !cat region x,y,w,h [flavor] [algorithm] [image]
+                    //        !cat region [flavor] [image] [mask] (second attachment is a grayscale mask)
+                    let region = parts.iter().skip(2).find_map(|s| utils::parse_region(s));
+                    let flavor = parts.iter().skip(2).find_map(|s| utils::parse_flavor(s)).unwrap_or(catppuccin::FlavorName::Latte);
+                    let algorithm = parts.iter().skip(2).find_map(|s| utils::parse_algorithm(s)).unwrap_or(selected_algorithm);
+                    let image_sources = find_image_sources(&ctx, &msg, &parts).await;
+                    let mask_source = image_sources.get(1).cloned();
+                    if region.is_none() && mask_source.is_none() {
+                        let _ = responses::warning(&ctx.http, msg.channel_id, "Please give a region as `x,y,w,h` (e.g. `!cat region 0,0,400,300 mocha [image]`) or attach a grayscale mask as a second image.").await;
+                        return;
+                    }
+                    let Some(image_url) = image_sources.first() else {
+                        let _ = responses::warning(&ctx.http, msg.channel_id, "Please attach an image or provide a direct image URL to process a region of.").await;
+                        return;
+                    };
+                    let _typing = msg.channel_id.start_typing(&ctx.http);
+                    let progress_bar = ProgressBar::new_spinner();
+                    progress_bar.set_style(
+                        ProgressStyle::default_spinner()
+                            .template("{spinner:.green} {wide_msg}")
+                            .unwrap()
+                    );
+                    progress_bar.set_message("🔲 Catppuccinifying selected region...");
+                    progress_bar.enable_steady_tick(Duration::from_millis(100));
+                    let img = match fetch_image(fetcher, image_url, autoscale).await {
+                        Ok(img) => img,
+                        Err(e) => {
+                            progress_bar.finish_with_message("❌ Failed to load image for region");
+                            let _ = responses::error(&ctx.http, msg.channel_id, e).await;
+                            return;
+                        }
+                    };
+                    let mask = match mask_source {
+                        Some(mask_source) => match fetch_image(fetcher, &mask_source, autoscale).await {
+                            Ok(mask_img) => Some(mask_img.to_luma8()),
+                            Err(e) => {
+                                progress_bar.finish_with_message("❌ Failed to load mask");
+                                let _ = responses::error(&ctx.http, msg.channel_id, format!("Mask: {}", e)).await;
+                                return;
+                            }
+                        },
+                        None => None,
+                    };
+                    let region_post_effects = post_effects.clone();
+                    let _reservation = memory::admit(&ctx, msg.channel_id, memory::still_estimate(img.width(), img.height())).await;
+                    let region_result = jobs::run(Job::new("region", &msg), move || {
+                        let original = img.to_rgba8();
+                        let mut processed = original.clone();
+                        let lut = image_processing::generate_catppuccin_lut(flavor, algorithm);
+                        image_processing::apply_algorithm_lut(&mut processed, &lut, algorithm);
+                        // A region takes precedence over the mask attachment
+                        let mask = match region {
+                            Some(region) => image_processing::rect_mask(original.width(), original.height(), region),
+                            None => mask.unwrap(),
+                        };
+                        let composited = image_processing::composite_with_mask(&original, &processed, &mask);
+                        let finished = image_processing::apply_post_effects(composited, &region_post_effects, flavor);
+                        let mut output_buffer = std::io::Cursor::new(Vec::new());
+                        finished.write_to(&mut output_buffer, image::ImageFormat::Png).map(|_| output_buffer.into_inner())
+                    }).await;
+                    match region_result {
+                        Ok(Ok(png_bytes)) => {
+                            let filename = crate::utils::sanitize_filename(&format!("catppuccinified_region_{}.png", flavor.to_string().to_lowercase()), "png");
+                            let attachment_data = serenity::builder::CreateAttachment::bytes(png_bytes, filename);
+                            let message_content = match region {
+                                Some((x, y, w, h)) => format!("Catppuccinified region {}x{} at ({}, {}) (Flavor: {})", w, h, x, y, flavor.to_string().to_uppercase()),
+                                None => format!("Catppuccinified masked area (Flavor: {})", flavor.to_string().to_uppercase()),
+                            };
+                            let message_builder = serenity::builder::CreateMessage::new().content(message_content);
+                            let _ = msg.channel_id.send_files(&ctx.http, vec![attachment_data], message_builder).await;
+                            progress_bar.finish_with_message("✅ Region processed!");
+                        }
+                        Err(panic) => {
+                            progress_bar.finish_with_message("❌ Failed to process region");
+                            let _ = responses::error(&ctx.http, msg.channel_id, panic.to_string()).await;
+                        }
+                        _ => {
+                            progress_bar.finish_with_message("❌ Failed to process region");
+                            let _ = responses::error(&ctx.http, msg.channel_id, "Failed to process region.").await;
+                        }
+                    }
+                    return;
+                } else if parts[1] == "background" || parts[1] == "foreground" {
+                    // --- BACKGROUND / FOREGROUND RECOLOR SUBCOMMAND ---
+                    // Usage: !cat background [flavor] [algorithm] [image]
+                    //        !cat foreground [flavor] [algorithm] [image]
+                    let target = parts[1];
+                    let flavor = parts.iter().skip(2).find_map(|s| utils::parse_flavor(s)).unwrap_or(catppuccin::FlavorName::Latte);
+                    let algorithm = parts.iter().skip(2).find_map(|s| utils::parse_algorithm(s)).unwrap_or(selected_algorithm);
+                    let Some(image_url) = find_image_source(&ctx, &msg, &parts).await else {
+                        let _ = responses::warning(&ctx.http, msg.channel_id, format!("Please attach an image or provide a direct image URL to recolor its {}.", target)).await;
+                        return;
+                    };
+                    let _typing = msg.channel_id.start_typing(&ctx.http);
+                    let progress_bar = ProgressBar::new_spinner();
+                    progress_bar.set_style(
+                        ProgressStyle::default_spinner()
+                            .template("{spinner:.green} {wide_msg}")
+                            .unwrap()
+                    );
+                    progress_bar.set_message(format!("✂️ Segmenting and recoloring {}...", target));
+                    progress_bar.enable_steady_tick(Duration::from_millis(100));
+                    let img = match fetch_image(fetcher, &image_url, autoscale).await {
+                        Ok(img) => img,
+                        Err(e) => {
+                            progress_bar.finish_with_message("❌ Failed to load image for segmentation");
+                            let _ = responses::error(&ctx.http, msg.channel_id, e).await;
+                            return;
+                        }
+                    };
+                    let recolor_background = target == "background";
+                    let segment_post_effects = post_effects.clone();
+                    let _reservation = memory::admit(&ctx, msg.channel_id, memory::still_estimate(img.width(), img.height())).await;
+                    let segment_result = jobs::run(Job::new("background", &msg), move || {
+                        let original = img.to_rgba8();
+                        let mut processed = original.clone();
+                        let lut = image_processing::generate_catppuccin_lut(flavor, algorithm);
+                        image_processing::apply_algorithm_lut(&mut processed, &lut, algorithm);
+                        let mut mask = image_processing::segment_background(&original);
+                        if !recolor_background {
+                            image::imageops::invert(&mut mask);
+                        }
+                        let composited = image_processing::composite_with_mask(&original, &processed, &mask);
+                        let finished = image_processing::apply_post_effects(composited, &segment_post_effects, flavor);
+                        let mut output_buffer = std::io::Cursor::new(Vec::new());
+                        finished.write_to(&mut output_buffer, image::ImageFormat::Png).map(|_| output_buffer.into_inner())
+                    }).await;
+                    match segment_result {
+                        Ok(Ok(png_bytes)) => {
+                            let filename = crate::utils::sanitize_filename(&format!("catppuccinified_{}_{}.png", target, flavor.to_string().to_lowercase()), "png");
+                            let attachment_data = serenity::builder::CreateAttachment::bytes(png_bytes, filename);
+                            let message_content = format!("Catppuccinified the {} only (Flavor: {}). Works best with a plain or evenly colored background.", target, flavor.to_string().to_uppercase());
+                            let message_builder = serenity::builder::CreateMessage::new().content(message_content);
+                            let _ = msg.channel_id.send_files(&ctx.http, vec![attachment_data], message_builder).await;
+                            progress_bar.finish_with_message("✅ Segmented recolor sent!");
+                        }
+                        Err(panic) => {
+                            progress_bar.finish_with_message("❌ Failed to recolor segment");
+                            let _ = responses::error(&ctx.http, msg.channel_id, panic.to_string()).await;
+                        }
+                        _ => {
+                            progress_bar.finish_with_message("❌ Failed to recolor segment");
+                            let _ = responses::error(&ctx.http, msg.channel_id, format!("Failed to recolor the {}.", target)).await;
+                        }
+                    }
+                    return;
+                } else if parts[1] == "sprites" {
+                    // --- SPRITE SHEET SUBCOMMAND ---
+                    // Usage: !cat sprites <cols>x<rows> [flavor] [algorithm] [image]
+                    let Some((cols, rows)) = parts.get(2).and_then(|s| utils::parse_grid(s)) else {
+                        let _ = responses::warning(&ctx.http, msg.channel_id, "Please give the sheet layout as `<cols>x<rows>`, e.g. `!cat sprites 8x4 mocha [image]`.").await;
+                        return;
+                    };
+                    let flavor = parts.iter().skip(3).find_map(|s| utils::parse_flavor(s)).unwrap_or(catppuccin::FlavorName::Latte);
+                    let algorithm = parts.iter().skip(3).find_map(|s| utils::parse_algorithm(s)).unwrap_or(selected_algorithm);
+                    let Some(image_url) = find_image_source(&ctx, &msg, &parts).await else {
+                        let _ = responses::warning(&ctx.http, msg.channel_id, "Please attach a sprite sheet or provide a direct image URL.").await;
+                        return;
+                    };
+                    let _typing = msg.channel_id.start_typing(&ctx.http);
+                    let progress_bar = ProgressBar::new_spinner();
+                    progress_bar.set_style(
+                        ProgressStyle::default_spinner()
+                            .template("{spinner:.green} {wide_msg}")
+                            .unwrap()
+                    );
+                    progress_bar.set_message("👾 Processing sprite sheet...");
+                    progress_bar.enable_steady_tick(Duration::from_millis(100));
+                    let img = match fetch_image(fetcher, &image_url, autoscale).await {
+                        Ok(img) => img,
+                        Err(e) => {
+                            progress_bar.finish_with_message("❌ Failed to load sprite sheet");
+                            let _ = responses::error(&ctx.http, msg.channel_id, e).await;
+                            return;
+                        }
+                    };
+                    let _reservation = memory::admit(&ctx, msg.channel_id, memory::still_estimate(img.width(), img.height())).await;
+                    let sprite_result = jobs::run(Job::new("sprites", &msg), move || {
+                        let lut = image_processing::generate_catppuccin_lut(flavor, algorithm);
+                        let (sheet, empty) = image_processing::process_sprite_sheet(&img.to_rgba8(), cols, rows, &lut, algorithm)?;
+                        let mut output_buffer = std::io::Cursor::new(Vec::new());
+                        sheet.write_to(&mut output_buffer, image::ImageFormat::Png).map_err(|_| "Failed to encode the sprite sheet.".to_string())?;
+                        Ok::<_, String>((output_buffer.into_inner(), empty))
+                    }).await;
+                    match sprite_result {
+                        Ok(Ok((png_bytes, empty))) => {
+                            let filename = crate::utils::sanitize_filename(&format!("catppuccin_sprites_{}.png", flavor.to_string().to_lowercase()), "png");
+                            let attachment_data = serenity::builder::CreateAttachment::bytes(png_bytes, filename);
+                            let processed = (cols * rows) as usize - empty;
+                            let message_content = format!("Catppuccinified {} sprites in a {}x{} sheet (Flavor: {}, {} empty cells skipped)", processed, cols, rows, flavor.to_string().to_uppercase(), empty);
+                            let message_builder = serenity::builder::CreateMessage::new().content(message_content);
+                            let _ = msg.channel_id.send_files(&ctx.http, vec![attachment_data], message_builder).await;
+                            progress_bar.finish_with_message("✅ Sprite sheet sent!");
+                        }
+                        Ok(Err(e)) => {
+                            progress_bar.finish_with_message("❌ Failed to process sprite sheet");
+                            let _ = responses::error(&ctx.http, msg.channel_id, e).await;
+                        }
+                        Err(panic) => {
+                            progress_bar.finish_with_message("❌ Failed to process sprite sheet");
+                            let _ = responses::error(&ctx.http, msg.channel_id, panic.to_string()).await;
+                        }
+                    }
+                    return;
+                } else if parts[1] == "resize" || parts[1] == "crop" || parts[1] == "fit" {
+                    // --- RESIZE / CROP / FIT SUBCOMMANDS ---
+                    // Usage: !cat resize <longest side> [flavor] [format] [image]
+                    //        !cat crop <w:h> [flavor] [format] [image]
+                    //        !cat fit <WxH> [flavor] [format] [image]
+                    // A flavor is optional; when given, the image is catppuccinified after resizing.
+                    enum Geometry { Resize(u32), Crop(u32, u32), Fit(u32, u32) }
+                    let arg = parts.get(2).copied().unwrap_or("");
+                    let geometry = match parts[1] {
+                        "resize" => arg.parse::<u32>().ok().filter(|n| (16..=4096).contains(n)).map(Geometry::Resize),
+                        "crop" => utils::parse_aspect_ratio(arg).map(|(w, h)| Geometry::Crop(w, h)),
+                        _ => utils::parse_dimensions(arg).map(|(w, h)| Geometry::Fit(w, h)),
+                    };
+                    let Some(geometry) = geometry else {
+                        let usage = match parts[1] {
+                            "resize" => "Usage: `!cat resize 512 [flavor] [image]` - longest side in pixels (16-4096)",
+                            "crop" => "Usage: `!cat crop 16:9 [flavor] [image]` - center-crop to an aspect ratio",
+                            _ => "Usage: `!cat fit 1920x1080 [flavor] [image]` - scale and center-crop to exact dimensions",
+                        };
+                        let _ = responses::warning(&ctx.http, msg.channel_id, usage).await;
+                        return;
+                    };
+                    let flavor = parts.iter().skip(3).find_map(|s| utils::parse_flavor(s));
+                    let algorithm = parts.iter().skip(3).find_map(|s| utils::parse_algorithm(s)).unwrap_or(selected_algorithm);
+                    let Some(image_url) = find_image_source(&ctx, &msg, &parts).await else {
+                        let _ = responses::warning(&ctx.http, msg.channel_id, "Please attach an image or provide a direct image URL.").await;
+                        return;
+                    };
+                    let _typing = msg.channel_id.start_typing(&ctx.http);
+                    let progress_bar = ProgressBar::new_spinner();
+                    progress_bar.set_style(
+                        ProgressStyle::default_spinner()
+                            .template("{spinner:.green} {wide_msg}")
+                            .unwrap()
+                    );
+                    progress_bar.set_message("📐 Resizing image...");
+                    progress_bar.enable_steady_tick(Duration::from_millis(100));
+                    let img = match fetch_image(fetcher, &image_url, autoscale).await {
+                        Ok(img) => img,
+                        Err(e) => {
+                            progress_bar.finish_with_message("❌ Failed to load image for resize");
+                            let _ = responses::error(&ctx.http, msg.channel_id, e).await;
+                            return;
+                        }
+                    };
+                    let output_format = parts.iter().skip(3).find_map(|s| utils::parse_format(s)).unwrap_or(image::ImageFormat::Png);
+                    let geometry_post_effects = post_effects.clone();
+                    let geometry_result = jobs::run(Job::new("resize", &msg), move || {
+                        let rgba_img = img.to_rgba8();
+                        let mut resized = match geometry {
+                            Geometry::Resize(longest) => image_processing::resize_longest_side(&rgba_img, longest),
+                            Geometry::Crop(w, h) => image_processing::crop_to_aspect(&rgba_img, w, h),
+                            Geometry::Fit(w, h) => image_processing::fit_to_size(&rgba_img, w, h),
+                        };
+                        if let Some(flavor) = flavor {
+                            let lut = image_processing::generate_catppuccin_lut(flavor, algorithm);
+                            image_processing::apply_algorithm_lut(&mut resized, &lut, algorithm);
+                        }
+                        let finished = image_processing::apply_post_effects(resized, &geometry_post_effects, flavor.unwrap_or(catppuccin::FlavorName::Latte));
+                        let dimensions = finished.dimensions();
+                        let mut output_buffer = std::io::Cursor::new(Vec::new());
+                        let mut output_img = image::DynamicImage::ImageRgba8(finished);
+                        if output_format == image::ImageFormat::Jpeg {
+                            // JPEG has no alpha channel
+                            output_img = image::DynamicImage::ImageRgb8(output_img.to_rgb8());
+                        }
+                        output_img.write_to(&mut output_buffer, output_format).map(|_| (output_buffer.into_inner(), dimensions))
+                    }).await;
+                    match geometry_result {
+                        Ok(Ok((bytes, (w, h)))) => {
+                            let extension = output_format.extensions_str().first().unwrap_or(&"png");
+                            let filename = crate::utils::sanitize_filename(&format!("{}_{}x{}.{}", parts[1], w, h, extension), extension);
+                            let attachment_data = serenity::builder::CreateAttachment::bytes(bytes, filename);
+                            let mut message_content = format!("Here's your image at {}x{}", w, h);
+                            if let Some(flavor) = flavor {
+                                message_content.push_str(&format!(" (Flavor: {})", flavor.to_string().to_uppercase()));
+                            }
+                            let message_builder = serenity::builder::CreateMessage::new().content(message_content);
+                            let _ = msg.channel_id.send_files(&ctx.http, vec![attachment_data], message_builder).await;
+                            progress_bar.finish_with_message("✅ Resized image sent!");
+                        }
+                        Err(panic) => {
+                            progress_bar.finish_with_message("❌ Failed to resize image");
+                            let _ = responses::error(&ctx.http, msg.channel_id, panic.to_string()).await;
+                        }
+                        _ => {
+                            progress_bar.finish_with_message("❌ Failed to resize image");
+                            let _ = responses::error(&ctx.http, msg.channel_id, "Failed to resize image.").await;
+                        }
+                    }
+                    return;
+                } else if parts[1] == "gif" {
+                    // --- GIF TOOLBOX SUBCOMMAND ---
+                    // Usage: !cat gif speed <factor>x [flavor] [mp4|webm] [gif]
+                    //        !cat gif reverse [flavor] [mp4|webm] [gif]
+                    //        !cat gif boomerang [flavor] [mp4|webm] [gif]
+                    let op = match parts.get(2).map(|s| s.to_lowercase()).as_deref() {
+                        Some("speed") => parts.get(3).and_then(|s| utils::parse_speed_factor(s)).map(image_processing::GifOp::Speed),
+                        Some("reverse") => Some(image_processing::GifOp::Reverse),
+                        Some("boomerang") => Some(image_processing::GifOp::Boomerang),
+                        _ => None,
+                    };
+                    let Some(op) = op else {
+                        let _ = responses::warning(&ctx.http, msg.channel_id, "Usage: `!cat gif speed 2x [flavor] [gif]`, `!cat gif reverse [flavor] [gif]`, or `!cat gif boomerang [flavor] [gif]` (speed from 0.1x to 10x).").await;
+                        return;
+                    };
+                    let strict = flags.strict;
+                    // Strict output is snapped to a flavor's colors, the server's default if none is named
+                    let flavor = parts.iter().skip(3).find_map(|s| utils::parse_flavor(s)).or(strict.then_some(selected_flavor));
+                    let algorithm = parts.iter().skip(3).find_map(|s| utils::parse_algorithm(s)).unwrap_or(selected_algorithm);
+                    let Some(gif_url) = find_image_source(&ctx, &msg, &parts).await else {
+                        let _ = responses::warning(&ctx.http, msg.channel_id, "Please attach a GIF or provide a direct GIF URL.").await;
+                        return;
+                    };
+                    let _typing = msg.channel_id.start_typing(&ctx.http);
+                    let progress_bar = ProgressBar::new_spinner();
+                    progress_bar.set_style(
+                        ProgressStyle::default_spinner()
+                            .template("{spinner:.green} {wide_msg}")
+                            .unwrap()
+                    );
+                    progress_bar.set_message("🎞️ Editing GIF...");
+                    progress_bar.enable_steady_tick(Duration::from_millis(100));
+                    let gif_bytes = match source::download(fetcher, &gif_url).await {
+                        Ok(bytes) if bytes.starts_with(b"GIF8") => bytes,
+                        Ok(_) => {
+                            progress_bar.finish_with_message("❌ Attachment is not a GIF");
+                            let _ = responses::warning(&ctx.http, msg.channel_id, "That file isn't a GIF.").await;
+                            return;
+                        }
+                        Err(e) => {
+                            progress_bar.finish_with_message("❌ Failed to download GIF");
+                            let _ = responses::error(&ctx.http, msg.channel_id, e).await;
+                            return;
+                        }
+                    };
+                    // Video encoding blends colors, so strict output stays a GIF
+                    let requested_video = parts.iter().find_map(|s| utils::parse_video_format(s)).filter(|_| !strict);
+                    let (gif_width, gif_height, gif_frames) = image_processing::gif_size(&gif_bytes).unwrap_or((0, 0, 0));
+                    let _reservation = memory::admit(&ctx, msg.channel_id, memory::gif_estimate(gif_width, gif_height, gif_frames)).await;
+                    let gif_result = jobs::run(Job::new("gif", &msg), move || {
+                        let lut = flavor.map(|f| image_processing::generate_catppuccin_lut(f, algorithm));
+                        let output = image_processing::transform_gif(&gif_bytes, op, lut.as_deref().map(|l| l.as_slice()), algorithm, flavor.filter(|_| strict))?;
+                        // Large GIFs often exceed upload limits, so fall back to MP4 when ffmpeg is available
+                        let video_format = requested_video.or_else(|| {
+                            (!strict && output.len() > 8 * 1024 * 1024 && crate::video::ffmpeg_available()).then_some(crate::video::VideoFormat::Mp4)
+                        });
+                        match video_format {
+                            Some(format) => {
+                                let background = utils::catppuccin_color_name_to_rgb("base", flavor.unwrap_or(catppuccin::FlavorName::Mocha)).unwrap();
+                                crate::video::gif_to_video(&output, format, background).map(|bytes| (bytes, Some(format)))
+                            }
+                            None => Ok((output, None)),
+                        }
+                    }).await;
+                    match gif_result {
+                        Ok(Ok((output, video_format))) => {
+                            let op_name = match op {
+                                image_processing::GifOp::Speed(factor) => format!("speed {}x", factor),
+                                image_processing::GifOp::Reverse => "reverse".to_string(),
+                                image_processing::GifOp::Boomerang => "boomerang".to_string(),
+                            };
+                            let extension = video_format.map_or("gif", |f| f.extension());
+                            let filename = crate::utils::sanitize_filename(&format!("catppuccin_gif_{}.{}", op_name.replace(' ', "_"), extension), extension);
+                            let attachment_data = serenity::builder::CreateAttachment::bytes(output, filename);
+                            let mut message_content = format!("Here's your GIF ({})", op_name);
+                            if let Some(flavor) = flavor {
+                                message_content.push_str(&format!(" (Flavor: {})", flavor.to_string().to_uppercase()));
+                            }
+                            let message_builder = serenity::builder::CreateMessage::new().content(message_content);
+                            let _ = msg.channel_id.send_files(&ctx.http, vec![attachment_data], message_builder).await;
+                            progress_bar.finish_with_message("✅ GIF sent!");
+                        }
+                        Ok(Err(e)) => {
+                            progress_bar.finish_with_message("❌ Failed to edit GIF");
+                            let _ = responses::error(&ctx.http, msg.channel_id, format!("Failed to edit GIF: {}", e)).await;
+                        }
+                        Err(panic) => {
+                            progress_bar.finish_with_message("❌ Failed to edit GIF");
+                            let _ = responses::error(&ctx.http, msg.channel_id, panic.to_string()).await;
+                        }
+                    }
+                    return;
+                } else if parts[1] == "frames" || parts[1] == "frame" {
+                    // --- GIF FRAME SUBCOMMANDS ---
+                    // Usage: !cat frames [flavor] [gif]          - contact sheet of all frames
+                    //        !cat frame <n> [flavor] [gif]       - process a single frame as a still
+                    let single_frame = parts[1] == "frame";
+                    let frame_index = parts.get(2).and_then(|s| s.parse::<usize>().ok());
+                    if single_frame && frame_index.is_none() {
+                        let _ = responses::warning(&ctx.http, msg.channel_id, "Please give a frame number, e.g. `!cat frame 12 mocha [gif]`. Use `!cat frames [gif]` to see them all.").await;
+                        return;
+                    }
+                    let flavor = parts.iter().skip(2).find_map(|s| utils::parse_flavor(s));
+                    let algorithm = parts.iter().skip(2).find_map(|s| utils::parse_algorithm(s)).unwrap_or(selected_algorithm);
+                    let Some(gif_url) = find_image_source(&ctx, &msg, &parts).await else {
+                        let _ = responses::warning(&ctx.http, msg.channel_id, "Please attach a GIF or provide a direct GIF URL.").await;
+                        return;
+                    };
+                    let _typing = msg.channel_id.start_typing(&ctx.http);
+                    let progress_bar = ProgressBar::new_spinner();
+                    progress_bar.set_style(
+                        ProgressStyle::default_spinner()
+                            .template("{spinner:.green} {wide_msg}")
+                            .unwrap()
+                    );
+                    progress_bar.set_message("🎞️ Reading GIF frames...");
+                    progress_bar.enable_steady_tick(Duration::from_millis(100));
+                    let gif_bytes = match source::download(fetcher, &gif_url).await {
+                        Ok(bytes) if bytes.starts_with(b"GIF8") => bytes,
+                        Ok(_) => {
+                            progress_bar.finish_with_message("❌ Attachment is not a GIF");
+                            let _ = responses::warning(&ctx.http, msg.channel_id, "That file isn't a GIF.").await;
+                            return;
+                        }
+                        Err(e) => {
+                            progress_bar.finish_with_message("❌ Failed to download GIF");
+                            let _ = responses::error(&ctx.http, msg.channel_id, e).await;
+                            return;
+                        }
+                    };
+                    let (gif_width, gif_height, gif_frames) = image_processing::gif_size(&gif_bytes).unwrap_or((0, 0, 0));
+                    let _reservation = memory::admit(&ctx, msg.channel_id, memory::gif_estimate(gif_width, gif_height, gif_frames)).await;
+                    let frames_result = jobs::run(Job::new("frame", &msg), move || {
+                        let mut img = if single_frame {
+                            image_processing::extract_gif_frame(&gif_bytes, frame_index.unwrap())?
+                        } else {
+                            image_processing::render_frame_contact_sheet(&gif_bytes, flavor.unwrap_or(catppuccin::FlavorName::Mocha))?
+                        };
+                        if single_frame {
+                            if let Some(flavor) = flavor {
+                                let lut = image_processing::generate_catppuccin_lut(flavor, algorithm);
+                                image_processing::apply_algorithm_lut(&mut img, &lut, algorithm);
+                            }
+                        }
+                        let mut output_buffer = std::io::Cursor::new(Vec::new());
+                        img.write_to(&mut output_buffer, image::ImageFormat::Png).map_err(|_| "Failed to encode the image.".to_string())?;
+                        Ok::<_, String>(output_buffer.into_inner())
+                    }).await;
+                    match frames_result {
+                        Ok(Ok(png_bytes)) => {
+                            let (filename, mut message_content) = if single_frame {
+                                (format!("frame_{}.png", frame_index.unwrap()), format!("Frame {}", frame_index.unwrap()))
+                            } else {
+                                ("gif_frames.png".to_string(), "**GIF Frames** - use `!cat frame <n> [flavor] [gif]` to grab one".to_string())
+                            };
+                            if single_frame {
+                                if let Some(flavor) = flavor {
+                                    message_content.push_str(&format!(" (Flavor: {})", flavor.to_string().to_uppercase()));
+                                }
+                            }
+                            let attachment_data = serenity::builder::CreateAttachment::bytes(png_bytes, crate::utils::sanitize_filename(&filename, "png"));
+                            let message_builder = serenity::builder::CreateMessage::new().content(message_content);
+                            let _ = msg.channel_id.send_files(&ctx.http, vec![attachment_data], message_builder).await;
+                            progress_bar.finish_with_message("✅ GIF frames sent!");
+                        }
+                        Ok(Err(e)) => {
+                            progress_bar.finish_with_message("❌ Failed to read GIF frames");
+                            let _ = responses::error(&ctx.http, msg.channel_id, e).await;
+                        }
+                        Err(panic) => {
+                            progress_bar.finish_with_message("❌ Failed to read GIF frames");
+                            let _ = responses::error(&ctx.http, msg.channel_id, panic.to_string()).await;
+                        }
+                    }
+                    return;
+                } else if parts[1] == "morph" {
+                    // --- FLAVOR MORPH ANIMATION SUBCOMMAND ---
+                    // Usage: !cat morph <from flavor> <to flavor> [frames] [mp4|webm] [image]
+                    let from = parts.get(2).and_then(|s| utils::parse_flavor(s));
+                    let to = parts.get(3).and_then(|s| utils::parse_flavor(s));
+                    let (Some(from), Some(to)) = (from, to) else {
+                        let _ = responses::warning(&ctx.http, msg.channel_id, "Please give two flavors to morph between, e.g. `!cat morph latte mocha [image]`.").await;
+                        return;
+                    };
+                    let steps = parts.iter().skip(4).find_map(|s| s.parse::<usize>().ok()).unwrap_or(16).clamp(4, 48);
+                    let video_format = parts.iter().skip(4).find_map(|s| utils::parse_video_format(s));
+                    let algorithm = parts.iter().skip(4).find_map(|s| utils::parse_algorithm(s)).unwrap_or(selected_algorithm);
+                    let Some(image_url) = find_image_source(&ctx, &msg, &parts).await else {
+                        let _ = responses::warning(&ctx.http, msg.channel_id, "Please attach an image or provide a direct image URL to morph.").await;
+                        return;
+                    };
+                    let _typing = msg.channel_id.start_typing(&ctx.http);
+                    let progress_bar = ProgressBar::new_spinner();
+                    progress_bar.set_style(
+                        ProgressStyle::default_spinner()
+                            .template("{spinner:.green} {wide_msg}")
+                            .unwrap()
+                    );
+                    progress_bar.set_message("🌗 Morphing between flavors...");
+                    progress_bar.enable_steady_tick(Duration::from_millis(100));
+                    let img = match fetch_image(fetcher, &image_url, autoscale).await {
+                        Ok(img) => img,
+                        Err(e) => {
+                            progress_bar.finish_with_message("❌ Failed to load image for morph");
+                            let _ = responses::error(&ctx.http, msg.channel_id, e).await;
+                            return;
+                        }
+                    };
+                    let _reservation = memory::admit(&ctx, msg.channel_id, memory::gif_estimate(img.width(), img.height(), steps)).await;
+                    let morph_result = jobs::run(Job::new("morph", &msg), move || {
+                        let frames = image_processing::generate_flavor_morph_frames(&img.to_rgba8(), from, to, algorithm, steps, 480);
+                        match video_format {
+                            Some(format) => {
+                                let background = utils::catppuccin_color_name_to_rgb("base", to).unwrap();
+                                crate::video::encode_frames_to_video(&FrameStore::from(frames), format, background)
+                            }
+                            None => image_processing::encode_gif_frames(frames),
+                        }
+                    }).await;
+                    match morph_result {
+                        Ok(Ok(output)) => {
+                            let extension = video_format.map_or("gif", |f| f.extension());
+                            let filename = crate::utils::sanitize_filename(
+                                &format!("catppuccin_morph_{}_{}.{}", from.to_string().to_lowercase(), to.to_string().to_lowercase(), extension),
+                                extension,
+                            );
+                            let attachment_data = serenity::builder::CreateAttachment::bytes(output, filename);
+                            let message_content = format!("**Flavor Morph**: {} ⇄ {}", from.to_string().to_uppercase(), to.to_string().to_uppercase());
+                            let message_builder = serenity::builder::CreateMessage::new().content(message_content);
+                            let _ = msg.channel_id.send_files(&ctx.http, vec![attachment_data], message_builder).await;
+                            progress_bar.finish_with_message("✅ Flavor morph sent!");
+                        }
+                        Ok(Err(e)) => {
+                            progress_bar.finish_with_message("❌ Failed to create flavor morph");
+                            let _ = responses::error(&ctx.http, msg.channel_id, format!("Failed to create flavor morph: {}", e)).await;
+                        }
+                        Err(panic) => {
+                            progress_bar.finish_with_message("❌ Failed to create flavor morph");
+                            let _ = responses::error(&ctx.http, msg.channel_id, panic.to_string()).await;
+                        }
+                    }
                     return;
+                } else if parts[1] == "blendgif" {
+                    // --- CROSSFADE ANIMATION SUBCOMMAND ---
+                    // Usage: !cat blendgif [flavor] [frames] [mp4|webm] [imageA] [imageB]
+                    let flavor = parts.iter().skip(2).find_map(|s| utils::parse_flavor(s)).unwrap_or(catppuccin::FlavorName::Latte);
+                    let algorithm = parts.iter().skip(2).find_map(|s| utils::parse_algorithm(s)).unwrap_or(selected_algorithm);
+                    let steps = parts.iter().skip(2).find_map(|s| s.parse::<usize>().ok()).unwrap_or(12).clamp(4, 48);
+                    let video_format = parts.iter().skip(2).find_map(|s| utils::parse_video_format(s));
+                    let image_sources = find_image_sources(&ctx, &msg, &parts).await;
+                    if image_sources.len() < 2 {
+                        let _ = responses::warning(&ctx.http, msg.channel_id, "Please attach two images (or give two direct image URLs) to crossfade between.").await;
+                        return;
+                    }
+                    let _typing = msg.channel_id.start_typing(&ctx.http);
+                    let progress_bar = ProgressBar::new_spinner();
+                    progress_bar.set_style(
+                        ProgressStyle::default_spinner()
+                            .template("{spinner:.green} {wide_msg}")
+                            .unwrap()
+                    );
+                    progress_bar.set_message("🎞️ Crossfading images...");
+                    progress_bar.enable_steady_tick(Duration::from_millis(100));
+                    let mut images = Vec::with_capacity(2);
+                    for image_source in image_sources.iter().take(2) {
+                        match fetch_image(fetcher, image_source, autoscale).await {
+                            Ok(img) => images.push(img.to_rgba8()),
+                            Err(e) => {
+                                progress_bar.finish_with_message("❌ Failed to load image for crossfade");
+                                let _ = responses::error(&ctx.http, msg.channel_id, e).await;
+                                return;
+                            }
+                        }
+                    }
+                    let _reservation = memory::admit(&ctx, msg.channel_id, memory::gif_estimate(images[0].width(), images[0].height(), steps)).await;
+                    let blend_result = jobs::run(Job::new("blendgif", &msg), move || {
+                        let lut = image_processing::generate_catppuccin_lut(flavor, algorithm);
+                        for img in images.iter_mut() {
+                            image_processing::apply_algorithm_lut(img, &lut, algorithm);
+                        }
+                        let frames = image_processing::generate_crossfade_frames(&images[0], &images[1], steps, 480);
+                        match video_format {
+                            Some(format) => {
+                                let background = utils::catppuccin_color_name_to_rgb("base", flavor).unwrap();
+                                crate::video::encode_frames_to_video(&FrameStore::from(frames), format, background)
+                            }
+                            None => image_processing::encode_gif_frames(frames),
+                        }
+                    }).await;
+                    match blend_result {
+                        Ok(Ok(output)) => {
+                            let extension = video_format.map_or("gif", |f| f.extension());
+                            let filename = crate::utils::sanitize_filename(
+                                &format!("catppuccin_blend_{}.{}", flavor.to_string().to_lowercase(), extension),
+                                extension,
+                            );
+                            let attachment_data = serenity::builder::CreateAttachment::bytes(output, filename);
+                            let message_content = format!("**Crossfade**: {}", flavor.to_string().to_uppercase());
+                            let message_builder = serenity::builder::CreateMessage::new().content(message_content);
+                            let _ = msg.channel_id.send_files(&ctx.http, vec![attachment_data], message_builder).await;
+                            progress_bar.finish_with_message("✅ Crossfade sent!");
+                        }
+                        Ok(Err(e)) => {
+                            progress_bar.finish_with_message("❌ Failed to create crossfade");
+                            let _ = responses::error(&ctx.http, msg.channel_id, format!("Failed to create crossfade: {}", e)).await;
+                        }
+                        Err(panic) => {
+                            progress_bar.finish_with_message("❌ Failed to create crossfade");
+                            let _ = responses::error(&ctx.http, msg.channel_id, panic.to_string()).await;
+                        }
+                    }
+                    return;
+                } else if parts[1] == "texture" {
+                    // --- TEXTURE OVERLAY SUBCOMMAND ---
+                    // Usage: !cat texture [type] [image]
+                    let texture_type = parts.get(2).map(|s| s.to_lowercase()).unwrap_or("dots".to_string());
+                    let valid_types = ["dots", "stripes", "noise", "grain", "paper", "grid", "diamonds"];
+                    if !valid_types.contains(&texture_type.as_str()) {
+                        let _ = responses::warning(&ctx.http, msg.channel_id, "Please specify a valid texture type: dots, stripes, noise, grain, paper, grid, diamonds.").await;
+                        return;
+                    }
+                    let texture_flavor = parts.iter().skip(3).find_map(|s| utils::parse_flavor(s)).unwrap_or(catppuccin::FlavorName::Latte);
+                    // These decode a single still, so videos contribute their poster frame
+                    let image_url = find_image_source(&ctx, &msg, &parts).await.map(|s| s.still_url().to_string());
+                    if let Some(image_url) = image_url {
+                        let _typing = msg.channel_id.start_typing(&ctx.http);
+                        let progress_bar = ProgressBar::new_spinner();
+                        progress_bar.set_style(
+                            ProgressStyle::default_spinner()
+                                .template("{spinner:.green} {wide_msg}")
+                                .unwrap()
+                        );
+                        progress_bar.set_message("🖌️ Applying Catppuccin texture overlay...");
+                        progress_bar.enable_steady_tick(Duration::from_millis(100));
+                        if let Ok(image_bytes) = source::fetch_url(&image_url).await {
+                            let img_reader = ImageReader::new(std::io::Cursor::new(&image_bytes)).with_guessed_format();
+                            if let Ok(reader) = img_reader {
+                                if let Ok(img) = reader.decode() {
+                                    let rgba_img = img.to_rgba8();
+                                    let textured_img = image_processing::overlay_catppuccin_texture(&rgba_img, &texture_type, texture_flavor);
+                                    let mut output_buffer = std::io::Cursor::new(Vec::new());
+                                    if let Err(_e) = textured_img.write_to(&mut output_buffer, image::ImageFormat::Png) {
+                                        progress_bar.finish_with_message("❌ Failed to generate texture overlay image");
+                                        let _ = responses::error(&ctx.http, msg.channel_id, "Failed to generate texture overlay image.").await;
+                                        return;
+                                    }
+                                    let filename = crate::utils::sanitize_filename(&format!("catppuccin_texture_{}.png", texture_type), "png");
+                                    let attachment_data = serenity::builder::CreateAttachment::bytes(output_buffer.into_inner(), filename);
+                                    let message_content = format!("**Catppuccin Texture Overlay: {}**", texture_type);
+                                    let message_builder = serenity::builder::CreateMessage::new().content(message_content);
+                                    let _ = msg.channel_id.send_files(&ctx.http, vec![attachment_data], message_builder).await;
+                                    progress_bar.finish_with_message("✅ Texture overlay image sent!");
+                                    return;
+                                }
+                            }
+                        }
+                        progress_bar.finish_with_message("❌ Failed to apply texture overlay");
+                        let _ = responses::error(&ctx.http, msg.channel_id, "Failed to apply texture overlay. Please ensure your image is valid and accessible.").await;
+                        return;
+                    } else {
+                        let _ = responses::warning(&ctx.http, msg.channel_id, "Please attach an image or provide a direct image URL to apply a texture overlay.").await;
+                        return;
+                    }
                 }
-                let filename = format!("catppuccinified_{}.{}", selected_flavor.to_string().to_lowercase(), output_format.extensions_str().first().unwrap_or(&"png"));
-                let attachment_data = serenity::builder::CreateAttachment::bytes(output_buffer.into_inner(), filename.clone());
-                let mut message_content = format!("Here's your Catppuccinified image (Flavor: {})!", selected_flavor.to_string().to_uppercase());
-                if let Some(quality) = selected_quality {
-                    message_content.push_str(&format!(" Quality: {}", quality));
-                }
-                if let Some(format) = selected_format {
-                    message_content.push_str(&format!(" Format: {}", format.extensions_str().first().unwrap_or(&"unknown")));
-                }
-                let message_builder = serenity::builder::CreateMessage::new().content(message_content);
-                progress_bar.set_message("📤 Uploading processed image...");
-                info!("Uploading processed image");
-                let _ = msg.channel_id.send_files(&ctx.http, vec![attachment_data], message_builder).await;
-                progress_bar.finish_with_message("✅ Image uploaded successfully!");
             }
+
+            let image_sources = find_image_sources(&ctx, &msg, &parts).await;
+            // Discord's typing indicator, while there's an image or color to work on
+            let _typing = (!image_sources.is_empty() || parts.len() > 1).then(|| msg.channel_id.start_typing(&ctx.http));
+            dispatch(&ctx.http, fetcher, &msg, &parts, flags, image_sources).await;
         }
     }
     async fn ready(&self, ctx: Context, ready: serenity::model::gateway::Ready) {
         info!("{} is connected!", ready.user.name);
         info!("Bot is ready!");
-        // Announce online in both specified channels
-        let channel_ids = [
-            serenity::model::id::ChannelId::from(1393064541063221319u64),
-            serenity::model::id::ChannelId::from(465193124852138011u64),
-        ];
-        for channel_id in channel_ids.iter() {
-            let _ = channel_id.say(&ctx.http, "🟢 Catppuccinifier Bot is now online!").await;
+        // Announce online in the notice channels
+        for channel_id in config::get().notice_channels.iter() {
+            let _ = responses::info(&ctx.http, *channel_id, "🟢 Catppuccinifier Bot is now online!").await;
         }
+        admin::load_application_owners(&ctx.http).await;
+        // Register `/cat` from the command registry so it never drifts from the prefix commands
+        if let Err(why) = serenity::model::application::Command::set_global_commands(&ctx.http, vec![registry::slash_command()]).await {
+            error!(?why, "Error registering slash commands");
+        }
+    }
+    async fn guild_create(&self, ctx: Context, guild: serenity::model::guild::Guild, is_new: Option<bool>) {
+        // Also sent for every server on startup; only greet servers that just added the bot
+        if is_new == Some(true) {
+            setup::welcome(&ctx.http, &guild).await;
+        }
+    }
+    async fn reaction_add(&self, ctx: Context, reaction: serenity::model::channel::Reaction) {
+        autochannel::handle_reaction(&ctx, &reaction).await;
     }
     async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
-        if let Interaction::MessageComponent(component) = interaction {
+        if let serenity::model::application::Interaction::Component(ref component) = interaction {
+            if HELP_PAGES.handle(&ctx.http, component).await || setup::handle(&ctx.http, component).await || quiz::handle(&ctx.http, component).await {
+                return;
+            }
+            // Re-run buttons hand the original message back to `message` as if it were sent again
+            if component.data.custom_id.starts_with("history:") {
+                if let Some(original) = history::rerun(&ctx.http, component).await {
+                    self.message(ctx, original).await;
+                }
+                return;
+            }
+        }
+        // Processing works on message attachments, so slash commands reply with the equivalent prefix command
+        if let serenity::model::application::Interaction::Command(ref command) = interaction {
+            let content = match registry::prefix_command_line(&command.data.options) {
+                Some(line) => format!("Send this as a message, with any image attached:\n`{}`", line),
+                None => "Unknown command. Try `!cat help`.".to_string(),
+            };
+            let response = serenity::builder::CreateInteractionResponse::Message(
+                serenity::builder::CreateInteractionResponseMessage::new()
+                    .embed(responses::embed(responses::Tone::Info, content))
+                    .ephemeral(true),
+            );
+            if let Err(why) = command.create_response(&ctx.http, response).await {
+                error!(?why, "Error responding to slash command");
+            }
+            return;
+        }
+        if let Interaction::Component(component) = interaction {
             if component.data.custom_id == "apply_suggested_flavor" {
-                let user_id = component.user.id.0;
-                let channel_id = component.channel_id.0;
-                let mut map = COLOR_CONFIRM_MAP.lock().unwrap();
-                if let Some((img_bytes, img_format, width, height, flavor, algorithm)) = map.remove(&(user_id, channel_id)) {
-                    // Decode image
-                    let img = image::load_from_memory_with_format(&img_bytes, img_format).unwrap();
-                    let mut rgba_img = img.to_rgba8();
-                    let lut = image_processing::generate_catppuccin_lut(flavor, &algorithm);
-                    image_processing::apply_lut_to_image(&mut rgba_img, &lut);
-                    let mut output_buffer = std::io::Cursor::new(Vec::new());
-                    let output_format = image::ImageFormat::Png;
-                    let dynamic_img = image::DynamicImage::ImageRgba8(rgba_img);
-                    dynamic_img.write_to(&mut output_buffer, output_format).unwrap();
+                let user_id = component.user.id.get();
+                let channel_id = component.channel_id.get();
+                // Taken in its own statement so the lock isn't held across the awaits below
+                let pending = COLOR_CONFIRM_MAP.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).remove(&(user_id, channel_id));
+                if let Some((img_bytes, img_format, _width, _height, flavor, algorithm)) = pending {
+                    let job = Job { command: "apply-suggested", user: component.user.id, message: component.message.id };
+                    let result = jobs::run(job, move || {
+                        let img = image::load_from_memory_with_format(&img_bytes, img_format).map_err(|e| BotError::Decode(e.to_string()))?;
+                        let mut rgba_img = img.to_rgba8();
+                        let lut = image_processing::generate_catppuccin_lut(flavor, &algorithm);
+                        image_processing::apply_algorithm_lut(&mut rgba_img, &lut, &algorithm);
+                        let mut output_buffer = std::io::Cursor::new(Vec::new());
+                        let dynamic_img = image::DynamicImage::ImageRgba8(rgba_img);
+                        dynamic_img.write_to(&mut output_buffer, image::ImageFormat::Png).map_err(|e| BotError::Encode(e.to_string()))?;
+                        Ok::<_, BotError>(output_buffer.into_inner())
+                    })
+                    .await
+                    .map_err(BotError::from)
+                    .and_then(|result| result);
+                    let output_bytes = match result {
+                        Ok(bytes) => bytes,
+                        Err(e) => {
+                            warn!(error = ?e, user = %component.user.id, "Failed to apply suggested flavor");
+                            let response = CreateInteractionResponse::Message(CreateInteractionResponseMessage::new().content(e.reply()).ephemeral(true));
+                            let _ = component.create_response(&ctx.http, response).await;
+                            return;
+                        }
+                    };
                     let filename = utils::sanitize_filename(&format!("catppuccinified_{}.png", flavor.to_string().to_lowercase()), "png");
-                    let attachment_data = serenity::builder::CreateAttachment::bytes(output_buffer.into_inner(), filename);
+                    let attachment_data = serenity::builder::CreateAttachment::bytes(output_bytes, filename);
                     let message_content = format!("Here's your Catppuccinified image (Flavor: {})!", flavor.to_string().to_uppercase());
                     let message_builder = serenity::builder::CreateMessage::new().content(message_content);
-                    let _ = component.create_interaction_response(&ctx.http, |r| {
-                        r.kind(InteractionResponseType::ChannelMessageWithSource)
-                            .interaction_response_data(|d| d.content(":art: Applying suggested flavor...").ephemeral(true))
-                    }).await;
-                    let _ = component.channel_id.send_files(&ctx.http, vec![attachment_data], message_builder).await;
+                    let response = CreateInteractionResponse::Message(CreateInteractionResponseMessage::new().content(":art: Applying suggested flavor...").ephemeral(true));
+                    let _ = component.create_response(&ctx.http, response).await;
+                    if let Err(why) = component.channel_id.send_files(&ctx.http, vec![attachment_data], message_builder).await {
+                        error!(?why, "Error sending suggested-flavor image");
+                        let _ = responses::error(&ctx.http, component.channel_id, BotError::Upload.reply()).await;
+                    }
                 } else {
-                    let _ = component.create_interaction_response(&ctx.http, |r| {
-                        r.kind(InteractionResponseType::ChannelMessageWithSource)
-                            .interaction_response_data(|d| d.content("No pending color analysis found.").ephemeral(true))
-                    }).await;
+                    let response = CreateInteractionResponse::Message(CreateInteractionResponseMessage::new().content("No pending color analysis found.").ephemeral(true));
+                    let _ = component.create_response(&ctx.http, response).await;
+                }
+            }
+        }
+    }
+}
+
+/// Flags `!cat` accepts anywhere in a command, pulled out before the positional arguments are read
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandFlags {
+    pub post_effects: Vec<image_processing::PostEffect>,
+    pub pre_adjust: image_processing::PreAdjust,
+    pub tuning: image_processing::Tuning,
+    pub encode_quality: Option<u8>,
+    /// Leave the server's watermark off this time
+    pub no_watermark: bool,
+    /// Add a breakdown of where the time went to the reply, for diagnosing slow jobs
+    pub debug_timing: bool,
+    /// Snap the output to exactly the palette's colors, dithered, for pixel-perfect theming
+    pub strict: bool,
+    /// Shrink images over the size limit to fit instead of refusing them. Servers can turn this on
+    /// for everyone, which `limits::autoscale` adds in.
+    pub autoscale: bool,
+}
+
+impl CommandFlags {
+    /// Split the flags out of a command's words, returning them and the words that are left
+    pub fn parse<'a>(all_parts: &[&'a str]) -> (Self, Vec<&'a str>) {
+        let (post_effects, parts) = utils::parse_post_effects(all_parts);
+        let (pre_adjust, parts) = utils::parse_pre_adjustments(&parts);
+        let (tuning, parts) = utils::parse_tuning(&parts);
+        let (encode_quality, mut parts) = utils::parse_encode_quality(&parts);
+        let mut switch = |name: &str| {
+            let on = parts.contains(&name);
+            parts.retain(|p| *p != name);
+            on
+        };
+        let flags = CommandFlags {
+            no_watermark: switch("--no-watermark"),
+            debug_timing: switch("--debug-timing"),
+            strict: switch("--strict"),
+            autoscale: switch("--autoscale"),
+            post_effects,
+            pre_adjust,
+            tuning,
+            encode_quality,
+        };
+        (flags, parts)
+    }
+}
+
+/// What the positional words of `!cat` ask of an image or color: a flavor, algorithm, quality or
+/// format, or one of the `all`, `palette`, `compare` and `stats` modes
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageArgs {
+    pub flavor: catppuccin::FlavorName,
+    /// Whether the flavor was named rather than left to the server's default
+    pub explicit_flavor: bool,
+    pub algorithm: &'static str,
+    pub quality: Option<String>,
+    pub format: Option<image::ImageFormat>,
+    pub all_flavors: bool,
+    pub show_palette: bool,
+    pub show_comparison: bool,
+    pub show_stats: bool,
+}
+
+impl ImageArgs {
+    /// Read `parts` (starting with `!cat`), falling back to `default_flavor`. `-f` picks the fastest algorithm.
+    pub fn parse(parts: &[&str], default_flavor: catppuccin::FlavorName) -> Self {
+        let fast = parts.contains(&"-f");
+        let mut args = ImageArgs {
+            flavor: default_flavor,
+            explicit_flavor: false,
+            algorithm: if fast { "nearest-neighbor" } else { "shepards-method" },
+            quality: fast.then(|| "fast".to_string()),
+            format: None,
+            all_flavors: false,
+            show_palette: false,
+            show_comparison: false,
+            show_stats: false,
+        };
+        match parts.get(1).copied() {
+            Some("all") => args.all_flavors = true,
+            Some("palette") => args.show_palette = true,
+            Some("compare") => args.show_comparison = true,
+            Some("stats") => args.show_stats = true,
+            Some(word) => {
+                if let Some(flavor) = utils::parse_flavor(word) {
+                    args.flavor = flavor;
+                    args.explicit_flavor = true;
+                } else if let Some(algorithm) = utils::parse_algorithm(word) {
+                    args.algorithm = algorithm;
+                } else if let Some(quality) = utils::parse_quality(word) {
+                    args.quality = Some(quality.to_string());
+                } else if let Some(format) = utils::parse_format(word) {
+                    args.format = Some(format);
+                }
+            }
+            None => {}
+        }
+        // After a flavor, the next word can pick the algorithm, quality or format
+        if let (true, Some(word)) = (args.explicit_flavor, parts.get(2).copied()) {
+            if let Some(algorithm) = utils::parse_algorithm(word) {
+                args.algorithm = algorithm;
+            } else if let Some(quality) = utils::parse_quality(word) {
+                args.quality = Some(quality.to_string());
+                args.algorithm = quality;
+            } else if let Some(format) = utils::parse_format(word) {
+                args.format = Some(format);
+            }
+        }
+        args
+    }
+}
+
+/// Catppuccinify `image_sources`, or convert a color or preview a palette when there are none, as
+/// `parts` and `flags` ask. This is everything `!cat` does once it isn't a subcommand; replies go
+/// through `outbox` and downloads through `fetcher`, so it can run without Discord.
+pub async fn dispatch(outbox: &dyn Outbox, fetcher: &dyn ImageFetcher, msg: &Message, parts: &[&str], flags: CommandFlags, image_sources: Vec<ImageSource>) {
+    let CommandFlags { mut post_effects, pre_adjust, tuning, encode_quality, debug_timing, strict, autoscale, .. } = flags;
+    let autoscale = limits::autoscale(msg.guild_id, autoscale);
+    let ImageArgs {
+        flavor: selected_flavor,
+        explicit_flavor: has_explicit_flavor_arg,
+        algorithm: selected_algorithm,
+        quality: selected_quality,
+        format: selected_format,
+        all_flavors: process_all_flavors,
+        show_palette,
+        show_comparison,
+        show_stats,
+    } = ImageArgs::parse(parts, config::default_flavor(msg.guild_id));
+    // Several images, attached or linked, are processed as a batch
+    let batch_mode = image_sources.len() > 1;
+
+    if show_palette && parts.len() > 2 {
+        // Create progress bar for palette generation
+        let progress_bar = ProgressBar::new_spinner();
+        progress_bar.set_style(
+            ProgressStyle::default_spinner()
+                .template("{spinner:.green} {wide_msg}")
+                .unwrap()
+        );
+        progress_bar.set_message("🎨 Generating palette preview...");
+        progress_bar.enable_steady_tick(Duration::from_millis(100));
+        
+        if parts[2] == "all" {
+            progress_bar.set_message("🎨 Generating all palette previews...");
+            let palette_img = palette::generate_all_palettes_preview();
+            let mut output_buffer = std::io::Cursor::new(Vec::new());
+            if let Err(_e) = palette_img.write_to(&mut output_buffer, image::ImageFormat::Png) {
+                progress_bar.finish_with_message("❌ Failed to generate palette preview");
+                let _ = responses::error(outbox, msg.channel_id, "Failed to generate palette preview.").await;
+                return;
+            }
+            let attachment_data = serenity::builder::CreateAttachment::bytes(output_buffer.into_inner(), "catppuccin_palettes_all.png");
+            let message_content = "**All Catppuccin Color Palettes**\nFrom left to right: Latte, Frappe, Macchiato, Mocha";
+            let message_builder = serenity::builder::CreateMessage::new().content(message_content);
+            progress_bar.set_message("📤 Uploading palette preview...");
+            let _ = outbox.send_files(msg.channel_id, vec![attachment_data], message_builder).await;
+            progress_bar.finish_with_message("✅ All palette previews uploaded successfully!");
+            return;
+        } else if let Some(flavor) = utils::parse_flavor(parts[2]) {
+            progress_bar.set_message("🎨 Generating palette preview...");
+            let palette_img = palette::generate_palette_preview(flavor);
+            let mut output_buffer = std::io::Cursor::new(Vec::new());
+            if let Err(_e) = palette_img.write_to(&mut output_buffer, image::ImageFormat::Png) {
+                progress_bar.finish_with_message("❌ Failed to generate palette preview");
+                let _ = responses::error(outbox, msg.channel_id, "Failed to generate palette preview.").await;
+                return;
+            }
+            let filename = format!("catppuccin_palette_{}.png", flavor.to_string().to_lowercase());
+            let attachment_data = serenity::builder::CreateAttachment::bytes(output_buffer.into_inner(), filename);
+            let message_content = format!("**Catppuccin {} Color Palette**", flavor.to_string().to_uppercase());
+            let message_builder = serenity::builder::CreateMessage::new().content(message_content);
+            progress_bar.set_message("📤 Uploading palette preview...");
+            let _ = outbox.send_files(msg.channel_id, vec![attachment_data], message_builder).await;
+            progress_bar.finish_with_message("✅ Palette preview uploaded successfully!");
+            return;
+        } else {
+            progress_bar.finish_with_message("❌ Invalid palette command");
+            let _ = responses::warning(outbox, msg.channel_id, "Invalid palette command. Use `!cat palette [flavor]` or `!cat palette all`").await;
+            return;
+        }
+    }
+
+    // Hex Color Conversion Logic
+    if image_sources.is_empty() {
+        let input_color_arg_index = if has_explicit_flavor_arg { 2 } else { 1 };
+        if parts.len() > input_color_arg_index {
+            let input_color = parts[input_color_arg_index];
+            let Some((r, g, b)) = utils::parse_color_literal(input_color) else {
+                let _ = responses::warning(outbox, msg.channel_id, "That doesn't look like a valid color or flavor. Please use formats like `#FF0000`, `rgb(255, 0, 0)`, `hsl(0, 100%, 50%)` or `tomato` for colors, or specify a flavor like `latte`, `frappe`, `macchiato`, `mocha` with an image.").await;
+                return;
+            };
+            let input_hex = format!("{:02X}{:02X}{:02X}", r, g, b);
+            // Create progress bar for hex conversion
+            let progress_bar = ProgressBar::new_spinner();
+            progress_bar.set_style(
+                ProgressStyle::default_spinner()
+                    .template("{spinner:.green} {wide_msg}")
+                    .unwrap()
+            );
+            progress_bar.set_message("🎨 Converting hex color to Catppuccin...");
+            progress_bar.enable_steady_tick(Duration::from_millis(100));
+            
+            match utils::find_closest_catppuccin_hex(&input_hex, selected_flavor) {
+                Some((color_name, converted_hex)) => {
+                    progress_bar.set_message("✅ Color conversion completed");
+                    let embed_color = u32::from_str_radix(&converted_hex, 16).unwrap_or(0x000000);
+                    let original_color_display = if utils::parse_hex_color(input_color).is_some() {
+                        format!("#{}", input_hex)
+                    } else {
+                        format!("{} (#{})", input_color, input_hex)
+                    };
+                    let converted_color_display = format!("#{}", converted_hex);
+                    // The closest color in every flavor, with its Delta-E from the input
+                    let all_flavors = [catppuccin::FlavorName::Latte, catppuccin::FlavorName::Frappe, catppuccin::FlavorName::Macchiato, catppuccin::FlavorName::Mocha]
+                        .iter()
+                        .filter_map(|flavor| {
+                            let (name, hex) = utils::find_closest_catppuccin_hex(&input_hex, *flavor)?;
+                            let distance = image_processing::delta_e((r, g, b), utils::parse_hex_color(&hex)?);
+                            let marker = if *flavor == selected_flavor { " ◀" } else { "" };
+                            Some(format!("**{}**: {} `#{}` (ΔE {:.1}){}", flavor, name, hex, distance, marker))
+                        })
+                        .collect::<Vec<_>>()
+                        .join("\n");
+                    let embed = responses::embed(responses::Tone::Accent, format!("Original Color: `{}`", original_color_display))
+                        .title("Catppuccin Color Conversion")
+                        .color(embed_color)
+                        .thumbnail(format!("attachment://{}", responses::SWATCH_FILENAME))
+                        .field(
+                            "Closest Catppuccin Color",
+                            format!("**{}** (`{}`) (Flavor: {})", color_name.to_uppercase(), converted_color_display, selected_flavor.to_string().to_uppercase()),
+                            false,
+                        )
+                        .field("All Flavors", all_flavors, false);
+                    // Input on the left, its match on the right
+                    let [_, mr, mg, mb] = embed_color.to_be_bytes();
+                    let swatch = responses::comparison_swatch_attachment((r, g, b), (mr, mg, mb));
+                    let builder = serenity::builder::CreateMessage::new().embed(embed);
+                    let _ = outbox.send_files(msg.channel_id, vec![swatch], builder).await;
+                    progress_bar.finish_with_message("✅ Color conversion result sent!");
+                }
+                None => {
+                    progress_bar.finish_with_message("❌ Error converting hex color");
+                    let _ = responses::error(outbox, msg.channel_id, "Error converting hex color. Please ensure it's a valid 3 or 6 digit hex code.").await;
+                }
+            }
+            return;
+        }
+    }
+
+    // Image Processing Logic
+    if strict && selected_format.is_some_and(|format| !codecs::keeps_exact_colors(format)) {
+        let _ = responses::warning(outbox, msg.channel_id, "`--strict` needs `png` or `gif` output to keep only the palette's colors; `jpg`, `webp` and `avif` blend them back in.").await;
+        return;
+    }
+    if let Some(format) = selected_format {
+        image_processing::ensure_opaque_background(&mut post_effects, format);
+    }
+    if batch_mode && !image_sources.is_empty() {
+        // Create progress bar for batch processing
+        let progress_bar = ProgressBar::new_spinner();
+        progress_bar.set_style(
+            ProgressStyle::default_spinner()
+                .template("{spinner:.green} {wide_msg}")
+                .unwrap()
+        );
+        progress_bar.set_message("🔄 Starting batch processing...");
+        progress_bar.enable_steady_tick(Duration::from_millis(100));
+
+        if image_sources.len() > MAX_BATCH_IMAGES {
+            let _ = responses::warning(outbox, msg.channel_id, format!("You sent {} images; only the first {} will be processed.", image_sources.len(), MAX_BATCH_IMAGES)).await;
+        }
+
+        // `!cat cancel` stops the batch between images
+        let cancel_guard = CancelGuard::register(msg.author.id);
+
+        // Batch processing: process every attached and linked image, with the same size limits as single images
+        let mut processed_attachments = Vec::new();
+        let mut failed_count = 0;
+        let mut batch_panic = None;
+        let mut cancelled = false;
+        let total = image_sources.len().min(MAX_BATCH_IMAGES);
+        // Progress is counted in images, each reported by its job once it's mapped
+        let status = format!("🕒 Processing {} images...", total);
+        let (progress_sender, progress_updates) = tokio::sync::mpsc::unbounded_channel();
+        let status_message = responses::info(outbox, msg.channel_id, &status).await.ok();
+        let batch = async {
+            // Downloads run a few at a time, outside the processing queue, and arrive in the order sent
+            // The stream owns its sources; futures borrowing them can't be held across the handler's awaits
+            let mut downloads = futures::stream::iter(image_sources.iter().take(MAX_BATCH_IMAGES).cloned())
+                .map(|image_source| async move {
+                    let fetched = fetch_image(fetcher, &image_source, autoscale).await;
+                    (image_source, fetched)
+                })
+                .buffered(BATCH_DOWNLOADS)
+                .enumerate();
+            while let Some((i, (image_source, fetched))) = downloads.next().await {
+                if cancel_guard.is_cancelled() {
+                    cancelled = true;
+                    break;
+                }
+                progress_bar.set_message(format!("📥 Processing image {}/{}...", i + 1, total));
+                let img = match fetched {
+                    Ok(img) => img,
+                    Err(e) => {
+                        warn!(url = %image_source.url, error = %e, "Skipping image in batch");
+                        failed_count += 1;
+                        continue;
+                    }
+                };
+                let output_format = selected_format.unwrap_or(image::ImageFormat::Png);
+                let post_effects = post_effects.clone();
+                let progress_sender = progress_sender.clone();
+                let _reservation = memory::admit(outbox, msg.channel_id, memory::still_estimate(img.width(), img.height())).await;
+                let result = jobs::run(Job::new("batch", msg), move || {
+                    let mut rgba_img = img.to_rgba8();
+                    let lut = image_processing::generate_tuned_lut(selected_flavor, selected_algorithm, &tuning);
+                    image_processing::apply_pre_adjustments(&mut rgba_img, &pre_adjust, selected_flavor);
+                    image_processing::apply_algorithm_lut(&mut rgba_img, &lut, selected_algorithm);
+                    let _ = progress_sender.send(image_processing::Progress { done: i + 1, total });
+                    let mut rgba_img = image_processing::apply_post_effects(rgba_img, &post_effects, selected_flavor);
+                    if strict {
+                        image_processing::quantize_to_palette(&mut rgba_img, selected_flavor);
+                    }
+                    codecs::encode(&image::DynamicImage::ImageRgba8(rgba_img), output_format, encode_quality).map(icc::tag_srgb_png)
+                }).await;
+                let bytes = match result {
+                    Ok(Ok(bytes)) => bytes,
+                    Ok(Err(_)) => {
+                        failed_count += 1;
+                        continue;
+                    }
+                    Err(panic) => {
+                        failed_count += 1;
+                        batch_panic = Some(panic);
+                        continue;
+                    }
+                };
+                let ext = output_format.extensions_str().first().unwrap_or(&"png");
+                let filename = utils::sanitize_filename(&format!("catppuccinified_{}_{}.{}", selected_flavor.to_string().to_lowercase(), image_source.filename, ext), ext);
+                processed_attachments.push(serenity::builder::CreateAttachment::bytes(bytes, filename));
+            }
+            // Tracking ends once every sender is gone
+            drop(progress_sender);
+        };
+        progress::alongside(outbox, status_message, status, progress_updates, batch).await;
+        drop(cancel_guard);
+        if cancelled {
+            progress_bar.finish_with_message("🛑 Batch cancelled");
+            let _ = responses::info(outbox, msg.channel_id, format!("🛑 {}", BotError::Cancelled.reply())).await;
+            return;
+        }
+        if !processed_attachments.is_empty() {
+            progress_bar.set_message("📤 Uploading batch processed images...");
+            let message_content = if failed_count > 0 {
+                format!("Here are your Catppuccinified images! ({} failed)", failed_count)
+            } else {
+                "Here are your Catppuccinified images!".to_string()
+            };
+            let message_builder = serenity::builder::CreateMessage::new().content(message_content);
+            let _ = outbox.send_files(msg.channel_id, processed_attachments, message_builder).await;
+            progress_bar.finish_with_message("✅ Batch processing completed!");
+        } else {
+            progress_bar.finish_with_message("❌ Failed to process any images. Please ensure your attachments are valid images.");
+            let _ = responses::error(outbox, msg.channel_id, "Failed to process any images. Please make sure your attachments and links are valid images under 8 MB and the size limit.").await;
+        }
+        if let Some(panic) = batch_panic {
+            let _ = responses::error(outbox, msg.channel_id, panic.to_string()).await;
+        }
+        return;
+    }
+    if let Some(attachment) = msg.attachments.first() {
+        info!(filename = %attachment.filename, url = %attachment.url, "Image received");
+        let request = ImageRequest {
+            flavor: selected_flavor,
+            algorithm: selected_algorithm,
+            quality: selected_quality,
+            format: selected_format,
+            pre_adjust,
+            tuning,
+            post_effects,
+            encode_quality,
+            strict,
+            autoscale,
+            show_stats,
+            show_comparison,
+            all_flavors: process_all_flavors,
+            debug_timing,
+            command: parts[1..].join(" "),
+        };
+        process_attachment(outbox, fetcher, msg, attachment, request).await;
+    }
+}
+
+/// What `!cat` was asked to do with an image, once its arguments and flags are parsed
+pub struct ImageRequest {
+    pub flavor: catppuccin::FlavorName,
+    pub algorithm: &'static str,
+    pub quality: Option<String>,
+    pub format: Option<image::ImageFormat>,
+    pub pre_adjust: image_processing::PreAdjust,
+    pub tuning: image_processing::Tuning,
+    pub post_effects: Vec<image_processing::PostEffect>,
+    pub encode_quality: Option<u8>,
+    /// Snap the output to exactly the palette's colors
+    pub strict: bool,
+    pub autoscale: bool,
+    pub show_stats: bool,
+    pub show_comparison: bool,
+    pub all_flavors: bool,
+    pub debug_timing: bool,
+    /// The arguments after `!cat`, as saved in job history
+    pub command: String,
+}
+
+/// Catppuccinify `msg`'s attachment: download it with `fetcher`, process it as `request` asks, and
+/// reply through `outbox`. Both are traits so the whole path can be tested without Discord.
+pub async fn process_attachment(outbox: &dyn Outbox, fetcher: &dyn ImageFetcher, msg: &Message, attachment: &serenity::model::channel::Attachment, request: ImageRequest) {
+    let ImageRequest {
+        flavor: selected_flavor,
+        algorithm: selected_algorithm,
+        quality: selected_quality,
+        format: selected_format,
+        pre_adjust,
+        tuning,
+        post_effects,
+        encode_quality,
+        strict,
+        autoscale,
+        show_stats,
+        show_comparison,
+        all_flavors: process_all_flavors,
+        debug_timing,
+        command,
+    } = request;
+
+    // Create progress bar for console output
+    let progress_bar = ProgressBar::new_spinner();
+    progress_bar.set_style(
+        ProgressStyle::default_spinner()
+            .template("{spinner:.green} {wide_msg}")
+            .unwrap()
+    );
+    progress_bar.set_message("🔄 Starting image processing...");
+    progress_bar.enable_steady_tick(Duration::from_millis(100));
+    
+    // Vector files are recolored in their markup rather than rasterized through the LUT; an SVG sent
+    // without a content type is still caught by its extension
+    if svg::is_svg(attachment.content_type.as_deref(), &attachment.filename) {
+        progress_bar.finish_and_clear();
+        return recolor_svg_attachment(outbox, fetcher, msg, attachment, selected_flavor).await;
+    }
+    // Only process if it's an image, going by the extension for formats Discord sends without a content type
+    let content_type_is_image = attachment.content_type.as_deref().is_some_and(|s| s.starts_with("image/"));
+    let is_image = content_type_is_image || source::is_unpreviewed_image(&attachment.filename);
+    if !is_image {
+        progress_bar.finish_with_message("❌ Attachment is not an image");
+        warn!(?attachment.content_type, "Attachment is not an image");
+        let _ = responses::warning(outbox, msg.channel_id, "Please attach an image to catppuccinify it.").await;
+        return;
+    }
+
+    // Download the image
+    let mut timings = timing::Timings::new("catppuccinify", msg);
+    progress_bar.set_message("📥 Downloading image...");
+    info!(url = %attachment.url, "Downloading image");
+    let mut progress = |done, total| {
+        progress_bar.set_message(format!("📥 Downloading image... {}", source::describe_download(done, total)));
+    };
+    let download = fetcher.fetch(&attachment.url, &mut progress);
+    let image_bytes = match timings.time_async("download", download).await {
+        Ok(bytes) => {
+            progress_bar.set_message("✅ Image downloaded successfully");
+            bytes
+        }
+        Err(e) => {
+            progress_bar.finish_with_message("❌ Failed to download image from Discord");
+            error!(error = %e, "Failed to download image from Discord");
+            let _ = responses::error(outbox, msg.channel_id, e).await;
+            return;
+        }
+    };
+
+    // Animated GIFs keep every frame. Stats, comparisons and other formats are about a still, so
+    // those read the first frame like any other image.
+    let wants_still = show_stats || show_comparison || process_all_flavors || selected_format.is_some_and(|format| format != image::ImageFormat::Gif);
+    if !wants_still && image_bytes.starts_with(b"GIF8") && image_processing::gif_frame_count(&image_bytes).is_ok_and(|frames| frames > 1) {
+        progress_bar.set_message("🎬 Detected animated GIF - processing all frames...");
+        let (gif_bytes, downscale_note) = match limits::fit_gif(&image_bytes, autoscale) {
+            Ok(None) => (image_bytes.clone(), None),
+            Ok(Some((resized, note))) => (resized, Some(note)),
+            Err(e) => {
+                progress_bar.finish_with_message("❌ GIF is too large");
+                let _ = responses::error(outbox, msg.channel_id, e).await;
+                return;
+            }
+        };
+        // Transparency is kept unless `--bg` gives a color to flatten onto
+        let background = image_processing::background_fill(&post_effects, selected_flavor);
+        let settings = format!("gif/{}/{}/{:?}/{:?}/{}", selected_flavor, selected_algorithm, pre_adjust, background, strict);
+        let shared = jobs::share(jobs::request_key(msg.author.id, "process", &settings, &image_bytes));
+        // Work is measured in pixels across all frames, which is what processing time scales with
+        let (gif_width, gif_height, gif_frames) = image_processing::gif_size(&gif_bytes).unwrap_or((0, 0, 0));
+        let work_units = gif_width as u64 * gif_height as u64 * gif_frames as u64;
+        let _reservation = if shared.joined {
+            None
+        } else {
+            Some(memory::admit(outbox, msg.channel_id, memory::gif_estimate(gif_width, gif_height, gif_frames)).await)
+        };
+        let status = if shared.joined {
+            "🕒 You already sent this GIF with these settings; you'll get the result of that job.".to_string()
+        } else {
+            let subject = format!("{}×{}, {}-frame GIF", gif_width, gif_height, gif_frames);
+            format!("🕒 Processing animated GIF (all frames)... {}", eta::announcement(selected_algorithm, eta::WorkKind::Gif, work_units, &subject))
+        };
+        let (progress_sender, progress_updates) = tokio::sync::mpsc::unbounded_channel();
+        let status_message = responses::info(outbox, msg.channel_id, &status).await.ok();
+        let job = shared.run(Job::new("process", msg), move || {
+            let started = std::time::Instant::now();
+            let processed = image_processing::process_gif_with_progress(&gif_bytes, selected_flavor, selected_algorithm, &pre_adjust, background, strict, Some(&progress_sender))
+                .map_err(BotError::Decode)?;
+            eta::record(selected_algorithm, eta::WorkKind::Gif, work_units, started.elapsed());
+            // Large GIFs often exceed upload limits, so fall back to MP4 when ffmpeg is available.
+            // Video encoding blends colors, so strict output stays a GIF.
+            if strict || processed.len() <= 8 * 1024 * 1024 || !crate::video::ffmpeg_available() {
+                return Ok((processed, None));
+            }
+            let background = background.unwrap_or_else(|| utils::catppuccin_color_name_to_rgb("base", selected_flavor).unwrap());
+            let video = crate::video::gif_to_video(&processed, crate::video::VideoFormat::Mp4, background).map_err(BotError::Encode)?;
+            Ok::<_, BotError>((video, Some(crate::video::VideoFormat::Mp4)))
+        });
+        let result = progress::alongside(outbox, status_message, status, progress_updates, job)
+            .await
+            .map_err(BotError::from)
+            .and_then(|result| result);
+        let (output, video_format) = match result {
+            Ok(output) => output,
+            Err(e) => {
+                progress_bar.finish_with_message("❌ Failed to process GIF");
+                error!(error = ?e, "Failed to process GIF");
+                let _ = responses::error(outbox, msg.channel_id, e).await;
+                return;
+            }
+        };
+        let extension = video_format.map_or("gif", |format| format.extension());
+        let filename = format!("catppuccinified_{}.{}", selected_flavor.to_string().to_lowercase(), extension);
+        let mut message_content = format!("Here's your Catppuccinified {} (Flavor: {})!", extension.to_uppercase(), selected_flavor.to_string().to_uppercase());
+        if strict {
+            message_content.push_str(" Palette colors only.");
+        }
+        if let Some(note) = &downscale_note {
+            message_content.push_str(&format!(" {}", note));
+        }
+        let message_builder = serenity::builder::CreateMessage::new().content(message_content);
+        progress_bar.set_message("📤 Uploading processed GIF...");
+        if outbox.send_files(msg.channel_id, vec![serenity::builder::CreateAttachment::bytes(output, filename)], message_builder).await.is_err() {
+            progress_bar.finish_with_message("❌ Failed to send processed GIF");
+            let _ = responses::error(outbox, msg.channel_id, BotError::Upload).await;
+            return;
+        }
+        if let Err(e) = history::record(msg, &command, &image_bytes) {
+            warn!(error = %e, "Failed to record job history");
+        }
+        progress_bar.finish_with_message("✅ GIF uploaded successfully!");
+        return;
+    }
+
+    // Load the image from bytes
+    progress_bar.set_message("🔍 Decoding image...");
+    info!("Decoding image");
+    let img = match timings.time("decode", || icc::decode(&image_bytes)) {
+        Ok(img) => {
+            progress_bar.set_message("✅ Image decoded successfully");
+            img
+        },
+        Err(_) => {
+            progress_bar.finish_with_message("❌ Failed to decode the image");
+            error!("Failed to decode the image");
+            let _ = responses::error(outbox, msg.channel_id, BotError::Decode(String::new())).await;
+            return;
+        }
+    };
+    let (img, downscale_note) = match limits::fit_still(img, autoscale) {
+        Ok(fitted) => fitted,
+        Err(e) => {
+            progress_bar.finish_with_message("❌ Image is too large");
+            let _ = responses::error(outbox, msg.channel_id, e).await;
+            return;
+        }
+    };
+
+    // Convert to RGBA
+    progress_bar.set_message("🔄 Converting image to RGBA...");
+    debug!("Converting image to RGBA");
+    let mut rgba_img = img.to_rgba8();
+    let (width, height) = rgba_img.dimensions();
+    progress_bar.set_message("📐 Image dimensions analyzed");
+
+    // Handle color statistics
+    if show_stats {
+        progress_bar.set_message("🎨 Analyzing image colors...");
+        info!("Analyzing image colors");
+        let (dominant_colors, suggested_flavor) = image_processing::analyze_image_colors(&rgba_img);
+        progress_bar.set_message("📊 Generating color statistics...");
+        let mut stats_message = "**Color Analysis Results**\n\n**Dominant Colors:**\n".to_string();
+        for (i, (r, g, b, count)) in dominant_colors.iter().enumerate() {
+            let hex = format!("{:02X}{:02X}{:02X}", r, g, b);
+            let percentage = (*count as f32 / (width * height) as f32 * 100.0).round() as u32;
+            stats_message.push_str(&format!("{}. `#{}` (RGB: {},{},{}) - {}%\n", i + 1, hex, r, g, b, percentage));
+        }
+        stats_message.push_str(&format!("\n**Suggested Flavor:** {}\n", suggested_flavor.to_string().to_uppercase()));
+        stats_message.push_str("\n*Based on average brightness of dominant colors*");
+        progress_bar.finish_with_message("✅ Color analysis completed");
+        // Store the image and context for confirmation
+        let mut buf = std::io::Cursor::new(Vec::new());
+        if let Err(e) = img.write_to(&mut buf, image::ImageFormat::Png) {
+            progress_bar.finish_and_clear();
+            warn!(error = %e, "Failed to store image for the suggested-flavor button");
+            let _ = responses::error(outbox, msg.channel_id, BotError::Encode(e.to_string()).reply()).await;
+            return;
+        }
+        {
+            let mut map = COLOR_CONFIRM_MAP.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            map.insert((msg.author.id.get(), msg.channel_id.get()), (buf.into_inner(), image::ImageFormat::Png, width, height, suggested_flavor, selected_algorithm.to_string()));
+        }
+        // Send stats message with button
+        let action_row = CreateActionRow::Buttons(vec![CreateButton::new("apply_suggested_flavor")
+            .label(format!("Apply {}", suggested_flavor.to_string().to_uppercase()))
+            .style(ButtonStyle::Primary)]);
+        let builder = serenity::builder::CreateMessage::new()
+            .content(stats_message)
+            .components(vec![action_row]);
+        let _ = outbox.send_message(msg.channel_id, builder).await;
+        return;
+    }
+
+    // Pre-adjustments steer the palette mapping, so they come before any LUT. A matte is in the
+    // palette being mapped to, so they're applied per flavor.
+    if process_all_flavors {
+        // All four flavors are mapped at once, each with its own copy and LUT
+        let _reservation = memory::admit(outbox, msg.channel_id, memory::still_estimate(width, height) * 4).await;
+        progress_bar.set_message("🎨 Processing image with all flavors...");
+        info!("Processing image with all flavors");
+        let flavors = [
+            (utils::parse_flavor("latte").unwrap(), "latte"),
+            (utils::parse_flavor("frappe").unwrap(), "frappe"),
+            (utils::parse_flavor("macchiato").unwrap(), "macchiato"),
+            (utils::parse_flavor("mocha").unwrap(), "mocha")
+        ];
+        // Each flavor is its own job, so the four run side by side within the processing queue
+        let source_img = std::sync::Arc::new(rgba_img);
+        let output_format = selected_format.unwrap_or(image::ImageFormat::Png);
+        let flavor_jobs = flavors.iter().map(|&(flavor, flavor_name)| {
+            let source_img = source_img.clone();
+            let post_effects = post_effects.clone();
+            info!(flavor = %flavor_name, "Processing image with flavor");
+            let job = jobs::run(Job::new("all", msg), move || {
+                let mut flavor_img = (*source_img).clone();
+                image_processing::apply_pre_adjustments(&mut flavor_img, &pre_adjust, flavor);
+                let lut = image_processing::generate_tuned_lut(flavor, selected_algorithm, &tuning);
+                image_processing::apply_algorithm_lut(&mut flavor_img, &lut, selected_algorithm);
+                let mut flavor_img = image_processing::apply_post_effects(flavor_img, &post_effects, flavor);
+                if strict {
+                    image_processing::quantize_to_palette(&mut flavor_img, flavor);
+                }
+                codecs::encode(&image::DynamicImage::ImageRgba8(flavor_img), output_format, encode_quality)
+            });
+            async move { (flavor_name, job.await) }
+        });
+        progress_bar.set_message("🎨 Processing all four flavors...");
+        let mut attachments = Vec::new();
+        for (flavor_name, result) in futures::future::join_all(flavor_jobs).await {
+            let output = match result {
+                Ok(Ok(output)) => output,
+                Ok(Err(e)) => {
+                    error!(flavor = %flavor_name, error = %e, "Failed to encode processed image");
+                    continue;
                 }
+                Err(panic) => {
+                    error!(flavor = %flavor_name, error = %panic, "Flavor job failed");
+                    continue;
+                }
+            };
+            let filename = format!("catppuccinified_{}.{}", flavor_name, output_format.extensions_str().first().unwrap_or(&"png"));
+            let attachment_data = serenity::builder::CreateAttachment::bytes(icc::tag_srgb_png(output), filename);
+            attachments.push(attachment_data);
+        }
+        if !attachments.is_empty() {
+            progress_bar.set_message("📤 Uploading all processed images...");
+            info!(count = attachments.len(), "Uploading all processed images");
+            let mut message_content = "Here are your Catppuccinified images with all flavors!".to_string();
+            if let Some(note) = &downscale_note {
+                message_content.push_str(&format!(" {}", note));
+            }
+            let message_builder = serenity::builder::CreateMessage::new().content(message_content);
+            let _ = outbox.send_files(msg.channel_id, attachments, message_builder).await;
+            progress_bar.finish_with_message("✅ All flavors processed and uploaded successfully!");
+        } else {
+            progress_bar.finish_with_message("❌ Failed to process any flavors");
+        }
+        return;
+    }
+
+    // Single flavor processing. Mapping through to encoding runs as a job, off the async threads.
+    progress_bar.set_message("🎨 Processing with flavor and algorithm...");
+    info!(flavor = ?selected_flavor, "Processing image with selected flavor");
+    let output_format = selected_format.unwrap_or(image::ImageFormat::Png);
+    // The same image and settings sent again while this runs shares its result
+    let settings = format!(
+        "still/{}/{}/{:?}/{:?}/{:?}/{:?}/{:?}/{}/{}",
+        selected_flavor, selected_algorithm, output_format, pre_adjust, tuning, post_effects, encode_quality, strict, show_comparison
+    );
+    let shared = jobs::share(jobs::request_key(msg.author.id, "process", &settings, &image_bytes));
+    // Joined requests reuse the first one's result, so only that one holds memory
+    let _reservation = if shared.joined {
+        None
+    } else {
+        Some(memory::admit(outbox, msg.channel_id, memory::still_estimate(width, height)).await)
+    };
+    let work_units = width as u64 * height as u64;
+    let status = if shared.joined {
+        "🕒 You already sent this image with these settings; you'll get the result of that job.".to_string()
+    } else {
+        let subject = format!("{}×{} image", width, height);
+        format!("🕒 Your image is now being processed... {}", eta::announcement(selected_algorithm, eta::WorkKind::Still, work_units, &subject))
+    };
+    let (progress_sender, progress_updates) = tokio::sync::mpsc::unbounded_channel();
+    let status_message = responses::info(outbox, msg.channel_id, &status).await.ok();
+    let job = shared.run(Job::new("process", msg), move || {
+        let started = std::time::Instant::now();
+        let mut stages = timing::StageTimes::default();
+        let lut = stages.time("lut", || image_processing::generate_tuned_lut(selected_flavor, selected_algorithm, &tuning));
+        let original_img = show_comparison.then(|| rgba_img.clone());
+        stages.time("map", || {
+            image_processing::apply_pre_adjustments(&mut rgba_img, &pre_adjust, selected_flavor);
+            image_processing::apply_algorithm_lut_with_progress(&mut rgba_img, &lut, selected_algorithm, Some(&progress_sender));
+        });
+        // What the next estimate for this algorithm goes by
+        eta::record(selected_algorithm, eta::WorkKind::Still, work_units, started.elapsed());
+        // Comparison mode shows the mapping alone, without post-effects
+        if let Some(original_img) = original_img {
+            let comparison_img = image_processing::create_comparison_image(&original_img, &rgba_img);
+            let mut output_buffer = std::io::Cursor::new(Vec::new());
+            comparison_img.write_to(&mut output_buffer, output_format).map_err(|e| BotError::Encode(e.to_string()))?;
+            return Ok((output_buffer.into_inner(), stages));
+        }
+        if !post_effects.is_empty() {
+            rgba_img = stages.time("effects", || image_processing::apply_post_effects(rgba_img, &post_effects, selected_flavor));
+        }
+        // Last, so post-effects can't bring in-between colors back
+        if strict {
+            stages.time("strict", || image_processing::quantize_to_palette(&mut rgba_img, selected_flavor));
+        }
+        let dynamic_img = image::DynamicImage::ImageRgba8(rgba_img);
+        let output = stages.time("encode", || codecs::encode(&dynamic_img, output_format, encode_quality)).map_err(BotError::Encode)?;
+        Ok::<_, BotError>((output, stages))
+    });
+    let result = progress::alongside(outbox, status_message, status, progress_updates, job)
+        .await
+        .map_err(BotError::from)
+        .and_then(|result| result);
+    let output = match result {
+        Ok((output, stages)) => {
+            timings.record(stages);
+            output
+        }
+        Err(e) => {
+            progress_bar.finish_with_message("❌ Failed to process the image");
+            error!(error = ?e, "Failed to process the image");
+            let _ = responses::error(outbox, msg.channel_id, e).await;
+            return;
+        }
+    };
+
+    // Handle comparison mode
+    if show_comparison {
+        let filename = format!("comparison_{}.{}", selected_flavor.to_string().to_lowercase(), output_format.extensions_str().first().unwrap_or(&"png"));
+        let attachment_data = serenity::builder::CreateAttachment::bytes(output, filename);
+        let message_content = format!("**Before/After Comparison**\nLeft: Original | Right: {} flavor", selected_flavor.to_string().to_uppercase());
+        let message_builder = serenity::builder::CreateMessage::new().content(message_content);
+        progress_bar.set_message("📤 Uploading comparison image...");
+        info!("Uploading comparison image");
+        let _ = outbox.send_files(msg.channel_id, vec![attachment_data], message_builder).await;
+        progress_bar.finish_with_message("✅ Comparison image uploaded successfully!");
+        return;
+    }
+
+    let output = icc::tag_srgb_png(output);
+    let filename = format!("catppuccinified_{}.{}", selected_flavor.to_string().to_lowercase(), output_format.extensions_str().first().unwrap_or(&"png"));
+    let attachment_data = serenity::builder::CreateAttachment::bytes(output.clone(), filename.clone());
+    let mut message_content = format!("Here's your Catppuccinified image (Flavor: {})!", selected_flavor.to_string().to_uppercase());
+    if let Some(quality) = selected_quality {
+        message_content.push_str(&format!(" Quality: {}", quality));
+    }
+    if let Some(format) = selected_format {
+        message_content.push_str(&format!(" Format: {}", format.extensions_str().first().unwrap_or(&"unknown")));
+    }
+    if !pre_adjust.is_identity() {
+        message_content.push_str(&format!(" Adjusted: {}", pre_adjust.describe()));
+    }
+    if !tuning.is_default() {
+        message_content.push_str(&format!(" Tuned: {}", tuning.describe()));
+    }
+    if strict {
+        message_content.push_str(" Palette colors only.");
+    }
+    if let Some(note) = &downscale_note {
+        message_content.push_str(&format!(" {}", note));
+    }
+    let message_builder = serenity::builder::CreateMessage::new().content(message_content.clone());
+    progress_bar.set_message("📤 Uploading processed image...");
+    info!("Uploading processed image");
+    let upload = outbox.send_files(msg.channel_id, vec![attachment_data], message_builder);
+    if let Ok(sent) = timings.time_async("upload", upload).await {
+        timings.finish();
+        // The upload time is only known once it's sent, so the breakdown is edited in
+        if debug_timing {
+            let content = format!("{}\n⏱️ {}", message_content, timings.summary());
+            if let Err(e) = outbox.edit_message(sent.channel_id, sent.id, serenity::builder::EditMessage::new().content(content)).await {
+                warn!(error = %e, "Failed to add timings to the reply");
             }
         }
+        if let Err(e) = history::record(msg, &command, &image_bytes) {
+            warn!(error = %e, "Failed to record job history");
+        }
+        last_result::remember(msg.author.id, std::sync::Arc::new(image_bytes.to_vec()), selected_flavor, selected_algorithm, output);
     }
-} 
\ No newline at end of file
+    progress_bar.finish_with_message("✅ Image uploaded successfully!");
+}
+
+/// Reply to an SVG attachment with the recolored SVG and a PNG preview of it
+async fn recolor_svg_attachment(outbox: &dyn Outbox, fetcher: &dyn ImageFetcher, msg: &Message, attachment: &serenity::model::channel::Attachment, flavor: catppuccin::FlavorName) {
+    let bytes = match fetcher.fetch(&attachment.url, &mut |_, _| {}).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            let _ = responses::error(outbox, msg.channel_id, e).await;
+            return;
+        }
+    };
+    if bytes.len() > svg::MAX_FILE_BYTES {
+        let _ = responses::warning(outbox, msg.channel_id, format!("That SVG is {}; SVGs can be up to {}.", utils::format_file_size(bytes.len()), utils::format_file_size(svg::MAX_FILE_BYTES))).await;
+        return;
+    }
+    let Ok(text) = String::from_utf8(bytes) else {
+        let _ = responses::warning(outbox, msg.channel_id, "That SVG isn't UTF-8 text.").await;
+        return;
+    };
+    let rendered = jobs::run(Job::new("svg", msg), move || {
+        let rewritten = svg::recolor(&text, flavor);
+        let preview = svg::rasterize(&rewritten.text, svg::PREVIEW_SIZE).and_then(|preview| {
+            let mut output_buffer = std::io::Cursor::new(Vec::new());
+            preview.write_to(&mut output_buffer, image::ImageFormat::Png).map_err(|e| e.to_string())?;
+            Ok(output_buffer.into_inner())
+        });
+        (rewritten, preview)
+    }).await;
+    let (rewritten, preview) = match rendered {
+        Ok(rendered) => rendered,
+        Err(panic) => {
+            let _ = responses::error(outbox, msg.channel_id, panic.to_string()).await;
+            return;
+        }
+    };
+    let preview = match preview {
+        Ok(preview) => preview,
+        Err(e) => {
+            warn!(error = %e, "Failed to render SVG");
+            let _ = responses::error(outbox, msg.channel_id, BotError::Decode(e)).await;
+            return;
+        }
+    };
+    if rewritten.conversions.is_empty() {
+        let _ = responses::info(outbox, msg.channel_id, "No `fill`, `stroke` or other color values found in that SVG.").await;
+        return;
+    }
+    let stem = attachment.filename.rsplit_once('.').map_or(attachment.filename.as_str(), |(stem, _)| stem);
+    let message_content = format!(
+        "**Recolored `{}` with {}** ({} colors, {} replacements)",
+        attachment.filename, flavor.to_string().to_uppercase(), rewritten.conversions.len(), rewritten.replaced
+    );
+    let attachments = vec![
+        serenity::builder::CreateAttachment::bytes(rewritten.text.into_bytes(), utils::sanitize_filename(&format!("{}-catppuccin-{}.svg", stem, flavor.identifier()), "svg")),
+        serenity::builder::CreateAttachment::bytes(preview, utils::sanitize_filename(&format!("{}-catppuccin-{}.png", stem, flavor.identifier()), "png")),
+    ];
+    let message_builder = serenity::builder::CreateMessage::new().content(message_content);
+    let _ = outbox.send_files(msg.channel_id, attachments, message_builder).await;
+}
@@ -0,0 +1,481 @@
+// src/config.rs
+
+use catppuccin::FlavorName;
+use once_cell::sync::Lazy;
+use serenity::json::{json, Value};
+use serenity::model::id::{ChannelId, GuildId, UserId};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use tracing::warn;
+
+// Where online/offline and maintenance notices went before they were configurable
+const DEFAULT_NOTICE_CHANNELS: [u64; 2] = [1393064541063221319, 465193124852138011];
+
+// The area of the 4096x4096 still the bot used to be limited to
+const DEFAULT_MAX_IMAGE_PIXELS: u64 = 4096 * 4096;
+
+// About 300 frames of 576x576
+const DEFAULT_MAX_GIF_PIXELS: u64 = 100_000_000;
+
+// Room for a couple of the largest GIFs at once
+const DEFAULT_MAX_JOB_MEMORY_MB: u64 = 2048;
+
+/// Bot-wide settings from the environment and `.env`. `!cat admin reload` re-reads them without a restart.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    /// Users allowed to run `!cat admin`, in addition to the application's owner
+    pub owners: Vec<UserId>,
+    /// Channels that get online/offline and maintenance notices
+    pub notice_channels: Vec<ChannelId>,
+    /// Moderation endpoint images are checked against in servers with a content policy
+    pub content_check_url: Option<String>,
+    /// Bearer token sent to the moderation endpoint
+    pub content_check_token: Option<String>,
+    /// Folder of vetted images that `stock` schedules pick from
+    pub daily_image_dir: Option<PathBuf>,
+    /// Endpoint that answers with an image, used by `stock` schedules when there's no folder
+    pub daily_image_url: Option<String>,
+    /// Headless-browser endpoint that `!cat shot` asks for webpage screenshots
+    pub screenshot_url: Option<String>,
+    /// Bearer token sent to the screenshot endpoint
+    pub screenshot_token: Option<String>,
+    /// Sites `!cat shot` may capture, with their subdomains; none means nothing can be captured
+    pub screenshot_hosts: Vec<String>,
+    /// Most pixels a still image may have
+    pub max_image_pixels: u64,
+    /// Most pixels a GIF may have across all of its frames
+    pub max_gif_pixels: u64,
+    /// Most bytes in-flight jobs may hold (decoded frames and LUTs) before new ones are queued
+    pub max_job_memory: u64,
+    /// Where members' recent conversions are saved for `!cat history`
+    pub history_file: PathBuf,
+}
+
+impl Config {
+    fn from_env() -> Self {
+        let var = |name: &str| std::env::var(name).ok();
+        let owners = var("OWNER_IDS").map(|s| parse_ids(&s)).unwrap_or_default();
+        let notice_channels = var("NOTICE_CHANNEL_IDS").map(|s| parse_ids(&s)).unwrap_or_else(|| DEFAULT_NOTICE_CHANNELS.to_vec());
+        Config {
+            owners: owners.into_iter().map(UserId::new).collect(),
+            notice_channels: notice_channels.into_iter().map(ChannelId::new).collect(),
+            content_check_url: var("CONTENT_CHECK_URL").filter(|url| !url.trim().is_empty()),
+            content_check_token: var("CONTENT_CHECK_TOKEN").filter(|token| !token.trim().is_empty()),
+            daily_image_dir: var("DAILY_IMAGE_DIR").filter(|dir| !dir.trim().is_empty()).map(PathBuf::from),
+            daily_image_url: var("DAILY_IMAGE_URL").filter(|url| !url.trim().is_empty()),
+            screenshot_url: var("SCREENSHOT_URL").filter(|url| !url.trim().is_empty()),
+            screenshot_token: var("SCREENSHOT_TOKEN").filter(|token| !token.trim().is_empty()),
+            screenshot_hosts: var("SCREENSHOT_ALLOWED_HOSTS").map(|s| parse_hosts(&s)).unwrap_or_default(),
+            max_image_pixels: var("MAX_IMAGE_PIXELS").and_then(|s| s.trim().parse().ok()).filter(|n| *n > 0).unwrap_or(DEFAULT_MAX_IMAGE_PIXELS),
+            max_gif_pixels: var("MAX_GIF_PIXELS").and_then(|s| s.trim().parse().ok()).filter(|n| *n > 0).unwrap_or(DEFAULT_MAX_GIF_PIXELS),
+            max_job_memory: var("MAX_JOB_MEMORY_MB").and_then(|s| s.trim().parse().ok()).filter(|n| *n > 0).unwrap_or(DEFAULT_MAX_JOB_MEMORY_MB) * 1024 * 1024,
+            history_file: var("JOB_HISTORY_FILE").filter(|path| !path.trim().is_empty()).map_or_else(|| PathBuf::from("history.json"), PathBuf::from),
+        }
+    }
+}
+
+static CONFIG: Lazy<RwLock<Arc<Config>>> = Lazy::new(|| RwLock::new(Arc::new(Config::from_env())));
+
+/// A comma- or space-separated list of Discord IDs; anything that isn't one is skipped
+fn parse_ids(s: &str) -> Vec<u64> {
+    s.split([',', ' ']).filter_map(|id| id.trim().parse::<u64>().ok()).filter(|id| *id != 0).collect()
+}
+
+/// A comma- or space-separated list of host names, lowercased, without a leading `*.` or trailing dot
+fn parse_hosts(s: &str) -> Vec<String> {
+    s.split([',', ' '])
+        .map(|host| host.trim().trim_start_matches("*.").trim_end_matches('.').to_lowercase())
+        .filter(|host| !host.is_empty())
+        .collect()
+}
+
+/// The current settings
+pub fn get() -> Arc<Config> {
+    CONFIG.read().unwrap().clone()
+}
+
+/// Use `config` as the current settings in place of the ones read from the environment, for
+/// programs and tests that run the bot's commands with settings of their own
+pub fn set(config: Config) {
+    *CONFIG.write().unwrap() = Arc::new(config);
+}
+
+/// Re-read `.env` (its values replace the ones loaded at startup) and rebuild the settings.
+/// Server settings are re-read from their file too, in case it was edited by hand.
+pub fn reload() -> Result<Arc<Config>, String> {
+    match dotenv::dotenv_iter() {
+        Ok(vars) => {
+            for var in vars {
+                let (key, value) = var.map_err(|e| format!("Failed to parse .env: {e}"))?;
+                std::env::set_var(key, value);
+            }
+        }
+        // Settings can come from the environment alone
+        Err(e) if e.not_found() => {}
+        Err(e) => return Err(format!("Failed to read .env: {e}")),
+    }
+    let config = Arc::new(Config::from_env());
+    *CONFIG.write().unwrap() = config.clone();
+    *GUILDS.write().unwrap() = load_guilds();
+    Ok(config)
+}
+
+/// What a server does with images the content check flags
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ContentPolicy {
+    /// Images aren't checked
+    #[default]
+    Off,
+    /// Flagged images are only processed in NSFW channels; images in NSFW channels aren't checked
+    NsfwChannels,
+    /// Flagged images are never processed
+    Strict,
+}
+
+impl ContentPolicy {
+    pub fn name(self) -> &'static str {
+        match self {
+            ContentPolicy::Off => "off",
+            ContentPolicy::NsfwChannels => "nsfw",
+            ContentPolicy::Strict => "strict",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "off" => Some(ContentPolicy::Off),
+            "nsfw" => Some(ContentPolicy::NsfwChannels),
+            "strict" => Some(ContentPolicy::Strict),
+            _ => None,
+        }
+    }
+}
+
+/// How often a scheduled batch runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cadence {
+    Daily,
+    Weekly,
+}
+
+impl Cadence {
+    pub fn name(self) -> &'static str {
+        match self {
+            Cadence::Daily => "daily",
+            Cadence::Weekly => "weekly",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "daily" => Some(Cadence::Daily),
+            "weekly" => Some(Cadence::Weekly),
+            _ => None,
+        }
+    }
+
+    /// Seconds between runs
+    pub fn period_secs(self) -> u64 {
+        match self {
+            Cadence::Daily => 24 * 60 * 60,
+            Cadence::Weekly => 7 * 24 * 60 * 60,
+        }
+    }
+}
+
+/// Which of a channel's images a scheduled batch picks up
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ScheduleSource {
+    /// Images posted since the last run
+    #[default]
+    New,
+    /// The channel's pinned images, every run
+    Pins,
+    /// One random image from the bot's stock source, in a random flavor
+    Stock,
+}
+
+impl ScheduleSource {
+    pub fn name(self) -> &'static str {
+        match self {
+            ScheduleSource::New => "new",
+            ScheduleSource::Pins => "pins",
+            ScheduleSource::Stock => "stock",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "new" => Some(ScheduleSource::New),
+            "pins" => Some(ScheduleSource::Pins),
+            "stock" => Some(ScheduleSource::Stock),
+            _ => None,
+        }
+    }
+}
+
+/// A recurring batch that catppuccinifies a channel's images (or a stock image) and posts them there
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schedule {
+    pub cadence: Cadence,
+    pub flavor: FlavorName,
+    pub source: ScheduleSource,
+    /// When it last ran (or was set up), in Unix seconds; saved so restarts don't reset the cadence
+    pub last_run: u64,
+}
+
+/// What a server's watermark shows
+#[derive(Debug, Clone, PartialEq)]
+pub enum WatermarkMark {
+    Text(String),
+    /// The server's icon, fetched when an image is processed so it follows icon changes
+    ServerIcon,
+}
+
+/// A mark stamped in the corner of a server's processed images
+#[derive(Debug, Clone, PartialEq)]
+pub struct Watermark {
+    pub mark: WatermarkMark,
+    /// Palette color name for text, resolved in each image's flavor
+    pub color: String,
+    /// 1 to 100 percent
+    pub opacity: u8,
+}
+
+/// Per-server settings, chosen in the setup panel
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GuildSettings {
+    /// Flavor for image processing when a command doesn't name one (Latte otherwise)
+    pub default_flavor: Option<FlavorName>,
+    /// Where the bot posts announcements and maintenance notices for this server
+    pub announcement_channel: Option<ChannelId>,
+    /// Channels the bot answers commands in; empty means every channel
+    pub allowed_channels: Vec<ChannelId>,
+    /// Channels where every posted image is catppuccinified without a command, with the flavor to use
+    pub auto_channels: HashMap<ChannelId, FlavorName>,
+    /// Members who opted out of automatic processing
+    pub auto_opt_out: Vec<UserId>,
+    pub content_policy: ContentPolicy,
+    /// Recurring batches, by the channel they work on
+    pub schedules: HashMap<ChannelId, Schedule>,
+    pub watermark: Option<Watermark>,
+    /// Shrink images over the size limit to fit instead of refusing them
+    pub autoscale: bool,
+}
+
+// Kept in memory and written back to `GUILD_CONFIG_FILE` on every change
+static GUILDS: Lazy<RwLock<HashMap<GuildId, GuildSettings>>> = Lazy::new(|| RwLock::new(load_guilds()));
+
+fn guilds_path() -> PathBuf {
+    std::env::var("GUILD_CONFIG_FILE").map(PathBuf::from).unwrap_or_else(|_| PathBuf::from("guilds.json"))
+}
+
+fn load_guilds() -> HashMap<GuildId, GuildSettings> {
+    let Ok(text) = std::fs::read_to_string(guilds_path()) else {
+        return HashMap::new();
+    };
+    match serenity::json::from_str::<Value>(text) {
+        Ok(value) => guilds_from_json(&value),
+        Err(e) => {
+            warn!(error = %e, "Failed to parse server settings; starting without them");
+            HashMap::new()
+        }
+    }
+}
+
+// IDs are stored as strings, as Discord's API does, so they survive tools that read numbers as doubles
+fn guilds_to_json(guilds: &HashMap<GuildId, GuildSettings>) -> Value {
+    let entries = guilds.iter().map(|(guild_id, settings)| {
+        let auto_channels: serenity::json::JsonMap =
+            settings.auto_channels.iter().map(|(channel, flavor)| (channel.to_string(), json!(flavor.identifier()))).collect();
+        let schedules: serenity::json::JsonMap = settings
+            .schedules
+            .iter()
+            .map(|(channel, schedule)| {
+                let schedule = json!({
+                    "cadence": schedule.cadence.name(),
+                    "flavor": schedule.flavor.identifier(),
+                    "source": schedule.source.name(),
+                    "last_run": schedule.last_run,
+                });
+                (channel.to_string(), schedule)
+            })
+            .collect();
+        let watermark = settings.watermark.as_ref().map(|w| {
+            let mut watermark = json!({ "color": w.color, "opacity": w.opacity });
+            match &w.mark {
+                WatermarkMark::Text(text) => watermark["text"] = json!(text),
+                WatermarkMark::ServerIcon => watermark["icon"] = json!(true),
+            }
+            watermark
+        });
+        let settings = json!({
+            "default_flavor": settings.default_flavor.map(|f| f.identifier()),
+            "announcement_channel": settings.announcement_channel.map(|c| c.to_string()),
+            "allowed_channels": settings.allowed_channels.iter().map(|c| c.to_string()).collect::<Vec<_>>(),
+            "auto_channels": auto_channels,
+            "auto_opt_out": settings.auto_opt_out.iter().map(|u| u.to_string()).collect::<Vec<_>>(),
+            "content_policy": settings.content_policy.name(),
+            "schedules": schedules,
+            "watermark": watermark,
+            "autoscale": settings.autoscale,
+        });
+        (guild_id.to_string(), settings)
+    });
+    Value::Object(entries.collect())
+}
+
+// Unknown or malformed fields are left at their defaults rather than failing the whole file
+fn guilds_from_json(value: &Value) -> HashMap<GuildId, GuildSettings> {
+    let id = |v: &Value| v.as_str()?.parse::<u64>().ok().filter(|id| *id != 0);
+    let ids = |v: Option<&Value>| v.and_then(Value::as_array).map(|ids| ids.iter().filter_map(id).collect::<Vec<_>>()).unwrap_or_default();
+    let Some(object) = value.as_object() else {
+        return HashMap::new();
+    };
+    object
+        .iter()
+        .filter_map(|(guild_id, settings)| {
+            let guild_id = GuildId::new(guild_id.parse::<u64>().ok().filter(|id| *id != 0)?);
+            let settings = GuildSettings {
+                default_flavor: settings.get("default_flavor").and_then(Value::as_str).and_then(crate::utils::parse_flavor),
+                announcement_channel: settings.get("announcement_channel").and_then(id).map(ChannelId::new),
+                allowed_channels: ids(settings.get("allowed_channels")).into_iter().map(ChannelId::new).collect(),
+                auto_channels: settings
+                    .get("auto_channels")
+                    .and_then(Value::as_object)
+                    .map(|channels| {
+                        channels
+                            .iter()
+                            .filter_map(|(channel, flavor)| {
+                                let channel = channel.parse::<u64>().ok().filter(|id| *id != 0)?;
+                                Some((ChannelId::new(channel), crate::utils::parse_flavor(flavor.as_str()?)?))
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+                auto_opt_out: ids(settings.get("auto_opt_out")).into_iter().map(UserId::new).collect(),
+                content_policy: settings.get("content_policy").and_then(Value::as_str).and_then(ContentPolicy::parse).unwrap_or_default(),
+                schedules: settings
+                    .get("schedules")
+                    .and_then(Value::as_object)
+                    .map(|schedules| {
+                        schedules
+                            .iter()
+                            .filter_map(|(channel, schedule)| {
+                                let channel = channel.parse::<u64>().ok().filter(|id| *id != 0)?;
+                                let schedule = Schedule {
+                                    cadence: Cadence::parse(schedule.get("cadence")?.as_str()?)?,
+                                    flavor: crate::utils::parse_flavor(schedule.get("flavor")?.as_str()?)?,
+                                    source: schedule.get("source").and_then(Value::as_str).and_then(ScheduleSource::parse).unwrap_or_default(),
+                                    last_run: schedule.get("last_run").and_then(Value::as_u64).unwrap_or(0),
+                                };
+                                Some((ChannelId::new(channel), schedule))
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+                watermark: settings.get("watermark").and_then(|w| {
+                    let mark = match w.get("text").and_then(Value::as_str) {
+                        Some(text) => WatermarkMark::Text(text.to_string()),
+                        None if w.get("icon").and_then(Value::as_bool) == Some(true) => WatermarkMark::ServerIcon,
+                        None => return None,
+                    };
+                    let color = w.get("color").and_then(Value::as_str).filter(|c| crate::utils::CATPPUCCIN_COLOR_NAMES.contains(c))?;
+                    let opacity = w.get("opacity").and_then(Value::as_u64).filter(|o| (1..=100).contains(o))?;
+                    Some(Watermark { mark, color: color.to_string(), opacity: opacity as u8 })
+                }),
+                autoscale: settings.get("autoscale").and_then(Value::as_bool).unwrap_or(false),
+            };
+            Some((guild_id, settings))
+        })
+        .collect()
+}
+
+/// A server's settings; defaults if it has none
+pub fn guild(guild_id: GuildId) -> GuildSettings {
+    GUILDS.read().unwrap().get(&guild_id).cloned().unwrap_or_default()
+}
+
+/// Change a server's settings and save them. The change is kept in memory even if saving fails.
+pub fn update_guild(guild_id: GuildId, change: impl FnOnce(&mut GuildSettings)) -> Result<GuildSettings, String> {
+    let mut guilds = GUILDS.write().unwrap();
+    let settings = guilds.entry(guild_id).or_default();
+    change(settings);
+    let updated = settings.clone();
+    let text = serenity::json::to_string_pretty(&guilds_to_json(&guilds)).map_err(|e| e.to_string())?;
+    std::fs::write(guilds_path(), text).map_err(|e| format!("Failed to save server settings: {e}"))?;
+    Ok(updated)
+}
+
+/// The flavor image processing uses when a command doesn't name one
+pub fn default_flavor(guild_id: Option<GuildId>) -> FlavorName {
+    guild_id.and_then(|id| guild(id).default_flavor).unwrap_or(FlavorName::Latte)
+}
+
+/// Whether the bot answers commands in a channel; direct messages are always allowed
+pub fn channel_allowed(guild_id: Option<GuildId>, channel_id: ChannelId) -> bool {
+    guild_id.is_none_or(|id| {
+        let allowed = guild(id).allowed_channels;
+        allowed.is_empty() || allowed.contains(&channel_id)
+    })
+}
+
+/// Every scheduled batch, with the server and channel it belongs to
+pub fn schedules() -> Vec<(GuildId, ChannelId, Schedule)> {
+    let guilds = GUILDS.read().unwrap();
+    guilds
+        .iter()
+        .flat_map(|(guild_id, settings)| settings.schedules.iter().map(|(channel_id, schedule)| (*guild_id, *channel_id, schedule.clone())))
+        .collect()
+}
+
+/// Every server's announcement channel
+pub fn announcement_channels() -> Vec<ChannelId> {
+    GUILDS.read().unwrap().values().filter_map(|settings| settings.announcement_channel).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ids() {
+        assert_eq!(parse_ids("123, 456 789"), vec![123, 456, 789]);
+        assert_eq!(parse_ids("123,,abc,0"), vec![123]);
+        assert!(parse_ids("").is_empty());
+        assert_eq!(parse_hosts("catppuccin.com, *.GitHub.io. "), vec!["catppuccin.com", "github.io"]);
+    }
+
+    #[test]
+    fn test_guild_settings_round_trip() {
+        let mut guilds = HashMap::new();
+        guilds.insert(
+            GuildId::new(1),
+            GuildSettings {
+                default_flavor: Some(FlavorName::Frappe),
+                announcement_channel: Some(ChannelId::new(2)),
+                allowed_channels: vec![ChannelId::new(3), ChannelId::new(4)],
+                auto_channels: HashMap::from([(ChannelId::new(6), FlavorName::Mocha)]),
+                auto_opt_out: vec![UserId::new(9)],
+                content_policy: ContentPolicy::Strict,
+                schedules: HashMap::from([(
+                    ChannelId::new(10),
+                    Schedule { cadence: Cadence::Weekly, flavor: FlavorName::Macchiato, source: ScheduleSource::Pins, last_run: 1_700_000_000 },
+                )]),
+                watermark: Some(Watermark { mark: WatermarkMark::Text("art club".to_string()), color: "mauve".to_string(), opacity: 60 }),
+                autoscale: true,
+            },
+        );
+        guilds.insert(GuildId::new(5), GuildSettings::default());
+        guilds.insert(
+            GuildId::new(11),
+            GuildSettings { watermark: Some(Watermark { mark: WatermarkMark::ServerIcon, color: "text".to_string(), opacity: 100 }), ..Default::default() },
+        );
+        assert_eq!(guilds_from_json(&guilds_to_json(&guilds)), guilds);
+        // Bad values fall back to defaults instead of dropping the server
+        let parsed = guilds_from_json(&json!({ "7": { "default_flavor": "espresso", "allowed_channels": ["x", "8"] }, "nope": {} }));
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed[&GuildId::new(7)], GuildSettings { allowed_channels: vec![ChannelId::new(8)], ..Default::default() });
+    }
+}
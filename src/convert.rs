@@ -0,0 +1,177 @@
+// src/convert.rs
+
+use catppuccin::FlavorName;
+use image::{Rgba, RgbaImage};
+use serenity::json::{json, Value};
+
+/// Most colors one `!cat convert` maps; the rest are left off
+pub const MAX_COLORS: usize = 64;
+
+/// One color as it was given, and the palette color it maps to
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conversion {
+    pub input: String,
+    pub rgb: (u8, u8, u8),
+    pub name: String,
+    pub matched: (u8, u8, u8),
+}
+
+impl Conversion {
+    /// Perceptual distance between the input and its match
+    pub fn delta_e(&self) -> f32 {
+        crate::image_processing::delta_e(self.rgb, self.matched)
+    }
+}
+
+fn hex((r, g, b): (u8, u8, u8)) -> String {
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+// Every string value in a valid JSON document, in the order it's written. The text is scanned rather
+// than parsed into a `Value`, whose objects sort their keys. Object keys are names, not colors, so
+// they're skipped.
+fn json_strings(text: &str) -> Vec<String> {
+    let mut strings = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('"') {
+        let after = &rest[start + 1..];
+        // The closing quote is the first one not escaped by a backslash
+        let mut escaped = false;
+        let Some(len) = after.find(|c| {
+            let closes = c == '"' && !escaped;
+            escaped = c == '\\' && !escaped;
+            closes
+        }) else {
+            break;
+        };
+        let literal = &rest[start..start + len + 2];
+        rest = &after[len + 1..];
+        if rest.trim_start().starts_with(':') {
+            continue;
+        }
+        if let Ok(string) = serenity::json::from_str::<String>(literal) {
+            strings.push(string);
+        }
+    }
+    strings
+}
+
+/// The colors in a list of colors: a JSON document (any string value that's a color), or plain text
+/// with colors separated by whitespace, commas, or newlines. Repeats of a color are kept once.
+pub fn colors_in_text(text: &str) -> Vec<(String, (u8, u8, u8))> {
+    let trimmed = text.trim_start();
+    let is_json = (trimmed.starts_with('{') || trimmed.starts_with('[')) && serenity::json::from_str::<Value>(text).is_ok();
+    let tokens: Vec<String> = if is_json {
+        json_strings(text)
+    } else {
+        text.lines().flat_map(crate::utils::split_args).map(str::to_string).collect()
+    };
+    let mut colors: Vec<(String, (u8, u8, u8))> = Vec::new();
+    for token in &tokens {
+        let token = token.trim().trim_matches(|c| matches!(c, ',' | ';' | '"' | '\''));
+        let Some(rgb) = crate::utils::parse_color_literal(token) else { continue };
+        if !colors.iter().any(|(_, seen)| *seen == rgb) {
+            colors.push((token.to_string(), rgb));
+        }
+    }
+    colors
+}
+
+/// Map each color to the flavor's closest named color
+pub fn convert_colors(colors: &[(String, (u8, u8, u8))], flavor: FlavorName) -> Vec<Conversion> {
+    colors
+        .iter()
+        .filter_map(|(input, rgb)| {
+            let (name, matched_hex) = crate::utils::find_closest_catppuccin_hex(&hex(*rgb), flavor)?;
+            let matched = crate::utils::parse_hex_color(&matched_hex)?;
+            Some(Conversion { input: input.clone(), rgb: *rgb, name, matched })
+        })
+        .collect()
+}
+
+/// A table on the flavor's base, one row per color: its swatch and hex, then its match's swatch, name and hex
+pub fn render_conversion_table(conversions: &[Conversion], flavor: FlavorName) -> RgbaImage {
+    use crate::text_rendering::{draw_text, fill_rect};
+    let swatch: u32 = 32;
+    let margin: u32 = 10;
+    let label_size = 18.0;
+    let column: u32 = 110;
+    let width = margin + swatch + margin + column + margin + swatch + margin + column * 2 + margin;
+    let height = margin + (conversions.len().max(1) as u32) * (swatch + margin);
+    let rgba = |(r, g, b): (u8, u8, u8)| Rgba([r, g, b, 255]);
+    let base = crate::utils::catppuccin_color_name_to_rgb("base", flavor).unwrap();
+    let text = crate::utils::catppuccin_color_name_to_rgb("text", flavor).unwrap();
+    let subtext = crate::utils::catppuccin_color_name_to_rgb("subtext0", flavor).unwrap();
+    let mut img = RgbaImage::from_pixel(width, height, rgba(base));
+    for (i, conversion) in conversions.iter().enumerate() {
+        let y = margin + i as u32 * (swatch + margin);
+        let label_y = (y + swatch / 2) as i32 - 9;
+        let mut x = margin;
+        fill_rect(&mut img, x as i32, y as i32, swatch, swatch, rgba(conversion.rgb));
+        x += swatch + margin;
+        draw_text(&mut img, &hex(conversion.rgb), x as i32, label_y, label_size, subtext);
+        x += column + margin;
+        fill_rect(&mut img, x as i32, y as i32, swatch, swatch, rgba(conversion.matched));
+        x += swatch + margin;
+        draw_text(&mut img, &format!("{} {}", conversion.name, hex(conversion.matched)), x as i32, label_y, label_size, text);
+    }
+    img
+}
+
+/// The mappings as JSON, for porting a theme by script
+pub fn mappings_json(conversions: &[Conversion], flavor: FlavorName) -> Vec<u8> {
+    let mappings: Vec<Value> = conversions
+        .iter()
+        .map(|c| {
+            json!({
+                "input": c.input,
+                "hex": hex(c.rgb),
+                "name": c.name,
+                "catppuccin": hex(c.matched),
+                "delta_e": (c.delta_e() * 10.0).round() / 10.0,
+            })
+        })
+        .collect();
+    let document = json!({ "flavor": flavor.identifier(), "mappings": mappings });
+    // Serializing a tree built from strings and numbers can't fail
+    serenity::json::to_vec_pretty(&document).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_colors_in_text() {
+        let text = "#ff0000, #00ff00\nrgb(17, 34, 51); tomato\n#FF0000 not-a-color";
+        let colors = colors_in_text(text);
+        let inputs: Vec<&str> = colors.iter().map(|(input, _)| input.as_str()).collect();
+        assert_eq!(inputs, vec!["#ff0000", "#00ff00", "rgb(17, 34, 51)", "tomato"]);
+        let json = r##"{ "name": "theme", "colors": { "bg": "#1e1e2e", "accents": ["#cba6f7", "hsl(0, 100%, 50%)"] } }"##;
+        let colors = colors_in_text(json);
+        assert_eq!(colors.iter().map(|(_, rgb)| *rgb).collect::<Vec<_>>(), vec![(30, 30, 46), (203, 166, 247), (255, 0, 0)]);
+        // Escaped quotes don't end a string, and a key that's a color isn't one
+        let json = r##"{ "note": "say \"#123456\": hi", "#ffffff": "blue" }"##;
+        assert_eq!(colors_in_text(json).iter().map(|(input, _)| input.as_str()).collect::<Vec<_>>(), vec!["blue"]);
+    }
+
+    #[test]
+    fn test_convert_colors_and_json() {
+        let mauve = crate::utils::catppuccin_color_name_to_rgb("mauve", FlavorName::Mocha).unwrap();
+        let conversions = convert_colors(&[("#cba6f7".to_string(), mauve)], FlavorName::Mocha);
+        assert_eq!(conversions[0].name, "mauve");
+        assert_eq!(conversions[0].matched, mauve);
+        let parsed: Value = serenity::json::from_slice(&mappings_json(&conversions, FlavorName::Mocha)).unwrap();
+        assert_eq!(parsed["flavor"], "mocha");
+        assert_eq!(parsed["mappings"][0]["catppuccin"], "#cba6f7");
+        assert_eq!(parsed["mappings"][0]["delta_e"], 0.0);
+    }
+
+    #[test]
+    fn test_render_conversion_table() {
+        let conversions = convert_colors(&[("#ff0000".to_string(), (255, 0, 0)), ("#00ff00".to_string(), (0, 255, 0))], FlavorName::Mocha);
+        let img = render_conversion_table(&conversions, FlavorName::Mocha);
+        assert_eq!(img.height(), 10 + 2 * (32 + 10));
+        assert_eq!(img.get_pixel(10 + 16, 10 + 42 + 16).0, [0, 255, 0, 255]);
+    }
+}
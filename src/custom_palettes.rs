@@ -0,0 +1,240 @@
+// src/custom_palettes.rs
+
+use once_cell::sync::Lazy;
+use serenity::json::{json, Value};
+use serenity::model::id::UserId;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::RwLock;
+use tracing::warn;
+
+/// Most colors one imported palette keeps
+pub const MAX_COLORS: usize = 32;
+/// Most palettes one member can keep; importing another under a new name past this is refused
+pub const MAX_PALETTES: usize = 25;
+
+/// A palette a member imported, saved under a name of their choosing
+#[derive(Debug, Clone, PartialEq)]
+pub struct CustomPalette {
+    pub name: String,
+    pub colors: Vec<(u8, u8, u8)>,
+}
+
+// Kept in memory and written back to `CUSTOM_PALETTE_FILE` on every change
+static PALETTES: Lazy<RwLock<HashMap<UserId, Vec<CustomPalette>>>> = Lazy::new(|| RwLock::new(load_palettes()));
+
+fn palettes_path() -> PathBuf {
+    std::env::var("CUSTOM_PALETTE_FILE").map(PathBuf::from).unwrap_or_else(|_| PathBuf::from("palettes.json"))
+}
+
+fn load_palettes() -> HashMap<UserId, Vec<CustomPalette>> {
+    let Ok(text) = std::fs::read_to_string(palettes_path()) else {
+        return HashMap::new();
+    };
+    match serenity::json::from_str::<Value>(text) {
+        Ok(value) => palettes_from_json(&value),
+        Err(e) => {
+            warn!(error = %e, "Failed to parse custom palettes; starting without them");
+            HashMap::new()
+        }
+    }
+}
+
+fn hex((r, g, b): (u8, u8, u8)) -> String {
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+fn palettes_to_json(palettes: &HashMap<UserId, Vec<CustomPalette>>) -> Value {
+    let entries = palettes.iter().map(|(user_id, palettes)| {
+        let palettes: Vec<Value> = palettes
+            .iter()
+            .map(|p| json!({ "name": p.name, "colors": p.colors.iter().map(|c| hex(*c)).collect::<Vec<_>>() }))
+            .collect();
+        (user_id.to_string(), Value::Array(palettes))
+    });
+    Value::Object(entries.collect())
+}
+
+// Palettes with a missing name or no readable colors are dropped; the rest of the file still loads
+fn palettes_from_json(value: &Value) -> HashMap<UserId, Vec<CustomPalette>> {
+    let Some(object) = value.as_object() else {
+        return HashMap::new();
+    };
+    object
+        .iter()
+        .filter_map(|(user_id, palettes)| {
+            let user_id = UserId::new(user_id.parse::<u64>().ok().filter(|id| *id != 0)?);
+            let palettes = palettes
+                .as_array()?
+                .iter()
+                .filter_map(|p| {
+                    let name = p.get("name")?.as_str()?.to_string();
+                    let colors = parse_hex_list(p.get("colors")?)?;
+                    Some(CustomPalette { name, colors })
+                })
+                .collect();
+            Some((user_id, palettes))
+        })
+        .collect()
+}
+
+// Every entry of a JSON array as a hex color, or None if any isn't one
+fn parse_hex_list(value: &Value) -> Option<Vec<(u8, u8, u8)>> {
+    let colors = value
+        .as_array()?
+        .iter()
+        .map(|c| c.as_str().and_then(crate::utils::parse_hex_color))
+        .collect::<Option<Vec<_>>>()?;
+    (!colors.is_empty()).then_some(colors)
+}
+
+/// A member's palettes, in the order they were imported
+pub fn list(user_id: UserId) -> Vec<CustomPalette> {
+    PALETTES.read().unwrap().get(&user_id).cloned().unwrap_or_default()
+}
+
+/// Save a palette for a member, replacing one with the same name, and write the file.
+/// The change is kept in memory even if saving fails.
+pub fn save(user_id: UserId, palette: CustomPalette) -> Result<(), String> {
+    let mut palettes = PALETTES.write().unwrap();
+    let saved = palettes.entry(user_id).or_default();
+    match saved.iter().position(|p| p.name == palette.name) {
+        Some(i) => saved[i] = palette,
+        None if saved.len() >= MAX_PALETTES => {
+            return Err(format!("You already have {} saved palettes. Re-import under an existing name to replace one.", MAX_PALETTES));
+        }
+        None => saved.push(palette),
+    }
+    let text = serenity::json::to_string_pretty(&palettes_to_json(&palettes)).map_err(|e| e.to_string())?;
+    std::fs::write(palettes_path(), text).map_err(|e| format!("Failed to save your palette: {e}"))
+}
+
+/// Lowercase letters, digits, `-` and `_`, at most 32 characters; None if nothing usable is left
+pub fn sanitize_name(name: &str) -> Option<String> {
+    let name: String = name
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_whitespace() { '-' } else { c })
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+        .take(32)
+        .collect();
+    let name = name.trim_matches('-').to_string();
+    (!name.is_empty()).then_some(name)
+}
+
+/// Colors from a coolors.co palette link, e.g. `https://coolors.co/264653-2a9d8f-e9c46a`
+/// or `https://coolors.co/palette/264653-2a9d8f-e9c46a`
+pub fn parse_coolors_url(url: &str) -> Option<Vec<(u8, u8, u8)>> {
+    let rest = url.strip_prefix("https://").or_else(|| url.strip_prefix("http://"))?;
+    let path = rest.strip_prefix("www.").unwrap_or(rest).strip_prefix("coolors.co/")?;
+    let path = path.split(['?', '#']).next()?.trim_end_matches('/');
+    let segment = path.rsplit('/').next()?;
+    let colors = segment
+        .split('-')
+        .map(|h| if h.len() == 6 { crate::utils::parse_hex_color(h) } else { None })
+        .collect::<Option<Vec<_>>>()?;
+    (!colors.is_empty()).then_some(colors)
+}
+
+// First byte of every share code; bump it when the layout changes so older codes are still recognized
+const SHARE_CODE_VERSION: u8 = 1;
+
+/// A short code for sharing a palette: base64url of a version byte, the name's length and bytes,
+/// then three bytes per color
+pub fn encode_share_code(palette: &CustomPalette) -> String {
+    use base64::Engine;
+    let name = &palette.name.as_bytes()[..palette.name.len().min(u8::MAX as usize)];
+    let mut bytes = vec![SHARE_CODE_VERSION, name.len() as u8];
+    bytes.extend_from_slice(name);
+    for &(r, g, b) in palette.colors.iter().take(MAX_COLORS) {
+        bytes.extend_from_slice(&[r, g, b]);
+    }
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// Read a share code made by [`encode_share_code`]
+pub fn decode_share_code(code: &str) -> Result<CustomPalette, String> {
+    use base64::Engine;
+    let invalid = || "That isn't a valid palette code.".to_string();
+    let bytes = base64::engine::general_purpose::URL_SAFE_NO_PAD.decode(code.trim().trim_matches('`')).map_err(|_| invalid())?;
+    match bytes.first() {
+        Some(&SHARE_CODE_VERSION) => {}
+        Some(&version) if version > SHARE_CODE_VERSION => {
+            return Err("That palette code was made by a newer version of the bot.".to_string());
+        }
+        _ => return Err(invalid()),
+    }
+    let name_len = *bytes.get(1).ok_or_else(invalid)? as usize;
+    let name = bytes.get(2..2 + name_len).ok_or_else(invalid)?;
+    let name = sanitize_name(&String::from_utf8_lossy(name)).unwrap_or_else(|| "shared".to_string());
+    let colors = &bytes[2 + name_len..];
+    if colors.is_empty() || colors.len() % 3 != 0 || colors.len() / 3 > MAX_COLORS {
+        return Err(invalid());
+    }
+    let colors = colors.chunks(3).map(|c| (c[0], c[1], c[2])).collect();
+    Ok(CustomPalette { name, colors })
+}
+
+/// A palette as read from an export: the name it was given there, if any, and its colors
+pub type Imported = (Option<String>, Vec<(u8, u8, u8)>);
+
+/// Colors from a terminal.sexy JSON export: background and foreground, then the 16 terminal colors
+pub fn parse_terminal_sexy(text: &str) -> Option<Imported> {
+    let value: Value = serenity::json::from_str(text).ok()?;
+    let single = |key: &str| value.get(key).and_then(Value::as_str).and_then(crate::utils::parse_hex_color);
+    let mut colors: Vec<_> = [single("background"), single("foreground")].into_iter().flatten().collect();
+    colors.extend(parse_hex_list(value.get("color")?)?);
+    let name = value.get("name").and_then(Value::as_str).filter(|n| !n.trim().is_empty()).map(str::to_string);
+    Some((name, colors))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_coolors_url() {
+        let colors = parse_coolors_url("https://coolors.co/palette/264653-2a9d8f-e9c46a").unwrap();
+        assert_eq!(colors, vec![(0x26, 0x46, 0x53), (0x2a, 0x9d, 0x8f), (0xe9, 0xc4, 0x6a)]);
+        assert_eq!(parse_coolors_url("https://coolors.co/264653-2A9D8F?ref=x").unwrap().len(), 2);
+        assert!(parse_coolors_url("https://coolors.co/generate").is_none());
+        assert!(parse_coolors_url("https://example.com/264653-2a9d8f").is_none());
+    }
+
+    #[test]
+    fn test_parse_terminal_sexy() {
+        let text = r##"{ "name": "Tomorrow Night", "author": "", "color": ["#1d1f21", "#cc6666"], "foreground": "#c5c8c6", "background": "#1d1f21" }"##;
+        let (name, colors) = parse_terminal_sexy(text).unwrap();
+        assert_eq!(name.as_deref(), Some("Tomorrow Night"));
+        assert_eq!(colors, vec![(0x1d, 0x1f, 0x21), (0xc5, 0xc8, 0xc6), (0x1d, 0x1f, 0x21), (0xcc, 0x66, 0x66)]);
+        assert!(parse_terminal_sexy(r#"{ "color": ["nope"] }"#).is_none());
+        assert!(parse_terminal_sexy("not json").is_none());
+    }
+
+    #[test]
+    fn test_share_code_round_trip() {
+        let palette = CustomPalette { name: "sunset".into(), colors: vec![(255, 94, 77), (38, 70, 83), (233, 196, 106)] };
+        let code = encode_share_code(&palette);
+        assert!(code.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+        assert_eq!(decode_share_code(&code), Ok(palette));
+        // Codes from a later format version are told apart from garbage
+        use base64::Engine;
+        let future = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode([2, 0, 1, 2, 3]);
+        assert!(decode_share_code(&future).unwrap_err().contains("newer version"));
+        assert!(decode_share_code("not a code!").is_err());
+        let truncated = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode([1, 6, b's', b'u', b'n', b's', b'e', b't', 255, 94]);
+        assert!(decode_share_code(&truncated).is_err());
+    }
+
+    #[test]
+    fn test_palettes_round_trip() {
+        let mut palettes = HashMap::new();
+        palettes.insert(UserId::new(1), vec![CustomPalette { name: "sunset".into(), colors: vec![(255, 94, 77), (38, 70, 83)] }]);
+        assert_eq!(palettes_from_json(&palettes_to_json(&palettes)), palettes);
+        // A palette with a bad color is dropped without losing the member's others
+        let parsed = palettes_from_json(&json!({ "2": [{ "name": "a", "colors": ["#zzzzzz"] }, { "name": "b", "colors": ["#000000"] }] }));
+        assert_eq!(parsed[&UserId::new(2)].len(), 1);
+        assert_eq!(sanitize_name("My Palette!"), Some("my-palette".to_string()));
+        assert_eq!(sanitize_name("!!"), None);
+    }
+}
@@ -0,0 +1,243 @@
+// src/emoji_retheme.rs
+
+use crate::icons;
+use crate::image_processing;
+use crate::jobs::{self, Job};
+use crate::responses;
+use crate::setup;
+use crate::source;
+use catppuccin::FlavorName;
+use dashmap::DashMap;
+use image::{Rgba, RgbaImage};
+use once_cell::sync::Lazy;
+use serenity::builder::{CreateAttachment, CreateMessage};
+use serenity::json::json;
+use serenity::model::channel::Message;
+use serenity::model::id::{EmojiId, GuildId, RoleId};
+use serenity::prelude::Context;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// How long a preview can be applied for; after that the server's emojis may have changed
+const PREVIEW_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// Pause between re-uploads. Emoji creation has a much stricter rate limit than other routes, one
+/// the API doesn't announce up front, so uploads are spread out rather than left to hit it.
+const UPLOAD_DELAY: Duration = Duration::from_secs(2);
+
+/// Discord's size limit for an emoji image
+const MAX_EMOJI_BYTES: usize = 256 * 1024;
+
+/// Side of each emoji in the preview grid
+const THUMBNAIL: u32 = 64;
+/// Original and themed pairs per row of the preview grid
+const PAIRS_PER_ROW: u32 = 8;
+
+/// One emoji as it will be re-uploaded
+struct Themed {
+    id: EmojiId,
+    name: String,
+    roles: Vec<RoleId>,
+    png: Vec<u8>,
+}
+
+/// A previewed retheme waiting for `apply`. The PNGs shown are the ones uploaded.
+struct Pending {
+    flavor: FlavorName,
+    emojis: Vec<Themed>,
+    at: Instant,
+}
+
+// One pending retheme per server; a new preview replaces the last
+static PENDING: Lazy<DashMap<GuildId, Pending>> = Lazy::new(DashMap::new);
+
+/// A grid on the flavor's base of each emoji beside its themed version
+fn render_preview_grid(pairs: &[(RgbaImage, RgbaImage)], flavor: FlavorName) -> RgbaImage {
+    let gap: u32 = 4;
+    let margin: u32 = 12;
+    let pair_width = THUMBNAIL * 2 + gap;
+    let columns = (pairs.len() as u32).clamp(1, PAIRS_PER_ROW);
+    let rows = (pairs.len() as u32).div_ceil(PAIRS_PER_ROW).max(1);
+    let width = margin + columns * (pair_width + margin);
+    let height = margin + rows * (THUMBNAIL + margin);
+    let (r, g, b) = crate::utils::catppuccin_color_name_to_rgb("base", flavor).unwrap();
+    let mut grid = RgbaImage::from_pixel(width, height, Rgba([r, g, b, 255]));
+    for (i, (original, themed)) in pairs.iter().enumerate() {
+        let x = margin + (i as u32 % PAIRS_PER_ROW) * (pair_width + margin);
+        let y = margin + (i as u32 / PAIRS_PER_ROW) * (THUMBNAIL + margin);
+        for (offset, img) in [(0, original), (THUMBNAIL + gap, themed)] {
+            let thumbnail = image::imageops::resize(img, THUMBNAIL, THUMBNAIL, image::imageops::FilterType::Triangle);
+            image::imageops::overlay(&mut grid, &thumbnail, (x + offset) as i64, y as i64);
+        }
+    }
+    grid
+}
+
+fn is_fresh(at: Instant) -> bool {
+    at.elapsed() < PREVIEW_TTL
+}
+
+/// `!cat retheme-emojis <flavor> preview|apply [algorithm]`: theme every static custom emoji of the
+/// server. `preview` posts a before/after grid; `apply` then replaces each emoji with its themed
+/// version, keeping its name and role restrictions. Both need the Manage Expressions permission.
+pub async fn run(ctx: &Context, msg: &Message, parts: &[&str]) {
+    let Some(guild_id) = msg.guild_id else {
+        let _ = responses::warning(&ctx.http, msg.channel_id, "Server emojis can only be rethemed in a server.").await;
+        return;
+    };
+    if !setup::can_manage_emojis(&ctx.cache, msg) {
+        let _ = responses::warning(&ctx.http, msg.channel_id, "You need the **Manage Expressions** permission to retheme this server's emojis.").await;
+        return;
+    }
+    let flavor = parts.iter().find_map(|s| crate::utils::parse_flavor(s)).unwrap_or(FlavorName::Mocha);
+    let algorithm = parts.iter().find_map(|s| crate::utils::parse_algorithm(s)).unwrap_or("shepards-method");
+    if parts.iter().any(|p| p.eq_ignore_ascii_case("apply")) {
+        apply(ctx, msg, guild_id, flavor).await;
+    } else {
+        preview(ctx, msg, guild_id, flavor, algorithm).await;
+    }
+}
+
+async fn preview(ctx: &Context, msg: &Message, guild_id: GuildId, flavor: FlavorName, algorithm: &'static str) {
+    let emojis = match guild_id.emojis(&ctx.http).await {
+        Ok(emojis) => emojis,
+        Err(why) => {
+            warn!(?why, guild = %guild_id, "Failed to list server emojis");
+            let _ = responses::error(&ctx.http, msg.channel_id, "Couldn't list this server's emojis.").await;
+            return;
+        }
+    };
+    // Animated emojis would have to be rethemed frame by frame and rarely fit the size limit afterwards
+    let (animated, still): (Vec<_>, Vec<_>) = emojis.into_iter().partition(|e| e.animated);
+    if still.is_empty() {
+        let _ = responses::info(&ctx.http, msg.channel_id, "This server has no static custom emojis to retheme.").await;
+        return;
+    }
+    let _typing = msg.channel_id.start_typing(&ctx.http);
+    let mut originals = Vec::new();
+    let mut unreadable = 0;
+    for emoji in still {
+        let img = match source::fetch_url(&emoji.url()).await.map(|bytes| image::load_from_memory(&bytes)) {
+            Ok(Ok(img)) => img,
+            _ => {
+                unreadable += 1;
+                continue;
+            }
+        };
+        originals.push((emoji.id, emoji.name, emoji.roles, img));
+    }
+    let result = jobs::run(Job::new("retheme-emojis", msg), move || {
+        let mut pairs = Vec::new();
+        let mut themed = Vec::new();
+        for (id, name, roles, img) in originals {
+            let processed = image_processing::process_image_with_palette(&img, flavor, algorithm).to_rgba8();
+            let png = icons::emoji_png(&processed);
+            if png.len() > MAX_EMOJI_BYTES {
+                continue;
+            }
+            pairs.push((icons::fit_emoji(&img.to_rgba8()), icons::fit_emoji(&processed)));
+            themed.push(Themed { id, name, roles, png });
+        }
+        let mut grid_png = std::io::Cursor::new(Vec::new());
+        // Encoding an in-memory PNG can't fail
+        render_preview_grid(&pairs, flavor).write_to(&mut grid_png, image::ImageFormat::Png).unwrap();
+        (themed, grid_png.into_inner())
+    })
+    .await;
+    let (themed, grid_png) = match result {
+        Ok(done) => done,
+        Err(panic) => {
+            let _ = responses::error(&ctx.http, msg.channel_id, panic.to_string()).await;
+            return;
+        }
+    };
+    let mut notes = Vec::new();
+    if !animated.is_empty() {
+        notes.push(format!("{} animated skipped", animated.len()));
+    }
+    if unreadable > 0 {
+        notes.push(format!("{} couldn't be downloaded", unreadable));
+    }
+    let content = format!(
+        "**Emoji retheme preview** ({}): {} emojis{}\nRun `!cat retheme-emojis {} apply` within {} minutes to replace them. \
+         This takes about {} seconds and needs one free emoji slot.",
+        flavor,
+        themed.len(),
+        if notes.is_empty() { String::new() } else { format!(" ({})", notes.join(", ")) },
+        flavor.identifier(),
+        PREVIEW_TTL.as_secs() / 60,
+        themed.len() as u64 * UPLOAD_DELAY.as_secs(),
+    );
+    PENDING.insert(guild_id, Pending { flavor, emojis: themed, at: Instant::now() });
+    let attachment = CreateAttachment::bytes(grid_png, format!("emoji_retheme_{}.png", flavor.identifier()));
+    let _ = msg.channel_id.send_files(&ctx.http, vec![attachment], CreateMessage::new().content(content)).await;
+}
+
+async fn apply(ctx: &Context, msg: &Message, guild_id: GuildId, flavor: FlavorName) {
+    use base64::Engine;
+    // Taking the preview out of the map also keeps a second `apply` from running alongside this one
+    let Some((_, pending)) = PENDING.remove_if(&guild_id, |_, p| p.flavor == flavor && is_fresh(p.at)) else {
+        let text = format!("Run `!cat retheme-emojis {} preview` first, then apply it within {} minutes.", flavor.identifier(), PREVIEW_TTL.as_secs() / 60);
+        let _ = responses::warning(&ctx.http, msg.channel_id, text).await;
+        return;
+    };
+    let total = pending.emojis.len();
+    info!(guild = %guild_id, user = %msg.author.id, emojis = total, flavor = flavor.identifier(), "Retheming server emojis");
+    let _ = responses::info(&ctx.http, msg.channel_id, format!("Replacing {} emojis with their {} versions...", total, flavor)).await;
+    let reason = format!("Catppuccin {} retheme requested by {}", flavor, msg.author.name);
+    let mut replaced = 0;
+    let mut not_removed = Vec::new();
+    let mut stopped = None;
+    for (i, emoji) in pending.emojis.iter().enumerate() {
+        if i > 0 {
+            tokio::time::sleep(UPLOAD_DELAY).await;
+        }
+        let image = format!("data:image/png;base64,{}", base64::engine::general_purpose::STANDARD.encode(&emoji.png));
+        let map = json!({ "name": emoji.name, "image": image, "roles": emoji.roles });
+        // The new emoji goes up before the old one comes down, so a failure never loses an emoji
+        if let Err(why) = ctx.http.create_emoji(guild_id, &map, Some(&reason)).await {
+            warn!(?why, guild = %guild_id, emoji = %emoji.name, "Failed to upload rethemed emoji");
+            stopped = Some((emoji.name.clone(), why.to_string()));
+            break;
+        }
+        match ctx.http.delete_emoji(guild_id, emoji.id, Some(&reason)).await {
+            Ok(()) => replaced += 1,
+            Err(why) => {
+                warn!(?why, guild = %guild_id, emoji = %emoji.name, "Failed to remove original emoji");
+                not_removed.push(emoji.name.clone());
+            }
+        }
+    }
+    let mut text = format!("Replaced {} of {} emojis with their {} versions.", replaced, total, flavor);
+    if !not_removed.is_empty() {
+        text.push_str(&format!("\nThe originals of `{}` couldn't be removed, so both versions are there now.", not_removed.join("`, `")));
+    }
+    match stopped {
+        Some((name, why)) => {
+            text.push_str(&format!("\nStopped at `{}`: {}. The server may be out of emoji slots.", name, why));
+            let _ = responses::warning(&ctx.http, msg.channel_id, text).await;
+        }
+        None => {
+            let _ = responses::success(&ctx.http, msg.channel_id, text).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_preview_grid() {
+        let original = RgbaImage::from_pixel(128, 128, Rgba([255, 0, 0, 255]));
+        let themed = RgbaImage::from_pixel(128, 128, Rgba([243, 139, 168, 255]));
+        let pairs = vec![(original, themed); 9];
+        let grid = render_preview_grid(&pairs, FlavorName::Mocha);
+        // Nine pairs wrap onto a second row of the eight-wide grid
+        assert_eq!(grid.dimensions(), (12 + 8 * (132 + 12), 12 + 2 * (64 + 12)));
+        assert_eq!(grid.get_pixel(12 + 32, 12 + 32).0, [255, 0, 0, 255]);
+        assert_eq!(grid.get_pixel(12 + 68 + 32, 12 + 32).0, [243, 139, 168, 255]);
+        assert_eq!(grid.get_pixel(6, 6).0, [30, 30, 46, 255]);
+        assert!(is_fresh(Instant::now()));
+    }
+}
@@ -0,0 +1,85 @@
+// src/error.rs
+
+use crate::jobs::JobPanic;
+
+/// Why a job couldn't produce its result. Each kind has a short code shown in replies, so a report
+/// like "I got E-DEC" says what went wrong without a screenshot.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum BotError {
+    /// The image couldn't be fetched; the reason is shown
+    #[error("{0}")]
+    Download(String),
+    /// The bytes aren't an image the bot can read; the detail is only logged
+    #[error("Failed to decode the image. Is it a valid image file?")]
+    Decode(String),
+    /// Over the download size or pixel limit; the message says which
+    #[error("{0}")]
+    TooLarge(String),
+    #[error("Your Catppuccinify job was cancelled.")]
+    Cancelled,
+    /// The result couldn't be written in the requested format; the detail is only logged
+    #[error("Failed to encode the processed image.")]
+    Encode(String),
+    #[error("Failed to send the processed image. Please try again later.")]
+    Upload,
+    /// A job panicked; the ID matches its log entry
+    #[error("Something went wrong on our side while processing this. If you report it, please include error ID `{error_id}`.")]
+    Internal { error_id: String },
+}
+
+impl BotError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            BotError::Download(_) => "E-DL",
+            BotError::Decode(_) => "E-DEC",
+            BotError::TooLarge(_) => "E-SIZE",
+            BotError::Cancelled => "E-CANCEL",
+            BotError::Encode(_) => "E-ENC",
+            BotError::Upload => "E-UP",
+            BotError::Internal { .. } => "E-INT",
+        }
+    }
+
+    /// The message for the user, ending with the code
+    pub fn reply(&self) -> String {
+        format!("{} (`{}`)", self, self.code())
+    }
+}
+
+impl From<JobPanic> for BotError {
+    fn from(panic: JobPanic) -> Self {
+        BotError::Internal { error_id: panic.error_id }
+    }
+}
+
+// Code that still reports errors as text gets the reply, so `?` works across the boundary
+impl From<BotError> for String {
+    fn from(error: BotError) -> Self {
+        error.reply()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replies_carry_codes() {
+        let errors = [
+            BotError::Download("Failed to fetch the image.".to_string()),
+            BotError::Decode("bad header".to_string()),
+            BotError::TooLarge("Image is too large.".to_string()),
+            BotError::Cancelled,
+            BotError::Encode("unsupported".to_string()),
+            BotError::Upload,
+            BotError::Internal { error_id: "1F3A9C07".to_string() },
+        ];
+        for (i, error) in errors.iter().enumerate() {
+            assert!(error.reply().ends_with(&format!("(`{}`)", error.code())));
+            assert!(errors[i + 1..].iter().all(|other| other.code() != error.code()));
+        }
+        // Details meant for the log stay out of the reply
+        assert!(!String::from(BotError::Decode("bad header".to_string())).contains("bad header"));
+        assert!(String::from(BotError::Internal { error_id: "1F3A9C07".to_string() }).contains("1F3A9C07"));
+    }
+}
@@ -0,0 +1,181 @@
+// src/eta.rs
+
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::warn;
+
+/// What a throughput figure counts: pixels for stills, pixels across every frame for GIFs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum WorkKind {
+    Still,
+    Gif,
+}
+
+impl WorkKind {
+    fn name(self) -> &'static str {
+        match self {
+            WorkKind::Still => "still",
+            WorkKind::Gif => "gif",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "still" => Some(WorkKind::Still),
+            "gif" => Some(WorkKind::Gif),
+            _ => None,
+        }
+    }
+
+    // A conservative guess in pixels per second, used until real jobs have been timed
+    fn default_rate(self) -> f64 {
+        match self {
+            WorkKind::Still => 4_000_000.0,
+            WorkKind::Gif => 2_000_000.0,
+        }
+    }
+}
+
+/// Weight of the newest sample in the rolling average
+const SMOOTHING: f64 = 0.3;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Throughput {
+    units_per_second: f64,
+    samples: u32,
+}
+
+type Stats = HashMap<(String, WorkKind), Throughput>;
+
+// Loaded once and written back after every job, so estimates survive restarts
+static THROUGHPUT: Lazy<Mutex<Stats>> = Lazy::new(|| {
+    let text = std::fs::read_to_string(stats_path()).unwrap_or_default();
+    Mutex::new(parse_stats(&text))
+});
+
+fn stats_path() -> PathBuf {
+    std::env::var("THROUGHPUT_FILE").map(PathBuf::from).unwrap_or_else(|_| PathBuf::from("throughput.tsv"))
+}
+
+// One `algorithm<TAB>kind<TAB>units_per_second<TAB>samples` line per entry; bad lines are skipped
+fn parse_stats(text: &str) -> Stats {
+    text.lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\t');
+            let algorithm = fields.next()?.to_string();
+            let kind = WorkKind::parse(fields.next()?)?;
+            let units_per_second = fields.next()?.parse::<f64>().ok().filter(|r| r.is_finite() && *r > 0.0)?;
+            let samples = fields.next()?.parse::<u32>().ok()?;
+            Some(((algorithm, kind), Throughput { units_per_second, samples }))
+        })
+        .collect()
+}
+
+fn format_stats(stats: &Stats) -> String {
+    let mut entries: Vec<_> = stats.iter().collect();
+    entries.sort_by(|a, b| a.0.cmp(b.0));
+    entries
+        .into_iter()
+        .map(|((algorithm, kind), t)| format!("{}\t{}\t{:.0}\t{}\n", algorithm, kind.name(), t.units_per_second, t.samples))
+        .collect()
+}
+
+// Fold a new measurement into the rolling average; the first sample replaces the default outright
+fn updated(previous: Option<Throughput>, rate: f64) -> Throughput {
+    match previous {
+        Some(prev) => Throughput {
+            units_per_second: prev.units_per_second * (1.0 - SMOOTHING) + rate * SMOOTHING,
+            samples: prev.samples.saturating_add(1),
+        },
+        None => Throughput { units_per_second: rate, samples: 1 },
+    }
+}
+
+/// How long a job of `units` should take with an algorithm, from past jobs
+pub fn estimate(algorithm: &str, kind: WorkKind, units: u64) -> Duration {
+    let stats = THROUGHPUT.lock().unwrap();
+    let rate = stats.get(&(algorithm.to_string(), kind)).map_or(kind.default_rate(), |t| t.units_per_second);
+    Duration::from_secs_f64(units as f64 / rate)
+}
+
+/// Record how long a finished job took
+pub fn record(algorithm: &str, kind: WorkKind, units: u64, elapsed: Duration) {
+    // Near-instant jobs are dominated by overhead and would skew the rate
+    if elapsed < Duration::from_millis(50) || units == 0 {
+        return;
+    }
+    let rate = units as f64 / elapsed.as_secs_f64();
+    let mut stats = THROUGHPUT.lock().unwrap();
+    let key = (algorithm.to_string(), kind);
+    let next = updated(stats.get(&key).copied(), rate);
+    stats.insert(key, next);
+    if let Err(e) = std::fs::write(stats_path(), format_stats(&stats)) {
+        warn!(error = %e, "Failed to save throughput stats");
+    }
+}
+
+/// Time left given how long a job has run and the fraction of it done
+pub fn remaining(elapsed: Duration, fraction_done: f64) -> Option<Duration> {
+    if fraction_done <= 0.0 || !fraction_done.is_finite() {
+        return None;
+    }
+    let fraction_done = fraction_done.min(1.0);
+    Some(Duration::from_secs_f64(elapsed.as_secs_f64() * (1.0 - fraction_done) / fraction_done))
+}
+
+/// A rough duration for people, e.g. `~45 s` or `~3 min`
+pub fn describe(duration: Duration) -> String {
+    let seconds = duration.as_secs_f64().round() as u64;
+    match seconds {
+        0 => "<1 s".to_string(),
+        1..=89 => format!("~{} s", seconds),
+        _ => format!("~{} min", (seconds + 30) / 60),
+    }
+}
+
+/// The estimate shown when a job is queued, e.g. `~45 s for this 2048×2048 image with gaussian-sampling`.
+/// `subject` describes the input, e.g. `2048×2048 image`.
+pub fn announcement(algorithm: &str, kind: WorkKind, units: u64, subject: &str) -> String {
+    let estimate = describe(estimate(algorithm, kind, units));
+    // The estimate covers processing only; a full queue adds an unknown wait before it starts
+    let queued = if crate::IMAGE_PROCESSING_SEMAPHORE.available_permits() == 0 { ", once a processing slot frees up" } else { "" };
+    format!("{} for this {} with {}{}", estimate, subject, algorithm, queued)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats_round_trip() {
+        let mut stats = Stats::new();
+        stats.insert(("shepards-method".to_string(), WorkKind::Still), Throughput { units_per_second: 5_000_000.0, samples: 3 });
+        stats.insert(("gaussian-sampling".to_string(), WorkKind::Gif), Throughput { units_per_second: 750_000.0, samples: 1 });
+        assert_eq!(parse_stats(&format_stats(&stats)), stats);
+        // Malformed and non-positive lines are ignored
+        let parsed = parse_stats("junk\nnearest-neighbor\tstill\t-4\t1\nnearest-neighbor\tvideo\t10\t1\n");
+        assert!(parsed.is_empty());
+    }
+
+    #[test]
+    fn test_rolling_average() {
+        let first = updated(None, 1000.0);
+        assert_eq!(first, Throughput { units_per_second: 1000.0, samples: 1 });
+        let second = updated(Some(first), 2000.0);
+        assert_eq!(second.samples, 2);
+        assert!((second.units_per_second - 1300.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_remaining_and_describe() {
+        assert_eq!(remaining(Duration::from_secs(10), 0.25), Some(Duration::from_secs(30)));
+        assert_eq!(remaining(Duration::from_secs(10), 1.0), Some(Duration::ZERO));
+        assert_eq!(remaining(Duration::from_secs(10), 0.0), None);
+        assert_eq!(describe(Duration::from_millis(300)), "<1 s");
+        assert_eq!(describe(Duration::from_secs(45)), "~45 s");
+        assert_eq!(describe(Duration::from_secs(170)), "~3 min");
+    }
+}
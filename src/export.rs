@@ -0,0 +1,234 @@
+// src/export.rs
+
+use catppuccin::FlavorName;
+
+/// File formats a list of named colors can be downloaded in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PaletteFormat {
+    Json,
+    Css,
+    Gpl,
+    /// Adobe Swatch Exchange, which Photoshop, Illustrator and Aseprite load
+    Ase,
+    /// Adobe Color Table: 256 RGB entries plus a color count
+    Act,
+}
+
+impl PaletteFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            PaletteFormat::Json => "json",
+            PaletteFormat::Css => "css",
+            PaletteFormat::Gpl => "gpl",
+            PaletteFormat::Ase => "ase",
+            PaletteFormat::Act => "act",
+        }
+    }
+}
+
+/// Terminal color scheme files a flavor can be exported as
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SchemeFormat {
+    Xresources,
+    Base16,
+}
+
+impl SchemeFormat {
+    pub fn filename(&self, flavor: FlavorName) -> String {
+        match self {
+            SchemeFormat::Xresources => format!("catppuccin-{}.Xresources", flavor.identifier()),
+            SchemeFormat::Base16 => format!("catppuccin-{}.yaml", flavor.identifier()),
+        }
+    }
+}
+
+// The 16 ANSI colors as palette names, following upstream Catppuccin. Latte is a light theme, so its
+// "black" and "white" swap to keep text readable on its background.
+fn ansi_colors(flavor: FlavorName) -> [&'static str; 16] {
+    let (black, bright_black, white, bright_white) = match flavor {
+        FlavorName::Latte => ("subtext1", "subtext0", "surface2", "surface1"),
+        _ => ("surface1", "surface2", "subtext1", "subtext0"),
+    };
+    let hues = ["red", "green", "yellow", "blue", "pink", "teal"];
+    let mut colors = [""; 16];
+    colors[0] = black;
+    colors[7] = white;
+    colors[8] = bright_black;
+    colors[15] = bright_white;
+    for (i, hue) in hues.iter().enumerate() {
+        colors[1 + i] = hue;
+        colors[9 + i] = hue;
+    }
+    colors
+}
+
+// Upstream Catppuccin's base16 slots, base00 through base0F
+const BASE16_COLORS: [&str; 16] = [
+    "base", "mantle", "surface0", "surface1", "surface2", "text", "rosewater", "lavender",
+    "red", "peach", "yellow", "green", "teal", "blue", "mauve", "flamingo",
+];
+
+/// Write a flavor as an Xresources file or a base16 scheme
+pub fn write_scheme(flavor: FlavorName, format: SchemeFormat) -> Vec<u8> {
+    let rgb = |name: &str| crate::utils::catppuccin_color_name_to_rgb(name, flavor).unwrap();
+    let title = format!("Catppuccin {}", flavor);
+    let text = match format {
+        SchemeFormat::Xresources => {
+            let mut xresources = format!("! {}\n", title);
+            xresources.push_str(&format!("*background: {}\n*foreground: {}\n*cursorColor: {}\n", hex(rgb("base")), hex(rgb("text")), hex(rgb("rosewater"))));
+            for (i, name) in ansi_colors(flavor).iter().enumerate() {
+                xresources.push_str(&format!("*color{}: {}\n", i, hex(rgb(name))));
+            }
+            xresources
+        }
+        SchemeFormat::Base16 => {
+            let mut yaml = format!("scheme: \"{}\"\nauthor: \"https://github.com/catppuccin/catppuccin\"\n", title);
+            for (i, name) in BASE16_COLORS.iter().enumerate() {
+                yaml.push_str(&format!("base{:02X}: \"{}\"\n", i, hex(rgb(name)).trim_start_matches('#')));
+            }
+            yaml
+        }
+    };
+    text.into_bytes()
+}
+
+// Names made unique by numbering repeats, e.g. `blue`, `blue-2`, so each can be a key or variable
+fn unique_names(colors: &[(&str, (u8, u8, u8))]) -> Vec<String> {
+    let mut seen: Vec<&str> = Vec::new();
+    colors
+        .iter()
+        .map(|(name, _)| {
+            seen.push(name);
+            match seen.iter().filter(|n| *n == name).count() {
+                1 => name.to_string(),
+                n => format!("{}-{}", name, n),
+            }
+        })
+        .collect()
+}
+
+fn hex((r, g, b): (u8, u8, u8)) -> String {
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+// Adobe Swatch Exchange: a header, then one big-endian block per color holding its UTF-16 name and RGB floats
+fn write_ase(names: &[String], colors: &[(&str, (u8, u8, u8))]) -> Vec<u8> {
+    let mut ase = b"ASEF".to_vec();
+    ase.extend_from_slice(&1u16.to_be_bytes());
+    ase.extend_from_slice(&0u16.to_be_bytes());
+    ase.extend_from_slice(&(colors.len() as u32).to_be_bytes());
+    for (name, (_, (r, g, b))) in names.iter().zip(colors) {
+        // Names are null-terminated
+        let name: Vec<u16> = name.encode_utf16().chain([0]).collect();
+        let mut block = Vec::new();
+        block.extend_from_slice(&(name.len() as u16).to_be_bytes());
+        name.iter().for_each(|unit| block.extend_from_slice(&unit.to_be_bytes()));
+        block.extend_from_slice(b"RGB ");
+        for channel in [r, g, b] {
+            block.extend_from_slice(&(*channel as f32 / 255.0).to_be_bytes());
+        }
+        // Normal (rather than global or spot) color
+        block.extend_from_slice(&2u16.to_be_bytes());
+        ase.extend_from_slice(&1u16.to_be_bytes());
+        ase.extend_from_slice(&(block.len() as u32).to_be_bytes());
+        ase.extend(block);
+    }
+    ase
+}
+
+// Adobe Color Table: 256 RGB triples (unused ones black), the number in use, and no transparent index
+fn write_act(colors: &[(&str, (u8, u8, u8))]) -> Vec<u8> {
+    let used = colors.len().min(256);
+    let mut act = vec![0u8; 256 * 3];
+    for (i, (_, (r, g, b))) in colors.iter().take(used).enumerate() {
+        act[i * 3..i * 3 + 3].copy_from_slice(&[*r, *g, *b]);
+    }
+    act.extend_from_slice(&(used as u16).to_be_bytes());
+    act.extend_from_slice(&0xFFFFu16.to_be_bytes());
+    act
+}
+
+/// Write named colors as a palette file titled `title`
+pub fn write_palette(title: &str, colors: &[(&str, (u8, u8, u8))], format: PaletteFormat) -> Vec<u8> {
+    let names = unique_names(colors);
+    let text = match format {
+        PaletteFormat::Json => {
+            let entries: Vec<String> = names
+                .iter()
+                .zip(colors)
+                .map(|(name, (_, rgb))| format!("    {{ \"name\": \"{}\", \"hex\": \"{}\", \"rgb\": [{}, {}, {}] }}", name, hex(*rgb), rgb.0, rgb.1, rgb.2))
+                .collect();
+            format!("{{\n  \"name\": \"{}\",\n  \"colors\": [\n{}\n  ]\n}}\n", title.replace('"', "'"), entries.join(",\n"))
+        }
+        PaletteFormat::Css => {
+            let mut css = format!("/* {} */\n:root {{\n", title.replace("*/", ""));
+            for (name, (_, rgb)) in names.iter().zip(colors) {
+                css.push_str(&format!("  --ctp-{}: {};\n", name, hex(*rgb)));
+            }
+            css.push_str("}\n");
+            css
+        }
+        PaletteFormat::Gpl => {
+            let mut gpl = format!("GIMP Palette\nName: {}\nColumns: {}\n#\n", title.replace('\n', " "), colors.len().min(16));
+            for (name, (_, (r, g, b))) in names.iter().zip(colors) {
+                gpl.push_str(&format!("{:>3} {:>3} {:>3}\t{}\n", r, g, b, name));
+            }
+            gpl
+        }
+        PaletteFormat::Ase => return write_ase(&names, colors),
+        PaletteFormat::Act => return write_act(colors),
+    };
+    text.into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_palette_formats() {
+        let colors = [("blue", (137, 180, 250)), ("mauve", (203, 166, 247)), ("blue", (30, 102, 245))];
+        let css = String::from_utf8(write_palette("Triadic", &colors, PaletteFormat::Css)).unwrap();
+        assert!(css.contains("--ctp-blue: #89b4fa;") && css.contains("--ctp-blue-2: #1e66f5;"));
+        let gpl = String::from_utf8(write_palette("Triadic", &colors, PaletteFormat::Gpl)).unwrap();
+        assert!(gpl.starts_with("GIMP Palette\nName: Triadic\n"));
+        assert!(gpl.contains("203 166 247\tmauve\n"));
+        let json = String::from_utf8(write_palette("Triadic", &colors, PaletteFormat::Json)).unwrap();
+        let parsed: serenity::json::Value = serenity::json::from_str(&json).unwrap();
+        assert_eq!(parsed["colors"][1]["hex"], "#cba6f7");
+        assert_eq!(parsed["colors"][2]["name"], "blue-2");
+    }
+
+    #[test]
+    fn test_write_scheme() {
+        let xresources = String::from_utf8(write_scheme(FlavorName::Mocha, SchemeFormat::Xresources)).unwrap();
+        assert!(xresources.contains("*background: #1e1e2e\n"));
+        assert!(xresources.contains("*color0: #45475a\n") && xresources.contains("*color13: #f5c2e7\n") && xresources.contains("*color15: #a6adc8\n"));
+        let latte = String::from_utf8(write_scheme(FlavorName::Latte, SchemeFormat::Xresources)).unwrap();
+        assert!(latte.contains("*color0: #5c5f77\n"));
+        let base16 = String::from_utf8(write_scheme(FlavorName::Mocha, SchemeFormat::Base16)).unwrap();
+        assert!(base16.starts_with("scheme: \"Catppuccin Mocha\"\n"));
+        assert!(base16.contains("base00: \"1e1e2e\"\n") && base16.contains("base0E: \"cba6f7\"\n"));
+        assert_eq!(base16.lines().filter(|l| l.starts_with("base")).count(), 16);
+    }
+
+    #[test]
+    fn test_write_binary_palettes() {
+        let colors = [("blue", (137, 180, 250)), ("mauve", (203, 166, 247))];
+        let ase = write_palette("Mocha", &colors, PaletteFormat::Ase);
+        assert_eq!(&ase[..12], b"ASEF\x00\x01\x00\x00\x00\x00\x00\x02");
+        // First block: type, length, then the name "blue" as null-terminated UTF-16
+        assert_eq!(&ase[12..14], &[0, 1]);
+        let block_len = u32::from_be_bytes(ase[14..18].try_into().unwrap()) as usize;
+        assert_eq!(block_len, 2 + 5 * 2 + 4 + 12 + 2);
+        assert_eq!(&ase[18..30], &[0, 5, 0, b'b', 0, b'l', 0, b'u', 0, b'e', 0, 0]);
+        assert_eq!(&ase[30..34], b"RGB ");
+        assert_eq!(f32::from_be_bytes(ase[34..38].try_into().unwrap()), 137.0 / 255.0);
+        // "mauve" is one UTF-16 unit longer
+        assert_eq!(ase.len(), 12 + (6 + block_len) + (6 + block_len + 2));
+        let act = write_palette("Mocha", &colors, PaletteFormat::Act);
+        assert_eq!(act.len(), 772);
+        assert_eq!(&act[3..6], &[203, 166, 247]);
+        assert_eq!(&act[768..], &[0, 2, 0xFF, 0xFF]);
+    }
+}
@@ -0,0 +1,172 @@
+// src/favorites.rs
+
+use crate::config;
+use crate::palette;
+use crate::responses;
+use crate::utils;
+use once_cell::sync::Lazy;
+use serenity::builder::{CreateAttachment, CreateMessage};
+use serenity::json::{json, Value};
+use serenity::model::channel::Message;
+use serenity::model::id::UserId;
+use serenity::prelude::Context;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::RwLock;
+use tracing::{error, warn};
+
+// Kept in memory and written back to `FAVORITES_FILE` on every change. Favorites are palette color
+// names, so they follow whichever flavor they're shown in.
+static FAVORITES: Lazy<RwLock<HashMap<UserId, Vec<String>>>> = Lazy::new(|| RwLock::new(load_favorites()));
+
+fn favorites_path() -> PathBuf {
+    std::env::var("FAVORITES_FILE").map(PathBuf::from).unwrap_or_else(|_| PathBuf::from("favorites.json"))
+}
+
+fn load_favorites() -> HashMap<UserId, Vec<String>> {
+    let Ok(text) = std::fs::read_to_string(favorites_path()) else {
+        return HashMap::new();
+    };
+    match serenity::json::from_str::<Value>(text) {
+        Ok(value) => favorites_from_json(&value),
+        Err(e) => {
+            warn!(error = %e, "Failed to parse favorite colors; starting without them");
+            HashMap::new()
+        }
+    }
+}
+
+fn favorites_to_json(favorites: &HashMap<UserId, Vec<String>>) -> Value {
+    Value::Object(favorites.iter().map(|(user_id, names)| (user_id.to_string(), json!(names))).collect())
+}
+
+// Names that aren't palette colors are dropped; the rest of the file still loads
+fn favorites_from_json(value: &Value) -> HashMap<UserId, Vec<String>> {
+    let Some(object) = value.as_object() else {
+        return HashMap::new();
+    };
+    object
+        .iter()
+        .filter_map(|(user_id, names)| {
+            let user_id = UserId::new(user_id.parse::<u64>().ok().filter(|id| *id != 0)?);
+            let names = names.as_array()?.iter().filter_map(|n| color_name(n.as_str()?)).collect();
+            Some((user_id, names))
+        })
+        .collect()
+}
+
+// The palette color a word names, lowercased
+fn color_name(word: &str) -> Option<String> {
+    let word = word.to_lowercase();
+    utils::CATPPUCCIN_COLOR_NAMES.contains(&word.as_str()).then_some(word)
+}
+
+/// A member's favorite colors, in the order they were added
+pub fn list(user_id: UserId) -> Vec<String> {
+    FAVORITES.read().unwrap().get(&user_id).cloned().unwrap_or_default()
+}
+
+/// Change a member's favorites and write the file. The change is kept in memory even if saving fails.
+fn update(user_id: UserId, change: impl FnOnce(&mut Vec<String>)) -> Result<Vec<String>, String> {
+    let mut favorites = FAVORITES.write().unwrap();
+    let names = favorites.entry(user_id).or_default();
+    change(names);
+    let names = names.clone();
+    let text = serenity::json::to_string_pretty(&favorites_to_json(&favorites)).map_err(|e| e.to_string())?;
+    std::fs::write(favorites_path(), text).map_err(|e| format!("Failed to save your favorites: {e}"))?;
+    Ok(names)
+}
+
+// Each color once, in the order given
+fn add_names(names: &mut Vec<String>, added: &[String]) {
+    for name in added {
+        if !names.contains(name) {
+            names.push(name.clone());
+        }
+    }
+}
+
+/// `!cat fav add|remove <color...>`, `!cat fav list`, `!cat fav palette [flavor]`; `parts` are the words
+/// after `fav`. The board and list use the given flavor, or the server's default.
+pub async fn run(ctx: &Context, msg: &Message, parts: &[&str]) {
+    let flavor = parts.iter().find_map(|s| utils::parse_flavor(s)).unwrap_or_else(|| config::default_flavor(msg.guild_id));
+    let colors: Vec<String> = parts.iter().skip(1).filter_map(|s| color_name(s)).collect();
+    let step = parts.first().map(|s| s.to_lowercase());
+    match step.as_deref() {
+        Some(step @ ("add" | "remove")) => {
+            if colors.is_empty() {
+                let text = format!("Name at least one Catppuccin color, e.g. `!cat fav {} mauve pink`.", step);
+                let _ = responses::warning(&ctx.http, msg.channel_id, text).await;
+                return;
+            }
+            let saved = update(msg.author.id, |names| {
+                if step == "add" {
+                    add_names(names, &colors);
+                } else {
+                    names.retain(|n| !colors.contains(n));
+                }
+            });
+            match saved {
+                Ok(names) => {
+                    let text = format!("Your favorites: {}", if names.is_empty() { "none yet".to_string() } else { format!("`{}`", names.join("`, `")) });
+                    let _ = responses::success(&ctx.http, msg.channel_id, text).await;
+                }
+                Err(e) => {
+                    error!(error = %e, user = %msg.author.id, "Failed to save favorites");
+                    let _ = responses::error(&ctx.http, msg.channel_id, "The change applies for now, but couldn't be saved and will be lost on restart.").await;
+                }
+            }
+        }
+        Some("list") => {
+            let names = list(msg.author.id);
+            if names.is_empty() {
+                let _ = responses::info(&ctx.http, msg.channel_id, "No favorites yet. Add some with `!cat fav add mauve pink`.").await;
+                return;
+            }
+            let lines: Vec<String> = names
+                .iter()
+                .filter_map(|name| {
+                    let (r, g, b) = utils::catppuccin_color_name_to_rgb(name, flavor)?;
+                    Some(format!("`{}` #{:02x}{:02x}{:02x}", name, r, g, b))
+                })
+                .collect();
+            let _ = responses::info(&ctx.http, msg.channel_id, format!("**Your favorites in {}**\n{}", flavor, lines.join("\n"))).await;
+        }
+        Some("palette") => {
+            let names = list(msg.author.id);
+            if names.is_empty() {
+                let _ = responses::info(&ctx.http, msg.channel_id, "No favorites yet. Add some with `!cat fav add mauve pink`.").await;
+                return;
+            }
+            let mut board_png = std::io::Cursor::new(Vec::new());
+            // Encoding an in-memory PNG can't fail
+            palette::render_favorites_board(&names, flavor).write_to(&mut board_png, image::ImageFormat::Png).unwrap();
+            let attachment = CreateAttachment::bytes(board_png.into_inner(), format!("favorites_{}.png", flavor.identifier()));
+            let content = format!("**{}'s favorites** in {}", msg.author.name, flavor);
+            let _ = msg.channel_id.send_files(&ctx.http, vec![attachment], CreateMessage::new().content(content)).await;
+        }
+        _ => {
+            let _ = responses::warning(&ctx.http, msg.channel_id, "Usage: `!cat fav add|remove <colors...>`, `!cat fav list [flavor]` or `!cat fav palette [flavor]`").await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_favorites() {
+        let mut names = vec!["mauve".to_string()];
+        add_names(&mut names, &["pink".to_string(), "mauve".to_string()]);
+        assert_eq!(names, vec!["mauve", "pink"]);
+        assert_eq!(color_name("Lavender").as_deref(), Some("lavender"));
+        assert_eq!(color_name("purple"), None);
+        let mut favorites = HashMap::new();
+        favorites.insert(UserId::new(7), names);
+        assert_eq!(favorites_from_json(&favorites_to_json(&favorites)), favorites);
+        // Unknown names are dropped on load
+        let parsed = favorites_from_json(&json!({ "8": ["blue", "purple"] }));
+        assert_eq!(parsed[&UserId::new(8)], vec!["blue"]);
+    }
+}
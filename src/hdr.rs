@@ -0,0 +1,84 @@
+// src/hdr.rs
+
+use crate::icc;
+use image::{DynamicImage, Rgba, Rgba32FImage, RgbaImage};
+use tracing::debug;
+
+/// Average scene luminance is exposed to middle gray
+const MIDDLE_GRAY: f32 = 0.18;
+
+/// Share of the brightest pixels allowed to clip, so a few speculars don't darken everything else
+const CLIPPED_SHARE: f32 = 0.005;
+
+/// Bring 16-bit and floating-point images down to 8-bit sRGB before palette mapping. 16-bit images are
+/// already display-referred and only lose precision; float images (EXR, HDR) hold linear light, and
+/// are tone-mapped when they go past 1.0 instead of clipping to white. 8-bit images are returned as is.
+pub fn to_display(img: DynamicImage) -> DynamicImage {
+    match img {
+        DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_) => {
+            debug!("Tone-mapping a floating-point image");
+            DynamicImage::ImageRgba8(tone_map(&img.to_rgba32f()))
+        }
+        DynamicImage::ImageLuma16(_) | DynamicImage::ImageLumaA16(_) | DynamicImage::ImageRgb16(_) | DynamicImage::ImageRgba16(_) => DynamicImage::ImageRgba8(img.to_rgba8()),
+        img => img,
+    }
+}
+
+fn luminance(p: &Rgba<f32>) -> f32 {
+    (0.2126 * p[0] + 0.7152 * p[1] + 0.0722 * p[2]).max(0.0)
+}
+
+/// Linear light to sRGB with extended Reinhard: the scene is exposed by its log-average luminance and
+/// compressed so the brightest unclipped pixel lands on white. Hue is kept by scaling each pixel's
+/// channels together. Images that never go past 1.0 are only encoded.
+fn tone_map(img: &Rgba32FImage) -> RgbaImage {
+    let luminances: Vec<f32> = img.pixels().map(luminance).collect();
+    let peak = luminances.iter().copied().fold(0.0, f32::max);
+    let scale = if peak <= 1.0 || luminances.is_empty() {
+        None
+    } else {
+        let log_average = (luminances.iter().map(|l| (l + 1e-4).ln()).sum::<f32>() / luminances.len() as f32).exp();
+        let exposure = MIDDLE_GRAY / log_average;
+        let mut sorted = luminances.clone();
+        let index = ((sorted.len() as f32 * (1.0 - CLIPPED_SHARE)) as usize).min(sorted.len() - 1);
+        let white = *sorted.select_nth_unstable_by(index, f32::total_cmp).1 * exposure;
+        Some((exposure, white.max(1.0)))
+    };
+    let encode = |v: f32| (icc::linear_to_srgb(v.clamp(0.0, 1.0)) * 255.0).round() as u8;
+    RgbaImage::from_fn(img.width(), img.height(), |x, y| {
+        let p = img.get_pixel(x, y);
+        let ratio = match scale {
+            Some((exposure, white)) => {
+                let l = luminance(p) * exposure;
+                if l <= 0.0 {
+                    0.0
+                } else {
+                    exposure * (1.0 + l / (white * white)) / (1.0 + l)
+                }
+            }
+            None => 1.0,
+        };
+        Rgba([encode(p[0] * ratio), encode(p[1] * ratio), encode(p[2] * ratio), (p[3].clamp(0.0, 1.0) * 255.0).round() as u8])
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tone_map() {
+        // A dim room with a window forty times brighter: the window lands near white instead of the room going black
+        let img = Rgba32FImage::from_fn(100, 100, |x, _| if x < 10 { Rgba([40.0, 38.0, 36.0, 1.0]) } else { Rgba([0.1, 0.08, 0.06, 1.0]) });
+        let mapped = tone_map(&img);
+        let window = mapped.get_pixel(0, 0).0;
+        let room = mapped.get_pixel(50, 0).0;
+        assert!(window[0] >= 240 && window[0] > window[2], "{:?}", window);
+        assert!((60..200).contains(&room[1]) && room[0] > room[2], "{:?}", room);
+        // Within 0-1 it's plain sRGB encoding
+        let sdr = tone_map(&Rgba32FImage::from_pixel(2, 2, Rgba([0.2159, 0.0, 1.0, 0.5])));
+        assert_eq!(sdr.get_pixel(0, 0).0, [128, 0, 255, 128]);
+        let deep = DynamicImage::ImageRgb16(image::ImageBuffer::from_pixel(1, 1, image::Rgb([65535, 32896, 0])));
+        assert_eq!(to_display(deep).as_rgba8().unwrap().get_pixel(0, 0).0, [255, 128, 0, 255]);
+    }
+}
@@ -0,0 +1,230 @@
+// src/history.rs
+
+use crate::responses::{self, Tone};
+use once_cell::sync::Lazy;
+use serenity::builder::{CreateActionRow, CreateButton, CreateInteractionResponse, CreateInteractionResponseMessage, CreateMessage};
+use serenity::http::Http;
+use serenity::json::{json, Value};
+use serenity::model::application::{ButtonStyle, ComponentInteraction};
+use serenity::model::channel::Message;
+use serenity::model::id::{ChannelId, MessageId, UserId};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::RwLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::warn;
+
+/// Most jobs remembered per member; older ones drop off
+pub const MAX_ENTRIES: usize = 10;
+
+/// One finished job: what was asked for and where the image was, but not the image itself
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryEntry {
+    /// The words after `!cat`
+    pub command: String,
+    /// The message holding the command and its image, which a re-run reads again
+    pub channel_id: ChannelId,
+    pub message_id: MessageId,
+    /// `jobs::content_key` of the image, so the same conversion isn't listed twice
+    pub source_hash: u64,
+    /// Unix seconds
+    pub at: u64,
+}
+
+// Kept in memory and written back to the configured `history_file` on every change
+static HISTORY: Lazy<RwLock<HashMap<UserId, Vec<HistoryEntry>>>> = Lazy::new(|| RwLock::new(load_history()));
+
+fn history_path() -> PathBuf {
+    crate::config::get().history_file.clone()
+}
+
+fn load_history() -> HashMap<UserId, Vec<HistoryEntry>> {
+    let Ok(text) = std::fs::read_to_string(history_path()) else {
+        return HashMap::new();
+    };
+    match serenity::json::from_str::<Value>(text) {
+        Ok(value) => history_from_json(&value),
+        Err(e) => {
+            warn!(error = %e, "Failed to parse job history; starting without it");
+            HashMap::new()
+        }
+    }
+}
+
+// IDs and hashes are stored as strings so they survive tools that read numbers as doubles
+fn history_to_json(history: &HashMap<UserId, Vec<HistoryEntry>>) -> Value {
+    let entries = history.iter().map(|(user_id, entries)| {
+        let entries: Vec<Value> = entries
+            .iter()
+            .map(|e| {
+                json!({
+                    "command": e.command,
+                    "channel_id": e.channel_id.to_string(),
+                    "message_id": e.message_id.to_string(),
+                    "source_hash": format!("{:016x}", e.source_hash),
+                    "at": e.at,
+                })
+            })
+            .collect();
+        (user_id.to_string(), Value::Array(entries))
+    });
+    Value::Object(entries.collect())
+}
+
+// Entries missing a field are dropped; the rest of the file still loads
+fn history_from_json(value: &Value) -> HashMap<UserId, Vec<HistoryEntry>> {
+    let id = |v: Option<&Value>| v?.as_str()?.parse::<u64>().ok().filter(|id| *id != 0);
+    let Some(object) = value.as_object() else {
+        return HashMap::new();
+    };
+    object
+        .iter()
+        .filter_map(|(user_id, entries)| {
+            let user_id = UserId::new(user_id.parse::<u64>().ok().filter(|id| *id != 0)?);
+            let entries = entries
+                .as_array()?
+                .iter()
+                .filter_map(|e| {
+                    Some(HistoryEntry {
+                        command: e.get("command")?.as_str()?.to_string(),
+                        channel_id: ChannelId::new(id(e.get("channel_id"))?),
+                        message_id: MessageId::new(id(e.get("message_id"))?),
+                        source_hash: u64::from_str_radix(e.get("source_hash")?.as_str()?, 16).ok()?,
+                        at: e.get("at")?.as_u64()?,
+                    })
+                })
+                .take(MAX_ENTRIES)
+                .collect();
+            Some((user_id, entries))
+        })
+        .collect()
+}
+
+/// A member's jobs, newest first
+pub fn list(user_id: UserId) -> Vec<HistoryEntry> {
+    HISTORY.read().unwrap().get(&user_id).cloned().unwrap_or_default()
+}
+
+/// Remember a finished job from `msg`, the message that asked for it, and write the file.
+/// Running the same command on the same image again moves it to the top instead of adding it twice.
+pub fn record(msg: &Message, command: &str, image: &[u8]) -> Result<(), String> {
+    let entry = HistoryEntry {
+        command: command.to_string(),
+        channel_id: msg.channel_id,
+        message_id: msg.id,
+        source_hash: crate::jobs::content_key(image),
+        at: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+    };
+    let mut history = HISTORY.write().unwrap();
+    let entries = history.entry(msg.author.id).or_default();
+    entries.retain(|e| !(e.command == entry.command && e.source_hash == entry.source_hash));
+    entries.insert(0, entry);
+    entries.truncate(MAX_ENTRIES);
+    let text = serenity::json::to_string_pretty(&history_to_json(&history)).map_err(|e| e.to_string())?;
+    std::fs::write(history_path(), text).map_err(|e| format!("Failed to save job history: {e}"))
+}
+
+// The list shown by `!cat history`, one numbered line per job
+fn describe(entries: &[HistoryEntry]) -> String {
+    if entries.is_empty() {
+        return "No jobs yet. Catppuccinify an image and it'll show up here to re-run.".to_string();
+    }
+    let lines: Vec<String> = entries
+        .iter()
+        .enumerate()
+        .map(|(i, e)| format!("**{}.** `!cat {}` · <t:{}:R> · image `{:08x}`", i + 1, e.command, e.at, e.source_hash as u32))
+        .collect();
+    format!("**Your recent jobs**\n{}\nPress a number to run it again, or copy a command to tweak its options.", lines.join("\n"))
+}
+
+/// The `!cat history` reply: the member's recent jobs with a re-run button for each. The buttons
+/// carry whose history they belong to (`history:<user>:<index>`), so they work across restarts.
+pub fn message(user_id: UserId) -> CreateMessage {
+    let entries = list(user_id);
+    let buttons: Vec<CreateButton> = (0..entries.len())
+        .map(|i| CreateButton::new(format!("history:{}:{}", user_id, i)).label(format!("{}", i + 1)).style(ButtonStyle::Secondary))
+        .collect();
+    // Discord allows five buttons per row
+    let rows: Vec<CreateActionRow> = buttons.chunks(5).map(|row| CreateActionRow::Buttons(row.to_vec())).collect();
+    CreateMessage::new().embed(responses::embed(Tone::Info, describe(&entries))).components(rows)
+}
+
+// Whose history a button belongs to, and which entry it re-runs
+fn parse_custom_id(custom_id: &str) -> Option<(UserId, usize)> {
+    let (user, index) = custom_id.strip_prefix("history:")?.split_once(':')?;
+    let user = user.parse::<u64>().ok().filter(|id| *id != 0)?;
+    Some((UserId::new(user), index.parse().ok()?))
+}
+
+async fn reply_privately(http: &Http, component: &ComponentInteraction, tone: Tone, text: &str) {
+    let response = CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new().embed(responses::embed(tone, text)).ephemeral(true),
+    );
+    if let Err(why) = component.create_response(http, response).await {
+        warn!(?why, "Failed to answer a history button");
+    }
+}
+
+/// For a re-run button: the original message, fetched again so its attachment links are fresh, and
+/// moved to the channel the button was pressed in so the result is posted there. The caller hands it
+/// to the message handler like a new command. None if the component isn't a history button or
+/// can't be re-run; the presser has been told why.
+pub async fn rerun(http: &Http, component: &ComponentInteraction) -> Option<Message> {
+    let (owner, index) = parse_custom_id(&component.data.custom_id)?;
+    if component.user.id != owner {
+        reply_privately(http, component, Tone::Warning, "These are someone else's jobs; use `!cat history` for yours.").await;
+        return None;
+    }
+    let Some(entry) = list(owner).into_iter().nth(index) else {
+        reply_privately(http, component, Tone::Warning, "That job is no longer in your history.").await;
+        return None;
+    };
+    let mut original = match entry.channel_id.message(http, entry.message_id).await {
+        Ok(original) => original,
+        Err(_) => {
+            reply_privately(http, component, Tone::Error, "The message with that image was deleted, so it can't be run again.").await;
+            return None;
+        }
+    };
+    let response = CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new().content(format!("🔁 Re-running `!cat {}`", entry.command)).ephemeral(true),
+    );
+    if let Err(why) = component.create_response(http, response).await {
+        warn!(?why, "Failed to answer a history button");
+    }
+    // Messages fetched over HTTP don't say which server they're in
+    original.guild_id = component.guild_id;
+    original.channel_id = component.channel_id;
+    Some(original)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_history_round_trip() {
+        let entry = |n: u64| HistoryEntry {
+            command: format!("mocha pixel {}", n),
+            channel_id: ChannelId::new(2),
+            message_id: MessageId::new(n),
+            source_hash: u64::MAX - n,
+            at: 1_700_000_000 + n,
+        };
+        let mut history = HashMap::new();
+        history.insert(UserId::new(1), vec![entry(3), entry(4)]);
+        assert_eq!(history_from_json(&history_to_json(&history)), history);
+        // An entry with a missing field is dropped without losing the member's others
+        let parsed = history_from_json(&json!({ "5": [{ "command": "latte" }, history_to_json(&history)["1"][0]] }));
+        assert_eq!(parsed[&UserId::new(5)], vec![entry(3)]);
+    }
+
+    #[test]
+    fn test_buttons_and_listing() {
+        assert_eq!(parse_custom_id("history:42:3"), Some((UserId::new(42), 3)));
+        assert_eq!(parse_custom_id("help:3"), None);
+        assert!(describe(&[]).starts_with("No jobs yet"));
+        let entry = HistoryEntry { command: "mocha".into(), channel_id: ChannelId::new(1), message_id: MessageId::new(2), source_hash: 0xab, at: 10 };
+        assert!(describe(&[entry]).contains("**1.** `!cat mocha` · <t:10:R> · image `000000ab`"));
+    }
+}
@@ -0,0 +1,289 @@
+// src/icc.rs
+
+use crate::codecs::{self, ModernFormat};
+use crate::hdr;
+use image::{DynamicImage, ImageDecoder, ImageError, ImageReader, ImageResult, RgbaImage};
+use std::io::Cursor;
+use tracing::debug;
+
+/// sRGB's colorants adapted to the D50 white every ICC profile is expressed against
+const SRGB_D50: [[f32; 3]; 3] = [[0.4360747, 0.3850649, 0.1430804], [0.2225045, 0.7168786, 0.0606169], [0.0139322, 0.0971045, 0.7141733]];
+
+/// A tone curve from a profile's rTRC/gTRC/bTRC tag, mapping encoded values to linear light
+#[derive(Debug, Clone, PartialEq)]
+enum Curve {
+    Table(Vec<f32>),
+    // The ICC type 4 parametric form that every other parametric type reduces to:
+    // (aX + b)^g + e when X >= d, cX + f below it
+    Parametric { g: f32, a: f32, b: f32, c: f32, d: f32, e: f32, f: f32 },
+}
+
+impl Curve {
+    fn eval(&self, x: f32) -> f32 {
+        match self {
+            Curve::Table(table) if table.is_empty() => x,
+            Curve::Table(table) => {
+                let pos = x.clamp(0.0, 1.0) * (table.len() - 1) as f32;
+                let i = pos.floor() as usize;
+                let next = table[(i + 1).min(table.len() - 1)];
+                table[i] + (next - table[i]) * (pos - i as f32)
+            }
+            Curve::Parametric { g, a, b, c, d, e, f } => {
+                if x >= *d {
+                    (a * x + b).max(0.0).powf(*g) + e
+                } else {
+                    c * x + f
+                }
+            }
+        }
+    }
+}
+
+/// The parts of a matrix/TRC RGB profile needed to reach sRGB: colorants and tone curves
+#[derive(Debug, Clone, PartialEq)]
+struct RgbProfile {
+    colorants: [[f32; 3]; 3],
+    curves: [Curve; 3],
+}
+
+fn u16_at(data: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(data.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn u32_at(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(data.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn s15_fixed16_at(data: &[u8], at: usize) -> Option<f32> {
+    Some(i32::from_be_bytes(data.get(at..at + 4)?.try_into().ok()?) as f32 / 65536.0)
+}
+
+// A tag's data, found through the tag table that follows the 128-byte header
+fn tag<'a>(profile: &'a [u8], signature: &[u8; 4]) -> Option<&'a [u8]> {
+    let count = u32_at(profile, 128)? as usize;
+    (0..count.min(256)).find_map(|i| {
+        let entry = 132 + i * 12;
+        if profile.get(entry..entry + 4)? != signature {
+            return None;
+        }
+        let offset = u32_at(profile, entry + 4)? as usize;
+        let size = u32_at(profile, entry + 8)? as usize;
+        profile.get(offset..offset.checked_add(size)?)
+    })
+}
+
+fn parse_xyz(data: &[u8]) -> Option<[f32; 3]> {
+    if data.get(0..4)? != b"XYZ " {
+        return None;
+    }
+    Some([s15_fixed16_at(data, 8)?, s15_fixed16_at(data, 12)?, s15_fixed16_at(data, 16)?])
+}
+
+fn parse_curve(data: &[u8]) -> Option<Curve> {
+    match data.get(0..4)? {
+        b"curv" => {
+            let count = u32_at(data, 8)? as usize;
+            match count {
+                0 => Some(Curve::Table(Vec::new())),
+                // A single entry is a gamma in u8Fixed8
+                1 => Some(Curve::Parametric { g: u16_at(data, 12)? as f32 / 256.0, a: 1.0, b: 0.0, c: 0.0, d: 0.0, e: 0.0, f: 0.0 }),
+                _ => (0..count).map(|i| Some(u16_at(data, 12 + i * 2)? as f32 / 65535.0)).collect::<Option<Vec<_>>>().map(Curve::Table),
+            }
+        }
+        b"para" => {
+            let function = u16_at(data, 8)?;
+            let count = [1, 3, 4, 5, 7].get(function as usize)?;
+            let p = (0..*count).map(|i| s15_fixed16_at(data, 12 + i * 4)).collect::<Option<Vec<_>>>()?;
+            let curve = |g, a, b, c, d, e, f| Curve::Parametric { g, a, b, c, d, e, f };
+            // Types 1 and 2 start at the X where aX + b reaches zero
+            let start = |a: f32, b: f32| if a == 0.0 { 0.0 } else { -b / a };
+            Some(match function {
+                0 => curve(p[0], 1.0, 0.0, 0.0, 0.0, 0.0, 0.0),
+                1 => curve(p[0], p[1], p[2], 0.0, start(p[1], p[2]), 0.0, 0.0),
+                2 => curve(p[0], p[1], p[2], 0.0, start(p[1], p[2]), p[3], p[3]),
+                3 => curve(p[0], p[1], p[2], p[3], p[4], 0.0, 0.0),
+                _ => curve(p[0], p[1], p[2], p[3], p[4], p[5], p[6]),
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Read a matrix/TRC RGB profile. Gray, CMYK and LUT-based profiles give None and are left alone.
+fn parse_profile(profile: &[u8]) -> Option<RgbProfile> {
+    if profile.get(16..20)? != b"RGB " {
+        return None;
+    }
+    let [r, g, b] = [b"rXYZ", b"gXYZ", b"bXYZ"].map(|sig| tag(profile, sig).and_then(parse_xyz));
+    let [r, g, b] = [r?, g?, b?];
+    let [rc, gc, bc] = [b"rTRC", b"gTRC", b"bTRC"].map(|sig| tag(profile, sig).and_then(parse_curve));
+    Some(RgbProfile {
+        colorants: [[r[0], g[0], b[0]], [r[1], g[1], b[1]], [r[2], g[2], b[2]]],
+        curves: [rc?, gc?, bc?],
+    })
+}
+
+fn invert(m: &[[f32; 3]; 3]) -> Option<[[f32; 3]; 3]> {
+    let [[a, b, c], [d, e, f], [g, h, i]] = *m;
+    let det = a * (e * i - f * h) - b * (d * i - f * g) + c * (d * h - e * g);
+    if det.abs() < 1e-6 {
+        return None;
+    }
+    Some([
+        [(e * i - f * h) / det, (c * h - b * i) / det, (b * f - c * e) / det],
+        [(f * g - d * i) / det, (a * i - c * g) / det, (c * d - a * f) / det],
+        [(d * h - e * g) / det, (b * g - a * h) / det, (a * e - b * d) / det],
+    ])
+}
+
+fn multiply(x: &[[f32; 3]; 3], y: &[[f32; 3]; 3]) -> [[f32; 3]; 3] {
+    let mut out = [[0.0; 3]; 3];
+    for (r, row) in out.iter_mut().enumerate() {
+        for (c, value) in row.iter_mut().enumerate() {
+            *value = (0..3).map(|k| x[r][k] * y[k][c]).sum();
+        }
+    }
+    out
+}
+
+fn srgb_to_linear(v: f32) -> f32 {
+    if v <= 0.04045 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Encode linear light (0-1) with the sRGB transfer curve
+pub fn linear_to_srgb(v: f32) -> f32 {
+    if v <= 0.0031308 {
+        v * 12.92
+    } else {
+        1.055 * v.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Convert pixels from the profile's color space to sRGB, keeping alpha. None if the profile is
+/// already sRGB in all but name, which is most of what phones and editors embed.
+fn to_srgb(img: &RgbaImage, profile: &RgbProfile) -> Option<RgbaImage> {
+    let matrix = multiply(&invert(&SRGB_D50)?, &profile.colorants);
+    let linear: Vec<[f32; 256]> = profile.curves.iter().map(|curve| std::array::from_fn(|i| curve.eval(i as f32 / 255.0))).collect();
+    let identity_matrix = (0..3).all(|r| (0..3).all(|c| (matrix[r][c] - if r == c { 1.0 } else { 0.0 }).abs() < 0.002));
+    let srgb_curves = linear.iter().all(|table| table.iter().enumerate().all(|(i, v)| (v - srgb_to_linear(i as f32 / 255.0)).abs() < 0.5 / 255.0));
+    if identity_matrix && srgb_curves {
+        return None;
+    }
+    let encode: Vec<u8> = (0..4096).map(|i| (linear_to_srgb(i as f32 / 4095.0) * 255.0).round() as u8).collect();
+    let mut out = img.clone();
+    for pixel in out.pixels_mut() {
+        let rgb = [linear[0][pixel[0] as usize], linear[1][pixel[1] as usize], linear[2][pixel[2] as usize]];
+        for (channel, row) in matrix.iter().enumerate() {
+            let v = (row[0] * rgb[0] + row[1] * rgb[1] + row[2] * rgb[2]).clamp(0.0, 1.0);
+            pixel[channel] = encode[(v * 4095.0).round() as usize];
+        }
+    }
+    Some(out)
+}
+
+/// Decode an image, converting it to sRGB if it carries an RGB ICC profile for another space
+/// (a Display P3 screenshot, say), so palette mapping sees the colors that were meant. 16-bit and
+/// floating-point images come back as 8-bit sRGB; see `hdr::to_display`.
+pub fn decode(bytes: &[u8]) -> ImageResult<DynamicImage> {
+    // AVIF and JPEG XL are read through ffmpeg, then go the same way as everything else
+    let transcoded;
+    let bytes = match ModernFormat::sniff(bytes) {
+        Some(format) => {
+            transcoded = codecs::decode_to_png(bytes, format).map_err(|e| ImageError::IoError(std::io::Error::other(e)))?;
+            transcoded.as_slice()
+        }
+        None => bytes,
+    };
+    let mut decoder = ImageReader::new(Cursor::new(bytes)).with_guessed_format()?.into_decoder()?;
+    let profile = decoder.icc_profile().ok().flatten();
+    let img = hdr::to_display(DynamicImage::from_decoder(decoder)?);
+    let Some(profile) = profile.as_deref().and_then(parse_profile) else {
+        return Ok(img);
+    };
+    match to_srgb(&img.to_rgba8(), &profile) {
+        Some(converted) => {
+            debug!("Converted an ICC-tagged image to sRGB");
+            Ok(DynamicImage::ImageRgba8(converted))
+        }
+        None => Ok(img),
+    }
+}
+
+/// Mark an encoded PNG as sRGB with an `sRGB` chunk after IHDR, so viewers don't guess.
+/// Anything that isn't a PNG comes back unchanged.
+pub fn tag_srgb_png(png: Vec<u8>) -> Vec<u8> {
+    // The 8-byte signature, then IHDR's length, type, 13 bytes of data and CRC
+    const IHDR_END: usize = 8 + 4 + 4 + 13 + 4;
+    if !png.starts_with(b"\x89PNG\r\n\x1a\n") || png.get(12..16) != Some(b"IHDR") || png.len() < IHDR_END {
+        return png;
+    }
+    // Rendering intent 0, perceptual
+    let chunk = [b"sRGB".as_slice(), &[0]].concat();
+    let mut tagged = Vec::with_capacity(png.len() + 13);
+    tagged.extend_from_slice(&png[..IHDR_END]);
+    tagged.extend_from_slice(&1u32.to_be_bytes());
+    tagged.extend_from_slice(&chunk);
+    tagged.extend_from_slice(&crc32fast::hash(&chunk).to_be_bytes());
+    tagged.extend_from_slice(&png[IHDR_END..]);
+    tagged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    // A minimal matrix/TRC profile: header, tag table, three XYZ tags and one shared sRGB `para` curve
+    fn profile(colorants: [[f32; 3]; 3]) -> Vec<u8> {
+        let fixed = |v: f32| ((v * 65536.0).round() as i32).to_be_bytes();
+        let mut data = Vec::new();
+        for xyz in colorants {
+            data.extend_from_slice(b"XYZ \0\0\0\0");
+            xyz.iter().for_each(|v| data.extend_from_slice(&fixed(*v)));
+        }
+        data.extend_from_slice(b"para\0\0\0\0\0\x03\0\0");
+        [2.4, 1.0 / 1.055, 0.055 / 1.055, 1.0 / 12.92, 0.04045].iter().for_each(|v| data.extend_from_slice(&fixed(*v)));
+        let tags: [(&[u8; 4], usize, usize); 6] = [(b"rXYZ", 0, 20), (b"gXYZ", 20, 20), (b"bXYZ", 40, 20), (b"rTRC", 60, 32), (b"gTRC", 60, 32), (b"bTRC", 60, 32)];
+        let start = 128 + 4 + tags.len() * 12;
+        let mut profile = vec![0; 128];
+        profile[16..20].copy_from_slice(b"RGB ");
+        profile.extend_from_slice(&(tags.len() as u32).to_be_bytes());
+        for (sig, offset, size) in tags {
+            profile.extend_from_slice(sig);
+            profile.extend_from_slice(&((start + offset) as u32).to_be_bytes());
+            profile.extend_from_slice(&(size as u32).to_be_bytes());
+        }
+        profile.extend_from_slice(&data);
+        profile
+    }
+
+    #[test]
+    fn test_display_p3_to_srgb() {
+        let columns = |m: [[f32; 3]; 3]| [0, 1, 2].map(|c| [m[0][c], m[1][c], m[2][c]]);
+        let img = RgbaImage::from_fn(2, 1, |x, _| if x == 0 { Rgba([200, 100, 50, 255]) } else { Rgba([128, 128, 128, 77]) });
+        let srgb = parse_profile(&profile(columns(SRGB_D50))).unwrap();
+        assert!(to_srgb(&img, &srgb).is_none());
+        let p3 = [[0.515102, 0.241182, -0.001050], [0.291965, 0.692236, 0.041882], [0.157153, 0.066582, 0.784378]];
+        let converted = to_srgb(&img, &parse_profile(&profile(p3)).unwrap()).unwrap();
+        // P3's orange is more saturated than sRGB's: about (215, 93, 31)
+        let [r, g, b, a] = converted.get_pixel(0, 0).0;
+        assert!((213..=217).contains(&r) && (91..=95).contains(&g) && (29..=33).contains(&b) && a == 255, "{:?}", (r, g, b));
+        // Grays share a white point, so they stay put, and alpha is untouched
+        let [r, g, b, a] = converted.get_pixel(1, 0).0;
+        assert!([r, g, b].iter().all(|v| v.abs_diff(128) <= 1) && a == 77, "{:?}", (r, g, b));
+    }
+
+    #[test]
+    fn test_tag_srgb_png() {
+        let mut png = std::io::Cursor::new(Vec::new());
+        DynamicImage::ImageRgba8(RgbaImage::new(3, 2)).write_to(&mut png, image::ImageFormat::Png).unwrap();
+        let tagged = tag_srgb_png(png.into_inner());
+        assert_eq!(&tagged[37..41], b"sRGB");
+        assert_eq!(image::load_from_memory(&tagged).unwrap().width(), 3);
+        assert_eq!(tag_srgb_png(b"GIF89a".to_vec()), b"GIF89a");
+    }
+}
@@ -0,0 +1,130 @@
+// src/icons.rs
+
+use image::RgbaImage;
+
+/// PNG sizes in an icon set, covering favicons through app store artwork
+pub const ICON_SIZES: [u32; 8] = [16, 32, 48, 64, 128, 192, 256, 512];
+/// Sizes packed into `favicon.ico`; the format tops out at 256
+const ICO_SIZES: [u32; 4] = [16, 32, 48, 256];
+
+fn encode_png(img: &RgbaImage) -> Vec<u8> {
+    let mut buffer = std::io::Cursor::new(Vec::new());
+    // Encoding an in-memory PNG can't fail
+    img.write_to(&mut buffer, image::ImageFormat::Png).unwrap();
+    buffer.into_inner()
+}
+
+/// An ICO file holding each image as an embedded PNG, as Windows Vista and every browser accept
+pub fn write_ico(images: &[(u32, Vec<u8>)]) -> Vec<u8> {
+    let mut ico = Vec::new();
+    ico.extend_from_slice(&0u16.to_le_bytes());
+    ico.extend_from_slice(&1u16.to_le_bytes()); // icon, not cursor
+    ico.extend_from_slice(&(images.len() as u16).to_le_bytes());
+    let mut offset = 6 + 16 * images.len() as u32;
+    for (size, png) in images {
+        // A dimension of 256 is written as 0
+        let dimension = if *size >= 256 { 0 } else { *size as u8 };
+        ico.extend_from_slice(&[dimension, dimension, 0, 0]);
+        ico.extend_from_slice(&1u16.to_le_bytes()); // color planes
+        ico.extend_from_slice(&32u16.to_le_bytes()); // bits per pixel
+        ico.extend_from_slice(&(png.len() as u32).to_le_bytes());
+        ico.extend_from_slice(&offset.to_le_bytes());
+        offset += png.len() as u32;
+    }
+    for (_, png) in images {
+        ico.extend_from_slice(png);
+    }
+    ico
+}
+
+/// A square icon at every size in [`ICON_SIZES`], center-cropped from `img`, plus a multi-size
+/// `favicon.ico`. Returns (file name, bytes) pairs ready to zip.
+pub fn render_icon_set(img: &RgbaImage) -> Vec<(String, Vec<u8>)> {
+    let side = img.width().min(img.height());
+    let square = crate::image_processing::crop_to_aspect(img, 1, 1);
+    let mut files = Vec::new();
+    let mut ico_images = Vec::new();
+    for size in ICON_SIZES {
+        // Upscaling past the source's resolution can't add detail, but every size is still provided
+        let filter = if size > side { image::imageops::FilterType::Triangle } else { image::imageops::FilterType::Lanczos3 };
+        let png = encode_png(&image::imageops::resize(&square, size, size, filter));
+        if ICO_SIZES.contains(&size) {
+            ico_images.push((size, png.clone()));
+        }
+        files.push((format!("icon-{}x{}.png", size, size), png));
+    }
+    files.push(("favicon.ico".to_string(), write_ico(&ico_images)));
+    files
+}
+
+/// Side of each image in an emoji pack, Discord's display size for emojis
+pub const EMOJI_SIZE: u32 = 128;
+
+/// Scale to fit within [`EMOJI_SIZE`] and center on a transparent square, so nothing is cropped
+/// and transparent backgrounds stay transparent
+pub fn fit_emoji(img: &RgbaImage) -> RgbaImage {
+    let scaled = crate::image_processing::resize_longest_side(img, EMOJI_SIZE);
+    let mut canvas = RgbaImage::new(EMOJI_SIZE, EMOJI_SIZE);
+    let x = (EMOJI_SIZE - scaled.width()) / 2;
+    let y = (EMOJI_SIZE - scaled.height()) / 2;
+    image::imageops::replace(&mut canvas, &scaled, x as i64, y as i64);
+    canvas
+}
+
+/// A valid emoji name for `stem` in a flavor, e.g. `catjam_mocha`: letters, digits and underscores,
+/// 2 to 32 characters, so the file uploads under its own name
+pub fn emoji_name(stem: &str, flavor: catppuccin::FlavorName) -> String {
+    let suffix = format!("_{}", flavor.identifier());
+    let mut base: String = stem
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+        .collect();
+    base = base.trim_matches('_').to_string();
+    if base.is_empty() {
+        base = "emoji".to_string();
+    }
+    base.truncate(32 - suffix.len());
+    format!("{}{}", base, suffix)
+}
+
+/// Encode one image of an emoji pack
+pub fn emoji_png(img: &RgbaImage) -> Vec<u8> {
+    encode_png(&fit_emoji(img))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_icon_set() {
+        let img = RgbaImage::from_pixel(300, 200, image::Rgba([30, 30, 46, 255]));
+        let files = render_icon_set(&img);
+        assert_eq!(files.len(), ICON_SIZES.len() + 1);
+        let (name, png) = &files[1];
+        assert_eq!(name, "icon-32x32.png");
+        let decoded = image::load_from_memory(png).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (32, 32));
+        let (name, ico) = files.last().unwrap();
+        assert_eq!(name, "favicon.ico");
+        assert_eq!(&ico[..6], &[0, 0, 1, 0, 4, 0]);
+        // The 256px entry is written with a dimension of 0, and its PNG is where the directory says
+        let entry = 6 + 16 * 3;
+        assert_eq!(&ico[entry..entry + 2], &[0, 0]);
+        let offset = u32::from_le_bytes(ico[entry + 12..entry + 16].try_into().unwrap()) as usize;
+        assert_eq!(&ico[offset + 1..offset + 4], b"PNG");
+    }
+
+    #[test]
+    fn test_fit_emoji_keeps_transparency() {
+        let img = RgbaImage::from_pixel(256, 64, image::Rgba([203, 166, 247, 255]));
+        let emoji = fit_emoji(&img);
+        assert_eq!(emoji.dimensions(), (EMOJI_SIZE, EMOJI_SIZE));
+        // A wide image is letterboxed with transparency rather than cropped
+        assert_eq!(emoji.get_pixel(64, 4).0[3], 0);
+        assert_eq!(emoji.get_pixel(64, 64).0, [203, 166, 247, 255]);
+        assert_eq!(emoji_name("Cat Jam!", catppuccin::FlavorName::Mocha), "cat_jam_mocha");
+        assert_eq!(emoji_name("a-very-long-emoji-name-for-testing", catppuccin::FlavorName::Macchiato).len(), 32);
+        assert_eq!(emoji_name("!!", catppuccin::FlavorName::Latte), "emoji_latte");
+    }
+}
@@ -3,105 +3,83 @@
 use rayon::prelude::*;
 use image::{RgbaImage, Rgba};
 use catppuccin::{PALETTE, FlavorName};
-use palette::{Lab, Srgb, IntoColor, color_difference::EuclideanDistance};
+use palette::{Hsv, Lab, Srgb, IntoColor, color_difference::{Ciede2000, EuclideanDistance}};
 use once_cell::sync::Lazy;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::sync::{Arc, Mutex};
 use gif::{Decoder as GifDecoder, Encoder as GifEncoder, Frame as GifFrame, Repeat};
 use std::io::Cursor;
+use crate::workdir::FrameStore;
 
-static LUT_CACHE: Lazy<Mutex<HashMap<(String, String), Arc<Vec<u8>>>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+// Cached LUTs by flavor and algorithm (with its tuning, for tuned ones)
+type LutKey = (String, String);
+type LutCache = HashMap<LutKey, Arc<Vec<u8>>>;
+type TunedLutCache = VecDeque<(LutKey, Arc<Vec<u8>>)>;
+
+static LUT_CACHE: Lazy<Mutex<LutCache>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// How many tuned LUTs are kept. Any number of tunings can be asked for and each LUT is 48 MB, so
+/// only the most recently used few stay, next to the one per flavor and algorithm in `LUT_CACHE`.
+const TUNED_LUT_CACHE_SIZE: usize = 2;
+
+// Tuned LUTs, least recently used first
+static TUNED_LUT_CACHE: Lazy<Mutex<TunedLutCache>> = Lazy::new(|| Mutex::new(VecDeque::new()));
 
 pub fn generate_catppuccin_lut(_flavor: FlavorName, _algorithm: &str) -> Arc<Vec<u8>> {
-    let key = (_flavor.to_string(), _algorithm.to_string());
+    generate_tuned_lut(_flavor, _algorithm, &Tuning::default())
+}
+
+/// `generate_catppuccin_lut` with the algorithm's parameters overridden. Only the last
+/// `TUNED_LUT_CACHE_SIZE` tunings are cached.
+pub fn generate_tuned_lut(flavor: FlavorName, algorithm: &str, tuning: &Tuning) -> Arc<Vec<u8>> {
+    if !tuning.is_default() {
+        return cached_tuned_lut(flavor, algorithm, tuning);
+    }
+    let key = (flavor.to_string(), algorithm.to_string());
     {
         let cache = LUT_CACHE.lock().unwrap();
         if let Some(lut) = cache.get(&key) {
             return lut.clone();
         }
     }
-    let colors_struct = match _flavor {
-        FlavorName::Latte => &PALETTE.latte.colors,
-        FlavorName::Frappe => &PALETTE.frappe.colors,
-        FlavorName::Macchiato => &PALETTE.macchiato.colors,
-        FlavorName::Mocha => &PALETTE.mocha.colors,
-    };
-    let catppuccin_colors = [
-        colors_struct.rosewater, colors_struct.flamingo, colors_struct.pink,
-        colors_struct.mauve, colors_struct.red, colors_struct.maroon,
-        colors_struct.peach, colors_struct.yellow, colors_struct.green,
-        colors_struct.teal, colors_struct.sky, colors_struct.sapphire,
-        colors_struct.blue, colors_struct.lavender, colors_struct.text,
-        colors_struct.subtext1, colors_struct.subtext0, colors_struct.overlay2,
-        colors_struct.overlay1, colors_struct.overlay0, colors_struct.surface2,
-        colors_struct.surface1, colors_struct.surface0, colors_struct.base,
-        colors_struct.mantle, colors_struct.crust,
-    ];
-    let catppuccin_labs: Vec<Lab> = catppuccin_colors.iter()
-        .map(|color| {
-            let (r, g, b) = (color.rgb.r, color.rgb.g, color.rgb.b);
-            Srgb::new(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0).into_color()
-        })
-        .collect();
+    let lut_arc = Arc::new(build_tuned_lut(flavor, algorithm, tuning));
+    let mut cache = LUT_CACHE.lock().unwrap();
+    cache.insert(key, lut_arc.clone());
+    lut_arc
+}
+
+fn cached_tuned_lut(flavor: FlavorName, algorithm: &str, tuning: &Tuning) -> Arc<Vec<u8>> {
+    let key = (flavor.to_string(), format!("{} ({})", algorithm, tuning.describe()));
+    {
+        let mut cache = TUNED_LUT_CACHE.lock().unwrap();
+        if let Some(i) = cache.iter().position(|(cached, _)| *cached == key) {
+            let entry = cache.remove(i).unwrap();
+            let lut = entry.1.clone();
+            cache.push_back(entry);
+            return lut;
+        }
+    }
+    let lut_arc = Arc::new(build_tuned_lut(flavor, algorithm, tuning));
+    let mut cache = TUNED_LUT_CACHE.lock().unwrap();
+    cache.push_back((key, lut_arc.clone()));
+    while cache.len() > TUNED_LUT_CACHE_SIZE {
+        cache.pop_front();
+    }
+    lut_arc
+}
+
+/// Generate a LUT, bypassing the cache; processing should go through `generate_catppuccin_lut`
+pub fn build_catppuccin_lut(_flavor: FlavorName, _algorithm: &str) -> Vec<u8> {
+    build_tuned_lut(_flavor, _algorithm, &Tuning::default())
+}
+
+fn build_tuned_lut(flavor: FlavorName, algorithm: &str, tuning: &Tuning) -> Vec<u8> {
+    let mapper = PaletteMapper::with_params(flavor, AlgorithmParams::for_algorithm(algorithm).tuned(tuning));
     let mut lut = vec![0u8; 256 * 256 * 256 * 3];
-    let (_iterations, power, use_weighted) = match _algorithm {
-        "shepards-method" => (100, 2.0, true),
-        "gaussian-rbf" => (50, 1.5, true),
-        "linear-rbf" => (30, 1.0, false),
-        "gaussian-sampling" => (200, 2.5, true),
-        "nearest-neighbor" => (1, 1.0, false),
-        "hald" => (150, 2.0, true),
-        "euclide" => (80, 1.0, false),
-        "mean" => (60, 1.5, true),
-        "std" => (90, 2.0, true),
-        _ => (100, 2.0, true),
-    };
     for r_idx in 0..256 {
         for g_idx in 0..256 {
             for b_idx in 0..256 {
-                let r = r_idx as f32 / 255.0;
-                let g = g_idx as f32 / 255.0;
-                let b = b_idx as f32 / 255.0;
-                let input_lab: Lab = Srgb::new(r, g, b).into_color();
-                let closest_color = if use_weighted {
-                    let mut total_weight = 0.0;
-                    let mut weighted_r = 0.0;
-                    let mut weighted_g = 0.0;
-                    let mut weighted_b = 0.0;
-                    for (i, cat_lab) in catppuccin_labs.iter().enumerate() {
-                        let distance = input_lab.distance_squared(*cat_lab);
-                        let weight = if distance > 0.0 { 1.0 / distance.powf(power) } else { 1e6 };
-                        let (cr, cg, cb) = (
-                            catppuccin_colors[i].rgb.r as f32 / 255.0,
-                            catppuccin_colors[i].rgb.g as f32 / 255.0,
-                            catppuccin_colors[i].rgb.b as f32 / 255.0,
-                        );
-                        weighted_r += cr * weight;
-                        weighted_g += cg * weight;
-                        weighted_b += cb * weight;
-                        total_weight += weight;
-                    }
-                    if total_weight > 0.0 {
-                        (
-                            (weighted_r / total_weight * 255.0).clamp(0.0, 255.0) as u8,
-                            (weighted_g / total_weight * 255.0).clamp(0.0, 255.0) as u8,
-                            (weighted_b / total_weight * 255.0).clamp(0.0, 255.0) as u8,
-                        )
-                    } else {
-                        (catppuccin_colors[0].rgb.r, catppuccin_colors[0].rgb.g, catppuccin_colors[0].rgb.b)
-                    }
-                } else {
-                    let mut min_distance = f32::MAX;
-                    let mut closest_color = catppuccin_colors[0];
-                    for (i, cat_lab) in catppuccin_labs.iter().enumerate() {
-                        let distance = input_lab.distance_squared(*cat_lab);
-                        if distance < min_distance {
-                            min_distance = distance;
-                            closest_color = catppuccin_colors[i];
-                        }
-                    }
-                    (closest_color.rgb.r, closest_color.rgb.g, closest_color.rgb.b)
-                };
+                let closest_color = mapper.map(r_idx as u8, g_idx as u8, b_idx as u8);
                 let lut_idx = (r_idx * 256 * 256 + g_idx * 256 + b_idx) * 3;
                 lut[lut_idx] = closest_color.0;
                 lut[lut_idx + 1] = closest_color.1;
@@ -109,10 +87,305 @@ pub fn generate_catppuccin_lut(_flavor: FlavorName, _algorithm: &str) -> Arc<Vec
             }
         }
     }
-    let lut_arc = Arc::new(lut);
+    lut
+}
+
+/// How an algorithm weighs palette colors by their Lab distance from the input color
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kernel {
+    /// Only the closest color, no blending
+    Nearest,
+    /// `1 / d^(2 * power)`, Shepard's inverse-distance weighting
+    InverseDistance,
+    /// `exp(-d² / 2σ²)`, a Gaussian radial basis function
+    Gaussian,
+    /// The average of the nearest colors to points jittered around the input with spread σ, so
+    /// boundaries between palette colors become short ramps instead of a blend of the whole palette
+    Sampling,
+}
+
+/// The internals of a mapping algorithm. Each algorithm has its own defaults; `Tuning` overrides them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AlgorithmParams {
+    pub kernel: Kernel,
+    /// Falloff of inverse-distance weighting; higher keeps colors closer to the nearest palette color
+    pub power: f32,
+    /// Width of the Gaussian kernel, in Lab distance
+    pub sigma: f32,
+    /// How many of the closest palette colors are blended, out of 26
+    pub neighbors: usize,
+    /// Weights under this fraction of the strongest one are dropped
+    pub cutoff: f32,
+    /// Spatial noise added to each pixel before lookup, in levels of 255, so flat areas don't band
+    pub noise: f32,
+}
+
+impl AlgorithmParams {
+    pub fn for_algorithm(algorithm: &str) -> Self {
+        // Nearest-K blends like Shepard's method, but only among the closest few colors, so saturated
+        // inputs aren't pulled toward the palette's average
+        if algorithm == "nearest-k" {
+            return AlgorithmParams { kernel: Kernel::InverseDistance, power: 2.0, sigma: 20.0, neighbors: 3, cutoff: 0.0, noise: 0.0 };
+        }
+        if algorithm == "gaussian-sampling" {
+            return AlgorithmParams { kernel: Kernel::Sampling, power: 2.5, sigma: 8.0, neighbors: 26, cutoff: 0.0, noise: 3.0 };
+        }
+        let (kernel, power) = match algorithm {
+            "shepards-method" => (Kernel::InverseDistance, 2.0),
+            "gaussian-rbf" => (Kernel::Gaussian, 1.5),
+            "linear-rbf" => (Kernel::Nearest, 1.0),
+            "nearest-neighbor" => (Kernel::Nearest, 1.0),
+            "hald" => (Kernel::InverseDistance, 2.0),
+            "euclide" => (Kernel::Nearest, 1.0),
+            "mean" => (Kernel::InverseDistance, 1.5),
+            "std" => (Kernel::InverseDistance, 2.0),
+            _ => (Kernel::InverseDistance, 2.0),
+        };
+        AlgorithmParams { kernel, power, sigma: 20.0, neighbors: 26, cutoff: 0.0, noise: 0.0 }
+    }
+
+    pub fn tuned(self, tuning: &Tuning) -> Self {
+        AlgorithmParams {
+            kernel: self.kernel,
+            power: tuning.power.unwrap_or(self.power),
+            sigma: tuning.sigma.unwrap_or(self.sigma),
+            neighbors: tuning.neighbors.unwrap_or(self.neighbors).clamp(1, 26),
+            cutoff: tuning.cutoff.unwrap_or(self.cutoff),
+            noise: self.noise,
+        }
+    }
+
+    // The blending weight of a palette color at `distance_squared` from the input
+    fn weight(&self, distance_squared: f32) -> f32 {
+        match self.kernel {
+            Kernel::Gaussian => (-distance_squared / (2.0 * self.sigma * self.sigma)).exp(),
+            _ if distance_squared > 0.0 => 1.0 / distance_squared.powf(self.power),
+            _ => 1e6,
+        }
+    }
+}
+
+/// Overrides for an algorithm's parameters from `--power`, `--sigma`, `--neighbors` and `--cutoff`.
+/// Kernels ignore the ones they don't use: nearest-color algorithms ignore them all.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Tuning {
+    pub power: Option<f32>,
+    pub sigma: Option<f32>,
+    pub neighbors: Option<usize>,
+    pub cutoff: Option<f32>,
+}
+
+impl Tuning {
+    pub fn is_default(&self) -> bool {
+        *self == Tuning::default()
+    }
+
+    /// The overrides for a reply, like `power 3, neighbors 4`
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(power) = self.power {
+            parts.push(format!("power {}", power));
+        }
+        if let Some(sigma) = self.sigma {
+            parts.push(format!("sigma {}", sigma));
+        }
+        if let Some(neighbors) = self.neighbors {
+            parts.push(format!("neighbors {}", neighbors));
+        }
+        if let Some(cutoff) = self.cutoff {
+            parts.push(format!("cutoff {}", cutoff));
+        }
+        parts.join(", ")
+    }
+}
+
+// Fixed standard-normal offsets for `Kernel::Sampling`, from a seeded hash so every LUT build jitters the same way
+static SAMPLE_OFFSETS: Lazy<[[f32; 3]; 16]> = Lazy::new(|| {
+    let mut state = 0x9E37_79B9_7F4A_7C15u64;
+    let mut uniform = move || {
+        // SplitMix64
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        ((z ^ (z >> 31)) >> 40) as f32 / (1u64 << 24) as f32
+    };
+    // Box-Muller
+    let mut normal = move || (-2.0 * (1.0 - uniform()).ln()).sqrt() * (std::f32::consts::TAU * uniform()).cos();
+    std::array::from_fn(|_| [normal(), normal(), normal()])
+});
+
+// Interleaved gradient noise in [0, 1): high-frequency and deterministic, so the same image always maps the same way
+fn gradient_noise(x: f32, y: f32) -> f32 {
+    (52.982_918 * (0.067_110_56 * x + 0.005_837_15 * y).fract()).fract()
+}
+
+// Each channel reads the noise at a different spot, so the jitter isn't only a change in brightness
+fn spatial_offset(x: usize, y: usize, amplitude: f32) -> [f32; 3] {
+    let (x, y) = (x as f32, y as f32);
+    [gradient_noise(x, y), gradient_noise(x + 19.0, y + 47.0), gradient_noise(x + 71.0, y + 13.0)].map(|n| (n * 2.0 - 1.0) * amplitude)
+}
+
+fn nudge(value: u8, offset: f32) -> u8 {
+    (value as f32 + offset).round().clamp(0.0, 255.0) as u8
+}
+
+/// Maps one color to a flavor's palette the way a LUT entry is built, so the mapping can be checked
+/// on a handful of colors without generating the whole 48 MB table
+pub struct PaletteMapper {
+    colors: [catppuccin::Color; 26],
+    labs: Vec<Lab>,
+    params: AlgorithmParams,
+}
+
+impl PaletteMapper {
+    pub fn new(flavor: FlavorName, algorithm: &str) -> Self {
+        Self::with_params(flavor, AlgorithmParams::for_algorithm(algorithm))
+    }
+
+    pub fn with_params(flavor: FlavorName, params: AlgorithmParams) -> Self {
+        let colors_struct = match flavor {
+            FlavorName::Latte => &PALETTE.latte.colors,
+            FlavorName::Frappe => &PALETTE.frappe.colors,
+            FlavorName::Macchiato => &PALETTE.macchiato.colors,
+            FlavorName::Mocha => &PALETTE.mocha.colors,
+        };
+        let colors = [
+            colors_struct.rosewater, colors_struct.flamingo, colors_struct.pink,
+            colors_struct.mauve, colors_struct.red, colors_struct.maroon,
+            colors_struct.peach, colors_struct.yellow, colors_struct.green,
+            colors_struct.teal, colors_struct.sky, colors_struct.sapphire,
+            colors_struct.blue, colors_struct.lavender, colors_struct.text,
+            colors_struct.subtext1, colors_struct.subtext0, colors_struct.overlay2,
+            colors_struct.overlay1, colors_struct.overlay0, colors_struct.surface2,
+            colors_struct.surface1, colors_struct.surface0, colors_struct.base,
+            colors_struct.mantle, colors_struct.crust,
+        ];
+        let labs = colors.iter()
+            .map(|color| {
+                let (r, g, b) = (color.rgb.r, color.rgb.g, color.rgb.b);
+                Srgb::new(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0).into_color()
+            })
+            .collect();
+        PaletteMapper { colors, labs, params }
+    }
+
+    /// The palette's colors, in the order of `catppuccin::FlavorColors`
+    pub fn palette(&self) -> impl Iterator<Item = (u8, u8, u8)> + '_ {
+        self.colors.iter().map(|color| (color.rgb.r, color.rgb.g, color.rgb.b))
+    }
+
+    /// `map` for the pixel at `x`, `y`, with the spatial noise the algorithm adds when a LUT is applied
+    pub fn map_pixel(&self, x: u32, y: u32, r: u8, g: u8, b: u8) -> (u8, u8, u8) {
+        if self.params.noise <= 0.0 {
+            return self.map(r, g, b);
+        }
+        let [dr, dg, db] = spatial_offset(x as usize, y as usize, self.params.noise);
+        self.map(nudge(r, dr), nudge(g, dg), nudge(b, db))
+    }
+
+    fn nearest(&self, lab: Lab) -> usize {
+        let mut nearest = 0;
+        let mut min_distance = f32::MAX;
+        for (i, cat_lab) in self.labs.iter().enumerate() {
+            let distance = lab.distance_squared(*cat_lab);
+            if distance < min_distance {
+                min_distance = distance;
+                nearest = i;
+            }
+        }
+        nearest
+    }
+
+    pub fn map(&self, r: u8, g: u8, b: u8) -> (u8, u8, u8) {
+        let input_lab: Lab = Srgb::new(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0).into_color();
+        if self.params.kernel == Kernel::Sampling {
+            let mut sum = [0.0f32; 3];
+            for [dl, da, db] in SAMPLE_OFFSETS.iter() {
+                let sigma = self.params.sigma;
+                let sample = Lab::new(input_lab.l + dl * sigma, input_lab.a + da * sigma, input_lab.b + db * sigma);
+                let color = self.colors[self.nearest(sample)];
+                sum[0] += color.rgb.r as f32;
+                sum[1] += color.rgb.g as f32;
+                sum[2] += color.rgb.b as f32;
+            }
+            let [r, g, b] = sum.map(|total| (total / SAMPLE_OFFSETS.len() as f32).round() as u8);
+            return (r, g, b);
+        }
+        let mut distances = [0.0f32; 26];
+        for (distance, cat_lab) in distances.iter_mut().zip(&self.labs) {
+            *distance = input_lab.distance_squared(*cat_lab);
+        }
+        let mut nearest = 0;
+        for (i, distance) in distances.iter().enumerate() {
+            if *distance < distances[nearest] {
+                nearest = i;
+            }
+        }
+        let closest_color = self.colors[nearest];
+        if self.params.kernel == Kernel::Nearest {
+            return (closest_color.rgb.r, closest_color.rgb.g, closest_color.rgb.b);
+        }
+        let mut weights = [0.0f32; 26];
+        for (weight, distance) in weights.iter_mut().zip(distances) {
+            *weight = self.params.weight(distance);
+        }
+        // Only the closest `neighbors` colors take part; the rest are zeroed rather than skipped so
+        // the sum runs in palette order either way
+        if self.params.neighbors < 26 {
+            let mut order: [usize; 26] = std::array::from_fn(|i| i);
+            order.select_nth_unstable_by(self.params.neighbors - 1, |a, b| distances[*a].total_cmp(&distances[*b]));
+            for i in &order[self.params.neighbors..] {
+                weights[*i] = 0.0;
+            }
+        }
+        let floor = weights.iter().copied().fold(0.0, f32::max) * self.params.cutoff;
+        let mut total_weight = 0.0;
+        let mut weighted_r = 0.0;
+        let mut weighted_g = 0.0;
+        let mut weighted_b = 0.0;
+        for (color, weight) in self.colors.iter().zip(weights) {
+            if weight < floor {
+                continue;
+            }
+            let (cr, cg, cb) = (
+                color.rgb.r as f32 / 255.0,
+                color.rgb.g as f32 / 255.0,
+                color.rgb.b as f32 / 255.0,
+            );
+            weighted_r += cr * weight;
+            weighted_g += cg * weight;
+            weighted_b += cb * weight;
+            total_weight += weight;
+        }
+        if total_weight > 0.0 {
+            (
+                (weighted_r / total_weight * 255.0).clamp(0.0, 255.0) as u8,
+                (weighted_g / total_weight * 255.0).clamp(0.0, 255.0) as u8,
+                (weighted_b / total_weight * 255.0).clamp(0.0, 255.0) as u8,
+            )
+        } else {
+            // A narrow Gaussian can underflow every weight far from the palette
+            (closest_color.rgb.r, closest_color.rgb.g, closest_color.rgb.b)
+        }
+    }
+}
+
+/// Drop every cached LUT (48 MB each); they're regenerated on next use. Returns how many were cached.
+pub fn clear_lut_cache() -> usize {
     let mut cache = LUT_CACHE.lock().unwrap();
-    cache.insert(key, lut_arc.clone());
-    lut_arc
+    let mut tuned = TUNED_LUT_CACHE.lock().unwrap();
+    let count = cache.len() + tuned.len();
+    cache.clear();
+    tuned.clear();
+    count
+}
+
+/// CIEDE2000 color difference between two sRGB colors
+pub fn delta_e(a: (u8, u8, u8), b: (u8, u8, u8)) -> f32 {
+    let lab_a: Lab = Srgb::new(a.0, a.1, a.2).into_format::<f32>().into_color();
+    let lab_b: Lab = Srgb::new(b.0, b.1, b.2).into_format::<f32>().into_color();
+    lab_a.difference(lab_b)
 }
 
 pub fn sample_lut(lut: &[u8], r: f32, g: f32, b: f32) -> [f32; 3] {
@@ -131,33 +404,262 @@ pub fn sample_lut(lut: &[u8], r: f32, g: f32, b: f32) -> [f32; 3] {
     }
 }
 
+/// Blend two LUTs: `t` = 0.0 gives `a`, 1.0 gives `b`
+pub fn interpolate_luts(a: &[u8], b: &[u8], t: f32) -> Vec<u8> {
+    let t = t.clamp(0.0, 1.0);
+    a.par_iter()
+        .zip(b.par_iter())
+        .map(|(&x, &y)| (x as f32 + (y as f32 - x as f32) * t).round() as u8)
+        .collect()
+}
+
+/// Work done so far by a long-running operation: rows of an image or frames of a GIF
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Progress {
+    pub done: usize,
+    pub total: usize,
+}
+
+impl Progress {
+    pub fn percent(&self) -> u8 {
+        if self.total == 0 {
+            return 100;
+        }
+        (self.done * 100 / self.total).min(100) as u8
+    }
+}
+
+/// Where processing functions report `Progress`; unbounded so blocking code never waits on it
+pub type ProgressSender = tokio::sync::mpsc::UnboundedSender<Progress>;
+
+// Progress is best-effort: nobody may be listening, or the listener may have gone away
+fn report_progress(progress: Option<&ProgressSender>, done: usize, total: usize) {
+    if let Some(sender) = progress {
+        let _ = sender.send(Progress { done, total });
+    }
+}
+
 pub fn apply_lut_to_image(img: &mut RgbaImage, lut: &[u8]) {
-    let (width, _height) = img.dimensions();
-    let pixels: Vec<(u32, u32, Rgba<u8>)> = img.pixels()
-        .enumerate()
-        .map(|(i, pixel)| {
-            let x = i as u32 % width;
-            let y = i as u32 / width;
-            (x, y, *pixel)
+    apply_lut_with_progress(img, lut, None);
+}
+
+/// Map every pixel through a LUT, reporting progress in rows
+pub fn apply_lut_with_progress(img: &mut RgbaImage, lut: &[u8], progress: Option<&ProgressSender>) {
+    let (width, height) = (img.width() as usize, img.height() as usize);
+    // Bands of rows are processed in parallel, one band at a time, for about 20 progress updates
+    let band_rows = height.div_ceil(20).max(1);
+    let mut rows_done = 0;
+    for band in img.chunks_mut((width * band_rows * 4).max(4)) {
+        band.par_chunks_mut(4).for_each(|pixel| {
+            let transformed = sample_lut(lut, pixel[0] as f32 / 255.0, pixel[1] as f32 / 255.0, pixel[2] as f32 / 255.0);
+            pixel[0] = (transformed[0] * 255.0).clamp(0.0, 255.0) as u8;
+            pixel[1] = (transformed[1] * 255.0).clamp(0.0, 255.0) as u8;
+            pixel[2] = (transformed[2] * 255.0).clamp(0.0, 255.0) as u8;
+        });
+        rows_done = (rows_done + band_rows).min(height);
+        report_progress(progress, rows_done, height);
+    }
+}
+
+/// Map an image through a LUT built for `algorithm`, adding the spatial noise that algorithm asks for
+pub fn apply_algorithm_lut(img: &mut RgbaImage, lut: &[u8], algorithm: &str) {
+    apply_algorithm_lut_with_progress(img, lut, algorithm, None);
+}
+
+/// `apply_algorithm_lut`, reporting progress in rows
+pub fn apply_algorithm_lut_with_progress(img: &mut RgbaImage, lut: &[u8], algorithm: &str, progress: Option<&ProgressSender>) {
+    apply_lut_dithered(img, lut, AlgorithmParams::for_algorithm(algorithm).noise, progress);
+}
+
+/// `apply_lut_with_progress`, first nudging each pixel by up to `noise` levels of position-dependent
+/// noise so flat areas and shallow gradients don't band. Matches `PaletteMapper::map_pixel`.
+pub fn apply_lut_dithered(img: &mut RgbaImage, lut: &[u8], noise: f32, progress: Option<&ProgressSender>) {
+    if noise <= 0.0 {
+        return apply_lut_with_progress(img, lut, progress);
+    }
+    let (width, height) = (img.width() as usize, img.height() as usize);
+    let band_rows = height.div_ceil(20).max(1);
+    let mut rows_done = 0;
+    for band in img.chunks_mut((width * band_rows * 4).max(4)) {
+        let first_pixel = rows_done * width;
+        band.par_chunks_mut(4).enumerate().for_each(|(i, pixel)| {
+            let position = first_pixel + i;
+            let [dr, dg, db] = spatial_offset(position % width, position / width, noise);
+            let (r, g, b) = (nudge(pixel[0], dr) as usize, nudge(pixel[1], dg) as usize, nudge(pixel[2], db) as usize);
+            let idx = (r * 256 * 256 + g * 256 + b) * 3;
+            if idx + 2 < lut.len() {
+                pixel[..3].copy_from_slice(&lut[idx..idx + 3]);
+            }
+        });
+        rows_done = (rows_done + band_rows).min(height);
+        report_progress(progress, rows_done, height);
+    }
+}
+
+/// Replace pixels within `tolerance` (CIEDE2000) of `source` with `target`, carrying over each pixel's
+/// lightness offset from the source so shading survives. Pixels near the tolerance edge are blended
+/// for a soft boundary. Returns how many pixels were touched.
+pub fn replace_color(img: &mut RgbaImage, source: (u8, u8, u8), target: (u8, u8, u8), tolerance: f32) -> usize {
+    let source_lab: Lab = Srgb::new(source.0, source.1, source.2).into_format::<f32>().into_color();
+    let target_lab: Lab = Srgb::new(target.0, target.1, target.2).into_format::<f32>().into_color();
+    let hard_edge = tolerance * 0.75;
+    img.par_chunks_mut(4)
+        .map(|pixel| {
+            let lab: Lab = Srgb::new(pixel[0], pixel[1], pixel[2]).into_format::<f32>().into_color();
+            let distance = lab.difference(source_lab);
+            if distance >= tolerance {
+                return 0;
+            }
+            let weight = if distance <= hard_edge { 1.0 } else { (tolerance - distance) / (tolerance - hard_edge) };
+            let shifted = Lab::new((target_lab.l + lab.l - source_lab.l).clamp(0.0, 100.0), target_lab.a, target_lab.b);
+            let rgb: Srgb = shifted.into_color();
+            let rgb = rgb.into_format::<u8>();
+            for (ch, value) in [rgb.red, rgb.green, rgb.blue].into_iter().enumerate() {
+                pixel[ch] = (pixel[ch] as f32 * (1.0 - weight) + value as f32 * weight).round() as u8;
+            }
+            1
         })
+        .sum()
+}
+
+/// Blend `processed` over `original` using a grayscale mask (white = processed, black = original).
+/// The mask is stretched to the image size if the dimensions differ.
+pub fn composite_with_mask(original: &RgbaImage, processed: &RgbaImage, mask: &image::GrayImage) -> RgbaImage {
+    let (w, h) = original.dimensions();
+    let mask = if mask.dimensions() != (w, h) {
+        image::imageops::resize(mask, w, h, image::imageops::FilterType::Triangle)
+    } else {
+        mask.clone()
+    };
+    RgbaImage::from_fn(w, h, |x, y| {
+        let m = mask.get_pixel(x, y)[0] as f32 / 255.0;
+        let a = original.get_pixel(x, y);
+        let b = processed.get_pixel(x, y);
+        let mut pixel = *a;
+        for ch in 0..4 {
+            pixel[ch] = (a[ch] as f32 * (1.0 - m) + b[ch] as f32 * m).round() as u8;
+        }
+        pixel
+    })
+}
+
+/// A mask that is white inside the rectangle (x, y, w, h) and black elsewhere
+pub fn rect_mask(width: u32, height: u32, region: (u32, u32, u32, u32)) -> image::GrayImage {
+    let (rx, ry, rw, rh) = region;
+    image::GrayImage::from_fn(width, height, |x, y| {
+        let inside = x >= rx && y >= ry && x < rx.saturating_add(rw) && y < ry.saturating_add(rh);
+        image::Luma([if inside { 255 } else { 0 }])
+    })
+}
+
+/// Estimate the background as a grayscale mask (white = background). Starting from the image border,
+/// flood-fills pixels whose color is close to one of the border colors, so enclosed subjects are left out
+/// even when they share the background's colors. Fully transparent pixels always count as background.
+pub fn segment_background(img: &RgbaImage) -> image::GrayImage {
+    let (w, h) = img.dimensions();
+    // Segment a small copy for speed; the mask is scaled back up and feathered afterwards
+    let scale = (256.0 / w.max(h) as f32).min(1.0);
+    let (sw, sh) = (((w as f32 * scale).round() as u32).max(1), ((h as f32 * scale).round() as u32).max(1));
+    let small = image::imageops::resize(img, sw, sh, image::imageops::FilterType::Triangle);
+    let labs: Vec<Lab> = small
+        .pixels()
+        .map(|p| Srgb::new(p[0], p[1], p[2]).into_format::<f32>().into_color())
+        .collect();
+    let border: Vec<usize> = (0..sw)
+        .flat_map(|x| [x as usize, ((sh - 1) * sw + x) as usize])
+        .chain((0..sh).flat_map(|y| [(y * sw) as usize, (y * sw + sw - 1) as usize]))
         .collect();
-    let transformed_pixels: Vec<(u32, u32, Rgba<u8>)> = pixels
-        .par_iter()
-        .map(|(x, y, pixel)| {
-            let r = pixel[0] as f32 / 255.0;
-            let g = pixel[1] as f32 / 255.0;
-            let b = pixel[2] as f32 / 255.0;
-            let a = pixel[3];
-            let transformed = sample_lut(lut, r, g, b);
-            let new_r = (transformed[0] * 255.0).clamp(0.0, 255.0) as u8;
-            let new_g = (transformed[1] * 255.0).clamp(0.0, 255.0) as u8;
-            let new_b = (transformed[2] * 255.0).clamp(0.0, 255.0) as u8;
-            (*x, *y, Rgba([new_r, new_g, new_b, a]))
+    let step = (border.len() / 64).max(1);
+    let samples: Vec<Lab> = border.iter().step_by(step).map(|&i| labs[i]).collect();
+    let threshold = 14.0;
+    let is_background_color = |i: usize| {
+        small.as_raw()[i * 4 + 3] < 16 || samples.iter().any(|s| labs[i].distance(*s) < threshold)
+    };
+    let mut background = vec![false; labs.len()];
+    let mut queue: std::collections::VecDeque<usize> = border.into_iter().filter(|&i| is_background_color(i)).collect();
+    for &i in &queue {
+        background[i] = true;
+    }
+    while let Some(i) = queue.pop_front() {
+        let (x, y) = ((i as u32 % sw) as i64, (i as u32 / sw) as i64);
+        for (nx, ny) in [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)] {
+            if nx < 0 || ny < 0 || nx >= sw as i64 || ny >= sh as i64 {
+                continue;
+            }
+            let n = (ny * sw as i64 + nx) as usize;
+            // Stop at strong edges as well as at colors unlike the border
+            if !background[n] && labs[n].distance(labs[i]) < threshold && is_background_color(n) {
+                background[n] = true;
+                queue.push_back(n);
+            }
+        }
+    }
+    let small_mask = image::GrayImage::from_fn(sw, sh, |x, y| image::Luma([if background[(y * sw + x) as usize] { 255 } else { 0 }]));
+    let mask = image::imageops::resize(&small_mask, w, h, image::imageops::FilterType::Triangle);
+    image::imageops::fast_blur(&mask, (w.max(h) as f32 / 512.0).max(1.0))
+}
+
+/// Process a sprite sheet of `cols`×`rows` equal cells independently: each cell is cropped to the
+/// bounding box of its visible pixels, mapped through `algorithm`'s LUT, and put back, so fully transparent
+/// margins are left byte-for-byte untouched. Returns the sheet and how many cells were empty.
+pub fn process_sprite_sheet(img: &RgbaImage, cols: u32, rows: u32, lut: &[u8], algorithm: &str) -> Result<(RgbaImage, usize), String> {
+    let (w, h) = img.dimensions();
+    if cols == 0 || rows == 0 || w % cols != 0 || h % rows != 0 {
+        return Err(format!("A {}x{} image can't be split evenly into {}x{} cells.", w, h, cols, rows));
+    }
+    let (cell_w, cell_h) = (w / cols, h / rows);
+    let cells: Vec<(u32, u32, Option<RgbaImage>)> = (0..cols * rows)
+        .into_par_iter()
+        .map(|i| {
+            let (x0, y0) = ((i % cols) * cell_w, (i / cols) * cell_h);
+            let cell = image::imageops::crop_imm(img, x0, y0, cell_w, cell_h).to_image();
+            let visible: Vec<(u32, u32)> = cell.enumerate_pixels().filter(|(_, _, p)| p[3] > 0).map(|(x, y, _)| (x, y)).collect();
+            if visible.is_empty() {
+                return (x0, y0, None);
+            }
+            let min_x = visible.iter().map(|p| p.0).min().unwrap();
+            let max_x = visible.iter().map(|p| p.0).max().unwrap();
+            let min_y = visible.iter().map(|p| p.1).min().unwrap();
+            let max_y = visible.iter().map(|p| p.1).max().unwrap();
+            let mut sprite = image::imageops::crop_imm(&cell, min_x, min_y, max_x - min_x + 1, max_y - min_y + 1).to_image();
+            apply_algorithm_lut(&mut sprite, lut, algorithm);
+            (x0 + min_x, y0 + min_y, Some(sprite))
         })
         .collect();
-    for (x, y, pixel) in transformed_pixels {
-        img.put_pixel(x, y, pixel);
+    let mut out = img.clone();
+    let mut empty = 0;
+    for (x, y, sprite) in cells {
+        match sprite {
+            Some(sprite) => image::imageops::replace(&mut out, &sprite, x as i64, y as i64),
+            None => empty += 1,
+        }
     }
+    Ok((out, empty))
+}
+
+/// Scale so the longest side is `longest` pixels, keeping the aspect ratio (Lanczos3)
+pub fn resize_longest_side(img: &RgbaImage, longest: u32) -> RgbaImage {
+    let scale = longest as f32 / img.width().max(img.height()) as f32;
+    let w = ((img.width() as f32 * scale).round() as u32).max(1);
+    let h = ((img.height() as f32 * scale).round() as u32).max(1);
+    image::imageops::resize(img, w, h, image::imageops::FilterType::Lanczos3)
+}
+
+/// Center-crop to the largest region with the given aspect ratio
+pub fn crop_to_aspect(img: &RgbaImage, aspect_w: u32, aspect_h: u32) -> RgbaImage {
+    let (w, h) = img.dimensions();
+    let (crop_w, crop_h) = if w as u64 * aspect_h as u64 > h as u64 * aspect_w as u64 {
+        (((h as u64 * aspect_w as u64) / aspect_h as u64).max(1) as u32, h)
+    } else {
+        (w, ((w as u64 * aspect_h as u64) / aspect_w as u64).max(1) as u32)
+    };
+    image::imageops::crop_imm(img, (w - crop_w) / 2, (h - crop_h) / 2, crop_w, crop_h).to_image()
+}
+
+/// Scale and center-crop to exactly `width`×`height`, filling the frame without distortion
+pub fn fit_to_size(img: &RgbaImage, width: u32, height: u32) -> RgbaImage {
+    let cropped = crop_to_aspect(img, width, height);
+    image::imageops::resize(&cropped, width, height, image::imageops::FilterType::Lanczos3)
 }
 
 pub fn create_comparison_image(original: &RgbaImage, processed: &RgbaImage) -> RgbaImage {
@@ -187,6 +689,104 @@ pub fn create_comparison_image(original: &RgbaImage, processed: &RgbaImage) -> R
     comparison
 }
 
+/// ΔE at which the delta map reaches its hottest color; larger changes are drawn the same
+pub const DELTA_MAP_SCALE: f32 = 40.0;
+
+/// A heatmap of how much processing changed each pixel, by CIEDE2000. Unchanged pixels are the
+/// flavor's base and changes run through blue, green, yellow and peach to red at `DELTA_MAP_SCALE`.
+/// Transparent pixels stay base and don't count towards the mean and largest change it also returns.
+pub fn render_delta_map(original: &RgbaImage, processed: &RgbaImage, flavor: FlavorName) -> (RgbaImage, f32, f32) {
+    let color = |name| crate::utils::catppuccin_color_name_to_rgb(name, flavor).unwrap();
+    let ramp = ["base", "blue", "green", "yellow", "peach", "red"].map(color);
+    let (width, height) = original.dimensions();
+    let deltas: Vec<Option<f32>> = original
+        .as_raw()
+        .par_chunks(4)
+        .zip(processed.as_raw().par_chunks(4))
+        .map(|(a, b)| (a[3] > 0).then(|| delta_e((a[0], a[1], a[2]), (b[0], b[1], b[2]))))
+        .collect();
+    let mut map = RgbaImage::new(width, height);
+    for (pixel, delta) in map.pixels_mut().zip(&deltas) {
+        let t = (delta.unwrap_or(0.0) / DELTA_MAP_SCALE).clamp(0.0, 1.0) * (ramp.len() - 1) as f32;
+        let (low, high) = (ramp[t.floor() as usize], ramp[(t.ceil() as usize).min(ramp.len() - 1)]);
+        let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t.fract()).round() as u8;
+        *pixel = Rgba([mix(low.0, high.0), mix(low.1, high.1), mix(low.2, high.2), 255]);
+    }
+    let counted: Vec<f32> = deltas.into_iter().flatten().collect();
+    let mean = if counted.is_empty() { 0.0 } else { counted.iter().sum::<f32>() / counted.len() as f32 };
+    let max = counted.iter().copied().fold(0.0, f32::max);
+    (map, mean, max)
+}
+
+/// Saturation of each hue-lightness plane `render_lut_slices` draws, from vivid to nearly gray
+pub const LUT_SLICE_SATURATIONS: [f32; 3] = [1.0, 0.6, 0.25];
+
+/// The most algorithms `render_lut_slices` lays side by side
+pub const LUT_SLICE_MAX_ALGORITHMS: usize = 3;
+
+const LUT_PLANE_WIDTH: u32 = 360;
+const LUT_PLANE_HEIGHT: u32 = 160;
+const LUT_LABEL_WIDTH: u32 = 72;
+const LUT_LABEL_HEIGHT: u32 = 28;
+const LUT_GAP: u32 = 8;
+
+/// What a LUT maps one exact input color to
+pub fn lut_lookup(lut: &[u8], (r, g, b): (u8, u8, u8)) -> (u8, u8, u8) {
+    let idx = (r as usize * 256 * 256 + g as usize * 256 + b as usize) * 3;
+    match lut.get(idx..idx + 3) {
+        Some(entry) => (entry[0], entry[1], entry[2]),
+        None => (r, g, b),
+    }
+}
+
+/// A plane through HSL space at one saturation: hue runs 0-360° across, lightness from white at
+/// the top to black at the bottom. Each point is drawn as `map` turns its input color.
+pub fn hue_lightness_plane(width: u32, height: u32, saturation: f32, map: impl Fn((u8, u8, u8)) -> (u8, u8, u8) + Sync) -> RgbaImage {
+    let mut plane = RgbaImage::new(width, height);
+    plane.par_chunks_mut(4).enumerate().for_each(|(i, pixel)| {
+        let (x, y) = (i as u32 % width, i as u32 / width);
+        let hue = x as f32 * 360.0 / width as f32;
+        let lightness = 1.0 - y as f32 / (height.max(2) - 1) as f32;
+        let (r, g, b) = map(crate::utils::hsl_to_rgb(hue, saturation, lightness));
+        pixel.copy_from_slice(&[r, g, b, 255]);
+    });
+    plane
+}
+
+/// Slices of each algorithm's LUT for `!cat lut show`: one row per `LUT_SLICE_SATURATIONS` plane,
+/// the unmapped input in the first column and an algorithm in each after it, labeled on the flavor's
+/// base. Also returns how many distinct colors each algorithm produced across its slices.
+pub fn render_lut_slices(flavor: FlavorName, algorithms: &[&str]) -> (RgbaImage, Vec<usize>) {
+    let rgb = |name: &str| crate::utils::catppuccin_color_name_to_rgb(name, flavor).unwrap();
+    let algorithms = &algorithms[..algorithms.len().min(LUT_SLICE_MAX_ALGORITHMS)];
+    let luts: Vec<Arc<Vec<u8>>> = algorithms.iter().map(|algorithm| generate_catppuccin_lut(flavor, algorithm)).collect();
+    let columns = 1 + luts.len() as u32;
+    let rows = LUT_SLICE_SATURATIONS.len() as u32;
+    let width = LUT_LABEL_WIDTH + columns * (LUT_PLANE_WIDTH + LUT_GAP);
+    let height = LUT_LABEL_HEIGHT + rows * (LUT_PLANE_HEIGHT + LUT_GAP);
+    let (br, bg, bb) = rgb("base");
+    let mut canvas = RgbaImage::from_pixel(width, height, Rgba([br, bg, bb, 255]));
+    let column_x = |column: u32| (LUT_LABEL_WIDTH + column * (LUT_PLANE_WIDTH + LUT_GAP)) as i64;
+    crate::text_rendering::draw_text(&mut canvas, "input", column_x(0) as i32, 4, 18.0, rgb("mauve"));
+    for (column, algorithm) in algorithms.iter().enumerate() {
+        crate::text_rendering::draw_text(&mut canvas, algorithm, column_x(column as u32 + 1) as i32, 4, 18.0, rgb("text"));
+    }
+    let mut distinct = vec![std::collections::HashSet::new(); luts.len()];
+    for (row, saturation) in LUT_SLICE_SATURATIONS.iter().enumerate() {
+        let y = (LUT_LABEL_HEIGHT + row as u32 * (LUT_PLANE_HEIGHT + LUT_GAP)) as i64;
+        let label = format!("S {:.0}%", saturation * 100.0);
+        crate::text_rendering::draw_text(&mut canvas, &label, 8, y as i32 + LUT_PLANE_HEIGHT as i32 / 2 - 9, 18.0, rgb("subtext0"));
+        let input = hue_lightness_plane(LUT_PLANE_WIDTH, LUT_PLANE_HEIGHT, *saturation, |color| color);
+        image::imageops::overlay(&mut canvas, &input, column_x(0), y);
+        for (column, lut) in luts.iter().enumerate() {
+            let plane = hue_lightness_plane(LUT_PLANE_WIDTH, LUT_PLANE_HEIGHT, *saturation, |color| lut_lookup(lut, color));
+            distinct[column].extend(plane.pixels().map(|p| (p[0], p[1], p[2])));
+            image::imageops::overlay(&mut canvas, &plane, column_x(column as u32 + 1), y);
+        }
+    }
+    (canvas, distinct.iter().map(|colors| colors.len()).collect())
+}
+
 pub fn analyze_image_colors(img: &RgbaImage) -> (Vec<(u8, u8, u8, u32)>, FlavorName) {
     let mut color_counts = std::collections::HashMap::new();
     for pixel in img.pixels() {
@@ -194,7 +794,7 @@ pub fn analyze_image_colors(img: &RgbaImage) -> (Vec<(u8, u8, u8, u32)>, FlavorN
         *color_counts.entry(key).or_insert(0) += 1;
     }
     let mut sorted_colors: Vec<_> = color_counts.into_iter().collect();
-    sorted_colors.sort_by(|a, b| b.1.cmp(&a.1));
+    sorted_colors.sort_by_key(|c| std::cmp::Reverse(c.1));
     let dominant_colors: Vec<(u8, u8, u8, u32)> = sorted_colors
         .into_iter()
         .take(5)
@@ -215,21 +815,285 @@ pub fn analyze_image_colors(img: &RgbaImage) -> (Vec<(u8, u8, u8, u32)>, FlavorN
     (dominant_colors, suggested_flavor)
 }
 
+/// What `!cat info` reports about an image file, read from its header without decoding the pixels
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageInfo {
+    pub format: image::ImageFormat,
+    pub width: u32,
+    pub height: u32,
+    /// 1 for stills
+    pub frames: usize,
+    pub color_type: image::ColorType,
+    pub file_size: usize,
+}
+
+impl ImageInfo {
+    /// Bits per channel
+    pub fn bit_depth(&self) -> u16 {
+        self.color_type.bits_per_pixel() / self.color_type.channel_count() as u16
+    }
+}
+
+pub fn image_info(bytes: &[u8]) -> Result<ImageInfo, String> {
+    use image::ImageDecoder;
+    let reader = image::ImageReader::new(Cursor::new(bytes)).with_guessed_format().map_err(|_| "Failed to read the image.".to_string())?;
+    let format = reader.format().ok_or_else(|| "Unrecognized image format.".to_string())?;
+    let decoder = reader.into_decoder().map_err(|e| format!("Failed to read the image: {e}"))?;
+    let (width, height) = decoder.dimensions();
+    let color_type = decoder.color_type();
+    let frames = if format == image::ImageFormat::Gif { gif_frame_count(bytes)? } else { 1 };
+    Ok(ImageInfo { format, width, height, frames, color_type, file_size: bytes.len() })
+}
+
+/// Average CIEDE2000 distance from an image's pixels to the nearest color of each flavor, closest
+/// flavor first. Around 4096 opaque pixels are sampled, so this is cheap even for large images.
+pub fn flavor_distances(img: &RgbaImage) -> Vec<(FlavorName, f32)> {
+    let step = ((img.width() as usize * img.height() as usize) / 4096).max(1);
+    let samples: Vec<(u8, u8, u8)> = img.pixels().step_by(step).filter(|p| p[3] >= 128).map(|p| (p[0], p[1], p[2])).collect();
+    let mut distances: Vec<(FlavorName, f32)> = [FlavorName::Latte, FlavorName::Frappe, FlavorName::Macchiato, FlavorName::Mocha]
+        .into_par_iter()
+        .map(|flavor| {
+            let colors = flavor_colors(flavor);
+            let total: f32 = samples
+                .iter()
+                .map(|rgb| colors.iter().map(|(_, c)| delta_e(*rgb, *c)).fold(f32::MAX, f32::min))
+                .sum();
+            (flavor, if samples.is_empty() { 0.0 } else { total / samples.len() as f32 })
+        })
+        .collect();
+    distances.sort_by(|a, b| a.1.total_cmp(&b.1));
+    distances
+}
+
+/// Color adjustments applied before the LUT, to steer which palette colors dominate the result
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PreAdjust {
+    /// Degrees around the color wheel
+    pub hue_shift: f32,
+    /// Saturation multiplier; 1 leaves it unchanged
+    pub saturation: f32,
+    /// Brightness (HSV value) multiplier; 1 leaves it unchanged
+    pub brightness: f32,
+    /// Neutralize color casts and stretch contrast first, so dark or tinted photos use the whole palette
+    pub auto_correct: bool,
+    /// White point shift from -100 (cool) to 100 (warm); 0 leaves it unchanged
+    pub temperature: f32,
+    /// The image's colors are premultiplied by alpha, so they're divided back out before mapping
+    pub premultiplied: bool,
+    /// Palette color (of the flavor being mapped to) the image is flattened onto first, making it opaque
+    pub matte: Option<&'static str>,
+}
+
+impl Default for PreAdjust {
+    fn default() -> Self {
+        PreAdjust { hue_shift: 0.0, saturation: 1.0, brightness: 1.0, auto_correct: false, temperature: 0.0, premultiplied: false, matte: None }
+    }
+}
+
+impl PreAdjust {
+    pub fn is_identity(&self) -> bool {
+        *self == PreAdjust::default()
+    }
+
+    /// The adjustments made, for result messages, e.g. `hue +30°, saturation ×1.5`
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if self.premultiplied {
+            parts.push("premultiplied alpha".to_string());
+        }
+        if let Some(matte) = self.matte {
+            parts.push(format!("on {}", matte));
+        }
+        if self.auto_correct {
+            parts.push("auto-corrected".to_string());
+        }
+        if self.temperature != 0.0 {
+            parts.push(format!("{} {}", if self.temperature > 0.0 { "warmer" } else { "cooler" }, self.temperature.abs()));
+        }
+        if self.hue_shift != 0.0 {
+            parts.push(format!("hue {:+}°", self.hue_shift));
+        }
+        if self.saturation != 1.0 {
+            parts.push(format!("saturation ×{}", self.saturation));
+        }
+        if self.brightness != 1.0 {
+            parts.push(format!("brightness ×{}", self.brightness));
+        }
+        parts.join(", ")
+    }
+}
+
+/// Gray-world white balance followed by a contrast stretch. Each channel is scaled so the image
+/// averages out to gray, then levels are stretched so the darkest and brightest 1% of pixels reach
+/// black and white. Fully transparent pixels are ignored.
+pub fn auto_correct(img: &mut RgbaImage) {
+    let opaque = || img.pixels().filter(|p| p[3] > 0);
+    let count = opaque().count();
+    if count == 0 {
+        return;
+    }
+    let mut sums = [0f64; 3];
+    for pixel in opaque() {
+        for c in 0..3 {
+            sums[c] += pixel[c] as f64;
+        }
+    }
+    let means = sums.map(|sum| (sum / count as f64) as f32);
+    let gray = (means[0] + means[1] + means[2]) / 3.0;
+    // A channel that's almost absent would be blown up into noise
+    let gains = means.map(|mean| if mean > 1.0 { (gray / mean).clamp(0.25, 4.0) } else { 1.0 });
+    let balance = |pixel: &Rgba<u8>, c: usize| (pixel[c] as f32 * gains[c]).min(255.0);
+
+    // Levels come from the balanced luminance; the same stretch for every channel keeps hues
+    let mut histogram = [0usize; 256];
+    for pixel in opaque() {
+        let l = 0.299 * balance(pixel, 0) + 0.587 * balance(pixel, 1) + 0.114 * balance(pixel, 2);
+        histogram[l.round() as usize] += 1;
+    }
+    let clip = count / 100;
+    let percentile = |levels: &mut dyn Iterator<Item = usize>| {
+        let mut seen = 0;
+        for level in levels {
+            seen += histogram[level];
+            if seen > clip {
+                return level;
+            }
+        }
+        0
+    };
+    let low = percentile(&mut (0..256)) as f32;
+    let high = percentile(&mut (0..256).rev()) as f32;
+    // Flat images have nothing to stretch
+    let (low, scale) = if high - low >= 8.0 { (low, 255.0 / (high - low)) } else { (0.0, 1.0) };
+
+    img.par_chunks_mut(4).for_each(|pixel| {
+        for c in 0..3 {
+            let balanced = (pixel[c] as f32 * gains[c]).min(255.0);
+            pixel[c] = ((balanced - low) * scale).round().clamp(0.0, 255.0) as u8;
+        }
+    });
+}
+
+/// Move the white point towards warm (positive) or cool (negative) light, `amount` from -100 to 100.
+/// Red and blue are traded against each other, then each pixel's HSL lightness is restored so only
+/// the tint changes.
+pub fn shift_temperature(img: &mut RgbaImage, amount: f32) {
+    let t = amount.clamp(-100.0, 100.0) / 100.0;
+    if t == 0.0 {
+        return;
+    }
+    let (red_gain, blue_gain) = (1.0 + 0.3 * t, 1.0 - 0.3 * t);
+    img.par_chunks_mut(4).for_each(|pixel| {
+        let (_, _, lightness) = crate::utils::rgb_to_hsl(pixel[0], pixel[1], pixel[2]);
+        let red = (pixel[0] as f32 * red_gain).round().clamp(0.0, 255.0) as u8;
+        let blue = (pixel[2] as f32 * blue_gain).round().clamp(0.0, 255.0) as u8;
+        let (hue, saturation, _) = crate::utils::rgb_to_hsl(red, pixel[1], blue);
+        let (r, g, b) = crate::utils::hsl_to_rgb(hue, saturation, lightness);
+        pixel[..3].copy_from_slice(&[r, g, b]);
+    });
+}
+
+/// Divide premultiplied colors back out by alpha, so semi-transparent edges map as the color they
+/// are rather than as a darker one. Fully transparent pixels have no color to recover.
+pub fn unpremultiply(img: &mut RgbaImage) {
+    img.par_chunks_mut(4).for_each(|pixel| {
+        let alpha = pixel[3] as u32;
+        if alpha > 0 && alpha < 255 {
+            for channel in pixel.iter_mut().take(3) {
+                *channel = ((*channel as u32 * 255 + alpha / 2) / alpha).min(255) as u8;
+            }
+        }
+    });
+}
+
+/// Composite the image onto a solid color, leaving it fully opaque
+pub fn flatten_onto(img: &mut RgbaImage, background: (u8, u8, u8)) {
+    let background = [background.0, background.1, background.2];
+    img.par_chunks_mut(4).for_each(|pixel| {
+        let alpha = pixel[3] as u32;
+        for c in 0..3 {
+            pixel[c] = ((pixel[c] as u32 * alpha + background[c] as u32 * (255 - alpha) + 127) / 255) as u8;
+        }
+        pixel[3] = 255;
+    });
+}
+
+/// Shift hue and scale saturation and brightness of every pixel, in HSV, after the auto-correction
+/// and temperature shift if they're on. Alpha is kept, unless a matte in `flavor`'s palette flattens
+/// the image first; premultiplied colors are recovered before anything else.
+pub fn apply_pre_adjustments(img: &mut RgbaImage, adjust: &PreAdjust, flavor: FlavorName) {
+    if adjust.premultiplied {
+        unpremultiply(img);
+    }
+    if let Some(background) = adjust.matte.and_then(|name| crate::utils::catppuccin_color_name_to_rgb(name, flavor)) {
+        flatten_onto(img, background);
+    }
+    if adjust.auto_correct {
+        auto_correct(img);
+    }
+    shift_temperature(img, adjust.temperature);
+    if (PreAdjust { auto_correct: false, temperature: 0.0, premultiplied: false, matte: None, ..*adjust }).is_identity() {
+        return;
+    }
+    img.par_chunks_mut(4).for_each(|pixel| {
+        let mut hsv: Hsv = Srgb::new(pixel[0], pixel[1], pixel[2]).into_format::<f32>().into_color();
+        hsv.hue += adjust.hue_shift;
+        hsv.saturation = (hsv.saturation * adjust.saturation).clamp(0.0, 1.0);
+        hsv.value = (hsv.value * adjust.brightness).clamp(0.0, 1.0);
+        let rgb: Srgb = hsv.into_color();
+        let rgb = rgb.into_format::<u8>();
+        pixel[0] = rgb.red;
+        pixel[1] = rgb.green;
+        pixel[2] = rgb.blue;
+    });
+}
+
 pub fn process_image_with_palette(img: &image::DynamicImage, _flavor: catppuccin::FlavorName, _algorithm: &str) -> image::DynamicImage {
-    let lut = generate_catppuccin_lut(_flavor, _algorithm);
+    process_image_with_progress(img, _flavor, _algorithm, &PreAdjust::default(), None)
+}
+
+/// `process_image_with_palette` with pre-adjustments, reporting progress in rows
+pub fn process_image_with_progress(
+    img: &image::DynamicImage,
+    flavor: catppuccin::FlavorName,
+    algorithm: &str,
+    adjust: &PreAdjust,
+    progress: Option<&ProgressSender>,
+) -> image::DynamicImage {
+    let lut = generate_catppuccin_lut(flavor, algorithm);
     let mut img_rgba = img.to_rgba8();
-    apply_lut_to_image(&mut img_rgba, &lut);
+    apply_pre_adjustments(&mut img_rgba, adjust, flavor);
+    apply_lut_dithered(&mut img_rgba, &lut, AlgorithmParams::for_algorithm(algorithm).noise, progress);
     image::DynamicImage::ImageRgba8(img_rgba)
 }
 
 pub fn process_gif_with_palette(gif_bytes: &[u8], flavor: catppuccin::FlavorName, algorithm: &str) -> Result<Vec<u8>, String> {
+    process_gif_with_progress(gif_bytes, flavor, algorithm, &PreAdjust::default(), None, false, None)
+}
+
+/// `process_gif_with_palette` with pre-adjustments, reporting progress in frames. Transparent pixels
+/// stay transparent unless a `background` is given to flatten every frame onto. `strict` snaps every
+/// frame to the palette, like `quantize_to_palette` does for stills.
+pub fn process_gif_with_progress(
+    gif_bytes: &[u8],
+    flavor: catppuccin::FlavorName,
+    algorithm: &str,
+    adjust: &PreAdjust,
+    background: Option<(u8, u8, u8)>,
+    strict: bool,
+    progress: Option<&ProgressSender>,
+) -> Result<Vec<u8>, String> {
+    // Counting costs an extra pass over the file, so only do it when someone is listening
+    let total_frames = if progress.is_some() { gif_frame_count(gif_bytes)? } else { 0 };
+    let mut frames_done = 0;
     let mut decoder = GifDecoder::new(Cursor::new(gif_bytes)).map_err(|e| format!("Failed to create GIF decoder: {e}"))?;
     let global_palette = decoder.global_palette().map(|p| p.to_vec());
-    let mut processed_frames = Vec::new();
+    // Frames are encoded as soon as they're processed rather than collected first
+    let mut encoder = None;
     while let Some(frame) = decoder.read_next_frame().map_err(|e| format!("Failed to read GIF frame: {e}"))? {
-        let width = frame.width as u16;
-        let height = frame.height as u16;
-        let palette = frame.palette.as_ref().map(|v| v.as_slice()).or(global_palette.as_ref().map(|v| v.as_slice()));
+        let width = frame.width;
+        let height = frame.height;
+        let palette = frame.palette.as_deref().or(global_palette.as_deref());
         println!("GIF frame: width={}, height={}, buffer_len={}, palette_len={}",
             width, height, frame.buffer.len(), palette.map(|p| p.len()).unwrap_or(0));
         // Convert indexed frame to RGBA
@@ -241,7 +1105,7 @@ pub fn process_gif_with_palette(gif_bytes: &[u8], flavor: catppuccin::FlavorName
                     rgba_buf.push(pal[i]);     // R
                     rgba_buf.push(pal[i + 1]); // G
                     rgba_buf.push(pal[i + 2]); // B
-                    rgba_buf.push(255);        // A
+                    rgba_buf.push(if frame.transparent == Some(idx) { 0 } else { 255 }); // A
                 } else {
                     rgba_buf.extend_from_slice(&[0, 0, 0, 255]);
                 }
@@ -255,31 +1119,293 @@ pub fn process_gif_with_palette(gif_bytes: &[u8], flavor: catppuccin::FlavorName
         let mut rgba_img = image::RgbaImage::from_raw(width as u32, height as u32, rgba_buf)
             .ok_or("Failed to convert GIF frame to RGBA image")?;
         let lut = generate_catppuccin_lut(flavor, algorithm);
-        apply_lut_to_image(&mut rgba_img, &lut);
+        apply_pre_adjustments(&mut rgba_img, adjust, flavor);
+        apply_algorithm_lut(&mut rgba_img, &lut, algorithm);
+        if let Some(background) = background {
+            flatten_onto(&mut rgba_img, background);
+        }
+        // After flattening, which blends edge pixels into in-between colors
+        if strict {
+            quantize_to_palette(&mut rgba_img, flavor);
+        }
         let mut processed_frame = GifFrame::from_rgba_speed(width, height, &mut rgba_img.into_raw(), 10);
         processed_frame.delay = frame.delay;
-        processed_frames.push(processed_frame);
-    }
-    // Encode new GIF
-    let mut output = Vec::new();
-    if let Some(first_frame) = processed_frames.first() {
-        let mut encoder = GifEncoder::new(&mut output, first_frame.width, first_frame.height, &[])
-            .map_err(|e| format!("Failed to create GIF encoder: {e}"))?;
-        encoder.set_repeat(Repeat::Infinite).map_err(|e| format!("Failed to set GIF repeat: {e}"))?;
-        for frame in processed_frames {
-            encoder.write_frame(&frame).map_err(|e| format!("Failed to write GIF frame: {e}"))?;
+        if encoder.is_none() {
+            let mut new_encoder = GifEncoder::new(Vec::new(), width, height, &[])
+                .map_err(|e| format!("Failed to create GIF encoder: {e}"))?;
+            new_encoder.set_repeat(Repeat::Infinite).map_err(|e| format!("Failed to set GIF repeat: {e}"))?;
+            encoder = Some(new_encoder);
         }
+        if let Some(encoder) = encoder.as_mut() {
+            encoder.write_frame(&processed_frame).map_err(|e| format!("Failed to write GIF frame: {e}"))?;
+        }
+        frames_done += 1;
+        report_progress(progress, frames_done, total_frames);
+    }
+    match encoder {
+        Some(encoder) => encoder.into_inner().map_err(|e| format!("Failed to finish GIF: {e}")),
+        None => Ok(Vec::new()),
     }
-    Ok(output)
 }
 
-/// Generate a simple animation effect (e.g., fade in/out) as a GIF from a static image
-pub fn animate_image_effect(img: &image::RgbaImage, effect: &str) -> Result<Vec<u8>, String> {
-    let width = img.width() as u16;
-    let height = img.height() as u16;
-    let mut frames = Vec::new();
-    let n_frames = 12;
-    match effect {
+/// A frame-order or timing change for `!cat gif`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GifOp {
+    /// Play faster (> 1.0) or slower (< 1.0)
+    Speed(f32),
+    Reverse,
+    /// Play forward then backward
+    Boomerang,
+}
+
+/// Decode a GIF into full-canvas RGBA frames with their delays (centiseconds),
+/// honoring frame offsets and disposal so partial frames come out complete.
+/// Large animations spill to disk rather than being held in memory.
+pub fn decode_gif_frames(gif_bytes: &[u8]) -> Result<FrameStore, String> {
+    let mut options = gif::DecodeOptions::new();
+    options.set_color_output(gif::ColorOutput::RGBA);
+    let mut decoder = options.read_info(Cursor::new(gif_bytes)).map_err(|e| format!("Failed to create GIF decoder: {e}"))?;
+    let (width, height) = (decoder.width() as u32, decoder.height() as u32);
+    let mut canvas = RgbaImage::new(width, height);
+    let mut frames = FrameStore::new();
+    while let Some(frame) = decoder.read_next_frame().map_err(|e| format!("Failed to read GIF frame: {e}"))? {
+        let previous = canvas.clone();
+        let patch = RgbaImage::from_raw(frame.width as u32, frame.height as u32, frame.buffer.to_vec())
+            .ok_or("Failed to convert GIF frame to RGBA image")?;
+        image::imageops::overlay(&mut canvas, &patch, frame.left as i64, frame.top as i64);
+        frames.push(canvas.clone(), frame.delay)?;
+        match frame.dispose {
+            gif::DisposalMethod::Background => {
+                for y in frame.top as u32..(frame.top as u32 + frame.height as u32).min(height) {
+                    for x in frame.left as u32..(frame.left as u32 + frame.width as u32).min(width) {
+                        canvas.put_pixel(x, y, Rgba([0, 0, 0, 0]));
+                    }
+                }
+            }
+            gif::DisposalMethod::Previous => canvas = previous,
+            _ => {}
+        }
+    }
+    if frames.is_empty() {
+        return Err("GIF has no frames".to_string());
+    }
+    Ok(frames)
+}
+
+/// Encode full-canvas frames with their delays (centiseconds) as a looping GIF
+pub fn encode_gif_frames(frames: Vec<(RgbaImage, u16)>) -> Result<Vec<u8>, String> {
+    encode_gif_stream(frames.into_iter().map(Ok))
+}
+
+// Frames are encoded as they arrive, so frames read back from a spilled FrameStore are never all loaded at once
+fn encode_gif_stream(frames: impl Iterator<Item = Result<(RgbaImage, u16), String>>) -> Result<Vec<u8>, String> {
+    let mut encoder = None;
+    for next in frames {
+        let (img, delay) = next?;
+        let (width, height) = (img.width() as u16, img.height() as u16);
+        if encoder.is_none() {
+            let mut new_encoder = GifEncoder::new(Vec::new(), width, height, &[]).map_err(|e| format!("Failed to create GIF encoder: {e}"))?;
+            new_encoder.set_repeat(Repeat::Infinite).map_err(|e| format!("Failed to set GIF repeat: {e}"))?;
+            encoder = Some(new_encoder);
+        }
+        let mut frame = GifFrame::from_rgba_speed(width, height, &mut img.into_raw(), 10);
+        frame.delay = delay;
+        // Every frame is a full canvas, so clear it before drawing the next one
+        frame.dispose = gif::DisposalMethod::Background;
+        if let Some(encoder) = encoder.as_mut() {
+            encoder.write_frame(&frame).map_err(|e| format!("Failed to write GIF frame: {e}"))?;
+        }
+    }
+    let encoder = encoder.ok_or("No frames to encode")?;
+    encoder.into_inner().map_err(|e| format!("Failed to finish GIF: {e}"))
+}
+
+/// Change a GIF's speed or frame order, optionally mapping every frame through `algorithm`'s LUT
+/// and snapping it to the `strict` flavor's colors. Browsers clamp delays under 2cs, so when
+/// speeding up, frames that would be shorter than that are dropped and their time is given to the
+/// next frame kept.
+pub fn transform_gif(gif_bytes: &[u8], op: GifOp, lut: Option<&[u8]>, algorithm: &str, strict: Option<catppuccin::FlavorName>) -> Result<Vec<u8>, String> {
+    let frames = decode_gif_frames(gif_bytes)?;
+    let count = frames.len();
+    // Which decoded frame to show at each step, and for how long
+    let plan: Vec<(usize, u16)> = match op {
+        GifOp::Speed(factor) => {
+            let mut sped_up = Vec::new();
+            let mut pending = 0.0;
+            for i in 0..count {
+                // A delay of 0 plays at roughly 10cs in browsers
+                let delay = if frames.delay(i) == 0 { 10 } else { frames.delay(i) };
+                pending += delay as f32 / factor;
+                if pending >= 2.0 || i == count - 1 {
+                    sped_up.push((i, pending.round().clamp(2.0, u16::MAX as f32) as u16));
+                    pending = 0.0;
+                }
+            }
+            sped_up
+        }
+        GifOp::Reverse => (0..count).rev().map(|i| (i, frames.delay(i))).collect(),
+        GifOp::Boomerang => {
+            // Skip the turnaround frames so the ends don't stutter
+            let backward = (0..count).rev().skip(1).take(count.saturating_sub(2));
+            (0..count).chain(backward).map(|i| (i, frames.delay(i))).collect()
+        }
+    };
+    encode_gif_stream(plan.into_iter().map(|(i, delay)| {
+        let mut img = frames.get(i)?;
+        if let Some(lut) = lut {
+            apply_algorithm_lut(&mut img, lut, algorithm);
+        }
+        if let Some(flavor) = strict {
+            quantize_to_palette(&mut img, flavor);
+        }
+        Ok((img, delay))
+    }))
+}
+
+/// Scale every frame of a GIF to `width`x`height`, keeping the timing
+pub fn resize_gif(gif_bytes: &[u8], width: u32, height: u32) -> Result<Vec<u8>, String> {
+    let frames = decode_gif_frames(gif_bytes)?;
+    encode_gif_stream((0..frames.len()).map(|i| {
+        let img = frames.get(i)?;
+        Ok((image::imageops::resize(&img, width, height, image::imageops::FilterType::Triangle), frames.delay(i)))
+    }))
+}
+
+/// Frames morphing an image from one flavor to another and back, by interpolating between the two
+/// flavors' LUTs with ease-in-out timing. The image is scaled down to fit within `max_side`.
+/// Returns frames with their delays (centiseconds), ready for GIF or video encoding.
+pub fn generate_flavor_morph_frames(
+    img: &RgbaImage,
+    from: catppuccin::FlavorName,
+    to: catppuccin::FlavorName,
+    algorithm: &str,
+    steps: usize,
+    max_side: u32,
+) -> Vec<(RgbaImage, u16)> {
+    let img = if img.width().max(img.height()) > max_side { resize_longest_side(img, max_side) } else { img.clone() };
+    let lut_from = generate_catppuccin_lut(from, algorithm);
+    let lut_to = generate_catppuccin_lut(to, algorithm);
+    let steps = steps.max(2);
+    let forward: Vec<RgbaImage> = (0..steps)
+        .map(|i| {
+            let t = crate::palette::apply_easing(i as f32 / (steps - 1) as f32, "ease-in-out");
+            let lut = interpolate_luts(&lut_from, &lut_to, t);
+            let mut frame = img.clone();
+            apply_algorithm_lut(&mut frame, &lut, algorithm);
+            frame
+        })
+        .collect();
+    // Hold on each flavor, then play back so the loop has no jump
+    let mut frames = Vec::new();
+    for (i, frame) in forward.iter().enumerate() {
+        let delay = if i == 0 || i == steps - 1 { 80 } else { 6 };
+        frames.push((frame.clone(), delay));
+    }
+    for frame in forward.iter().rev().skip(1).take(steps - 2) {
+        frames.push((frame.clone(), 6));
+    }
+    frames
+}
+
+/// Frames crossfading from one image to another and back. `a` is scaled down to fit within `max_side`
+/// and `b` is scaled and center-cropped to match it. Returns frames with their delays (centiseconds).
+pub fn generate_crossfade_frames(a: &RgbaImage, b: &RgbaImage, steps: usize, max_side: u32) -> Vec<(RgbaImage, u16)> {
+    let a = if a.width().max(a.height()) > max_side { resize_longest_side(a, max_side) } else { a.clone() };
+    let b = if b.dimensions() == a.dimensions() { b.clone() } else { fit_to_size(b, a.width(), a.height()) };
+    let steps = steps.max(2);
+    let forward: Vec<RgbaImage> = (0..steps)
+        .map(|i| {
+            let t = crate::palette::apply_easing(i as f32 / (steps - 1) as f32, "ease-in-out");
+            let mut frame = a.clone();
+            for (p, q) in frame.pixels_mut().zip(b.pixels()) {
+                for ch in 0..4 {
+                    p[ch] = (p[ch] as f32 + (q[ch] as f32 - p[ch] as f32) * t).round() as u8;
+                }
+            }
+            frame
+        })
+        .collect();
+    // Hold on each image, then fade back so the loop has no jump
+    let mut frames = Vec::new();
+    for (i, frame) in forward.iter().enumerate() {
+        let delay = if i == 0 || i == steps - 1 { 100 } else { 5 };
+        frames.push((frame.clone(), delay));
+    }
+    for frame in forward.iter().rev().skip(1).take(steps - 2) {
+        frames.push((frame.clone(), 5));
+    }
+    frames
+}
+
+/// Number of frames in a GIF. Frame data is skipped rather than converted, so this is cheap.
+pub fn gif_frame_count(gif_bytes: &[u8]) -> Result<usize, String> {
+    gif_size(gif_bytes).map(|(_, _, count)| count)
+}
+
+/// A GIF's canvas width and height and its number of frames, without decoding any pixels
+pub fn gif_size(gif_bytes: &[u8]) -> Result<(u32, u32, usize), String> {
+    let mut decoder = GifDecoder::new(Cursor::new(gif_bytes)).map_err(|e| format!("Failed to create GIF decoder: {e}"))?;
+    let (width, height) = (decoder.width() as u32, decoder.height() as u32);
+    let mut count = 0;
+    while decoder.next_frame_info().map_err(|e| format!("Failed to read GIF frame: {e}"))?.is_some() {
+        count += 1;
+    }
+    Ok((width, height, count))
+}
+
+/// A single fully-composited GIF frame by 1-based index
+pub fn extract_gif_frame(gif_bytes: &[u8], index: usize) -> Result<RgbaImage, String> {
+    let frames = decode_gif_frames(gif_bytes)?;
+    let count = frames.len();
+    if index == 0 || index > count {
+        return Err(format!("Frame {} is out of range; this GIF has {} frames.", index, count));
+    }
+    frames.get(index - 1)
+}
+
+/// Lay out a GIF's frames as a grid of numbered thumbnails with their delays. GIFs with more than
+/// 64 frames are sampled evenly. Labels use the 1-based numbering `extract_gif_frame` expects.
+pub fn render_frame_contact_sheet(gif_bytes: &[u8], flavor: catppuccin::FlavorName) -> Result<RgbaImage, String> {
+    use crate::text_rendering;
+    let frames = decode_gif_frames(gif_bytes)?;
+    let max_frames = 64;
+    let step = frames.len().div_ceil(max_frames);
+    let selected: Vec<usize> = (0..frames.len()).step_by(step).collect();
+    let crust = crate::utils::catppuccin_color_name_to_rgb("crust", flavor).unwrap();
+    let surface = crate::utils::catppuccin_color_name_to_rgb("surface0", flavor).unwrap();
+    let text = crate::utils::catppuccin_color_name_to_rgb("text", flavor).unwrap();
+    let (frame_w, frame_h) = frames.dimensions(0);
+    let thumb_scale = (160.0 / frame_w.max(frame_h) as f32).min(1.0);
+    let thumb_w = ((frame_w as f32 * thumb_scale).round() as u32).max(1);
+    let thumb_h = ((frame_h as f32 * thumb_scale).round() as u32).max(1);
+    let gap = 8;
+    let label_h = 22;
+    let cols = (selected.len() as f32).sqrt().ceil() as u32;
+    let rows = (selected.len() as u32).div_ceil(cols);
+    let cell_w = thumb_w.max(90) + gap;
+    let cell_h = thumb_h + label_h + gap;
+    let mut sheet = RgbaImage::from_pixel(gap + cols * cell_w, gap + rows * cell_h, Rgba([crust.0, crust.1, crust.2, 255]));
+    for (slot, index) in selected.into_iter().enumerate() {
+        let frame = frames.get(index)?;
+        let x = (gap + (slot as u32 % cols) * cell_w) as i32;
+        let y = (gap + (slot as u32 / cols) * cell_h) as i32;
+        // Transparent frame areas show the surface color instead of the sheet background
+        text_rendering::fill_rect(&mut sheet, x, y, thumb_w, thumb_h, Rgba([surface.0, surface.1, surface.2, 255]));
+        let thumb = image::imageops::resize(&frame, thumb_w, thumb_h, image::imageops::FilterType::Triangle);
+        image::imageops::overlay(&mut sheet, &thumb, x as i64, y as i64);
+        let label = format!("#{}  {}ms", index + 1, frames.delay(index) as u32 * 10);
+        text_rendering::draw_text(&mut sheet, &label, x, y + thumb_h as i32 + 3, 14.0, text);
+    }
+    Ok(sheet)
+}
+
+/// Generate a simple animation effect (e.g., fade in/out) as a GIF from a static image
+pub fn animate_image_effect(img: &image::RgbaImage, effect: &str) -> Result<Vec<u8>, String> {
+    let width = img.width() as u16;
+    let height = img.height() as u16;
+    let mut frames = Vec::new();
+    let n_frames = 12;
+    match effect {
         "fade" | "fadein" | "fade-in" => {
             for i in 0..n_frames {
                 let alpha = ((i as f32) / (n_frames as f32 - 1.0) * 255.0).round() as u8;
@@ -297,7 +1423,7 @@ pub fn animate_image_effect(img: &image::RgbaImage, effect: &str) -> Result<Vec<
     }
     // Encode as GIF
     let mut output = Vec::new();
-    if let Some(first_frame) = frames.first() {
+    if !frames.is_empty() {
         let mut encoder = gif::Encoder::new(&mut output, width, height, &[])
             .map_err(|e| format!("Failed to create GIF encoder: {e}"))?;
         encoder.set_repeat(gif::Repeat::Infinite).map_err(|e| format!("Failed to set GIF repeat: {e}"))?;
@@ -331,10 +1457,10 @@ pub fn overlay_catppuccin_texture(
                 for x in (0..width).step_by(spacing) {
                     for dy in 0..(radius * 2) {
                         for dx in 0..(radius * 2) {
-                            let px = x as i32 + dx - radius as i32;
-                            let py = y as i32 + dy - radius as i32;
+                            let px = x as i32 + dx - radius;
+                            let py = y as i32 + dy - radius;
                             if px >= 0 && py >= 0 && px < width as i32 && py < height as i32 {
-                                let dist = ((dx as i32 - radius as i32).pow(2) + (dy as i32 - radius as i32).pow(2)) as f32;
+                                let dist = ((dx - radius).pow(2) + (dy - radius).pow(2)) as f32;
                                 if dist <= (radius as f32).powi(2) {
                                     let base = out.get_pixel_mut(px as u32, py as u32);
                                     let alpha = dot_color[3] as f32 / 255.0;
@@ -363,7 +1489,55 @@ pub fn overlay_catppuccin_texture(
                 }
             }
         }
-        // Add more patterns here (e.g., grid, noise)
+        "noise" => {
+            // Soft Perlin clouds: push toward mauve on peaks and toward crust in valleys
+            let peak = crate::utils::catppuccin_color_name_to_rgb("mauve", flavor).unwrap();
+            let valley = crate::utils::catppuccin_color_name_to_rgb("crust", flavor).unwrap();
+            for (x, y, pixel) in out.enumerate_pixels_mut() {
+                let n = fractal_noise(x as f32 / 96.0, y as f32 / 96.0, 4, 7);
+                let color = if n > 0.0 { peak } else { valley };
+                blend_pixel(pixel, color, (n.abs() * 0.5).min(0.35));
+            }
+        }
+        "grain" => {
+            // Fine film grain, alternating light and dark specks
+            let light = crate::utils::catppuccin_color_name_to_rgb("text", flavor).unwrap();
+            let dark = crate::utils::catppuccin_color_name_to_rgb("crust", flavor).unwrap();
+            for (x, y, pixel) in out.enumerate_pixels_mut() {
+                let n = hash2(x as i32, y as i32, 13) as f32 / u32::MAX as f32 * 2.0 - 1.0;
+                let color = if n > 0.0 { light } else { dark };
+                blend_pixel(pixel, color, n.abs() * 0.12);
+            }
+        }
+        "paper" => {
+            // Low-frequency fibers plus a touch of grain, tinted warm
+            let fiber = crate::utils::catppuccin_color_name_to_rgb("rosewater", flavor).unwrap();
+            let shade = crate::utils::catppuccin_color_name_to_rgb("overlay0", flavor).unwrap();
+            for (x, y, pixel) in out.enumerate_pixels_mut() {
+                let fibers = fractal_noise(x as f32 / 6.0, y as f32 / 48.0, 3, 21);
+                let speck = hash2(x as i32, y as i32, 29) as f32 / u32::MAX as f32;
+                let color = if fibers > 0.0 { fiber } else { shade };
+                blend_pixel(pixel, color, (fibers.abs() * 0.2 + speck * 0.05).min(0.25));
+            }
+        }
+        "grid" => {
+            let line = crate::utils::catppuccin_color_name_to_rgb("surface2", flavor).unwrap();
+            let spacing = 32;
+            for (x, y, pixel) in out.enumerate_pixels_mut() {
+                if x % spacing == 0 || y % spacing == 0 {
+                    blend_pixel(pixel, line, 0.3);
+                }
+            }
+        }
+        "diamonds" => {
+            let line = crate::utils::catppuccin_color_name_to_rgb("lavender", flavor).unwrap();
+            let spacing = 40;
+            for (x, y, pixel) in out.enumerate_pixels_mut() {
+                if (x + y) % spacing == 0 || (x + spacing * 1024 - y) % spacing == 0 {
+                    blend_pixel(pixel, line, 0.2);
+                }
+            }
+        }
         _ => {
             // No overlay for unknown type
         }
@@ -371,6 +1545,769 @@ pub fn overlay_catppuccin_texture(
     out
 }
 
+/// Overlay impact-style top and bottom captions in the flavor's text color with a crust outline
+pub fn caption_image(img: &RgbaImage, top: &str, bottom: &str, flavor: catppuccin::FlavorName) -> RgbaImage {
+    use crate::text_rendering;
+    let mut out = img.clone();
+    let (width, height) = out.dimensions();
+    let fill = crate::utils::catppuccin_color_name_to_rgb("text", flavor).unwrap();
+    let outline = crate::utils::catppuccin_color_name_to_rgb("crust", flavor).unwrap();
+    let size = (width as f32 / 10.0).clamp(16.0, 120.0);
+    let thickness = ((size / 16.0).round() as i32).max(1);
+    let margin = (height / 30) as i32;
+    let max_width = (width as f32 * 0.92) as u32;
+    let step = text_rendering::line_height(size) as i32;
+    let draw_lines = |out: &mut RgbaImage, lines: &[String], y0: i32| {
+        for (i, line) in lines.iter().enumerate() {
+            let (line_w, _) = text_rendering::measure_text(line, size);
+            let x = (width as i32 - line_w as i32) / 2;
+            text_rendering::draw_text_outlined(out, line, x, y0 + i as i32 * step, size, fill, outline, thickness);
+        }
+    };
+    if !top.trim().is_empty() {
+        let lines = text_rendering::wrap_text(&top.to_uppercase(), size, max_width);
+        draw_lines(&mut out, &lines, margin);
+    }
+    if !bottom.trim().is_empty() {
+        let lines = text_rendering::wrap_text(&bottom.to_uppercase(), size, max_width);
+        let y0 = height as i32 - margin - lines.len() as i32 * step;
+        draw_lines(&mut out, &lines, y0);
+    }
+    out
+}
+
+/// A compositing step applied after the LUT, in the order given on the command line
+#[derive(Debug, Clone, PartialEq)]
+pub enum PostEffect {
+    /// Round the corners with the given radius in pixels
+    Rounded(u32),
+    /// Surround the image with a palette-colored border: (color name, thickness in pixels)
+    Border(String, u32),
+    /// Add a soft crust-colored drop shadow on a transparent canvas
+    Shadow,
+    /// A server's watermark in the bottom-right corner: (mark, palette color name, opacity percent)
+    Watermark { mark: crate::watermark::Mark, color: String, opacity: u8 },
+    /// Flatten transparency onto a solid color: a palette color name or a color literal like `#1e1e2e`
+    Background(String),
+}
+
+/// The color a `Background` effect among `effects` fills with in `flavor`, if there is one
+pub fn background_fill(effects: &[PostEffect], flavor: catppuccin::FlavorName) -> Option<(u8, u8, u8)> {
+    effects.iter().rev().find_map(|effect| match effect {
+        PostEffect::Background(color) => crate::utils::parse_color(color, flavor),
+        _ => None,
+    })
+}
+
+/// Formats without an alpha channel would show whatever color transparent pixels happen to hold, so
+/// unless a background was asked for they're flattened onto the flavor's base
+pub fn ensure_opaque_background(effects: &mut Vec<PostEffect>, format: image::ImageFormat) {
+    let has_alpha = matches!(format, image::ImageFormat::Png | image::ImageFormat::Gif | image::ImageFormat::WebP | image::ImageFormat::Tiff);
+    if !has_alpha && !effects.iter().any(|effect| matches!(effect, PostEffect::Background(_))) {
+        effects.push(PostEffect::Background("base".to_string()));
+    }
+}
+
+/// Apply post-effects in order. Borders follow any rounding applied before them.
+pub fn apply_post_effects(img: RgbaImage, effects: &[PostEffect], flavor: catppuccin::FlavorName) -> RgbaImage {
+    let mut out = img;
+    let mut corner_radius = 0;
+    for effect in effects {
+        match effect {
+            PostEffect::Rounded(radius) => {
+                corner_radius = (*radius).min(out.width() / 2).min(out.height() / 2);
+                round_corners(&mut out, corner_radius);
+            }
+            PostEffect::Border(color_name, thickness) => {
+                let color = crate::utils::catppuccin_color_name_to_rgb(color_name, flavor)
+                    .unwrap_or_else(|| crate::utils::catppuccin_color_name_to_rgb("mauve", flavor).unwrap());
+                let outer_radius = if corner_radius > 0 { corner_radius + thickness } else { 0 };
+                out = add_border(&out, color, *thickness, outer_radius);
+                corner_radius = outer_radius;
+            }
+            PostEffect::Shadow => {
+                let crust = crate::utils::catppuccin_color_name_to_rgb("crust", flavor).unwrap();
+                out = add_drop_shadow(&out, crust);
+            }
+            PostEffect::Watermark { mark, color, opacity } => {
+                let color = crate::utils::catppuccin_color_name_to_rgb(color, flavor).unwrap_or_else(|| crate::utils::catppuccin_color_name_to_rgb("text", flavor).unwrap());
+                crate::watermark::stamp(&mut out, mark, color, *opacity);
+            }
+            PostEffect::Background(color) => {
+                if let Some(color) = crate::utils::parse_color(color, flavor) {
+                    flatten_onto(&mut out, color);
+                }
+            }
+        }
+    }
+    out
+}
+
+// Coverage of pixel (x, y) by a rounded rectangle of size (w, h), antialiased over one pixel
+fn rounded_rect_coverage(x: u32, y: u32, w: u32, h: u32, radius: u32) -> f32 {
+    if radius == 0 {
+        return 1.0;
+    }
+    let r = radius as f32;
+    let px = x as f32 + 0.5;
+    let py = y as f32 + 0.5;
+    let cx = px.clamp(r, w as f32 - r);
+    let cy = py.clamp(r, h as f32 - r);
+    let dist = ((px - cx).powi(2) + (py - cy).powi(2)).sqrt();
+    (r - dist + 0.5).clamp(0.0, 1.0)
+}
+
+fn round_corners(img: &mut RgbaImage, radius: u32) {
+    let (w, h) = img.dimensions();
+    for (x, y, pixel) in img.enumerate_pixels_mut() {
+        let coverage = rounded_rect_coverage(x, y, w, h, radius);
+        if coverage < 1.0 {
+            pixel[3] = (pixel[3] as f32 * coverage).round() as u8;
+        }
+    }
+}
+
+fn add_border(img: &RgbaImage, color: (u8, u8, u8), thickness: u32, outer_radius: u32) -> RgbaImage {
+    let (w, h) = (img.width() + thickness * 2, img.height() + thickness * 2);
+    let mut canvas = RgbaImage::from_fn(w, h, |x, y| {
+        let coverage = rounded_rect_coverage(x, y, w, h, outer_radius);
+        Rgba([color.0, color.1, color.2, (coverage * 255.0).round() as u8])
+    });
+    image::imageops::overlay(&mut canvas, img, thickness as i64, thickness as i64);
+    canvas
+}
+
+fn add_drop_shadow(img: &RgbaImage, shadow_color: (u8, u8, u8)) -> RgbaImage {
+    let offset = (img.width().min(img.height()) / 64).max(4);
+    let sigma = offset as f32 * 2.0;
+    let margin = (sigma * 3.0).ceil() as u32;
+    let (w, h) = (img.width() + margin * 2, img.height() + margin * 2);
+    // Transparent pixels keep the shadow's RGB so blurring doesn't darken or lighten its edge
+    let mut shadow = RgbaImage::from_pixel(w, h, Rgba([shadow_color.0, shadow_color.1, shadow_color.2, 0]));
+    for (x, y, pixel) in img.enumerate_pixels() {
+        let alpha = (pixel[3] as f32 * 0.6).round() as u8;
+        shadow.put_pixel(x + margin + offset, y + margin + offset, Rgba([shadow_color.0, shadow_color.1, shadow_color.2, alpha]));
+    }
+    let mut canvas = image::imageops::fast_blur(&shadow, sigma);
+    image::imageops::overlay(&mut canvas, img, margin as i64, margin as i64);
+    canvas
+}
+
+/// A named image effect, applied after palette mapping by `!cat effect <name>`
+pub struct Effect {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub apply: fn(&RgbaImage, catppuccin::FlavorName) -> RgbaImage,
+}
+
+/// Every effect available to `!cat effect`, in the order they are listed in help
+pub static EFFECTS: &[Effect] = &[
+    Effect { name: "vignette", description: "Darken the edges toward crust", apply: effect_vignette },
+    Effect { name: "glow", description: "Soft lavender-tinted halo", apply: effect_glow },
+    Effect { name: "bloom", description: "Highlights bleed with a warm peach tint", apply: effect_bloom },
+    Effect { name: "scanlines", description: "Horizontal crust scanlines", apply: effect_scanlines },
+    Effect { name: "crt", description: "Chromatic aberration, scanlines, and a curved-tube vignette", apply: effect_crt },
+    Effect { name: "glitch", description: "RGB split and displaced bands with accent-colored streaks", apply: effect_glitch },
+];
+
+/// Look up an effect by name (case-insensitive)
+pub fn find_effect(name: &str) -> Option<&'static Effect> {
+    EFFECTS.iter().find(|e| e.name.eq_ignore_ascii_case(name))
+}
+
+fn luma(pixel: &Rgba<u8>) -> f32 {
+    (0.2126 * pixel[0] as f32 + 0.7152 * pixel[1] as f32 + 0.0722 * pixel[2] as f32) / 255.0
+}
+
+// Mix each pixel's color toward a tint, keeping alpha
+fn tint_image(img: &RgbaImage, tint: (u8, u8, u8), amount: f32) -> RgbaImage {
+    let mut out = img.clone();
+    for pixel in out.pixels_mut() {
+        blend_pixel(pixel, tint, amount);
+    }
+    out
+}
+
+fn effect_vignette(img: &RgbaImage, flavor: catppuccin::FlavorName) -> RgbaImage {
+    let crust = crate::utils::catppuccin_color_name_to_rgb("crust", flavor).unwrap();
+    let (w, h) = img.dimensions();
+    let (cx, cy) = (w as f32 / 2.0, h as f32 / 2.0);
+    let mut out = img.clone();
+    for (x, y, pixel) in out.enumerate_pixels_mut() {
+        // Normalized so the corners are at distance 1.0
+        let dx = (x as f32 + 0.5 - cx) / cx;
+        let dy = (y as f32 + 0.5 - cy) / cy;
+        let dist = ((dx * dx + dy * dy) / 2.0).sqrt();
+        let t = ((dist - 0.45) / 0.55).clamp(0.0, 1.0);
+        blend_pixel(pixel, crust, t * t * (3.0 - 2.0 * t) * 0.75);
+    }
+    out
+}
+
+fn effect_glow(img: &RgbaImage, flavor: catppuccin::FlavorName) -> RgbaImage {
+    let lavender = crate::utils::catppuccin_color_name_to_rgb("lavender", flavor).unwrap();
+    let sigma = (img.width().min(img.height()) as f32 / 80.0).max(2.0);
+    let halo = image::imageops::fast_blur(&tint_image(img, lavender, 0.3), sigma);
+    let mut out = img.clone();
+    for (pixel, halo_pixel) in out.pixels_mut().zip(halo.pixels()) {
+        // Screen blend at 60% strength
+        for ch in 0..3 {
+            let base = pixel[ch] as f32 / 255.0;
+            let screened = 1.0 - (1.0 - base) * (1.0 - halo_pixel[ch] as f32 / 255.0);
+            pixel[ch] = ((base + (screened - base) * 0.6) * 255.0).round() as u8;
+        }
+    }
+    out
+}
+
+fn effect_bloom(img: &RgbaImage, flavor: catppuccin::FlavorName) -> RgbaImage {
+    let peach = crate::utils::catppuccin_color_name_to_rgb("peach", flavor).unwrap();
+    let sigma = (img.width().min(img.height()) as f32 / 40.0).max(3.0);
+    // Bright pass: keep only the highlights, faded in above the threshold
+    let mut highlights = tint_image(img, peach, 0.4);
+    for (pixel, source) in highlights.pixels_mut().zip(img.pixels()) {
+        let strength = ((luma(source) - 0.65) / 0.35).clamp(0.0, 1.0);
+        for ch in 0..3 {
+            pixel[ch] = (pixel[ch] as f32 * strength).round() as u8;
+        }
+    }
+    let bloom = image::imageops::fast_blur(&highlights, sigma);
+    let mut out = img.clone();
+    for (pixel, bloom_pixel) in out.pixels_mut().zip(bloom.pixels()) {
+        for ch in 0..3 {
+            pixel[ch] = (pixel[ch] as f32 + bloom_pixel[ch] as f32 * 0.8).min(255.0) as u8;
+        }
+    }
+    out
+}
+
+// Shift the red channel right and the blue channel left by `shift` pixels
+fn chromatic_aberration(img: &RgbaImage, shift: i32) -> RgbaImage {
+    let (w, h) = img.dimensions();
+    let mut out = img.clone();
+    for y in 0..h {
+        for x in 0..w {
+            let red_x = (x as i32 - shift).clamp(0, w as i32 - 1) as u32;
+            let blue_x = (x as i32 + shift).clamp(0, w as i32 - 1) as u32;
+            let pixel = out.get_pixel_mut(x, y);
+            pixel[0] = img.get_pixel(red_x, y)[0];
+            pixel[2] = img.get_pixel(blue_x, y)[2];
+        }
+    }
+    out
+}
+
+fn effect_scanlines(img: &RgbaImage, flavor: catppuccin::FlavorName) -> RgbaImage {
+    let crust = crate::utils::catppuccin_color_name_to_rgb("crust", flavor).unwrap();
+    // Roughly 270 lines regardless of resolution, like a low-res display
+    let period = (img.height() / 270).max(2);
+    let mut out = img.clone();
+    for (_, y, pixel) in out.enumerate_pixels_mut() {
+        if y % period >= period / 2 {
+            blend_pixel(pixel, crust, 0.35);
+        }
+    }
+    out
+}
+
+fn effect_crt(img: &RgbaImage, flavor: catppuccin::FlavorName) -> RgbaImage {
+    let shift = (img.width() / 400).max(1) as i32;
+    let split = chromatic_aberration(img, shift);
+    effect_vignette(&effect_scanlines(&split, flavor), flavor)
+}
+
+fn effect_glitch(img: &RgbaImage, flavor: catppuccin::FlavorName) -> RgbaImage {
+    let accents = [
+        crate::utils::catppuccin_color_name_to_rgb("mauve", flavor).unwrap(),
+        crate::utils::catppuccin_color_name_to_rgb("teal", flavor).unwrap(),
+        crate::utils::catppuccin_color_name_to_rgb("pink", flavor).unwrap(),
+    ];
+    let (w, h) = img.dimensions();
+    let split = chromatic_aberration(img, (w / 120).max(2) as i32);
+    let mut out = split.clone();
+    // Seed from the image size so the same image always glitches the same way
+    let seed = w.wrapping_mul(31).wrapping_add(h);
+    let band_height = (h / 40).max(2);
+    for band in 0..h.div_ceil(band_height) {
+        let roll = hash2(band as i32, 0, seed);
+        if !roll.is_multiple_of(4) {
+            continue;
+        }
+        let max_offset = (w / 12).max(1) as i32;
+        let offset = (hash2(band as i32, 1, seed) % (max_offset as u32 * 2 + 1)) as i32 - max_offset;
+        let streak = (roll / 4).is_multiple_of(3);
+        let accent = accents[(hash2(band as i32, 2, seed) % accents.len() as u32) as usize];
+        for y in band * band_height..((band + 1) * band_height).min(h) {
+            for x in 0..w {
+                let src_x = (x as i32 - offset).rem_euclid(w as i32) as u32;
+                let mut pixel = *split.get_pixel(src_x, y);
+                if streak {
+                    blend_pixel(&mut pixel, accent, 0.4);
+                }
+                out.put_pixel(x, y, pixel);
+            }
+        }
+    }
+    out
+}
+
+// Every named color of a flavor, in palette order
+fn flavor_colors(flavor: catppuccin::FlavorName) -> Vec<(&'static str, (u8, u8, u8))> {
+    crate::utils::CATPPUCCIN_COLOR_NAMES
+        .iter()
+        .map(|name| (*name, crate::utils::catppuccin_color_name_to_rgb(name, flavor).unwrap()))
+        .collect()
+}
+
+// Index of the perceptually closest palette color
+fn nearest_palette_index(rgb: (u8, u8, u8), colors: &[(&'static str, (u8, u8, u8))]) -> usize {
+    colors
+        .iter()
+        .enumerate()
+        .min_by(|(_, a), (_, b)| delta_e(rgb, a.1).partial_cmp(&delta_e(rgb, b.1)).unwrap())
+        .map(|(i, _)| i)
+        .unwrap()
+}
+
+/// The flavor's perceptually closest named color to `rgb`
+pub fn nearest_palette_color(rgb: (u8, u8, u8), flavor: catppuccin::FlavorName) -> (&'static str, (u8, u8, u8)) {
+    let colors = flavor_colors(flavor);
+    colors[nearest_palette_index(rgb, &colors)]
+}
+
+/// Render a halftone: dots on the flavor's base, sized by how far each cell's luminance is from it,
+/// inked in the closest palette color
+pub fn render_halftone(img: &RgbaImage, flavor: catppuccin::FlavorName) -> RgbaImage {
+    let colors = flavor_colors(flavor);
+    let base = crate::utils::catppuccin_color_name_to_rgb("base", flavor).unwrap();
+    let base_luma = luma(&Rgba([base.0, base.1, base.2, 255]));
+    let max_contrast = base_luma.max(1.0 - base_luma);
+    let (w, h) = img.dimensions();
+    let cell = (w.min(h) / 80).clamp(4, 32);
+    let mut out = RgbaImage::from_pixel(w, h, Rgba([base.0, base.1, base.2, 255]));
+    for cy in (0..h).step_by(cell as usize) {
+        for cx in (0..w).step_by(cell as usize) {
+            let (mut sum, mut count) = ([0u32; 3], 0u32);
+            for y in cy..(cy + cell).min(h) {
+                for x in cx..(cx + cell).min(w) {
+                    let p = img.get_pixel(x, y);
+                    for ch in 0..3 {
+                        sum[ch] += p[ch] as u32;
+                    }
+                    count += 1;
+                }
+            }
+            let avg = Rgba([(sum[0] / count) as u8, (sum[1] / count) as u8, (sum[2] / count) as u8, 255]);
+            let t = ((luma(&avg) - base_luma).abs() / max_contrast).clamp(0.0, 1.0);
+            // Dot area is proportional to t; at t = 1 neighboring dots just touch the cell corners
+            let radius = cell as f32 * std::f32::consts::FRAC_1_SQRT_2 * t.sqrt();
+            if radius < 0.5 {
+                continue;
+            }
+            let ink = colors[nearest_palette_index((avg[0], avg[1], avg[2]), &colors)].1;
+            let center = (cx as f32 + cell as f32 / 2.0, cy as f32 + cell as f32 / 2.0);
+            let x0 = (center.0 - radius).floor().max(0.0) as u32;
+            let y0 = (center.1 - radius).floor().max(0.0) as u32;
+            let x1 = ((center.0 + radius).ceil() as u32).min(w);
+            let y1 = ((center.1 + radius).ceil() as u32).min(h);
+            for y in y0..y1 {
+                for x in x0..x1 {
+                    let dist = ((x as f32 + 0.5 - center.0).powi(2) + (y as f32 + 0.5 - center.1).powi(2)).sqrt();
+                    let coverage = (radius - dist + 0.5).clamp(0.0, 1.0);
+                    if coverage > 0.0 {
+                        blend_pixel(out.get_pixel_mut(x, y), ink, coverage);
+                    }
+                }
+            }
+        }
+    }
+    out
+}
+
+// One chart symbol per palette color, in palette order
+const STITCH_SYMBOLS: [char; 26] = [
+    'X', 'O', '+', '#', '*', '@', '%', '&', '=', '~', 'S', 'Z', 'A', 'V', 'H', 'K', 'M', 'W', 'T', 'U', 'N', 'E', 'C', 'D', 'F', 'G',
+];
+
+/// Render a printable cross-stitch chart: the image reduced to at most `max_stitches` per side,
+/// each stitch mapped to a palette color and symbol, with a legend underneath.
+/// Returns the chart and the legend as (color name, symbol, stitch count), most used first.
+pub fn render_cross_stitch(img: &RgbaImage, flavor: catppuccin::FlavorName, max_stitches: u32) -> (RgbaImage, Vec<(&'static str, char, u32)>) {
+    use crate::text_rendering;
+    let colors = flavor_colors(flavor);
+    let base = crate::utils::catppuccin_color_name_to_rgb("base", flavor).unwrap();
+    let text = crate::utils::catppuccin_color_name_to_rgb("text", flavor).unwrap();
+    let grid_line = crate::utils::catppuccin_color_name_to_rgb("overlay0", flavor).unwrap();
+
+    let scale = (max_stitches as f32 / img.width().max(img.height()) as f32).min(1.0);
+    let grid_w = ((img.width() as f32 * scale).round() as u32).max(1);
+    let grid_h = ((img.height() as f32 * scale).round() as u32).max(1);
+    let small = image::imageops::resize(img, grid_w, grid_h, image::imageops::FilterType::Triangle);
+    let mut counts = [0u32; 26];
+    let cells: Vec<usize> = small
+        .pixels()
+        .map(|p| {
+            let index = nearest_palette_index((p[0], p[1], p[2]), &colors);
+            counts[index] += 1;
+            index
+        })
+        .collect();
+    let mut legend: Vec<(&'static str, char, u32)> = (0..colors.len())
+        .filter(|&i| counts[i] > 0)
+        .map(|i| (colors[i].0, STITCH_SYMBOLS[i], counts[i]))
+        .collect();
+    legend.sort_by_key(|entry| std::cmp::Reverse(entry.2));
+
+    let cell: u32 = 18;
+    let margin: u32 = 24;
+    let legend_row_h: u32 = 26;
+    let legend_col_w: u32 = 240;
+    let chart_w = grid_w * cell + 1;
+    let chart_h = grid_h * cell + 1;
+    let width = (chart_w + margin * 2).max(legend_col_w * 2 + margin * 2);
+    let legend_cols = ((width - margin * 2) / legend_col_w).max(1);
+    let legend_rows = (legend.len() as u32).div_ceil(legend_cols);
+    let height = margin + chart_h + margin + legend_rows * legend_row_h + margin;
+    let mut out = RgbaImage::from_pixel(width, height, Rgba([base.0, base.1, base.2, 255]));
+
+    let chart_x = ((width - chart_w) / 2) as i32;
+    let chart_y = margin as i32;
+    let symbol_size = 13.0;
+    // Symbols in whichever of text/base contrasts more with the stitch color
+    let symbol_color = |rgb: (u8, u8, u8)| {
+        let l = luma(&Rgba([rgb.0, rgb.1, rgb.2, 255]));
+        let l_text = luma(&Rgba([text.0, text.1, text.2, 255]));
+        let l_base = luma(&Rgba([base.0, base.1, base.2, 255]));
+        if (l - l_text).abs() > (l - l_base).abs() { text } else { base }
+    };
+    for (i, &index) in cells.iter().enumerate() {
+        let gx = (i as u32 % grid_w) as i32;
+        let gy = (i as u32 / grid_w) as i32;
+        let rgb = colors[index].1;
+        let x = chart_x + gx * cell as i32;
+        let y = chart_y + gy * cell as i32;
+        text_rendering::fill_rect(&mut out, x, y, cell, cell, Rgba([rgb.0, rgb.1, rgb.2, 255]));
+        let symbol = STITCH_SYMBOLS[index].to_string();
+        let (sw, sh) = text_rendering::measure_text(&symbol, symbol_size);
+        text_rendering::draw_text(&mut out, &symbol, x + (cell as i32 - sw as i32) / 2, y + (cell as i32 - sh as i32) / 2, symbol_size, symbol_color(rgb));
+    }
+    // Thin lines every stitch, thick lines every ten like a printed chart
+    for gx in 0..=grid_w {
+        let (color, thickness) = if gx % 10 == 0 || gx == grid_w { (text, 2) } else { (grid_line, 1) };
+        let x = chart_x + (gx * cell) as i32 - thickness / 2;
+        text_rendering::fill_rect(&mut out, x, chart_y, thickness as u32, chart_h, Rgba([color.0, color.1, color.2, 255]));
+    }
+    for gy in 0..=grid_h {
+        let (color, thickness) = if gy % 10 == 0 || gy == grid_h { (text, 2) } else { (grid_line, 1) };
+        let y = chart_y + (gy * cell) as i32 - thickness / 2;
+        text_rendering::fill_rect(&mut out, chart_x, y, chart_w, thickness as u32, Rgba([color.0, color.1, color.2, 255]));
+    }
+
+    let legend_y = (margin + chart_h + margin) as i32;
+    for (i, (name, symbol, count)) in legend.iter().enumerate() {
+        let col = i as u32 % legend_cols;
+        let row = i as u32 / legend_cols;
+        let x = (margin + col * legend_col_w) as i32;
+        let y = legend_y + (row * legend_row_h) as i32;
+        let rgb = crate::utils::catppuccin_color_name_to_rgb(name, flavor).unwrap();
+        text_rendering::fill_rect(&mut out, x, y, cell, cell, Rgba([rgb.0, rgb.1, rgb.2, 255]));
+        let symbol = symbol.to_string();
+        let (sw, sh) = text_rendering::measure_text(&symbol, symbol_size);
+        text_rendering::draw_text(&mut out, &symbol, x + (cell as i32 - sw as i32) / 2, y + (cell as i32 - sh as i32) / 2, symbol_size, symbol_color(rgb));
+        text_rendering::draw_text(&mut out, &format!("{}  {}", name, count), x + cell as i32 + 10, y, 15.0, text);
+    }
+    (out, legend)
+}
+
+// 8x8 Bayer matrix, the threshold pattern for ordered dithering
+const BAYER_8: [[u8; 8]; 8] = [
+    [0, 32, 8, 40, 2, 34, 10, 42],
+    [48, 16, 56, 24, 50, 18, 58, 26],
+    [12, 44, 4, 36, 14, 46, 6, 38],
+    [60, 28, 52, 20, 62, 30, 54, 22],
+    [3, 35, 11, 43, 1, 33, 9, 41],
+    [51, 19, 59, 27, 49, 17, 57, 25],
+    [15, 47, 7, 39, 13, 45, 5, 37],
+    [63, 31, 55, 23, 61, 29, 53, 21],
+];
+
+// How far ordered dithering pushes a pixel before snapping, in levels of 255; about the gap between
+// neighboring palette colors, so in-between shades become a mix of the two
+const STRICT_DITHER_SPREAD: f32 = 32.0;
+
+/// Snap every pixel to exactly one of the flavor's 26 colors, with ordered (Bayer) dithering so
+/// in-between shades become a fine pattern of palette colors instead of bands. Alpha is left alone.
+pub fn quantize_to_palette(img: &mut RgbaImage, flavor: catppuccin::FlavorName) {
+    let mapper = PaletteMapper::new(flavor, "nearest-neighbor");
+    let width = img.width().max(1) as usize;
+    img.par_chunks_mut(4).enumerate().for_each(|(i, pixel)| {
+        let threshold = (BAYER_8[(i / width) % 8][(i % width) % 8] as f32 + 0.5) / 64.0 - 0.5;
+        let offset = threshold * STRICT_DITHER_SPREAD;
+        let (r, g, b) = mapper.map(nudge(pixel[0], offset), nudge(pixel[1], offset), nudge(pixel[2], offset));
+        pixel[0] = r;
+        pixel[1] = g;
+        pixel[2] = b;
+    });
+}
+
+// Each pixel quantized to `levels` steps per channel, then snapped to the closest palette color.
+// Quantizing first keeps the palette lookups to at most `levels`³ colors.
+fn posterize_to_palette(img: &RgbaImage, levels: u32, colors: &[(&'static str, (u8, u8, u8))]) -> RgbaImage {
+    let step = 255.0 / (levels - 1) as f32;
+    let quantize = |v: u8| ((v as f32 / step).round() * step).round() as u8;
+    let mut nearest: HashMap<(u8, u8, u8), (u8, u8, u8)> = HashMap::new();
+    let mut out = img.clone();
+    for pixel in out.pixels_mut() {
+        let key = (quantize(pixel[0]), quantize(pixel[1]), quantize(pixel[2]));
+        let ink = *nearest.entry(key).or_insert_with(|| colors[nearest_palette_index(key, colors)].1);
+        *pixel = Rgba([ink.0, ink.1, ink.2, pixel[3]]);
+    }
+    out
+}
+
+/// Posterize to `levels` steps per channel (2 to 8), using only the flavor's colors
+pub fn render_posterize(img: &RgbaImage, flavor: catppuccin::FlavorName, levels: u32) -> RgbaImage {
+    posterize_to_palette(img, levels.clamp(2, 8), &flavor_colors(flavor))
+}
+
+// Sobel gradient magnitude of the luminance, row-major and scaled to 0..1; edge pixels repeat outward
+fn sobel_magnitude(img: &RgbaImage) -> Vec<f32> {
+    let (w, h) = img.dimensions();
+    let lum: Vec<f32> = img.pixels().map(luma).collect();
+    let at = |x: i64, y: i64| lum[(y.clamp(0, h as i64 - 1) * w as i64 + x.clamp(0, w as i64 - 1)) as usize];
+    (0..h as i64)
+        .into_par_iter()
+        .flat_map_iter(|y| {
+            let at = &at;
+            (0..w as i64).map(move |x| {
+                let gx = at(x + 1, y - 1) + 2.0 * at(x + 1, y) + at(x + 1, y + 1) - at(x - 1, y - 1) - 2.0 * at(x - 1, y) - at(x - 1, y + 1);
+                let gy = at(x - 1, y + 1) + 2.0 * at(x, y + 1) + at(x + 1, y + 1) - at(x - 1, y - 1) - 2.0 * at(x, y - 1) - at(x + 1, y - 1);
+                // The largest possible response is 4 on each axis
+                ((gx * gx + gy * gy).sqrt() / (4.0 * std::f32::consts::SQRT_2)).min(1.0)
+            })
+        })
+        .collect()
+}
+
+/// Cel shading: a smoothed image filled with a few flat palette colors, outlined in crust where
+/// the luminance changes sharply
+pub fn render_cel_shade(img: &RgbaImage, flavor: catppuccin::FlavorName) -> RgbaImage {
+    let crust = crate::utils::catppuccin_color_name_to_rgb("crust", flavor).unwrap();
+    // Smoothing first merges texture and noise into flat regions
+    let smoothed = image::imageops::fast_blur(img, (img.width().min(img.height()) as f32 / 300.0).max(1.0));
+    let mut out = posterize_to_palette(&smoothed, 4, &flavor_colors(flavor));
+    let edges = sobel_magnitude(&smoothed);
+    for (pixel, edge) in out.pixels_mut().zip(edges) {
+        // Soft threshold, so outlines are antialiased instead of stair-stepped
+        let ink = ((edge - 0.12) / 0.1).clamp(0.0, 1.0);
+        if ink > 0.0 {
+            blend_pixel(pixel, crust, ink);
+        }
+    }
+    out
+}
+
+/// Line art: edges of the image drawn in `ink` on the flavor's base. With `wash`, a faint
+/// catppuccinified copy of the image shows through the background.
+pub fn render_sketch(img: &RgbaImage, flavor: catppuccin::FlavorName, ink: (u8, u8, u8), wash: bool) -> RgbaImage {
+    let base = crate::utils::catppuccin_color_name_to_rgb("base", flavor).unwrap();
+    // A light blur keeps fine texture and noise from turning into scribbles
+    let smoothed = image::imageops::fast_blur(img, (img.width().min(img.height()) as f32 / 500.0).max(0.8));
+    let mut out = RgbaImage::from_pixel(img.width(), img.height(), Rgba([base.0, base.1, base.2, 255]));
+    if wash {
+        let lut = generate_catppuccin_lut(flavor, "shepards-method");
+        let mut mapped = img.clone();
+        apply_lut_to_image(&mut mapped, &lut);
+        for (pixel, source) in out.pixels_mut().zip(mapped.pixels()) {
+            blend_pixel(pixel, (source[0], source[1], source[2]), 0.25 * source[3] as f32 / 255.0);
+        }
+    }
+    let edges = sobel_magnitude(&smoothed);
+    for ((pixel, source), edge) in out.pixels_mut().zip(img.pixels()).zip(edges) {
+        // Faint gradients stay blank; strong ones get solid, antialiased strokes
+        let stroke = ((edge - 0.04) / 0.2).clamp(0.0, 1.0) * source[3] as f32 / 255.0;
+        if stroke > 0.0 {
+            blend_pixel(pixel, ink, stroke);
+        }
+    }
+    out
+}
+
+// Rotate by `degrees` (clockwise) onto a transparent canvas large enough to hold the result.
+// Interpolates in premultiplied alpha so edges don't pick up dark fringes.
+fn rotate_image(img: &RgbaImage, degrees: f32) -> RgbaImage {
+    let (w, h) = (img.width() as f32, img.height() as f32);
+    let (sin, cos) = degrees.to_radians().sin_cos();
+    let out_w = (w * cos.abs() + h * sin.abs()).ceil() as u32;
+    let out_h = (w * sin.abs() + h * cos.abs()).ceil() as u32;
+    let (cx, cy) = (w / 2.0, h / 2.0);
+    let (ocx, ocy) = (out_w as f32 / 2.0, out_h as f32 / 2.0);
+    let fetch = |x: i32, y: i32| -> [f32; 4] {
+        if x < 0 || y < 0 || x >= img.width() as i32 || y >= img.height() as i32 {
+            return [0.0; 4];
+        }
+        let p = img.get_pixel(x as u32, y as u32);
+        let a = p[3] as f32 / 255.0;
+        [p[0] as f32 * a, p[1] as f32 * a, p[2] as f32 * a, a]
+    };
+    RgbaImage::from_fn(out_w, out_h, |x, y| {
+        let dx = x as f32 + 0.5 - ocx;
+        let dy = y as f32 + 0.5 - ocy;
+        let sx = dx * cos + dy * sin + cx - 0.5;
+        let sy = -dx * sin + dy * cos + cy - 0.5;
+        let (x0, y0) = (sx.floor() as i32, sy.floor() as i32);
+        let (fx, fy) = (sx - sx.floor(), sy - sy.floor());
+        let mut acc = [0.0f32; 4];
+        for (ox, oy, weight) in [(0, 0, (1.0 - fx) * (1.0 - fy)), (1, 0, fx * (1.0 - fy)), (0, 1, (1.0 - fx) * fy), (1, 1, fx * fy)] {
+            let sample = fetch(x0 + ox, y0 + oy);
+            for ch in 0..4 {
+                acc[ch] += sample[ch] * weight;
+            }
+        }
+        if acc[3] <= 0.0 {
+            return Rgba([0, 0, 0, 0]);
+        }
+        Rgba([
+            (acc[0] / acc[3]).round().min(255.0) as u8,
+            (acc[1] / acc[3]).round().min(255.0) as u8,
+            (acc[2] / acc[3]).round().min(255.0) as u8,
+            (acc[3] * 255.0).round() as u8,
+        ])
+    })
+}
+
+/// Place an image in a polaroid-style frame: base-colored border, a caption strip along the bottom,
+/// a slight tilt, and a crust drop shadow on a transparent canvas
+pub fn render_polaroid(img: &RgbaImage, caption: &str, flavor: catppuccin::FlavorName) -> RgbaImage {
+    use crate::text_rendering;
+    let base = crate::utils::catppuccin_color_name_to_rgb("base", flavor).unwrap();
+    let text = crate::utils::catppuccin_color_name_to_rgb("text", flavor).unwrap();
+    let crust = crate::utils::catppuccin_color_name_to_rgb("crust", flavor).unwrap();
+    let max_side = 720;
+    let photo = if img.width().max(img.height()) > max_side {
+        let scale = max_side as f32 / img.width().max(img.height()) as f32;
+        image::imageops::resize(img, ((img.width() as f32 * scale).round() as u32).max(1), ((img.height() as f32 * scale).round() as u32).max(1), image::imageops::FilterType::Lanczos3)
+    } else {
+        img.clone()
+    };
+    let border = (photo.width().max(photo.height()) / 16).max(8);
+    let strip = border * 4;
+    let (w, h) = (photo.width() + border * 2, photo.height() + border + strip);
+    let mut frame = RgbaImage::from_pixel(w, h, Rgba([base.0, base.1, base.2, 255]));
+    image::imageops::overlay(&mut frame, &photo, border as i64, border as i64);
+    if !caption.trim().is_empty() {
+        let size = (strip as f32 * 0.3).max(12.0);
+        let lines: Vec<String> = text_rendering::wrap_text(caption.trim(), size, w - border * 2).into_iter().take(2).collect();
+        let block_h = lines.len() as i32 * text_rendering::line_height(size) as i32;
+        let y = (photo.height() + border) as i32 + (strip as i32 - block_h) / 2;
+        text_rendering::draw_text_centered(&mut frame, &lines, y, w, size, text);
+    }
+    add_drop_shadow(&rotate_image(&frame, -3.0), crust)
+}
+
+/// Make a seamless `size`×`size` tile. "mirror" reflects a quarter-size copy into a 2×2 block;
+/// "blend" wraps the image by half a tile and fades the original back in toward the center to hide the seam.
+pub fn make_seamless_tile(img: &RgbaImage, mode: &str, size: u32) -> RgbaImage {
+    use image::imageops::{self, FilterType};
+    // Center-crop to a square first so the tile isn't stretched
+    let side = img.width().min(img.height());
+    let square = imageops::crop_imm(img, (img.width() - side) / 2, (img.height() - side) / 2, side, side).to_image();
+    match mode {
+        "mirror" => {
+            let half = (size / 2).max(1);
+            let quarter = imageops::resize(&square, half, half, FilterType::Lanczos3);
+            let mut out = RgbaImage::new(half * 2, half * 2);
+            imageops::overlay(&mut out, &quarter, 0, 0);
+            imageops::overlay(&mut out, &imageops::flip_horizontal(&quarter), half as i64, 0);
+            imageops::overlay(&mut out, &imageops::flip_vertical(&quarter), 0, half as i64);
+            imageops::overlay(&mut out, &imageops::rotate180(&quarter), half as i64, half as i64);
+            out
+        }
+        _ => {
+            let tile = imageops::resize(&square, size, size, FilterType::Lanczos3);
+            let smooth = |t: f32| t * t * (3.0 - 2.0 * t);
+            RgbaImage::from_fn(size, size, |x, y| {
+                let wrapped = tile.get_pixel((x + size / 2) % size, (y + size / 2) % size);
+                let original = tile.get_pixel(x, y);
+                let wx = 1.0 - ((2.0 * (x as f32 + 0.5) / size as f32) - 1.0).abs();
+                let wy = 1.0 - ((2.0 * (y as f32 + 0.5) / size as f32) - 1.0).abs();
+                let weight = smooth((wx.min(wy) * 2.0).clamp(0.0, 1.0));
+                let mut pixel = *wrapped;
+                for ch in 0..4 {
+                    pixel[ch] = (wrapped[ch] as f32 * (1.0 - weight) + original[ch] as f32 * weight).round() as u8;
+                }
+                pixel
+            })
+        }
+    }
+}
+
+/// Repeat a tile `count`×`count` times, for previewing how it tiles
+pub fn repeat_tile(tile: &RgbaImage, count: u32) -> RgbaImage {
+    let (w, h) = tile.dimensions();
+    RgbaImage::from_fn(w * count, h * count, |x, y| *tile.get_pixel(x % w, y % h))
+}
+
+/// Generate a wallpaper: the flavor's base color with a texture style on top
+pub fn generate_wallpaper(style: &str, flavor: catppuccin::FlavorName, width: u32, height: u32) -> image::RgbaImage {
+    let (r, g, b) = crate::utils::catppuccin_color_name_to_rgb("base", flavor).unwrap();
+    let canvas = image::RgbaImage::from_pixel(width, height, image::Rgba([r, g, b, 255]));
+    overlay_catppuccin_texture(&canvas, style, flavor)
+}
+
+// Alpha-blend an RGB color over a pixel, leaving its alpha untouched
+fn blend_pixel(pixel: &mut Rgba<u8>, color: (u8, u8, u8), alpha: f32) {
+    let alpha = alpha.clamp(0.0, 1.0);
+    let rgb = [color.0, color.1, color.2];
+    for c in 0..3 {
+        pixel[c] = ((1.0 - alpha) * pixel[c] as f32 + alpha * rgb[c] as f32).round() as u8;
+    }
+}
+
+// Integer hash for deterministic per-pixel randomness
+fn hash2(x: i32, y: i32, seed: u32) -> u32 {
+    let mut h = (x as u32).wrapping_mul(0x27d4eb2d) ^ (y as u32).wrapping_mul(0x165667b1) ^ seed.wrapping_mul(0x9e3779b9);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x85ebca6b);
+    h ^= h >> 13;
+    h = h.wrapping_mul(0xc2b2ae35);
+    h ^ (h >> 16)
+}
+
+// Classic 2D Perlin gradient noise in roughly -1..1
+fn perlin_noise(x: f32, y: f32, seed: u32) -> f32 {
+    let (x0, y0) = (x.floor() as i32, y.floor() as i32);
+    let (fx, fy) = (x - x0 as f32, y - y0 as f32);
+    let gradient = |ix: i32, iy: i32, dx: f32, dy: f32| {
+        let angle = hash2(ix, iy, seed) as f32 / u32::MAX as f32 * std::f32::consts::TAU;
+        angle.cos() * dx + angle.sin() * dy
+    };
+    let fade = |t: f32| t * t * t * (t * (t * 6.0 - 15.0) + 10.0);
+    let (u, v) = (fade(fx), fade(fy));
+    let n00 = gradient(x0, y0, fx, fy);
+    let n10 = gradient(x0 + 1, y0, fx - 1.0, fy);
+    let n01 = gradient(x0, y0 + 1, fx, fy - 1.0);
+    let n11 = gradient(x0 + 1, y0 + 1, fx - 1.0, fy - 1.0);
+    let nx0 = n00 + u * (n10 - n00);
+    let nx1 = n01 + u * (n11 - n01);
+    (nx0 + v * (nx1 - nx0)) * std::f32::consts::SQRT_2
+}
+
+// Sum several octaves of Perlin noise for a natural, cloudy look
+fn fractal_noise(x: f32, y: f32, octaves: u32, seed: u32) -> f32 {
+    let mut total = 0.0;
+    let mut amplitude = 1.0;
+    let mut frequency = 1.0;
+    let mut max_amplitude = 0.0;
+    for octave in 0..octaves {
+        total += perlin_noise(x * frequency, y * frequency, seed + octave) * amplitude;
+        max_amplitude += amplitude;
+        amplitude *= 0.5;
+        frequency *= 2.0;
+    }
+    total / max_amplitude
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -389,6 +2326,87 @@ mod tests {
         assert_ne!(lut1[..100], lut2[..100]); // The LUTs should differ for different flavors
     }
 
+    #[test]
+    fn test_tuned_palette_mapping() {
+        let nearest = PaletteMapper::new(FlavorName::Mocha, "nearest-neighbor");
+        let one_neighbor = Tuning { neighbors: Some(1), ..Tuning::default() };
+        let shepards = PaletteMapper::with_params(FlavorName::Mocha, AlgorithmParams::for_algorithm("shepards-method").tuned(&one_neighbor));
+        // A Gaussian this narrow leaves only the nearest color with any weight
+        let narrow = Tuning { sigma: Some(1.0), ..Tuning::default() };
+        let gaussian = PaletteMapper::with_params(FlavorName::Mocha, AlgorithmParams::for_algorithm("gaussian-rbf").tuned(&narrow));
+        // Blending a single color back out of floats can land one below it
+        let close = |a: (u8, u8, u8), b: (u8, u8, u8)| a.0.abs_diff(b.0) <= 1 && a.1.abs_diff(b.1) <= 1 && a.2.abs_diff(b.2) <= 1;
+        for (r, g, b) in [(255, 0, 0), (12, 200, 90), (128, 128, 128)] {
+            assert!(close(shepards.map(r, g, b), nearest.map(r, g, b)));
+            assert!(close(gaussian.map(r, g, b), nearest.map(r, g, b)));
+        }
+        assert_eq!(AlgorithmParams::for_algorithm("gaussian-rbf").kernel, Kernel::Gaussian);
+        assert_eq!(Tuning { power: Some(3.0), neighbors: Some(4), ..Tuning::default() }.describe(), "power 3, neighbors 4");
+    }
+
+    #[test]
+    fn test_nearest_k_keeps_saturation() {
+        let chroma = |(r, g, b): (u8, u8, u8)| r.max(g).max(b) - r.min(g).min(b);
+        let shepards = PaletteMapper::new(FlavorName::Mocha, "shepards-method");
+        let nearest_k = PaletteMapper::new(FlavorName::Mocha, "nearest-k");
+        for (r, g, b) in [(255, 0, 0), (0, 200, 0), (40, 60, 255)] {
+            assert!(chroma(nearest_k.map(r, g, b)) > chroma(shepards.map(r, g, b)));
+        }
+    }
+
+    #[test]
+    fn test_gaussian_sampling_dithers_flat_areas() {
+        let lut: Vec<u8> = (0..256 * 256 * 256).flat_map(|i: u32| [(i >> 16) as u8, (i >> 8) as u8, i as u8]).collect();
+        let flat = RgbaImage::from_pixel(16, 16, Rgba([128, 128, 128, 255]));
+        let noise = AlgorithmParams::for_algorithm("gaussian-sampling").noise;
+        let mut dithered = flat.clone();
+        apply_lut_dithered(&mut dithered, &lut, noise, None);
+        assert!(dithered.pixels().all(|p| p.0[..3].iter().all(|v| v.abs_diff(128) as f32 <= noise)));
+        assert!(dithered.pixels().any(|p| p != flat.get_pixel(0, 0)));
+        // The noise depends only on position, and matches what `PaletteMapper::map_pixel` adds
+        let mut again = flat.clone();
+        apply_lut_dithered(&mut again, &lut, noise, None);
+        assert_eq!(dithered, again);
+        let mut plain = flat.clone();
+        apply_lut_dithered(&mut plain, &lut, 0.0, None);
+        assert_eq!(plain, flat);
+    }
+
+    #[test]
+    fn test_hue_lightness_plane() {
+        let plane = hue_lightness_plane(36, 11, 1.0, |color| color);
+        assert_eq!(plane.get_pixel(0, 0).0, [255, 255, 255, 255]);
+        assert_eq!(plane.get_pixel(20, 10).0, [0, 0, 0, 255]);
+        // Middle lightness at hue 0 and 120 is pure red and green
+        assert_eq!(plane.get_pixel(0, 5).0, [255, 0, 0, 255]);
+        assert_eq!(plane.get_pixel(12, 5).0, [0, 255, 0, 255]);
+        let lut: Vec<u8> = (0..256 * 256 * 256).flat_map(|i: u32| [(i >> 16) as u8, (i >> 8) as u8, i as u8]).collect();
+        assert_eq!(lut_lookup(&lut, (12, 34, 56)), (12, 34, 56));
+    }
+
+    #[test]
+    fn test_quantize_to_palette() {
+        let mut img = RgbaImage::from_fn(64, 16, |x, _| Rgba([(x * 4) as u8, 90, 200 - x as u8, 128]));
+        quantize_to_palette(&mut img, FlavorName::Frappe);
+        let palette: Vec<(u8, u8, u8)> = flavor_colors(FlavorName::Frappe).into_iter().map(|(_, rgb)| rgb).collect();
+        assert!(img.pixels().all(|p| palette.contains(&(p[0], p[1], p[2])) && p[3] == 128));
+        let used: std::collections::HashSet<_> = img.pixels().map(|p| p.0).collect();
+        assert!(used.len() > 2);
+    }
+
+    #[test]
+    fn test_strict_gif_frames_use_only_palette_colors() {
+        let frames = (0..3u32).map(|i| (RgbaImage::from_fn(32, 8, |x, y| Rgba([(x * 8) as u8, (y * 30) as u8, (i * 80) as u8, 255])), 10)).collect();
+        let gif = encode_gif_frames(frames).unwrap();
+        let output = process_gif_with_progress(&gif, FlavorName::Mocha, "shepards-method", &PreAdjust::default(), None, true, None).unwrap();
+        let palette: Vec<(u8, u8, u8)> = flavor_colors(FlavorName::Mocha).into_iter().map(|(_, rgb)| rgb).collect();
+        let decoded = decode_gif_frames(&output).unwrap();
+        assert_eq!(decoded.len(), 3);
+        for i in 0..decoded.len() {
+            assert!(decoded.get(i).unwrap().pixels().all(|p| palette.contains(&(p[0], p[1], p[2]))));
+        }
+    }
+
     #[test]
     fn test_create_comparison_image() {
         use image::{RgbaImage, Rgba};
@@ -408,6 +2426,443 @@ mod tests {
         assert_eq!(cmp.get_pixel(10 + 20, 0), &Rgba([0, 255, 0, 255]));
     }
 
+    #[test]
+    fn test_generate_wallpaper_styles() {
+        let flat = generate_wallpaper("none", FlavorName::Mocha, 64, 64);
+        let base = crate::utils::catppuccin_color_name_to_rgb("base", FlavorName::Mocha).unwrap();
+        assert!(flat.pixels().all(|p| p.0 == [base.0, base.1, base.2, 255]));
+        for style in ["noise", "grain", "paper", "grid", "diamonds"] {
+            let img = generate_wallpaper(style, FlavorName::Mocha, 64, 64);
+            assert_eq!(img.dimensions(), (64, 64));
+            assert!(img.pixels().any(|p| p.0 != [base.0, base.1, base.2, 255]), "style {} left the canvas flat", style);
+        }
+    }
+
+    #[test]
+    fn test_caption_image_draws_top_and_bottom() {
+        let img = RgbaImage::from_pixel(300, 300, Rgba([128, 128, 128, 255]));
+        let captioned = caption_image(&img, "top", "bottom", FlavorName::Mocha);
+        let changed_rows: Vec<u32> = (0..300).filter(|&y| (0..300).any(|x| captioned.get_pixel(x, y) != img.get_pixel(x, y))).collect();
+        assert!(changed_rows.iter().any(|&y| y < 100));
+        assert!(changed_rows.iter().any(|&y| y > 200));
+        assert!(!changed_rows.contains(&150));
+    }
+
+    #[test]
+    fn test_apply_post_effects_chain() {
+        let img = RgbaImage::from_pixel(100, 80, Rgba([200, 100, 50, 255]));
+        let rounded = apply_post_effects(img.clone(), &[PostEffect::Rounded(20)], FlavorName::Mocha);
+        assert_eq!(rounded.dimensions(), (100, 80));
+        assert_eq!(rounded.get_pixel(0, 0)[3], 0);
+        assert_eq!(rounded.get_pixel(50, 40)[3], 255);
+
+        let bordered = apply_post_effects(img.clone(), &[PostEffect::Rounded(20), PostEffect::Border("mauve".to_string(), 8)], FlavorName::Mocha);
+        assert_eq!(bordered.dimensions(), (116, 96));
+        assert_eq!(bordered.get_pixel(0, 0)[3], 0);
+        let mauve = crate::utils::catppuccin_color_name_to_rgb("mauve", FlavorName::Mocha).unwrap();
+        assert_eq!(bordered.get_pixel(58, 2), &Rgba([mauve.0, mauve.1, mauve.2, 255]));
+
+        let shadowed = apply_post_effects(img, &[PostEffect::Shadow], FlavorName::Mocha);
+        assert!(shadowed.width() > 100 && shadowed.height() > 80);
+        assert_eq!(shadowed.get_pixel(0, 0)[3], 0);
+    }
+
+    #[test]
+    fn test_background_fill() {
+        let img = RgbaImage::from_pixel(100, 80, Rgba([200, 100, 50, 255]));
+        let filled = apply_post_effects(img.clone(), &[PostEffect::Rounded(20), PostEffect::Background("crust".to_string())], FlavorName::Mocha);
+        let crust = crate::utils::catppuccin_color_name_to_rgb("crust", FlavorName::Mocha).unwrap();
+        assert_eq!(filled.get_pixel(0, 0), &Rgba([crust.0, crust.1, crust.2, 255]));
+        assert_eq!(filled.get_pixel(50, 40), &Rgba([200, 100, 50, 255]));
+        let filled = apply_post_effects(img, &[PostEffect::Rounded(20), PostEffect::Background("#102030".to_string())], FlavorName::Mocha);
+        assert_eq!(filled.get_pixel(0, 0), &Rgba([16, 32, 48, 255]));
+
+        // Formats without alpha get the base unless a background was given
+        let mut effects = vec![PostEffect::Shadow];
+        ensure_opaque_background(&mut effects, image::ImageFormat::Jpeg);
+        assert_eq!(effects.last(), Some(&PostEffect::Background("base".to_string())));
+        let mut effects = vec![PostEffect::Background("crust".to_string())];
+        ensure_opaque_background(&mut effects, image::ImageFormat::Jpeg);
+        assert_eq!(effects.len(), 1);
+        let mut effects = Vec::new();
+        ensure_opaque_background(&mut effects, image::ImageFormat::Png);
+        assert!(effects.is_empty());
+    }
+
+    #[test]
+    fn test_effects_registry() {
+        let mut img = RgbaImage::from_pixel(64, 64, Rgba([120, 120, 140, 255]));
+        img.put_pixel(32, 32, Rgba([255, 255, 255, 255]));
+        for effect in EFFECTS {
+            let out = (effect.apply)(&img, FlavorName::Mocha);
+            assert_eq!(out.dimensions(), img.dimensions(), "{} changed dimensions", effect.name);
+            assert_ne!(out, img, "{} had no effect", effect.name);
+        }
+        assert!(find_effect("Vignette").is_some());
+        assert!(find_effect("sparkle").is_none());
+    }
+
+    #[test]
+    fn test_render_posterize_uses_palette_colors() {
+        let img = RgbaImage::from_fn(64, 8, |x, _| Rgba([(x * 4) as u8, 100, 255 - (x * 4) as u8, 200]));
+        let out = render_posterize(&img, FlavorName::Frappe, 3);
+        let colors: Vec<(u8, u8, u8)> = flavor_colors(FlavorName::Frappe).into_iter().map(|(_, rgb)| rgb).collect();
+        assert!(out.pixels().all(|p| colors.contains(&(p[0], p[1], p[2])) && p[3] == 200));
+        // Levels are clamped, so one level doesn't divide by zero
+        assert_eq!(render_posterize(&img, FlavorName::Frappe, 1).dimensions(), (64, 8));
+    }
+
+    #[test]
+    fn test_sobel_and_cel_shade_outline_edges() {
+        let img = RgbaImage::from_fn(40, 40, |x, _| if x < 20 { Rgba([255, 255, 255, 255]) } else { Rgba([0, 0, 0, 255]) });
+        let edges = sobel_magnitude(&img);
+        assert_eq!(edges[10 * 40 + 5], 0.0);
+        assert!(edges[10 * 40 + 19] > 0.5 && edges[10 * 40 + 20] > 0.5);
+        let crust = crate::utils::catppuccin_color_name_to_rgb("crust", FlavorName::Mocha).unwrap();
+        let out = render_cel_shade(&img, FlavorName::Mocha);
+        let edge = out.get_pixel(19, 20);
+        assert_eq!((edge[0], edge[1], edge[2]), crust);
+        assert_ne!(out.get_pixel(2, 20), edge);
+    }
+
+    #[test]
+    fn test_render_sketch() {
+        let img = RgbaImage::from_fn(40, 40, |x, _| if x < 20 { Rgba([240, 200, 180, 255]) } else { Rgba([20, 40, 80, 255]) });
+        let base = crate::utils::catppuccin_color_name_to_rgb("base", FlavorName::Mocha).unwrap();
+        let ink = crate::utils::catppuccin_color_name_to_rgb("mauve", FlavorName::Mocha).unwrap();
+        let out = render_sketch(&img, FlavorName::Mocha, ink, false);
+        assert_eq!(*out.get_pixel(5, 5), Rgba([base.0, base.1, base.2, 255]));
+        // The stroke over the hard edge is antialiased but almost solid ink
+        let edge = out.get_pixel(20, 5);
+        assert!([ink.0, ink.1, ink.2].iter().zip(edge.0).all(|(i, e)| (*i as i32 - e as i32).abs() <= 20), "{:?}", edge);
+        // The wash tints flat areas away from plain base
+        let washed = render_sketch(&img, FlavorName::Mocha, ink, true);
+        assert_ne!(*washed.get_pixel(5, 5), Rgba([base.0, base.1, base.2, 255]));
+    }
+
+    #[test]
+    fn test_render_halftone_uses_base_and_ink() {
+        let mut img = RgbaImage::from_pixel(80, 80, Rgba([30, 30, 46, 255]));
+        for y in 0..80 {
+            for x in 40..80 {
+                img.put_pixel(x, y, Rgba([245, 194, 231, 255]));
+            }
+        }
+        let out = render_halftone(&img, FlavorName::Mocha);
+        assert_eq!(out.dimensions(), (80, 80));
+        let base = crate::utils::catppuccin_color_name_to_rgb("base", FlavorName::Mocha).unwrap();
+        // The left half matches the base, so no dots are drawn there
+        assert!((0..80).all(|y| (0..36).all(|x| out.get_pixel(x, y) == &Rgba([base.0, base.1, base.2, 255]))));
+        assert!(out.get_pixel(62, 42) != &Rgba([base.0, base.1, base.2, 255]));
+    }
+
+    #[test]
+    fn test_render_cross_stitch_legend() {
+        let mut img = RgbaImage::from_pixel(200, 100, Rgba([30, 30, 46, 255]));
+        for y in 0..100 {
+            for x in 100..200 {
+                img.put_pixel(x, y, Rgba([166, 227, 161, 255]));
+            }
+        }
+        let (chart, legend) = render_cross_stitch(&img, FlavorName::Mocha, 20);
+        let total: u32 = legend.iter().map(|(_, _, count)| count).sum();
+        assert_eq!(total, 20 * 10);
+        assert!(legend.iter().any(|(name, _, _)| *name == "green"));
+        assert!(legend.iter().any(|(name, _, _)| *name == "base"));
+        assert!(chart.width() >= 20 * 18 && chart.height() > 10 * 18);
+    }
+
+    #[test]
+    fn test_render_polaroid() {
+        let img = RgbaImage::from_pixel(200, 150, Rgba([200, 100, 50, 255]));
+        let out = render_polaroid(&img, "summer", FlavorName::Latte);
+        assert!(out.width() > 200 && out.height() > 150);
+        // Tilted onto a transparent canvas
+        assert_eq!(out.get_pixel(0, 0)[3], 0);
+        assert!(out.pixels().any(|p| p == &Rgba([200, 100, 50, 255])));
+    }
+
+    #[test]
+    fn test_rotate_image_zero_is_identity() {
+        let img = RgbaImage::from_fn(10, 6, |x, y| Rgba([(x * 20) as u8, (y * 40) as u8, 7, 255]));
+        assert_eq!(rotate_image(&img, 0.0), img);
+    }
+
+    #[test]
+    fn test_make_seamless_tile_edges_match() {
+        let img = RgbaImage::from_fn(120, 90, |x, y| Rgba([(x * 2) as u8, (y * 2) as u8, 100, 255]));
+        for mode in ["mirror", "blend"] {
+            let tile = make_seamless_tile(&img, mode, 64);
+            assert_eq!(tile.dimensions(), (64, 64));
+            // Wrapping from the last column/row to the first should be a small step, not a seam
+            for i in 0..64 {
+                let horizontal = delta_e(rgb_of(tile.get_pixel(63, i)), rgb_of(tile.get_pixel(0, i)));
+                let vertical = delta_e(rgb_of(tile.get_pixel(i, 63)), rgb_of(tile.get_pixel(i, 0)));
+                assert!(horizontal < 5.0 && vertical < 5.0, "{} tile has a seam at {}", mode, i);
+            }
+        }
+        assert_eq!(repeat_tile(&make_seamless_tile(&img, "mirror", 64), 3).dimensions(), (192, 192));
+    }
+
+    fn rgb_of(p: &Rgba<u8>) -> (u8, u8, u8) {
+        (p[0], p[1], p[2])
+    }
+
+    #[test]
+    fn test_replace_color_preserves_shading() {
+        let mut img = RgbaImage::new(3, 1);
+        img.put_pixel(0, 0, Rgba([255, 0, 0, 255]));
+        img.put_pixel(1, 0, Rgba([200, 0, 0, 255]));
+        img.put_pixel(2, 0, Rgba([0, 0, 255, 128]));
+        let target = crate::utils::catppuccin_color_name_to_rgb("green", FlavorName::Mocha).unwrap();
+        let replaced = replace_color(&mut img, (255, 0, 0), target, 20.0);
+        assert_eq!(replaced, 2);
+        let exact = img.get_pixel(0, 0);
+        assert!(delta_e((exact[0], exact[1], exact[2]), target) < 1.0);
+        // The darker red stays darker than the exact match
+        assert!(luma(img.get_pixel(1, 0)) < luma(exact));
+        assert_eq!(img.get_pixel(2, 0), &Rgba([0, 0, 255, 128]));
+    }
+
+    #[test]
+    fn test_composite_with_mask() {
+        let original = RgbaImage::from_pixel(4, 4, Rgba([0, 0, 0, 255]));
+        let processed = RgbaImage::from_pixel(4, 4, Rgba([200, 100, 50, 255]));
+        let out = composite_with_mask(&original, &processed, &rect_mask(4, 4, (1, 1, 2, 2)));
+        assert_eq!(out.get_pixel(0, 0), &Rgba([0, 0, 0, 255]));
+        assert_eq!(out.get_pixel(1, 1), &Rgba([200, 100, 50, 255]));
+        assert_eq!(out.get_pixel(2, 2), &Rgba([200, 100, 50, 255]));
+        assert_eq!(out.get_pixel(3, 3), &Rgba([0, 0, 0, 255]));
+
+        // A half-gray mask of a different size blends halfway everywhere
+        let gray = image::GrayImage::from_pixel(2, 2, image::Luma([128]));
+        let out = composite_with_mask(&original, &processed, &gray);
+        assert_eq!(out.get_pixel(3, 0), &Rgba([100, 50, 25, 255]));
+    }
+
+    #[test]
+    fn test_segment_background_finds_subject() {
+        let mut img = RgbaImage::from_pixel(100, 100, Rgba([230, 230, 235, 255]));
+        for y in 30..70 {
+            for x in 30..70 {
+                img.put_pixel(x, y, Rgba([200, 40, 60, 255]));
+            }
+        }
+        let mask = segment_background(&img);
+        assert_eq!(mask.dimensions(), (100, 100));
+        assert!(mask.get_pixel(5, 5)[0] > 200);
+        assert!(mask.get_pixel(95, 50)[0] > 200);
+        assert!(mask.get_pixel(50, 50)[0] < 50);
+    }
+
+    #[test]
+    fn test_process_sprite_sheet_preserves_margins() {
+        // 2x1 sheet: left cell has a sprite in its middle, right cell is empty
+        let mut img = RgbaImage::from_pixel(8, 4, Rgba([10, 20, 30, 0]));
+        img.put_pixel(1, 1, Rgba([255, 0, 0, 255]));
+        img.put_pixel(2, 2, Rgba([0, 255, 0, 255]));
+        let lut = generate_catppuccin_lut(FlavorName::Mocha, "nearest-neighbor");
+        let (out, empty) = process_sprite_sheet(&img, 2, 1, &lut, "nearest-neighbor").unwrap();
+        assert_eq!(empty, 1);
+        assert_eq!(out.get_pixel(0, 0), &Rgba([10, 20, 30, 0]));
+        assert_eq!(out.get_pixel(6, 3), &Rgba([10, 20, 30, 0]));
+        assert_ne!(out.get_pixel(1, 1), &Rgba([255, 0, 0, 255]));
+        assert_eq!(out.get_pixel(1, 1)[3], 255);
+        assert!(process_sprite_sheet(&img, 3, 1, &lut, "nearest-neighbor").is_err());
+    }
+
+    #[test]
+    fn test_resize_crop_fit() {
+        let img = RgbaImage::from_pixel(400, 200, Rgba([1, 2, 3, 255]));
+        assert_eq!(resize_longest_side(&img, 100).dimensions(), (100, 50));
+        assert_eq!(crop_to_aspect(&img, 1, 1).dimensions(), (200, 200));
+        assert_eq!(crop_to_aspect(&img, 16, 9).dimensions(), (355, 200));
+        assert_eq!(crop_to_aspect(&img, 1, 2).dimensions(), (100, 200));
+        assert_eq!(fit_to_size(&img, 64, 64).dimensions(), (64, 64));
+        assert_eq!(fit_to_size(&img, 1920, 1080).dimensions(), (1920, 1080));
+    }
+
+    fn test_gif(n: u8) -> Vec<u8> {
+        let frames = (0..n).map(|i| (RgbaImage::from_pixel(4, 4, Rgba([i * 40, 0, 0, 255])), 10)).collect();
+        encode_gif_frames(frames).unwrap()
+    }
+
+    #[test]
+    fn test_transform_gif_ops() {
+        let gif = test_gif(5);
+        let reversed = decode_gif_frames(&transform_gif(&gif, GifOp::Reverse, None, "nearest-neighbor", None).unwrap()).unwrap();
+        assert_eq!(reversed.len(), 5);
+        assert!(reversed.get(0).unwrap().get_pixel(0, 0)[0] > reversed.get(4).unwrap().get_pixel(0, 0)[0]);
+
+        let boomerang = decode_gif_frames(&transform_gif(&gif, GifOp::Boomerang, None, "nearest-neighbor", None).unwrap()).unwrap();
+        assert_eq!(boomerang.len(), 8);
+
+        let faster = decode_gif_frames(&transform_gif(&gif, GifOp::Speed(2.0), None, "nearest-neighbor", None).unwrap()).unwrap();
+        assert!(faster.delays().iter().all(|delay| *delay == 5));
+        let slower = decode_gif_frames(&transform_gif(&gif, GifOp::Speed(0.5), None, "nearest-neighbor", None).unwrap()).unwrap();
+        assert!(slower.delays().iter().all(|delay| *delay == 20));
+        // 10x would be 1cs per frame, so every other frame is dropped
+        let fastest = decode_gif_frames(&transform_gif(&gif, GifOp::Speed(10.0), None, "nearest-neighbor", None).unwrap()).unwrap();
+        assert_eq!(fastest.len(), 3);
+        assert!(fastest.delays().iter().all(|delay| *delay >= 2));
+
+        // Strict output keeps to the flavor's colors in every frame
+        let palette: Vec<(u8, u8, u8)> = flavor_colors(FlavorName::Latte).into_iter().map(|(_, rgb)| rgb).collect();
+        let strict = decode_gif_frames(&transform_gif(&gif, GifOp::Reverse, None, "nearest-neighbor", Some(FlavorName::Latte)).unwrap()).unwrap();
+        assert!((0..strict.len()).all(|i| strict.get(i).unwrap().pixels().all(|p| palette.contains(&(p[0], p[1], p[2])))));
+    }
+
+    #[test]
+    fn test_gif_frame_indexing() {
+        let gif = test_gif(5);
+        assert_eq!(gif_frame_count(&gif).unwrap(), 5);
+        assert_eq!(gif_size(&gif).unwrap(), (4, 4, 5));
+        assert_eq!(gif_size(&resize_gif(&gif, 2, 3).unwrap()).unwrap(), (2, 3, 5));
+        let third = extract_gif_frame(&gif, 3).unwrap();
+        assert_eq!(third.get_pixel(0, 0)[0], 80);
+        assert!(extract_gif_frame(&gif, 0).is_err());
+        assert!(extract_gif_frame(&gif, 6).is_err());
+        let sheet = render_frame_contact_sheet(&gif, FlavorName::Mocha).unwrap();
+        // 5 frames lay out as a 3x2 grid
+        assert!(sheet.width() > sheet.height());
+    }
+
+    #[test]
+    fn test_image_info() {
+        let gif = image_info(&test_gif(5)).unwrap();
+        assert_eq!((gif.format, gif.width, gif.height, gif.frames), (image::ImageFormat::Gif, 4, 4, 5));
+        let mut png = Cursor::new(Vec::new());
+        image::DynamicImage::ImageRgb8(image::RgbImage::new(6, 3)).write_to(&mut png, image::ImageFormat::Png).unwrap();
+        let png = png.into_inner();
+        let info = image_info(&png).unwrap();
+        assert_eq!((info.width, info.height, info.frames, info.bit_depth()), (6, 3, 1, 8));
+        assert_eq!((info.color_type, info.file_size), (image::ColorType::Rgb8, png.len()));
+        assert!(image_info(b"not an image").is_err());
+    }
+
+    #[test]
+    fn test_flavor_distances() {
+        // An image made of Mocha's base is exactly on Mocha's palette
+        let base = crate::utils::catppuccin_color_name_to_rgb("base", FlavorName::Mocha).unwrap();
+        let img = RgbaImage::from_pixel(16, 16, Rgba([base.0, base.1, base.2, 255]));
+        let distances = flavor_distances(&img);
+        assert_eq!(distances.len(), 4);
+        assert_eq!(distances[0].0, FlavorName::Mocha);
+        assert!(distances[0].1 < 0.5);
+        assert!(distances[3].1 > distances[0].1);
+    }
+
+    #[test]
+    fn test_render_delta_map() {
+        let base = crate::utils::catppuccin_color_name_to_rgb("base", FlavorName::Mocha).unwrap();
+        let red = crate::utils::catppuccin_color_name_to_rgb("red", FlavorName::Mocha).unwrap();
+        let original = RgbaImage::from_pixel(3, 1, Rgba([255, 255, 255, 255]));
+        let mut processed = original.clone();
+        processed.put_pixel(1, 0, Rgba([0, 0, 0, 255]));
+        let (map, mean, max) = render_delta_map(&original, &processed, FlavorName::Mocha);
+        // Unchanged pixels are base, and white to black is well past the scale
+        assert_eq!(*map.get_pixel(0, 0), Rgba([base.0, base.1, base.2, 255]));
+        assert_eq!(*map.get_pixel(1, 0), Rgba([red.0, red.1, red.2, 255]));
+        assert!(max > DELTA_MAP_SCALE);
+        assert!((mean - max / 3.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_apply_pre_adjustments() {
+        let mut img = RgbaImage::from_pixel(2, 1, Rgba([255, 0, 0, 128]));
+        apply_pre_adjustments(&mut img, &PreAdjust::default(), FlavorName::Mocha);
+        assert_eq!(*img.get_pixel(0, 0), Rgba([255, 0, 0, 128]));
+        // A third of the way round turns red into green, keeping alpha
+        apply_pre_adjustments(&mut img, &PreAdjust { hue_shift: 120.0, ..Default::default() }, FlavorName::Mocha);
+        assert_eq!(*img.get_pixel(0, 0), Rgba([0, 255, 0, 128]));
+        apply_pre_adjustments(&mut img, &PreAdjust { saturation: 0.0, brightness: 0.5, ..Default::default() }, FlavorName::Mocha);
+        assert_eq!(*img.get_pixel(1, 0), Rgba([128, 128, 128, 128]));
+        let adjust = PreAdjust { hue_shift: -30.0, saturation: 1.5, ..Default::default() };
+        assert_eq!(adjust.describe(), "hue -30°, saturation ×1.5");
+    }
+
+    #[test]
+    fn test_alpha_pre_adjustments() {
+        // A half-transparent pink edge stored premultiplied reads as a darker pink until it's divided out
+        let mut img = RgbaImage::from_pixel(1, 1, Rgba([122, 97, 118, 128]));
+        apply_pre_adjustments(&mut img, &PreAdjust { premultiplied: true, ..Default::default() }, FlavorName::Mocha);
+        assert_eq!(*img.get_pixel(0, 0), Rgba([243, 193, 235, 128]));
+        // Flattened onto Mocha's base (#1e1e2e), halfway between it and the pink, and opaque
+        let adjust = PreAdjust { premultiplied: true, matte: Some("base"), ..Default::default() };
+        let mut img = RgbaImage::from_pixel(1, 1, Rgba([122, 97, 118, 128]));
+        apply_pre_adjustments(&mut img, &adjust, FlavorName::Mocha);
+        assert_eq!(*img.get_pixel(0, 0), Rgba([137, 112, 141, 255]));
+        assert_eq!(adjust.describe(), "premultiplied alpha, on base");
+    }
+
+    #[test]
+    fn test_shift_temperature() {
+        let mut img = RgbaImage::from_pixel(1, 1, Rgba([128, 128, 128, 200]));
+        shift_temperature(&mut img, 40.0);
+        let warm = *img.get_pixel(0, 0);
+        assert!(warm[0] > warm[2] && warm[3] == 200, "{:?}", warm);
+        // Lightness is kept, so the tint doesn't brighten or darken the pixel
+        assert!((crate::utils::rgb_to_hsl(warm[0], warm[1], warm[2]).2 - 128.0 / 255.0).abs() < 0.01);
+        let mut img = RgbaImage::from_pixel(1, 1, Rgba([128, 128, 128, 255]));
+        shift_temperature(&mut img, -40.0);
+        let cool = *img.get_pixel(0, 0);
+        assert!(cool[2] > cool[0], "{:?}", cool);
+        assert_eq!(PreAdjust { temperature: -20.0, ..Default::default() }.describe(), "cooler 20");
+    }
+
+    #[test]
+    fn test_auto_correct() {
+        // A dim, blue-tinted gradient comes out neutral and spanning the full range
+        let mut img = RgbaImage::from_fn(64, 1, |x, _| Rgba([(x / 2) as u8, (x / 2) as u8, (x / 2 + 30) as u8, 255]));
+        auto_correct(&mut img);
+        let (first, last) = (img.get_pixel(0, 0), img.get_pixel(63, 0));
+        assert!(first[0] < 10 && last[0] > 245, "{:?} {:?}", first, last);
+        let mean = |c: usize| img.pixels().map(|p| p[c] as f32).sum::<f32>() / 64.0;
+        assert!((mean(0) - mean(2)).abs() < 8.0, "{} {}", mean(0), mean(2));
+        // Flat and transparent images are left alone
+        let mut flat = RgbaImage::from_pixel(4, 4, Rgba([90, 90, 90, 255]));
+        auto_correct(&mut flat);
+        assert_eq!(*flat.get_pixel(0, 0), Rgba([90, 90, 90, 255]));
+        let mut clear = RgbaImage::from_pixel(4, 4, Rgba([200, 10, 10, 0]));
+        auto_correct(&mut clear);
+        assert_eq!(*clear.get_pixel(0, 0), Rgba([200, 10, 10, 0]));
+        assert_eq!(PreAdjust { auto_correct: true, brightness: 1.2, ..Default::default() }.describe(), "auto-corrected, brightness ×1.2");
+    }
+
+    #[test]
+    fn test_interpolate_luts() {
+        let a = vec![0u8, 100, 255];
+        let b = vec![255u8, 100, 0];
+        assert_eq!(interpolate_luts(&a, &b, 0.0), a);
+        assert_eq!(interpolate_luts(&a, &b, 1.0), b);
+        assert_eq!(interpolate_luts(&a, &b, 0.5), vec![128, 100, 128]);
+    }
+
+    #[test]
+    fn test_generate_flavor_morph_frames_loops() {
+        let img = RgbaImage::from_pixel(8, 8, Rgba([200, 120, 90, 255]));
+        let frames = generate_flavor_morph_frames(&img, FlavorName::Latte, FlavorName::Mocha, "nearest-neighbor", 5, 480);
+        // 5 forward frames plus 3 on the way back
+        assert_eq!(frames.len(), 8);
+        assert_eq!(frames[0].1, 80);
+        assert_eq!(frames[4].1, 80);
+        assert_eq!(frames[3].0, frames[5].0);
+        assert_ne!(frames[0].0, frames[4].0);
+    }
+
+    #[test]
+    fn test_generate_crossfade_frames_matches_sizes() {
+        let a = RgbaImage::from_pixel(20, 10, Rgba([0, 0, 0, 255]));
+        let b = RgbaImage::from_pixel(7, 30, Rgba([255, 255, 255, 255]));
+        let frames = generate_crossfade_frames(&a, &b, 4, 480);
+        assert_eq!(frames.len(), 6);
+        assert!(frames.iter().all(|(f, _)| f.dimensions() == (20, 10)));
+        assert_eq!(frames[0].0.get_pixel(0, 0)[0], 0);
+        assert_eq!(frames[3].0.get_pixel(0, 0)[0], 255);
+        let mid = frames[1].0.get_pixel(0, 0)[0];
+        assert!(mid > 0 && mid < 255);
+    }
+
     #[test]
     fn test_process_gif_with_palette_minimal() {
         // Minimal 2-frame GIF (1x1 px, red and green)
@@ -420,4 +2875,21 @@ mod tests {
         let out = result.unwrap();
         assert!(!out.is_empty());
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_progress_reports_rows_and_frames() {
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        let img = image::DynamicImage::ImageRgba8(RgbaImage::from_pixel(3, 50, Rgba([200, 30, 30, 255])));
+        let processed = process_image_with_progress(&img, FlavorName::Mocha, "shepards-method", &PreAdjust::default(), Some(&sender));
+        // Same pixels as the plain version
+        assert_eq!(processed, process_image_with_palette(&img, FlavorName::Mocha, "shepards-method"));
+        let rows: Vec<Progress> = std::iter::from_fn(|| receiver.try_recv().ok()).collect();
+        assert!(rows.windows(2).all(|w| w[0].done < w[1].done));
+        assert_eq!(rows.last(), Some(&Progress { done: 50, total: 50 }));
+
+        process_gif_with_progress(&test_gif(4), FlavorName::Mocha, "shepards-method", &PreAdjust::default(), None, false, Some(&sender)).unwrap();
+        let frames: Vec<Progress> = std::iter::from_fn(|| receiver.try_recv().ok()).collect();
+        assert_eq!(frames.iter().map(|p| (p.done, p.total)).collect::<Vec<_>>(), vec![(1, 4), (2, 4), (3, 4), (4, 4)]);
+        assert_eq!(frames[1].percent(), 50);
+    }
+}
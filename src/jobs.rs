@@ -0,0 +1,375 @@
+// src/jobs.rs
+
+use crate::error::BotError;
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
+use serenity::model::channel::Message;
+use serenity::model::id::{MessageId, UserId};
+use std::any::Any;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::OnceCell;
+use tracing::error;
+
+/// How long a finished job's result is reused for an identical request
+const DEDUP_WINDOW: Duration = Duration::from_secs(30);
+
+/// Most results kept for reuse at once; past it the oldest that nobody is waiting on are dropped early
+const MAX_SHARED_JOBS: usize = 32;
+
+// A shared result cell, type-erased so jobs with different outputs can live in one map
+struct SharedEntry {
+    started: Instant,
+    cell: Arc<dyn Any + Send + Sync>,
+}
+
+static SHARED_JOBS: Lazy<DashMap<u64, SharedEntry>> = Lazy::new(DashMap::new);
+
+// Jobs waiting for or holding a processing permit, by ticket number
+static QUEUE: Lazy<DashMap<u64, QueuedJob>> = Lazy::new(DashMap::new);
+static NEXT_TICKET: AtomicU64 = AtomicU64::new(0);
+
+/// What a blocking job is doing and for whom, for logs and error IDs
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Job {
+    pub command: &'static str,
+    pub user: UserId,
+    pub message: MessageId,
+}
+
+impl Job {
+    pub fn new(command: &'static str, msg: &Message) -> Self {
+        Job { command, user: msg.author.id, message: msg.id }
+    }
+
+    /// A short ID derived from the command and the message that started it, so a failure
+    /// reported by a user can be matched to its log entry (FNV-1a, stable across builds)
+    pub fn error_id(&self) -> String {
+        let key = format!("{}:{}", self.command, self.message);
+        format!("{:08X}", fnv1a(&[key.as_bytes()]) as u32)
+    }
+}
+
+// FNV-1a over several byte strings, each followed by a separator so their boundaries count
+fn fnv1a(parts: &[&[u8]]) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325u64;
+    for byte in parts.iter().flat_map(|part| part.iter().chain(&[0xff])) {
+        hash = (hash ^ *byte as u64).wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+/// Identifies a request by who sent it, the command and its settings, and the image's contents
+/// (not its URL, so re-uploading the same file counts as the same request)
+pub fn request_key(user: UserId, command: &str, settings: &str, image: &[u8]) -> u64 {
+    fnv1a(&[&user.get().to_le_bytes(), command.as_bytes(), settings.as_bytes(), image])
+}
+
+/// Identifies an image by its contents alone, whoever posted it
+pub fn content_key(image: &[u8]) -> u64 {
+    fnv1a(&[image])
+}
+
+/// A job that panicked instead of returning. Its message is safe to show users.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JobPanic {
+    pub error_id: String,
+}
+
+// Shown to users as the `Internal` error, code included
+impl std::fmt::Display for JobPanic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&BotError::Internal { error_id: self.error_id.clone() }.reply())
+    }
+}
+
+/// Run blocking work on the blocking thread pool while holding an image processing permit.
+/// The permit is released however the work ends; a panic is logged with the job's context
+/// and comes back as a `JobPanic` rather than taking anything else down with it.
+pub async fn run<T, F>(job: Job, work: F) -> Result<T, JobPanic>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    let ticket = QueueTicket::enter(job);
+    let _permit = crate::IMAGE_PROCESSING_SEMAPHORE.acquire().await.expect("Semaphore closed");
+    ticket.start();
+    supervise(job, work).await
+}
+
+/// A job in the processing queue
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QueuedJob {
+    pub job: Job,
+    pub queued: Instant,
+    /// When it got a permit; `None` while it's still waiting
+    pub started: Option<Instant>,
+}
+
+// A job's place in `QUEUE`, given up when the job ends or its caller stops waiting
+struct QueueTicket(u64);
+
+impl QueueTicket {
+    fn enter(job: Job) -> Self {
+        let ticket = NEXT_TICKET.fetch_add(1, Ordering::Relaxed);
+        QUEUE.insert(ticket, QueuedJob { job, queued: Instant::now(), started: None });
+        QueueTicket(ticket)
+    }
+
+    fn start(&self) {
+        if let Some(mut entry) = QUEUE.get_mut(&self.0) {
+            entry.started = Some(Instant::now());
+        }
+    }
+}
+
+impl Drop for QueueTicket {
+    fn drop(&mut self) {
+        QUEUE.remove(&self.0);
+    }
+}
+
+/// Every job that is running or waiting to run, oldest first
+pub fn queue() -> Vec<QueuedJob> {
+    let mut jobs: Vec<QueuedJob> = QUEUE.iter().map(|entry| *entry.value()).collect();
+    jobs.sort_by_key(|queued| queued.queued);
+    jobs
+}
+
+async fn supervise<T, F>(job: Job, work: F) -> Result<T, JobPanic>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    match tokio::task::spawn_blocking(work).await {
+        Ok(output) => Ok(output),
+        Err(join_error) => {
+            let error_id = job.error_id();
+            let reason = if join_error.is_panic() {
+                panic_message(join_error.into_panic())
+            } else {
+                "the task was cancelled".to_string()
+            };
+            error!(error_id, command = job.command, user = %job.user, message = %job.message, reason, "Processing job panicked");
+            Err(JobPanic { error_id })
+        }
+    }
+}
+
+/// The result slot for one request, shared by every identical request made while it runs
+/// or within `DEDUP_WINDOW` of it starting
+pub struct SharedJob<T> {
+    cell: Arc<OnceCell<Result<T, JobPanic>>>,
+    /// Whether an identical request got here first, so this one reuses its result
+    pub joined: bool,
+}
+
+/// Forget every result kept for duplicate requests, so the next request runs fresh. Jobs already
+/// running still finish for whoever is waiting on them. Returns how many were dropped.
+pub fn clear_shared() -> usize {
+    let before = SHARED_JOBS.len();
+    SHARED_JOBS.clear();
+    before
+}
+
+/// Drop results past `DEDUP_WINDOW`, then the oldest idle ones over `MAX_SHARED_JOBS`. Entries stay
+/// while someone still holds the cell, i.e. while the job is running or being awaited.
+pub fn prune_shared() {
+    let in_use = |entry: &SharedEntry| Arc::strong_count(&entry.cell) > 1;
+    SHARED_JOBS.retain(|_, entry| entry.started.elapsed() < DEDUP_WINDOW || in_use(entry));
+    let excess = SHARED_JOBS.len().saturating_sub(MAX_SHARED_JOBS);
+    if excess > 0 {
+        let mut idle: Vec<(Instant, u64)> = SHARED_JOBS.iter().filter(|entry| !in_use(entry.value())).map(|entry| (entry.started, *entry.key())).collect();
+        idle.sort();
+        for (_, key) in idle.into_iter().take(excess) {
+            SHARED_JOBS.remove_if(&key, |_, entry| !in_use(entry));
+        }
+    }
+}
+
+/// Prune shared results every `DEDUP_WINDOW`, so they're let go even when no new requests come in
+pub fn spawn_pruning() {
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(DEDUP_WINDOW).await;
+            prune_shared();
+        }
+    });
+}
+
+/// Look up or register the job for a request key
+pub fn share<T: Send + Sync + 'static>(key: u64) -> SharedJob<T> {
+    prune_shared();
+    let mut joined = true;
+    let entry = SHARED_JOBS.entry(key).or_insert_with(|| {
+        joined = false;
+        SharedEntry { started: Instant::now(), cell: Arc::new(OnceCell::<Result<T, JobPanic>>::new()) }
+    });
+    match entry.cell.clone().downcast::<OnceCell<Result<T, JobPanic>>>() {
+        Ok(cell) => SharedJob { cell, joined },
+        // Keys include the command, so a mismatch is a hash collision between commands; don't share
+        Err(_) => SharedJob { cell: Arc::new(OnceCell::new()), joined: false },
+    }
+}
+
+impl<T: Clone + Send + 'static> SharedJob<T> {
+    /// Run the job like `run`, or wait for the identical one already running and reuse its result.
+    /// If the first request is abandoned mid-run, the next one waiting runs its own work instead.
+    pub async fn run<F>(self, job: Job, work: F) -> Result<T, JobPanic>
+    where
+        F: FnOnce() -> T + Send + 'static,
+    {
+        self.cell.get_or_init(|| run(job, work)).await.clone()
+    }
+}
+
+// Panics carry a &str or String payload unless something unusual was passed to panic_any
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(message) => *message,
+        Err(payload) => payload.downcast_ref::<&str>().map_or("unknown panic payload", |s| s).to_string(),
+    }
+}
+
+/// A user's entry in `CANCEL_FLAGS` for the length of a job. `!cat cancel` sets the flag of the
+/// user's latest job. Each job has its own flag, and the entry is removed when the guard is dropped
+/// (including when the job fails or panics) only if it's still this job's, so a job finishing
+/// doesn't make a newer one uncancellable.
+pub struct CancelGuard {
+    user: UserId,
+    flag: Arc<AtomicBool>,
+}
+
+impl CancelGuard {
+    pub fn register(user: UserId) -> Self {
+        let flag = Arc::new(AtomicBool::new(false));
+        crate::CANCEL_FLAGS.insert(user, flag.clone());
+        CancelGuard { user, flag }
+    }
+
+    /// The flag itself, for checking from inside blocking work
+    pub fn flag(&self) -> Arc<AtomicBool> {
+        self.flag.clone()
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.flag.load(Ordering::SeqCst)
+    }
+}
+
+impl Drop for CancelGuard {
+    fn drop(&mut self) {
+        crate::CANCEL_FLAGS.remove_if(&self.user, |_, flag| Arc::ptr_eq(flag, &self.flag));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(command: &'static str, message: u64) -> Job {
+        Job { command, user: UserId::new(1), message: MessageId::new(message) }
+    }
+
+    #[test]
+    fn test_error_id_is_deterministic() {
+        assert_eq!(job("gif", 42).error_id(), job("gif", 42).error_id());
+        assert_ne!(job("gif", 42).error_id(), job("gif", 43).error_id());
+        assert_ne!(job("gif", 42).error_id(), job("tile", 42).error_id());
+        assert_eq!(job("gif", 42).error_id().len(), 8);
+    }
+
+    #[tokio::test]
+    async fn test_supervise_catches_panics() {
+        assert_eq!(supervise(job("gif", 1), || 7).await, Ok(7));
+        let panicked = supervise(job("gif", 1), || -> u8 { panic!("boom") }).await.unwrap_err();
+        assert_eq!(panicked.error_id, job("gif", 1).error_id());
+        assert!(panicked.to_string().contains(&panicked.error_id));
+    }
+
+    #[test]
+    fn test_request_key() {
+        let key = request_key(UserId::new(1), "process", "mocha", b"image");
+        assert_eq!(key, request_key(UserId::new(1), "process", "mocha", b"image"));
+        assert_ne!(key, request_key(UserId::new(2), "process", "mocha", b"image"));
+        assert_ne!(key, request_key(UserId::new(1), "process", "latte", b"image"));
+        assert_ne!(key, request_key(UserId::new(1), "process", "mocha", b"other"));
+        // Field boundaries matter
+        assert_ne!(request_key(UserId::new(1), "ab", "c", b""), request_key(UserId::new(1), "a", "bc", b""));
+    }
+
+    #[tokio::test]
+    async fn test_shared_job_runs_once() {
+        let runs = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let work = |runs: Arc<std::sync::atomic::AtomicUsize>| {
+            move || {
+                std::thread::sleep(Duration::from_millis(50));
+                runs.fetch_add(1, Ordering::SeqCst)
+            }
+        };
+        let first = share::<usize>(7);
+        let second = share::<usize>(7);
+        assert!(!first.joined && second.joined);
+        let (a, b) = tokio::join!(
+            first.cell.get_or_init(|| supervise(job("process", 1), work(runs.clone()))),
+            second.cell.get_or_init(|| supervise(job("process", 2), work(runs.clone()))),
+        );
+        assert_eq!((a, b), (&Ok(0), &Ok(0)));
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+        // A different key is a different job
+        assert!(!share::<usize>(8).joined);
+    }
+
+    #[test]
+    fn test_idle_shared_results_are_capped() {
+        let first_key = 1 << 40;
+        for key in first_key..first_key + MAX_SHARED_JOBS as u64 + 5 {
+            drop(share::<usize>(key));
+        }
+        // The oldest results went to make room; the newest is kept
+        assert!(!SHARED_JOBS.contains_key(&first_key));
+        assert!(SHARED_JOBS.contains_key(&(first_key + MAX_SHARED_JOBS as u64 + 4)));
+    }
+
+    #[test]
+    fn test_cancel_guard_leaves_newer_jobs_flag() {
+        let user = UserId::new(9);
+        let first = CancelGuard::register(user);
+        let second = CancelGuard::register(user);
+        drop(first);
+        crate::CANCEL_FLAGS.get(&user).unwrap().store(true, Ordering::SeqCst);
+        assert!(second.is_cancelled());
+        drop(second);
+        assert!(crate::CANCEL_FLAGS.get(&user).is_none());
+    }
+
+    #[test]
+    fn test_queue_tracks_waiting_and_running_jobs() {
+        let queued = job("queue-test", 5);
+        let waiting = QueueTicket::enter(queued);
+        let mine = |q: &QueuedJob| q.job == queued;
+        assert_eq!(queue().iter().find(|q| mine(q)).unwrap().started, None);
+        waiting.start();
+        assert!(queue().iter().find(|q| mine(q)).unwrap().started.is_some());
+        drop(waiting);
+        assert!(!queue().iter().any(mine));
+    }
+
+    #[test]
+    fn test_panic_message() {
+        assert_eq!(panic_message(Box::new("static")), "static");
+        assert_eq!(panic_message(Box::new(String::from("owned"))), "owned");
+        assert_eq!(panic_message(Box::new(5)), "unknown panic payload");
+    }
+
+    #[test]
+    fn test_cancel_guard_removes_entry() {
+        let user = UserId::new(99);
+        let guard = CancelGuard::register(user);
+        crate::CANCEL_FLAGS.get(&user).unwrap().store(true, Ordering::SeqCst);
+        assert!(guard.is_cancelled());
+        drop(guard);
+        assert!(!crate::CANCEL_FLAGS.contains_key(&user));
+    }
+}
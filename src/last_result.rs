@@ -0,0 +1,158 @@
+// src/last_result.rs
+
+use crate::icc;
+use crate::image_processing;
+use crate::jobs::{self, Job};
+use crate::responses;
+use catppuccin::FlavorName;
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
+use serenity::builder::{CreateAttachment, CreateMessage};
+use serenity::model::channel::Message;
+use serenity::model::id::UserId;
+use serenity::prelude::Context;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::info;
+
+/// How long a result stays around for `!cat again` and `!cat compare last`
+const KEEP_FOR: Duration = Duration::from_secs(30 * 60);
+
+/// A finished conversion: the image it started from and what came out
+#[derive(Clone)]
+pub struct LastResult {
+    pub source: Arc<Vec<u8>>,
+    pub flavor: FlavorName,
+    pub algorithm: &'static str,
+    pub output: Arc<Vec<u8>>,
+    at: Instant,
+}
+
+/// A member's latest result and the one before it
+struct Results {
+    current: LastResult,
+    previous: Option<LastResult>,
+}
+
+// Images are held in memory, so only the last two per member and only for a while
+static RESULTS: Lazy<DashMap<UserId, Results>> = Lazy::new(DashMap::new);
+
+fn is_fresh(result: &LastResult) -> bool {
+    result.at.elapsed() < KEEP_FOR
+}
+
+/// Keep a finished conversion as the member's current result; the one it replaces becomes the previous
+pub fn remember(user_id: UserId, source: Arc<Vec<u8>>, flavor: FlavorName, algorithm: &'static str, output: Vec<u8>) {
+    // Anyone else's expired results go at the same time, so nothing is held much past `KEEP_FOR`
+    RESULTS.retain(|_, results| is_fresh(&results.current));
+    let current = LastResult { source, flavor, algorithm, output: Arc::new(output), at: Instant::now() };
+    let previous = RESULTS.remove(&user_id).map(|(_, results)| results.current);
+    RESULTS.insert(user_id, Results { current, previous });
+}
+
+/// The member's latest result, if it hasn't expired
+pub fn current(user_id: UserId) -> Option<LastResult> {
+    RESULTS.get(&user_id).map(|r| r.current.clone()).filter(is_fresh)
+}
+
+/// The member's previous and latest results, if both are still kept
+pub fn previous_and_current(user_id: UserId) -> Option<(LastResult, LastResult)> {
+    let results = RESULTS.get(&user_id)?;
+    let previous = results.previous.clone().filter(is_fresh)?;
+    Some((previous, results.current.clone()))
+}
+
+fn encode_png(img: &image::DynamicImage) -> Vec<u8> {
+    let mut buffer = std::io::Cursor::new(Vec::new());
+    // Encoding an in-memory PNG can't fail
+    img.write_to(&mut buffer, image::ImageFormat::Png).unwrap();
+    buffer.into_inner()
+}
+
+/// `!cat again <flavor> [algorithm]`: run the member's last source image again with another flavor.
+/// The algorithm stays the same unless one is given.
+pub async fn again(ctx: &Context, msg: &Message, parts: &[&str]) {
+    let Some(last) = current(msg.author.id) else {
+        let text = format!("Nothing to redo. Catppuccinify an image first; it's kept for {} minutes.", KEEP_FOR.as_secs() / 60);
+        let _ = responses::warning(&ctx.http, msg.channel_id, text).await;
+        return;
+    };
+    let Some(flavor) = parts.iter().find_map(|s| crate::utils::parse_flavor(s)) else {
+        let _ = responses::warning(&ctx.http, msg.channel_id, "Usage: `!cat again <flavor> [algorithm]`. Example: `!cat again frappe`").await;
+        return;
+    };
+    let algorithm = parts.iter().find_map(|s| crate::utils::parse_algorithm(s)).unwrap_or(last.algorithm);
+    let _typing = msg.channel_id.start_typing(&ctx.http);
+    let source = Arc::clone(&last.source);
+    let result = jobs::run(Job::new("again", msg), move || {
+        let img = icc::decode(&source).map_err(|e| format!("Failed to decode image: {e}"))?;
+        Ok::<_, String>(icc::tag_srgb_png(encode_png(&image_processing::process_image_with_palette(&img, flavor, algorithm))))
+    })
+    .await;
+    let output = match result {
+        Ok(Ok(output)) => output,
+        Ok(Err(e)) => {
+            let _ = responses::error(&ctx.http, msg.channel_id, e).await;
+            return;
+        }
+        Err(panic) => {
+            let _ = responses::error(&ctx.http, msg.channel_id, panic.to_string()).await;
+            return;
+        }
+    };
+    info!(user = %msg.author.id, from = last.flavor.identifier(), to = flavor.identifier(), "Re-processing last image");
+    let attachment = CreateAttachment::bytes(output.clone(), format!("catppuccinified_{}.png", flavor.identifier()));
+    let content = format!("Here's your last image again (Flavor: {})! `!cat compare last` shows it beside the {} one.", flavor.to_string().to_uppercase(), last.flavor);
+    if msg.channel_id.send_files(&ctx.http, vec![attachment], CreateMessage::new().content(content)).await.is_ok() {
+        remember(msg.author.id, last.source, flavor, algorithm, output);
+    }
+}
+
+/// `!cat compare last`: the member's previous result beside their latest one
+pub async fn compare_last(ctx: &Context, msg: &Message) {
+    let Some((previous, current)) = previous_and_current(msg.author.id) else {
+        let text = "There's no earlier result to compare with. Try `!cat again <flavor>` after a conversion, then compare.";
+        let _ = responses::warning(&ctx.http, msg.channel_id, text).await;
+        return;
+    };
+    let result = jobs::run(Job::new("compare", msg), move || {
+        let decode = |bytes: &[u8]| image::load_from_memory(bytes).map(|img| img.to_rgba8());
+        let (before, after) = (decode(&previous.output)?, decode(&current.output)?);
+        let comparison = image_processing::create_comparison_image(&before, &after);
+        Ok::<_, image::ImageError>(encode_png(&image::DynamicImage::ImageRgba8(comparison)))
+    })
+    .await;
+    let comparison = match result {
+        Ok(Ok(comparison)) => comparison,
+        Ok(Err(_)) => {
+            let _ = responses::error(&ctx.http, msg.channel_id, "Failed to read one of the results back.").await;
+            return;
+        }
+        Err(panic) => {
+            let _ = responses::error(&ctx.http, msg.channel_id, panic.to_string()).await;
+            return;
+        }
+    };
+    let attachment = CreateAttachment::bytes(comparison, "comparison_last.png");
+    let content = format!("**Previous/Current Comparison**\nLeft: {} | Right: {}", previous.flavor.to_string().to_uppercase(), current.flavor.to_string().to_uppercase());
+    let _ = msg.channel_id.send_files(&ctx.http, vec![attachment], CreateMessage::new().content(content)).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remember_keeps_previous() {
+        let user = UserId::new(9_000_001);
+        let source = Arc::new(vec![1, 2, 3]);
+        assert!(current(user).is_none());
+        remember(user, Arc::clone(&source), FlavorName::Latte, "shepards-method", vec![4]);
+        assert!(previous_and_current(user).is_none());
+        remember(user, source, FlavorName::Frappe, "nearest-neighbor", vec![5]);
+        let (previous, latest) = previous_and_current(user).unwrap();
+        assert_eq!((previous.flavor, previous.output.as_slice()), (FlavorName::Latte, &[4][..]));
+        assert_eq!((latest.flavor, latest.algorithm), (FlavorName::Frappe, "nearest-neighbor"));
+        assert_eq!(*current(user).unwrap().source, vec![1, 2, 3]);
+    }
+}
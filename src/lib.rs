@@ -0,0 +1,62 @@
+#![allow(deprecated)]
+// src/lib.rs
+
+//! The bot's modules, shared by the binary in `main.rs` and the benchmarks in `benches/`
+
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
+use serenity::model::id::UserId;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+
+static IMAGE_PROCESSING_SEMAPHORE: Lazy<Semaphore> = Lazy::new(|| Semaphore::const_new(2));
+pub static CANCEL_FLAGS: Lazy<DashMap<UserId, Arc<std::sync::atomic::AtomicBool>>> = Lazy::new(DashMap::new);
+
+pub mod accessibility;
+pub mod admin;
+pub mod archive;
+pub mod audit;
+pub mod autochannel;
+pub mod bench;
+pub mod codecs;
+pub mod commands;
+pub mod config;
+pub mod convert;
+pub mod custom_palettes;
+pub mod emoji_retheme;
+pub mod error;
+pub mod eta;
+pub mod export;
+pub mod favorites;
+pub mod hdr;
+pub mod history;
+pub mod icc;
+pub mod icons;
+pub mod image_processing;
+pub mod jobs;
+pub mod last_result;
+pub mod limits;
+pub mod memory;
+pub mod mockups;
+pub mod outbox;
+pub mod pagination;
+pub mod palette;
+pub mod pdf;
+pub mod policy;
+pub mod progress;
+pub mod quiz;
+pub mod registry;
+pub mod responses;
+pub mod role_colors;
+pub mod schedule;
+pub mod screenshot;
+pub mod setup;
+pub mod source;
+pub mod svg;
+pub mod text_rendering;
+pub mod theme_file;
+pub mod timing;
+pub mod utils;
+pub mod video;
+pub mod watermark;
+pub mod workdir;
@@ -0,0 +1,150 @@
+// src/limits.rs
+
+use crate::config;
+use crate::error::BotError;
+use crate::image_processing;
+use crate::responses;
+use crate::setup;
+use image::imageops::FilterType;
+use image::DynamicImage;
+use serenity::model::channel::Message;
+use serenity::model::id::GuildId;
+use serenity::prelude::Context;
+use tracing::{info, warn};
+
+/// How far over the pixel budget an input can be and still be shrunk to fit without `--autoscale`
+const DOWNSCALE_SLACK: f64 = 1.5;
+
+fn megapixels(pixels: u64) -> String {
+    format!("{:.1} MP", pixels as f64 / 1_000_000.0)
+}
+
+/// Check `frames` frames of `width`x`height` against a pixel budget: `Ok(None)` when they fit, `Ok(Some(size))`
+/// with a smaller frame size when they should be shrunk (any amount over with `autoscale`, slightly over
+/// without), and the reply to send when they're too large to process
+fn fit(width: u32, height: u32, frames: u64, budget: u64, what: &str, autoscale: bool) -> Result<Option<(u32, u32)>, BotError> {
+    let pixels = width as u64 * height as u64 * frames.max(1);
+    if pixels <= budget {
+        return Ok(None);
+    }
+    if !autoscale && pixels as f64 > budget as f64 * DOWNSCALE_SLACK {
+        return Err(BotError::TooLarge(format!(
+            "That {} is too large to process: {}, and the limit is {}. Try a smaller version, or add `--autoscale` to shrink it to fit.",
+            what,
+            megapixels(pixels),
+            megapixels(budget)
+        )));
+    }
+    let scale = (budget as f64 / pixels as f64).sqrt();
+    Ok(Some((((width as f64 * scale) as u32).max(1), ((height as f64 * scale) as u32).max(1))))
+}
+
+/// Whether oversized inputs are shrunk to fit: asked for with `--autoscale`, or the server's setting
+pub fn autoscale(guild_id: Option<GuildId>, requested: bool) -> bool {
+    requested || guild_id.is_some_and(|guild_id| config::guild(guild_id).autoscale)
+}
+
+/// Check a still image against `MAX_IMAGE_PIXELS`
+pub fn still(width: u32, height: u32, autoscale: bool) -> Result<Option<(u32, u32)>, BotError> {
+    fit(width, height, 1, config::get().max_image_pixels, "image", autoscale)
+}
+
+/// Check a GIF against `MAX_GIF_PIXELS`, counted across all of its frames; the size is per frame
+pub fn gif(width: u32, height: u32, frames: usize, autoscale: bool) -> Result<Option<(u32, u32)>, BotError> {
+    fit(width, height, frames as u64, config::get().max_gif_pixels, "GIF", autoscale)
+}
+
+/// The note added to a reply when an input was shrunk to fit
+pub fn downscale_note(from: (u32, u32), to: (u32, u32)) -> String {
+    format!("Shrunk from {}×{} to {}×{} to fit the size limit.", from.0, from.1, to.0, to.1)
+}
+
+/// Shrink a still image over the budget to fit, with a note saying so for the reply
+pub fn fit_still(img: DynamicImage, autoscale: bool) -> Result<(DynamicImage, Option<String>), BotError> {
+    let (width, height) = (img.width(), img.height());
+    match still(width, height, autoscale)? {
+        None => Ok((img, None)),
+        Some((new_width, new_height)) => {
+            info!(width, height, new_width, new_height, "Downscaling image to fit the pixel budget");
+            Ok((img.resize_exact(new_width, new_height, FilterType::Lanczos3), Some(downscale_note((width, height), (new_width, new_height)))))
+        }
+    }
+}
+
+/// Shrink a GIF over the budget to fit: `None` when it already fits, otherwise the smaller GIF and a
+/// note for the reply
+pub fn fit_gif(gif_bytes: &[u8], autoscale: bool) -> Result<Option<(Vec<u8>, String)>, BotError> {
+    let (width, height, frames) = image_processing::gif_size(gif_bytes).map_err(BotError::Decode)?;
+    match gif(width, height, frames, autoscale)? {
+        None => Ok(None),
+        Some((new_width, new_height)) => {
+            info!(width, height, frames, new_width, new_height, "Downscaling GIF to fit the pixel budget");
+            let resized = image_processing::resize_gif(gif_bytes, new_width, new_height).map_err(BotError::Decode)?;
+            Ok(Some((resized, downscale_note((width, height), (new_width, new_height)))))
+        }
+    }
+}
+
+/// `!cat config autoscale [on|off]`; `parts` are the words after `autoscale`. Without a mode it shows
+/// the current setting.
+pub async fn configure(ctx: &Context, msg: &Message, parts: &[&str]) {
+    let Some(guild_id) = msg.guild_id else {
+        let _ = responses::warning(&ctx.http, msg.channel_id, "Autoscaling is a server setting; run this in a server. `--autoscale` works anywhere.").await;
+        return;
+    };
+    let on = parts.iter().any(|p| p.eq_ignore_ascii_case("on"));
+    if !on && !parts.iter().any(|p| p.eq_ignore_ascii_case("off")) {
+        let text = if config::guild(guild_id).autoscale {
+            "**Autoscale:** on. Images over the size limit are shrunk to fit."
+        } else {
+            "**Autoscale:** off. Images well over the size limit are refused unless the command has `--autoscale`."
+        };
+        let _ = responses::info(&ctx.http, msg.channel_id, text).await;
+        return;
+    }
+    if !setup::can_manage(&ctx.cache, msg) {
+        let _ = responses::warning(&ctx.http, msg.channel_id, "You need the **Manage Server** permission to change autoscaling.").await;
+        return;
+    }
+    match config::update_guild(guild_id, |settings| settings.autoscale = on) {
+        Ok(_) => {
+            info!(guild = %guild_id, on, "Autoscale changed");
+            let text = if on {
+                "Images over the size limit will be shrunk to fit, with a note in the reply."
+            } else {
+                "Images well over the size limit will be refused again, unless the command has `--autoscale`."
+            };
+            let _ = responses::success(&ctx.http, msg.channel_id, text).await;
+        }
+        Err(e) => {
+            warn!(error = %e, guild = %guild_id, "Failed to save autoscale setting");
+            let _ = responses::error(&ctx.http, msg.channel_id, "The change applies for now, but couldn't be saved and will be lost on restart.").await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fit() {
+        let budget = 4096 * 4096;
+        assert_eq!(fit(4000, 4000, 1, budget, "image", false), Ok(None));
+        // A long panorama is judged by its area, not its longest side
+        assert_eq!(fit(8000, 200, 1, budget, "image", false), Ok(None));
+        let (width, height) = fit(4600, 4600, 1, budget, "image", false).unwrap().unwrap();
+        assert!(width as u64 * height as u64 <= budget && width == height && width > 4000);
+        assert!(fit(6000, 6000, 1, budget, "image", false).is_err());
+        assert_eq!(fit(100, 100, 50, 1_000_000, "GIF", false), Ok(None));
+        assert!(fit(100, 100, 200, 1_000_000, "GIF", false).is_err());
+    }
+
+    #[test]
+    fn test_fit_with_autoscale() {
+        let (width, height) = fit(12000, 9000, 1, 4096 * 4096, "image", true).unwrap().unwrap();
+        assert!(width as u64 * height as u64 <= 4096 * 4096);
+        assert_eq!((width as f32 / height as f32 * 3.0).round(), 4.0);
+        assert_eq!(fit(100, 100, 200, 1_000_000, "GIF", true), Ok(Some((70, 70))));
+    }
+}
@@ -0,0 +1,153 @@
+// src/memory.rs
+
+//! Approximate memory held by in-flight jobs. Large jobs reserve their estimate before they start,
+//! and wait while the total would go over `MAX_JOB_MEMORY_MB` rather than risk the bot being killed.
+
+use crate::config;
+use crate::outbox::Outbox;
+use crate::responses;
+use once_cell::sync::Lazy;
+use serenity::model::id::ChannelId;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use tokio::sync::Notify;
+use tracing::info;
+
+/// A full 256³ RGB lookup table
+pub const LUT_BYTES: u64 = 256 * 256 * 256 * 3;
+
+static RESERVED: AtomicU64 = AtomicU64::new(0);
+static WAITING: AtomicUsize = AtomicUsize::new(0);
+static FREED: Lazy<Notify> = Lazy::new(Notify::new);
+
+/// Decoded RGBA, the working copy, and the encoded output, plus a LUT
+pub fn still_estimate(width: u32, height: u32) -> u64 {
+    width as u64 * height as u64 * 4 * 3 + LUT_BYTES
+}
+
+/// Every frame decoded and re-encoded, plus a LUT
+pub fn gif_estimate(width: u32, height: u32, frames: usize) -> u64 {
+    width as u64 * height as u64 * frames.max(1) as u64 * 4 * 2 + LUT_BYTES
+}
+
+/// Bytes reserved by running jobs
+pub fn reserved() -> u64 {
+    RESERVED.load(Ordering::SeqCst)
+}
+
+/// Jobs waiting for memory to free up
+pub fn waiting() -> usize {
+    WAITING.load(Ordering::SeqCst)
+}
+
+/// A job's share of the memory budget, given back when dropped
+#[derive(Debug)]
+pub struct Reservation {
+    bytes: u64,
+}
+
+impl Reservation {
+    pub fn bytes(&self) -> u64 {
+        self.bytes
+    }
+}
+
+impl Drop for Reservation {
+    fn drop(&mut self) {
+        RESERVED.fetch_sub(self.bytes, Ordering::SeqCst);
+        FREED.notify_waiters();
+    }
+}
+
+/// Reserve `bytes` if they fit within `budget` alongside running jobs. A job larger than the whole
+/// budget is let through once nothing else is running, so it waits instead of never starting.
+pub fn try_reserve(bytes: u64, budget: u64) -> Option<Reservation> {
+    RESERVED
+        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |reserved| {
+            (reserved == 0 || reserved + bytes <= budget).then_some(reserved + bytes)
+        })
+        .ok()
+        .map(|_| Reservation { bytes })
+}
+
+// A job's place in the `WAITING` count, given up however the wait ends, including when the
+// request is dropped before any memory frees up
+struct Waiter;
+
+impl Waiter {
+    fn enter() -> Self {
+        WAITING.fetch_add(1, Ordering::SeqCst);
+        Waiter
+    }
+}
+
+impl Drop for Waiter {
+    fn drop(&mut self) {
+        WAITING.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Reserve `bytes`, waiting for running jobs to finish while they don't fit
+pub async fn reserve(bytes: u64, budget: u64) -> Reservation {
+    let _waiter = Waiter::enter();
+    loop {
+        // Registered before trying, so a job finishing in between still wakes this one
+        let freed = FREED.notified();
+        if let Some(reservation) = try_reserve(bytes, budget) {
+            return reservation;
+        }
+        freed.await;
+    }
+}
+
+/// Reserve memory for a job against `MAX_JOB_MEMORY_MB`. When the bot is too busy, the channel is
+/// told the job is queued, and this returns once it can start.
+pub async fn admit(outbox: impl Outbox, channel_id: ChannelId, bytes: u64) -> Reservation {
+    let budget = config::get().max_job_memory;
+    if let Some(reservation) = try_reserve(bytes, budget) {
+        return reservation;
+    }
+    info!(bytes, reserved = reserved(), budget, "Queueing job until memory frees up");
+    let _ = responses::info(&outbox, channel_id, "⏳ The bot is busy with other large jobs, so yours is queued. It will start as soon as there's room.").await;
+    reserve(bytes, budget).await
+}
+
+fn megabytes(bytes: u64) -> u64 {
+    bytes.div_ceil(1024 * 1024)
+}
+
+/// A line for `!cat admin queue`
+pub fn describe() -> String {
+    format!("**Memory:** {} MB of {} MB reserved, {} waiting", megabytes(reserved()), megabytes(config::get().max_job_memory), waiting())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_estimates() {
+        assert_eq!(still_estimate(100, 100), 120_000 + LUT_BYTES);
+        assert_eq!(gif_estimate(100, 100, 10), 800_000 + LUT_BYTES);
+        // A GIF without frame info still counts as one frame
+        assert_eq!(gif_estimate(100, 100, 0), gif_estimate(100, 100, 1));
+    }
+
+    #[tokio::test]
+    async fn test_reservations_wait_for_room() {
+        // Other tests in this process may hold reservations, so the budget starts from what's reserved
+        let budget = reserved() + 1000;
+        let first = try_reserve(800, budget).unwrap();
+        assert!(try_reserve(300, budget).is_none());
+        let waiter = tokio::spawn(reserve(300, budget));
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!waiter.is_finished());
+        drop(first);
+        let second = tokio::time::timeout(Duration::from_secs(1), waiter).await.unwrap().unwrap();
+        assert_eq!(second.bytes(), 300);
+        // A wait given up before memory frees up, as when the request's task is dropped, stops counting
+        let before = waiting();
+        assert!(tokio::time::timeout(Duration::from_millis(20), reserve(800, budget)).await.is_err());
+        assert_eq!(waiting(), before);
+    }
+}
@@ -0,0 +1,433 @@
+// src/mockups.rs
+
+use crate::text_rendering::{draw_text, fill_rect, line_height, measure_text};
+use catppuccin::FlavorName;
+use image::{Rgba, RgbaImage};
+
+/// Which kind of fake app window `!cat preview` renders
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PreviewKind {
+    Code,
+    Terminal,
+    Editor,
+}
+
+const FONT_SIZE: f32 = 18.0;
+const TITLE_BAR_HEIGHT: u32 = 36;
+const WINDOW_RADIUS: u32 = 12;
+const BACKDROP_PADDING: u32 = 56;
+
+const CODE_SNIPPET: &[&str] = &[
+    "// Brew a cup for every flavor",
+    "use std::collections::HashMap;",
+    "",
+    "#[derive(Debug, Clone)]",
+    "pub struct Flavor {",
+    "    name: String,",
+    "    dark: bool,",
+    "}",
+    "",
+    "fn main() {",
+    "    let mut cups = HashMap::new();",
+    "    cups.insert(\"mocha\", 4);",
+    "    for (name, count) in &cups {",
+    "        println!(\"{name}: {count} cups\");",
+    "    }",
+    "    let total: u32 = cups.values().sum();",
+    "    assert_eq!(total, 4, \"need more coffee\");",
+    "}",
+];
+
+const KEYWORDS: &[&str] = &[
+    "as", "const", "else", "enum", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod",
+    "mut", "pub", "return", "self", "Self", "struct", "true", "use", "where", "while",
+];
+
+fn rgb(name: &str, flavor: FlavorName) -> (u8, u8, u8) {
+    crate::utils::catppuccin_color_name_to_rgb(name, flavor).unwrap()
+}
+
+fn rgba(name: &str, flavor: FlavorName) -> Rgba<u8> {
+    let (r, g, b) = rgb(name, flavor);
+    Rgba([r, g, b, 255])
+}
+
+/// Split a line of Rust-like code into (text, palette color name) spans, following
+/// the Catppuccin style guide: keywords mauve, functions blue, strings green, etc.
+pub fn highlight_line(line: &str) -> Vec<(String, &'static str)> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut spans: Vec<(String, &'static str)> = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        let role = if c == '/' && chars.get(i + 1) == Some(&'/') {
+            i = chars.len();
+            "overlay2"
+        } else if c == '"' {
+            i += 1;
+            while i < chars.len() && chars[i] != '"' {
+                i += 1;
+            }
+            i = (i + 1).min(chars.len());
+            "green"
+        } else if c == '#' && chars.get(i + 1) == Some(&'[') {
+            while i < chars.len() && chars[i] != ']' {
+                i += 1;
+            }
+            i = (i + 1).min(chars.len());
+            "yellow"
+        } else if c.is_ascii_digit() {
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.' || chars[i] == '_') {
+                i += 1;
+            }
+            "peach"
+        } else if c.is_alphabetic() || c == '_' {
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            match chars.get(i) {
+                Some('!') => {
+                    i += 1;
+                    "teal"
+                }
+                Some('(') => "blue",
+                _ if KEYWORDS.contains(&word.as_str()) => "mauve",
+                _ if c.is_uppercase() => "yellow",
+                _ => "text",
+            }
+        } else if c.is_whitespace() {
+            while i < chars.len() && chars[i].is_whitespace() {
+                i += 1;
+            }
+            "text"
+        } else {
+            i += 1;
+            if "=+-*/<>&|:!".contains(c) { "sky" } else { "overlay2" }
+        };
+        spans.push((chars[start..i].iter().collect(), role));
+    }
+    spans
+}
+
+/// Draw colored spans left to right starting at (x, y); returns the x after the last span
+fn draw_spans(img: &mut RgbaImage, spans: &[(String, &str)], x: i32, y: i32, flavor: FlavorName) -> i32 {
+    let mut caret = x;
+    for (text, role) in spans {
+        draw_text(img, text, caret, y, FONT_SIZE, rgb(role, flavor));
+        caret += measure_text(text, FONT_SIZE).0 as i32;
+    }
+    caret
+}
+
+/// An empty app window: base body, mantle title bar with traffic-light buttons and a centered title
+pub fn window_chrome(width: u32, height: u32, title: &str, flavor: FlavorName) -> RgbaImage {
+    let mut window = RgbaImage::from_pixel(width, height, rgba("base", flavor));
+    fill_rect(&mut window, 0, 0, width, TITLE_BAR_HEIGHT, rgba("mantle", flavor));
+    let r = 6i32;
+    let cy = (TITLE_BAR_HEIGHT / 2) as i32;
+    for (i, name) in ["red", "yellow", "green"].iter().enumerate() {
+        let cx = 20 + i as i32 * 22;
+        let color = rgba(name, flavor);
+        for y in cy - r..=cy + r {
+            for x in cx - r..=cx + r {
+                if (x - cx).pow(2) + (y - cy).pow(2) <= r * r {
+                    window.put_pixel(x as u32, y as u32, color);
+                }
+            }
+        }
+    }
+    let (title_w, title_h) = measure_text(title, 15.0);
+    draw_text(&mut window, title, (width as i32 - title_w as i32) / 2, (TITLE_BAR_HEIGHT as i32 - title_h as i32) / 2, 15.0, rgb("subtext0", flavor));
+    window
+}
+
+/// Round a window's corners, give it a drop shadow, and draw it with its top-left corner at (x, y)
+pub fn place_window(canvas: &mut RgbaImage, window: RgbaImage, x: i64, y: i64, flavor: FlavorName) {
+    use crate::image_processing::{apply_post_effects, PostEffect};
+    let (w, _) = window.dimensions();
+    let framed = apply_post_effects(window, &[PostEffect::Rounded(WINDOW_RADIUS), PostEffect::Shadow], flavor);
+    // The shadow pads the window by the same margin on every side
+    let margin = ((framed.width() - w) / 2) as i64;
+    image::imageops::overlay(canvas, &framed, x - margin, y - margin);
+}
+
+fn on_backdrop(window: RgbaImage, flavor: FlavorName) -> RgbaImage {
+    let (w, h) = window.dimensions();
+    let mut canvas = RgbaImage::from_pixel(w + BACKDROP_PADDING * 2, h + BACKDROP_PADDING * 2, rgba("crust", flavor));
+    place_window(&mut canvas, window, BACKDROP_PADDING as i64, BACKDROP_PADDING as i64, flavor);
+    canvas
+}
+
+fn render_code_window(flavor: FlavorName) -> RgbaImage {
+    let lh = line_height(FONT_SIZE);
+    let padding = 24;
+    let gutter = 44;
+    let width = 760;
+    let height = TITLE_BAR_HEIGHT + padding * 2 + CODE_SNIPPET.len() as u32 * lh;
+    let mut window = window_chrome(width, height, "flavor.rs", flavor);
+    for (i, line) in CODE_SNIPPET.iter().enumerate() {
+        let y = (TITLE_BAR_HEIGHT + padding + i as u32 * lh) as i32;
+        let number = (i + 1).to_string();
+        let number_x = (padding + gutter - 16) as i32 - measure_text(&number, FONT_SIZE).0 as i32;
+        draw_text(&mut window, &number, number_x, y, FONT_SIZE, rgb("overlay1", flavor));
+        draw_spans(&mut window, &highlight_line(line), (padding + gutter) as i32, y, flavor);
+    }
+    window
+}
+
+fn render_terminal_window(flavor: FlavorName) -> RgbaImage {
+    let lh = line_height(FONT_SIZE) as i32;
+    let s = |text: &str, role: &'static str| (text.to_string(), role);
+    let prompt = |command: &str| vec![s("~/catppuccin ", "blue"), s("main ", "pink"), s("❯ ", "mauve"), s(command, "text")];
+    let lines: Vec<Vec<(String, &str)>> = vec![
+        prompt("ls"),
+        vec![s("assets/  src/  ", "blue"), s("Cargo.toml  README.md  ", "text"), s("build.sh", "green")],
+        prompt("git status --short"),
+        vec![s(" M ", "yellow"), s("src/main.rs", "text")],
+        vec![s("?? ", "red"), s("palette.json", "text")],
+        prompt("cargo build --release"),
+        vec![s("   Compiling ", "green"), s("catppuccin v2.5.1", "text")],
+        vec![s("warning", "yellow"), s(": unused variable: `latte`", "text")],
+        vec![s("    Finished ", "green"), s("release [optimized] target(s) in 4.20s", "text")],
+        prompt("echo $FLAVOR"),
+        vec![s(&flavor.to_string().to_lowercase(), "text")],
+        prompt(""),
+    ];
+    let padding = 24;
+    let swatch = 28;
+    let width = 760;
+    let height = TITLE_BAR_HEIGHT as i32 + padding * 3 + lines.len() as i32 * lh + swatch * 2 + 8;
+    let mut window = window_chrome(width, height as u32, "zsh", flavor);
+    let mut y = TITLE_BAR_HEIGHT as i32 + padding;
+    for (i, spans) in lines.iter().enumerate() {
+        let end = draw_spans(&mut window, spans, padding, y, flavor);
+        if i == lines.len() - 1 {
+            // Block cursor after the last prompt
+            fill_rect(&mut window, end, y + 2, 10, lh as u32 - 4, rgba("rosewater", flavor));
+        }
+        y += lh;
+    }
+    // The 16 terminal colors, normal and bright rows
+    let ansi = [
+        ["surface1", "red", "green", "yellow", "blue", "pink", "teal", "subtext1"],
+        ["surface2", "red", "green", "yellow", "blue", "pink", "teal", "subtext0"],
+    ];
+    y += padding;
+    for (row, names) in ansi.iter().enumerate() {
+        for (col, name) in names.iter().enumerate() {
+            let x = padding + col as i32 * (swatch + 8);
+            fill_rect(&mut window, x, y + row as i32 * (swatch + 8), swatch as u32, swatch as u32, rgba(name, flavor));
+        }
+    }
+    window
+}
+
+fn render_editor_window(flavor: FlavorName) -> RgbaImage {
+    let lh = line_height(FONT_SIZE);
+    let width = 980;
+    let sidebar = 210;
+    let tab_bar = 34;
+    let status_bar = 30;
+    let padding = 16;
+    let gutter = 44;
+    let cursor_line = 13;
+    let height = TITLE_BAR_HEIGHT + tab_bar + padding * 2 + CODE_SNIPPET.len() as u32 * lh + status_bar;
+    let mut window = window_chrome(width, height, "catppuccin — flavor.rs", flavor);
+    let top = TITLE_BAR_HEIGHT;
+    let body_bottom = height - status_bar;
+
+    // File tree
+    fill_rect(&mut window, 0, top as i32, sidebar, body_bottom - top, rgba("mantle", flavor));
+    let tree = [
+        ("▾ catppuccin", "text", 0),
+        ("▾ src", "blue", 1),
+        ("flavor.rs", "peach", 2),
+        ("main.rs", "peach", 2),
+        ("palette.rs", "peach", 2),
+        ("▸ assets", "blue", 1),
+        ("Cargo.toml", "yellow", 1),
+        ("README.md", "sky", 1),
+    ];
+    for (i, (name, role, depth)) in tree.iter().enumerate() {
+        let y = (top + padding + i as u32 * lh) as i32;
+        if i == 2 {
+            fill_rect(&mut window, 0, y - 2, sidebar, lh, rgba("surface0", flavor));
+        }
+        draw_text(&mut window, name, 14 + depth * 16, y, FONT_SIZE - 2.0, rgb(role, flavor));
+    }
+
+    // Tabs
+    let tabs_x = sidebar as i32;
+    fill_rect(&mut window, tabs_x, top as i32, width - sidebar, tab_bar, rgba("crust", flavor));
+    let mut tab_x = tabs_x;
+    for (i, name) in ["flavor.rs", "main.rs"].iter().enumerate() {
+        let tab_w = measure_text(name, 15.0).0 as i32 + 40;
+        if i == 0 {
+            fill_rect(&mut window, tab_x, top as i32, tab_w as u32, tab_bar, rgba("base", flavor));
+            fill_rect(&mut window, tab_x, top as i32, tab_w as u32, 2, rgba("mauve", flavor));
+        }
+        let color = if i == 0 { rgb("text", flavor) } else { rgb("overlay1", flavor) };
+        draw_text(&mut window, name, tab_x + 20, top as i32 + 8, 15.0, color);
+        tab_x += tab_w;
+    }
+
+    // Code with line numbers and the cursor line highlighted
+    let code_x = sidebar as i32 + padding as i32;
+    for (i, line) in CODE_SNIPPET.iter().enumerate() {
+        let y = (top + tab_bar + padding + i as u32 * lh) as i32;
+        let number_color = if i == cursor_line {
+            fill_rect(&mut window, sidebar as i32, y - 2, width - sidebar, lh, rgba("surface0", flavor));
+            rgb("lavender", flavor)
+        } else {
+            rgb("overlay1", flavor)
+        };
+        let number = (i + 1).to_string();
+        let number_x = code_x + gutter - 16 - measure_text(&number, FONT_SIZE).0 as i32;
+        draw_text(&mut window, &number, number_x, y, FONT_SIZE, number_color);
+        let end = draw_spans(&mut window, &highlight_line(line), code_x + gutter, y, flavor);
+        if i == cursor_line {
+            fill_rect(&mut window, end + 2, y, 2, lh - 4, rgba("rosewater", flavor));
+        }
+    }
+
+    // Status line: mode pill, branch, file, and position
+    let sy = body_bottom as i32;
+    fill_rect(&mut window, 0, sy, width, status_bar, rgba("mantle", flavor));
+    let mode_w = measure_text("NORMAL", 14.0).0 + 24;
+    fill_rect(&mut window, 0, sy, mode_w, status_bar, rgba("blue", flavor));
+    draw_text(&mut window, "NORMAL", 12, sy + 7, 14.0, rgb("crust", flavor));
+    fill_rect(&mut window, mode_w as i32, sy, 90, status_bar, rgba("surface0", flavor));
+    draw_text(&mut window, "main", mode_w as i32 + 14, sy + 7, 14.0, rgb("pink", flavor));
+    draw_text(&mut window, "flavor.rs", mode_w as i32 + 104, sy + 7, 14.0, rgb("subtext1", flavor));
+    let right = format!("utf-8   rust   {}:{}", cursor_line + 1, CODE_SNIPPET[cursor_line].chars().count() + 1);
+    let right_w = measure_text(&right, 14.0).0 as i32;
+    draw_text(&mut window, &right, width as i32 - right_w - 14, sy + 7, 14.0, rgb("subtext0", flavor));
+    window
+}
+
+/// Render a fake app window in the flavor, on a crust backdrop, so users can preview
+/// how a flavor looks in practice without installing anything
+pub fn render_theme_preview(flavor: FlavorName, kind: PreviewKind) -> RgbaImage {
+    let window = match kind {
+        PreviewKind::Code => render_code_window(flavor),
+        PreviewKind::Terminal => render_terminal_window(flavor),
+        PreviewKind::Editor => render_editor_window(flavor),
+    };
+    on_backdrop(window, flavor)
+}
+
+// Blend a color over a rectangle, clipped to the image
+fn blend_rect(img: &mut RgbaImage, x: i32, y: i32, w: u32, h: u32, color: (u8, u8, u8), opacity: f32) {
+    let x0 = x.max(0) as u32;
+    let y0 = y.max(0) as u32;
+    let x1 = ((x + w as i32).max(0) as u32).min(img.width());
+    let y1 = ((y + h as i32).max(0) as u32).min(img.height());
+    let rgb = [color.0, color.1, color.2];
+    for py in y0..y1 {
+        for px in x0..x1 {
+            let pixel = img.get_pixel_mut(px, py);
+            for ch in 0..3 {
+                pixel[ch] = (pixel[ch] as f32 * (1.0 - opacity) + rgb[ch] as f32 * opacity).round() as u8;
+            }
+        }
+    }
+}
+
+fn render_player_window(flavor: FlavorName) -> RgbaImage {
+    let (width, height) = (480, 200);
+    let mut window = window_chrome(width, height, "Now Playing", flavor);
+    let cover = 120;
+    let top = TITLE_BAR_HEIGHT as i32 + 22;
+    // Album cover: a diagonal mauve to blue gradient
+    let stops = crate::palette::evenly_spaced_stops(&[rgb("mauve", flavor), rgb("blue", flavor)]);
+    let art = crate::palette::generate_gradient_image(&stops, cover, cover, crate::palette::GradientDirection::Linear(45.0), "linear");
+    image::imageops::overlay(&mut window, &art, 22, top as i64);
+    let text_x = 22 + cover as i32 + 22;
+    draw_text(&mut window, "Soothing Pastels", text_x, top + 6, 20.0, rgb("text", flavor));
+    draw_text(&mut window, "The Catppuccins", text_x, top + 36, 16.0, rgb("subtext0", flavor));
+    let bar_w = width - text_x as u32 - 28;
+    fill_rect(&mut window, text_x, top + 84, bar_w, 6, rgba("surface1", flavor));
+    fill_rect(&mut window, text_x, top + 84, bar_w * 3 / 5, 6, rgba("mauve", flavor));
+    draw_text(&mut window, "2:14", text_x, top + 98, 14.0, rgb("overlay1", flavor));
+    let (total_w, _) = measure_text("3:42", 14.0);
+    draw_text(&mut window, "3:42", text_x + bar_w as i32 - total_w as i32, top + 98, 14.0, rgb("overlay1", flavor));
+    window
+}
+
+/// Compose a 1920×1080 "rice preview": the wallpaper behind a status bar, a terminal,
+/// a code window, and a music player, all in the flavor's colors
+pub fn compose_rice(wallpaper: &RgbaImage, flavor: FlavorName) -> RgbaImage {
+    let (width, height) = (1920, 1080);
+    let mut canvas = crate::image_processing::fit_to_size(wallpaper, width, height);
+
+    // Status bar: workspaces on the left, clock in the middle, status on the right
+    let bar_h = 44;
+    blend_rect(&mut canvas, 0, 0, width, bar_h, rgb("crust", flavor), 0.9);
+    for i in 0..5 {
+        let (pill_w, color) = if i == 0 { (36, "mauve") } else { (14, "surface2") };
+        let x = 24 + i * 26 + if i > 0 { 22 } else { 0 };
+        fill_rect(&mut canvas, x, (bar_h as i32 - 14) / 2, pill_w, 14, rgba(color, flavor));
+    }
+    let clock = "Mon 09:41";
+    let (clock_w, clock_h) = measure_text(clock, 18.0);
+    draw_text(&mut canvas, clock, (width as i32 - clock_w as i32) / 2, (bar_h as i32 - clock_h as i32) / 2, 18.0, rgb("text", flavor));
+    let mut status_x = width as i32 - 24;
+    for (label, color) in [("97%", "green"), ("wifi", "blue"), ("vol 80", "peach")] {
+        let (label_w, label_h) = measure_text(label, 16.0);
+        status_x -= label_w as i32;
+        draw_text(&mut canvas, label, status_x, (bar_h as i32 - label_h as i32) / 2, 16.0, rgb(color, flavor));
+        status_x -= 28;
+    }
+
+    let terminal = render_terminal_window(flavor);
+    let code = render_code_window(flavor);
+    let code_x = (width - code.width() - 96) as i64;
+    let code_y = (height - code.height() - 96) as i64;
+    place_window(&mut canvas, terminal, 96, bar_h as i64 + 56, flavor);
+    place_window(&mut canvas, code, code_x, code_y, flavor);
+    let player = render_player_window(flavor);
+    let player_y = (height - player.height() - 96) as i64;
+    place_window(&mut canvas, player, 120, player_y, flavor);
+    canvas
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highlight_line_roles() {
+        let spans = highlight_line("    let total: u32 = sum(\"cups\", 4); // done");
+        let role_of = |text: &str| spans.iter().find(|(t, _)| t == text).map(|(_, r)| *r);
+        assert_eq!(role_of("let"), Some("mauve"));
+        assert_eq!(role_of("sum"), Some("blue"));
+        assert_eq!(role_of("\"cups\""), Some("green"));
+        assert_eq!(role_of("4"), Some("peach"));
+        assert_eq!(role_of("// done"), Some("overlay2"));
+        // Spans cover the whole line
+        let joined: String = spans.iter().map(|(t, _)| t.as_str()).collect();
+        assert_eq!(joined, "    let total: u32 = sum(\"cups\", 4); // done");
+    }
+
+    #[test]
+    fn test_compose_rice_covers_wallpaper() {
+        let wallpaper = RgbaImage::from_pixel(64, 36, Rgba([10, 200, 30, 255]));
+        let rice = compose_rice(&wallpaper, FlavorName::Mocha);
+        assert_eq!(rice.dimensions(), (1920, 1080));
+        // Wallpaper still shows between the windows, and the bar is tinted crust
+        assert_eq!(rice.get_pixel(1900, 100).0, [10, 200, 30, 255]);
+        assert_ne!(rice.get_pixel(1900, 2).0, [10, 200, 30, 255]);
+    }
+
+    #[test]
+    fn test_render_theme_preview_uses_flavor() {
+        for kind in [PreviewKind::Code, PreviewKind::Terminal, PreviewKind::Editor] {
+            let img = render_theme_preview(FlavorName::Mocha, kind);
+            assert_eq!(img.get_pixel(0, 0), &rgba("crust", FlavorName::Mocha));
+            let center = img.get_pixel(img.width() / 2, img.height() - BACKDROP_PADDING - 50);
+            assert_ne!(center, &rgba("crust", FlavorName::Mocha));
+        }
+    }
+}
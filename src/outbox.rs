@@ -0,0 +1,80 @@
+// src/outbox.rs
+
+use serenity::async_trait;
+use serenity::builder::{CreateAttachment, CreateMessage, EditMessage};
+use serenity::http::Http;
+use serenity::model::channel::Message;
+use serenity::model::id::{ChannelId, MessageId};
+use serenity::prelude::Context;
+use std::sync::Arc;
+
+/// Where replies are sent. The bot sends through Discord's HTTP client; tests substitute a recorder
+/// so commands can run without a token.
+#[async_trait]
+pub trait Outbox: Send + Sync {
+    async fn send_message(&self, channel_id: ChannelId, builder: CreateMessage) -> serenity::Result<Message>;
+
+    async fn send_files(&self, channel_id: ChannelId, files: Vec<CreateAttachment>, builder: CreateMessage) -> serenity::Result<Message>;
+
+    async fn edit_message(&self, channel_id: ChannelId, message_id: MessageId, builder: EditMessage) -> serenity::Result<Message>;
+}
+
+#[async_trait]
+impl Outbox for Http {
+    async fn send_message(&self, channel_id: ChannelId, builder: CreateMessage) -> serenity::Result<Message> {
+        channel_id.send_message(self, builder).await
+    }
+
+    async fn send_files(&self, channel_id: ChannelId, files: Vec<CreateAttachment>, builder: CreateMessage) -> serenity::Result<Message> {
+        channel_id.send_files(self, files, builder).await
+    }
+
+    async fn edit_message(&self, channel_id: ChannelId, message_id: MessageId, builder: EditMessage) -> serenity::Result<Message> {
+        channel_id.edit_message(self, message_id, builder).await
+    }
+}
+
+#[async_trait]
+impl Outbox for Context {
+    async fn send_message(&self, channel_id: ChannelId, builder: CreateMessage) -> serenity::Result<Message> {
+        Outbox::send_message(&*self.http, channel_id, builder).await
+    }
+
+    async fn send_files(&self, channel_id: ChannelId, files: Vec<CreateAttachment>, builder: CreateMessage) -> serenity::Result<Message> {
+        Outbox::send_files(&*self.http, channel_id, files, builder).await
+    }
+
+    async fn edit_message(&self, channel_id: ChannelId, message_id: MessageId, builder: EditMessage) -> serenity::Result<Message> {
+        Outbox::edit_message(&*self.http, channel_id, message_id, builder).await
+    }
+}
+
+#[async_trait]
+impl<T: Outbox + ?Sized> Outbox for &T {
+    async fn send_message(&self, channel_id: ChannelId, builder: CreateMessage) -> serenity::Result<Message> {
+        (**self).send_message(channel_id, builder).await
+    }
+
+    async fn send_files(&self, channel_id: ChannelId, files: Vec<CreateAttachment>, builder: CreateMessage) -> serenity::Result<Message> {
+        (**self).send_files(channel_id, files, builder).await
+    }
+
+    async fn edit_message(&self, channel_id: ChannelId, message_id: MessageId, builder: EditMessage) -> serenity::Result<Message> {
+        (**self).edit_message(channel_id, message_id, builder).await
+    }
+}
+
+#[async_trait]
+impl<T: Outbox + ?Sized> Outbox for Arc<T> {
+    async fn send_message(&self, channel_id: ChannelId, builder: CreateMessage) -> serenity::Result<Message> {
+        (**self).send_message(channel_id, builder).await
+    }
+
+    async fn send_files(&self, channel_id: ChannelId, files: Vec<CreateAttachment>, builder: CreateMessage) -> serenity::Result<Message> {
+        (**self).send_files(channel_id, files, builder).await
+    }
+
+    async fn edit_message(&self, channel_id: ChannelId, message_id: MessageId, builder: EditMessage) -> serenity::Result<Message> {
+        (**self).edit_message(channel_id, message_id, builder).await
+    }
+}
@@ -0,0 +1,145 @@
+// src/pagination.rs
+
+use serenity::builder::{
+    CreateActionRow, CreateButton, CreateEmbed, CreateEmbedFooter, CreateInteractionResponse,
+    CreateInteractionResponseMessage, CreateMessage, CreateSelectMenu, CreateSelectMenuKind, CreateSelectMenuOption,
+};
+use serenity::http::Http;
+use serenity::model::application::{ButtonStyle, ComponentInteraction, ComponentInteractionDataKind};
+
+/// One page of a paged embed
+pub struct Page {
+    pub category: &'static str,
+    pub title: &'static str,
+    pub body: String,
+}
+
+/// Embed pages navigated with Previous/Next buttons and a category select menu.
+/// Stateless: the page being shown is encoded in each component's custom_id
+/// (`<key>:prev:<page>`, `<key>:next:<page>`, `<key>:category`), so old messages
+/// keep working across restarts and nothing has to be cleaned up.
+pub struct PagedEmbed {
+    key: &'static str,
+    pages: Vec<Page>,
+}
+
+impl PagedEmbed {
+    pub fn new(key: &'static str, pages: Vec<Page>) -> Self {
+        assert!(!pages.is_empty(), "A paged embed needs at least one page");
+        PagedEmbed { key, pages }
+    }
+
+    /// Each category in order of first appearance, with the index of its first page
+    pub fn categories(&self) -> Vec<(&'static str, usize)> {
+        let mut categories: Vec<(&'static str, usize)> = Vec::new();
+        for (i, page) in self.pages.iter().enumerate() {
+            if !categories.iter().any(|(name, _)| *name == page.category) {
+                categories.push((page.category, i));
+            }
+        }
+        categories
+    }
+
+    /// The themed embed for a page, with its position in the footer
+    pub fn embed(&self, index: usize) -> CreateEmbed {
+        let index = index.min(self.pages.len() - 1);
+        let page = &self.pages[index];
+        let footer = format!("{} • Page {}/{} • {}", page.category, index + 1, self.pages.len(), crate::responses::footer_text());
+        crate::responses::embed(crate::responses::Tone::Accent, page.body.clone())
+            .title(page.title)
+            .footer(CreateEmbedFooter::new(footer))
+    }
+
+    /// The category menu and Previous/Next buttons for a page
+    pub fn components(&self, index: usize) -> Vec<CreateActionRow> {
+        let current_category = self.pages[index.min(self.pages.len() - 1)].category;
+        let options = self
+            .categories()
+            .into_iter()
+            .map(|(name, first)| {
+                CreateSelectMenuOption::new(name, first.to_string())
+                    .description(format!("Jump to page {}", first + 1))
+                    .default_selection(name == current_category)
+            })
+            .collect();
+        let menu = CreateSelectMenu::new(format!("{}:category", self.key), CreateSelectMenuKind::String { options })
+            .placeholder("Jump to a category");
+        let buttons = vec![
+            CreateButton::new(format!("{}:prev:{}", self.key, index))
+                .label("◀ Previous")
+                .style(ButtonStyle::Secondary)
+                .disabled(index == 0),
+            CreateButton::new(format!("{}:next:{}", self.key, index))
+                .label("Next ▶")
+                .style(ButtonStyle::Secondary)
+                .disabled(index + 1 >= self.pages.len()),
+        ];
+        vec![CreateActionRow::SelectMenu(menu), CreateActionRow::Buttons(buttons)]
+    }
+
+    /// A new message showing the given page
+    pub fn message(&self, index: usize) -> CreateMessage {
+        CreateMessage::new().embed(self.embed(index)).components(self.components(index))
+    }
+
+    /// The page a component press should show, or None if the custom_id isn't one of ours.
+    /// `selected` is the chosen value for select menus.
+    pub fn target_page(&self, custom_id: &str, selected: Option<&str>) -> Option<usize> {
+        let rest = custom_id.strip_prefix(self.key)?.strip_prefix(':')?;
+        let last = self.pages.len() - 1;
+        let target = match rest.split_once(':') {
+            Some(("prev", page)) => page.parse::<usize>().ok()?.saturating_sub(1),
+            Some(("next", page)) => page.parse::<usize>().ok()? + 1,
+            None if rest == "category" => selected?.parse::<usize>().ok()?,
+            _ => return None,
+        };
+        Some(target.min(last))
+    }
+
+    /// Update the message in place if the component belongs to this embed; returns whether it did
+    pub async fn handle(&self, http: impl AsRef<Http>, component: &ComponentInteraction) -> bool {
+        let selected = match &component.data.kind {
+            ComponentInteractionDataKind::StringSelect { values } => values.first().map(|v| v.as_str()),
+            _ => None,
+        };
+        let Some(page) = self.target_page(&component.data.custom_id, selected) else {
+            return false;
+        };
+        let response = CreateInteractionResponse::UpdateMessage(
+            CreateInteractionResponseMessage::new().embed(self.embed(page)).components(self.components(page)),
+        );
+        if let Err(why) = component.create_response(http.as_ref(), response).await {
+            tracing::error!(?why, "Error updating paged embed");
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pages() -> PagedEmbed {
+        let page = |category, title| Page { category, title, body: String::new() };
+        PagedEmbed::new("help", vec![page("Basics", "A"), page("Basics", "B"), page("Creative", "C"), page("Admin", "D")])
+    }
+
+    #[test]
+    fn test_categories_point_at_first_page() {
+        assert_eq!(pages().categories(), vec![("Basics", 0), ("Creative", 2), ("Admin", 3)]);
+    }
+
+    #[test]
+    fn test_target_page() {
+        let paged = pages();
+        assert_eq!(paged.target_page("help:next:0", None), Some(1));
+        assert_eq!(paged.target_page("help:prev:2", None), Some(1));
+        // Clamped at both ends
+        assert_eq!(paged.target_page("help:prev:0", None), Some(0));
+        assert_eq!(paged.target_page("help:next:3", None), Some(3));
+        assert_eq!(paged.target_page("help:category", Some("2")), Some(2));
+        assert_eq!(paged.target_page("help:category", None), None);
+        assert_eq!(paged.target_page("helpx:next:0", None), None);
+        assert_eq!(paged.target_page("apply_suggested_flavor", None), None);
+    }
+}
@@ -4,6 +4,9 @@ use image::RgbaImage;
 use catppuccin::{PALETTE, FlavorName};
 use image::Rgba;
 
+/// A palette color's name and value
+pub type NamedColor = (&'static str, (u8, u8, u8));
+
 pub fn generate_palette_preview(flavor: FlavorName) -> RgbaImage {
     let colors_struct = match flavor {
         FlavorName::Latte => &PALETTE.latte.colors,
@@ -46,7 +49,6 @@ pub fn generate_all_palettes_preview() -> RgbaImage {
     let flavors = [FlavorName::Latte, FlavorName::Frappe, FlavorName::Macchiato, FlavorName::Mocha];
     let swatch_size: u32 = 40;
     let margin: u32 = 5;
-    let colors_per_flavor: u32 = 26;
     let grid_cols: u32 = 5;
     let grid_rows: u32 = 6; // 5x6=30, enough for 26 colors
     let flavor_width = grid_cols * swatch_size + (grid_cols + 1) * margin;
@@ -93,38 +95,451 @@ pub fn generate_all_palettes_preview() -> RgbaImage {
     img
 }
 
-/// Generate a horizontal gradient image from a list of RGB tuples
-pub fn generate_gradient_image(colors: &[(u8, u8, u8)], width: u32, height: u32) -> image::RgbaImage {
+/// Direction of a gradient: a linear sweep at an angle in degrees
+/// (0 = left to right, 90 = top to bottom), or radial from the center outward
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GradientDirection {
+    Linear(f32),
+    Radial,
+}
+
+/// Apply a named easing function to a value in 0..=1
+pub fn apply_easing(t: f32, easing: &str) -> f32 {
+    let t = t.clamp(0.0, 1.0);
+    match easing {
+        "ease-in" => t * t,
+        "ease-out" => 1.0 - (1.0 - t) * (1.0 - t),
+        "ease-in-out" => t * t * (3.0 - 2.0 * t),
+        "sine" => 0.5 - 0.5 * (std::f32::consts::PI * t).cos(),
+        _ => t,
+    }
+}
+
+/// Spread colors evenly from 0.0 to 1.0, for callers without explicit stop positions
+pub fn evenly_spaced_stops(colors: &[(u8, u8, u8)]) -> Vec<((u8, u8, u8), f32)> {
     let n = colors.len();
+    colors.iter().enumerate()
+        .map(|(i, c)| (*c, if n > 1 { i as f32 / (n - 1) as f32 } else { 0.0 }))
+        .collect()
+}
+
+/// Sample the color at position `t` along a list of color stops (positions in 0..=1, sorted)
+pub fn sample_gradient(stops: &[((u8, u8, u8), f32)], t: f32, easing: &str) -> (u8, u8, u8) {
+    let first = stops[0];
+    let last = stops[stops.len() - 1];
+    if t <= first.1 {
+        return first.0;
+    }
+    if t >= last.1 {
+        return last.0;
+    }
+    let seg = stops.windows(2).position(|w| t >= w[0].1 && t <= w[1].1).unwrap_or(0);
+    let ((r1, g1, b1), p1) = stops[seg];
+    let ((r2, g2, b2), p2) = stops[seg + 1];
+    let local_t = if p2 > p1 { (t - p1) / (p2 - p1) } else { 0.0 };
+    let local_t = apply_easing(local_t, easing);
+    let r = (r1 as f32 * (1.0 - local_t) + r2 as f32 * local_t).round() as u8;
+    let g = (g1 as f32 * (1.0 - local_t) + g2 as f32 * local_t).round() as u8;
+    let b = (b1 as f32 * (1.0 - local_t) + b2 as f32 * local_t).round() as u8;
+    (r, g, b)
+}
+
+/// Compute the position (0..=1) along the gradient for every pixel, in row-major order
+fn gradient_positions(width: u32, height: u32, direction: GradientDirection) -> Vec<f32> {
+    let w = width as f32;
+    let h = height as f32;
+    let (cx, cy) = ((w - 1.0) / 2.0, (h - 1.0) / 2.0);
+    let max_radius = (cx * cx + cy * cy).sqrt().max(1.0);
+    let (dx, dy) = match direction {
+        GradientDirection::Linear(deg) => (deg.to_radians().cos(), deg.to_radians().sin()),
+        GradientDirection::Radial => (0.0, 0.0),
+    };
+    // Project the corners onto the gradient axis so the sweep spans the whole image at any angle
+    let corners = [(0.0, 0.0), (w - 1.0, 0.0), (0.0, h - 1.0), (w - 1.0, h - 1.0)];
+    let projections: Vec<f32> = corners.iter().map(|(x, y)| x * dx + y * dy).collect();
+    let min_p = projections.iter().cloned().fold(f32::MAX, f32::min);
+    let max_p = projections.iter().cloned().fold(f32::MIN, f32::max);
+    let span = (max_p - min_p).max(1.0);
+    let mut positions = Vec::with_capacity((width * height) as usize);
+    for y in 0..height {
+        for x in 0..width {
+            let (xf, yf) = (x as f32, y as f32);
+            positions.push(match direction {
+                GradientDirection::Linear(_) => (xf * dx + yf * dy - min_p) / span,
+                GradientDirection::Radial => ((xf - cx).powi(2) + (yf - cy).powi(2)).sqrt() / max_radius,
+            });
+        }
+    }
+    positions
+}
+
+/// Generate a gradient image from color stops, in the given direction, with easing between stops
+pub fn generate_gradient_image(
+    stops: &[((u8, u8, u8), f32)],
+    width: u32,
+    height: u32,
+    direction: GradientDirection,
+    easing: &str,
+) -> image::RgbaImage {
     let mut img = image::RgbaImage::new(width, height);
-    if n == 0 {
+    if stops.is_empty() || width == 0 || height == 0 {
         return img;
     }
-    for x in 0..width {
-        // Determine which segment this x falls into
-        let t = x as f32 / (width - 1) as f32;
-        let seg = if n == 1 {
-            0
-        } else {
-            ((t * (n as f32 - 1.0)).floor() as usize).min(n - 2)
-        };
-        let local_t = if n == 1 {
-            0.0
-        } else {
-            (t * (n as f32 - 1.0)) - seg as f32
-        };
-        let (r1, g1, b1) = colors[seg];
-        let (r2, g2, b2) = if seg + 1 < n { colors[seg + 1] } else { colors[seg] };
-        let r = (r1 as f32 * (1.0 - local_t) + r2 as f32 * local_t).round() as u8;
-        let g = (g1 as f32 * (1.0 - local_t) + g2 as f32 * local_t).round() as u8;
-        let b = (b1 as f32 * (1.0 - local_t) + b2 as f32 * local_t).round() as u8;
-        for y in 0..height {
-            img.put_pixel(x, y, image::Rgba([r, g, b, 255]));
+    let mut stops = stops.to_vec();
+    stops.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+    let positions = gradient_positions(width, height, direction);
+    for (pixel, t) in img.pixels_mut().zip(positions) {
+        let (r, g, b) = sample_gradient(&stops, t, easing);
+        *pixel = image::Rgba([r, g, b, 255]);
+    }
+    img
+}
+
+/// Generate a looping GIF where the gradient shifts along its axis, cycling through the colors.
+/// `frame_delay` is in hundredths of a second.
+pub fn generate_animated_gradient_gif(
+    colors: &[(u8, u8, u8)],
+    width: u32,
+    height: u32,
+    direction: GradientDirection,
+    easing: &str,
+    n_frames: u32,
+    frame_delay: u16,
+) -> Result<Vec<u8>, String> {
+    if colors.len() < 2 {
+        return Err("At least two colors are required".to_string());
+    }
+    if width == 0 || height == 0 || width > u16::MAX as u32 || height > u16::MAX as u32 {
+        return Err(format!("Invalid GIF dimensions: {}x{}", width, height));
+    }
+    // Close the loop by repeating the first color, so the last frame flows into the first
+    let mut cyclic = colors.to_vec();
+    cyclic.push(colors[0]);
+    let stops = evenly_spaced_stops(&cyclic);
+    // Compute positions once, then offset the sample position per frame
+    let positions = gradient_positions(width, height, direction);
+    let mut output = Vec::new();
+    {
+        let mut encoder = gif::Encoder::new(&mut output, width as u16, height as u16, &[])
+            .map_err(|e| format!("Failed to create GIF encoder: {e}"))?;
+        encoder.set_repeat(gif::Repeat::Infinite).map_err(|e| format!("Failed to set GIF repeat: {e}"))?;
+        for i in 0..n_frames.max(1) {
+            let offset = i as f32 / n_frames.max(1) as f32;
+            let mut frame_img = RgbaImage::new(width, height);
+            for (pixel, t) in frame_img.pixels_mut().zip(positions.iter()) {
+                let (r, g, b) = sample_gradient(&stops, (t + offset) % 1.0, easing);
+                *pixel = Rgba([r, g, b, 255]);
+            }
+            let mut frame = gif::Frame::from_rgba_speed(width as u16, height as u16, &mut frame_img.into_raw(), 10);
+            frame.delay = frame_delay;
+            encoder.write_frame(&frame).map_err(|e| format!("Failed to write GIF frame: {e}"))?;
+        }
+    }
+    Ok(output)
+}
+
+/// Generate a deterministic 5x5 mirrored identicon for a seed, using the flavor's
+/// base as background and one accent color (picked from the seed) as foreground
+pub fn generate_identicon(seed: &str, flavor: FlavorName, size: u32) -> RgbaImage {
+    // FNV-1a, so the same seed gives the same avatar across builds and platforms
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in seed.as_bytes() {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    let accents = &crate::utils::CATPPUCCIN_COLOR_NAMES[..14];
+    let accent = accents[(hash % accents.len() as u64) as usize];
+    let (fr, fg, fb) = crate::utils::catppuccin_color_name_to_rgb(accent, flavor).unwrap();
+    let (br, bg, bb) = crate::utils::catppuccin_color_name_to_rgb("base", flavor).unwrap();
+    let mut img = RgbaImage::from_pixel(size, size, Rgba([br, bg, bb, 255]));
+    let grid: u32 = 5;
+    let margin = size / 12;
+    let cell = (size - 2 * margin) / grid;
+    let offset = (size - cell * grid) / 2;
+    // 15 bits decide the left 3 columns; the right 2 mirror them
+    let bits = hash >> 8;
+    for row in 0..grid {
+        for col in 0..3 {
+            if (bits >> (row * 3 + col)) & 1 == 0 {
+                continue;
+            }
+            for mirrored_col in [col, grid - 1 - col] {
+                let x0 = offset + mirrored_col * cell;
+                let y0 = offset + row * cell;
+                for x in x0..x0 + cell {
+                    for y in y0..y0 + cell {
+                        img.put_pixel(x, y, Rgba([fr, fg, fb, 255]));
+                    }
+                }
+            }
+        }
+    }
+    img
+}
+
+/// Generate a quote/announcement card: flavor base background, accent border,
+/// and the text wrapped and centered in the flavor's text color
+pub fn generate_text_card(text: &str, flavor: FlavorName, accent: &str) -> RgbaImage {
+    use crate::text_rendering;
+    let width: u32 = 800;
+    let border: u32 = 8;
+    let padding: u32 = 56;
+    let font_size = 40.0;
+    let lines = text_rendering::wrap_text(text, font_size, width - 2 * (border + padding));
+    let text_height = lines.len() as u32 * text_rendering::line_height(font_size);
+    let height = (text_height + 2 * (border + padding)).max(240);
+    let (br, bg, bb) = crate::utils::catppuccin_color_name_to_rgb("base", flavor).unwrap();
+    let (ar, ag, ab) = crate::utils::catppuccin_color_name_to_rgb(accent, flavor)
+        .or_else(|| crate::utils::catppuccin_color_name_to_rgb("mauve", flavor))
+        .unwrap();
+    let text_color = crate::utils::catppuccin_color_name_to_rgb("text", flavor).unwrap();
+    let mut img = RgbaImage::from_pixel(width, height, Rgba([ar, ag, ab, 255]));
+    text_rendering::fill_rect(&mut img, border as i32, border as i32, width - 2 * border, height - 2 * border, Rgba([br, bg, bb, 255]));
+    // Accent bar above the text
+    text_rendering::fill_rect(&mut img, (width / 2 - 40) as i32, (border + padding / 2) as i32, 80, 4, Rgba([ar, ag, ab, 255]));
+    let y = (height - text_height) / 2;
+    text_rendering::draw_text_centered(&mut img, &lines, y as i32, width, font_size, text_color);
+    img
+}
+
+/// Generate a side-by-side table of every named color in two flavors: the color's name, its swatch
+/// in each flavor, and a bar whose length is proportional to the Delta-E between the pair, with the
+/// value written after it. Returns the image along with (color name, Delta-E) for each row.
+pub fn generate_flavor_diff_image(flavor_a: FlavorName, flavor_b: FlavorName) -> (RgbaImage, Vec<(&'static str, f32)>) {
+    use crate::text_rendering::{draw_text, fill_rect, line_height, measure_text};
+    let swatch_w: u32 = 60;
+    let row_h: u32 = 24;
+    let margin: u32 = 6;
+    let bar_max: u32 = 240;
+    let label_size = 14.0;
+    let names = crate::utils::CATPPUCCIN_COLOR_NAMES;
+    let rows = names.len() as u32;
+    let name_w = names.iter().map(|name| measure_text(name, label_size).0).max().unwrap_or(0);
+    let value_w = measure_text("ΔE 100.0", label_size).0;
+    let header_h = line_height(label_size) + margin;
+    let xa = margin + name_w + margin;
+    let xb = xa + swatch_w + margin;
+    let xbar = xb + swatch_w + margin * 2;
+    let width = xbar + bar_max + margin + value_w + margin;
+    let height = margin + header_h + rows * (row_h + margin);
+    let (br, bg, bb) = crate::utils::catppuccin_color_name_to_rgb("crust", FlavorName::Mocha).unwrap();
+    let text = crate::utils::catppuccin_color_name_to_rgb("text", FlavorName::Mocha).unwrap();
+    let subtext = crate::utils::catppuccin_color_name_to_rgb("subtext0", FlavorName::Mocha).unwrap();
+    let mut img = RgbaImage::from_pixel(width, height, Rgba([br, bg, bb, 255]));
+    // Column headings, centered over their swatches
+    let heading = |img: &mut RgbaImage, label: &str, x: u32, w: u32| {
+        let label_w = measure_text(label, label_size).0;
+        draw_text(img, label, (x + w.saturating_sub(label_w) / 2) as i32, margin as i32, label_size, text);
+    };
+    heading(&mut img, &flavor_a.to_string(), xa, swatch_w);
+    heading(&mut img, &flavor_b.to_string(), xb, swatch_w);
+    draw_text(&mut img, "ΔE (CIEDE2000)", xbar as i32, margin as i32, label_size, text);
+    let text_offset = row_h.saturating_sub(measure_text("M", label_size).1) / 2;
+    let mut deltas = Vec::new();
+    for (i, name) in names.iter().enumerate() {
+        let a = crate::utils::catppuccin_color_name_to_rgb(name, flavor_a).unwrap();
+        let b = crate::utils::catppuccin_color_name_to_rgb(name, flavor_b).unwrap();
+        let de = crate::image_processing::delta_e(a, b);
+        deltas.push((*name, de));
+        let y0 = margin + header_h + i as u32 * (row_h + margin);
+        // Delta-E of 50 or more fills the whole bar
+        let bar_len = ((de / 50.0).min(1.0) * bar_max as f32).round() as u32;
+        draw_text(&mut img, name, margin as i32, (y0 + text_offset) as i32, label_size, subtext);
+        fill_rect(&mut img, xa as i32, y0 as i32, swatch_w, row_h, Rgba([a.0, a.1, a.2, 255]));
+        fill_rect(&mut img, xb as i32, y0 as i32, swatch_w, row_h, Rgba([b.0, b.1, b.2, 255]));
+        fill_rect(&mut img, xbar as i32, y0 as i32, bar_len, row_h, Rgba([b.0, b.1, b.2, 255]));
+        draw_text(&mut img, &format!("{:.1}", de), (xbar + bar_len + margin) as i32, (y0 + text_offset) as i32, label_size, text);
+    }
+    (img, deltas)
+}
+
+/// Kinds of color scheme `!cat scheme` can build
+pub const SCHEME_TYPES: [&str; 4] = ["monochromatic", "complementary", "analogous", "triadic"];
+
+/// The colors of a scheme built around `base`, by rotating its hue or (for monochromatic) varying its lightness
+pub fn generate_scheme(base: (u8, u8, u8), kind: &str) -> Vec<(u8, u8, u8)> {
+    use crate::utils::{hsl_to_rgb, rgb_to_hsl};
+    let (h, s, l) = rgb_to_hsl(base.0, base.1, base.2);
+    let rotated = |degrees: f32| hsl_to_rgb((h + degrees) % 360.0, s, l);
+    match kind {
+        "monochromatic" => [l * 0.5, l * 0.75, l, l + 0.25, l + 0.5]
+            .iter()
+            .map(|lightness| hsl_to_rgb(h, s, lightness.clamp(0.0, 1.0)))
+            .collect(),
+        "complementary" => vec![base, rotated(180.0)],
+        "analogous" => vec![rotated(330.0), base, rotated(30.0)],
+        "triadic" => vec![base, rotated(120.0), rotated(240.0)],
+        _ => vec![base],
+    }
+}
+
+/// The most common exact color in an image
+pub fn dominant_color(img: &RgbaImage) -> Option<(u8, u8, u8)> {
+    let mut counts = std::collections::HashMap::new();
+    for pixel in img.pixels() {
+        *counts.entry((pixel[0], pixel[1], pixel[2])).or_insert(0u32) += 1;
+    }
+    counts.into_iter().max_by_key(|(_, count)| *count).map(|(rgb, _)| rgb)
+}
+
+/// Swatches on the flavor's base: each color above the palette color it snaps to, labeled with that
+/// color's name. Returns the image and the snapped colors in order.
+pub fn render_snapped_swatches(colors: &[(u8, u8, u8)], flavor: FlavorName) -> (RgbaImage, Vec<NamedColor>) {
+    use crate::text_rendering::{draw_text, fill_rect, measure_text};
+    let swatch: u32 = 100;
+    let margin: u32 = 12;
+    let label_size = 18.0;
+    let label_h: u32 = 28;
+    let snapped: Vec<_> = colors.iter().map(|c| crate::image_processing::nearest_palette_color(*c, flavor)).collect();
+    let width = margin + colors.len() as u32 * (swatch + margin);
+    let height = margin + 2 * swatch + label_h + margin;
+    let rgba = |(r, g, b): (u8, u8, u8)| Rgba([r, g, b, 255]);
+    let base = crate::utils::catppuccin_color_name_to_rgb("base", flavor).unwrap();
+    let text = crate::utils::catppuccin_color_name_to_rgb("text", flavor).unwrap();
+    let mut img = RgbaImage::from_pixel(width, height, rgba(base));
+    for (i, (color, (name, palette_color))) in colors.iter().zip(&snapped).enumerate() {
+        let x = margin + i as u32 * (swatch + margin);
+        fill_rect(&mut img, x as i32, margin as i32, swatch, swatch, rgba(*color));
+        fill_rect(&mut img, x as i32, (margin + swatch) as i32, swatch, swatch, rgba(*palette_color));
+        let label_w = measure_text(name, label_size).0;
+        draw_text(&mut img, name, (x + swatch.saturating_sub(label_w) / 2) as i32, (margin + 2 * swatch + 4) as i32, label_size, text);
+    }
+    (img, snapped)
+}
+
+/// An imported palette on the flavor's base: swatches in rows of eight, each labeled with its hex code
+pub fn render_custom_palette_preview(colors: &[(u8, u8, u8)], flavor: FlavorName) -> RgbaImage {
+    use crate::text_rendering::{draw_text, fill_rect, measure_text};
+    let swatch: u32 = 90;
+    let margin: u32 = 12;
+    let columns: u32 = 8;
+    let label_size = 16.0;
+    let label_h: u32 = 24;
+    let shown_columns = (colors.len() as u32).clamp(1, columns);
+    let rows = (colors.len() as u32).div_ceil(columns).max(1);
+    let width = margin + shown_columns * (swatch + margin);
+    let height = margin + rows * (swatch + label_h + margin);
+    let rgba = |(r, g, b): (u8, u8, u8)| Rgba([r, g, b, 255]);
+    let base = crate::utils::catppuccin_color_name_to_rgb("base", flavor).unwrap();
+    let text = crate::utils::catppuccin_color_name_to_rgb("subtext1", flavor).unwrap();
+    let mut img = RgbaImage::from_pixel(width, height, rgba(base));
+    for (i, &(r, g, b)) in colors.iter().enumerate() {
+        let x = margin + (i as u32 % columns) * (swatch + margin);
+        let y = margin + (i as u32 / columns) * (swatch + label_h + margin);
+        fill_rect(&mut img, x as i32, y as i32, swatch, swatch, rgba((r, g, b)));
+        let label = format!("#{:02X}{:02X}{:02X}", r, g, b);
+        let label_w = measure_text(&label, label_size).0;
+        draw_text(&mut img, &label, (x + swatch.saturating_sub(label_w) / 2) as i32, (y + swatch + 4) as i32, label_size, text);
+    }
+    img
+}
+
+/// A member's favorite colors in one flavor on its base: swatches in rows of six, each labeled with
+/// its name and hex code
+pub fn render_favorites_board(names: &[String], flavor: FlavorName) -> RgbaImage {
+    use crate::text_rendering::{draw_text, fill_rect, measure_text};
+    let swatch: u32 = 100;
+    let margin: u32 = 12;
+    let columns: u32 = 6;
+    let label_size = 16.0;
+    let label_h: u32 = 44;
+    let shown_columns = (names.len() as u32).clamp(1, columns);
+    let rows = (names.len() as u32).div_ceil(columns).max(1);
+    let width = margin + shown_columns * (swatch + margin);
+    let height = margin + rows * (swatch + label_h + margin);
+    let rgba = |(r, g, b): (u8, u8, u8)| Rgba([r, g, b, 255]);
+    let base = crate::utils::catppuccin_color_name_to_rgb("base", flavor).unwrap();
+    let text = crate::utils::catppuccin_color_name_to_rgb("text", flavor).unwrap();
+    let subtext = crate::utils::catppuccin_color_name_to_rgb("subtext0", flavor).unwrap();
+    let mut img = RgbaImage::from_pixel(width, height, rgba(base));
+    let colors = names.iter().filter_map(|name| Some((name, crate::utils::catppuccin_color_name_to_rgb(name, flavor)?)));
+    for (i, (name, (r, g, b))) in colors.enumerate() {
+        let x = margin + (i as u32 % columns) * (swatch + margin);
+        let y = margin + (i as u32 / columns) * (swatch + label_h + margin);
+        fill_rect(&mut img, x as i32, y as i32, swatch, swatch, rgba((r, g, b)));
+        let hex = format!("#{:02X}{:02X}{:02X}", r, g, b);
+        for (line, line_y, color) in [(name.as_str(), y + swatch + 4, text), (hex.as_str(), y + swatch + 24, subtext)] {
+            let line_w = measure_text(line, label_size).0;
+            draw_text(&mut img, line, (x + swatch.saturating_sub(line_w) / 2) as i32, line_y as i32, label_size, color);
         }
     }
     img
 }
 
+// Most swatches `!cat match` reports from one palette image
+const MAX_SWATCHES: usize = 24;
+
+/// Find the flat color swatches in a palette screenshot: connected regions of (nearly) one color that
+/// are big and solid enough to be a swatch rather than text, edges, or gradients. The background,
+/// which spans the whole image, is skipped, and repeats of the same color are reported once.
+/// Returned in reading order: top to bottom, then left to right.
+pub fn detect_swatches(img: &RgbaImage) -> Vec<(u8, u8, u8)> {
+    // Nearest-neighbor keeps flat regions exactly flat
+    let scale = (256.0 / img.width().max(img.height()) as f32).min(1.0);
+    let small = image::imageops::resize(
+        img,
+        ((img.width() as f32 * scale).round() as u32).max(1),
+        ((img.height() as f32 * scale).round() as u32).max(1),
+        image::imageops::FilterType::Nearest,
+    );
+    let (w, h) = (small.width() as usize, small.height() as usize);
+    let min_area = (w * h / 500).max(16);
+    let mut visited = vec![false; w * h];
+    // (mean color, top, left, bottom)
+    let mut regions = Vec::new();
+    for start in 0..w * h {
+        if visited[start] {
+            continue;
+        }
+        visited[start] = true;
+        if small.get_pixel((start % w) as u32, (start / w) as u32)[3] < 128 {
+            continue;
+        }
+        let (mut sum, mut area) = ([0u64; 3], 0usize);
+        let (mut lowest, mut highest) = ([u8::MAX; 3], [0u8; 3]);
+        let (mut top, mut left, mut bottom, mut right) = (h, w, 0, 0);
+        let mut stack = vec![start];
+        while let Some(i) = stack.pop() {
+            let (x, y) = (i % w, i / w);
+            let pixel = *small.get_pixel(x as u32, y as u32);
+            for c in 0..3 {
+                sum[c] += pixel[c] as u64;
+                lowest[c] = lowest[c].min(pixel[c]);
+                highest[c] = highest[c].max(pixel[c]);
+            }
+            area += 1;
+            (top, left, bottom, right) = (top.min(y), left.min(x), bottom.max(y), right.max(x));
+            // Neighbors a step apart join, so compression noise doesn't split a swatch
+            let close = |p: &Rgba<u8>| p[3] >= 128 && (0..3).all(|c| p[c].abs_diff(pixel[c]) <= 6);
+            let neighbors = [(x > 0).then(|| i - 1), (x + 1 < w).then(|| i + 1), (y > 0).then(|| i - w), (y + 1 < h).then(|| i + w)];
+            for n in neighbors.into_iter().flatten() {
+                if !visited[n] && close(small.get_pixel((n % w) as u32, (n / w) as u32)) {
+                    visited[n] = true;
+                    stack.push(n);
+                }
+            }
+        }
+        // ...but a region that drifts far overall is a gradient, not a swatch
+        let flat = (0..3).all(|c| highest[c] - lowest[c] <= 24);
+        let box_area = (bottom - top + 1) * (right - left + 1);
+        let is_background = top == 0 && left == 0 && bottom == h - 1 && right == w - 1;
+        if flat && area >= min_area && area * 10 >= box_area * 6 && !is_background {
+            let mean = sum.map(|s| (s / area as u64) as u8);
+            regions.push(((mean[0], mean[1], mean[2]), top, left, bottom));
+        }
+    }
+    // Swatches in a row rarely line up exactly, so rows are bands a twentieth of the image tall
+    let band = (h / 20).max(1);
+    regions.sort_by_key(|(_, top, left, bottom)| ((top + bottom) / 2 / band, *left));
+    let mut swatches: Vec<(u8, u8, u8)> = Vec::new();
+    for (color, ..) in regions {
+        if swatches.len() < MAX_SWATCHES && swatches.iter().all(|s| crate::image_processing::delta_e(*s, color) >= 2.0) {
+            swatches.push(color);
+        }
+    }
+    swatches
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -151,9 +566,156 @@ mod tests {
     #[test]
     fn test_generate_all_palettes_preview_dimensions() {
         let img = generate_all_palettes_preview();
-        // 4 flavors, each flavor_width = 4*40 + 5*5 = 185, total_width = 4*185 + 5*5 = 765
-        // flavor_height = 16*40 + 17*5 + 30 = 755
-        assert_eq!(img.width(), 765);
-        assert_eq!(img.height(), 755);
+        // 4 flavors, each flavor_width = 5*40 + 6*5 = 230, total_width = 4*230 + 5*5 = 945
+        // flavor_height = 6*40 + 7*5 + 30 = 305
+        assert_eq!(img.width(), 945);
+        assert_eq!(img.height(), 305);
+    }
+
+    #[test]
+    fn test_generate_flavor_diff_image_same_flavor() {
+        let (img, deltas) = generate_flavor_diff_image(FlavorName::Mocha, FlavorName::Mocha);
+        assert_eq!(deltas.len(), 26);
+        assert!(deltas.iter().all(|(_, de)| *de < 0.01));
+        let header_h = crate::text_rendering::line_height(14.0) + 6;
+        assert_eq!(img.height(), 6 + header_h + 26 * 30);
+    }
+
+    #[test]
+    fn test_flavor_diff_image_is_labeled() {
+        let (img, deltas) = generate_flavor_diff_image(FlavorName::Latte, FlavorName::Mocha);
+        let text = crate::utils::catppuccin_color_name_to_rgb("text", FlavorName::Mocha).unwrap();
+        let has_text = |x0: u32, x1: u32, y0: u32, y1: u32| {
+            (y0..y1).any(|y| (x0..x1).any(|x| {
+                let p = img.get_pixel(x, y);
+                (p[0], p[1], p[2]) == text
+            }))
+        };
+        // Flavor headings above the swatches
+        let header_h = crate::text_rendering::line_height(14.0) + 6;
+        assert!(has_text(0, img.width(), 0, header_h));
+        // Each row's Delta-E is written after its bar; the name beside it is in subtext
+        let first_row = 6 + header_h;
+        assert!(has_text(0, img.width(), first_row, first_row + 24));
+        assert!(deltas.iter().any(|(_, de)| *de > 1.0));
+    }
+
+    #[test]
+    fn test_generate_gradient_image_directions() {
+        let stops = evenly_spaced_stops(&[(255, 0, 0), (0, 0, 255)]);
+        let horizontal = generate_gradient_image(&stops, 64, 8, GradientDirection::Linear(0.0), "linear");
+        assert_eq!(horizontal.get_pixel(0, 4).0, [255, 0, 0, 255]);
+        assert_eq!(horizontal.get_pixel(63, 4).0, [0, 0, 255, 255]);
+        let vertical = generate_gradient_image(&stops, 8, 64, GradientDirection::Linear(90.0), "linear");
+        assert_eq!(vertical.get_pixel(4, 0).0, [255, 0, 0, 255]);
+        assert_eq!(vertical.get_pixel(4, 63).0, [0, 0, 255, 255]);
+        let radial = generate_gradient_image(&stops, 65, 65, GradientDirection::Radial, "linear");
+        assert_eq!(radial.get_pixel(32, 32).0, [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_sample_gradient_stop_positions() {
+        let stops = [((0, 0, 0), 0.0), ((255, 255, 255), 0.5)];
+        assert_eq!(sample_gradient(&stops, 0.25, "linear"), (128, 128, 128));
+        assert_eq!(sample_gradient(&stops, 0.9, "linear"), (255, 255, 255));
+        assert_eq!(sample_gradient(&stops, 0.25, "ease-in"), (64, 64, 64));
+    }
+
+    #[test]
+    fn test_generate_identicon_deterministic() {
+        let a = generate_identicon("catppuccin", FlavorName::Mocha, 120);
+        let b = generate_identicon("catppuccin", FlavorName::Mocha, 120);
+        let c = generate_identicon("something else", FlavorName::Mocha, 120);
+        assert_eq!(a.dimensions(), (120, 120));
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        // Mirrored horizontally
+        for y in 0..120 {
+            for x in 0..120 {
+                assert_eq!(a.get_pixel(x, y), a.get_pixel(119 - x, y));
+            }
+        }
+    }
+
+    #[test]
+    fn test_generate_text_card_grows_with_text() {
+        let short = generate_text_card("Hello", FlavorName::Mocha, "mauve");
+        let long = generate_text_card(&"Catppuccin is a soothing pastel theme. ".repeat(12), FlavorName::Mocha, "mauve");
+        assert_eq!(short.width(), 800);
+        assert!(long.height() > short.height());
+        // Border uses the accent color
+        let mauve = crate::utils::catppuccin_color_name_to_rgb("mauve", FlavorName::Mocha).unwrap();
+        assert_eq!(short.get_pixel(0, 0).0, [mauve.0, mauve.1, mauve.2, 255]);
+    }
+
+    #[test]
+    fn test_generate_scheme() {
+        let blue = (0, 0, 255);
+        assert_eq!(generate_scheme(blue, "complementary"), vec![blue, (255, 255, 0)]);
+        assert_eq!(generate_scheme(blue, "triadic"), vec![blue, (255, 0, 0), (0, 255, 0)]);
+        assert_eq!(generate_scheme(blue, "monochromatic").len(), 5);
+    }
+
+    #[test]
+    fn test_render_snapped_swatches() {
+        let mauve = crate::utils::catppuccin_color_name_to_rgb("mauve", FlavorName::Mocha).unwrap();
+        let (img, snapped) = render_snapped_swatches(&[(200, 160, 250), (10, 10, 20)], FlavorName::Mocha);
+        assert_eq!(snapped[0], ("mauve", mauve));
+        assert_eq!(snapped[1].0, "crust");
+        assert_eq!(img.get_pixel(12 + 50, 12 + 50).0, [200, 160, 250, 255]);
+        assert_eq!(img.get_pixel(12 + 50, 12 + 150).0, [mauve.0, mauve.1, mauve.2, 255]);
+    }
+
+    #[test]
+    fn test_render_custom_palette_preview() {
+        let colors = vec![(38, 70, 83); 10];
+        let img = render_custom_palette_preview(&colors, FlavorName::Mocha);
+        // Ten colors wrap onto a second row of eight columns
+        assert_eq!(img.width(), 12 + 8 * (90 + 12));
+        assert_eq!(img.height(), 12 + 2 * (90 + 24 + 12));
+        assert_eq!(img.get_pixel(12 + 45, 12 + 126 + 45).0, [38, 70, 83, 255]);
+    }
+
+    #[test]
+    fn test_render_favorites_board() {
+        let names: Vec<String> = ["mauve", "pink", "blue", "green", "red", "peach", "text"].iter().map(|s| s.to_string()).collect();
+        let img = render_favorites_board(&names, FlavorName::Mocha);
+        // Seven colors wrap onto a second row of six columns
+        assert_eq!(img.dimensions(), (12 + 6 * (100 + 12), 12 + 2 * (100 + 44 + 12)));
+        assert_eq!(img.get_pixel(12 + 50, 12 + 50).0, [203, 166, 247, 255]);
+    }
+
+    #[test]
+    fn test_detect_swatches() {
+        // Three swatches on a white card, plus a repeat of the first and a gradient that isn't a swatch
+        let mut img = RgbaImage::from_pixel(300, 200, Rgba([255, 255, 255, 255]));
+        let swatch = |img: &mut RgbaImage, x: i32, y: i32, color: (u8, u8, u8)| {
+            crate::text_rendering::fill_rect(img, x, y, 60, 60, Rgba([color.0, color.1, color.2, 255]));
+        };
+        swatch(&mut img, 20, 20, (30, 30, 46));
+        swatch(&mut img, 120, 22, (203, 166, 247));
+        swatch(&mut img, 220, 20, (166, 227, 161));
+        swatch(&mut img, 20, 120, (30, 30, 46));
+        for x in 120..280 {
+            for y in 120..180 {
+                img.put_pixel(x, y, Rgba([(x - 100) as u8, 100, 200, 255]));
+            }
+        }
+        assert_eq!(detect_swatches(&img), vec![(30, 30, 46), (203, 166, 247), (166, 227, 161)]);
+        // A photo-like gradient has none
+        let gradient = RgbaImage::from_fn(200, 200, |x, y| Rgba([x as u8, y as u8, 128, 255]));
+        assert!(detect_swatches(&gradient).is_empty());
+    }
+
+    #[test]
+    fn test_generate_animated_gradient_gif_frames() {
+        let gif_bytes = generate_animated_gradient_gif(&[(255, 0, 0), (0, 0, 255)], 16, 8, GradientDirection::Linear(0.0), "linear", 6, 5).unwrap();
+        let mut decoder = gif::DecodeOptions::new().read_info(std::io::Cursor::new(gif_bytes)).unwrap();
+        let mut frames = 0;
+        while decoder.read_next_frame().unwrap().is_some() {
+            frames += 1;
+        }
+        assert_eq!(frames, 6);
+        assert!(generate_animated_gradient_gif(&[(255, 0, 0)], 16, 8, GradientDirection::Radial, "linear", 6, 5).is_err());
     }
 } 
\ No newline at end of file
@@ -0,0 +1,125 @@
+// src/pdf.rs
+
+use crate::config;
+use crate::icc;
+use crate::image_processing;
+use crate::jobs::{self, Job};
+use crate::responses;
+use crate::source;
+use crate::utils;
+use serenity::builder::{CreateAttachment, CreateMessage};
+use serenity::model::channel::{Attachment, Message};
+use serenity::prelude::Context;
+use tracing::info;
+
+/// Long side of a rendered page; a portrait A4 page comes out about 1450x2048
+#[cfg(feature = "pdf")]
+const PAGE_SIZE: u32 = 2048;
+
+fn is_pdf(attachment: &Attachment) -> bool {
+    attachment.content_type.as_deref() == Some("application/pdf") || attachment.filename.to_lowercase().ends_with(".pdf")
+}
+
+// The first PDF attached to the message, or to the message it replies to
+fn find_pdf(msg: &Message) -> Option<&Attachment> {
+    let replied = msg.referenced_message.iter().flat_map(|m| m.attachments.iter());
+    msg.attachments.iter().chain(replied).find(|a| is_pdf(a))
+}
+
+/// Render one page (counting from 1) of a PDF as a PNG with poppler's `pdftoppm`
+#[cfg(feature = "pdf")]
+fn rasterize(bytes: &[u8], page: u32) -> Result<Vec<u8>, String> {
+    use std::process::{Command, Stdio};
+    if !bytes.starts_with(b"%PDF-") {
+        return Err("That attachment isn't a PDF.".to_string());
+    }
+    let work_dir = crate::workdir::WorkDir::new()?;
+    let input_path = work_dir.file("input.pdf");
+    std::fs::write(&input_path, bytes).map_err(|e| format!("Failed to buffer the PDF: {e}"))?;
+    let page = page.to_string();
+    let output = Command::new("pdftoppm")
+        .args(["-q", "-png", "-singlefile", "-f", &page, "-l", &page, "-scale-to", &PAGE_SIZE.to_string()])
+        .arg(&input_path)
+        // `-singlefile` writes `<root>.png`
+        .arg(work_dir.file("page"))
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()
+        .map_err(|_| "Rendering PDFs needs `pdftoppm` (poppler-utils), which isn't installed on this bot's host.".to_string())?;
+    if !output.status.success() {
+        return Err(format!("Couldn't render page {} of that PDF. Does it have that many pages?", page));
+    }
+    std::fs::read(work_dir.file("page.png")).map_err(|e| format!("Failed to read the rendered page: {e}"))
+}
+
+#[cfg(not(feature = "pdf"))]
+fn rasterize(_bytes: &[u8], _page: u32) -> Result<Vec<u8>, String> {
+    Err("PDF rendering isn't enabled on this bot. Its host can turn it on by building with `--features pdf`.".to_string())
+}
+
+/// The page number among a command's words, counting from 1
+fn parse_page(parts: &[&str]) -> Option<u32> {
+    parts.iter().find_map(|s| s.parse::<u32>().ok()).filter(|page| (1..=9999).contains(page))
+}
+
+/// `!cat pdf [page] [flavor] [algorithm]` with a PDF attached (or replied to): render one page, page 1
+/// unless another is given, and catppuccinify it
+pub async fn run(ctx: &Context, msg: &Message, parts: &[&str]) {
+    let Some(attachment) = find_pdf(msg) else {
+        let _ = responses::warning(&ctx.http, msg.channel_id, "Attach a PDF (or reply to one) with `!cat pdf [page] [flavor]`, e.g. `!cat pdf 1 mocha`.").await;
+        return;
+    };
+    if parts.iter().any(|s| s.parse::<i64>().is_ok()) && parse_page(parts).is_none() {
+        let _ = responses::warning(&ctx.http, msg.channel_id, "Pages are numbered from 1.").await;
+        return;
+    }
+    let page = parse_page(parts).unwrap_or(1);
+    let flavor = parts.iter().find_map(|s| utils::parse_flavor(s)).unwrap_or_else(|| config::default_flavor(msg.guild_id));
+    let algorithm = parts.iter().find_map(|s| utils::parse_algorithm(s)).unwrap_or("shepards-method");
+    let _typing = msg.channel_id.start_typing(&ctx.http);
+    let bytes = match source::fetch_url(&attachment.url).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            let _ = responses::error(&ctx.http, msg.channel_id, e).await;
+            return;
+        }
+    };
+    info!(user = %msg.author.id, page, flavor = flavor.identifier(), "Rendering PDF page");
+    let result = jobs::run(Job::new("pdf", msg), move || {
+        let png = rasterize(&bytes, page)?;
+        let img = icc::decode(&png).map_err(|_| "Failed to read the rendered page.".to_string())?;
+        let processed = image_processing::process_image_with_palette(&img, flavor, algorithm);
+        let mut buffer = std::io::Cursor::new(Vec::new());
+        processed.write_to(&mut buffer, image::ImageFormat::Png).map_err(|e| format!("Failed to encode image: {e}"))?;
+        Ok::<_, String>(icc::tag_srgb_png(buffer.into_inner()))
+    })
+    .await;
+    let output = match result {
+        Ok(Ok(output)) => output,
+        Ok(Err(e)) => {
+            let _ = responses::error(&ctx.http, msg.channel_id, e).await;
+            return;
+        }
+        Err(panic) => {
+            let _ = responses::error(&ctx.http, msg.channel_id, panic.to_string()).await;
+            return;
+        }
+    };
+    let stem = attachment.filename.rsplit_once('.').map_or(attachment.filename.as_str(), |(stem, _)| stem);
+    let filename = utils::sanitize_filename(&format!("{}_page{}_{}.png", stem, page, flavor.identifier()), "png");
+    let content = format!("Page {} of `{}` (Flavor: {})", page, attachment.filename, flavor.to_string().to_uppercase());
+    let _ = msg.channel_id.send_files(&ctx.http, vec![CreateAttachment::bytes(output, filename)], CreateMessage::new().content(content)).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_page() {
+        assert_eq!(parse_page(&["3", "mocha"]), Some(3));
+        assert_eq!(parse_page(&["mocha"]), None);
+        assert_eq!(parse_page(&["0"]), None);
+    }
+}
@@ -0,0 +1,242 @@
+// src/policy.rs
+
+use crate::config::{self, ContentPolicy};
+use crate::responses;
+use crate::setup;
+use crate::source;
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
+use serenity::cache::Cache;
+use serenity::json::{json, Value};
+use serenity::model::channel::Message;
+use serenity::model::id::{ChannelId, MessageId};
+use serenity::prelude::Context;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::OnceCell;
+use tracing::{info, warn};
+
+/// Most images of one message sent to the content check
+const MAX_CHECKED_IMAGES: usize = 10;
+
+/// How long the moderation endpoint gets to answer
+const CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// What the content check said about an image
+#[derive(Debug, Clone, PartialEq)]
+pub struct Verdict {
+    /// Not safe outside NSFW channels
+    pub flagged: bool,
+    pub reason: Option<String>,
+}
+
+/// Something that can judge an image by its URL, such as an external moderation API
+#[serenity::async_trait]
+pub trait ContentCheck: Send + Sync {
+    async fn check(&self, url: &str) -> Result<Verdict, String>;
+}
+
+/// Posts `{"url": ...}` to `CONTENT_CHECK_URL` and expects `{"flagged": bool, "reason": "..."}` back
+struct HttpCheck {
+    url: String,
+    token: Option<String>,
+}
+
+#[serenity::async_trait]
+impl ContentCheck for HttpCheck {
+    async fn check(&self, url: &str) -> Result<Verdict, String> {
+        let client = reqwest::Client::builder().timeout(CHECK_TIMEOUT).build().map_err(|e| e.to_string())?;
+        let mut request = client
+            .post(&self.url)
+            .header("Content-Type", "application/json")
+            .body(serenity::json::to_string(&json!({ "url": url })).map_err(|e| e.to_string())?);
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+        let response = request.send().await.map_err(|e| format!("Content check request failed: {e}"))?;
+        if !response.status().is_success() {
+            return Err(format!("Content check returned {}", response.status()));
+        }
+        parse_verdict(&response.text().await.map_err(|e| e.to_string())?)
+    }
+}
+
+// The configured content check, if any. Other checks can be plugged in here.
+fn content_check() -> Option<Box<dyn ContentCheck>> {
+    let config = config::get();
+    let url = config.content_check_url.clone()?;
+    Some(Box::new(HttpCheck { url, token: config.content_check_token.clone() }))
+}
+
+/// Whether a content check is configured, so servers can turn a policy on
+pub fn check_available() -> bool {
+    content_check().is_some()
+}
+
+fn parse_verdict(text: &str) -> Result<Verdict, String> {
+    let value: Value = serenity::json::from_str(text).map_err(|e| format!("Content check sent invalid JSON: {e}"))?;
+    let flagged = value.get("flagged").and_then(Value::as_bool).ok_or("Content check response has no `flagged`")?;
+    let reason = value.get("reason").and_then(Value::as_str).filter(|r| !r.is_empty()).map(str::to_string);
+    Ok(Verdict { flagged, reason })
+}
+
+// Whether images need checking at all under a policy
+fn applies(policy: ContentPolicy, channel_nsfw: bool) -> bool {
+    match policy {
+        ContentPolicy::Off => false,
+        ContentPolicy::NsfwChannels => !channel_nsfw,
+        ContentPolicy::Strict => true,
+    }
+}
+
+// The refusal for an image under a server's policy, or None if it may be processed.
+// A failed check refuses too: servers with a policy would rather miss a result than post a bad one.
+fn decide(policy: ContentPolicy, channel_nsfw: bool, verdict: Result<&Verdict, &String>) -> Option<String> {
+    if !applies(policy, channel_nsfw) {
+        return None;
+    }
+    match verdict {
+        Ok(Verdict { flagged: false, .. }) => None,
+        Ok(Verdict { flagged: true, reason }) => {
+            let reason = reason.as_ref().map_or(String::new(), |r| format!(" ({})", r));
+            Some(match policy {
+                ContentPolicy::NsfwChannels => format!("🔞 That image was flagged{}; this server only processes it in NSFW channels.", reason),
+                _ => format!("🚫 That image was flagged{} and this server doesn't allow processing it.", reason),
+            })
+        }
+        Err(_) => Some(match policy {
+            ContentPolicy::NsfwChannels => "The content check is unavailable right now. Please try again later, or use an NSFW channel.".to_string(),
+            _ => "The content check is unavailable right now, and this server requires it. Please try again later.".to_string(),
+        }),
+    }
+}
+
+/// Whether a channel is marked NSFW; threads take the flag from their parent
+pub fn channel_is_nsfw(cache: &Cache, channel_id: ChannelId) -> bool {
+    let Some((nsfw, parent)) = cache.channel(channel_id).map(|c| (c.nsfw, c.parent_id.filter(|_| c.thread_metadata.is_some()))) else {
+        return false;
+    };
+    match parent {
+        Some(parent) => cache.channel(parent).is_some_and(|p| p.nsfw),
+        None => nsfw,
+    }
+}
+
+// When a message was checked, and its refusal once the check finishes
+type CachedVerdict = (Instant, Arc<OnceCell<Option<String>>>);
+
+// Verdicts per message, shared so the command and framework handlers check each message only once
+static VERDICTS: Lazy<DashMap<MessageId, CachedVerdict>> = Lazy::new(DashMap::new);
+
+/// The refusal to send instead of processing a message's images, or None if they may be processed.
+/// Images linked from an NSFW channel are never reposted outside one; beyond that, servers with a
+/// content policy have every image checked. `parts` are the words after `!cat`.
+pub async fn vet(ctx: &Context, msg: &Message, parts: &[&str]) -> Option<String> {
+    let guild_id = msg.guild_id?;
+    let cell = {
+        VERDICTS.retain(|_, (at, _)| at.elapsed() < Duration::from_secs(60));
+        VERDICTS.entry(msg.id).or_insert_with(|| (Instant::now(), Arc::new(OnceCell::new()))).1.clone()
+    };
+    cell.get_or_init(|| async {
+        let channel_nsfw = channel_is_nsfw(&ctx.cache, msg.channel_id);
+        let links_nsfw = parts.iter().filter_map(|p| source::parse_message_link(p)).any(|(channel_id, _)| channel_is_nsfw(&ctx.cache, channel_id));
+        if links_nsfw && !channel_nsfw {
+            return Some("🔞 That image is from an NSFW channel, so I'll only post it in one.".to_string());
+        }
+        let policy = config::guild(guild_id).content_policy;
+        if !applies(policy, channel_nsfw) {
+            return None;
+        }
+        let sources = source::resolve(&ctx.http, msg, parts).await;
+        let check = content_check();
+        for image in sources.iter().take(MAX_CHECKED_IMAGES) {
+            let verdict = match &check {
+                Some(check) => check.check(image.still_url()).await,
+                None => Err("No content check is configured".to_string()),
+            };
+            if let Err(e) = &verdict {
+                warn!(error = %e, guild = %guild_id, "Content check failed");
+            }
+            if let Some(refusal) = decide(policy, channel_nsfw, verdict.as_ref()) {
+                info!(guild = %guild_id, user = %msg.author.id, url = %image.url, "Refused an image under the server's content policy");
+                return Some(refusal);
+            }
+        }
+        None
+    })
+    .await
+    .clone()
+}
+
+/// `!cat config contentpolicy [off|nsfw|strict]`; `parts` are the words after `contentpolicy`.
+/// Without a mode it shows the current policy.
+pub async fn configure(ctx: &Context, msg: &Message, parts: &[&str]) {
+    let Some(guild_id) = msg.guild_id else {
+        let _ = responses::warning(&ctx.http, msg.channel_id, "Content policies are a server setting; run this in a server.").await;
+        return;
+    };
+    let Some(policy) = parts.iter().find_map(|p| ContentPolicy::parse(p)) else {
+        let text = format!(
+            "**Content policy:** `{}`\nImages linked from NSFW channels are only ever posted in NSFW channels.",
+            config::guild(guild_id).content_policy.name()
+        );
+        let _ = responses::info(&ctx.http, msg.channel_id, text).await;
+        return;
+    };
+    if !setup::can_manage(&ctx.cache, msg) {
+        let _ = responses::warning(&ctx.http, msg.channel_id, "You need the **Manage Server** permission to change the content policy.").await;
+        return;
+    }
+    if policy != ContentPolicy::Off && !check_available() {
+        let text = "This bot has no content check configured, so it can't enforce a policy. Its owner can set `CONTENT_CHECK_URL`.";
+        let _ = responses::warning(&ctx.http, msg.channel_id, text).await;
+        return;
+    }
+    match config::update_guild(guild_id, |settings| settings.content_policy = policy) {
+        Ok(_) => {
+            info!(guild = %guild_id, policy = policy.name(), "Content policy changed");
+            let text = match policy {
+                ContentPolicy::Off => "Images are no longer checked before processing.",
+                ContentPolicy::NsfwChannels => "Images are now checked before processing; flagged ones are only processed in NSFW channels.",
+                ContentPolicy::Strict => "Images are now checked before processing; flagged ones are refused everywhere.",
+            };
+            let _ = responses::success(&ctx.http, msg.channel_id, text).await;
+        }
+        Err(e) => {
+            warn!(error = %e, guild = %guild_id, "Failed to save content policy");
+            let _ = responses::error(&ctx.http, msg.channel_id, "The change applies for now, but couldn't be saved and will be lost on restart.").await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_verdict() {
+        assert_eq!(parse_verdict(r#"{"flagged": false}"#), Ok(Verdict { flagged: false, reason: None }));
+        assert_eq!(
+            parse_verdict(r#"{"flagged": true, "reason": "nudity"}"#),
+            Ok(Verdict { flagged: true, reason: Some("nudity".to_string()) })
+        );
+        assert!(parse_verdict(r#"{"reason": "nudity"}"#).is_err());
+        assert!(parse_verdict("<html>").is_err());
+    }
+
+    #[test]
+    fn test_decide() {
+        let clean = Verdict { flagged: false, reason: None };
+        let flagged = Verdict { flagged: true, reason: Some("gore".to_string()) };
+        let failed = "timed out".to_string();
+        assert_eq!(decide(ContentPolicy::Off, false, Ok(&flagged)), None);
+        assert_eq!(decide(ContentPolicy::Strict, false, Ok(&clean)), None);
+        // NSFW channels accept anything under the NSFW policy, but not under the strict one
+        assert_eq!(decide(ContentPolicy::NsfwChannels, true, Ok(&flagged)), None);
+        assert_eq!(decide(ContentPolicy::NsfwChannels, true, Err(&failed)), None);
+        assert!(decide(ContentPolicy::NsfwChannels, false, Ok(&flagged)).unwrap().contains("(gore)"));
+        assert!(decide(ContentPolicy::Strict, true, Ok(&flagged)).is_some());
+        // Failed checks refuse
+        assert!(decide(ContentPolicy::Strict, false, Err(&failed)).is_some());
+    }
+}
@@ -0,0 +1,173 @@
+// src/progress.rs
+
+use crate::eta;
+use crate::image_processing::Progress;
+use crate::outbox::Outbox;
+use crate::responses::{self, Tone};
+use crate::text_rendering;
+use catppuccin::FlavorName;
+use image::{Rgba, RgbaImage};
+use serenity::builder::{CreateAttachment, EditAttachments, EditMessage};
+use serenity::model::channel::Message;
+use std::future::Future;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::UnboundedReceiver;
+use tracing::warn;
+
+/// Cells in a text progress bar
+const BAR_WIDTH: usize = 12;
+
+/// Discord rate-limits message edits, so a status message is edited at most this often
+const EDIT_INTERVAL: Duration = Duration::from_millis(1500);
+
+/// Jobs still running after this long get a progress image under the text bar
+const IMAGE_AFTER: Duration = Duration::from_secs(4);
+
+/// Uploading an image is heavier than editing text, so image updates are spaced further apart
+const IMAGE_EDIT_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Filename used for progress images; refer to it as `attachment://progress.png`
+pub const PROGRESS_FILENAME: &str = "progress.png";
+
+/// A progress bar drawn in block characters, e.g. `▓▓▓▓▓░░░░░░░ 42%`
+pub fn bar(percent: u8) -> String {
+    let percent = percent.min(100);
+    let filled = (percent as usize * BAR_WIDTH + 50) / 100;
+    format!("{}{} {}%", "▓".repeat(filled), "░".repeat(BAR_WIDTH - filled), percent)
+}
+
+fn status_text(label: &str, percent: u8, remaining: Option<Duration>) -> String {
+    match remaining {
+        Some(remaining) => format!("{}\n`{}` · {} left", label, bar(percent), eta::describe(remaining)),
+        None => format!("{}\n`{}`", label, bar(percent)),
+    }
+}
+
+/// A progress bar image: a track in the flavor's surface color filled with its accent, and the percentage
+pub fn render_bar(percent: u8, flavor: FlavorName) -> RgbaImage {
+    let percent = percent.min(100);
+    let color = |name: &str| crate::utils::catppuccin_color_name_to_rgb(name, flavor).unwrap();
+    let opaque = |(r, g, b): (u8, u8, u8)| Rgba([r, g, b, 255]);
+    let (width, height, margin) = (480, 48, 12);
+    let mut img = RgbaImage::from_pixel(width, height, opaque(color("mantle")));
+    // Leave room on the right for the widest label, "100%"
+    let label_width = text_rendering::measure_text("100%", 18.0).0 + margin;
+    let track_width = width - label_width - 2 * margin;
+    let track_height = 16;
+    let track_y = ((height - track_height) / 2) as i32;
+    text_rendering::fill_rect(&mut img, margin as i32, track_y, track_width, track_height, opaque(color("surface0")));
+    let filled = track_width * percent as u32 / 100;
+    if filled > 0 {
+        text_rendering::fill_rect(&mut img, margin as i32, track_y, filled, track_height, opaque(color("mauve")));
+    }
+    let label = format!("{}%", percent);
+    let (label_w, label_h) = text_rendering::measure_text(&label, 18.0);
+    text_rendering::draw_text(&mut img, &label, (width - margin - label_w) as i32, ((height - label_h) / 2) as i32, 18.0, color("text"));
+    img
+}
+
+fn progress_attachment(percent: u8) -> CreateAttachment {
+    let mut buffer = std::io::Cursor::new(Vec::new());
+    // Encoding a small in-memory PNG can't fail
+    render_bar(percent, responses::theme_flavor()).write_to(&mut buffer, image::ImageFormat::Png).unwrap();
+    CreateAttachment::bytes(buffer.into_inner(), PROGRESS_FILENAME)
+}
+
+fn status_edit(label: &str, percent: u8, remaining: Option<Duration>, with_image: bool) -> EditMessage {
+    let embed = responses::embed(Tone::Info, status_text(label, percent, remaining));
+    if with_image {
+        // Replaces the previous progress image rather than adding another
+        EditMessage::new()
+            .embed(embed.image(format!("attachment://{}", PROGRESS_FILENAME)))
+            .attachments(EditAttachments::new().add(progress_attachment(percent)))
+    } else {
+        EditMessage::new().embed(embed)
+    }
+}
+
+/// Show progress on a status message until the job drops its sender, with the time left as measured
+/// from the job's own pace. Long jobs also get a progress image. Updates that arrive before the next
+/// edit is due are skipped, but the final one is always shown.
+pub async fn track(outbox: impl Outbox, status: Message, label: String, mut updates: UnboundedReceiver<Progress>) {
+    let started = Instant::now();
+    let mut shown = None;
+    let mut latest = None;
+    let mut last_edit: Option<Instant> = None;
+    // When the first update arrived and how far along it was; time spent queued before it doesn't count
+    let mut first: Option<(Instant, f64)> = None;
+    let mut remaining = None;
+    loop {
+        let update = updates.recv().await;
+        if let Some(progress) = update {
+            latest = Some(progress.percent());
+            let fraction = progress.percent() as f64 / 100.0;
+            let (first_at, first_fraction) = *first.get_or_insert((Instant::now(), fraction));
+            if first_fraction < 1.0 {
+                remaining = eta::remaining(first_at.elapsed(), (fraction - first_fraction) / (1.0 - first_fraction));
+            }
+        }
+        let finished = update.is_none();
+        let with_image = started.elapsed() >= IMAGE_AFTER;
+        let interval = if with_image { IMAGE_EDIT_INTERVAL } else { EDIT_INTERVAL };
+        let due = last_edit.is_none_or(|at| at.elapsed() >= interval);
+        if let Some(percent) = latest.filter(|p| shown != Some(*p) && (due || finished)) {
+            if let Err(why) = outbox.edit_message(status.channel_id, status.id, status_edit(&label, percent, remaining.filter(|_| !finished), with_image)).await {
+                warn!(?why, "Failed to update progress message");
+            }
+            shown = Some(percent);
+            last_edit = Some(Instant::now());
+        }
+        if finished {
+            return;
+        }
+    }
+}
+
+/// Run `work` while `track` shows its progress on `status`, if the status message could be sent.
+/// `work` must own the sender `updates` comes from, so tracking ends when it does.
+pub async fn alongside<T>(outbox: impl Outbox, status: Option<Message>, label: String, updates: UnboundedReceiver<Progress>, work: impl Future<Output = T>) -> T {
+    match status {
+        Some(status) => tokio::join!(work, track(outbox, status, label, updates)).0,
+        None => work.await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bar() {
+        assert_eq!(bar(0), "░░░░░░░░░░░░ 0%");
+        assert_eq!(bar(50), "▓▓▓▓▓▓░░░░░░ 50%");
+        assert_eq!(bar(100), "▓▓▓▓▓▓▓▓▓▓▓▓ 100%");
+        // Out-of-range input is clamped rather than overflowing the bar
+        assert_eq!(bar(250), bar(100));
+        assert!(status_text("Processing...", 25, None).ends_with("`▓▓▓░░░░░░░░░ 25%`"));
+        assert!(status_text("Processing...", 25, Some(Duration::from_secs(12))).ends_with("25%` · ~12 s left"));
+    }
+
+    #[test]
+    fn test_render_bar_fills_with_accent() {
+        let (r, g, b) = crate::utils::catppuccin_color_name_to_rgb("mauve", FlavorName::Mocha).unwrap();
+        let accent = Rgba([r, g, b, 255]);
+        let empty = render_bar(0, FlavorName::Mocha);
+        let half = render_bar(50, FlavorName::Mocha);
+        let full = render_bar(100, FlavorName::Mocha);
+        assert_eq!(empty.dimensions(), (480, 48));
+        assert!(!empty.pixels().any(|p| *p == accent));
+        // The track starts at the left margin, mid-height
+        assert_eq!(*half.get_pixel(14, 24), accent);
+        assert_ne!(*half.get_pixel(300, 24), accent);
+        assert_eq!(full.pixels().filter(|p| **p == accent).count(), 2 * half.pixels().filter(|p| **p == accent).count());
+    }
+
+    #[test]
+    fn test_status_edit_carries_the_progress_image() {
+        let with_image = serenity::json::to_value(status_edit("Processing...", 40, None, true)).unwrap();
+        assert_eq!(with_image["embeds"][0]["image"]["url"], "attachment://progress.png");
+        assert_eq!(with_image["attachments"].as_array().map(Vec::len), Some(1));
+        let text_only = serenity::json::to_value(status_edit("Processing...", 40, None, false)).unwrap();
+        assert!(text_only["embeds"][0]["image"].is_null());
+    }
+}
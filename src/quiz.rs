@@ -0,0 +1,305 @@
+// src/quiz.rs
+
+use crate::palette;
+use crate::responses::{self, Tone};
+use catppuccin::FlavorName;
+use dashmap::DashMap;
+use image::{Rgba, RgbaImage};
+use once_cell::sync::Lazy;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serenity::builder::{CreateActionRow, CreateAttachment, CreateButton, CreateInteractionResponse, CreateInteractionResponseMessage, CreateMessage};
+use serenity::http::Http;
+use serenity::json::{json, Value};
+use serenity::model::application::{ButtonStyle, ComponentInteraction};
+use serenity::model::channel::Message;
+use serenity::model::id::UserId;
+use serenity::prelude::Context;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+use tracing::{error, warn};
+
+/// How long a question takes answers
+const QUESTION_TTL: Duration = Duration::from_secs(2 * 60);
+
+/// Members shown on the leaderboard
+const LEADERBOARD_SIZE: usize = 10;
+
+const FLAVORS: [FlavorName; 4] = [FlavorName::Latte, FlavorName::Frappe, FlavorName::Macchiato, FlavorName::Mocha];
+
+/// The accent colors, the ones distinct enough to tell apart by eye
+const ACCENTS: [&str; 14] = [
+    "rosewater", "flamingo", "pink", "mauve", "red", "maroon", "peach", "yellow", "green", "teal", "sky", "sapphire", "blue", "lavender",
+];
+
+/// A question waiting for answers. Everyone gets one guess.
+struct Question {
+    choices: Vec<String>,
+    answer: usize,
+    answered: HashSet<UserId>,
+    at: Instant,
+}
+
+/// A member's quiz record
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Score {
+    pub correct: u32,
+    pub streak: u32,
+    pub best_streak: u32,
+}
+
+impl Score {
+    /// Count an answer: a right one extends the streak, a wrong one ends it
+    pub fn answer(&mut self, correct: bool) {
+        if correct {
+            self.correct += 1;
+            self.streak += 1;
+            self.best_streak = self.best_streak.max(self.streak);
+        } else {
+            self.streak = 0;
+        }
+    }
+}
+
+// Open questions by the ID in their buttons
+static QUESTIONS: Lazy<DashMap<u64, Question>> = Lazy::new(DashMap::new);
+
+// Kept in memory and written back to `QUIZ_SCORES_FILE` after every answer
+static SCORES: Lazy<RwLock<HashMap<UserId, Score>>> = Lazy::new(|| RwLock::new(load_scores()));
+
+fn scores_path() -> PathBuf {
+    std::env::var("QUIZ_SCORES_FILE").map(PathBuf::from).unwrap_or_else(|_| PathBuf::from("quiz.json"))
+}
+
+fn load_scores() -> HashMap<UserId, Score> {
+    let Ok(text) = std::fs::read_to_string(scores_path()) else {
+        return HashMap::new();
+    };
+    match serenity::json::from_str::<Value>(text) {
+        Ok(value) => scores_from_json(&value),
+        Err(e) => {
+            warn!(error = %e, "Failed to parse quiz scores; starting without them");
+            HashMap::new()
+        }
+    }
+}
+
+fn scores_to_json(scores: &HashMap<UserId, Score>) -> Value {
+    let entries = scores.iter().map(|(user_id, s)| {
+        (user_id.to_string(), json!({ "correct": s.correct, "streak": s.streak, "best_streak": s.best_streak }))
+    });
+    Value::Object(entries.collect())
+}
+
+// Records with a missing count are dropped; the rest of the file still loads
+fn scores_from_json(value: &Value) -> HashMap<UserId, Score> {
+    let Some(object) = value.as_object() else {
+        return HashMap::new();
+    };
+    let count = |s: &Value, key: &str| s.get(key)?.as_u64().map(|n| n as u32);
+    object
+        .iter()
+        .filter_map(|(user_id, s)| {
+            let user_id = UserId::new(user_id.parse::<u64>().ok().filter(|id| *id != 0)?);
+            Some((user_id, Score { correct: count(s, "correct")?, streak: count(s, "streak")?, best_streak: count(s, "best_streak")? }))
+        })
+        .collect()
+}
+
+/// Count a member's answer and write the file. The score is kept in memory even if saving fails.
+fn record_answer(user_id: UserId, correct: bool) -> (Score, Result<(), String>) {
+    let mut scores = SCORES.write().unwrap();
+    let score = scores.entry(user_id).or_default();
+    score.answer(correct);
+    let score = *score;
+    let saved = serenity::json::to_string_pretty(&scores_to_json(&scores))
+        .map_err(|e| e.to_string())
+        .and_then(|text| std::fs::write(scores_path(), text).map_err(|e| format!("Failed to save quiz scores: {e}")));
+    (score, saved)
+}
+
+/// The best members by longest streak, then by answers right
+fn leaderboard(scores: &HashMap<UserId, Score>) -> Vec<(UserId, Score)> {
+    let mut ranked: Vec<(UserId, Score)> = scores.iter().filter(|(_, s)| s.correct > 0).map(|(u, s)| (*u, *s)).collect();
+    ranked.sort_by(|(a_id, a), (b_id, b)| (b.best_streak, b.correct).cmp(&(a.best_streak, a.correct)).then(a_id.cmp(b_id)));
+    ranked.truncate(LEADERBOARD_SIZE);
+    ranked
+}
+
+/// What a question shows and asks
+struct Prompt {
+    text: String,
+    image: RgbaImage,
+    choices: Vec<String>,
+    answer: usize,
+}
+
+// The right answer among three other picks, shuffled
+fn shuffled_choices(rng: &mut impl Rng, answer: &str, pool: &[&str]) -> (Vec<String>, usize) {
+    let mut choices: Vec<&str> = pool.iter().copied().filter(|c| *c != answer).collect::<Vec<_>>().choose_multiple(rng, 3).copied().collect();
+    choices.push(answer);
+    choices.shuffle(rng);
+    let index = choices.iter().position(|c| *c == answer).unwrap();
+    (choices.into_iter().map(str::to_string).collect(), index)
+}
+
+/// A swatch of one accent color: which color is it?
+fn color_prompt(rng: &mut impl Rng) -> Prompt {
+    let flavor = *FLAVORS.choose(rng).unwrap();
+    let name = *ACCENTS.choose(rng).unwrap();
+    let (r, g, b) = crate::utils::catppuccin_color_name_to_rgb(name, flavor).unwrap();
+    let (choices, answer) = shuffled_choices(rng, name, &ACCENTS);
+    Prompt { text: format!("Which **{}** color is this?", flavor), image: RgbaImage::from_pixel(256, 256, Rgba([r, g, b, 255])), choices, answer }
+}
+
+/// A flavor's palette: which flavor is it?
+fn flavor_prompt(rng: &mut impl Rng) -> Prompt {
+    let flavor = *FLAVORS.choose(rng).unwrap();
+    let names: Vec<String> = FLAVORS.iter().map(|f| f.to_string()).collect();
+    let pool: Vec<&str> = names.iter().map(String::as_str).collect();
+    let (choices, answer) = shuffled_choices(rng, &flavor.to_string(), &pool);
+    Prompt { text: "Which flavor is this palette?".to_string(), image: palette::generate_palette_preview(flavor), choices, answer }
+}
+
+// `quiz:<question>:<choice>`
+fn parse_custom_id(custom_id: &str) -> Option<(u64, usize)> {
+    let (question, choice) = custom_id.strip_prefix("quiz:")?.split_once(':')?;
+    Some((question.parse().ok()?, choice.parse().ok()?))
+}
+
+/// `!cat quiz [color|flavor]` posts a question with a button per choice; `!cat quiz leaderboard` shows
+/// the longest streaks. `parts` are the words after `quiz`.
+pub async fn run(ctx: &Context, msg: &Message, parts: &[&str]) {
+    let kind = parts.first().map(|s| s.to_lowercase());
+    if kind.as_deref() == Some("leaderboard") {
+        let ranked = leaderboard(&SCORES.read().unwrap());
+        let text = if ranked.is_empty() {
+            "Nobody has answered a question yet. Start one with `!cat quiz`.".to_string()
+        } else {
+            let lines: Vec<String> = ranked
+                .iter()
+                .enumerate()
+                .map(|(i, (user_id, s))| format!("**{}.** <@{}> — best streak {} · {} right", i + 1, user_id, s.best_streak, s.correct))
+                .collect();
+            format!("**Quiz leaderboard**\n{}", lines.join("\n"))
+        };
+        let _ = responses::info(&ctx.http, msg.channel_id, text).await;
+        return;
+    }
+    let (id, prompt) = {
+        let mut rng = rand::thread_rng();
+        let prompt = match kind.as_deref() {
+            Some("color") => color_prompt(&mut rng),
+            Some("flavor") => flavor_prompt(&mut rng),
+            _ if rng.gen_bool(0.5) => color_prompt(&mut rng),
+            _ => flavor_prompt(&mut rng),
+        };
+        (rng.gen::<u64>(), prompt)
+    };
+    let buttons: Vec<CreateButton> = prompt
+        .choices
+        .iter()
+        .enumerate()
+        .map(|(i, choice)| CreateButton::new(format!("quiz:{}:{}", id, i)).label(choice).style(ButtonStyle::Secondary))
+        .collect();
+    let mut image_png = std::io::Cursor::new(Vec::new());
+    // Encoding an in-memory PNG can't fail
+    prompt.image.write_to(&mut image_png, image::ImageFormat::Png).unwrap();
+    let content = format!("🎲 {} Everyone gets one guess in the next {} minutes.", prompt.text, QUESTION_TTL.as_secs() / 60);
+    let builder = CreateMessage::new().content(content).components(vec![CreateActionRow::Buttons(buttons)]);
+    // Questions nobody finished answering go when the next one is asked
+    QUESTIONS.retain(|_, q| q.at.elapsed() < QUESTION_TTL);
+    QUESTIONS.insert(id, Question { choices: prompt.choices, answer: prompt.answer, answered: HashSet::new(), at: Instant::now() });
+    let attachment = CreateAttachment::bytes(image_png.into_inner(), "quiz.png");
+    if let Err(why) = msg.channel_id.send_files(&ctx.http, vec![attachment], builder).await {
+        error!(?why, "Error sending quiz question");
+        QUESTIONS.remove(&id);
+    }
+}
+
+async fn reply_privately(http: &Http, component: &ComponentInteraction, tone: Tone, text: &str) {
+    let response = CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new().embed(responses::embed(tone, text)).ephemeral(true),
+    );
+    if let Err(why) = component.create_response(http, response).await {
+        error!(?why, "Error answering a quiz button");
+    }
+}
+
+/// Handle a quiz answer button. Returns false for components that aren't one.
+pub async fn handle(http: impl AsRef<Http>, component: &ComponentInteraction) -> bool {
+    let http = http.as_ref();
+    let Some((id, choice)) = parse_custom_id(&component.data.custom_id) else {
+        return false;
+    };
+    let user_id = component.user.id;
+    // Decided before replying so the question isn't locked across an await
+    let verdict = match QUESTIONS.get_mut(&id) {
+        Some(mut q) if q.at.elapsed() < QUESTION_TTL => Some(q.answered.insert(user_id).then(|| (choice == q.answer, q.choices[q.answer].clone()))),
+        _ => None,
+    };
+    let (correct, answer) = match verdict {
+        Some(Some(verdict)) => verdict,
+        Some(None) => {
+            reply_privately(http, component, Tone::Info, "You've already answered this one.").await;
+            return true;
+        }
+        None => {
+            reply_privately(http, component, Tone::Info, "This question has closed. Start a new one with `!cat quiz`.").await;
+            return true;
+        }
+    };
+    let (score, saved) = record_answer(user_id, correct);
+    if let Err(e) = saved {
+        error!(error = %e, "Failed to save quiz scores");
+    }
+    if correct {
+        let text = format!("Right, it's **{}**! Streak: {} (best {})", answer, score.streak, score.best_streak);
+        reply_privately(http, component, Tone::Success, &text).await;
+    } else {
+        let text = format!("Not quite, it was **{}**. Streak reset (best {}).", answer, score.best_streak);
+        reply_privately(http, component, Tone::Warning, &text).await;
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_prompts() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for _ in 0..20 {
+            let prompt = color_prompt(&mut rng);
+            assert_eq!(prompt.choices.len(), 4);
+            assert_eq!(prompt.choices.iter().collect::<HashSet<_>>().len(), 4);
+            assert!(ACCENTS.contains(&prompt.choices[prompt.answer].as_str()));
+            let prompt = flavor_prompt(&mut rng);
+            assert_eq!(prompt.choices.len(), 4);
+        }
+        assert_eq!(parse_custom_id("quiz:12:3"), Some((12, 3)));
+        assert_eq!(parse_custom_id("setup:flavor"), None);
+    }
+
+    #[test]
+    fn test_scores_and_leaderboard() {
+        let mut score = Score::default();
+        for correct in [true, true, false, true] {
+            score.answer(correct);
+        }
+        assert_eq!(score, Score { correct: 3, streak: 1, best_streak: 2 });
+        let mut scores = HashMap::new();
+        scores.insert(UserId::new(1), score);
+        scores.insert(UserId::new(2), Score { correct: 1, streak: 0, best_streak: 4 });
+        scores.insert(UserId::new(3), Score::default());
+        assert_eq!(scores_from_json(&scores_to_json(&scores)), scores);
+        // Longest streak first; members who never got one right aren't listed
+        let ranked: Vec<UserId> = leaderboard(&scores).into_iter().map(|(u, _)| u).collect();
+        assert_eq!(ranked, vec![UserId::new(2), UserId::new(1)]);
+    }
+}
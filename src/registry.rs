@@ -0,0 +1,1323 @@
+// src/registry.rs
+
+use crate::pagination::{Page, PagedEmbed};
+use serenity::builder::{CreateCommand, CreateCommandOption};
+use serenity::model::application::{CommandDataOption, CommandDataOptionValue, CommandOptionType};
+
+/// What kind of value an argument takes; decides how it's shown, validated, and exposed as a slash option
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArgKind {
+    Flavor,
+    Algorithm,
+    Quality,
+    Format,
+    VideoFormat,
+    Image,
+    Gif,
+    Integer { min: i64, max: i64 },
+    Number { min: f64, max: f64 },
+    Choice(&'static [&'static str]),
+    Effect,
+    PaletteColor,
+    /// A CSS color: hex, `rgb()`, `hsl()`, or a color name
+    Hex,
+    /// A palette color name or a CSS color
+    Color,
+    /// A channel mention, e.g. `#general`
+    Channel,
+    /// A link to a Discord message
+    MessageLink,
+    Dimensions,
+    AspectRatio,
+    Grid,
+    Region,
+    Speed,
+    /// A percentage from 1 to 100, e.g. `60%`
+    Severity,
+    /// Quoted text
+    Text,
+    /// Free-form words (seeds, gradient stops); commands taking these aren't validated
+    Any,
+}
+
+impl ArgKind {
+    /// Whether a single prefix-command token is a valid value of this kind
+    pub fn accepts(&self, token: &str) -> bool {
+        use crate::utils;
+        match self {
+            ArgKind::Flavor => utils::parse_flavor(token).is_some(),
+            ArgKind::Algorithm => utils::parse_algorithm(token).is_some(),
+            ArgKind::Quality => utils::parse_quality(token).is_some(),
+            ArgKind::Format => utils::parse_format(token).is_some(),
+            ArgKind::VideoFormat => utils::parse_video_format(token).is_some(),
+            ArgKind::Image | ArgKind::Gif => {
+                token.starts_with("http://") || token.starts_with("https://") || crate::source::parse_custom_emoji(token).is_some()
+            }
+            ArgKind::Integer { .. } => token.parse::<i64>().is_ok(),
+            ArgKind::Number { .. } => token.parse::<f64>().is_ok(),
+            ArgKind::Choice(choices) => choices.iter().any(|c| c.eq_ignore_ascii_case(token)),
+            ArgKind::Effect => crate::image_processing::find_effect(token).is_some(),
+            ArgKind::PaletteColor => crate::utils::CATPPUCCIN_COLOR_NAMES.contains(&token.to_lowercase().as_str()),
+            ArgKind::Hex => utils::parse_color_literal(token).is_some(),
+            ArgKind::Color => ArgKind::PaletteColor.accepts(token) || ArgKind::Hex.accepts(token),
+            ArgKind::Channel => utils::parse_channel_mention(token).is_some(),
+            ArgKind::MessageLink => crate::source::parse_message_link(token).is_some(),
+            ArgKind::Dimensions => utils::parse_dimensions(token).is_some(),
+            ArgKind::AspectRatio => utils::parse_aspect_ratio(token).is_some(),
+            ArgKind::Grid => utils::parse_grid(token).is_some(),
+            ArgKind::Region => utils::parse_region(token).is_some(),
+            ArgKind::Speed => utils::parse_speed_factor(token).is_some(),
+            ArgKind::Severity => utils::parse_severity(token).is_some(),
+            ArgKind::Text | ArgKind::Any => true,
+        }
+    }
+
+    /// Every valid value, for kinds that take one of a fixed set
+    pub fn values(&self) -> Vec<&'static str> {
+        let table = |table: &OptionTable| table.values.iter().map(|(value, _)| *value).collect();
+        match self {
+            ArgKind::Flavor => table(&FLAVORS),
+            ArgKind::Algorithm => table(&ALGORITHMS),
+            ArgKind::Quality => table(&QUALITIES),
+            ArgKind::Format => table(&FORMATS),
+            ArgKind::VideoFormat => vec!["mp4", "webm"],
+            ArgKind::Choice(choices) => choices.to_vec(),
+            ArgKind::Effect => crate::image_processing::EFFECTS.iter().map(|e| e.name).collect(),
+            ArgKind::PaletteColor => crate::utils::CATPPUCCIN_COLOR_NAMES.to_vec(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// What a valid value looks like, for error messages
+    pub fn expected(&self) -> String {
+        let values = self.values();
+        if !values.is_empty() {
+            let values: Vec<String> = values.iter().map(|v| format!("`{}`", v)).collect();
+            return format!("one of {}", values.join(", "));
+        }
+        match self {
+            ArgKind::Image | ArgKind::Gif => "an attachment, image link, or message link".to_string(),
+            ArgKind::Integer { min, max } => format!("a whole number from {} to {}", min, max),
+            ArgKind::Number { min, max } => format!("a number from {} to {}", min, max),
+            ArgKind::Hex => "a color like `#ff0000`, `rgb(255, 0, 0)`, `hsl(0, 100%, 50%)` or `tomato`".to_string(),
+            ArgKind::Color => "a Catppuccin color name like `mauve`, or a CSS color like `#89b4fa` or `rgb(137, 180, 250)`".to_string(),
+            ArgKind::Channel => "a channel mention like #general".to_string(),
+            ArgKind::MessageLink => "a message link (right-click a message, then Copy Message Link)".to_string(),
+            ArgKind::Dimensions => "a size like `1920x1080`".to_string(),
+            ArgKind::AspectRatio => "an aspect ratio like `16:9`".to_string(),
+            ArgKind::Grid => "a grid like `8x4` (1-64 each)".to_string(),
+            ArgKind::Region => "a rectangle like `0,0,400,300` (x,y,width,height)".to_string(),
+            ArgKind::Speed => "a speed like `2x` (0.1x to 10x)".to_string(),
+            ArgKind::Severity => "a severity like `60%` (1% to 100%)".to_string(),
+            ArgKind::Text => "quoted text".to_string(),
+            _ => "anything".to_string(),
+        }
+    }
+}
+
+/// One argument of a command
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ArgSpec {
+    /// Shown in usage and used as the slash option name (lowercase, no spaces)
+    pub name: &'static str,
+    pub kind: ArgKind,
+    pub required: bool,
+}
+
+impl ArgSpec {
+    /// How the argument reads in a usage line, e.g. `<WxH>` or `[flavor]`
+    pub fn usage(&self) -> String {
+        let label = match self.kind {
+            ArgKind::Choice(choices) => choices.join("|"),
+            ArgKind::VideoFormat => "mp4|webm".to_string(),
+            ArgKind::Text => format!("\"{}\"", self.name),
+            ArgKind::Hex => "#hex".to_string(),
+            ArgKind::Color => "color|#hex".to_string(),
+            ArgKind::Channel => "#channel".to_string(),
+            ArgKind::MessageLink => "message link".to_string(),
+            ArgKind::Dimensions => "WxH".to_string(),
+            ArgKind::AspectRatio => "w:h".to_string(),
+            ArgKind::Grid => "colsxrows".to_string(),
+            ArgKind::Region => "x,y,w,h".to_string(),
+            ArgKind::Speed => "2x".to_string(),
+            ArgKind::Severity => "severity%".to_string(),
+            _ => self.name.to_string(),
+        };
+        if self.required { format!("<{}>", label) } else { format!("[{}]", label) }
+    }
+}
+
+/// A group of commands: one help page and one slash subcommand group
+pub struct Section {
+    /// Slash subcommand group name
+    pub name: &'static str,
+    pub title: &'static str,
+    pub category: &'static str,
+}
+
+/// Everything the bot knows about a command, used for help, slash registration, and validation
+#[derive(Debug)]
+pub struct CommandSpec {
+    /// The word after `!cat`, also the slash subcommand name
+    pub name: &'static str,
+    pub aliases: &'static [&'static str],
+    /// Implicit commands aren't typed (`!cat mocha [image]`); `name` only names them for slash and help
+    pub implicit: bool,
+    pub section: &'static str,
+    pub args: &'static [ArgSpec],
+    pub description: &'static str,
+    pub examples: &'static [&'static str],
+}
+
+impl CommandSpec {
+    /// The usage line, e.g. `!cat tile [mirror|blend] [size] [flavor] [image]`
+    pub fn usage(&self) -> String {
+        let mut words = vec!["!cat".to_string()];
+        if !self.implicit {
+            words.push(self.name.to_string());
+        }
+        words.extend(self.args.iter().map(|a| a.usage()));
+        words.join(" ")
+    }
+
+    /// Whether the arguments are too free-form to validate token by token
+    fn free_form(&self) -> bool {
+        self.args.iter().any(|a| matches!(a.kind, ArgKind::Text | ArgKind::Any))
+    }
+}
+
+/// A named list of option values with descriptions, shown in help and offered as slash choices
+pub struct OptionTable {
+    pub title: &'static str,
+    pub values: &'static [(&'static str, &'static str)],
+}
+
+pub static FLAVORS: OptionTable = OptionTable {
+    title: "Available Flavors",
+    values: &[
+        ("latte", "Light, warm theme"),
+        ("frappe", "Medium, balanced theme"),
+        ("macchiato", "Dark, rich theme"),
+        ("mocha", "Darkest, deep theme"),
+    ],
+};
+
+pub static ALGORITHMS: OptionTable = OptionTable {
+    title: "Available Algorithms",
+    values: &[
+        ("shepards", "Best quality (default)"),
+        ("gaussian", "Smooth gradients"),
+        ("linear", "Fast processing"),
+        ("sampling", "Jittered color sampling with spatial noise, for smooth gradients without banding"),
+        ("nearest", "Fastest, basic"),
+        ("nearest-k", "Blends only the K closest colors (`--neighbors`, default 3), keeping saturated colors vivid"),
+        ("hald", "Hald CLUT method"),
+        ("euclide", "Euclidean distance"),
+        ("mean", "Mean-based mapping"),
+        ("std", "Standard deviation method"),
+    ],
+};
+
+pub static QUALITIES: OptionTable = OptionTable {
+    title: "Quality Levels",
+    values: &[
+        ("fast", "Nearest neighbor (fastest)"),
+        ("normal", "Shepard's method (balanced)"),
+        ("high", "Gaussian sampling (best quality)"),
+    ],
+};
+
+pub static FORMATS: OptionTable = OptionTable {
+    title: "Export Formats",
+    values: &[
+        ("png", "Lossless, supports transparency"),
+        ("jpg", "Compressed, smaller files"),
+        ("webp", "Modern, good compression"),
+        ("gif", "Animated images"),
+        ("avif", "Smallest files, for sharing outside Discord (needs ffmpeg on the host)"),
+    ],
+};
+
+/// Flags accepted anywhere in a command, with descriptions
+pub static FLAGS: &[(&str, &str)] = &[
+    ("--rounded [radius]", "Round the corners (default 24)"),
+    ("--border [color] [thickness]", "Add a palette-colored border (default mauve 8)"),
+    ("--shadow", "Add a soft drop shadow"),
+    ("--bg <color|keep>", "Flatten transparency onto a palette color or hex, or keep it (jpg and avif default to base)"),
+    ("--auto-correct", "Fix white balance and stretch contrast before mapping, for dark or tinted photos"),
+    ("--hue-shift <degrees>", "Rotate hues before mapping, to steer which palette colors dominate"),
+    ("--saturate <factor>", "Scale saturation before mapping (0 to 4)"),
+    ("--brightness <factor>", "Scale brightness before mapping (0 to 4)"),
+    ("--temperature <amount>", "Warm (positive) or cool (negative) the white point before mapping (-100 to 100)"),
+    ("--power <exponent>", "Falloff of inverse-distance blending (shepards, nearest-k, hald, mean, std); higher stays closer to the nearest color"),
+    ("--sigma <width>", "Width of the gaussian kernel (default 20) or of sampling's jitter (default 8), in Lab distance; lower is sharper"),
+    ("--neighbors <1-26>", "Blend only this many of the closest palette colors (default all 26, or 3 for nearest-k)"),
+    ("--cutoff <0-1>", "Drop palette colors weighted under this fraction of the strongest (default 0)"),
+    ("--q <1-100>", "Encoder quality for jpg and avif output (default 75 for jpg, 60 for avif)"),
+    ("--premultiplied", "The image's colors are premultiplied by alpha; recover them before mapping to avoid dark fringes"),
+    ("--matte <color>", "Flatten transparency onto a palette color before mapping"),
+    ("--strict", "Use only the palette's 26 colors, dithered, for pixel-perfect theming (png or gif output)"),
+    ("--autoscale", "Shrink an image over the size limit to fit instead of refusing it"),
+    ("--debug-timing", "Add how long each step took (download, decode, mapping, encode, upload) to the reply"),
+    ("-f", "Fast mode (nearest-neighbor algorithm)"),
+];
+
+pub static SECTIONS: &[Section] = &[
+    Section { name: "basics", title: "Catppuccinifier Bot Commands", category: "Basics" },
+    Section { name: "analysis", title: "Color Analysis", category: "Analysis" },
+    Section { name: "create", title: "Effects & Generators", category: "Creative" },
+    Section { name: "edit", title: "Editing", category: "Creative" },
+    Section { name: "animate", title: "Animation", category: "Creative" },
+    Section { name: "utility", title: "Utility & Admin", category: "Admin" },
+];
+
+const fn opt(name: &'static str, kind: ArgKind) -> ArgSpec {
+    ArgSpec { name, kind, required: false }
+}
+
+const fn req(name: &'static str, kind: ArgKind) -> ArgSpec {
+    ArgSpec { name, kind, required: true }
+}
+
+const FLAVOR: ArgSpec = opt("flavor", ArgKind::Flavor);
+const ALGORITHM: ArgSpec = opt("algorithm", ArgKind::Algorithm);
+const IMAGE: ArgSpec = opt("image", ArgKind::Image);
+const GIF: ArgSpec = opt("gif", ArgKind::Gif);
+const VIDEO: ArgSpec = opt("video", ArgKind::VideoFormat);
+const TEXTURES: &[&str] = &["noise", "grain", "paper", "grid", "diamonds", "dots", "stripes"];
+
+pub static COMMANDS: &[CommandSpec] = &[
+    // --- Basics ---
+    CommandSpec {
+        name: "process",
+        aliases: &[],
+        implicit: true,
+        section: "basics",
+        args: &[FLAVOR, ALGORITHM, opt("quality", ArgKind::Quality), opt("format", ArgKind::Format), VIDEO, IMAGE],
+        description: "Catppuccinify an image (default Latte); attach or link up to 10 images to batch-process them",
+        examples: &["!cat mocha shepards [image]", "!cat frappe gaussian [image]", "!cat mocha high [image]", "!cat latte png [image]", "!cat mocha mp4 [gif]"],
+    },
+    CommandSpec {
+        name: "hex",
+        aliases: &[],
+        implicit: true,
+        section: "basics",
+        args: &[FLAVOR, req("color", ArgKind::Hex)],
+        description: "Convert a hex color to the closest Catppuccin color",
+        examples: &["!cat #FF0000", "!cat mocha #FF0000"],
+    },
+    CommandSpec {
+        name: "convert",
+        aliases: &[],
+        implicit: false,
+        section: "basics",
+        args: &[FLAVOR, opt("colors", ArgKind::Any)],
+        description: "Convert many colors at once, given inline or as an attached `.txt` or `.json` file; returns a table and a JSON mapping",
+        examples: &["!cat convert mocha #ff0000 #00ff00 #112233", "!cat convert latte [colors.json]"],
+    },
+    CommandSpec {
+        name: "convertfile",
+        aliases: &[],
+        implicit: false,
+        section: "basics",
+        args: &[FLAVOR],
+        description: "Rewrite an attached CSS file or Tailwind config with every color snapped to the palette, plus a mapping report",
+        examples: &["!cat convertfile mocha [tailwind.config.js]"],
+    },
+    CommandSpec {
+        name: "palette",
+        aliases: &[],
+        implicit: false,
+        section: "basics",
+        args: &[
+            req("which", ArgKind::Choice(&["latte", "frappe", "macchiato", "mocha", "all", "import", "export", "mine"])),
+            opt("source", ArgKind::Any),
+        ],
+        description: "Show a flavor's color palette or all four; import, share, and list your own palettes",
+        examples: &[
+            "!cat palette latte",
+            "!cat palette all",
+            "!cat palette import https://coolors.co/264653-2a9d8f-e9c46a sunset",
+            "!cat palette export sunset",
+            "!cat palette export mocha ase",
+        ],
+    },
+    CommandSpec {
+        name: "fav",
+        aliases: &[],
+        implicit: false,
+        section: "basics",
+        args: &[req("step", ArgKind::Choice(&["add", "remove", "list", "palette"])), opt("colors", ArgKind::PaletteColor), FLAVOR],
+        description: "Keep favorite palette colors and see them as a swatch board in any flavor",
+        examples: &["!cat fav add mauve pink", "!cat fav palette frappe"],
+    },
+    CommandSpec {
+        name: "theme",
+        aliases: &[],
+        implicit: false,
+        section: "basics",
+        args: &[
+            req("action", ArgKind::Choice(&["export"])),
+            req("flavor", ArgKind::Flavor),
+            req("format", ArgKind::Choice(&["xresources", "base16"])),
+        ],
+        description: "Download a flavor as an Xresources file or base16 scheme, with the upstream ANSI color mapping",
+        examples: &["!cat theme export mocha xresources", "!cat theme export latte base16"],
+    },
+    CommandSpec {
+        name: "batch",
+        aliases: &[],
+        implicit: false,
+        section: "basics",
+        args: &[FLAVOR, ALGORITHM],
+        description: "Process every attached or linked image at once (up to 10)",
+        examples: &[],
+    },
+    CommandSpec {
+        name: "compare",
+        aliases: &[],
+        implicit: false,
+        section: "basics",
+        args: &[FLAVOR, ALGORITHM, IMAGE, opt("result", ArgKind::Choice(&["last"]))],
+        description: "Send the original and processed image side by side; `last` puts your previous result beside your latest",
+        examples: &["!cat compare [image]", "!cat compare last"],
+    },
+    CommandSpec {
+        name: "again",
+        aliases: &[],
+        implicit: false,
+        section: "basics",
+        args: &[req("flavor", ArgKind::Flavor), ALGORITHM],
+        description: "Process your last image again with another flavor (kept for 30 minutes)",
+        examples: &["!cat again frappe"],
+    },
+    CommandSpec {
+        name: "pdf",
+        aliases: &[],
+        implicit: false,
+        section: "basics",
+        args: &[opt("page", ArgKind::Integer { min: 1, max: 9999 }), FLAVOR, ALGORITHM],
+        description: "Render a page of an attached PDF (page 1 by default) and catppuccinify it",
+        examples: &["!cat pdf 1 mocha"],
+    },
+    CommandSpec {
+        name: "shot",
+        aliases: &[],
+        implicit: false,
+        section: "basics",
+        args: &[req("url", ArgKind::Any), FLAVOR, ALGORITHM],
+        description: "Screenshot a website from the bot's allowed list and catppuccinify it",
+        examples: &["!cat shot https://catppuccin.com mocha"],
+    },
+    CommandSpec {
+        name: "all",
+        aliases: &[],
+        implicit: false,
+        section: "basics",
+        args: &[ALGORITHM, IMAGE],
+        description: "Process the image with all four flavors (Latte, Frappe, Macchiato, Mocha)",
+        examples: &["!cat all [image]"],
+    },
+    // --- Analysis ---
+    CommandSpec {
+        name: "info",
+        aliases: &[],
+        implicit: false,
+        section: "analysis",
+        args: &[IMAGE],
+        description: "Show an image's format, size, frames and bit depth, estimated processing times, and its closest flavor",
+        examples: &["!cat info [image]"],
+    },
+    CommandSpec {
+        name: "deltamap",
+        aliases: &[],
+        implicit: false,
+        section: "analysis",
+        args: &[FLAVOR, ALGORITHM, IMAGE],
+        description: "Heatmap of how much processing changes each pixel, by perceptual difference (ΔE)",
+        examples: &["!cat deltamap mocha [image]"],
+    },
+    CommandSpec {
+        name: "stats",
+        aliases: &[],
+        implicit: false,
+        section: "analysis",
+        args: &[IMAGE],
+        description: "Show dominant colors and suggest the best flavor",
+        examples: &[],
+    },
+    CommandSpec {
+        name: "simulate",
+        aliases: &[],
+        implicit: false,
+        section: "analysis",
+        args: &[
+            opt("type", ArgKind::Choice(&[
+                "all", "protanopia", "deuteranopia", "tritanopia", "protanomaly", "deuteranomaly", "tritanomaly", "achromatopsia",
+            ])),
+            opt("severity", ArgKind::Severity),
+            FLAVOR,
+            IMAGE,
+        ],
+        description: "Simulate color blindness at a given severity, or every type side by side with `all`",
+        examples: &["!cat simulate deuteranopia [image]", "!cat simulate protanomaly 40% [image]", "!cat simulate all [image]"],
+    },
+    CommandSpec {
+        name: "daltonize",
+        aliases: &[],
+        implicit: false,
+        section: "analysis",
+        args: &[
+            opt("type", ArgKind::Choice(&["protanopia", "deuteranopia", "tritanopia", "protanomaly", "deuteranomaly", "tritanomaly"])),
+            opt("severity", ArgKind::Severity),
+            IMAGE,
+        ],
+        description: "Correct an image so colors a color-blind viewer would confuse stay distinguishable",
+        examples: &["!cat daltonize protanopia [image]"],
+    },
+    CommandSpec {
+        name: "audit",
+        aliases: &[],
+        implicit: false,
+        section: "analysis",
+        args: &[FLAVOR, opt("message", ArgKind::MessageLink)],
+        description: "Check a message's embed colors and images against the palette, with a substitution for each color that's off (or reply to the message)",
+        examples: &["!cat audit mocha https://discord.com/channels/1/2/3"],
+    },
+    CommandSpec {
+        name: "match",
+        aliases: &[],
+        implicit: false,
+        section: "analysis",
+        args: &[FLAVOR, IMAGE],
+        description: "Find the swatches in a palette screenshot and match each to its nearest palette color",
+        examples: &["!cat match mocha [palette image]"],
+    },
+    CommandSpec {
+        name: "temperature",
+        aliases: &[],
+        implicit: false,
+        section: "analysis",
+        args: &[
+            opt("direction", ArgKind::Choice(&["warm", "cool"])),
+            opt("amount", ArgKind::Integer { min: 1, max: 100 }),
+            FLAVOR,
+            ALGORITHM,
+            IMAGE,
+        ],
+        description: "Report warm vs cool colors, or warm/cool the white point (then map it with a flavor)",
+        examples: &["!cat temperature [image]", "!cat temperature warm 20 [image]", "!cat temperature cool 30 mocha [image]"],
+    },
+    CommandSpec {
+        name: "scheme",
+        aliases: &[],
+        implicit: false,
+        section: "analysis",
+        args: &[
+            opt("type", ArgKind::Choice(&crate::palette::SCHEME_TYPES)),
+            opt("color", ArgKind::Color),
+            FLAVOR,
+            opt("export", ArgKind::Choice(&["json", "css", "gpl", "ase", "act"])),
+            IMAGE,
+        ],
+        description: "Color schemes from a base color or an image's dominant color, snapped to the palette",
+        examples: &["!cat scheme triadic mauve", "!cat scheme analogous #89b4fa mocha css", "!cat scheme triadic [image]"],
+    },
+    CommandSpec {
+        name: "diff",
+        aliases: &[],
+        implicit: false,
+        section: "analysis",
+        args: &[req("first", ArgKind::Flavor), req("second", ArgKind::Flavor)],
+        description: "Compare every named color across two flavors with Delta-E values",
+        examples: &["!cat diff latte mocha"],
+    },
+    CommandSpec {
+        name: "lut",
+        aliases: &[],
+        implicit: false,
+        section: "analysis",
+        args: &[req("action", ArgKind::Choice(&["show"])), req("flavor", ArgKind::Flavor), opt("algorithm", ArgKind::Algorithm)],
+        description: "Render hue-lightness slices of a flavor's lookup table to see how algorithms map colors (up to 3 side by side)",
+        examples: &["!cat lut show mocha shepards-method", "!cat lut show latte nearest-neighbor gaussian-rbf"],
+    },
+    // --- Effects & Generators ---
+    CommandSpec {
+        name: "gradient",
+        aliases: &[],
+        implicit: false,
+        section: "create",
+        args: &[req("colors", ArgKind::Any)],
+        description: "Gradient from palette names or hex codes; add a direction, easing, WxH, color@pos stops, or `animate`",
+        examples: &[
+            "!cat gradient rosewater mauve blue",
+            "!cat gradient radial sine 800x800 rosewater@0 mauve@30 blue@100",
+            "!cat gradient animate fast rosewater mauve blue",
+        ],
+    },
+    CommandSpec {
+        name: "texture",
+        aliases: &[],
+        implicit: false,
+        section: "create",
+        args: &[opt("type", ArgKind::Choice(TEXTURES)), FLAVOR, IMAGE],
+        description: "Overlay a Catppuccin-themed texture on an image",
+        examples: &["!cat texture dots [image]"],
+    },
+    CommandSpec {
+        name: "effect",
+        aliases: &[],
+        implicit: false,
+        section: "create",
+        args: &[req("name", ArgKind::Effect), FLAVOR, IMAGE],
+        description: "Apply a palette-tinted effect (vignette, glow, bloom, scanlines, crt, glitch)",
+        examples: &["!cat effect bloom mocha [image]", "!cat effect crt frappe [image]"],
+    },
+    CommandSpec {
+        name: "halftone",
+        aliases: &[],
+        implicit: false,
+        section: "create",
+        args: &[FLAVOR, IMAGE],
+        description: "Render the image as palette-colored halftone dots",
+        examples: &[],
+    },
+    CommandSpec {
+        name: "stitch",
+        aliases: &[],
+        implicit: false,
+        section: "create",
+        args: &[FLAVOR, opt("stitches", ArgKind::Integer { min: 10, max: 120 }), IMAGE],
+        description: "Printable cross-stitch chart with a color legend and symbol key",
+        examples: &[],
+    },
+    CommandSpec {
+        name: "posterize",
+        aliases: &[],
+        implicit: false,
+        section: "create",
+        args: &[req("levels", ArgKind::Integer { min: 2, max: 8 }), FLAVOR, IMAGE],
+        description: "Posterize to a few flat steps per channel, using only palette colors",
+        examples: &["!cat posterize 4 mocha [image]"],
+    },
+    CommandSpec {
+        name: "cel",
+        aliases: &[],
+        implicit: false,
+        section: "create",
+        args: &[FLAVOR, IMAGE],
+        description: "Cartoon-style cel shading: flat palette fills with crust outlines",
+        examples: &[],
+    },
+    CommandSpec {
+        name: "sketch",
+        aliases: &[],
+        implicit: false,
+        section: "create",
+        args: &[FLAVOR, opt("ink", ArgKind::PaletteColor), opt("wash", ArgKind::Choice(&["wash"])), IMAGE],
+        description: "Line art traced from the image's edges in a palette ink on the flavor's base",
+        examples: &["!cat sketch mocha mauve wash [image]"],
+    },
+    CommandSpec {
+        name: "icons",
+        aliases: &[],
+        implicit: false,
+        section: "create",
+        args: &[FLAVOR, ALGORITHM, IMAGE],
+        description: "Catppuccinify an image into a zip of square app icons (16 to 512 px PNGs) and a favicon.ico",
+        examples: &["!cat icons mocha [image]"],
+    },
+    CommandSpec {
+        name: "emojipack",
+        aliases: &[],
+        implicit: false,
+        section: "create",
+        args: &[ALGORITHM, opt("source", ArgKind::Any), IMAGE],
+        description: "Zip of an image or custom emoji in all four flavors at 128×128, named for upload as server emojis",
+        examples: &["!cat emojipack [image]", "!cat emojipack <:catjam:123456789>"],
+    },
+    CommandSpec {
+        name: "polaroid",
+        aliases: &[],
+        implicit: false,
+        section: "create",
+        args: &[FLAVOR, IMAGE, opt("caption", ArgKind::Text)],
+        description: "Frame the catppuccinified image as a tilted polaroid",
+        examples: &[],
+    },
+    CommandSpec {
+        name: "wallpaper",
+        aliases: &[],
+        implicit: false,
+        section: "create",
+        args: &[opt("style", ArgKind::Choice(TEXTURES)), FLAVOR, opt("size", ArgKind::Dimensions)],
+        description: "Generate a textured wallpaper in a flavor's colors",
+        examples: &["!cat wallpaper grain mocha 2560x1440"],
+    },
+    CommandSpec {
+        name: "caption",
+        aliases: &[],
+        implicit: false,
+        section: "create",
+        args: &[req("top", ArgKind::Text), opt("bottom", ArgKind::Text), FLAVOR, IMAGE],
+        description: "Catppuccinify an image and add meme-style captions",
+        examples: &[],
+    },
+    CommandSpec {
+        name: "card",
+        aliases: &[],
+        implicit: false,
+        section: "create",
+        args: &[req("text", ArgKind::Text), FLAVOR, opt("accent", ArgKind::PaletteColor)],
+        description: "Render a styled quote/announcement card",
+        examples: &["!cat card \"Welcome to the server!\" mocha"],
+    },
+    CommandSpec {
+        name: "preview",
+        aliases: &[],
+        implicit: false,
+        section: "create",
+        args: &[FLAVOR, opt("kind", ArgKind::Choice(&["code", "terminal", "editor"]))],
+        description: "Render a mock code snippet, terminal, or editor in a flavor",
+        examples: &["!cat preview latte terminal"],
+    },
+    CommandSpec {
+        name: "rice",
+        aliases: &[],
+        implicit: false,
+        section: "create",
+        args: &[FLAVOR, ALGORITHM, IMAGE],
+        description: "Compose a desktop \"rice\" mockup over your catppuccinified wallpaper",
+        examples: &[],
+    },
+    CommandSpec {
+        name: "identicon",
+        aliases: &[],
+        implicit: false,
+        section: "create",
+        args: &[opt("seed", ArgKind::Any), FLAVOR],
+        description: "Generate a deterministic avatar from a seed (defaults to your user ID)",
+        examples: &[],
+    },
+    // --- Editing ---
+    CommandSpec {
+        name: "tile",
+        aliases: &[],
+        implicit: false,
+        section: "edit",
+        args: &[opt("mode", ArgKind::Choice(&["mirror", "blend"])), opt("size", ArgKind::Integer { min: 64, max: 1024 }), FLAVOR, IMAGE],
+        description: "Seamless repeating tile (default 256px) with a 3×3 preview",
+        examples: &[],
+    },
+    CommandSpec {
+        name: "replace",
+        aliases: &[],
+        implicit: false,
+        section: "edit",
+        args: &[
+            req("source", ArgKind::Hex),
+            req("target", ArgKind::PaletteColor),
+            FLAVOR,
+            opt("tolerance", ArgKind::Number { min: 1.0, max: 100.0 }),
+            IMAGE,
+        ],
+        description: "Swap one color for a Catppuccin color, leaving the rest untouched",
+        examples: &["!cat replace #ff0000 red mocha [image]"],
+    },
+    CommandSpec {
+        name: "region",
+        aliases: &[],
+        implicit: false,
+        section: "edit",
+        args: &[opt("region", ArgKind::Region), FLAVOR, ALGORITHM, IMAGE],
+        description: "Catppuccinify only a rectangle (or attach a grayscale mask as a second image)",
+        examples: &["!cat region 0,0,400,300 mocha [image]"],
+    },
+    CommandSpec {
+        name: "background",
+        aliases: &[],
+        implicit: false,
+        section: "edit",
+        args: &[FLAVOR, ALGORITHM, IMAGE],
+        description: "Catppuccinify only the background, keeping the subject's colors",
+        examples: &[],
+    },
+    CommandSpec {
+        name: "foreground",
+        aliases: &[],
+        implicit: false,
+        section: "edit",
+        args: &[FLAVOR, ALGORITHM, IMAGE],
+        description: "Catppuccinify only the subject, keeping the background's colors",
+        examples: &[],
+    },
+    CommandSpec {
+        name: "sprites",
+        aliases: &[],
+        implicit: false,
+        section: "edit",
+        args: &[req("grid", ArgKind::Grid), FLAVOR, ALGORITHM, IMAGE],
+        description: "Process each sprite-sheet cell independently, keeping transparent margins",
+        examples: &["!cat sprites 8x4 mocha [image]"],
+    },
+    CommandSpec {
+        name: "resize",
+        aliases: &[],
+        implicit: false,
+        section: "edit",
+        args: &[req("size", ArgKind::Integer { min: 16, max: 4096 }), FLAVOR, ALGORITHM, opt("format", ArgKind::Format), IMAGE],
+        description: "Resize so the longest side is `size` pixels",
+        examples: &["!cat resize 512 mocha [image]"],
+    },
+    CommandSpec {
+        name: "crop",
+        aliases: &[],
+        implicit: false,
+        section: "edit",
+        args: &[req("ratio", ArgKind::AspectRatio), FLAVOR, ALGORITHM, opt("format", ArgKind::Format), IMAGE],
+        description: "Center-crop to an aspect ratio, e.g. 16:9",
+        examples: &[],
+    },
+    CommandSpec {
+        name: "fit",
+        aliases: &[],
+        implicit: false,
+        section: "edit",
+        args: &[req("size", ArgKind::Dimensions), FLAVOR, ALGORITHM, opt("format", ArgKind::Format), IMAGE],
+        description: "Scale and center-crop to exact dimensions",
+        examples: &["!cat fit 1920x1080 [image]"],
+    },
+    // --- Animation ---
+    CommandSpec {
+        name: "animate",
+        aliases: &[],
+        implicit: false,
+        section: "animate",
+        args: &[opt("effect", ArgKind::Choice(&["fade"])), IMAGE],
+        description: "Add an animation effect to an image as a GIF",
+        examples: &["!cat animate fade [image]"],
+    },
+    CommandSpec {
+        name: "gif",
+        aliases: &[],
+        implicit: false,
+        section: "animate",
+        args: &[req("operation", ArgKind::Choice(&["speed", "reverse", "boomerang"])), opt("factor", ArgKind::Speed), FLAVOR, ALGORITHM, VIDEO, GIF],
+        description: "Change a GIF's speed (0.1x to 10x) or frame order, optionally applying a flavor",
+        examples: &["!cat gif speed 2x mocha [gif]", "!cat gif boomerang [gif]"],
+    },
+    CommandSpec {
+        name: "morph",
+        aliases: &[],
+        implicit: false,
+        section: "animate",
+        args: &[req("from", ArgKind::Flavor), req("to", ArgKind::Flavor), opt("frames", ArgKind::Integer { min: 4, max: 48 }), ALGORITHM, VIDEO, IMAGE],
+        description: "Animate the image smoothly morphing between two flavors",
+        examples: &["!cat morph latte mocha [image]"],
+    },
+    CommandSpec {
+        name: "blendgif",
+        aliases: &[],
+        implicit: false,
+        section: "animate",
+        args: &[FLAVOR, ALGORITHM, opt("frames", ArgKind::Integer { min: 4, max: 48 }), VIDEO, opt("first", ArgKind::Image), opt("second", ArgKind::Image)],
+        description: "Looping crossfade between two catppuccinified images",
+        examples: &[],
+    },
+    CommandSpec {
+        name: "frames",
+        aliases: &[],
+        implicit: false,
+        section: "animate",
+        args: &[GIF],
+        description: "Contact sheet of a GIF's numbered frames",
+        examples: &[],
+    },
+    CommandSpec {
+        name: "frame",
+        aliases: &[],
+        implicit: false,
+        section: "animate",
+        args: &[req("number", ArgKind::Integer { min: 1, max: 10000 }), FLAVOR, ALGORITHM, GIF],
+        description: "Grab one frame as a still image, optionally catppuccinified",
+        examples: &["!cat frame 12 mocha [gif]"],
+    },
+    // --- Utility & Admin ---
+    CommandSpec {
+        name: "random",
+        aliases: &[],
+        implicit: false,
+        section: "utility",
+        args: &[opt("what", ArgKind::Choice(&["palette"]))],
+        description: "Get a random Catppuccin color, or a random palette preview",
+        examples: &["!cat random", "!cat random palette"],
+    },
+    CommandSpec {
+        name: "list",
+        aliases: &[],
+        implicit: false,
+        section: "utility",
+        args: &[],
+        description: "List all flavors, algorithms, and formats",
+        examples: &[],
+    },
+    CommandSpec {
+        name: "cancel",
+        aliases: &[],
+        implicit: false,
+        section: "utility",
+        args: &[],
+        description: "Cancel your current job",
+        examples: &[],
+    },
+    CommandSpec {
+        name: "config",
+        aliases: &[],
+        implicit: false,
+        section: "utility",
+        args: &[
+            req("setting", ArgKind::Choice(&["autochannel", "contentpolicy", "watermark", "autoscale"])),
+            opt("channel", ArgKind::Channel),
+            FLAVOR,
+            opt("mode", ArgKind::Choice(&["on", "off", "optin", "nsfw", "strict", "icon"])),
+            opt("text", ArgKind::Text),
+            opt("color", ArgKind::PaletteColor),
+            opt("opacity", ArgKind::Severity),
+        ],
+        description: "Server settings (Manage Server): `autochannel` Catppuccinifies every image posted in a channel, `optin` undoes your 🚫 opt-out; `contentpolicy` checks images first (`nsfw` or `strict`); `watermark` stamps text or the server icon on outputs; `autoscale on` shrinks oversized images to fit instead of refusing them",
+        examples: &[
+            "!cat config autochannel #art mocha",
+            "!cat config autochannel #art off",
+            "!cat config contentpolicy nsfw",
+            "!cat config watermark \"art club\" mauve 60%",
+            "!cat config autoscale on",
+        ],
+    },
+    CommandSpec {
+        name: "retheme-emojis",
+        aliases: &[],
+        implicit: false,
+        section: "utility",
+        args: &[req("flavor", ArgKind::Flavor), req("step", ArgKind::Choice(&["preview", "apply"])), ALGORITHM],
+        description: "Server emojis (Manage Expressions): `preview` shows every static emoji themed, `apply` then replaces them",
+        examples: &["!cat retheme-emojis mocha preview", "!cat retheme-emojis mocha apply"],
+    },
+    CommandSpec {
+        name: "roles",
+        aliases: &[],
+        implicit: false,
+        section: "utility",
+        args: &[req("step", ArgKind::Choice(&["suggest", "apply"])), FLAVOR],
+        description: "Role colors (Manage Roles): `suggest` charts each colored role beside its closest palette color, `apply` then recolors them",
+        examples: &["!cat roles suggest mocha", "!cat roles apply"],
+    },
+    CommandSpec {
+        name: "schedule",
+        aliases: &[],
+        implicit: false,
+        section: "utility",
+        args: &[
+            opt("cadence", ArgKind::Choice(&["daily", "weekly", "off"])),
+            opt("channel", ArgKind::Channel),
+            FLAVOR,
+            opt("images", ArgKind::Choice(&["new", "pins", "stock"])),
+        ],
+        description: "Recurring batches (Manage Server): Catppuccinify a channel's new or pinned images, or a stock image, every day or week and post them there",
+        examples: &["!cat schedule weekly #art mocha", "!cat schedule daily #showcase frappe pins", "!cat schedule daily #lounge stock", "!cat schedule off #art"],
+    },
+    CommandSpec {
+        name: "quiz",
+        aliases: &[],
+        implicit: false,
+        section: "utility",
+        args: &[opt("kind", ArgKind::Choice(&["color", "flavor", "leaderboard"]))],
+        description: "Guess the color or flavor from a swatch or palette; streaks count toward the leaderboard",
+        examples: &["!cat quiz", "!cat quiz color", "!cat quiz leaderboard"],
+    },
+    CommandSpec {
+        name: "history",
+        aliases: &[],
+        implicit: false,
+        section: "utility",
+        args: &[],
+        description: "List your last 10 conversions with buttons to run them again",
+        examples: &[],
+    },
+    CommandSpec {
+        name: "setup",
+        aliases: &[],
+        implicit: false,
+        section: "utility",
+        args: &[],
+        description: "Show the server setup panel: default flavor, announcement channel, and allowed channels (Manage Server to change)",
+        examples: &[],
+    },
+    CommandSpec {
+        name: "admin",
+        aliases: &[],
+        implicit: false,
+        section: "utility",
+        args: &[
+            req("action", ArgKind::Choice(&["flush", "reload", "pause", "resume", "queue", "broadcast"])),
+            opt("message", ArgKind::Text),
+        ],
+        description: "Owners only: flush caches, reload config, pause or resume processing, view the queue, or broadcast a notice",
+        examples: &["!cat admin queue", "!cat admin pause \"Updating, back in 5 minutes\""],
+    },
+    CommandSpec {
+        name: "help",
+        aliases: &["-h", "--help"],
+        implicit: false,
+        section: "utility",
+        args: &[],
+        description: "Show this help message",
+        examples: &[],
+    },
+];
+
+/// Look up a typed command by name or alias
+pub fn find(name: &str) -> Option<&'static CommandSpec> {
+    COMMANDS
+        .iter()
+        .filter(|c| !c.implicit)
+        .find(|c| c.name.eq_ignore_ascii_case(name) || c.aliases.iter().any(|a| a.eq_ignore_ascii_case(name)))
+}
+
+/// Why a command's arguments were rejected
+#[derive(Debug)]
+pub enum ArgError {
+    /// A token no argument accepts, with the argument it most likely meant when that can be told
+    Unrecognized { token: String, meant: Option<ArgSpec>, command: &'static CommandSpec },
+    /// A required argument wasn't given
+    Missing { arg: ArgSpec, command: &'static CommandSpec },
+}
+
+impl std::fmt::Display for ArgError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ArgError::Unrecognized { token, meant: Some(arg), command } => write!(
+                f,
+                "I didn't understand `{}` as a {}. Expected {}.\nUsage: `{}`",
+                token,
+                arg.name,
+                arg.kind.expected(),
+                command.usage()
+            ),
+            ArgError::Unrecognized { token, meant: None, command } => {
+                writeln!(f, "I didn't understand `{}`. This command takes:", token)?;
+                for arg in command.args {
+                    writeln!(f, "• {}: {}", arg.name, arg.kind.expected())?;
+                }
+                write!(f, "Usage: `{}`", command.usage())
+            }
+            ArgError::Missing { arg, command } => write!(
+                f,
+                "Missing the {}: expected {}.\nUsage: `{}`",
+                arg.name,
+                arg.kind.expected(),
+                command.usage()
+            ),
+        }
+    }
+}
+
+// Levenshtein distance, for spotting typos of known values
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// The argument an unrecognized token was most likely meant for: the one with a value
+/// within two edits of it, or the only argument that isn't an image
+fn guess_arg(spec: &CommandSpec, token: &str) -> Option<ArgSpec> {
+    let token = token.to_lowercase();
+    let closest = spec
+        .args
+        .iter()
+        .filter_map(|arg| arg.kind.values().iter().map(|v| edit_distance(&token, v)).min().map(|d| (d, arg)))
+        .filter(|(distance, _)| *distance <= 2)
+        .min_by_key(|(distance, _)| *distance);
+    if let Some((_, arg)) = closest {
+        return Some(*arg);
+    }
+    let mut candidates = spec.args.iter().filter(|a| !matches!(a.kind, ArgKind::Image | ArgKind::Gif));
+    match (candidates.next(), candidates.next()) {
+        (Some(arg), None) => Some(*arg),
+        _ => None,
+    }
+}
+
+/// Check that every argument token means something to the command.
+/// Leading required arguments are positional; the rest may come in any order.
+pub fn validate(spec: &'static CommandSpec, tokens: &[&str]) -> Result<(), ArgError> {
+    // Fast mode is accepted everywhere; post-effects are stripped before this point
+    let tokens: Vec<&str> = tokens.iter().copied().filter(|t| *t != "-f").collect();
+    let leading = spec.args.iter().take_while(|a| a.required).count();
+    for (arg, token) in spec.args[..leading].iter().zip(&tokens) {
+        if !arg.kind.accepts(token) {
+            return Err(ArgError::Unrecognized { token: token.to_string(), meant: Some(*arg), command: spec });
+        }
+    }
+    if tokens.len() < leading {
+        return Err(ArgError::Missing { arg: spec.args[tokens.len()], command: spec });
+    }
+    if spec.free_form() {
+        return Ok(());
+    }
+    for token in &tokens[leading..] {
+        if !spec.args.iter().any(|a| a.kind.accepts(token)) {
+            return Err(ArgError::Unrecognized { token: token.to_string(), meant: guess_arg(spec, token), command: spec });
+        }
+    }
+    Ok(())
+}
+
+/// Check a whole `!cat` invocation (without the `!cat`): a typed command's arguments,
+/// or for image processing and hex conversion, that every token is a known option
+pub fn check(tokens: &[&str]) -> Result<(), ArgError> {
+    if let Some(spec) = tokens.first().and_then(|name| find(name)) {
+        return validate(spec, &tokens[1..]);
+    }
+    let implicit: Vec<&'static CommandSpec> = COMMANDS.iter().filter(|c| c.implicit).collect();
+    for token in tokens.iter().filter(|t| **t != "-f") {
+        if !implicit.iter().any(|c| c.args.iter().any(|a| a.kind.accepts(token))) {
+            // Report against image processing, the usual intent
+            let process = implicit[0];
+            return Err(ArgError::Unrecognized { token: token.to_string(), meant: guess_arg(process, token), command: process });
+        }
+    }
+    Ok(())
+}
+
+fn option_table_text(table: &OptionTable) -> String {
+    let mut text = format!("**{}:**\n", table.title);
+    for (value, description) in table.values {
+        text.push_str(&format!("• `{}` - {}\n", value, description));
+    }
+    text
+}
+
+/// The flavors, algorithms, quality levels and formats with their descriptions, for `!cat list` and help
+pub fn options_text() -> String {
+    [&FLAVORS, &ALGORITHMS, &QUALITIES, &FORMATS].map(option_table_text).join("\n")
+}
+
+/// Help pages generated from the registry, grouped by category
+pub fn help_pages() -> Vec<Page> {
+    let mut pages = Vec::new();
+    for (i, section) in SECTIONS.iter().enumerate() {
+        let mut body = String::new();
+        let mut examples = Vec::new();
+        for spec in COMMANDS.iter().filter(|c| c.section == section.name) {
+            body.push_str(&format!("`{}` - {}\n", spec.usage(), spec.description));
+            examples.extend(spec.examples.iter().copied());
+        }
+        if !examples.is_empty() {
+            body.push_str("\n**Examples:**\n");
+            for example in examples {
+                body.push_str(&format!("`{}`\n", example));
+            }
+        }
+        pages.push(Page { category: section.category, title: section.title, body });
+        // Reference pages follow the basics
+        if i == 0 {
+            pages.push(Page { category: section.category, title: "Flavors, Algorithms & Formats", body: options_text() });
+            let mut flags = String::from("**Post-Effects & Flags (chainable, applied in order):**\n");
+            for (flag, description) in FLAGS {
+                flags.push_str(&format!("`{}` - {}\n", flag, description));
+            }
+            flags.push_str("Example: `!cat mocha --rounded 24 --border mauve 8 --shadow [image]`\n");
+            flags.push_str("Example: `!cat mocha --hue-shift 40 --saturate 1.3 [image]`\n");
+            pages.push(Page { category: section.category, title: "Post-Effects & Flags", body: flags });
+        }
+    }
+    pages
+}
+
+/// The paged help embed
+pub fn help() -> PagedEmbed {
+    PagedEmbed::new("help", help_pages())
+}
+
+// Discord caps descriptions at 100 characters
+fn slash_description(text: &str) -> String {
+    let plain = text.replace('`', "");
+    if plain.chars().count() <= 100 { plain } else { format!("{}…", plain.chars().take(99).collect::<String>()) }
+}
+
+fn slash_option(arg: &ArgSpec) -> CreateCommandOption {
+    let table_choices = |option: CreateCommandOption, table: &OptionTable| {
+        table.values.iter().fold(option, |o, (value, _)| o.add_string_choice(*value, *value))
+    };
+    let (kind, description) = match arg.kind {
+        ArgKind::Image | ArgKind::Gif => (CommandOptionType::Attachment, format!("The {} to use", arg.name)),
+        ArgKind::Channel => (CommandOptionType::Channel, format!("The {} to use", arg.name)),
+        ArgKind::Integer { min, max } => (CommandOptionType::Integer, format!("{} ({}-{})", arg.name, min, max)),
+        ArgKind::Number { min, max } => (CommandOptionType::Number, format!("{} ({}-{})", arg.name, min, max)),
+        _ => (CommandOptionType::String, arg.usage()),
+    };
+    let option = CreateCommandOption::new(kind, arg.name, slash_description(&description)).required(arg.required);
+    match arg.kind {
+        ArgKind::Flavor => table_choices(option, &FLAVORS),
+        ArgKind::Algorithm => table_choices(option, &ALGORITHMS),
+        ArgKind::Quality => table_choices(option, &QUALITIES),
+        ArgKind::Format => table_choices(option, &FORMATS),
+        ArgKind::VideoFormat => option.add_string_choice("mp4", "mp4").add_string_choice("webm", "webm"),
+        ArgKind::Choice(choices) => choices.iter().fold(option, |o, c| o.add_string_choice(*c, *c)),
+        ArgKind::Integer { min, max } => option.min_int_value(min.max(0) as u64).max_int_value(max as u64),
+        ArgKind::Number { min, max } => option.min_number_value(min).max_number_value(max),
+        _ => option,
+    }
+}
+
+/// The `/cat` slash command: one subcommand group per section, one subcommand per command
+pub fn slash_command() -> CreateCommand {
+    let mut command = CreateCommand::new("cat").description("Catppuccinify images and explore the palette");
+    for section in SECTIONS {
+        let mut group = CreateCommandOption::new(CommandOptionType::SubCommandGroup, section.name, section.title);
+        for spec in COMMANDS.iter().filter(|c| c.section == section.name) {
+            let mut subcommand = CreateCommandOption::new(CommandOptionType::SubCommand, spec.name, slash_description(spec.description));
+            // Discord wants required options first
+            let (required, optional): (Vec<&ArgSpec>, Vec<&ArgSpec>) = spec.args.iter().partition(|a| a.required);
+            for arg in required.into_iter().chain(optional) {
+                subcommand = subcommand.add_sub_option(slash_option(arg));
+            }
+            group = group.add_sub_option(subcommand);
+        }
+        command = command.add_option(group);
+    }
+    command
+}
+
+/// Turn a `/cat <group> <command> ...` invocation back into the equivalent prefix command,
+/// leaving out attachments (which have to be sent with the message)
+pub fn prefix_command_line(options: &[CommandDataOption]) -> Option<String> {
+    let CommandDataOptionValue::SubCommandGroup(subcommands) = &options.first()?.value else {
+        return None;
+    };
+    let subcommand = subcommands.first()?;
+    let spec = COMMANDS.iter().find(|c| c.name == subcommand.name)?;
+    let CommandDataOptionValue::SubCommand(values) = &subcommand.value else {
+        return None;
+    };
+    let mut words = vec!["!cat".to_string()];
+    if !spec.implicit {
+        words.push(spec.name.to_string());
+    }
+    // Keep the documented argument order, whatever order the user filled the options in
+    for arg in spec.args {
+        let Some(value) = values.iter().find(|v| v.name == arg.name) else { continue };
+        match &value.value {
+            CommandDataOptionValue::String(s) if arg.kind == ArgKind::Text => words.push(format!("\"{}\"", s)),
+            CommandDataOptionValue::String(s) => words.push(s.clone()),
+            CommandDataOptionValue::Integer(n) => words.push(n.to_string()),
+            CommandDataOptionValue::Number(n) => words.push(n.to_string()),
+            CommandDataOptionValue::Channel(id) => words.push(format!("<#{}>", id)),
+            _ => {}
+        }
+    }
+    Some(words.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_usage_lines() {
+        assert_eq!(find("tile").unwrap().usage(), "!cat tile [mirror|blend] [size] [flavor] [image]");
+        assert_eq!(find("sprites").unwrap().usage(), "!cat sprites <colsxrows> [flavor] [algorithm] [image]");
+        assert_eq!(find("-h").unwrap().name, "help");
+        // Implicit commands can't be typed
+        assert!(find("process").is_none());
+    }
+
+    #[test]
+    fn test_validate_reports_unknown_token() {
+        let resize = find("resize").unwrap();
+        assert!(validate(resize, &["512", "mocha", "nearest", "webp", "https://example.com/a.png", "-f"]).is_ok());
+        // A typo is matched to the argument it was meant for
+        let err = validate(resize, &["512", "mochaa"]).unwrap_err();
+        assert!(matches!(&err, ArgError::Unrecognized { token, meant: Some(arg), .. } if token == "mochaa" && arg.kind == ArgKind::Flavor));
+        let text = err.to_string();
+        assert!(text.contains("`mochaa`") && text.contains("`macchiato`") && text.contains("!cat resize <size>"));
+        // Leading required arguments are positional
+        assert!(matches!(validate(resize, &["mocha"]), Err(ArgError::Unrecognized { meant: Some(ArgSpec { name: "size", .. }), .. })));
+        assert!(matches!(validate(resize, &[]), Err(ArgError::Missing { arg: ArgSpec { name: "size", .. }, .. })));
+        // Free-form commands only need their required arguments
+        assert!(validate(find("gradient").unwrap(), &["rosewater@0", "whatever"]).is_ok());
+    }
+
+    #[test]
+    fn test_check_implicit_commands() {
+        assert!(check(&["mocha", "gaussian", "webp"]).is_ok());
+        assert!(check(&["frappe", "#ff0000"]).is_ok());
+        assert!(check(&["palette", "all"]).is_ok());
+        // Custom emojis are images too
+        assert!(check(&["mocha", "<:catjam:123>"]).is_ok());
+        let err = check(&["mocha", "gausian"]).unwrap_err();
+        assert!(matches!(err, ArgError::Unrecognized { meant: Some(ArgSpec { kind: ArgKind::Algorithm, .. }), .. }));
+        let err = check(&["blorp"]).unwrap_err();
+        assert!(matches!(err, ArgError::Unrecognized { meant: None, .. }));
+        assert!(err.to_string().contains("• flavor: one of `latte`"));
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("mocha", "mocha"), 0);
+        assert_eq!(edit_distance("mochaa", "mocha"), 1);
+        assert_eq!(edit_distance("gausian", "gaussian"), 1);
+        assert_eq!(edit_distance("", "png"), 3);
+    }
+
+    #[test]
+    fn test_registry_fits_discord_limits() {
+        let mut names = std::collections::HashSet::new();
+        for section in SECTIONS {
+            let count = COMMANDS.iter().filter(|c| c.section == section.name).count();
+            assert!(count > 0 && count <= 25, "{} has {} commands", section.name, count);
+        }
+        for spec in COMMANDS {
+            assert!(names.insert(spec.name), "duplicate command {}", spec.name);
+            assert!(SECTIONS.iter().any(|s| s.name == spec.section));
+            assert!(spec.args.len() <= 25);
+            let mut arg_names = std::collections::HashSet::new();
+            for arg in spec.args {
+                assert!(arg_names.insert(arg.name), "duplicate option {} in {}", arg.name, spec.name);
+                assert!(arg.name.chars().all(|c| c.is_ascii_lowercase()));
+            }
+        }
+    }
+
+    #[test]
+    fn test_help_pages_cover_every_command() {
+        let pages = help_pages();
+        let text: String = pages.iter().map(|p| p.body.as_str()).collect();
+        for spec in COMMANDS {
+            assert!(text.contains(&spec.usage()), "{} missing from help", spec.name);
+        }
+        // Discord rejects embed descriptions over 4096 characters
+        assert!(pages.iter().all(|p| p.body.chars().count() <= 4096));
+    }
+
+    #[test]
+    fn test_options_text_lists_values_the_parsers_accept() {
+        let text = options_text();
+        for (algorithm, _) in ALGORITHMS.values {
+            assert!(text.contains(&format!("`{}`", algorithm)));
+            assert!(crate::utils::parse_algorithm(algorithm).is_some(), "{} isn't a known algorithm", algorithm);
+        }
+        for (flavor, _) in FLAVORS.values {
+            assert!(crate::utils::parse_flavor(flavor).is_some());
+        }
+    }
+}
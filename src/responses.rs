@@ -0,0 +1,140 @@
+// src/responses.rs
+
+use crate::outbox::Outbox;
+use catppuccin::FlavorName;
+use once_cell::sync::Lazy;
+use serenity::builder::{CreateAttachment, CreateEmbed, CreateEmbedFooter, CreateMessage};
+use serenity::model::channel::Message;
+use serenity::model::id::ChannelId;
+
+/// What a reply is telling the user, which picks its embed color
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Tone {
+    /// Help, branding, and results that aren't a status
+    Accent,
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+impl Tone {
+    fn color_name(self) -> &'static str {
+        match self {
+            Tone::Accent => "mauve",
+            Tone::Info => "blue",
+            Tone::Success => "green",
+            Tone::Warning => "peach",
+            Tone::Error => "red",
+        }
+    }
+}
+
+/// Styling shared by every embed the bot sends
+pub struct EmbedTheme {
+    pub flavor: FlavorName,
+    pub footer: String,
+}
+
+// Read once from the environment (after dotenv has loaded), so hosts can restyle replies without a rebuild
+static THEME: Lazy<EmbedTheme> = Lazy::new(|| EmbedTheme {
+    flavor: std::env::var("EMBED_FLAVOR").ok().and_then(|s| crate::utils::parse_flavor(&s)).unwrap_or(FlavorName::Mocha),
+    footer: std::env::var("EMBED_FOOTER").unwrap_or_else(|_| "Catppuccinifier 🐱".to_string()),
+});
+
+/// Filename used for swatch thumbnails; refer to it as `attachment://swatch.png`
+pub const SWATCH_FILENAME: &str = "swatch.png";
+
+/// The embed color for a tone in the configured flavor
+pub fn tone_color(tone: Tone) -> u32 {
+    let (r, g, b) = crate::utils::catppuccin_color_name_to_rgb(tone.color_name(), THEME.flavor).unwrap();
+    u32::from_be_bytes([0, r, g, b])
+}
+
+/// The branding footer shown on every embed
+pub fn footer_text() -> &'static str {
+    &THEME.footer
+}
+
+/// The flavor replies are styled in
+pub fn theme_flavor() -> FlavorName {
+    THEME.flavor
+}
+
+/// A themed embed with the text as its description and the branding footer
+pub fn embed(tone: Tone, text: impl Into<String>) -> CreateEmbed {
+    CreateEmbed::new()
+        .description(text)
+        .color(tone_color(tone))
+        .footer(CreateEmbedFooter::new(&THEME.footer))
+}
+
+/// A small solid-color PNG to attach alongside an embed and show as its thumbnail
+pub fn swatch_attachment(color: (u8, u8, u8)) -> CreateAttachment {
+    swatch_png(image::RgbaImage::from_pixel(64, 64, image::Rgba([color.0, color.1, color.2, 255])))
+}
+
+/// A swatch split down the middle, `left` beside `right`, for showing a color next to what it became
+pub fn comparison_swatch_attachment(left: (u8, u8, u8), right: (u8, u8, u8)) -> CreateAttachment {
+    swatch_png(comparison_swatch(left, right))
+}
+
+fn comparison_swatch(left: (u8, u8, u8), right: (u8, u8, u8)) -> image::RgbaImage {
+    image::RgbaImage::from_fn(128, 64, |x, _| {
+        let (r, g, b) = if x < 64 { left } else { right };
+        image::Rgba([r, g, b, 255])
+    })
+}
+
+fn swatch_png(swatch: image::RgbaImage) -> CreateAttachment {
+    let mut buffer = std::io::Cursor::new(Vec::new());
+    // Encoding a tiny in-memory PNG can't fail
+    swatch.write_to(&mut buffer, image::ImageFormat::Png).unwrap();
+    CreateAttachment::bytes(buffer.into_inner(), SWATCH_FILENAME)
+}
+
+/// Send a themed embed in place of a plain text message
+pub async fn send(outbox: impl Outbox, channel_id: ChannelId, tone: Tone, text: impl Into<String>) -> serenity::Result<Message> {
+    let builder = CreateMessage::new().embed(embed(tone, text));
+    outbox.send_message(channel_id, builder).await
+}
+
+pub async fn info(outbox: impl Outbox, channel_id: ChannelId, text: impl Into<String>) -> serenity::Result<Message> {
+    send(outbox, channel_id, Tone::Info, text).await
+}
+
+pub async fn success(outbox: impl Outbox, channel_id: ChannelId, text: impl Into<String>) -> serenity::Result<Message> {
+    send(outbox, channel_id, Tone::Success, text).await
+}
+
+pub async fn warning(outbox: impl Outbox, channel_id: ChannelId, text: impl Into<String>) -> serenity::Result<Message> {
+    send(outbox, channel_id, Tone::Warning, text).await
+}
+
+pub async fn error(outbox: impl Outbox, channel_id: ChannelId, text: impl Into<String>) -> serenity::Result<Message> {
+    send(outbox, channel_id, Tone::Error, text).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tone_colors_are_distinct_palette_colors() {
+        let tones = [Tone::Accent, Tone::Info, Tone::Success, Tone::Warning, Tone::Error];
+        for (i, a) in tones.iter().enumerate() {
+            let (r, g, b) = crate::utils::catppuccin_color_name_to_rgb(a.color_name(), THEME.flavor).unwrap();
+            assert_eq!(tone_color(*a), (r as u32) << 16 | (g as u32) << 8 | b as u32);
+            for other in &tones[i + 1..] {
+                assert_ne!(tone_color(*a), tone_color(*other));
+            }
+        }
+    }
+
+    #[test]
+    fn test_comparison_swatch_halves() {
+        let swatch = comparison_swatch((255, 0, 0), (0, 0, 255));
+        assert_eq!(swatch.get_pixel(10, 32).0, [255, 0, 0, 255]);
+        assert_eq!(swatch.get_pixel(100, 32).0, [0, 0, 255, 255]);
+    }
+}
@@ -0,0 +1,214 @@
+// src/role_colors.rs
+
+use crate::config;
+use crate::convert::{self, Conversion};
+use crate::responses;
+use crate::setup;
+use catppuccin::FlavorName;
+use dashmap::DashMap;
+use image::{Rgba, RgbaImage};
+use once_cell::sync::Lazy;
+use serenity::builder::{CreateAttachment, CreateMessage, EditRole};
+use serenity::model::channel::Message;
+use serenity::model::guild::Role;
+use serenity::model::id::{GuildId, RoleId};
+use serenity::model::Colour;
+use serenity::prelude::Context;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// How long suggestions can be applied for; after that the roles may have changed
+const SUGGESTION_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// Most roles one suggestion covers, keeping the chart a readable height; the highest roles come first
+const MAX_ROLES: usize = 50;
+
+/// Longest role name drawn on the chart before it's cut short
+const MAX_NAME_CHARS: usize = 22;
+
+/// A role's color and the palette color suggested for it
+struct Suggestion {
+    role: RoleId,
+    role_name: String,
+    conversion: Conversion,
+}
+
+/// Suggestions waiting for `apply`
+struct Pending {
+    flavor: FlavorName,
+    suggestions: Vec<Suggestion>,
+    at: Instant,
+}
+
+// One set of suggestions per server; a new `suggest` replaces the last
+static PENDING: Lazy<DashMap<GuildId, Pending>> = Lazy::new(DashMap::new);
+
+/// The roles with a color, highest first, each mapped to the flavor's closest named color. Roles
+/// without a color (shown in the default name color) are left out, as are ones already in the palette.
+fn suggest(roles: &[Role], flavor: FlavorName) -> Vec<Suggestion> {
+    let mut colored: Vec<&Role> = roles.iter().filter(|r| r.colour.0 != 0).collect();
+    colored.sort_by_key(|r| std::cmp::Reverse(r.position));
+    colored
+        .into_iter()
+        .filter_map(|role| {
+            let rgb = role.colour.tuple();
+            let conversion = convert::convert_colors(&[(role.name.clone(), rgb)], flavor).pop()?;
+            if conversion.matched == rgb {
+                return None;
+            }
+            Some(Suggestion { role: role.id, role_name: role.name.clone(), conversion })
+        })
+        .take(MAX_ROLES)
+        .collect()
+}
+
+fn short_name(name: &str) -> String {
+    if name.chars().count() <= MAX_NAME_CHARS {
+        name.to_string()
+    } else {
+        format!("{}…", name.chars().take(MAX_NAME_CHARS - 1).collect::<String>())
+    }
+}
+
+/// A before/after chart on the flavor's base: each role's current color and name, then the
+/// suggested color's swatch, name and hex, with the role name redrawn in it
+fn render_chart(suggestions: &[Suggestion], flavor: FlavorName) -> RgbaImage {
+    use crate::text_rendering::{draw_text, fill_rect};
+    let swatch: u32 = 32;
+    let margin: u32 = 10;
+    let label_size = 18.0;
+    let name_column: u32 = 220;
+    let label_column: u32 = 180;
+    let width = margin + swatch + margin + name_column + margin + swatch + margin + label_column + margin + name_column + margin;
+    let height = margin + (suggestions.len().max(1) as u32) * (swatch + margin);
+    let rgba = |(r, g, b): (u8, u8, u8)| Rgba([r, g, b, 255]);
+    let base = crate::utils::catppuccin_color_name_to_rgb("base", flavor).unwrap();
+    let subtext = crate::utils::catppuccin_color_name_to_rgb("subtext0", flavor).unwrap();
+    let mut img = RgbaImage::from_pixel(width, height, rgba(base));
+    for (i, suggestion) in suggestions.iter().enumerate() {
+        let conversion = &suggestion.conversion;
+        let name = short_name(&suggestion.role_name);
+        let y = margin + i as u32 * (swatch + margin);
+        let label_y = (y + swatch / 2) as i32 - 9;
+        let mut x = margin;
+        fill_rect(&mut img, x as i32, y as i32, swatch, swatch, rgba(conversion.rgb));
+        x += swatch + margin;
+        draw_text(&mut img, &name, x as i32, label_y, label_size, conversion.rgb);
+        x += name_column + margin;
+        fill_rect(&mut img, x as i32, y as i32, swatch, swatch, rgba(conversion.matched));
+        x += swatch + margin;
+        let hex = format!("#{:02x}{:02x}{:02x}", conversion.matched.0, conversion.matched.1, conversion.matched.2);
+        draw_text(&mut img, &format!("{} {}", conversion.name, hex), x as i32, label_y, label_size, subtext);
+        x += label_column + margin;
+        draw_text(&mut img, &name, x as i32, label_y, label_size, conversion.matched);
+    }
+    img
+}
+
+/// `!cat roles suggest|apply [flavor]`: map every colored role to the flavor's closest named color.
+/// `suggest` posts a before/after chart; `apply` then recolors the roles it listed. Both need Manage Roles.
+pub async fn run(ctx: &Context, msg: &Message, parts: &[&str]) {
+    let Some(guild_id) = msg.guild_id else {
+        let _ = responses::warning(&ctx.http, msg.channel_id, "Role colors are a server thing; run this in a server.").await;
+        return;
+    };
+    if !setup::can_manage_roles(&ctx.cache, msg) {
+        let _ = responses::warning(&ctx.http, msg.channel_id, "You need the **Manage Roles** permission to recolor this server's roles.").await;
+        return;
+    }
+    let flavor = parts.iter().find_map(|s| crate::utils::parse_flavor(s));
+    if parts.first().is_some_and(|p| p.eq_ignore_ascii_case("apply")) {
+        apply(ctx, msg, guild_id, flavor).await;
+    } else {
+        suggest_for(ctx, msg, guild_id, flavor.unwrap_or_else(|| config::default_flavor(Some(guild_id)))).await;
+    }
+}
+
+async fn suggest_for(ctx: &Context, msg: &Message, guild_id: GuildId, flavor: FlavorName) {
+    let roles = match guild_id.roles(&ctx.http).await {
+        Ok(roles) => roles.into_values().collect::<Vec<_>>(),
+        Err(why) => {
+            warn!(?why, guild = %guild_id, "Failed to list server roles");
+            let _ = responses::error(&ctx.http, msg.channel_id, "Couldn't list this server's roles.").await;
+            return;
+        }
+    };
+    let suggestions = suggest(&roles, flavor);
+    if suggestions.is_empty() {
+        let text = format!("Every colored role here is already a {} color.", flavor);
+        let _ = responses::success(&ctx.http, msg.channel_id, text).await;
+        return;
+    }
+    let mut chart_png = std::io::Cursor::new(Vec::new());
+    // Encoding an in-memory PNG can't fail
+    render_chart(&suggestions, flavor).write_to(&mut chart_png, image::ImageFormat::Png).unwrap();
+    let content = format!(
+        "**Role colors in {}**: {} roles to change\nRun `!cat roles apply {}` within {} minutes to recolor them. \
+         Roles above the bot's own can't be changed.",
+        flavor,
+        suggestions.len(),
+        flavor.identifier(),
+        SUGGESTION_TTL.as_secs() / 60,
+    );
+    PENDING.insert(guild_id, Pending { flavor, suggestions, at: Instant::now() });
+    let attachment = CreateAttachment::bytes(chart_png.into_inner(), format!("role_colors_{}.png", flavor.identifier()));
+    let _ = msg.channel_id.send_files(&ctx.http, vec![attachment], CreateMessage::new().content(content)).await;
+}
+
+async fn apply(ctx: &Context, msg: &Message, guild_id: GuildId, flavor: Option<FlavorName>) {
+    // Taking the suggestions out of the map also keeps a second `apply` from running alongside this one
+    let taken = PENDING.remove_if(&guild_id, |_, p| p.at.elapsed() < SUGGESTION_TTL && flavor.is_none_or(|f| f == p.flavor));
+    let Some((_, pending)) = taken else {
+        let text = format!("Run `!cat roles suggest` first, then apply it within {} minutes.", SUGGESTION_TTL.as_secs() / 60);
+        let _ = responses::warning(&ctx.http, msg.channel_id, text).await;
+        return;
+    };
+    info!(guild = %guild_id, user = %msg.author.id, roles = pending.suggestions.len(), flavor = pending.flavor.identifier(), "Recoloring server roles");
+    let _typing = msg.channel_id.start_typing(&ctx.http);
+    let reason = format!("Catppuccin {} role colors requested by {}", pending.flavor, msg.author.name);
+    let mut failed = Vec::new();
+    for suggestion in &pending.suggestions {
+        let (r, g, b) = suggestion.conversion.matched;
+        let builder = EditRole::new().colour(Colour::from_rgb(r, g, b)).audit_log_reason(&reason);
+        if let Err(why) = guild_id.edit_role(ctx, suggestion.role, builder).await {
+            warn!(?why, guild = %guild_id, role = %suggestion.role, "Failed to recolor role");
+            failed.push(suggestion.role_name.clone());
+        }
+    }
+    let recolored = pending.suggestions.len() - failed.len();
+    let text = format!("Recolored {} of {} roles with {} colors.", recolored, pending.suggestions.len(), pending.flavor);
+    if failed.is_empty() {
+        let _ = responses::success(&ctx.http, msg.channel_id, text).await;
+    } else {
+        let text = format!("{}\nCouldn't change `{}`; they may be above the bot's highest role.", text, failed.join("`, `"));
+        let _ = responses::warning(&ctx.http, msg.channel_id, text).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn role(id: u64, name: &str, colour: u32, position: u16) -> Role {
+        let mut role = Role::default();
+        role.id = RoleId::new(id);
+        role.name = name.to_string();
+        role.colour = Colour::new(colour);
+        role.position = position;
+        role
+    }
+
+    #[test]
+    fn test_suggest_role_colors() {
+        let roles = vec![role(1, "Member", 0xff0000, 1), role(2, "Mods", 0x00ff00, 5), role(3, "everyone", 0, 0), role(4, "Mauve", 0xcba6f7, 2)];
+        let suggestions = suggest(&roles, FlavorName::Mocha);
+        // Uncolored roles and ones already in the palette are left alone, and the highest role comes first
+        let names: Vec<&str> = suggestions.iter().map(|s| s.role_name.as_str()).collect();
+        assert_eq!(names, vec!["Mods", "Member"]);
+        assert_eq!(suggestions[1].conversion.name, "red");
+        let chart = render_chart(&suggestions, FlavorName::Mocha);
+        assert_eq!(chart.height(), 10 + 2 * (32 + 10));
+        assert_eq!(chart.get_pixel(10 + 16, 10 + 16).0, [0, 255, 0, 255]);
+        assert_eq!(short_name("a role name that goes on and on").chars().count(), MAX_NAME_CHARS);
+    }
+}
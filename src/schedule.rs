@@ -0,0 +1,378 @@
+// src/schedule.rs
+
+use crate::admin;
+use crate::autochannel;
+use crate::config::{self, Cadence, Schedule, ScheduleSource};
+use crate::jobs::{self, Job};
+use crate::memory;
+use crate::responses;
+use crate::setup;
+use crate::source::{self, SourceKind};
+use crate::utils;
+use serenity::builder::{CreateAttachment, CreateMessage, GetMessages};
+use serenity::http::Http;
+use serenity::model::channel::Message;
+use catppuccin::FlavorName;
+use rand::seq::SliceRandom;
+use serenity::model::id::{ChannelId, GuildId, MessageId};
+use serenity::prelude::Context;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{error, info, warn};
+
+/// How often the scheduler looks for batches that are due
+const CHECK_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Most scheduled channels per server
+const MAX_SCHEDULES: usize = 5;
+
+/// Most images one run posts: Discord's attachment limit for a message
+const MAX_IMAGES_PER_RUN: usize = 10;
+
+/// Pages of 100 messages read looking for new images
+const MAX_MESSAGE_PAGES: usize = 5;
+
+/// Files in `DAILY_IMAGE_DIR` that stock schedules pick from
+const STOCK_EXTENSIONS: [&str; 5] = ["png", "jpg", "jpeg", "webp", "gif"];
+
+// Discord's epoch (2015-01-01) in Unix milliseconds, for turning a time into a message ID
+const DISCORD_EPOCH_MS: u64 = 1_420_070_400_000;
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// Whether a schedule is due to run
+pub fn is_due(schedule: &Schedule, now: u64) -> bool {
+    now >= schedule.last_run + schedule.cadence.period_secs()
+}
+
+/// The lowest message ID a message sent at `secs` could have, for fetching everything posted since
+fn message_id_at(secs: u64) -> MessageId {
+    MessageId::new((secs * 1000).saturating_sub(DISCORD_EPOCH_MS).max(1) << 22)
+}
+
+/// Check for due batches every few minutes for as long as the bot runs. Schedules live in the
+/// server settings file, so ones that came due while the bot was down run shortly after it starts.
+pub fn spawn(http: Arc<Http>) {
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(CHECK_INTERVAL).await;
+            // Due batches wait for intake to resume rather than being skipped
+            if admin::is_paused() {
+                continue;
+            }
+            let now = now_secs();
+            for (guild_id, channel_id, schedule) in config::schedules() {
+                if is_due(&schedule, now) {
+                    run(&http, guild_id, channel_id, schedule, now).await;
+                }
+            }
+        }
+    });
+}
+
+fn is_stock_image(path: &Path) -> bool {
+    path.extension().and_then(|e| e.to_str()).is_some_and(|e| STOCK_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+}
+
+/// Whether `DAILY_IMAGE_DIR` or `DAILY_IMAGE_URL` is set, so stock schedules have something to post
+pub fn has_stock_source() -> bool {
+    let config = config::get();
+    config.daily_image_dir.is_some() || config.daily_image_url.is_some()
+}
+
+/// A random stock image and its filename: a file from `DAILY_IMAGE_DIR`, or else whatever `DAILY_IMAGE_URL`
+/// answers with. Only sources the bot's operator picked are used, never anything from a server.
+async fn stock_image() -> Result<(Vec<u8>, String), String> {
+    let config = config::get();
+    if let Some(dir) = &config.daily_image_dir {
+        let files: Vec<PathBuf> = std::fs::read_dir(dir)
+            .map_err(|e| format!("Failed to read DAILY_IMAGE_DIR: {e}"))?
+            .filter_map(|entry| Some(entry.ok()?.path()))
+            .filter(|path| is_stock_image(path))
+            .collect();
+        let path = files.choose(&mut rand::thread_rng()).ok_or("DAILY_IMAGE_DIR has no images")?;
+        let bytes = std::fs::read(path).map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+        let filename = path.file_name().map_or("daily.png".into(), |name| name.to_string_lossy().into_owned());
+        return Ok((bytes, filename));
+    }
+    if let Some(url) = &config.daily_image_url {
+        let bytes = source::fetch_url(url).await?;
+        return Ok((bytes, "daily.png".to_string()));
+    }
+    Err("Neither DAILY_IMAGE_DIR nor DAILY_IMAGE_URL is set".to_string())
+}
+
+// "Daily Catppuccin" or "Weekly Catppuccin"
+fn stock_title(cadence: Cadence) -> &'static str {
+    match cadence {
+        Cadence::Daily => "Daily Catppuccin",
+        Cadence::Weekly => "Weekly Catppuccin",
+    }
+}
+
+// A stock schedule's run: one random image in a random flavor
+async fn post_stock(http: &Http, guild_id: GuildId, channel_id: ChannelId, cadence: Cadence, now: u64) {
+    let (bytes, name) = match stock_image().await {
+        Ok(image) => image,
+        Err(e) => {
+            warn!(error = %e, guild = %guild_id, channel = %channel_id, "No stock image for a scheduled run");
+            return;
+        }
+    };
+    let flavor = *[FlavorName::Latte, FlavorName::Frappe, FlavorName::Macchiato, FlavorName::Mocha].choose(&mut rand::thread_rng()).unwrap();
+    let autoscale = config::guild(guild_id).autoscale;
+    // Nobody sent a message for this, so the job is the bot's own, stamped with the run's time
+    let bot = match http.get_current_user().await {
+        Ok(bot) => bot.id,
+        Err(why) => {
+            warn!(?why, "Failed to look up the bot's user for a scheduled run");
+            return;
+        }
+    };
+    let job = Job { command: "schedule", user: bot, message: message_id_at(now) };
+    let _reservation = memory::reserve(autochannel::memory_estimate(&bytes), config::get().max_job_memory).await;
+    let (output, extension) = match jobs::run(job, move || autochannel::catppuccinify(&bytes, flavor, autoscale)).await {
+        Ok(Ok(done)) => done,
+        Ok(Err(e)) => {
+            warn!(error = %e, image = %name, "Scheduled processing failed");
+            return;
+        }
+        // Already logged with its error ID
+        Err(_) => return,
+    };
+    let stem = name.rsplit_once('.').map_or(name.as_str(), |(stem, _)| stem);
+    let filename = utils::sanitize_filename(&format!("{}_{}.{}", stem, flavor.identifier(), extension), extension);
+    let content = format!("**{}** · {}", stock_title(cadence), flavor);
+    match channel_id.send_files(http, vec![CreateAttachment::bytes(output, filename)], CreateMessage::new().content(content)).await {
+        Ok(_) => info!(guild = %guild_id, channel = %channel_id, flavor = flavor.identifier(), "Stock image posted"),
+        Err(why) => warn!(?why, guild = %guild_id, channel = %channel_id, "Failed to post a stock image"),
+    }
+}
+
+// The messages a run looks at: those posted since the last run (oldest first), or the pins
+async fn candidate_messages(http: &Http, channel_id: ChannelId, schedule: &Schedule) -> serenity::Result<Vec<Message>> {
+    if schedule.source == ScheduleSource::Pins {
+        return channel_id.pins(http).await;
+    }
+    let mut after = message_id_at(schedule.last_run);
+    let mut messages = Vec::new();
+    for _ in 0..MAX_MESSAGE_PAGES {
+        let mut page = channel_id.messages(http, GetMessages::new().after(after).limit(100)).await?;
+        page.sort_by_key(|m| m.id);
+        let Some(last) = page.last() else { break };
+        after = last.id;
+        let full = page.len() == 100;
+        messages.extend(page);
+        if !full {
+            break;
+        }
+    }
+    Ok(messages)
+}
+
+// One run of a schedule: catppuccinify the channel's images and post them in one message
+async fn run(http: &Http, guild_id: GuildId, channel_id: ChannelId, schedule: Schedule, now: u64) {
+    // Recorded up front so a run that fails part way isn't retried every few minutes
+    let saved = config::update_guild(guild_id, |settings| {
+        if let Some(saved) = settings.schedules.get_mut(&channel_id) {
+            saved.last_run = now;
+        }
+    });
+    if let Err(e) = saved {
+        error!(error = %e, guild = %guild_id, channel = %channel_id, "Failed to save a schedule's last run");
+    }
+    if schedule.source == ScheduleSource::Stock {
+        post_stock(http, guild_id, channel_id, schedule.cadence, now).await;
+        return;
+    }
+    let messages = match candidate_messages(http, channel_id, &schedule).await {
+        Ok(messages) => messages,
+        Err(why) => {
+            warn!(?why, guild = %guild_id, channel = %channel_id, "Failed to read a scheduled channel");
+            return;
+        }
+    };
+    let images: Vec<(Message, source::ImageSource)> = messages
+        .into_iter()
+        .filter(|m| !m.author.bot)
+        .filter_map(|m| {
+            let image = source::message_sources(&m).into_iter().find(|s| s.kind == SourceKind::Image)?;
+            Some((m, image))
+        })
+        .take(MAX_IMAGES_PER_RUN)
+        .collect();
+    if images.is_empty() {
+        info!(guild = %guild_id, channel = %channel_id, "Scheduled run found no images");
+        return;
+    }
+    let flavor = schedule.flavor;
+    let autoscale = config::guild(guild_id).autoscale;
+    let mut attachments = Vec::new();
+    let mut links = Vec::new();
+    for (message, image) in &images {
+        let bytes = match source::fetch_url(&image.url).await {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                warn!(error = %e, url = %image.url, "Failed to download image for a scheduled run");
+                continue;
+            }
+        };
+        let _reservation = memory::reserve(autochannel::memory_estimate(&bytes), config::get().max_job_memory).await;
+        match jobs::run(Job::new("schedule", message), move || autochannel::catppuccinify(&bytes, flavor, autoscale)).await {
+            Ok(Ok((output, extension))) => {
+                let stem = image.filename.rsplit_once('.').map_or(image.filename.as_str(), |(stem, _)| stem);
+                let filename = utils::sanitize_filename(&format!("{}_{}.{}", stem, flavor.identifier(), extension), extension);
+                attachments.push(CreateAttachment::bytes(output, filename));
+                links.push(message.link());
+            }
+            Ok(Err(e)) => warn!(error = %e, url = %image.url, "Scheduled processing failed"),
+            // Already logged with its error ID
+            Err(_) => {}
+        }
+    }
+    if attachments.is_empty() {
+        return;
+    }
+    let period = match schedule.cadence {
+        Cadence::Daily => "day",
+        Cadence::Weekly => "week",
+    };
+    let what = match schedule.source {
+        ScheduleSource::New => format!("new images from the past {}", period),
+        // Stock runs posted their image above
+        ScheduleSource::Pins | ScheduleSource::Stock => "pinned images".to_string(),
+    };
+    let content = format!("**Catppuccinified with {}**: {} {}\n{}", flavor, attachments.len(), what, links.join("\n"));
+    let count = attachments.len();
+    match channel_id.send_files(http, attachments, CreateMessage::new().content(content)).await {
+        Ok(_) => info!(guild = %guild_id, channel = %channel_id, images = count, "Scheduled run posted"),
+        Err(why) => warn!(?why, guild = %guild_id, channel = %channel_id, "Failed to post a scheduled run"),
+    }
+}
+
+// The server's schedules, one per line
+fn describe_schedules(schedules: &[(ChannelId, Schedule)]) -> String {
+    if schedules.is_empty() {
+        return "No scheduled channels yet. Add one with `!cat schedule weekly #channel mocha`.".to_string();
+    }
+    let lines: Vec<String> = schedules
+        .iter()
+        .map(|(channel_id, s)| {
+            let next = s.last_run + s.cadence.period_secs();
+            match s.source {
+                ScheduleSource::Stock => format!("<#{}> — {}, a stock image in a random flavor · next <t:{}:R>", channel_id, stock_title(s.cadence), next),
+                ScheduleSource::Pins => format!("<#{}> — {} {}, pinned images · next <t:{}:R>", channel_id, s.cadence.name(), s.flavor, next),
+                ScheduleSource::New => format!("<#{}> — {} {}, new images · next <t:{}:R>", channel_id, s.cadence.name(), s.flavor, next),
+            }
+        })
+        .collect();
+    format!("**Scheduled channels:**\n{}", lines.join("\n"))
+}
+
+/// `!cat schedule <daily|weekly|off> [#channel] [flavor] [new|pins|stock]`; `parts` are the words after
+/// `schedule`. The channel defaults to the current one; with no cadence it lists the server's schedules.
+pub async fn configure(ctx: &Context, msg: &Message, parts: &[&str]) {
+    let Some(guild_id) = msg.guild_id else {
+        let _ = responses::warning(&ctx.http, msg.channel_id, "Schedules are a server setting; run this in a server.").await;
+        return;
+    };
+    let off = parts.iter().any(|p| p.eq_ignore_ascii_case("off"));
+    let cadence = parts.iter().find_map(|p| Cadence::parse(p));
+    if cadence.is_none() && !off {
+        let mut schedules: Vec<(ChannelId, Schedule)> = config::guild(guild_id).schedules.into_iter().collect();
+        schedules.sort_by_key(|(channel_id, _)| *channel_id);
+        let _ = responses::info(&ctx.http, msg.channel_id, describe_schedules(&schedules)).await;
+        return;
+    }
+    if !setup::can_manage(&ctx.cache, msg) {
+        let _ = responses::warning(&ctx.http, msg.channel_id, "You need the **Manage Server** permission to change schedules.").await;
+        return;
+    }
+    let channel_id = parts.iter().find_map(|p| utils::parse_channel_mention(p)).unwrap_or(msg.channel_id);
+    let settings = config::guild(guild_id);
+    if !off && !settings.schedules.contains_key(&channel_id) && settings.schedules.len() >= MAX_SCHEDULES {
+        let text = format!("This server already has {} scheduled channels, the most it can have. Turn one off first.", MAX_SCHEDULES);
+        let _ = responses::warning(&ctx.http, msg.channel_id, text).await;
+        return;
+    }
+    let source = parts.iter().find_map(|p| ScheduleSource::parse(p)).unwrap_or_default();
+    if !off && source == ScheduleSource::Stock && !has_stock_source() {
+        let _ = responses::warning(&ctx.http, msg.channel_id, "Stock images aren't set up for this bot; its operator needs to set `DAILY_IMAGE_DIR` or `DAILY_IMAGE_URL`.").await;
+        return;
+    }
+    let schedule = cadence.map(|cadence| Schedule {
+        cadence,
+        flavor: parts.iter().find_map(|p| utils::parse_flavor(p)).unwrap_or_else(|| config::default_flavor(Some(guild_id))),
+        source,
+        last_run: now_secs(),
+    });
+    let saved = config::update_guild(guild_id, |settings| match (off, &schedule) {
+        (false, Some(schedule)) => {
+            settings.schedules.insert(channel_id, schedule.clone());
+        }
+        _ => {
+            settings.schedules.remove(&channel_id);
+        }
+    });
+    match saved {
+        Ok(_) => {
+            info!(guild = %guild_id, channel = %channel_id, off, "Schedule changed");
+            let text = match schedule.filter(|_| !off) {
+                Some(s) if s.source == ScheduleSource::Stock => format!(
+                    "**{}**: a stock image in a random flavor will be posted in <#{}> {}, starting <t:{}:R>.",
+                    stock_title(s.cadence),
+                    channel_id,
+                    s.cadence.name(),
+                    s.last_run + s.cadence.period_secs()
+                ),
+                Some(s) => format!(
+                    "{} images in <#{}> will be Catppuccinified with {} and posted there {}, starting <t:{}:R>.",
+                    if s.source == ScheduleSource::Pins { "Pinned" } else { "New" },
+                    channel_id,
+                    s.flavor,
+                    s.cadence.name(),
+                    s.last_run + s.cadence.period_secs()
+                ),
+                None => format!("Stopped the schedule in <#{}>.", channel_id),
+            };
+            let _ = responses::success(&ctx.http, msg.channel_id, text).await;
+        }
+        Err(e) => {
+            error!(error = %e, guild = %guild_id, "Failed to save schedule");
+            let _ = responses::error(&ctx.http, msg.channel_id, "The change applies for now, but couldn't be saved and will be lost on restart.").await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use catppuccin::FlavorName;
+
+    #[test]
+    fn test_schedule_timing() {
+        let schedule = Schedule { cadence: Cadence::Daily, flavor: FlavorName::Mocha, source: ScheduleSource::New, last_run: 1_000_000 };
+        assert!(!is_due(&schedule, 1_000_000 + 24 * 60 * 60 - 1));
+        assert!(is_due(&schedule, 1_000_000 + 24 * 60 * 60));
+        // 2015-01-01 plus one second is 1000ms into Discord's epoch
+        assert_eq!(message_id_at(1_420_070_401).get(), 1000 << 22);
+        assert_eq!(message_id_at(0).get(), 1 << 22);
+    }
+
+    #[test]
+    fn test_describe_schedules() {
+        assert!(describe_schedules(&[]).starts_with("No scheduled channels"));
+        let schedule = Schedule { cadence: Cadence::Weekly, flavor: FlavorName::Frappe, source: ScheduleSource::Pins, last_run: 100 };
+        assert_eq!(
+            describe_schedules(&[(ChannelId::new(5), schedule.clone())]),
+            format!("**Scheduled channels:**\n<#5> — weekly Frappé, pinned images · next <t:{}:R>", 100 + 7 * 24 * 60 * 60)
+        );
+        let stock = Schedule { source: ScheduleSource::Stock, cadence: Cadence::Daily, ..schedule };
+        assert!(describe_schedules(&[(ChannelId::new(5), stock)]).contains("<#5> — Daily Catppuccin, a stock image in a random flavor"));
+        assert!(is_stock_image(Path::new("stock/Cat.JPG")));
+        assert!(!is_stock_image(Path::new("stock/notes.txt")));
+    }
+}
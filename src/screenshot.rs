@@ -0,0 +1,138 @@
+// src/screenshot.rs
+
+use crate::config;
+use crate::icc;
+use crate::image_processing;
+use crate::jobs::{self, Job};
+use crate::responses;
+use crate::source::MAX_DOWNLOAD_BYTES;
+use crate::utils;
+use serenity::builder::{CreateAttachment, CreateMessage};
+use serenity::json::json;
+use serenity::model::channel::Message;
+use serenity::prelude::Context;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Viewport the page is rendered at
+const VIEWPORT: (u32, u32) = (1280, 800);
+
+/// How long the screenshot endpoint gets to load and render a page
+const SHOT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The host of a website URL, if it's one `!cat shot` may capture: https only, no credentials, ports or
+/// IP addresses, and the host is an allowed site or one of its subdomains
+fn allowed_host(url: &str, allowed: &[String]) -> Result<String, String> {
+    let url = reqwest::Url::parse(url).map_err(|_| "That doesn't look like a website URL.".to_string())?;
+    if url.scheme() != "https" {
+        return Err("Only `https://` sites can be captured.".to_string());
+    }
+    if !url.username().is_empty() || url.password().is_some() || url.port().is_some() {
+        return Err("Site URLs can't include a login or a port.".to_string());
+    }
+    // `domain` is None for IP addresses
+    let host = url.domain().ok_or("Sites have to be given by name, not by IP address.")?.trim_end_matches('.').to_lowercase();
+    if allowed.iter().any(|site| host == *site || host.ends_with(&format!(".{}", site))) {
+        Ok(host)
+    } else {
+        Err(format!("`{}` isn't on this bot's list of sites that can be captured.", host))
+    }
+}
+
+/// Posts `{"url": ..., "width": ..., "height": ...}` to `SCREENSHOT_URL` and expects the image back
+async fn capture(endpoint: &str, token: Option<&str>, url: &str) -> Result<Vec<u8>, String> {
+    let client = reqwest::Client::builder().timeout(SHOT_TIMEOUT).build().map_err(|e| e.to_string())?;
+    let body = json!({ "url": url, "width": VIEWPORT.0, "height": VIEWPORT.1 });
+    let mut request = client.post(endpoint).header("Content-Type", "application/json").body(serenity::json::to_string(&body).map_err(|e| e.to_string())?);
+    if let Some(token) = token {
+        request = request.bearer_auth(token);
+    }
+    let response = request.send().await.map_err(|e| format!("Screenshot request failed: {e}"))?;
+    if !response.status().is_success() {
+        return Err(format!("Screenshot endpoint returned {}", response.status()));
+    }
+    if response.content_length().is_some_and(|len| len > MAX_DOWNLOAD_BYTES as u64) {
+        return Err("Screenshot is too large".to_string());
+    }
+    let bytes = response.bytes().await.map_err(|e| format!("Failed to read the screenshot: {e}"))?;
+    if bytes.len() > MAX_DOWNLOAD_BYTES {
+        return Err("Screenshot is too large".to_string());
+    }
+    Ok(bytes.to_vec())
+}
+
+/// `!cat shot <website url> [flavor] [algorithm]`: screenshot an allowed site through the configured
+/// headless-browser endpoint and catppuccinify it
+pub async fn run(ctx: &Context, msg: &Message, parts: &[&str]) {
+    let config = config::get();
+    let Some(endpoint) = config.screenshot_url.clone() else {
+        let _ = responses::warning(&ctx.http, msg.channel_id, "Website screenshots aren't set up on this bot.").await;
+        return;
+    };
+    let Some(url) = parts.iter().find(|s| s.starts_with("http://") || s.starts_with("https://")) else {
+        let _ = responses::warning(&ctx.http, msg.channel_id, "Usage: `!cat shot <website url> [flavor]`, e.g. `!cat shot https://catppuccin.com mocha`").await;
+        return;
+    };
+    // Discord users wrap links in <> to hide the preview
+    let url = url.trim_start_matches('<').trim_end_matches('>');
+    let host = match allowed_host(url, &config.screenshot_hosts) {
+        Ok(host) => host,
+        Err(e) => {
+            let _ = responses::warning(&ctx.http, msg.channel_id, e).await;
+            return;
+        }
+    };
+    let flavor = parts.iter().find_map(|s| utils::parse_flavor(s)).unwrap_or_else(|| config::default_flavor(msg.guild_id));
+    let algorithm = parts.iter().find_map(|s| utils::parse_algorithm(s)).unwrap_or("shepards-method");
+    let _typing = msg.channel_id.start_typing(&ctx.http);
+    info!(user = %msg.author.id, %host, "Capturing website screenshot");
+    let shot = match capture(&endpoint, config.screenshot_token.as_deref(), url).await {
+        Ok(shot) => shot,
+        Err(e) => {
+            warn!(error = %e, %host, "Website screenshot failed");
+            let _ = responses::error(&ctx.http, msg.channel_id, "Couldn't capture that page right now. Please try again later.").await;
+            return;
+        }
+    };
+    let result = jobs::run(Job::new("shot", msg), move || {
+        let img = icc::decode(&shot).map_err(|_| "The screenshot endpoint didn't send back an image.".to_string())?;
+        let processed = image_processing::process_image_with_palette(&img, flavor, algorithm);
+        let mut buffer = std::io::Cursor::new(Vec::new());
+        processed.write_to(&mut buffer, image::ImageFormat::Png).map_err(|e| format!("Failed to encode image: {e}"))?;
+        Ok::<_, String>(icc::tag_srgb_png(buffer.into_inner()))
+    })
+    .await;
+    let output = match result {
+        Ok(Ok(output)) => output,
+        Ok(Err(e)) => {
+            let _ = responses::error(&ctx.http, msg.channel_id, e).await;
+            return;
+        }
+        Err(panic) => {
+            let _ = responses::error(&ctx.http, msg.channel_id, panic.to_string()).await;
+            return;
+        }
+    };
+    let filename = utils::sanitize_filename(&format!("{}_{}.png", host, flavor.identifier()), "png");
+    let content = format!("`{}` in {}", host, flavor);
+    let _ = msg.channel_id.send_files(&ctx.http, vec![CreateAttachment::bytes(output, filename)], CreateMessage::new().content(content)).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allowed_host() {
+        let allowed = vec!["catppuccin.com".to_string()];
+        assert_eq!(allowed_host("https://catppuccin.com/palette", &allowed).unwrap(), "catppuccin.com");
+        assert_eq!(allowed_host("https://Docs.Catppuccin.com", &allowed).unwrap(), "docs.catppuccin.com");
+        assert!(allowed_host("https://evilcatppuccin.com", &allowed).is_err());
+        assert!(allowed_host("https://catppuccin.com.evil.net", &allowed).is_err());
+        assert!(allowed_host("http://catppuccin.com", &allowed).is_err());
+        assert!(allowed_host("https://user@catppuccin.com", &allowed).is_err());
+        assert!(allowed_host("https://catppuccin.com:8443", &allowed).is_err());
+        assert!(allowed_host("https://127.0.0.1", &["127.0.0.1".to_string()]).is_err());
+        assert!(allowed_host("https://catppuccin.com", &[]).is_err());
+    }
+}
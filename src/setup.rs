@@ -0,0 +1,258 @@
+// src/setup.rs
+
+use crate::config::{self, GuildSettings};
+use crate::registry::FLAVORS;
+use crate::responses::{self, Tone};
+use catppuccin::FlavorName;
+use serenity::builder::{
+    CreateActionRow, CreateButton, CreateEmbed, CreateInteractionResponse, CreateInteractionResponseMessage, CreateMessage,
+    CreateSelectMenu, CreateSelectMenuKind, CreateSelectMenuOption,
+};
+use serenity::cache::Cache;
+use serenity::http::Http;
+use serenity::model::application::{ButtonStyle, ComponentInteraction, ComponentInteractionDataKind};
+use serenity::model::channel::{ChannelType, GuildChannel, Message};
+use serenity::model::guild::Guild;
+use serenity::model::id::ChannelId;
+use serenity::model::permissions::Permissions;
+use tracing::{error, info, warn};
+
+/// Discord's limit on values picked in one select menu
+const MAX_ALLOWED_CHANNELS: u8 = 25;
+
+/// A change made in the setup panel. Like the help pages, the panel is stateless: its components are
+/// identified by custom_id (`setup:flavor`, `setup:announce`, `setup:channels`, `setup:everywhere`,
+/// `setup:done`) and the settings live in the config store, so panels keep working across restarts.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SetupChoice {
+    DefaultFlavor(FlavorName),
+    /// `None` when the selection was cleared
+    AnnouncementChannel(Option<ChannelId>),
+    AllowedChannels(Vec<ChannelId>),
+    /// Answer in every channel again
+    Everywhere,
+    /// Close the panel
+    Done,
+}
+
+impl SetupChoice {
+    /// The change a component press makes, or None if the component isn't part of the panel
+    pub fn parse(custom_id: &str, kind: &ComponentInteractionDataKind) -> Option<Self> {
+        match (custom_id.strip_prefix("setup:")?, kind) {
+            ("flavor", ComponentInteractionDataKind::StringSelect { values }) => {
+                values.first().and_then(|v| crate::utils::parse_flavor(v)).map(SetupChoice::DefaultFlavor)
+            }
+            ("announce", ComponentInteractionDataKind::ChannelSelect { values }) => Some(SetupChoice::AnnouncementChannel(values.first().copied())),
+            ("channels", ComponentInteractionDataKind::ChannelSelect { values }) => Some(SetupChoice::AllowedChannels(values.clone())),
+            ("everywhere", ComponentInteractionDataKind::Button) => Some(SetupChoice::Everywhere),
+            ("done", ComponentInteractionDataKind::Button) => Some(SetupChoice::Done),
+            _ => None,
+        }
+    }
+
+    pub fn apply(&self, settings: &mut GuildSettings) {
+        match self {
+            SetupChoice::DefaultFlavor(flavor) => settings.default_flavor = Some(*flavor),
+            SetupChoice::AnnouncementChannel(channel) => settings.announcement_channel = *channel,
+            SetupChoice::AllowedChannels(channels) => settings.allowed_channels = channels.clone(),
+            SetupChoice::Everywhere => settings.allowed_channels.clear(),
+            SetupChoice::Done => {}
+        }
+    }
+}
+
+// What's configured so far, as embed lines
+fn summary(settings: &GuildSettings) -> String {
+    let flavor = settings.default_flavor.unwrap_or(FlavorName::Latte);
+    let announcement = settings.announcement_channel.map_or("Not set".to_string(), |c| format!("<#{}>", c));
+    let allowed = if settings.allowed_channels.is_empty() {
+        "Every channel".to_string()
+    } else {
+        settings.allowed_channels.iter().map(|c| format!("<#{}>", c)).collect::<Vec<_>>().join(", ")
+    };
+    format!("**Default flavor:** {}\n**Announcement channel:** {}\n**Answers commands in:** {}", flavor, announcement, allowed)
+}
+
+fn embed(settings: &GuildSettings, finished: bool) -> CreateEmbed {
+    if finished {
+        let text = format!("{}\n\nRun `!cat setup` to change these later.", summary(settings));
+        return responses::embed(Tone::Success, text).title("✅ Setup complete");
+    }
+    let text = format!(
+        "Thanks for adding me! I turn images into the Catppuccin palette: send `!cat mocha` with an image attached, \
+         or `!cat help` for everything else.\n\nAnyone with **Manage Server** can set me up below.\n\n{}",
+        summary(settings)
+    );
+    responses::embed(Tone::Accent, text).title("🐱 Catppuccinifier setup")
+}
+
+fn components(settings: &GuildSettings) -> Vec<CreateActionRow> {
+    let current_flavor = settings.default_flavor.unwrap_or(FlavorName::Latte);
+    let flavor_options = FLAVORS
+        .values
+        .iter()
+        .map(|(value, description)| {
+            CreateSelectMenuOption::new(*value, *value)
+                .description(*description)
+                .default_selection(crate::utils::parse_flavor(value) == Some(current_flavor))
+        })
+        .collect();
+    let text_channels = || Some(vec![ChannelType::Text, ChannelType::News]);
+    let flavor = CreateSelectMenu::new("setup:flavor", CreateSelectMenuKind::String { options: flavor_options })
+        .placeholder("Default flavor");
+    let announce = CreateSelectMenu::new(
+        "setup:announce",
+        CreateSelectMenuKind::Channel { channel_types: text_channels(), default_channels: settings.announcement_channel.map(|c| vec![c]) },
+    )
+    .placeholder("Announcement channel")
+    .min_values(0)
+    .max_values(1);
+    let channels = CreateSelectMenu::new(
+        "setup:channels",
+        CreateSelectMenuKind::Channel {
+            channel_types: text_channels(),
+            default_channels: Some(settings.allowed_channels.clone()).filter(|c| !c.is_empty()),
+        },
+    )
+    .placeholder("Only answer in these channels")
+    .min_values(0)
+    .max_values(MAX_ALLOWED_CHANNELS);
+    let buttons = vec![
+        CreateButton::new("setup:everywhere").label("Answer everywhere").style(ButtonStyle::Secondary),
+        CreateButton::new("setup:done").label("Done").style(ButtonStyle::Success),
+    ];
+    vec![
+        CreateActionRow::SelectMenu(flavor),
+        CreateActionRow::SelectMenu(announce),
+        CreateActionRow::SelectMenu(channels),
+        CreateActionRow::Buttons(buttons),
+    ]
+}
+
+/// The setup panel for a server, showing its current settings
+pub fn message(settings: &GuildSettings) -> CreateMessage {
+    CreateMessage::new().embed(embed(settings, false)).components(components(settings))
+}
+
+// Where to greet a server: its system channel, or else its top text channel
+fn welcome_channel(guild: &Guild) -> Option<ChannelId> {
+    guild.system_channel_id.or_else(|| {
+        let mut text_channels: Vec<&GuildChannel> = guild.channels.values().filter(|c| c.kind == ChannelType::Text).collect();
+        text_channels.sort_by_key(|c| c.position);
+        text_channels.first().map(|c| c.id)
+    })
+}
+
+/// Post the setup panel in a server the bot has just joined
+pub async fn welcome(http: impl AsRef<Http>, guild: &Guild) {
+    info!(guild = %guild.id, name = %guild.name, "Joined a server");
+    let Some(channel_id) = welcome_channel(guild) else {
+        warn!(guild = %guild.id, "No channel to post the setup panel in");
+        return;
+    };
+    if let Err(why) = channel_id.send_message(http.as_ref(), message(&config::guild(guild.id))).await {
+        warn!(?why, guild = %guild.id, %channel_id, "Failed to post the setup panel");
+    }
+}
+
+/// Whether a message's author has Manage Server, worked out from the cache. False outside servers.
+pub fn can_manage(cache: &Cache, msg: &Message) -> bool {
+    author_permissions(cache, msg).is_some_and(|p| p.manage_guild())
+}
+
+/// Whether the author of a message may add and remove the server's emojis (Manage Expressions)
+pub fn can_manage_emojis(cache: &Cache, msg: &Message) -> bool {
+    author_permissions(cache, msg).is_some_and(|p| p.manage_guild_expressions())
+}
+
+/// Whether the author of a message may edit the server's roles (Manage Roles)
+pub fn can_manage_roles(cache: &Cache, msg: &Message) -> bool {
+    author_permissions(cache, msg).is_some_and(|p| p.manage_roles())
+}
+
+// The author's permissions in the channel a message was sent in, if the server is cached
+fn author_permissions(cache: &Cache, msg: &Message) -> Option<Permissions> {
+    let (guild_id, member) = (msg.guild_id?, msg.member.as_deref()?);
+    let guild = cache.guild(guild_id)?;
+    let channel = guild.channels.get(&msg.channel_id)?;
+    Some(guild.partial_member_permissions_in(channel, msg.author.id, member))
+}
+
+// An ephemeral reply to whoever pressed a component
+async fn reply_privately(http: &Http, component: &ComponentInteraction, tone: Tone, text: &str) {
+    let response = CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new().embed(responses::embed(tone, text)).ephemeral(true),
+    );
+    if let Err(why) = component.create_response(http, response).await {
+        error!(?why, "Error responding to setup panel");
+    }
+}
+
+/// Apply a setup panel press and redraw the panel; returns whether the component was part of one
+pub async fn handle(http: impl AsRef<Http>, component: &ComponentInteraction) -> bool {
+    let http = http.as_ref();
+    let Some(choice) = SetupChoice::parse(&component.data.custom_id, &component.data.kind) else {
+        return false;
+    };
+    let Some(guild_id) = component.guild_id else {
+        return true;
+    };
+    let can_manage = component.member.as_ref().and_then(|m| m.permissions).is_some_and(|p| p.manage_guild());
+    if !can_manage {
+        reply_privately(http, component, Tone::Warning, "You need the **Manage Server** permission to change these settings.").await;
+        return true;
+    }
+    let settings = match config::update_guild(guild_id, |settings| choice.apply(settings)) {
+        Ok(settings) => settings,
+        Err(e) => {
+            error!(error = %e, guild = %guild_id, "Failed to save server settings");
+            reply_privately(http, component, Tone::Error, "Your change applies for now, but couldn't be saved and will be lost on restart.").await;
+            return true;
+        }
+    };
+    info!(guild = %guild_id, user = %component.user.id, ?choice, "Server settings changed");
+    let finished = choice == SetupChoice::Done;
+    let update = CreateInteractionResponseMessage::new()
+        .embed(embed(&settings, finished))
+        .components(if finished { Vec::new() } else { components(&settings) });
+    if let Err(why) = component.create_response(http, CreateInteractionResponse::UpdateMessage(update)).await {
+        error!(?why, "Error updating setup panel");
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_apply_choices() {
+        let strings = |v: &[&str]| ComponentInteractionDataKind::StringSelect { values: v.iter().map(|s| s.to_string()).collect() };
+        let channels = |v: &[u64]| ComponentInteractionDataKind::ChannelSelect { values: v.iter().map(|id| ChannelId::new(*id)).collect() };
+        let mut settings = GuildSettings::default();
+        for (custom_id, kind) in [
+            ("setup:flavor", strings(&["mocha"])),
+            ("setup:announce", channels(&[10])),
+            ("setup:channels", channels(&[11, 12])),
+        ] {
+            SetupChoice::parse(custom_id, &kind).unwrap().apply(&mut settings);
+        }
+        assert_eq!(settings.default_flavor, Some(FlavorName::Mocha));
+        assert_eq!(settings.announcement_channel, Some(ChannelId::new(10)));
+        assert_eq!(settings.allowed_channels, vec![ChannelId::new(11), ChannelId::new(12)]);
+        // Clearing a selection and the "everywhere" button undo them
+        SetupChoice::parse("setup:announce", &channels(&[])).unwrap().apply(&mut settings);
+        SetupChoice::parse("setup:everywhere", &ComponentInteractionDataKind::Button).unwrap().apply(&mut settings);
+        assert_eq!((settings.announcement_channel, settings.allowed_channels.len()), (None, 0));
+        // Other components aren't ours
+        assert_eq!(SetupChoice::parse("help:next:0", &ComponentInteractionDataKind::Button), None);
+        assert_eq!(SetupChoice::parse("setup:flavor", &strings(&["espresso"])), None);
+    }
+
+    #[test]
+    fn test_summary() {
+        assert!(summary(&GuildSettings::default()).contains("**Default flavor:** Latte"));
+        let settings = GuildSettings { allowed_channels: vec![ChannelId::new(3), ChannelId::new(4)], ..Default::default() };
+        assert!(summary(&settings).ends_with("**Answers commands in:** <#3>, <#4>"));
+    }
+}
@@ -0,0 +1,337 @@
+// src/source.rs
+
+use crate::error::BotError;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serenity::http::{Http, LightMethod, Request, Route};
+use serenity::json::Value;
+use serenity::model::channel::{Attachment, Embed, Message, MessageReferenceKind};
+use serenity::model::sticker::StickerItem;
+use serenity::model::id::{ChannelId, MessageId};
+use tracing::warn;
+
+/// Largest file the bot will download, in bytes
+pub const MAX_DOWNLOAD_BYTES: usize = 8 * 1024 * 1024;
+
+/// Longest stretch of a video that is converted for processing, in seconds
+const MAX_VIDEO_SECONDS: u32 = 10;
+
+static IMAGE_URL_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(https?://[\w\-./%?=&]+\.(png|jpe?g|gif|bmp|webp|tiff?|exr|avif|jxl))$").unwrap());
+static MESSAGE_LINK_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^https://(?:(?:ptb|canary)\.)?discord(?:app)?\.com/channels/(\d+|@me)/(\d+)/(\d+)$").unwrap());
+static CUSTOM_EMOJI_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"^<(a?):(\w{2,32}):(\d+)>$").unwrap());
+
+/// Whether a source is a still/animated image or a video that has to be converted first
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SourceKind {
+    Image,
+    Video,
+}
+
+/// An image a command can work on, wherever it was found
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageSource {
+    pub url: String,
+    /// A file name for naming the output
+    pub filename: String,
+    pub kind: SourceKind,
+    /// A still frame to fall back on for videos (an embed's thumbnail)
+    pub poster: Option<String>,
+}
+
+impl ImageSource {
+    fn image(url: &str, filename: Option<&str>) -> Self {
+        ImageSource {
+            url: url.to_string(),
+            filename: filename.map(str::to_string).unwrap_or_else(|| filename_from_url(url).to_string()),
+            kind: SourceKind::Image,
+            poster: None,
+        }
+    }
+
+    fn video(url: &str, filename: Option<&str>, poster: Option<&str>) -> Self {
+        ImageSource { kind: SourceKind::Video, poster: poster.map(str::to_string), ..ImageSource::image(url, filename) }
+    }
+
+    /// A URL that decodes as an image: the poster frame for videos
+    pub fn still_url(&self) -> &str {
+        match self.kind {
+            SourceKind::Image => &self.url,
+            SourceKind::Video => self.poster.as_deref().unwrap_or(&self.url),
+        }
+    }
+}
+
+/// The file name at the end of a URL, without any query string
+pub fn filename_from_url(url: &str) -> &str {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    path.rsplit('/').next().filter(|name| !name.is_empty()).unwrap_or("image")
+}
+
+/// Parse a Discord message link into its channel and message IDs
+pub fn parse_message_link(s: &str) -> Option<(ChannelId, MessageId)> {
+    let caps = MESSAGE_LINK_REGEX.captures(s)?;
+    let channel_id = caps.get(2)?.as_str().parse::<u64>().ok().filter(|id| *id != 0)?;
+    let message_id = caps.get(3)?.as_str().parse::<u64>().ok().filter(|id| *id != 0)?;
+    Some((ChannelId::new(channel_id), MessageId::new(message_id)))
+}
+
+/// The image behind a custom emoji like `<:catjam:123>` (or `<a:catjam:123>` when animated), named after the emoji
+pub fn parse_custom_emoji(s: &str) -> Option<ImageSource> {
+    let caps = CUSTOM_EMOJI_REGEX.captures(s)?;
+    let extension = if caps[1].is_empty() { "png" } else { "gif" };
+    let id = caps[3].parse::<u64>().ok().filter(|id| *id != 0)?;
+    let url = format!("https://cdn.discordapp.com/emojis/{}.{}", id, extension);
+    Some(ImageSource::image(&url, Some(&format!("{}.{}", &caps[2], extension))))
+}
+
+// EXR and JPEG XL files usually arrive without an image content type or dimensions, since Discord can't preview them
+pub fn is_unpreviewed_image(filename: &str) -> bool {
+    let filename = filename.to_lowercase();
+    filename.ends_with(".exr") || filename.ends_with(".jxl")
+}
+
+// Images among a message's attachments, then embeds (image, video, thumbnail), then stickers
+fn collect_sources(attachments: &[Attachment], embeds: &[Embed], stickers: &[StickerItem]) -> Vec<ImageSource> {
+    let mut sources = Vec::new();
+    for attachment in attachments {
+        let content_type = attachment.content_type.as_deref().unwrap_or("");
+        if content_type.starts_with("video/") {
+            sources.push(ImageSource::video(&attachment.url, Some(&attachment.filename), None));
+        } else if content_type.starts_with("image/") || (attachment.width.is_some() && attachment.height.is_some()) || is_unpreviewed_image(&attachment.filename) {
+            sources.push(ImageSource::image(&attachment.url, Some(&attachment.filename)));
+        }
+    }
+    for embed in embeds {
+        let thumbnail = embed.thumbnail.as_ref().map(|t| t.url.as_str());
+        if let Some(image) = &embed.image {
+            sources.push(ImageSource::image(&image.url, None));
+        } else if let Some(video) = &embed.video {
+            // GIFV embeds (Tenor, Giphy) are videos with a still thumbnail
+            sources.push(ImageSource::video(&video.url, None, thumbnail));
+        } else if let Some(thumbnail) = thumbnail {
+            sources.push(ImageSource::image(thumbnail, None));
+        }
+    }
+    for sticker in stickers {
+        // Lottie stickers have no image URL
+        if let Some(url) = sticker.image_url() {
+            let filename = format!("{}.{}", sticker.name, filename_from_url(&url).rsplit('.').next().unwrap_or("png"));
+            sources.push(ImageSource::image(&url, Some(&filename)));
+        }
+    }
+    sources
+}
+
+/// Images carried by a message itself: attachments, then embeds (image, video, thumbnail), then stickers
+pub fn message_sources(msg: &Message) -> Vec<ImageSource> {
+    collect_sources(&msg.attachments, &msg.embeds, &msg.sticker_items)
+}
+
+/// Images inside the snapshots of a raw message object, which is where forwarded messages keep
+/// their attachments, embeds, and stickers
+pub fn snapshot_sources(raw: &Value) -> Vec<ImageSource> {
+    let mut sources = Vec::new();
+    let snapshots = raw.get("message_snapshots").and_then(Value::as_array);
+    for snapshot in snapshots.into_iter().flatten() {
+        let Some(message) = snapshot.get("message") else { continue };
+        let field = |name: &str| message.get(name).cloned().unwrap_or(Value::Array(Vec::new()));
+        let attachments: Vec<Attachment> = serenity::json::from_value(field("attachments")).unwrap_or_default();
+        let embeds: Vec<Embed> = serenity::json::from_value(field("embeds")).unwrap_or_default();
+        let stickers: Vec<StickerItem> = serenity::json::from_value(field("sticker_items")).unwrap_or_default();
+        sources.extend(collect_sources(&attachments, &embeds, &stickers));
+    }
+    sources
+}
+
+/// A message's images, including those of the message it forwards.
+/// Serenity doesn't model message snapshots yet, so forwards are re-fetched as raw JSON.
+async fn message_sources_with_forwards(http: &Http, msg: &Message) -> Vec<ImageSource> {
+    let mut sources = message_sources(msg);
+    let is_forward = msg.message_reference.as_ref().is_some_and(|r| r.kind == MessageReferenceKind::Forward);
+    if is_forward {
+        let request = Request::new(Route::ChannelMessage { channel_id: msg.channel_id, message_id: msg.id }, LightMethod::Get);
+        match http.fire::<Value>(request).await {
+            Ok(raw) => sources.extend(snapshot_sources(&raw)),
+            Err(why) => warn!(?why, message_id = %msg.id, "Failed to fetch forwarded message snapshots"),
+        }
+    }
+    sources
+}
+
+/// Every image a command could work on, in order: the message's own attachments, embeds and
+/// stickers, direct image URL and custom emoji arguments, the message being replied to, then any linked messages.
+/// Replied-to and linked messages that are forwards contribute the forwarded images.
+pub async fn resolve(http: impl AsRef<Http>, msg: &Message, parts: &[&str]) -> Vec<ImageSource> {
+    let http = http.as_ref();
+    let mut sources = message_sources(msg);
+    // Discord embeds the URLs it can preview, so skip URL arguments already found that way
+    for part in parts.iter().filter(|s| IMAGE_URL_REGEX.is_match(s)) {
+        if !sources.iter().any(|s| s.url == *part) {
+            sources.push(ImageSource::image(part, None));
+        }
+    }
+    sources.extend(parts.iter().filter_map(|s| parse_custom_emoji(s)));
+    if let Some(replied) = &msg.referenced_message {
+        sources.extend(message_sources_with_forwards(http, replied).await);
+    }
+    for (channel_id, message_id) in parts.iter().filter_map(|s| parse_message_link(s)) {
+        match channel_id.message(http, message_id).await {
+            Ok(linked) => sources.extend(message_sources_with_forwards(http, &linked).await),
+            Err(why) => warn!(?why, %channel_id, %message_id, "Failed to fetch linked message"),
+        }
+    }
+    sources
+}
+
+/// Download a URL, enforcing the size limit
+pub async fn fetch_url(url: &str) -> Result<Vec<u8>, BotError> {
+    fetch_url_with_progress(url, |_, _| {}).await
+}
+
+/// `fetch_url`, calling `progress` with the bytes received so far and the expected total (when the
+/// server sent a length) as each chunk arrives. The body is streamed, so a download without a
+/// `Content-Length` is abandoned as soon as it passes the limit rather than after it's all buffered.
+pub async fn fetch_url_with_progress(url: &str, mut progress: impl FnMut(usize, Option<u64>)) -> Result<Vec<u8>, BotError> {
+    let too_large = || BotError::TooLarge(format!("Image is too large. Maximum allowed size is {} MB.", MAX_DOWNLOAD_BYTES / (1024 * 1024)));
+    let mut resp = reqwest::get(url).await.map_err(|_| BotError::Download("Failed to fetch the image.".to_string()))?;
+    let total = resp.content_length();
+    if total.is_some_and(|len| len > MAX_DOWNLOAD_BYTES as u64) {
+        return Err(too_large());
+    }
+    let mut bytes = Vec::with_capacity(total.map_or(0, |len| len as usize));
+    while let Some(chunk) = resp.chunk().await.map_err(|_| BotError::Download("Failed to download the image.".to_string()))? {
+        if bytes.len() + chunk.len() > MAX_DOWNLOAD_BYTES {
+            return Err(too_large());
+        }
+        bytes.extend_from_slice(&chunk);
+        progress(bytes.len(), total);
+    }
+    Ok(bytes)
+}
+
+/// Downloads images by URL. `HttpFetcher` in the bot; tests substitute canned bytes.
+#[serenity::async_trait]
+pub trait ImageFetcher: Send + Sync {
+    /// Download `url`, reporting progress like `fetch_url_with_progress`
+    async fn fetch(&self, url: &str, progress: &mut (dyn FnMut(usize, Option<u64>) + Send)) -> Result<Vec<u8>, BotError>;
+}
+
+/// Downloads over the network with `fetch_url_with_progress`
+pub struct HttpFetcher;
+
+#[serenity::async_trait]
+impl ImageFetcher for HttpFetcher {
+    async fn fetch(&self, url: &str, progress: &mut (dyn FnMut(usize, Option<u64>) + Send)) -> Result<Vec<u8>, BotError> {
+        fetch_url_with_progress(url, progress).await
+    }
+}
+
+/// How far along a download is, for status messages: `2.1 / 6.4 MB`, or `2.1 MB` without a known total
+pub fn describe_download(done: usize, total: Option<u64>) -> String {
+    let mb = |n: f64| n / (1024.0 * 1024.0);
+    match total {
+        Some(total) if total > 0 => format!("{:.1} / {:.1} MB", mb(done as f64), mb(total as f64)),
+        _ => format!("{:.1} MB", mb(done as f64)),
+    }
+}
+
+/// Download a source with `fetcher` as bytes the image pipeline can decode.
+/// Videos are converted to a GIF, or fall back to their poster frame when that isn't possible.
+pub async fn download(fetcher: &dyn ImageFetcher, source: &ImageSource) -> Result<Vec<u8>, BotError> {
+    match source.kind {
+        SourceKind::Image => fetcher.fetch(&source.url, &mut |_, _| {}).await,
+        SourceKind::Video => {
+            let converted = match fetcher.fetch(&source.url, &mut |_, _| {}).await {
+                Ok(bytes) => tokio::task::spawn_blocking(move || crate::video::video_to_gif(&bytes, MAX_VIDEO_SECONDS))
+                    .await
+                    .map_err(|_| "Video conversion failed unexpectedly.".to_string())
+                    .and_then(|result| result)
+                    .map_err(BotError::Download),
+                Err(e) => Err(e),
+            };
+            match (converted, &source.poster) {
+                (Ok(gif), _) => Ok(gif),
+                (Err(e), Some(poster)) => {
+                    warn!(error = %e, url = %source.url, "Falling back to the video's poster frame");
+                    fetcher.fetch(poster, &mut |_, _| {}).await
+                }
+                (Err(e), None) => Err(e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_message_link() {
+        let (channel, message) = parse_message_link("https://discord.com/channels/123/456/789").unwrap();
+        assert_eq!((channel.get(), message.get()), (456, 789));
+        assert!(parse_message_link("https://canary.discord.com/channels/@me/456/789").is_some());
+        assert!(parse_message_link("https://discordapp.com/channels/1/2/3").is_some());
+        assert!(parse_message_link("https://example.com/channels/1/2/3").is_none());
+        assert!(parse_message_link("https://discord.com/channels/1/2").is_none());
+    }
+
+    #[test]
+    fn test_describe_download() {
+        assert_eq!(describe_download(2 * 1024 * 1024, Some(8 * 1024 * 1024)), "2.0 / 8.0 MB");
+        assert_eq!(describe_download(512 * 1024, None), "0.5 MB");
+    }
+
+    #[test]
+    fn test_parse_custom_emoji() {
+        let still = parse_custom_emoji("<:catjam:123>").unwrap();
+        assert_eq!((still.url.as_str(), still.filename.as_str()), ("https://cdn.discordapp.com/emojis/123.png", "catjam.png"));
+        assert_eq!(parse_custom_emoji("<a:party_cat:456>").unwrap().url, "https://cdn.discordapp.com/emojis/456.gif");
+        assert!(parse_custom_emoji(":catjam:").is_none());
+        assert!(parse_custom_emoji("<:catjam:0>").is_none());
+    }
+
+    #[test]
+    fn test_snapshot_sources() {
+        let raw: Value = serenity::json::from_str(
+            r#"{
+                "id": "1",
+                "message_snapshots": [{
+                    "message": {
+                        "attachments": [{
+                            "id": "2", "filename": "cat.png", "size": 10, "content_type": "image/png",
+                            "url": "https://cdn.discordapp.com/attachments/1/2/cat.png",
+                            "proxy_url": "https://media.discordapp.net/attachments/1/2/cat.png",
+                            "width": 64, "height": 64
+                        }],
+                        "embeds": [{
+                            "type": "gifv",
+                            "thumbnail": { "url": "https://media.tenor.com/x/cat.png" },
+                            "video": { "url": "https://media.tenor.com/x/cat.mp4" }
+                        }]
+                    }
+                }]
+            }"#,
+        )
+        .unwrap();
+        let sources = snapshot_sources(&raw);
+        assert_eq!(sources.len(), 2);
+        assert_eq!((sources[0].filename.as_str(), sources[0].kind), ("cat.png", SourceKind::Image));
+        assert_eq!(sources[1].kind, SourceKind::Video);
+        assert_eq!(sources[1].still_url(), "https://media.tenor.com/x/cat.png");
+        // Ordinary messages have no snapshots
+        assert!(snapshot_sources(&serenity::json::from_str::<Value>(r#"{"id": "1"}"#).unwrap()).is_empty());
+    }
+
+    #[test]
+    fn test_filename_and_still_url() {
+        assert_eq!(filename_from_url("https://cdn.example.com/a/cat.png?ex=1&is=2"), "cat.png");
+        assert_eq!(filename_from_url("https://example.com/"), "image");
+        let video = ImageSource::video("https://media.tenor.com/x/cat.mp4", None, Some("https://media.tenor.com/x/cat.png"));
+        assert_eq!(video.filename, "cat.mp4");
+        assert_eq!(video.still_url(), "https://media.tenor.com/x/cat.png");
+        let image = ImageSource::image("https://example.com/a.webp", Some("mine.webp"));
+        assert_eq!((image.filename.as_str(), image.still_url()), ("mine.webp", "https://example.com/a.webp"));
+    }
+}
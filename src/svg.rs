@@ -0,0 +1,126 @@
+// src/svg.rs
+
+//! SVG recoloring: color values in the markup are swapped for palette colors, keeping every shape,
+//! path and gradient as it was, instead of running a rasterized copy through the LUT pipeline
+
+use crate::theme_file::{self, RewrittenFile};
+use catppuccin::FlavorName;
+use image::RgbaImage;
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use resvg::{tiny_skia, usvg};
+
+/// Largest SVG that gets recolored
+pub const MAX_FILE_BYTES: usize = 1024 * 1024;
+
+/// Longest side of the PNG preview
+pub const PREVIEW_SIZE: u32 = 1024;
+
+// A color property as an attribute (`fill="#f00"`) or a CSS declaration in `style` or `<style>`
+// (`fill: #f00`). The value stops at a quote, `;`, `}` or `!important`.
+static COLOR_PROPERTY_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?i)\b(fill|stroke|stop-color|flood-color|lighting-color|color)(\s*=\s*["']|\s*:\s*)([^"';}!<>]+)"#).unwrap()
+});
+
+/// Whether an attachment is an SVG, by its content type or file name
+pub fn is_svg(content_type: Option<&str>, filename: &str) -> bool {
+    content_type.is_some_and(|t| t.starts_with("image/svg")) || filename.to_lowercase().ends_with(".svg")
+}
+
+// Values that aren't colors to recolor, like `none`, `currentColor` and `url(#gradient)`, are kept
+fn recolor_value(value: &str, flavor: FlavorName, rewritten: &mut RewrittenFile) -> Option<String> {
+    // CSS needs the `#`; bare words like `bad` aren't hex colors
+    let is_color = value.starts_with('#') || value.contains('(') || value.chars().all(|c| c.is_ascii_alphabetic());
+    if !is_color || value.to_lowercase().starts_with("url(") {
+        return None;
+    }
+    let (r, g, b) = theme_file::match_literal(value, flavor, &mut rewritten.conversions)?;
+    rewritten.replaced += 1;
+    // Color names become hex; hex and color functions keep their form
+    if value.chars().all(|c| c.is_ascii_alphabetic()) {
+        return Some(format!("#{:02x}{:02x}{:02x}", r, g, b));
+    }
+    Some(theme_file::replacement(value, (r, g, b)))
+}
+
+/// Swap every `fill`, `stroke` and other color property in an SVG, whether an attribute or CSS, for
+/// the flavor's closest named color. Everything else in the file is left byte for byte.
+pub fn recolor(svg: &str, flavor: FlavorName) -> RewrittenFile {
+    let mut rewritten = RewrittenFile { text: String::new(), conversions: Vec::new(), replaced: 0 };
+    let text = COLOR_PROPERTY_REGEX.replace_all(svg, |caps: &Captures| {
+        let raw = &caps[3];
+        let value = raw.trim();
+        match recolor_value(value, flavor, &mut rewritten) {
+            // Whitespace around the value, like the space before `!important`, stays put
+            Some(color) => format!("{}{}{}", &caps[1], &caps[2], raw.replacen(value, &color, 1)),
+            None => caps[0].to_string(),
+        }
+    });
+    rewritten.text = text.into_owned();
+    rewritten
+}
+
+/// Render an SVG to fit within `max_side` on its longest side. Images it links to aren't loaded,
+/// so a file can't pull in anything from the bot's disk; embedded `data:` images still render.
+pub fn rasterize(svg: &str, max_side: u32) -> Result<RgbaImage, String> {
+    let mut options = usvg::Options::default();
+    options.image_href_resolver.resolve_string = Box::new(|_, _| None);
+    let tree = usvg::Tree::from_str(svg, &options).map_err(|e| format!("Couldn't read the SVG: {e}"))?;
+    let size = tree.size();
+    let scale = max_side as f32 / size.width().max(size.height());
+    let (width, height) = (((size.width() * scale).round() as u32).max(1), ((size.height() * scale).round() as u32).max(1));
+    let mut pixmap = tiny_skia::Pixmap::new(width, height).ok_or("The SVG has no area to render")?;
+    resvg::render(&tree, tiny_skia::Transform::from_scale(scale, scale), &mut pixmap.as_mut());
+    let pixels = pixmap.pixels().iter().flat_map(|p| {
+        let c = p.demultiply();
+        [c.red(), c.green(), c.blue(), c.alpha()]
+    });
+    RgbaImage::from_raw(width, height, pixels.collect()).ok_or_else(|| "Failed to copy the rendered SVG".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SVG: &str = r##"<svg xmlns="http://www.w3.org/2000/svg" width="20" height="10" viewBox="0 0 20 10">
+<style>.bg { fill: #ff0000 !important; }</style>
+<rect class="bg" width="10" height="10"/>
+<rect x="10" width="10" height="10" fill="blue" stroke="none" style="stroke-width: 2; fill-opacity: 1"/>
+<linearGradient id="g"><stop stop-color="rgb(0, 255, 0)"/></linearGradient>
+<circle fill="url(#g)" r="1"/>
+</svg>"##;
+
+    #[test]
+    fn test_recolor_keeps_structure() {
+        let rewritten = recolor(SVG, FlavorName::Mocha);
+        let hex = |input| {
+            let (r, g, b) = rewritten.conversions.iter().find(|c| c.input == input).unwrap().matched;
+            format!("#{:02x}{:02x}{:02x}", r, g, b)
+        };
+        assert!(rewritten.text.contains(&format!(".bg {{ fill: {} !important; }}", hex("#ff0000"))));
+        assert!(rewritten.text.contains(&format!(r#"fill="{}" stroke="none""#, hex("blue"))));
+        assert!(rewritten.text.contains(r#"stop-color="rgb("#));
+        // References, keywords and other properties aren't colors
+        assert!(rewritten.text.contains(r##"fill="url(#g)""##));
+        assert!(rewritten.text.contains("stroke-width: 2; fill-opacity: 1"));
+        assert_eq!(rewritten.replaced, 3);
+        assert_eq!(rewritten.text.lines().count(), SVG.lines().count());
+    }
+
+    #[test]
+    fn test_rasterize() {
+        let rewritten = recolor(SVG, FlavorName::Mocha);
+        let preview = rasterize(&rewritten.text, 40).unwrap();
+        assert_eq!(preview.dimensions(), (40, 20));
+        let red = crate::utils::catppuccin_color_name_to_rgb("red", FlavorName::Mocha).unwrap();
+        assert_eq!(preview.get_pixel(5, 10).0, [red.0, red.1, red.2, 255]);
+        assert!(rasterize("not an svg", 40).is_err());
+    }
+
+    #[test]
+    fn test_is_svg() {
+        assert!(is_svg(Some("image/svg+xml"), "logo"));
+        assert!(is_svg(None, "Logo.SVG"));
+        assert!(!is_svg(Some("image/png"), "logo.png"));
+    }
+}
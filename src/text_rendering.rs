@@ -0,0 +1,151 @@
+// src/text_rendering.rs
+
+use ab_glyph::{point, Font, FontRef, GlyphId, PxScale, ScaleFont};
+use image::{Rgba, RgbaImage};
+use once_cell::sync::Lazy;
+
+// Bundled so rendering works the same on every host (see assets/fonts/LICENSE-DejaVu.txt)
+static FONT: Lazy<FontRef<'static>> = Lazy::new(|| {
+    FontRef::try_from_slice(include_bytes!("../assets/fonts/DejaVuSans-Bold.ttf")).expect("Bundled font is valid")
+});
+
+/// Width and height in pixels of a single line of text at the given size
+pub fn measure_text(text: &str, size: f32) -> (u32, u32) {
+    let scaled = FONT.as_scaled(PxScale::from(size));
+    let mut width = 0.0;
+    let mut last: Option<GlyphId> = None;
+    for c in text.chars() {
+        let id = scaled.glyph_id(c);
+        if let Some(prev) = last {
+            width += scaled.kern(prev, id);
+        }
+        width += scaled.h_advance(id);
+        last = Some(id);
+    }
+    (width.ceil() as u32, scaled.height().ceil() as u32)
+}
+
+/// Distance in pixels between the tops of consecutive lines at the given size
+pub fn line_height(size: f32) -> u32 {
+    let scaled = FONT.as_scaled(PxScale::from(size));
+    (scaled.height() + scaled.line_gap()).ceil() as u32
+}
+
+/// Greedily wrap text into lines no wider than `max_width`, keeping explicit newlines
+pub fn wrap_text(text: &str, size: f32, max_width: u32) -> Vec<String> {
+    let mut lines = Vec::new();
+    for paragraph in text.split('\n') {
+        let mut current = String::new();
+        for word in paragraph.split_whitespace() {
+            let candidate = if current.is_empty() { word.to_string() } else { format!("{} {}", current, word) };
+            if measure_text(&candidate, size).0 <= max_width || current.is_empty() {
+                current = candidate;
+            } else {
+                lines.push(std::mem::take(&mut current));
+                current = word.to_string();
+            }
+        }
+        lines.push(current);
+    }
+    lines
+}
+
+/// Draw a single line of text with its top-left corner at (x, y), blending glyph coverage over the image
+pub fn draw_text(img: &mut RgbaImage, text: &str, x: i32, y: i32, size: f32, color: (u8, u8, u8)) {
+    let scale = PxScale::from(size);
+    let scaled = FONT.as_scaled(scale);
+    let mut caret = x as f32;
+    let mut last: Option<GlyphId> = None;
+    for c in text.chars() {
+        let id = scaled.glyph_id(c);
+        if let Some(prev) = last {
+            caret += scaled.kern(prev, id);
+        }
+        let glyph = id.with_scale_and_position(scale, point(caret, y as f32 + scaled.ascent()));
+        caret += scaled.h_advance(id);
+        last = Some(id);
+        if let Some(outlined) = FONT.outline_glyph(glyph) {
+            let bounds = outlined.px_bounds();
+            outlined.draw(|gx, gy, coverage| {
+                let px = bounds.min.x as i32 + gx as i32;
+                let py = bounds.min.y as i32 + gy as i32;
+                if px < 0 || py < 0 || px >= img.width() as i32 || py >= img.height() as i32 {
+                    return;
+                }
+                let pixel = img.get_pixel_mut(px as u32, py as u32);
+                let alpha = coverage.clamp(0.0, 1.0);
+                let rgb = [color.0, color.1, color.2];
+                for ch in 0..3 {
+                    pixel[ch] = ((1.0 - alpha) * pixel[ch] as f32 + alpha * rgb[ch] as f32).round() as u8;
+                }
+                pixel[3] = pixel[3].max((alpha * 255.0).round() as u8);
+            });
+        }
+    }
+}
+
+/// Draw text with a solid outline of the given thickness, for legibility over busy images
+#[allow(clippy::too_many_arguments)]
+pub fn draw_text_outlined(
+    img: &mut RgbaImage,
+    text: &str,
+    x: i32,
+    y: i32,
+    size: f32,
+    fill: (u8, u8, u8),
+    outline: (u8, u8, u8),
+    thickness: i32,
+) {
+    for dy in -thickness..=thickness {
+        for dx in -thickness..=thickness {
+            if (dx != 0 || dy != 0) && dx * dx + dy * dy <= thickness * thickness {
+                draw_text(img, text, x + dx, y + dy, size, outline);
+            }
+        }
+    }
+    draw_text(img, text, x, y, size, fill);
+}
+
+/// Draw each line horizontally centered within `width`, starting at `y`
+pub fn draw_text_centered(img: &mut RgbaImage, lines: &[String], y: i32, width: u32, size: f32, color: (u8, u8, u8)) {
+    let step = line_height(size) as i32;
+    for (i, line) in lines.iter().enumerate() {
+        let (line_w, _) = measure_text(line, size);
+        let x = (width as i32 - line_w as i32) / 2;
+        draw_text(img, line, x, y + i as i32 * step, size, color);
+    }
+}
+
+/// Fill an axis-aligned rectangle, clipped to the image
+pub fn fill_rect(img: &mut RgbaImage, x: i32, y: i32, w: u32, h: u32, color: Rgba<u8>) {
+    let x0 = x.max(0) as u32;
+    let y0 = y.max(0) as u32;
+    let x1 = ((x + w as i32).max(0) as u32).min(img.width());
+    let y1 = ((y + h as i32).max(0) as u32).min(img.height());
+    for py in y0..y1 {
+        for px in x0..x1 {
+            img.put_pixel(px, py, color);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap_text_respects_width() {
+        let lines = wrap_text("the quick brown fox jumps over the lazy dog", 32.0, 200);
+        assert!(lines.len() > 1);
+        for line in &lines {
+            assert!(measure_text(line, 32.0).0 <= 200 || !line.contains(' '));
+        }
+    }
+
+    #[test]
+    fn test_draw_text_changes_pixels() {
+        let mut img = RgbaImage::from_pixel(100, 40, Rgba([0, 0, 0, 255]));
+        draw_text(&mut img, "Hi", 4, 4, 24.0, (255, 255, 255));
+        assert!(img.pixels().any(|p| p[0] > 128));
+    }
+}
@@ -0,0 +1,110 @@
+// src/theme_file.rs
+
+use crate::convert::Conversion;
+use catppuccin::FlavorName;
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+
+/// Largest theme file `!cat convertfile` rewrites
+pub const MAX_FILE_BYTES: u32 = 256 * 1024;
+
+/// File extensions `!cat convertfile` reads as theme files
+pub const EXTENSIONS: [&str; 10] = ["css", "scss", "sass", "less", "js", "cjs", "mjs", "ts", "json", "txt"];
+
+// Hex colors (3, 6 or 8 digits, not running into more word characters) and CSS color functions.
+// Bare color names are left alone: in a stylesheet or config they're as likely to be keys as values.
+static COLOR_LITERAL_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)#(?:[0-9a-f]{8}|[0-9a-f]{6}|[0-9a-f]{3})\b|\b(?:rgba?|hsla?)\([^()]*\)").unwrap());
+
+/// A theme file with its color literals swapped for palette colors
+pub struct RewrittenFile {
+    pub text: String,
+    /// Each distinct color found, in order of first appearance, with its match
+    pub conversions: Vec<Conversion>,
+    /// How many literals were replaced, counting repeats
+    pub replaced: usize,
+}
+
+// What follows an ID selector like `#add` or `#bad:hover`, which would otherwise read as a color
+fn continues_selector(rest: &str) -> bool {
+    matches!(rest.trim_start().chars().next(), Some('{' | '.' | '[' | '>' | '~' | '+' | ':'))
+}
+
+// The matched color written like the literal it replaces: hex stays hex (keeping its case and any
+// alpha), and color functions become `rgb()`, or `rgba()` when they carried an alpha
+pub(crate) fn replacement(literal: &str, (r, g, b): (u8, u8, u8)) -> String {
+    if let Some(digits) = literal.strip_prefix('#') {
+        let hex = format!("{:02x}{:02x}{:02x}", r, g, b);
+        let hex = if digits.chars().any(|c| c.is_ascii_uppercase()) { hex.to_uppercase() } else { hex };
+        let alpha = if digits.len() == 8 { &digits[6..] } else { "" };
+        return format!("#{}{}", hex, alpha);
+    }
+    let inner = literal.split_once('(').map(|(_, rest)| rest.trim_end_matches(')')).unwrap_or("");
+    let args: Vec<&str> = inner.split([',', ' ', '/']).filter(|a| !a.is_empty()).collect();
+    match args.get(3) {
+        Some(alpha) => format!("rgba({}, {}, {}, {})", r, g, b, alpha),
+        None => format!("rgb({}, {}, {})", r, g, b),
+    }
+}
+
+/// Swap every hex color and `rgb()`/`hsl()` color in a stylesheet or Tailwind config for the flavor's
+/// closest named color, leaving everything else, ID selectors included, untouched
+pub fn rewrite_theme(text: &str, flavor: FlavorName) -> RewrittenFile {
+    let mut conversions: Vec<Conversion> = Vec::new();
+    let mut replaced = 0;
+    let rewritten = COLOR_LITERAL_REGEX.replace_all(text, |caps: &Captures| {
+        let found = caps.get(0).unwrap();
+        let literal = found.as_str();
+        if literal.starts_with('#') && continues_selector(&text[found.end()..]) {
+            return literal.to_string();
+        }
+        let Some(matched) = match_literal(literal, flavor, &mut conversions) else {
+            return literal.to_string();
+        };
+        replaced += 1;
+        replacement(literal, matched)
+    });
+    RewrittenFile { text: rewritten.into_owned(), conversions, replaced }
+}
+
+/// The palette color a color literal maps to, recording it in `conversions` the first time it's seen
+pub(crate) fn match_literal(literal: &str, flavor: FlavorName, conversions: &mut Vec<Conversion>) -> Option<(u8, u8, u8)> {
+    let rgb = crate::utils::parse_color_literal(literal)?;
+    if let Some(conversion) = conversions.iter().find(|c| c.rgb == rgb) {
+        return Some(conversion.matched);
+    }
+    let conversion = crate::convert::convert_colors(&[(literal.to_string(), rgb)], flavor).pop()?;
+    let matched = conversion.matched;
+    conversions.push(conversion);
+    Some(matched)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rewrite_css() {
+        let css = "#add { color: #CBA6F7; }\n#bad:hover { background: rgba(203, 166, 247, 0.5); border: 1px solid #cba6f780; }\n.a { color: #ff0000 }";
+        let rewritten = rewrite_theme(css, FlavorName::Mocha);
+        assert_eq!(
+            rewritten.text,
+            "#add { color: #CBA6F7; }\n#bad:hover { background: rgba(203, 166, 247, 0.5); border: 1px solid #cba6f780; }\n.a { color: #f38ba8 }"
+        );
+        assert_eq!(rewritten.replaced, 4);
+        let names: Vec<&str> = rewritten.conversions.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["mauve", "red"]);
+    }
+
+    #[test]
+    fn test_rewrite_tailwind_config() {
+        let config = "module.exports = { theme: { colors: { primary: '#f00', muted: 'hsl(0, 0%, 50%)', red: '#ffffff80' } } }";
+        let rewritten = rewrite_theme(config, FlavorName::Mocha);
+        let red = crate::utils::catppuccin_color_name_to_rgb("red", FlavorName::Mocha).unwrap();
+        assert!(rewritten.text.contains(&format!("primary: '#{:02x}{:02x}{:02x}'", red.0, red.1, red.2)));
+        assert!(rewritten.text.contains("muted: 'rgb("));
+        // Alpha survives, and keys named like colors aren't touched
+        assert!(rewritten.text.contains("red: '#") && rewritten.text.ends_with("80' } } }"));
+        assert_eq!(rewritten.replaced, 3);
+    }
+}
@@ -0,0 +1,105 @@
+// src/timing.rs
+
+use serenity::model::channel::Message;
+use std::future::Future;
+use std::time::{Duration, Instant};
+use tracing::{field, info, info_span, Instrument, Span};
+
+/// Times the stages of one command. Each stage runs in its own `stage` span under a `command` span,
+/// with its duration recorded as `duration_ms`, and the breakdown can be added to the reply with
+/// `--debug-timing`.
+pub struct Timings {
+    span: Span,
+    started: Instant,
+    stages: Vec<(&'static str, Duration)>,
+}
+
+impl Timings {
+    pub fn new(command: &'static str, msg: &Message) -> Self {
+        let span = info_span!("command", command, user = %msg.author.id, message = %msg.id, total_ms = field::Empty);
+        Timings { span, started: Instant::now(), stages: Vec::new() }
+    }
+
+    fn stage_span(&self, stage: &'static str) -> Span {
+        info_span!(parent: &self.span, "stage", stage, duration_ms = field::Empty)
+    }
+
+    fn finish_stage(&mut self, span: &Span, stage: &'static str, elapsed: Duration) {
+        span.record("duration_ms", elapsed.as_millis() as u64);
+        self.stages.push((stage, elapsed));
+    }
+
+    /// Run blocking work as a stage
+    pub fn time<T>(&mut self, stage: &'static str, work: impl FnOnce() -> T) -> T {
+        let span = self.stage_span(stage);
+        let start = Instant::now();
+        let output = span.in_scope(work);
+        self.finish_stage(&span, stage, start.elapsed());
+        output
+    }
+
+    /// Await a future as a stage
+    pub async fn time_async<T>(&mut self, stage: &'static str, work: impl Future<Output = T>) -> T {
+        let span = self.stage_span(stage);
+        let start = Instant::now();
+        let output = work.instrument(span.clone()).await;
+        self.finish_stage(&span, stage, start.elapsed());
+        output
+    }
+
+    /// Add stages that were timed inside a blocking job, in the order they ran
+    pub fn record(&mut self, times: StageTimes) {
+        for (stage, elapsed) in times.0 {
+            let span = self.stage_span(stage);
+            self.finish_stage(&span, stage, elapsed);
+        }
+    }
+
+    /// Record the total on the command span and log the breakdown
+    pub fn finish(&self) {
+        let total_ms = self.started.elapsed().as_millis() as u64;
+        self.span.record("total_ms", total_ms);
+        self.span.in_scope(|| info!(total_ms, breakdown = %self.summary(), "Command timings"));
+    }
+
+    /// The breakdown for the reply, like `download 120 ms · decode 35 ms · total 160 ms`
+    pub fn summary(&self) -> String {
+        let mut parts: Vec<String> = self.stages.iter().map(|(stage, elapsed)| format!("{} {}", stage, format_duration(*elapsed))).collect();
+        parts.push(format!("total {}", format_duration(self.started.elapsed())));
+        parts.join(" · ")
+    }
+}
+
+/// Stage durations measured inside a `jobs::run` closure, which can't borrow the command's `Timings`;
+/// they're added to it with `Timings::record` once the job is done
+#[derive(Debug, Default, Clone)]
+pub struct StageTimes(Vec<(&'static str, Duration)>);
+
+impl StageTimes {
+    pub fn time<T>(&mut self, stage: &'static str, work: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let output = work();
+        self.0.push((stage, start.elapsed()));
+        output
+    }
+}
+
+fn format_duration(elapsed: Duration) -> String {
+    if elapsed < Duration::from_secs(10) {
+        format!("{} ms", elapsed.as_millis())
+    } else {
+        format!("{:.1} s", elapsed.as_secs_f64())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_micros(1500)), "1 ms");
+        assert_eq!(format_duration(Duration::from_millis(9999)), "9999 ms");
+        assert_eq!(format_duration(Duration::from_millis(12_345)), "12.3 s");
+    }
+}
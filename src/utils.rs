@@ -5,11 +5,12 @@ use serenity::prelude::*;
 use image::ImageFormat;
 use catppuccin::FlavorName;
 
-// Catppuccin Mocha theme colors
-pub const MOCHA_MAUVE: u32 = 0xcba6f7; // accent
-pub const MOCHA_GREEN: u32 = 0xa6e3a1; // success
-pub const MOCHA_BLUE: u32 = 0x89b4fa; // info/progress
-pub const MOCHA_RED: u32 = 0xf38ba8; // error
+// All Catppuccin color names, in palette order
+pub const CATPPUCCIN_COLOR_NAMES: [&str; 26] = [
+    "rosewater", "flamingo", "pink", "mauve", "red", "maroon", "peach", "yellow", "green", "teal", "sky", "sapphire", "blue",
+    "lavender", "text", "subtext1", "subtext0", "overlay2", "overlay1", "overlay0", "surface2", "surface1", "surface0", "base",
+    "mantle", "crust",
+];
 
 // Parse a string into a Catppuccin FlavorName enum
 pub fn parse_flavor(s: &str) -> Option<FlavorName> {
@@ -30,6 +31,7 @@ pub fn parse_algorithm(s: &str) -> Option<&'static str> {
         "linear" | "linear-rbf" => Some("linear-rbf"),
         "sampling" | "gaussian-sampling" | "gauss" => Some("gaussian-sampling"),
         "nearest" | "nearest-neighbor" | "nn" => Some("nearest-neighbor"),
+        "nearest-k" | "knn" => Some("nearest-k"),
         "hald" => Some("hald"),
         "euclide" => Some("euclide"),
         "mean" => Some("mean"),
@@ -55,10 +57,508 @@ pub fn parse_format(s: &str) -> Option<ImageFormat> {
         "jpg" | "jpeg" => Some(ImageFormat::Jpeg),
         "webp" => Some(ImageFormat::WebP),
         "gif" => Some(ImageFormat::Gif),
+        "avif" => Some(ImageFormat::Avif),
         _ => None,
     }
 }
 
+// Parse a video output format for animations
+pub fn parse_video_format(s: &str) -> Option<crate::video::VideoFormat> {
+    match s.to_lowercase().as_str() {
+        "mp4" => Some(crate::video::VideoFormat::Mp4),
+        "webm" => Some(crate::video::VideoFormat::WebM),
+        _ => None,
+    }
+}
+
+// Parse a color vision deficiency for `!cat simulate`
+pub fn parse_deficiency(s: &str) -> Option<crate::accessibility::Deficiency> {
+    let s = s.to_lowercase();
+    crate::accessibility::Deficiency::ALL.into_iter().find(|d| d.name() == s)
+}
+
+// Parse a severity percentage like "60" or "60%" into 0..1
+pub fn parse_severity(s: &str) -> Option<f32> {
+    s.strip_suffix('%').unwrap_or(s)
+        .parse::<f32>()
+        .ok()
+        .filter(|p| (1.0..=100.0).contains(p))
+        .map(|p| p / 100.0)
+}
+
+// Parse a palette file format for color exports
+pub fn parse_palette_format(s: &str) -> Option<crate::export::PaletteFormat> {
+    match s.to_lowercase().as_str() {
+        "json" => Some(crate::export::PaletteFormat::Json),
+        "css" => Some(crate::export::PaletteFormat::Css),
+        "gpl" | "gimp" => Some(crate::export::PaletteFormat::Gpl),
+        "ase" | "aseprite" => Some(crate::export::PaletteFormat::Ase),
+        "act" => Some(crate::export::PaletteFormat::Act),
+        _ => None,
+    }
+}
+
+// Parse a terminal color scheme format for `!cat theme export`
+pub fn parse_scheme_format(s: &str) -> Option<crate::export::SchemeFormat> {
+    match s.to_lowercase().as_str() {
+        "xresources" | "xdefaults" => Some(crate::export::SchemeFormat::Xresources),
+        "base16" => Some(crate::export::SchemeFormat::Base16),
+        _ => None,
+    }
+}
+
+// Parse which app window `!cat preview` renders
+pub fn parse_preview_kind(s: &str) -> Option<crate::mockups::PreviewKind> {
+    match s.to_lowercase().as_str() {
+        "code" => Some(crate::mockups::PreviewKind::Code),
+        "terminal" => Some(crate::mockups::PreviewKind::Terminal),
+        "editor" => Some(crate::mockups::PreviewKind::Editor),
+        _ => None,
+    }
+}
+
+// Parse gradient easing function
+pub fn parse_easing(s: &str) -> Option<&'static str> {
+    match s.to_lowercase().as_str() {
+        "linear" => Some("linear"),
+        "ease-in" | "easein" => Some("ease-in"),
+        "ease-out" | "easeout" => Some("ease-out"),
+        "ease-in-out" | "easeinout" | "ease" => Some("ease-in-out"),
+        "sine" | "sin" => Some("sine"),
+        _ => None,
+    }
+}
+
+// Parse gradient direction: a named direction or an angle like "30deg"
+pub fn parse_gradient_direction(s: &str) -> Option<crate::palette::GradientDirection> {
+    use crate::palette::GradientDirection;
+    let s = s.to_lowercase();
+    match s.as_str() {
+        "horizontal" | "h" => Some(GradientDirection::Linear(0.0)),
+        "vertical" | "v" => Some(GradientDirection::Linear(90.0)),
+        "diagonal" | "d" => Some(GradientDirection::Linear(45.0)),
+        "radial" | "r" => Some(GradientDirection::Radial),
+        _ => s.strip_suffix("deg")
+            .and_then(|deg| deg.parse::<f32>().ok())
+            .filter(|deg| deg.is_finite())
+            .map(GradientDirection::Linear),
+    }
+}
+
+// Parse an animation speed ("slow", "normal", "fast", or a multiplier like "2x") into a GIF frame delay in centiseconds
+pub fn parse_animation_speed(s: &str) -> Option<u16> {
+    match s.to_lowercase().as_str() {
+        "slow" => Some(8),
+        "normal" => Some(5),
+        "fast" => Some(3),
+        other => other.strip_suffix('x')
+            .and_then(|m| m.parse::<f32>().ok())
+            .filter(|m| m.is_finite() && *m > 0.0)
+            // GIF viewers clamp delays below 2cs, so that's the fastest we go
+            .map(|m| (5.0 / m).round().clamp(2.0, 100.0) as u16),
+    }
+}
+
+// Parse dimensions like "1920x1080", capped at 4096 per side
+pub fn parse_dimensions(s: &str) -> Option<(u32, u32)> {
+    let (w, h) = s.to_lowercase().split_once('x').map(|(w, h)| (w.to_string(), h.to_string()))?;
+    let w = w.parse::<u32>().ok()?;
+    let h = h.parse::<u32>().ok()?;
+    if w < 2 || h < 2 || w > 4096 || h > 4096 {
+        return None;
+    }
+    Some((w, h))
+}
+
+// Parse a channel mention like `<#123456>` into its channel ID
+pub fn parse_channel_mention(s: &str) -> Option<serenity::model::id::ChannelId> {
+    let id = s.strip_prefix("<#")?.strip_suffix('>')?.parse::<u64>().ok().filter(|id| *id != 0)?;
+    Some(serenity::model::id::ChannelId::new(id))
+}
+
+// A file size for people, e.g. `512 B`, `14.2 KB` or `3.4 MB`
+pub fn format_file_size(bytes: usize) -> String {
+    match bytes {
+        0..=1023 => format!("{} B", bytes),
+        1024..=1_048_575 => format!("{:.1} KB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0)),
+    }
+}
+
+// Parse a 3, 6 or 8 digit hex color (with or without '#') into an RGB tuple; an 8-digit color's alpha is dropped
+pub fn parse_hex_color(s: &str) -> Option<(u8, u8, u8)> {
+    let h = s.trim_start_matches('#');
+    if !h.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    if h.len() == 6 || h.len() == 8 {
+        Some((
+            u8::from_str_radix(&h[0..2], 16).ok()?,
+            u8::from_str_radix(&h[2..4], 16).ok()?,
+            u8::from_str_radix(&h[4..6], 16).ok()?,
+        ))
+    } else if h.len() == 3 {
+        Some((
+            u8::from_str_radix(&h[0..1].repeat(2), 16).ok()?,
+            u8::from_str_radix(&h[1..2].repeat(2), 16).ok()?,
+            u8::from_str_radix(&h[2..3].repeat(2), 16).ok()?,
+        ))
+    } else {
+        None
+    }
+}
+
+// The CSS named colors, as 0xRRGGBB
+const CSS_COLORS: [(&str, u32); 148] = [
+    ("aliceblue", 0xf0f8ff), ("antiquewhite", 0xfaebd7), ("aqua", 0x00ffff), ("aquamarine", 0x7fffd4),
+    ("azure", 0xf0ffff), ("beige", 0xf5f5dc), ("bisque", 0xffe4c4), ("black", 0x000000), ("blanchedalmond", 0xffebcd),
+    ("blue", 0x0000ff), ("blueviolet", 0x8a2be2), ("brown", 0xa52a2a), ("burlywood", 0xdeb887), ("cadetblue", 0x5f9ea0),
+    ("chartreuse", 0x7fff00), ("chocolate", 0xd2691e), ("coral", 0xff7f50), ("cornflowerblue", 0x6495ed),
+    ("cornsilk", 0xfff8dc), ("crimson", 0xdc143c), ("cyan", 0x00ffff), ("darkblue", 0x00008b), ("darkcyan", 0x008b8b),
+    ("darkgoldenrod", 0xb8860b), ("darkgray", 0xa9a9a9), ("darkgreen", 0x006400), ("darkgrey", 0xa9a9a9),
+    ("darkkhaki", 0xbdb76b), ("darkmagenta", 0x8b008b), ("darkolivegreen", 0x556b2f), ("darkorange", 0xff8c00),
+    ("darkorchid", 0x9932cc), ("darkred", 0x8b0000), ("darksalmon", 0xe9967a), ("darkseagreen", 0x8fbc8f),
+    ("darkslateblue", 0x483d8b), ("darkslategray", 0x2f4f4f), ("darkslategrey", 0x2f4f4f), ("darkturquoise", 0x00ced1),
+    ("darkviolet", 0x9400d3), ("deeppink", 0xff1493), ("deepskyblue", 0x00bfff), ("dimgray", 0x696969),
+    ("dimgrey", 0x696969), ("dodgerblue", 0x1e90ff), ("firebrick", 0xb22222), ("floralwhite", 0xfffaf0),
+    ("forestgreen", 0x228b22), ("fuchsia", 0xff00ff), ("gainsboro", 0xdcdcdc), ("ghostwhite", 0xf8f8ff),
+    ("gold", 0xffd700), ("goldenrod", 0xdaa520), ("gray", 0x808080), ("green", 0x008000), ("greenyellow", 0xadff2f),
+    ("grey", 0x808080), ("honeydew", 0xf0fff0), ("hotpink", 0xff69b4), ("indianred", 0xcd5c5c), ("indigo", 0x4b0082),
+    ("ivory", 0xfffff0), ("khaki", 0xf0e68c), ("lavender", 0xe6e6fa), ("lavenderblush", 0xfff0f5),
+    ("lawngreen", 0x7cfc00), ("lemonchiffon", 0xfffacd), ("lightblue", 0xadd8e6), ("lightcoral", 0xf08080),
+    ("lightcyan", 0xe0ffff), ("lightgoldenrodyellow", 0xfafad2), ("lightgray", 0xd3d3d3), ("lightgreen", 0x90ee90),
+    ("lightgrey", 0xd3d3d3), ("lightpink", 0xffb6c1), ("lightsalmon", 0xffa07a), ("lightseagreen", 0x20b2aa),
+    ("lightskyblue", 0x87cefa), ("lightslategray", 0x778899), ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xb0c4de), ("lightyellow", 0xffffe0), ("lime", 0x00ff00), ("limegreen", 0x32cd32),
+    ("linen", 0xfaf0e6), ("magenta", 0xff00ff), ("maroon", 0x800000), ("mediumaquamarine", 0x66cdaa),
+    ("mediumblue", 0x0000cd), ("mediumorchid", 0xba55d3), ("mediumpurple", 0x9370db), ("mediumseagreen", 0x3cb371),
+    ("mediumslateblue", 0x7b68ee), ("mediumspringgreen", 0x00fa9a), ("mediumturquoise", 0x48d1cc),
+    ("mediumvioletred", 0xc71585), ("midnightblue", 0x191970), ("mintcream", 0xf5fffa), ("mistyrose", 0xffe4e1),
+    ("moccasin", 0xffe4b5), ("navajowhite", 0xffdead), ("navy", 0x000080), ("oldlace", 0xfdf5e6), ("olive", 0x808000),
+    ("olivedrab", 0x6b8e23), ("orange", 0xffa500), ("orangered", 0xff4500), ("orchid", 0xda70d6),
+    ("palegoldenrod", 0xeee8aa), ("palegreen", 0x98fb98), ("paleturquoise", 0xafeeee), ("palevioletred", 0xdb7093),
+    ("papayawhip", 0xffefd5), ("peachpuff", 0xffdab9), ("peru", 0xcd853f), ("pink", 0xffc0cb), ("plum", 0xdda0dd),
+    ("powderblue", 0xb0e0e6), ("purple", 0x800080), ("rebeccapurple", 0x663399), ("red", 0xff0000),
+    ("rosybrown", 0xbc8f8f), ("royalblue", 0x4169e1), ("saddlebrown", 0x8b4513), ("salmon", 0xfa8072),
+    ("sandybrown", 0xf4a460), ("seagreen", 0x2e8b57), ("seashell", 0xfff5ee), ("sienna", 0xa0522d),
+    ("silver", 0xc0c0c0), ("skyblue", 0x87ceeb), ("slateblue", 0x6a5acd), ("slategray", 0x708090),
+    ("slategrey", 0x708090), ("snow", 0xfffafa), ("springgreen", 0x00ff7f), ("steelblue", 0x4682b4), ("tan", 0xd2b48c),
+    ("teal", 0x008080), ("thistle", 0xd8bfd8), ("tomato", 0xff6347), ("turquoise", 0x40e0d0), ("violet", 0xee82ee),
+    ("wheat", 0xf5deb3), ("white", 0xffffff), ("whitesmoke", 0xf5f5f5), ("yellow", 0xffff00), ("yellowgreen", 0x9acd32),
+];
+
+// Parse a CSS color function like `rgb(30, 30, 46)`, `rgba(30 30 46 / 50%)` or `hsl(240, 21%, 15%)`; alpha is dropped
+fn parse_color_function(s: &str) -> Option<(u8, u8, u8)> {
+    let s = s.trim().to_lowercase();
+    let (name, rest) = s.split_once('(')?;
+    let args: Vec<&str> = rest.strip_suffix(')')?.split([',', ' ', '/']).filter(|a| !a.is_empty()).collect();
+    if args.len() != 3 && args.len() != 4 {
+        return None;
+    }
+    // A number, or a percentage of `full`
+    let value = |arg: &str, full: f32| match arg.strip_suffix('%') {
+        Some(percent) => percent.parse::<f32>().ok().map(|p| p / 100.0 * full),
+        None => arg.parse::<f32>().ok(),
+    };
+    match name.trim() {
+        "rgb" | "rgba" => {
+            let channel = |arg: &str| value(arg, 255.0).map(|v| v.round().clamp(0.0, 255.0) as u8);
+            Some((channel(args[0])?, channel(args[1])?, channel(args[2])?))
+        }
+        "hsl" | "hsla" => {
+            let hue = args[0].trim_end_matches("deg").parse::<f32>().ok()?.rem_euclid(360.0);
+            let fraction = |arg: &str| arg.strip_suffix('%').unwrap_or(arg).parse::<f32>().ok().map(|p| (p / 100.0).clamp(0.0, 1.0));
+            Some(hsl_to_rgb(hue, fraction(args[1])?, fraction(args[2])?))
+        }
+        _ => None,
+    }
+}
+
+/// A color written the way CSS would: hex (3, 6 or 8 digits), `rgb()`/`rgba()`, `hsl()`/`hsla()`, or a CSS color name
+pub fn parse_color_literal(s: &str) -> Option<(u8, u8, u8)> {
+    if let Some(rgb) = parse_hex_color(s).or_else(|| parse_color_function(s)) {
+        return Some(rgb);
+    }
+    let name = s.to_lowercase();
+    let &(_, value) = CSS_COLORS.iter().find(|(css, _)| *css == name)?;
+    let [_, r, g, b] = value.to_be_bytes();
+    Some((r, g, b))
+}
+
+/// A Catppuccin color name in `flavor`, or any color [`parse_color_literal`] reads. Palette names win
+/// where they overlap CSS names, so `red` is the flavor's red.
+pub fn parse_color(s: &str, flavor: FlavorName) -> Option<(u8, u8, u8)> {
+    catppuccin_color_name_to_rgb(s, flavor).or_else(|| parse_color_literal(s))
+}
+
+// Split a command into arguments on whitespace, keeping a color function like `rgb(30, 30, 46)` as one argument
+pub fn split_args(s: &str) -> Vec<&str> {
+    let is_color_function = |token: &str| matches!(token.to_lowercase().as_str(), "rgb" | "rgba" | "hsl" | "hsla");
+    let mut args = Vec::new();
+    let mut start = None;
+    let mut in_function = false;
+    for (i, c) in s.char_indices() {
+        match start {
+            None if !c.is_whitespace() => start = Some(i),
+            Some(begin) if c.is_whitespace() && !in_function => {
+                args.push(&s[begin..i]);
+                start = None;
+            }
+            _ => {}
+        }
+        if c == '(' && start.is_some_and(|begin| is_color_function(&s[begin..i])) {
+            in_function = true;
+        } else if c == ')' {
+            in_function = false;
+        }
+    }
+    if let Some(begin) = start {
+        args.push(&s[begin..]);
+    }
+    args
+}
+
+// Parse a playback speed multiplier like "2x" or "0.5x" (0.1x to 10x)
+pub fn parse_speed_factor(s: &str) -> Option<f32> {
+    let factor = s.to_lowercase().strip_suffix('x')?.parse::<f32>().ok()?;
+    if (0.1..=10.0).contains(&factor) {
+        Some(factor)
+    } else {
+        None
+    }
+}
+
+// Parse an aspect ratio like "16:9" into (width, height)
+pub fn parse_aspect_ratio(s: &str) -> Option<(u32, u32)> {
+    let (w, h) = s.split_once(':')?;
+    let (w, h) = (w.parse::<u32>().ok()?, h.parse::<u32>().ok()?);
+    if (1..=100).contains(&w) && (1..=100).contains(&h) {
+        Some((w, h))
+    } else {
+        None
+    }
+}
+
+// Parse a sprite-sheet grid like "4x2" into (columns, rows), 1-64 each
+pub fn parse_grid(s: &str) -> Option<(u32, u32)> {
+    let (c, r) = s.to_lowercase().split_once('x').map(|(c, r)| (c.parse::<u32>().ok(), r.parse::<u32>().ok()))?;
+    let (c, r) = (c?, r?);
+    if (1..=64).contains(&c) && (1..=64).contains(&r) {
+        Some((c, r))
+    } else {
+        None
+    }
+}
+
+// Parse a region like "10,20,300,200" into (x, y, width, height)
+pub fn parse_region(s: &str) -> Option<(u32, u32, u32, u32)> {
+    let values: Vec<u32> = s.split(',').map(|v| v.trim().parse().ok()).collect::<Option<Vec<u32>>>()?;
+    match values.as_slice() {
+        [x, y, w, h] if *w > 0 && *h > 0 => Some((*x, *y, *w, *h)),
+        _ => None,
+    }
+}
+
+// Extract the double-quoted segments from a command (straight or curly quotes)
+pub fn extract_quoted_args(s: &str) -> Vec<String> {
+    let normalized = s.replace(['\u{201C}', '\u{201D}'], "\"");
+    normalized.split('"')
+        .enumerate()
+        .filter(|(i, _)| i % 2 == 1)
+        .map(|(_, part)| part.to_string())
+        .collect()
+}
+
+// Remove double-quoted segments from a command, leaving the unquoted arguments
+pub fn strip_quoted_args(s: &str) -> String {
+    let normalized = s.replace(['\u{201C}', '\u{201D}'], "\"");
+    normalized.split('"')
+        .enumerate()
+        .filter(|(i, _)| i % 2 == 0)
+        .map(|(_, part)| part)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// Split post-effect flags (`--rounded [radius]`, `--border [color] [thickness]`, `--shadow`, `--bg <color|keep>`)
+// from the other arguments
+pub fn parse_post_effects<'a>(parts: &[&'a str]) -> (Vec<crate::image_processing::PostEffect>, Vec<&'a str>) {
+    use crate::image_processing::PostEffect;
+    let mut effects = Vec::new();
+    let mut remaining = Vec::new();
+    let mut i = 0;
+    while i < parts.len() {
+        match parts[i] {
+            "--rounded" => {
+                let radius = parts.get(i + 1).and_then(|s| s.parse::<u32>().ok());
+                if radius.is_some() {
+                    i += 1;
+                }
+                effects.push(PostEffect::Rounded(radius.unwrap_or(24).min(1024)));
+            }
+            "--border" => {
+                let mut color = "mauve".to_string();
+                if let Some(name) = parts.get(i + 1).filter(|s| CATPPUCCIN_COLOR_NAMES.contains(&s.to_lowercase().as_str())) {
+                    color = name.to_lowercase();
+                    i += 1;
+                }
+                let thickness = parts.get(i + 1).and_then(|s| s.parse::<u32>().ok());
+                if thickness.is_some() {
+                    i += 1;
+                }
+                effects.push(PostEffect::Border(color, thickness.unwrap_or(8).clamp(1, 256)));
+            }
+            "--shadow" => effects.push(PostEffect::Shadow),
+            // `keep` is the default for formats with alpha; a color that doesn't parse is left in place so it's reported
+            "--bg" => match parts.get(i + 1).map(|s| s.to_lowercase()) {
+                Some(value) if value == "keep" => i += 1,
+                Some(value) if CATPPUCCIN_COLOR_NAMES.contains(&value.as_str()) || parse_color_literal(&value).is_some() => {
+                    effects.push(PostEffect::Background(value));
+                    i += 1;
+                }
+                _ => remaining.push(parts[i]),
+            },
+            other => remaining.push(other),
+        }
+        i += 1;
+    }
+    (effects, remaining)
+}
+
+// Split pre-adjustment flags (`--auto-correct`, `--hue-shift <degrees>`, `--saturate <factor>`,
+// `--brightness <factor>`, `--premultiplied`, `--matte <color>`) from the other arguments. A flag without a
+// valid value is left in place so it's reported.
+pub fn parse_pre_adjustments<'a>(parts: &[&'a str]) -> (crate::image_processing::PreAdjust, Vec<&'a str>) {
+    let mut adjust = crate::image_processing::PreAdjust::default();
+    let mut remaining = Vec::new();
+    let mut i = 0;
+    while i < parts.len() {
+        let value = parts.get(i + 1).and_then(|s| s.parse::<f32>().ok()).filter(|v| v.is_finite());
+        let color = parts.get(i + 1).and_then(|s| CATPPUCCIN_COLOR_NAMES.iter().find(|name| name.eq_ignore_ascii_case(s)).copied());
+        match (parts[i], value) {
+            ("--auto-correct", _) => {
+                adjust.auto_correct = true;
+                i += 1;
+                continue;
+            }
+            ("--premultiplied", _) => {
+                adjust.premultiplied = true;
+                i += 1;
+                continue;
+            }
+            ("--matte", _) if color.is_some() => adjust.matte = color,
+            ("--hue-shift", Some(degrees)) => adjust.hue_shift = degrees.clamp(-360.0, 360.0),
+            ("--saturate", Some(factor)) => adjust.saturation = factor.clamp(0.0, 4.0),
+            ("--brightness", Some(factor)) => adjust.brightness = factor.clamp(0.0, 4.0),
+            ("--temperature", Some(amount)) => adjust.temperature = amount.clamp(-100.0, 100.0),
+            (other, _) => {
+                remaining.push(other);
+                i += 1;
+                continue;
+            }
+        }
+        i += 2;
+    }
+    (adjust, remaining)
+}
+
+// Split algorithm tuning flags (`--power <exponent>`, `--sigma <width>`, `--neighbors <1-26>`,
+// `--cutoff <0-1>`) from the other arguments. A flag without a valid value is left in place so it's reported.
+pub fn parse_tuning<'a>(parts: &[&'a str]) -> (crate::image_processing::Tuning, Vec<&'a str>) {
+    let mut tuning = crate::image_processing::Tuning::default();
+    let mut remaining = Vec::new();
+    let mut i = 0;
+    while i < parts.len() {
+        let value = parts.get(i + 1).and_then(|s| s.parse::<f32>().ok()).filter(|v| v.is_finite());
+        match (parts[i], value) {
+            ("--power", Some(power)) => tuning.power = Some(power.clamp(0.1, 8.0)),
+            ("--sigma", Some(sigma)) => tuning.sigma = Some(sigma.clamp(1.0, 100.0)),
+            ("--neighbors", Some(neighbors)) if neighbors.fract() == 0.0 => tuning.neighbors = Some(neighbors.clamp(1.0, 26.0) as usize),
+            ("--cutoff", Some(cutoff)) => tuning.cutoff = Some(cutoff.clamp(0.0, 1.0)),
+            (other, _) => {
+                remaining.push(other);
+                i += 1;
+                continue;
+            }
+        }
+        i += 2;
+    }
+    (tuning, remaining)
+}
+
+// Split the encoder quality flag (`--q <1-100>`, for jpg and avif output) from the other arguments.
+// Without a valid value the flag is left in place so it's reported.
+pub fn parse_encode_quality<'a>(parts: &[&'a str]) -> (Option<u8>, Vec<&'a str>) {
+    let mut quality = None;
+    let mut remaining = Vec::new();
+    let mut i = 0;
+    while i < parts.len() {
+        match (parts[i], parts.get(i + 1).and_then(|s| s.parse::<u8>().ok()).filter(|q| (1..=100).contains(q))) {
+            ("--q", Some(q)) => {
+                quality = Some(q);
+                i += 2;
+            }
+            (other, _) => {
+                remaining.push(other);
+                i += 1;
+            }
+        }
+    }
+    (quality, remaining)
+}
+
+// Convert RGB to HSL: hue in degrees, saturation and lightness in 0..1
+pub fn rgb_to_hsl(r: u8, g: u8, b: u8) -> (f32, f32, f32) {
+    let r = r as f32 / 255.0;
+    let g = g as f32 / 255.0;
+    let b = b as f32 / 255.0;
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let l = (max + min) / 2.0;
+    let d = max - min;
+    let (h, s);
+    if d == 0.0 {
+        h = 0.0;
+        s = 0.0;
+    } else {
+        s = if l > 0.5 { d / (2.0 - max - min) } else { d / (max + min) };
+        h = if max == r {
+            ((g - b) / d) % 6.0
+        } else if max == g {
+            ((b - r) / d) + 2.0
+        } else {
+            ((r - g) / d) + 4.0
+        } * 60.0;
+    }
+    let h = if h < 0.0 { h + 360.0 } else { h };
+    (h, s, l)
+}
+
+// Convert HSL back to RGB
+pub fn hsl_to_rgb(h: f32, s: f32, l: f32) -> (u8, u8, u8) {
+    let c = (1.0 - (2.0 * l - 1.0).abs()) * s;
+    let h_ = h / 60.0;
+    let x = c * (1.0 - ((h_ % 2.0) - 1.0).abs());
+    let (r1, g1, b1) = if (0.0..1.0).contains(&h_) {
+        (c, x, 0.0)
+    } else if (1.0..2.0).contains(&h_) {
+        (x, c, 0.0)
+    } else if (2.0..3.0).contains(&h_) {
+        (0.0, c, x)
+    } else if (3.0..4.0).contains(&h_) {
+        (0.0, x, c)
+    } else if (4.0..5.0).contains(&h_) {
+        (x, 0.0, c)
+    } else {
+        (c, 0.0, x)
+    };
+    let m = l - c / 2.0;
+    let r = ((r1 + m) * 255.0).round().clamp(0.0, 255.0) as u8;
+    let g = ((g1 + m) * 255.0).round().clamp(0.0, 255.0) as u8;
+    let b = ((b1 + m) * 255.0).round().clamp(0.0, 255.0) as u8;
+    (r, g, b)
+}
+
 // Find closest Catppuccin color for a given hex string
 pub fn find_closest_catppuccin_hex(input_hex: &str, flavor: FlavorName) -> Option<(String, String)> {
     let hex_str = input_hex.trim_start_matches('#');
@@ -96,14 +596,15 @@ pub fn find_closest_catppuccin_hex(input_hex: &str, flavor: FlavorName) -> Optio
     ];
     let mut min_dist = f32::MAX;
     let mut closest = &palette[0];
-    for (name, color) in &palette {
-        let dr = *r as f32 - color.rgb.r as f32;
-        let dg = *g as f32 - color.rgb.g as f32;
-        let db = *b as f32 - color.rgb.b as f32;
+    for entry in &palette {
+        let color = &entry.1;
+        let dr = r as f32 - color.rgb.r as f32;
+        let dg = g as f32 - color.rgb.g as f32;
+        let db = b as f32 - color.rgb.b as f32;
         let dist = dr * dr + dg * dg + db * db;
         if dist < min_dist {
             min_dist = dist;
-            closest = &(*name, *color);
+            closest = entry;
         }
     }
     let hex = format!("{:02X}{:02X}{:02X}", closest.1.rgb.r, closest.1.rgb.g, closest.1.rgb.b);
@@ -165,7 +666,7 @@ pub fn sanitize_filename(filename: &str, default_ext: &str) -> String {
     if !name.contains('.') {
         name.push('.');
         name.push_str(default_ext);
-    } else if let Some(ext) = name.split('.').last() {
+    } else if let Some(ext) = name.split('.').next_back() {
         if ext.len() > 8 || ext.is_empty() {
             name.push('.');
             name.push_str(default_ext);
@@ -184,7 +685,7 @@ pub async fn update_progress_message(
     let embed = serenity::builder::CreateEmbed::default()
         .title("🔄 Catppuccinifier Bot - Processing")
         .description(progress_text)
-        .color(MOCHA_BLUE)
+        .color(crate::responses::tone_color(crate::responses::Tone::Info))
         .footer(serenity::builder::CreateEmbedFooter::new("Processing your image..."));
     let builder = serenity::builder::EditMessage::new().embed(embed);
     match message.edit(&ctx.http, builder).await {
@@ -193,7 +694,7 @@ pub async fn update_progress_message(
             let new_embed = serenity::builder::CreateEmbed::default()
                 .title("🔄 Catppuccinifier Bot - Processing")
                 .description(progress_text)
-                .color(MOCHA_BLUE)
+                .color(crate::responses::tone_color(crate::responses::Tone::Info))
                 .footer(serenity::builder::CreateEmbedFooter::new("Processing your image..."));
             let new_builder = serenity::builder::CreateMessage::new().embed(new_embed);
             channel_id.send_message(&ctx.http, new_builder).await?;
@@ -212,7 +713,7 @@ pub async fn send_success_message(
     let embed = serenity::builder::CreateEmbed::default()
         .title("✅ Catppuccinifier Bot - Complete")
         .description(success_text)
-        .color(MOCHA_GREEN)
+        .color(crate::responses::tone_color(crate::responses::Tone::Success))
         .footer(serenity::builder::CreateEmbedFooter::new("Processing complete!"));
     let builder = serenity::builder::EditMessage::new().embed(embed);
     message.edit(&ctx.http, builder).await?;
@@ -232,10 +733,115 @@ mod tests {
         assert!(parse_flavor("unknown").is_none());
     }
 
+    #[test]
+    fn test_parse_encode_quality() {
+        assert_eq!(parse_encode_quality(&["!cat", "mocha", "avif", "--q", "45"]), (Some(45), vec!["!cat", "mocha", "avif"]));
+        assert_eq!(parse_encode_quality(&["!cat", "jpg", "--q", "0"]), (None, vec!["!cat", "jpg", "--q", "0"]));
+    }
+
+    #[test]
+    fn test_parse_tuning() {
+        let (tuning, remaining) = parse_tuning(&["!cat", "mocha", "--power", "3", "gaussian", "--neighbors", "40", "--sigma", "12.5"]);
+        assert_eq!(tuning, crate::image_processing::Tuning { power: Some(3.0), sigma: Some(12.5), neighbors: Some(26), cutoff: None });
+        assert_eq!(remaining, vec!["!cat", "mocha", "gaussian"]);
+        let (tuning, remaining) = parse_tuning(&["--neighbors", "2.5", "--cutoff"]);
+        assert!(tuning.is_default());
+        assert_eq!(remaining, vec!["--neighbors", "2.5", "--cutoff"]);
+    }
+
+    #[test]
+    fn test_parse_pre_adjustments() {
+        use crate::image_processing::PreAdjust;
+        let (adjust, remaining) = parse_pre_adjustments(&["!cat", "--hue-shift", "-45", "mocha", "--saturate", "9", "--auto-correct", "--brightness", "0.8", "--temperature", "-30", "--premultiplied", "--matte", "Crust"]);
+        assert_eq!(adjust, PreAdjust { hue_shift: -45.0, saturation: 4.0, brightness: 0.8, auto_correct: true, temperature: -30.0, premultiplied: true, matte: Some("crust") });
+        assert_eq!(remaining, vec!["!cat", "mocha"]);
+        // Flags without a value stay put
+        let (adjust, remaining) = parse_pre_adjustments(&["--hue-shift", "mocha"]);
+        assert!(adjust.is_identity());
+        assert_eq!(remaining, vec!["--hue-shift", "mocha"]);
+    }
+
+    #[test]
+    fn test_format_file_size() {
+        assert_eq!(format_file_size(512), "512 B");
+        assert_eq!(format_file_size(14_540), "14.2 KB");
+        assert_eq!(format_file_size(3_565_158), "3.4 MB");
+    }
+
+    #[test]
+    fn test_parse_channel_mention() {
+        assert_eq!(parse_channel_mention("<#123>").map(|c| c.get()), Some(123));
+        assert_eq!(parse_channel_mention("#general"), None);
+        assert_eq!(parse_channel_mention("<#0>"), None);
+        assert_eq!(parse_channel_mention("<@123>"), None);
+    }
+
+    #[test]
+    fn test_parse_speed_factor() {
+        assert_eq!(parse_speed_factor("2x"), Some(2.0));
+        assert_eq!(parse_speed_factor("0.5X"), Some(0.5));
+        assert_eq!(parse_speed_factor("2"), None);
+        assert_eq!(parse_speed_factor("20x"), None);
+    }
+
+    #[test]
+    fn test_parse_aspect_ratio() {
+        assert_eq!(parse_aspect_ratio("16:9"), Some((16, 9)));
+        assert_eq!(parse_aspect_ratio("1:1"), Some((1, 1)));
+        assert_eq!(parse_aspect_ratio("0:1"), None);
+        assert_eq!(parse_aspect_ratio("16x9"), None);
+    }
+
+    #[test]
+    fn test_parse_deficiency_and_severity() {
+        assert_eq!(parse_deficiency("Tritanomaly"), Some(crate::accessibility::Deficiency::Tritanomaly));
+        assert_eq!(parse_deficiency("all"), None);
+        assert_eq!(parse_severity("60%"), Some(0.6));
+        assert_eq!(parse_severity("100"), Some(1.0));
+        assert_eq!(parse_severity("0"), None);
+        assert_eq!(parse_severity("150%"), None);
+    }
+
+    #[test]
+    fn test_parse_grid() {
+        assert_eq!(parse_grid("4x2"), Some((4, 2)));
+        assert_eq!(parse_grid("1X8"), Some((1, 8)));
+        assert_eq!(parse_grid("0x2"), None);
+        assert_eq!(parse_grid("65x1"), None);
+        assert_eq!(parse_grid("4by2"), None);
+    }
+
+    #[test]
+    fn test_parse_region() {
+        assert_eq!(parse_region("10,20,300,200"), Some((10, 20, 300, 200)));
+        assert_eq!(parse_region("0, 0, 5, 5"), Some((0, 0, 5, 5)));
+        assert_eq!(parse_region("10,20,0,200"), None);
+        assert_eq!(parse_region("10,20,300"), None);
+        assert_eq!(parse_region("a,b,c,d"), None);
+    }
+
+    #[test]
+    fn test_parse_post_effects() {
+        use crate::image_processing::PostEffect;
+        let parts = ["!cat", "mocha", "--rounded", "12", "--border", "Peach", "4", "--shadow", "png"];
+        let (effects, remaining) = parse_post_effects(&parts);
+        assert_eq!(effects, vec![PostEffect::Rounded(12), PostEffect::Border("peach".to_string(), 4), PostEffect::Shadow]);
+        assert_eq!(remaining, vec!["!cat", "mocha", "png"]);
+
+        let (effects, remaining) = parse_post_effects(&["!cat", "--border", "--rounded", "mocha"]);
+        assert_eq!(effects, vec![PostEffect::Border("mauve".to_string(), 8), PostEffect::Rounded(24)]);
+        assert_eq!(remaining, vec!["!cat", "mocha"]);
+
+        let (effects, remaining) = parse_post_effects(&["!cat", "--bg", "Crust", "--bg", "#1E1E2E", "--bg", "keep", "--bg", "nope"]);
+        assert_eq!(effects, vec![PostEffect::Background("crust".to_string()), PostEffect::Background("#1e1e2e".to_string())]);
+        assert_eq!(remaining, vec!["!cat", "--bg", "nope"]);
+    }
+
     #[test]
     fn test_parse_algorithm() {
         assert_eq!(parse_algorithm("shepards-method").unwrap(), "shepards-method");
         assert_eq!(parse_algorithm("nearest-neighbor").unwrap(), "nearest-neighbor");
+        assert_eq!(parse_algorithm("knn").unwrap(), "nearest-k");
         assert!(parse_algorithm("not-an-algo").is_none());
     }
 
@@ -246,5 +852,59 @@ mod tests {
         assert!(parse_format("not-a-format").is_none());
     }
 
+    #[test]
+    fn test_parse_dimensions() {
+        assert_eq!(parse_dimensions("1920x1080"), Some((1920, 1080)));
+        assert_eq!(parse_dimensions("512X80"), Some((512, 80)));
+        assert!(parse_dimensions("10000x10").is_none());
+        assert!(parse_dimensions("abc").is_none());
+    }
+
+    #[test]
+    fn test_parse_animation_speed() {
+        assert_eq!(parse_animation_speed("fast"), Some(3));
+        assert_eq!(parse_animation_speed("0.5x"), Some(10));
+        assert_eq!(parse_animation_speed("100x"), Some(2));
+        assert!(parse_animation_speed("zoom").is_none());
+    }
+
+    #[test]
+    fn test_extract_quoted_args() {
+        let cmd = "!cat caption \"top text\" \u{201C}bottom\u{201D} mocha";
+        assert_eq!(extract_quoted_args(cmd), vec!["top text".to_string(), "bottom".to_string()]);
+        assert_eq!(strip_quoted_args(cmd).split_whitespace().collect::<Vec<_>>(), vec!["!cat", "caption", "mocha"]);
+    }
+
+    #[test]
+    fn test_parse_hex_color() {
+        assert_eq!(parse_hex_color("#FF0000"), Some((255, 0, 0)));
+        assert_eq!(parse_hex_color("0f0"), Some((0, 255, 0)));
+        assert!(parse_hex_color("#GGGGGG").is_none());
+    }
+
+    #[test]
+    fn test_parse_color_literal() {
+        assert_eq!(parse_color_literal("#1e1e2e80"), Some((30, 30, 46)));
+        assert_eq!(parse_color_literal("rgb(30,30,46)"), Some((30, 30, 46)));
+        assert_eq!(parse_color_literal("RGBA(30 30 46 / 50%)"), Some((30, 30, 46)));
+        assert_eq!(parse_color_literal("rgb(100%, 0%, 50%)"), Some((255, 0, 128)));
+        assert_eq!(parse_color_literal("hsl(240,21%,15%)"), Some((30, 30, 46)));
+        assert_eq!(parse_color_literal("hsla(120deg, 100%, 50%, 0.3)"), Some((0, 255, 0)));
+        assert_eq!(parse_color_literal("RebeccaPurple"), Some((102, 51, 153)));
+        assert!(parse_color_literal("rgb(1,2)").is_none());
+        assert!(parse_color_literal("mauve").is_none());
+        // Palette names take precedence over CSS names
+        assert_eq!(parse_color("red", FlavorName::Mocha), Some((243, 139, 168)));
+        assert_eq!(parse_color("tomato", FlavorName::Mocha), Some((255, 99, 71)));
+    }
+
+    #[test]
+    fn test_split_args() {
+        assert_eq!(split_args("!cat  rgb(30, 30, 46) mocha"), vec!["!cat", "rgb(30, 30, 46)", "mocha"]);
+        assert_eq!(split_args("!cat gradient hsl(0 50% 50%) blue"), vec!["!cat", "gradient", "hsl(0 50% 50%)", "blue"]);
+        // Other parentheses don't join arguments
+        assert_eq!(split_args("!cat meme \"a (b\" c"), vec!["!cat", "meme", "\"a", "(b\"", "c"]);
+    }
+
     // Add more tests for color conversion helpers if present
 } 
\ No newline at end of file
@@ -0,0 +1,164 @@
+// src/video.rs
+
+use crate::workdir::{FrameStore, WorkDir};
+use once_cell::sync::Lazy;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Silent video container for animated output
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VideoFormat {
+    Mp4,
+    WebM,
+}
+
+impl VideoFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            VideoFormat::Mp4 => "mp4",
+            VideoFormat::WebM => "webm",
+        }
+    }
+}
+
+// Video encoding shells out to ffmpeg, which is optional; checked once per process
+static FFMPEG_AVAILABLE: Lazy<bool> = Lazy::new(|| {
+    Command::new("ffmpeg")
+        .arg("-version")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map(|s| s.success())
+        .unwrap_or(false)
+});
+
+/// Whether an `ffmpeg` binary is on the PATH
+pub fn ffmpeg_available() -> bool {
+    *FFMPEG_AVAILABLE
+}
+
+/// Pick a constant frame rate for GIF delays (centiseconds) and how many times to repeat each frame.
+/// Uses the largest rate that divides every delay exactly, capped at 50 fps.
+pub fn constant_frame_rate(delays: &[u16]) -> (u32, Vec<usize>) {
+    fn gcd(a: u32, b: u32) -> u32 {
+        if b == 0 { a } else { gcd(b, a % b) }
+    }
+    // A delay of 0 plays at roughly 10cs in browsers
+    let delays: Vec<u32> = delays.iter().map(|&d| if d == 0 { 10 } else { d as u32 }).collect();
+    let step = delays.iter().fold(0, |acc, &d| gcd(acc, d)).max(2);
+    let fps = 100 / step;
+    let repeats = delays.iter().map(|&d| ((d as f32 / step as f32).round() as usize).max(1)).collect();
+    (fps, repeats)
+}
+
+/// Encode frames (with GIF delays in centiseconds) as a silent MP4 (H.264) or WebM (VP9) using ffmpeg.
+/// Transparent areas are flattened onto `background`, since neither format is played with alpha by Discord.
+/// Frames are read from the store one at a time as ffmpeg takes them.
+pub fn encode_frames_to_video(frames: &FrameStore, format: VideoFormat, background: (u8, u8, u8)) -> Result<Vec<u8>, String> {
+    if !ffmpeg_available() {
+        return Err("Video output needs ffmpeg, which isn't installed on this bot's host.".to_string());
+    }
+    if frames.is_empty() {
+        return Err("No frames to encode".to_string());
+    }
+    let (width, height) = frames.dimensions(0);
+    let (fps, repeats) = constant_frame_rate(&frames.delays());
+    let work_dir = WorkDir::new()?;
+    let output_path = work_dir.file(&format!("output.{}", format.extension()));
+    let codec_args: &[&str] = match format {
+        VideoFormat::Mp4 => &["-c:v", "libx264", "-preset", "veryfast", "-crf", "23", "-movflags", "+faststart"],
+        VideoFormat::WebM => &["-c:v", "libvpx-vp9", "-b:v", "0", "-crf", "32", "-row-mt", "1"],
+    };
+    let mut child = Command::new("ffmpeg")
+        .args(["-hide_banner", "-loglevel", "error", "-y", "-f", "rawvideo", "-pix_fmt", "rgb24"])
+        .args(["-s", &format!("{}x{}", width, height), "-r", &fps.to_string(), "-i", "-"])
+        // yuv420p needs even dimensions
+        .args(["-an", "-vf", "pad=ceil(iw/2)*2:ceil(ih/2)*2", "-pix_fmt", "yuv420p"])
+        .args(codec_args)
+        .arg(&output_path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to start ffmpeg: {e}"))?;
+    let mut stdin = child.stdin.take().ok_or("Failed to open ffmpeg input")?;
+    // Feed frames from another thread so ffmpeg's stderr can't fill up and stall both sides
+    let (output, write_result) = std::thread::scope(|scope| {
+        let writer = scope.spawn(move || -> Result<(), String> {
+            for (index, repeat) in repeats.into_iter().enumerate() {
+                let img = frames.get(index)?;
+                let mut rgb = Vec::with_capacity((width * height * 3) as usize);
+                for p in img.pixels() {
+                    let a = p[3] as f32 / 255.0;
+                    rgb.push((p[0] as f32 * a + background.0 as f32 * (1.0 - a)).round() as u8);
+                    rgb.push((p[1] as f32 * a + background.1 as f32 * (1.0 - a)).round() as u8);
+                    rgb.push((p[2] as f32 * a + background.2 as f32 * (1.0 - a)).round() as u8);
+                }
+                for _ in 0..repeat {
+                    stdin.write_all(&rgb).map_err(|e| format!("Failed to send frames to ffmpeg: {e}"))?;
+                }
+            }
+            Ok(())
+        });
+        let output = child.wait_with_output();
+        (output, writer.join())
+    });
+    let output = output.map_err(|e| format!("ffmpeg failed: {e}"))?;
+    let write_result = write_result.map_err(|_| "ffmpeg input thread panicked".to_string())?;
+    if !output.status.success() {
+        Err(format!("ffmpeg failed: {}", String::from_utf8_lossy(&output.stderr).trim()))
+    } else if let Err(e) = write_result {
+        Err(e)
+    } else {
+        std::fs::read(&output_path).map_err(|e| format!("Failed to read encoded video: {e}"))
+    }
+}
+
+/// Convert a GIF to a silent video, keeping its timing
+pub fn gif_to_video(gif_bytes: &[u8], format: VideoFormat, background: (u8, u8, u8)) -> Result<Vec<u8>, String> {
+    let frames = crate::image_processing::decode_gif_frames(gif_bytes)?;
+    encode_frames_to_video(&frames, format, background)
+}
+
+/// Convert a short video (e.g. an embedded GIFV) to a looping GIF the image pipeline can read,
+/// keeping at most `max_seconds` at 15 fps and no wider than 480px
+pub fn video_to_gif(video_bytes: &[u8], max_seconds: u32) -> Result<Vec<u8>, String> {
+    if !ffmpeg_available() {
+        return Err("Reading videos needs ffmpeg, which isn't installed on this bot's host.".to_string());
+    }
+    let work_dir = WorkDir::new()?;
+    // MP4 input has to be seekable, so it goes through a file rather than stdin
+    let input_path = work_dir.file("input");
+    let output_path = work_dir.file("output.gif");
+    std::fs::write(&input_path, video_bytes).map_err(|e| format!("Failed to buffer video: {e}"))?;
+    let output = Command::new("ffmpeg")
+        .args(["-hide_banner", "-loglevel", "error", "-y", "-t", &max_seconds.to_string(), "-i"])
+        .arg(&input_path)
+        // A palette generated from the clip itself keeps the GIF close to the original colors
+        .args(["-an", "-vf", "fps=15,scale='min(480,iw)':-2:flags=lanczos,split[a][b];[a]palettegen[p];[b][p]paletteuse", "-loop", "0"])
+        .arg(&output_path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()
+        .map_err(|e| format!("Failed to start ffmpeg: {e}"));
+    match output {
+        Ok(output) if output.status.success() => std::fs::read(&output_path).map_err(|e| format!("Failed to read converted GIF: {e}")),
+        Ok(output) => Err(format!("ffmpeg failed: {}", String::from_utf8_lossy(&output.stderr).trim())),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_constant_frame_rate() {
+        assert_eq!(constant_frame_rate(&[10, 10, 10]), (10, vec![1, 1, 1]));
+        assert_eq!(constant_frame_rate(&[4, 8, 12]), (25, vec![1, 2, 3]));
+        // Zero delays play like 10cs; 1cs delays are capped at 50 fps
+        assert_eq!(constant_frame_rate(&[0, 5]), (20, vec![2, 1]));
+        assert_eq!(constant_frame_rate(&[1, 3]).0, 50);
+    }
+}
@@ -0,0 +1,169 @@
+// src/watermark.rs
+
+use crate::config::{self, Watermark, WatermarkMark};
+use crate::image_processing::PostEffect;
+use crate::responses;
+use crate::setup;
+use crate::source;
+use crate::text_rendering::{draw_text, measure_text};
+use crate::utils;
+use dashmap::DashMap;
+use image::{Rgba, RgbaImage};
+use once_cell::sync::Lazy;
+use serenity::model::channel::Message;
+use serenity::model::id::GuildId;
+use serenity::prelude::Context;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// Longest watermark text, which keeps it to a corner
+const MAX_TEXT_CHARS: usize = 32;
+
+/// Side server icons are kept at; they're scaled down further to fit each image
+const ICON_SIZE: u32 = 128;
+
+/// What's stamped: text, or an image ready to composite
+#[derive(Debug, Clone, PartialEq)]
+pub enum Mark {
+    Text(String),
+    Logo(Arc<RgbaImage>),
+}
+
+// Server icons by URL, which changes whenever the icon does
+static ICONS: Lazy<DashMap<String, Arc<RgbaImage>>> = Lazy::new(DashMap::new);
+
+async fn server_icon(ctx: &Context, guild_id: GuildId) -> Option<Arc<RgbaImage>> {
+    let url = ctx.cache.guild(guild_id)?.icon_url()?;
+    if let Some(icon) = ICONS.get(&url) {
+        return Some(icon.clone());
+    }
+    let icon = match source::fetch_url(&url).await.map(|bytes| image::load_from_memory(&bytes)) {
+        Ok(Ok(icon)) => Arc::new(icon.thumbnail(ICON_SIZE, ICON_SIZE).to_rgba8()),
+        _ => {
+            warn!(guild = %guild_id, "Failed to fetch the server icon for its watermark");
+            return None;
+        }
+    };
+    ICONS.insert(url, icon.clone());
+    Some(icon)
+}
+
+/// The server's watermark as the last post-effect, or None if it has none (or its icon can't be fetched)
+pub async fn effect(ctx: &Context, guild_id: GuildId) -> Option<PostEffect> {
+    let Watermark { mark, color, opacity } = config::guild(guild_id).watermark?;
+    let mark = match mark {
+        WatermarkMark::Text(text) => Mark::Text(text),
+        WatermarkMark::ServerIcon => Mark::Logo(server_icon(ctx, guild_id).await?),
+    };
+    Some(PostEffect::Watermark { mark, color, opacity })
+}
+
+/// Stamp a mark in the bottom-right corner, sized to the image, at `opacity` percent
+pub fn stamp(img: &mut RgbaImage, mark: &Mark, color: (u8, u8, u8), opacity: u8) {
+    let short_side = img.width().min(img.height());
+    let margin = (short_side / 40).max(4);
+    let mut layer = match mark {
+        Mark::Text(text) => {
+            let size = (short_side as f32 / 24.0).clamp(12.0, 64.0);
+            let (w, h) = measure_text(text, size);
+            // Starting from the text color at zero alpha, glyph edges blend to the color rather than to black
+            let mut layer = RgbaImage::from_pixel(w.max(1), h.max(1), Rgba([color.0, color.1, color.2, 0]));
+            draw_text(&mut layer, text, 0, 0, size, color);
+            layer
+        }
+        Mark::Logo(logo) => {
+            let side = (short_side / 8).clamp(16, ICON_SIZE);
+            image::imageops::resize(logo.as_ref(), side, side, image::imageops::FilterType::Triangle)
+        }
+    };
+    for pixel in layer.pixels_mut() {
+        pixel[3] = (pixel[3] as u32 * opacity.min(100) as u32 / 100) as u8;
+    }
+    let x = img.width() as i64 - layer.width() as i64 - margin as i64;
+    let y = img.height() as i64 - layer.height() as i64 - margin as i64;
+    image::imageops::overlay(img, &layer, x, y);
+}
+
+/// `!cat config watermark <"text"|icon|off> [color] [opacity%]`; `parts` are the words after `watermark`.
+/// With nothing else it shows the server's watermark. Members skip it for one command with `--no-watermark`.
+pub async fn configure(ctx: &Context, msg: &Message, parts: &[&str]) {
+    let Some(guild_id) = msg.guild_id else {
+        let _ = responses::warning(&ctx.http, msg.channel_id, "Watermarks are a server setting; run this in a server.").await;
+        return;
+    };
+    let text = utils::extract_quoted_args(&msg.content).into_iter().next().map(|t| t.trim().to_string()).filter(|t| !t.is_empty());
+    let off = parts.iter().any(|p| p.eq_ignore_ascii_case("off"));
+    let icon = parts.iter().any(|p| p.eq_ignore_ascii_case("icon"));
+    let mark = match (text, icon) {
+        (Some(text), _) => Some(WatermarkMark::Text(text)),
+        (None, true) => Some(WatermarkMark::ServerIcon),
+        (None, false) => None,
+    };
+    if mark.is_none() && !off {
+        let text = match config::guild(guild_id).watermark {
+            Some(w) => format!("**Watermark:** {}, {} at {}%\nTurn it off with `!cat config watermark off`.", describe(&w.mark), w.color, w.opacity),
+            None => "No watermark. Add one with `!cat config watermark \"your text\" mauve 60%` or `!cat config watermark icon`.".to_string(),
+        };
+        let _ = responses::info(&ctx.http, msg.channel_id, text).await;
+        return;
+    }
+    if !setup::can_manage(&ctx.cache, msg) {
+        let _ = responses::warning(&ctx.http, msg.channel_id, "You need the **Manage Server** permission to change the watermark.").await;
+        return;
+    }
+    if let Some(WatermarkMark::Text(text)) = &mark {
+        if text.chars().count() > MAX_TEXT_CHARS {
+            let text = format!("Watermark text can be at most {} characters.", MAX_TEXT_CHARS);
+            let _ = responses::warning(&ctx.http, msg.channel_id, text).await;
+            return;
+        }
+    }
+    let watermark = mark.filter(|_| !off).map(|mark| Watermark {
+        mark,
+        color: parts.iter().map(|p| p.to_lowercase()).find(|p| utils::CATPPUCCIN_COLOR_NAMES.contains(&p.as_str())).unwrap_or_else(|| "text".to_string()),
+        opacity: parts.iter().find_map(|p| utils::parse_severity(p)).map_or(50, |o| (o * 100.0).round() as u8),
+    });
+    match config::update_guild(guild_id, |settings| settings.watermark = watermark.clone()) {
+        Ok(_) => {
+            info!(guild = %guild_id, on = watermark.is_some(), "Watermark changed");
+            let text = match &watermark {
+                Some(w) => format!("Processed images will carry {} in {} at {}% opacity. Members can skip it with `--no-watermark`.", describe(&w.mark), w.color, w.opacity),
+                None => "Processed images no longer carry a watermark.".to_string(),
+            };
+            let _ = responses::success(&ctx.http, msg.channel_id, text).await;
+        }
+        Err(e) => {
+            warn!(error = %e, guild = %guild_id, "Failed to save watermark");
+            let _ = responses::error(&ctx.http, msg.channel_id, "The change applies for now, but couldn't be saved and will be lost on restart.").await;
+        }
+    }
+}
+
+fn describe(mark: &WatermarkMark) -> String {
+    match mark {
+        WatermarkMark::Text(text) => format!("\"{}\"", text),
+        WatermarkMark::ServerIcon => "the server icon".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stamp() {
+        let base = Rgba([30, 30, 46, 255]);
+        let mut img = RgbaImage::from_pixel(400, 300, base);
+        let logo = Arc::new(RgbaImage::from_pixel(128, 128, Rgba([255, 255, 255, 255])));
+        stamp(&mut img, &Mark::Logo(logo), (0, 0, 0), 50);
+        // A 37px logo (300 / 8) half blended into the bottom-right corner, 7px (300 / 40) in
+        let blended = img.get_pixel(400 - 7 - 18, 300 - 7 - 18).0;
+        assert!((130..=150).contains(&blended[0]), "{:?}", blended);
+        assert_eq!(img.get_pixel(400 - 7 - 40, 300 - 7 - 18).0, base.0);
+        assert_eq!(img.get_pixel(0, 0).0, base.0);
+        let mut img = RgbaImage::from_pixel(400, 300, base);
+        stamp(&mut img, &Mark::Text("club".to_string()), (203, 166, 247), 100);
+        assert!(img.pixels().any(|p| p.0 == [203, 166, 247, 255]));
+        assert_eq!(img.get_pixel(0, 0).0, base.0);
+    }
+}
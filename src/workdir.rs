@@ -0,0 +1,270 @@
+// src/workdir.rs
+
+use image::RgbaImage;
+use once_cell::sync::Lazy;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tracing::warn;
+
+// Every job directory lives under one root, so anything a crashed run left behind can be found again
+static ROOT: Lazy<PathBuf> = Lazy::new(|| {
+    std::env::var("WORK_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| std::env::temp_dir().join("catppuccinifier"))
+});
+
+// How much decoded frame data a FrameStore keeps in memory before moving it to disk
+static SPILL_THRESHOLD_BYTES: Lazy<usize> = Lazy::new(|| {
+    let megabytes = std::env::var("SPILL_THRESHOLD_MB").ok().and_then(|s| s.parse::<usize>().ok()).unwrap_or(128);
+    megabytes * 1024 * 1024
+});
+
+static NEXT_JOB: AtomicU64 = AtomicU64::new(0);
+
+/// A scratch directory for one job. It is deleted with everything in it when dropped,
+/// which also happens when the job fails or panics.
+pub struct WorkDir {
+    path: PathBuf,
+}
+
+impl WorkDir {
+    pub fn new() -> Result<Self, String> {
+        // Named `<pid>-<n>` so startup cleanup can tell this process's directories from stale ones
+        let name = format!("{}-{}", std::process::id(), NEXT_JOB.fetch_add(1, Ordering::Relaxed));
+        let path = ROOT.join(name);
+        std::fs::create_dir_all(&path).map_err(|e| format!("Failed to create a working directory: {e}"))?;
+        Ok(WorkDir { path })
+    }
+
+    #[cfg(test)]
+    pub fn path(&self) -> &std::path::Path {
+        &self.path
+    }
+
+    /// Path for a file inside the directory
+    pub fn file(&self, name: &str) -> PathBuf {
+        self.path.join(name)
+    }
+}
+
+impl Drop for WorkDir {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_dir_all(&self.path) {
+            warn!(error = %e, path = %self.path.display(), "Failed to remove working directory");
+        }
+    }
+}
+
+// Whether a process is still running. Without `/proc` there's no telling, so it's assumed to be.
+fn process_alive(pid: u32) -> bool {
+    let proc = std::path::Path::new("/proc");
+    !proc.is_dir() || proc.join(pid.to_string()).exists()
+}
+
+// A directory named `<pid>-<n>` like `WorkDir::new` makes, whose process has exited
+fn is_stale(name: &str) -> bool {
+    let Some((pid, job)) = name.split_once('-') else {
+        return false;
+    };
+    match (pid.parse::<u32>(), job.parse::<u64>()) {
+        (Ok(pid), Ok(_)) => pid != std::process::id() && !process_alive(pid),
+        _ => false,
+    }
+}
+
+/// Remove job directories left behind by earlier runs that were killed before they could clean up.
+/// Only directories named like this bot's own whose process is gone are touched, so anything else
+/// in `WORK_DIR`, or another running bot's jobs, is left alone. Returns how many were removed.
+pub fn clean_stale() -> usize {
+    let Ok(entries) = std::fs::read_dir(&*ROOT) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()) && is_stale(&entry.file_name().to_string_lossy()))
+        .filter(|entry| std::fs::remove_dir_all(entry.path()).is_ok())
+        .count()
+}
+
+enum Slot {
+    Memory(RgbaImage),
+    Disk { width: u32, height: u32 },
+}
+
+/// Animation frames with their delays (centiseconds). Frames are kept in memory until they add up
+/// to more than the spill threshold; from then on every frame lives in a `WorkDir` as raw RGBA and
+/// is read back one at a time, so long or large GIFs never have to fit in memory at once.
+pub struct FrameStore {
+    slots: Vec<(Slot, u16)>,
+    memory_bytes: usize,
+    threshold: usize,
+    dir: Option<WorkDir>,
+}
+
+impl Default for FrameStore {
+    fn default() -> Self {
+        FrameStore::new()
+    }
+}
+
+/// Wraps frames that are already in memory, without spilling them
+impl From<Vec<(RgbaImage, u16)>> for FrameStore {
+    fn from(frames: Vec<(RgbaImage, u16)>) -> Self {
+        let memory_bytes = frames.iter().map(|(img, _)| img.as_raw().len()).sum();
+        let slots = frames.into_iter().map(|(img, delay)| (Slot::Memory(img), delay)).collect();
+        FrameStore { slots, memory_bytes, threshold: *SPILL_THRESHOLD_BYTES, dir: None }
+    }
+}
+
+impl FrameStore {
+    /// An empty store using the `SPILL_THRESHOLD_MB` threshold
+    pub fn new() -> Self {
+        FrameStore::with_threshold(*SPILL_THRESHOLD_BYTES)
+    }
+
+    pub fn with_threshold(threshold: usize) -> Self {
+        FrameStore { slots: Vec::new(), memory_bytes: 0, threshold, dir: None }
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    /// Whether the frames have been moved to disk
+    #[cfg(test)]
+    pub fn is_spilled(&self) -> bool {
+        self.dir.is_some()
+    }
+
+    pub fn push(&mut self, frame: RgbaImage, delay: u16) -> Result<(), String> {
+        let size = frame.as_raw().len();
+        if self.dir.is_none() && self.memory_bytes + size > self.threshold {
+            self.spill()?;
+        }
+        let slot = match &self.dir {
+            Some(dir) => {
+                write_frame(dir, self.slots.len(), &frame)?;
+                Slot::Disk { width: frame.width(), height: frame.height() }
+            }
+            None => {
+                self.memory_bytes += size;
+                Slot::Memory(frame)
+            }
+        };
+        self.slots.push((slot, delay));
+        Ok(())
+    }
+
+    // Move every frame held so far to disk
+    fn spill(&mut self) -> Result<(), String> {
+        let dir = WorkDir::new()?;
+        for (index, (slot, _)) in self.slots.iter_mut().enumerate() {
+            if let Slot::Memory(frame) = slot {
+                write_frame(&dir, index, frame)?;
+                *slot = Slot::Disk { width: frame.width(), height: frame.height() };
+            }
+        }
+        self.memory_bytes = 0;
+        self.dir = Some(dir);
+        Ok(())
+    }
+
+    /// Delay of a frame in centiseconds
+    pub fn delay(&self, index: usize) -> u16 {
+        self.slots[index].1
+    }
+
+    pub fn delays(&self) -> Vec<u16> {
+        self.slots.iter().map(|(_, delay)| *delay).collect()
+    }
+
+    pub fn dimensions(&self, index: usize) -> (u32, u32) {
+        match &self.slots[index].0 {
+            Slot::Memory(frame) => frame.dimensions(),
+            Slot::Disk { width, height } => (*width, *height),
+        }
+    }
+
+    /// A copy of a frame, read back from disk if it was spilled
+    pub fn get(&self, index: usize) -> Result<RgbaImage, String> {
+        match (&self.slots[index].0, &self.dir) {
+            (Slot::Memory(frame), _) => Ok(frame.clone()),
+            (Slot::Disk { width, height }, Some(dir)) => {
+                let bytes = std::fs::read(frame_path(dir, index)).map_err(|e| format!("Failed to read a spilled frame: {e}"))?;
+                RgbaImage::from_raw(*width, *height, bytes).ok_or_else(|| "A spilled frame is corrupt".to_string())
+            }
+            (Slot::Disk { .. }, None) => unreachable!("frames are only on disk once the store has a directory"),
+        }
+    }
+
+    /// Every frame with its delay, loaded one at a time
+    #[cfg(test)]
+    pub fn iter(&self) -> impl Iterator<Item = Result<(RgbaImage, u16), String>> + '_ {
+        (0..self.len()).map(|index| self.get(index).map(|frame| (frame, self.delay(index))))
+    }
+}
+
+fn frame_path(dir: &WorkDir, index: usize) -> PathBuf {
+    dir.file(&format!("frame_{:06}.rgba", index))
+}
+
+fn write_frame(dir: &WorkDir, index: usize, frame: &RgbaImage) -> Result<(), String> {
+    std::fs::write(frame_path(dir, index), frame.as_raw()).map_err(|e| format!("Failed to spill a frame to disk: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    #[test]
+    fn test_work_dir_removed_on_drop() {
+        let dir = WorkDir::new().unwrap();
+        let path = dir.path().to_path_buf();
+        std::fs::write(dir.file("scratch.bin"), [1, 2, 3]).unwrap();
+        assert!(path.is_dir());
+        drop(dir);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_only_dead_processes_job_dirs_are_stale() {
+        assert!(!is_stale(&format!("{}-3", std::process::id())));
+        // Above any real pid_max, so never a running process
+        assert!(is_stale(&format!("{}-3", u32::MAX)));
+        assert!(!is_stale(&format!("{}-cache", u32::MAX)));
+        assert!(!is_stale("notes"));
+        assert!(!is_stale("backup-2024"));
+    }
+
+    #[test]
+    fn test_frame_store_spills_past_threshold() {
+        // Each 4x4 frame is 64 bytes, so the third push goes over the threshold
+        let mut store = FrameStore::with_threshold(150);
+        for i in 0..5u8 {
+            store.push(RgbaImage::from_pixel(4, 4, Rgba([i, 0, 0, 255])), i as u16 * 10).unwrap();
+            assert_eq!(store.is_spilled(), i >= 2);
+        }
+        assert_eq!(store.len(), 5);
+        assert_eq!(store.delays(), vec![0, 10, 20, 30, 40]);
+        // Frames written before and after the spill both read back intact
+        for (i, frame) in store.iter().enumerate() {
+            let (img, delay) = frame.unwrap();
+            assert_eq!((img.get_pixel(3, 3)[0], delay), (i as u8, i as u16 * 10));
+        }
+        let path = store.dir.as_ref().unwrap().path().to_path_buf();
+        drop(store);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_frames_in_memory_are_not_spilled() {
+        let store = FrameStore::from(vec![(RgbaImage::new(2, 2), 5), (RgbaImage::new(2, 2), 7)]);
+        assert!(!store.is_spilled());
+        assert_eq!((store.len(), store.dimensions(1), store.delay(1)), (2, (2, 2), 7));
+    }
+}
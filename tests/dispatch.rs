@@ -0,0 +1,379 @@
+// tests/dispatch.rs
+
+//! `!cat` commands and the catppuccinify path run end to end against a recording `Outbox` and a
+//! canned `ImageFetcher`, so replies and attachments can be checked without a Discord token or
+//! network access.
+
+use catppuccin::FlavorName;
+use catppuccin_bot::bench;
+use catppuccin_bot::commands::{self, CommandFlags, ImageArgs, ImageRequest};
+use catppuccin_bot::config::{self, Config};
+use catppuccin_bot::error::BotError;
+use catppuccin_bot::image_processing::{PreAdjust, Tuning};
+use catppuccin_bot::outbox::Outbox;
+use catppuccin_bot::source::{ImageFetcher, ImageSource, SourceKind};
+use catppuccin_bot::utils;
+use serenity::async_trait;
+use serenity::builder::{CreateAttachment, CreateMessage, EditMessage};
+use serenity::json::{self, json, Value};
+use serenity::model::channel::{Attachment, Message};
+use serenity::model::id::{ChannelId, MessageId, UserId};
+use std::collections::HashMap;
+use std::sync::{Mutex, Once};
+
+/// One reply: the message as Discord would receive it, and any files by name
+struct Sent {
+    channel_id: ChannelId,
+    body: Value,
+    files: Vec<(String, Vec<u8>)>,
+}
+
+#[derive(Default)]
+struct MockOutbox {
+    sent: Mutex<Vec<Sent>>,
+}
+
+impl MockOutbox {
+    fn record(&self, channel_id: ChannelId, body: Value, files: Vec<CreateAttachment>) -> Message {
+        let mut sent = self.sent.lock().unwrap();
+        sent.push(Sent { channel_id, body, files: files.into_iter().map(|file| (file.filename, file.data)).collect() });
+        let mut message = Message::default();
+        message.id = MessageId::new(sent.len() as u64);
+        message.channel_id = channel_id;
+        message
+    }
+
+    fn take(&self) -> Vec<Sent> {
+        std::mem::take(&mut self.sent.lock().unwrap())
+    }
+}
+
+#[async_trait]
+impl Outbox for MockOutbox {
+    async fn send_message(&self, channel_id: ChannelId, builder: CreateMessage) -> serenity::Result<Message> {
+        Ok(self.record(channel_id, json::to_value(&builder)?, Vec::new()))
+    }
+
+    async fn send_files(&self, channel_id: ChannelId, files: Vec<CreateAttachment>, builder: CreateMessage) -> serenity::Result<Message> {
+        Ok(self.record(channel_id, json::to_value(&builder)?, files))
+    }
+
+    async fn edit_message(&self, channel_id: ChannelId, _message_id: MessageId, builder: EditMessage) -> serenity::Result<Message> {
+        Ok(self.record(channel_id, json::to_value(&builder)?, Vec::new()))
+    }
+}
+
+/// Serves bytes by URL; anything else fails like a broken link
+#[derive(Default)]
+struct MockFetcher {
+    files: HashMap<String, Vec<u8>>,
+}
+
+#[async_trait]
+impl ImageFetcher for MockFetcher {
+    async fn fetch(&self, url: &str, progress: &mut (dyn FnMut(usize, Option<u64>) + Send)) -> Result<Vec<u8>, BotError> {
+        let bytes = self.files.get(url).cloned().ok_or_else(|| BotError::Download("Failed to fetch the image.".to_string()))?;
+        progress(bytes.len(), Some(bytes.len() as u64));
+        Ok(bytes)
+    }
+}
+
+const IMAGE_URL: &str = "https://cdn.example.com/cat.png";
+
+// Job history is saved to a scratch file rather than the working directory. Tests run in parallel,
+// so the settings are swapped once instead of each test setting the environment.
+fn use_scratch_history() {
+    static SETUP: Once = Once::new();
+    SETUP.call_once(|| {
+        let history_file = std::env::temp_dir().join("catppuccin_bot_dispatch_history.json");
+        config::set(Config { history_file, ..(*config::get()).clone() });
+    });
+}
+
+fn message_with_attachment(content_type: &str) -> Message {
+    message_with_file("cat.png", Some(content_type))
+}
+
+fn message_with_file(filename: &str, content_type: Option<&str>) -> Message {
+    use_scratch_history();
+    let attachment: Attachment = json::from_value(json!({
+        "id": "1",
+        "filename": filename,
+        "size": 0,
+        "url": IMAGE_URL,
+        "proxy_url": IMAGE_URL,
+        "content_type": content_type,
+    }))
+    .unwrap();
+    let mut msg = Message::default();
+    msg.id = MessageId::new(100);
+    msg.channel_id = ChannelId::new(200);
+    msg.attachments = vec![attachment];
+    msg
+}
+
+fn request(flavor: FlavorName) -> ImageRequest {
+    ImageRequest {
+        flavor,
+        algorithm: "nearest-neighbor",
+        quality: None,
+        format: None,
+        pre_adjust: PreAdjust::default(),
+        tuning: Tuning::default(),
+        post_effects: Vec::new(),
+        encode_quality: None,
+        strict: false,
+        autoscale: false,
+        show_stats: false,
+        show_comparison: false,
+        all_flavors: false,
+        debug_timing: false,
+        command: "mocha".to_string(),
+    }
+}
+
+fn png(width: u32, height: u32) -> Vec<u8> {
+    let mut buffer = std::io::Cursor::new(Vec::new());
+    bench::sample_image(width, height).write_to(&mut buffer, image::ImageFormat::Png).unwrap();
+    buffer.into_inner()
+}
+
+fn embed_description(sent: &Sent) -> &str {
+    sent.body["embeds"][0]["description"].as_str().unwrap_or_default()
+}
+
+/// The reply carrying the results, after any status message and its progress edits
+fn upload(sent: &[Sent]) -> &Sent {
+    sent.iter().find(|s| !s.files.is_empty()).expect("no files were sent")
+}
+
+async fn run(fetcher: &MockFetcher, msg: &Message, request: ImageRequest) -> Vec<Sent> {
+    let outbox = MockOutbox::default();
+    commands::process_attachment(&outbox, fetcher, msg, &msg.attachments[0], request).await;
+    outbox.take()
+}
+
+/// `msg` sent as a command, its images already resolved to `sources`
+async fn dispatch(fetcher: &MockFetcher, msg: &Message, sources: Vec<ImageSource>) -> Vec<Sent> {
+    let outbox = MockOutbox::default();
+    let all_parts = utils::split_args(&msg.content);
+    let (flags, parts) = CommandFlags::parse(&all_parts);
+    commands::dispatch(&outbox, fetcher, msg, &parts, flags, sources).await;
+    outbox.take()
+}
+
+fn command(content: &str) -> Message {
+    let mut msg = message_with_attachment("image/png");
+    msg.content = content.to_string();
+    msg
+}
+
+fn image_source(url: &str, filename: &str) -> ImageSource {
+    ImageSource { url: url.to_string(), filename: filename.to_string(), kind: SourceKind::Image, poster: None }
+}
+
+#[tokio::test]
+async fn test_replies_with_the_catppuccinified_image() {
+    let fetcher = MockFetcher { files: HashMap::from([(IMAGE_URL.to_string(), png(16, 12))]) };
+    let msg = message_with_attachment("image/png");
+    let sent = run(&fetcher, &msg, request(FlavorName::Mocha)).await;
+    let reply = upload(&sent);
+    assert_eq!(reply.channel_id, msg.channel_id);
+    assert!(reply.body["content"].as_str().unwrap().contains("Flavor: MOCHA"));
+    let (filename, data) = &reply.files[0];
+    assert_eq!(filename, "catppuccinified_mocha.png");
+    let output = image::load_from_memory(data).unwrap();
+    assert_eq!((output.width(), output.height()), (16, 12));
+}
+
+#[tokio::test]
+async fn test_download_failure_is_reported_with_its_code() {
+    let msg = message_with_attachment("image/png");
+    let sent = run(&MockFetcher::default(), &msg, request(FlavorName::Latte)).await;
+    assert_eq!(sent.len(), 1);
+    assert!(sent[0].files.is_empty());
+    assert!(embed_description(&sent[0]).contains("`E-DL`"));
+}
+
+#[tokio::test]
+async fn test_non_images_are_refused_before_downloading() {
+    let msg = message_with_attachment("text/plain");
+    // Nothing is served, so a download attempt would show up as an `E-DL` reply instead
+    let sent = run(&MockFetcher::default(), &msg, request(FlavorName::Latte)).await;
+    assert_eq!(sent.len(), 1);
+    assert!(embed_description(&sent[0]).contains("Please attach an image"));
+}
+
+const SVG: &str = r##"<svg xmlns="http://www.w3.org/2000/svg" width="8" height="4"><rect width="8" height="4" fill="#ff0000"/></svg>"##;
+
+async fn assert_svg_recolored(content_type: Option<&str>) {
+    let fetcher = MockFetcher { files: HashMap::from([(IMAGE_URL.to_string(), SVG.as_bytes().to_vec())]) };
+    let msg = message_with_file("logo.svg", content_type);
+    let sent = run(&fetcher, &msg, request(FlavorName::Mocha)).await;
+    assert_eq!(sent.len(), 1);
+    let names: Vec<&str> = sent[0].files.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, ["logo-catppuccin-mocha.svg", "logo-catppuccin-mocha.png"]);
+    let recolored = String::from_utf8(sent[0].files[0].1.clone()).unwrap();
+    assert!(recolored.contains(r##"fill="#f38ba8""##));
+    let preview = image::load_from_memory(&sent[0].files[1].1).unwrap();
+    assert_eq!((preview.width(), preview.height()), (1024, 512));
+}
+
+#[tokio::test]
+async fn test_svgs_come_back_recolored_with_a_preview() {
+    assert_svg_recolored(Some("image/svg+xml")).await;
+}
+
+#[tokio::test]
+async fn test_svgs_without_a_content_type_are_recognized_by_extension() {
+    assert_svg_recolored(None).await;
+}
+
+#[tokio::test]
+async fn test_exr_without_a_content_type_is_processed() {
+    let hdr = image::DynamicImage::ImageRgba32F(image::DynamicImage::ImageRgba8(bench::sample_image(16, 12)).to_rgba32f());
+    let mut exr = std::io::Cursor::new(Vec::new());
+    hdr.write_to(&mut exr, image::ImageFormat::OpenExr).unwrap();
+    let fetcher = MockFetcher { files: HashMap::from([(IMAGE_URL.to_string(), exr.into_inner())]) };
+    let msg = message_with_file("render.exr", None);
+    let sent = run(&fetcher, &msg, request(FlavorName::Mocha)).await;
+    let output = image::load_from_memory(&upload(&sent).files[0].1).unwrap();
+    assert_eq!((output.width(), output.height()), (16, 12));
+}
+
+#[tokio::test]
+async fn test_jxl_without_a_content_type_reaches_the_decoder() {
+    // A JPEG XL codestream signature; the decoder rejects the rest, but only after the type check let it through
+    let fetcher = MockFetcher { files: HashMap::from([(IMAGE_URL.to_string(), vec![0xFF, 0x0A, 0x00, 0x00])]) };
+    let msg = message_with_file("photo.jxl", None);
+    let sent = run(&fetcher, &msg, request(FlavorName::Mocha)).await;
+    assert_eq!(sent.len(), 1);
+    assert!(!embed_description(&sent[0]).contains("Please attach an image"));
+    assert!(embed_description(&sent[0]).contains("`E-DEC`"));
+}
+
+#[test]
+fn test_command_flags_and_positional_arguments() {
+    let all_parts = utils::split_args("!cat mocha jpg --strict --debug-timing -f");
+    let (flags, parts) = CommandFlags::parse(&all_parts);
+    assert!(flags.strict && flags.debug_timing && !flags.autoscale && !flags.no_watermark);
+    assert_eq!(parts, ["!cat", "mocha", "jpg", "-f"]);
+    let args = ImageArgs::parse(&parts, FlavorName::Latte);
+    assert_eq!((args.flavor, args.explicit_flavor, args.format), (FlavorName::Mocha, true, Some(image::ImageFormat::Jpeg)));
+    assert_eq!((args.algorithm, args.quality.as_deref()), ("nearest-neighbor", Some("fast")));
+    // The server's default stands in for an unnamed flavor, and the second word only follows a flavor
+    let args = ImageArgs::parse(&["!cat", "stats", "mocha"], FlavorName::Frappe);
+    assert_eq!((args.flavor, args.explicit_flavor, args.show_stats), (FlavorName::Frappe, false, true));
+}
+
+#[tokio::test]
+async fn test_dispatch_catppuccinifies_an_attachment_in_the_named_flavor() {
+    let fetcher = MockFetcher { files: HashMap::from([(IMAGE_URL.to_string(), png(16, 12))]) };
+    let msg = command("!cat macchiato");
+    let sent = dispatch(&fetcher, &msg, vec![image_source(IMAGE_URL, "cat.png")]).await;
+    assert_eq!(upload(&sent).files[0].0, "catppuccinified_macchiato.png");
+}
+
+#[tokio::test]
+async fn test_dispatch_refuses_strict_with_a_lossy_format() {
+    let fetcher = MockFetcher { files: HashMap::from([(IMAGE_URL.to_string(), png(16, 12))]) };
+    let msg = command("!cat mocha jpg --strict");
+    let sent = dispatch(&fetcher, &msg, vec![image_source(IMAGE_URL, "cat.png")]).await;
+    assert_eq!(sent.len(), 1);
+    assert!(sent[0].files.is_empty());
+    assert!(embed_description(&sent[0]).contains("`--strict` needs `png` or `gif` output"));
+}
+
+#[tokio::test]
+async fn test_dispatch_converts_a_color_without_an_image() {
+    let mut msg = command("!cat mocha #ff0000");
+    msg.attachments.clear();
+    let sent = dispatch(&MockFetcher::default(), &msg, Vec::new()).await;
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].body["embeds"][0]["title"], "Catppuccin Color Conversion");
+}
+
+#[tokio::test]
+async fn test_batch_downloads_go_through_the_fetcher() {
+    // The video itself isn't served, so its poster frame is downloaded in its place
+    let poster = "https://cdn.example.com/clip.png";
+    let fetcher = MockFetcher { files: HashMap::from([(IMAGE_URL.to_string(), png(16, 12)), (poster.to_string(), png(8, 8))]) };
+    let video = ImageSource { kind: SourceKind::Video, poster: Some(poster.to_string()), ..image_source("https://cdn.example.com/clip.mp4", "clip.mp4") };
+    let msg = command("!cat frappe");
+    let sent = dispatch(&fetcher, &msg, vec![image_source(IMAGE_URL, "cat.png"), video]).await;
+    let reply = upload(&sent);
+    assert_eq!(reply.body["content"], "Here are your Catppuccinified images!");
+    let names: Vec<&str> = reply.files.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names.len(), 2);
+    assert!(names[1].contains("clip"));
+}
+
+#[tokio::test]
+async fn test_the_same_attachment_sent_twice_runs_one_job() {
+    let fetcher = MockFetcher { files: HashMap::from([(IMAGE_URL.to_string(), png(14, 10))]) };
+    let mut msg = message_with_attachment("image/png");
+    // A user of its own, so no other test's request can be joined
+    msg.author.id = UserId::new(2895);
+    let first = run(&fetcher, &msg, request(FlavorName::Mocha)).await;
+    let second = run(&fetcher, &msg, request(FlavorName::Mocha)).await;
+    let joined = |sent: &[Sent]| sent.iter().any(|s| embed_description(s).contains("You already sent this image"));
+    assert!(!joined(&first));
+    assert!(joined(&second));
+    // The second request gets the first one's output rather than running its own
+    let output = |sent: &[Sent]| sent.iter().find_map(|s| s.files.first().map(|(_, data)| data.clone())).unwrap();
+    assert_eq!(output(&first), output(&second));
+}
+
+#[tokio::test]
+async fn test_progress_is_shown_on_a_status_message() {
+    let fetcher = MockFetcher { files: HashMap::from([(IMAGE_URL.to_string(), png(12, 40))]) };
+    let msg = message_with_attachment("image/png");
+    let sent = run(&fetcher, &msg, request(FlavorName::Latte)).await;
+    assert!(embed_description(&sent[0]).starts_with("🕒 Your image is now being processed..."));
+    // With an estimate of how long it should take, going by past jobs
+    assert!(embed_description(&sent[0]).contains(" for this 12×40 image with nearest-neighbor"));
+    // The status message is edited as rows are mapped, and ends on a full bar before the upload
+    let edits: Vec<&str> = sent[1..sent.len() - 1].iter().map(embed_description).collect();
+    assert!(edits.last().is_some_and(|edit| edit.ends_with("▓▓▓▓▓▓▓▓▓▓▓▓ 100%`")), "{:?}", edits);
+    assert!(!sent.last().unwrap().files.is_empty());
+}
+
+#[tokio::test]
+async fn test_batch_progress_counts_images() {
+    let second_url = "https://cdn.example.com/dog.png";
+    let fetcher = MockFetcher { files: HashMap::from([(IMAGE_URL.to_string(), png(8, 8)), (second_url.to_string(), png(6, 6))]) };
+    let msg = command("!cat latte");
+    let sent = dispatch(&fetcher, &msg, vec![image_source(IMAGE_URL, "cat.png"), image_source(second_url, "dog.png")]).await;
+    assert_eq!(embed_description(&sent[0]), "🕒 Processing 2 images...");
+    assert!(embed_description(&sent[sent.len() - 2]).ends_with("100%`"));
+    assert_eq!(upload(&sent).files.len(), 2);
+}
+
+fn animated_gif(frames: u8) -> Vec<u8> {
+    let mut buffer = Vec::new();
+    {
+        let mut encoder = image::codecs::gif::GifEncoder::new(&mut buffer);
+        for i in 0..frames {
+            let frame = image::RgbaImage::from_fn(12, 8, |x, y| image::Rgba([(x * 20) as u8, (y * 30) as u8, i * 60, 255]));
+            encoder.encode_frame(image::Frame::new(frame)).unwrap();
+        }
+    }
+    buffer
+}
+
+#[tokio::test]
+async fn test_dispatch_snaps_every_frame_of_a_strict_gif() {
+    use image::AnimationDecoder;
+    let fetcher = MockFetcher { files: HashMap::from([(IMAGE_URL.to_string(), animated_gif(3))]) };
+    let mut msg = message_with_file("dance.gif", Some("image/gif"));
+    msg.content = "!cat mocha --strict".to_string();
+    let sent = dispatch(&fetcher, &msg, vec![image_source(IMAGE_URL, "dance.gif")]).await;
+    let reply = upload(&sent);
+    assert_eq!(reply.files[0].0, "catppuccinified_mocha.gif");
+    assert!(reply.body["content"].as_str().unwrap().contains("Palette colors only."));
+    let decoder = image::codecs::gif::GifDecoder::new(std::io::Cursor::new(&reply.files[0].1)).unwrap();
+    let frames = decoder.into_frames().collect_frames().unwrap();
+    assert_eq!(frames.len(), 3);
+    let palette: Vec<(u8, u8, u8)> = utils::CATPPUCCIN_COLOR_NAMES.iter().filter_map(|name| utils::catppuccin_color_name_to_rgb(name, FlavorName::Mocha)).collect();
+    assert!(frames.iter().all(|frame| frame.buffer().pixels().all(|p| palette.contains(&(p[0], p[1], p[2])))));
+}
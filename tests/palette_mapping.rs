@@ -0,0 +1,102 @@
+// tests/palette_mapping.rs
+
+//! Invariants of palette mapping, checked on generated colors and images, and golden-image snapshots
+//! of every algorithm and flavor (with the spatial noise some algorithms add), so a change to LUT
+//! generation can't silently change the output.
+//!
+//! After an intended change to the mapping, rewrite the snapshots with
+//! `UPDATE_GOLDEN=1 cargo test --test palette_mapping` and review the new images in `tests/golden`.
+
+use catppuccin::FlavorName;
+use catppuccin_bot::bench;
+use catppuccin_bot::image_processing::{self, PaletteMapper};
+use image::{Rgba, RgbaImage};
+use proptest::prelude::*;
+use std::path::PathBuf;
+
+const FLAVORS: [FlavorName; 4] = [FlavorName::Latte, FlavorName::Frappe, FlavorName::Macchiato, FlavorName::Mocha];
+
+// Algorithms that blend palette colors rather than pick one
+const WEIGHTED: &[&str] = &["shepards-method", "gaussian-rbf", "gaussian-sampling", "nearest-k", "hald", "mean", "std"];
+
+fn flavor() -> impl Strategy<Value = FlavorName> {
+    prop::sample::select(FLAVORS.to_vec())
+}
+
+fn image() -> impl Strategy<Value = RgbaImage> {
+    (1u32..24, 1u32..24).prop_flat_map(|(width, height)| {
+        prop::collection::vec(any::<[u8; 4]>(), (width * height) as usize).prop_map(move |pixels| {
+            RgbaImage::from_raw(width, height, pixels.concat()).unwrap()
+        })
+    })
+}
+
+proptest! {
+    #[test]
+    fn nearest_neighbor_maps_onto_the_palette(r: u8, g: u8, b: u8, flavor in flavor()) {
+        let mapper = PaletteMapper::new(flavor, "nearest-neighbor");
+        let mapped = mapper.map(r, g, b);
+        prop_assert!(mapper.palette().any(|color| color == mapped), "{:?} mapped to {:?}, not a palette color", (r, g, b), mapped);
+    }
+
+    // A blend of palette colors can't leave their bounding box (a necessary condition for staying in the hull)
+    #[test]
+    fn blended_colors_stay_within_the_palette(r: u8, g: u8, b: u8, flavor in flavor(), algorithm in prop::sample::select(WEIGHTED.to_vec())) {
+        let mapper = PaletteMapper::new(flavor, algorithm);
+        let mapped = mapper.map(r, g, b);
+        let channels = |pick: fn((u8, u8, u8)) -> u8| {
+            let values: Vec<u8> = mapper.palette().map(pick).collect();
+            (*values.iter().min().unwrap(), *values.iter().max().unwrap())
+        };
+        for (value, (low, high)) in [(mapped.0, channels(|c| c.0)), (mapped.1, channels(|c| c.1)), (mapped.2, channels(|c| c.2))] {
+            // Truncation to u8 can land one below the lowest palette value
+            prop_assert!(value + 1 >= low && value <= high, "{:?} mapped to {:?} with {}", (r, g, b), mapped, algorithm);
+        }
+    }
+
+    #[test]
+    fn lut_application_keeps_size_and_alpha(original in image(), color: [u8; 3]) {
+        // Every entry the same color, so the expected output is known without generating a real LUT
+        let lut = color.repeat(256 * 256 * 256);
+        let mut mapped = original.clone();
+        image_processing::apply_lut_to_image(&mut mapped, &lut);
+        prop_assert_eq!(mapped.dimensions(), original.dimensions());
+        for (before, after) in original.pixels().zip(mapped.pixels()) {
+            prop_assert_eq!(after.0, [color[0], color[1], color[2], before[3]]);
+        }
+    }
+}
+
+fn golden_path(algorithm: &str, flavor: FlavorName) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden").join(format!("{}-{}.png", algorithm, flavor.to_string().to_lowercase()))
+}
+
+#[test]
+fn mapping_matches_golden_images() {
+    let update = std::env::var_os("UPDATE_GOLDEN").is_some();
+    let input = bench::sample_image(32, 32);
+    let mut mismatches = Vec::new();
+    for algorithm in bench::ALGORITHMS {
+        for flavor in FLAVORS {
+            let mapper = PaletteMapper::new(flavor, algorithm);
+            let output = RgbaImage::from_fn(input.width(), input.height(), |x, y| {
+                let [r, g, b, a] = input.get_pixel(x, y).0;
+                let (r, g, b) = mapper.map_pixel(x, y, r, g, b);
+                Rgba([r, g, b, a])
+            });
+            let path = golden_path(algorithm, flavor);
+            if update {
+                output.save(&path).unwrap();
+                continue;
+            }
+            let golden = image::open(&path).unwrap_or_else(|e| panic!("missing golden image {}: {e}", path.display())).to_rgba8();
+            // One step of slack per channel for floating-point differences between platforms
+            let matches = golden.dimensions() == output.dimensions()
+                && golden.pixels().zip(output.pixels()).all(|(want, got)| want.0.iter().zip(got.0).all(|(w, g)| w.abs_diff(g) <= 1));
+            if !matches {
+                mismatches.push(path.display().to_string());
+            }
+        }
+    }
+    assert!(mismatches.is_empty(), "mapping differs from the golden images (rerun with UPDATE_GOLDEN=1 if intended): {mismatches:?}");
+}